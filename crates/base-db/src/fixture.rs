@@ -1,7 +1,7 @@
 //! A set of high-level utility fixture methods to use in tests.
 use std::{mem, str::FromStr, sync::Arc};
 
-use cfg::CfgOptions;
+use cfg::{CfgOptions, CfgVersion};
 use rustc_hash::FxHashMap;
 use test_utils::{
     extract_range_or_offset, Fixture, RangeOrOffset, CURSOR_MARKER, ESCAPED_CURSOR_MARKER,
@@ -111,6 +111,7 @@ impl ChangeFixture {
         let mut crate_deps = Vec::new();
         let mut default_crate_root: Option<FileId> = None;
         let mut default_cfg = CfgOptions::default();
+        let mut default_env = Env::default();
 
         let mut file_set = FileSet::default();
         let mut current_source_root_kind = SourceRootKind::Local;
@@ -174,6 +175,7 @@ impl ChangeFixture {
                 assert!(default_crate_root.is_none());
                 default_crate_root = Some(file_id);
                 default_cfg = meta.cfg;
+                default_env = meta.env;
             }
 
             change.change_file(file_id, Some(Arc::new(text)));
@@ -193,7 +195,7 @@ impl ChangeFixture {
                 None,
                 default_cfg.clone(),
                 default_cfg,
-                Env::default(),
+                default_env,
                 Default::default(),
                 false,
                 CrateOrigin::CratesIo { repo: None },
@@ -289,13 +291,19 @@ impl ChangeFixture {
         };
         roots.push(root);
         change.set_roots(roots);
+        crate_graph.set_target_layout(&Arc::from(DEFAULT_TARGET_LAYOUT));
+        crate_graph.set_rustc_version(DEFAULT_RUSTC_VERSION);
         change.set_crate_graph(crate_graph);
 
         ChangeFixture { file_position, files, change }
     }
 }
 
-fn default_test_proc_macros() -> [(String, ProcMacro); 4] {
+/// The data layout of `x86_64-unknown-linux-gnu`, used for all crates of a fixture.
+const DEFAULT_TARGET_LAYOUT: &str = "e-m:e-i64:64-f80:128-n8:16:32:64-S128";
+const DEFAULT_RUSTC_VERSION: CfgVersion = CfgVersion { major: 1, minor: 65, patch: 0 };
+
+fn default_test_proc_macros() -> [(String, ProcMacro); 5] {
     [
         (
             r#"
@@ -353,6 +361,20 @@ pub fn mirror(input: TokenStream) -> TokenStream {
                 expander: Arc::new(MirrorProcMacroExpander),
             },
         ),
+        (
+            r#"
+#[proc_macro_attribute]
+pub fn panic_attr(_attr: TokenStream, _item: TokenStream) -> TokenStream {
+    panic!("panic_attr")
+}
+"#
+            .into(),
+            ProcMacro {
+                name: "panic_attr".into(),
+                kind: crate::ProcMacroKind::Attr,
+                expander: Arc::new(PanicProcMacroExpander),
+            },
+        ),
     ]
 }
 
@@ -492,3 +514,17 @@ impl ProcMacroExpander for MirrorProcMacroExpander {
         Ok(traverse(input))
     }
 }
+
+// Always fails, as if the proc macro panicked
+#[derive(Debug)]
+struct PanicProcMacroExpander;
+impl ProcMacroExpander for PanicProcMacroExpander {
+    fn expand(
+        &self,
+        _: &Subtree,
+        _: Option<&Subtree>,
+        _: &Env,
+    ) -> Result<Subtree, ProcMacroExpansionError> {
+        Err(ProcMacroExpansionError::Panic("panic_attr".into()))
+    }
+}
@@ -8,7 +8,7 @@
 
 use std::{fmt, iter::FromIterator, ops, panic::RefUnwindSafe, str::FromStr, sync::Arc};
 
-use cfg::CfgOptions;
+use cfg::{CfgOptions, CfgVersion};
 use rustc_hash::{FxHashMap, FxHashSet};
 use syntax::SmolStr;
 use tt::Subtree;
@@ -223,6 +223,17 @@ pub trait ProcMacroExpander: fmt::Debug + Send + Sync + RefUnwindSafe {
         attrs: Option<&Subtree>,
         env: &Env,
     ) -> Result<Subtree, ProcMacroExpansionError>;
+
+    /// Expands the macro again, outside of any caching, with the full backtrace in the message
+    /// of a panic.
+    fn expand_with_backtrace(
+        &self,
+        subtree: &Subtree,
+        attrs: Option<&Subtree>,
+        env: &Env,
+    ) -> Result<Subtree, ProcMacroExpansionError> {
+        self.expand(subtree, attrs, env)
+    }
 }
 
 pub enum ProcMacroExpansionError {
@@ -257,6 +268,11 @@ pub struct CrateData {
     pub proc_macro: Vec<ProcMacro>,
     pub origin: CrateOrigin,
     pub is_proc_macro: bool,
+    /// The LLVM data layout string of the compilation target, if it is known.
+    pub target_layout: Option<Arc<str>>,
+    /// The target triple of the crate, if it is one of the further targets the workspace is
+    /// analyzed for rather than its default one.
+    pub target_triple: Option<Arc<str>>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -280,21 +296,32 @@ pub struct Dependency {
     pub crate_id: CrateId,
     pub name: CrateName,
     prelude: bool,
+    dev: bool,
 }
 
 impl Dependency {
     pub fn new(name: CrateName, crate_id: CrateId) -> Self {
-        Self { name, crate_id, prelude: true }
+        Self { name, crate_id, prelude: true, dev: false }
     }
 
     pub fn with_prelude(name: CrateName, crate_id: CrateId, prelude: bool) -> Self {
-        Self { name, crate_id, prelude }
+        Self { name, crate_id, prelude, dev: false }
+    }
+
+    /// Creates a dependency that only exists because of a `[dev-dependencies]` entry.
+    pub fn new_dev(name: CrateName, crate_id: CrateId) -> Self {
+        Self { name, crate_id, prelude: true, dev: true }
     }
 
     /// Whether this dependency is to be added to the depending crate's extern prelude.
     pub fn is_prelude(&self) -> bool {
         self.prelude
     }
+
+    /// Whether this dependency is a dev-dependency, only used by tests, benches and examples.
+    pub fn is_dev(&self) -> bool {
+        self.dev
+    }
 }
 
 impl CrateGraph {
@@ -323,6 +350,8 @@ impl CrateGraph {
             dependencies: Vec::new(),
             origin,
             is_proc_macro,
+            target_layout: None,
+            target_triple: None,
         };
         let crate_id = CrateId(self.arena.len() as u32);
         let prev = self.arena.insert(crate_id, data);
@@ -351,6 +380,39 @@ impl CrateGraph {
         Ok(())
     }
 
+    /// Sets the data layout of the compilation target of all crates in the graph that don't have
+    /// a crate-specific one yet.
+    pub fn set_target_layout(&mut self, target_layout: &Arc<str>) {
+        for data in self.arena.values_mut() {
+            data.target_layout.get_or_insert_with(|| target_layout.clone());
+        }
+    }
+
+    /// Sets the toolchain version `cfg(version(..))` predicates of all crates are checked against.
+    pub fn set_rustc_version(&mut self, version: CfgVersion) {
+        for data in self.arena.values_mut() {
+            data.cfg_options.set_rustc_version(version);
+            data.potential_cfg_options.set_rustc_version(version);
+        }
+    }
+
+    /// Marks `krate` as analyzed for `target_triple`, for crates that copy a workspace member for
+    /// one of the further targets of its workspace.
+    pub fn set_crate_target_triple(&mut self, krate: CrateId, target_triple: Arc<str>) {
+        self.arena.get_mut(&krate).unwrap().target_triple = Some(target_triple);
+    }
+
+    /// Replaces the enabled cfgs of `krate`, e.g. to add the ones the user configured for it.
+    pub fn set_crate_cfg_options(&mut self, krate: CrateId, cfg_options: CfgOptions) {
+        self.arena.get_mut(&krate).unwrap().cfg_options = cfg_options;
+    }
+
+    /// Sets the data layout of the compilation target of `krate`, for crates that are compiled
+    /// for a different target than the rest of the graph.
+    pub fn set_crate_target_layout(&mut self, krate: CrateId, target_layout: Arc<str>) {
+        self.arena.get_mut(&krate).unwrap().target_layout = Some(target_layout);
+    }
+
     pub fn is_empty(&self) -> bool {
         self.arena.is_empty()
     }
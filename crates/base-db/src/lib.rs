@@ -5,7 +5,7 @@ pub mod fixture;
 
 use std::{panic, sync::Arc};
 
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
 use syntax::{ast, Parse, SourceFile, TextRange, TextSize};
 
 pub use crate::{
@@ -69,6 +69,11 @@ pub trait SourceDatabase: FileLoader + std::fmt::Debug {
     /// The crate graph.
     #[salsa::input]
     fn crate_graph(&self) -> Arc<CrateGraph>;
+
+    /// The target chosen for a file that belongs to one crate per target, when the workspace is
+    /// analyzed for several ones. Files without an entry are analyzed for the default target.
+    #[salsa::input]
+    fn active_targets(&self) -> Arc<FxHashMap<FileId, Arc<str>>>;
 }
 
 fn parse_query(db: &dyn SourceDatabase, file_id: FileId) -> Parse<ast::SourceFile> {
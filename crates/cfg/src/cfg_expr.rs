@@ -49,11 +49,71 @@ impl fmt::Display for CfgAtom {
     }
 }
 
+/// A toolchain version, as compared against by `#[cfg(version("1.70"))]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(test, derive(derive_arbitrary::Arbitrary))]
+pub struct CfgVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl CfgVersion {
+    /// Parses the `major.minor` or `major.minor.patch` syntax accepted by `cfg(version(..))`.
+    pub fn parse(text: &str) -> Option<CfgVersion> {
+        let mut parts = text.split('.').map(|it| it.parse::<u32>().ok());
+        let major = parts.next()??;
+        let minor = parts.next()??;
+        let patch = match parts.next() {
+            Some(patch) => patch?,
+            None => 0,
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(CfgVersion { major, minor, patch })
+    }
+}
+
+impl fmt::Display for CfgVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// The path checked by `#[cfg(accessible(path))]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CfgPath {
+    segments: Box<[SmolStr]>,
+}
+
+impl CfgPath {
+    pub fn segments(&self) -> &[SmolStr] {
+        &self.segments
+    }
+}
+
+impl fmt::Display for CfgPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i != 0 {
+                f.write_str("::")?;
+            }
+            f.write_str(segment)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(test, derive(derive_arbitrary::Arbitrary))]
 pub enum CfgExpr {
     Invalid,
     Atom(CfgAtom),
+    /// eg. `#[cfg(version("1.70"))]`
+    Version(CfgVersion),
+    /// eg. `#[cfg(accessible(std::mem::take))]`
+    Accessible(CfgPath),
     All(Vec<CfgExpr>),
     Any(Vec<CfgExpr>),
     Not(Box<CfgExpr>),
@@ -70,17 +130,32 @@ impl CfgExpr {
         next_cfg_expr(&mut tt.token_trees.iter()).unwrap_or(CfgExpr::Invalid)
     }
     /// Fold the cfg by querying all basic `Atom` and `KeyValue` predicates.
+    ///
+    /// `version(..)` and `accessible(..)` predicates are considered undecidable.
     pub fn fold(&self, query: &dyn Fn(&CfgAtom) -> bool) -> Option<bool> {
+        self.fold_with(query, &|_| None, &|_| None)
+    }
+
+    /// Like [`CfgExpr::fold`], but also evaluates `version(..)` and `accessible(..)` predicates.
+    /// Those queries return `None` when the answer isn't known.
+    pub fn fold_with(
+        &self,
+        query: &dyn Fn(&CfgAtom) -> bool,
+        version: &dyn Fn(CfgVersion) -> Option<bool>,
+        accessible: &dyn Fn(&CfgPath) -> Option<bool>,
+    ) -> Option<bool> {
         match self {
             CfgExpr::Invalid => None,
             CfgExpr::Atom(atom) => Some(query(atom)),
-            CfgExpr::All(preds) => {
-                preds.iter().try_fold(true, |s, pred| Some(s && pred.fold(query)?))
-            }
-            CfgExpr::Any(preds) => {
-                preds.iter().try_fold(false, |s, pred| Some(s || pred.fold(query)?))
-            }
-            CfgExpr::Not(pred) => pred.fold(query).map(|s| !s),
+            CfgExpr::Version(it) => version(*it),
+            CfgExpr::Accessible(path) => accessible(path),
+            CfgExpr::All(preds) => preds
+                .iter()
+                .try_fold(true, |s, pred| Some(s && pred.fold_with(query, version, accessible)?)),
+            CfgExpr::Any(preds) => preds
+                .iter()
+                .try_fold(false, |s, pred| Some(s || pred.fold_with(query, version, accessible)?)),
+            CfgExpr::Not(pred) => pred.fold_with(query, version, accessible).map(|s| !s),
         }
     }
 }
@@ -109,13 +184,19 @@ fn next_cfg_expr(it: &mut SliceIter<tt::TokenTree>) -> Option<CfgExpr> {
         }
         Some(tt::TokenTree::Subtree(subtree)) => {
             it.next();
-            let mut sub_it = subtree.token_trees.iter();
-            let mut subs = std::iter::from_fn(|| next_cfg_expr(&mut sub_it)).collect();
             match name.as_str() {
-                "all" => CfgExpr::All(subs),
-                "any" => CfgExpr::Any(subs),
-                "not" => CfgExpr::Not(Box::new(subs.pop().unwrap_or(CfgExpr::Invalid))),
-                _ => CfgExpr::Invalid,
+                "version" => version_predicate(subtree),
+                "accessible" => accessible_predicate(subtree),
+                _ => {
+                    let mut sub_it = subtree.token_trees.iter();
+                    let mut subs = std::iter::from_fn(|| next_cfg_expr(&mut sub_it)).collect();
+                    match name.as_str() {
+                        "all" => CfgExpr::All(subs),
+                        "any" => CfgExpr::Any(subs),
+                        "not" => CfgExpr::Not(Box::new(subs.pop().unwrap_or(CfgExpr::Invalid))),
+                        _ => CfgExpr::Invalid,
+                    }
+                }
             }
         }
         _ => CfgAtom::Flag(name).into(),
@@ -130,6 +211,56 @@ fn next_cfg_expr(it: &mut SliceIter<tt::TokenTree>) -> Option<CfgExpr> {
     Some(ret)
 }
 
+/// Parses the arguments of `version("1.70")`.
+fn version_predicate(subtree: &tt::Subtree) -> CfgExpr {
+    match &*subtree.token_trees {
+        [tt::TokenTree::Leaf(tt::Leaf::Literal(literal))] => literal
+            .text
+            .strip_prefix('"')
+            .and_then(|it| it.strip_suffix('"'))
+            .and_then(CfgVersion::parse)
+            .map_or(CfgExpr::Invalid, CfgExpr::Version),
+        _ => CfgExpr::Invalid,
+    }
+}
+
+/// Parses the arguments of `accessible(path)`. A leading `::` is accepted and dropped.
+fn accessible_predicate(subtree: &tt::Subtree) -> CfgExpr {
+    let is_colon = |tt: &tt::TokenTree| matches!(tt, tt::TokenTree::Leaf(tt::Leaf::Punct(punct)) if punct.char == ':');
+
+    let mut tts = &*subtree.token_trees;
+    if let [first, second, rest @ ..] = tts {
+        if is_colon(first) && is_colon(second) {
+            tts = rest;
+        }
+    }
+
+    let mut segments = Vec::new();
+    loop {
+        match tts {
+            [tt::TokenTree::Leaf(tt::Leaf::Ident(ident)), rest @ ..] => {
+                segments.push(ident.text.clone());
+                tts = rest;
+            }
+            _ => return CfgExpr::Invalid,
+        }
+        match tts {
+            [] => break,
+            [first, second, rest @ ..] if is_colon(first) && is_colon(second) => tts = rest,
+            _ => return CfgExpr::Invalid,
+        }
+    }
+    CfgExpr::Accessible(CfgPath { segments: segments.into() })
+}
+
+#[cfg(test)]
+impl arbitrary::Arbitrary<'_> for CfgPath {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Self> {
+        let segments = Vec::<String>::arbitrary(u)?;
+        Ok(CfgPath { segments: segments.into_iter().map(SmolStr::from).collect() })
+    }
+}
+
 #[cfg(test)]
 impl arbitrary::Arbitrary<'_> for CfgAtom {
     fn arbitrary(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Self> {
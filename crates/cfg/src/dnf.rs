@@ -10,7 +10,7 @@ use std::fmt::{self, Write};
 
 use rustc_hash::FxHashSet;
 
-use crate::{CfgAtom, CfgDiff, CfgExpr, CfgOptions, InactiveReason};
+use crate::{CfgAtom, CfgDiff, CfgExpr, CfgOptions, CfgPath, CfgVersion, InactiveReason};
 
 /// A `#[cfg]` directive in Disjunctive Normal Form (DNF).
 pub struct DnfExpr {
@@ -23,7 +23,16 @@ struct Conjunction {
 
 struct Literal {
     negate: bool,
-    var: Option<CfgAtom>, // None = Invalid
+    var: Var,
+}
+
+enum Var {
+    Atom(CfgAtom),
+    // `version(..)` and `accessible(..)` don't depend on `CfgOptions` atoms, so they are opaque
+    // to the hint and reason computations below, just like invalid predicates.
+    Version(CfgVersion),
+    Accessible(CfgPath),
+    Invalid,
 }
 
 impl DnfExpr {
@@ -46,7 +55,7 @@ impl DnfExpr {
         for conj in &self.conjunctions {
             let mut conj_is_true = true;
             for lit in &conj.literals {
-                let atom = lit.var.as_ref()?;
+                let atom = lit.atom()?;
                 let enabled = opts.enabled.contains(atom);
                 if lit.negate == enabled {
                     // Literal is false, but needs to be true for this conjunction.
@@ -84,7 +93,7 @@ impl DnfExpr {
             let mut enable = FxHashSet::default();
             let mut disable = FxHashSet::default();
             for lit in &conj.literals {
-                let atom = lit.var.as_ref()?;
+                let atom = lit.atom()?;
                 let enabled = opts.enabled.contains(atom);
                 if lit.negate && enabled {
                     disable.insert(atom.clone());
@@ -96,7 +105,7 @@ impl DnfExpr {
 
             // Check that this actually makes `conj` true.
             for lit in &conj.literals {
-                let atom = lit.var.as_ref()?;
+                let atom = lit.atom()?;
                 let enabled = enable.contains(atom)
                     || (opts.enabled.contains(atom) && !disable.contains(atom));
                 if enabled == lit.negate {
@@ -147,7 +156,11 @@ impl Conjunction {
         let mut literals = Vec::new();
         for part in parts {
             match part {
-                CfgExpr::Invalid | CfgExpr::Atom(_) | CfgExpr::Not(_) => {
+                CfgExpr::Invalid
+                | CfgExpr::Atom(_)
+                | CfgExpr::Version(_)
+                | CfgExpr::Accessible(_)
+                | CfgExpr::Not(_) => {
                     literals.push(Literal::new(part));
                 }
                 CfgExpr::All(conj) => {
@@ -185,14 +198,35 @@ impl fmt::Display for Conjunction {
 impl Literal {
     fn new(expr: CfgExpr) -> Self {
         match expr {
-            CfgExpr::Invalid => Self { negate: false, var: None },
-            CfgExpr::Atom(atom) => Self { negate: false, var: Some(atom) },
             CfgExpr::Not(expr) => match *expr {
-                CfgExpr::Invalid => Self { negate: true, var: None },
-                CfgExpr::Atom(atom) => Self { negate: true, var: Some(atom) },
-                _ => unreachable!("non-atom {:?}", expr),
+                CfgExpr::Any(_) | CfgExpr::All(_) | CfgExpr::Not(_) => {
+                    unreachable!("non-atom {:?}", expr)
+                }
+                expr => Self { negate: true, var: Var::new(expr) },
             },
             CfgExpr::Any(_) | CfgExpr::All(_) => unreachable!("non-literal {:?}", expr),
+            expr => Self { negate: false, var: Var::new(expr) },
+        }
+    }
+
+    fn atom(&self) -> Option<&CfgAtom> {
+        match &self.var {
+            Var::Atom(atom) => Some(atom),
+            Var::Version(_) | Var::Accessible(_) | Var::Invalid => None,
+        }
+    }
+}
+
+impl Var {
+    fn new(expr: CfgExpr) -> Self {
+        match expr {
+            CfgExpr::Invalid => Var::Invalid,
+            CfgExpr::Atom(atom) => Var::Atom(atom),
+            CfgExpr::Version(version) => Var::Version(version),
+            CfgExpr::Accessible(path) => Var::Accessible(path),
+            CfgExpr::Any(_) | CfgExpr::All(_) | CfgExpr::Not(_) => {
+                unreachable!("non-atom {:?}", expr)
+            }
         }
     }
 }
@@ -204,8 +238,10 @@ impl fmt::Display for Literal {
         }
 
         match &self.var {
-            Some(var) => var.fmt(f)?,
-            None => f.write_str("<invalid>")?,
+            Var::Atom(atom) => atom.fmt(f)?,
+            Var::Version(version) => write!(f, "version(\"{}\")", version)?,
+            Var::Accessible(path) => write!(f, "accessible({})", path)?,
+            Var::Invalid => f.write_str("<invalid>")?,
         }
 
         if self.negate {
@@ -226,7 +262,11 @@ impl Builder {
         let expr = make_dnf(expr);
 
         match expr {
-            CfgExpr::Invalid | CfgExpr::Atom(_) | CfgExpr::Not(_) => {
+            CfgExpr::Invalid
+            | CfgExpr::Atom(_)
+            | CfgExpr::Version(_)
+            | CfgExpr::Accessible(_)
+            | CfgExpr::Not(_) => {
                 self.expr.conjunctions.push(Conjunction::new(vec![expr]));
             }
             CfgExpr::All(conj) => {
@@ -236,7 +276,12 @@ impl Builder {
                 disj.reverse();
                 while let Some(conj) = disj.pop() {
                     match conj {
-                        CfgExpr::Invalid | CfgExpr::Atom(_) | CfgExpr::All(_) | CfgExpr::Not(_) => {
+                        CfgExpr::Invalid
+                        | CfgExpr::Atom(_)
+                        | CfgExpr::Version(_)
+                        | CfgExpr::Accessible(_)
+                        | CfgExpr::All(_)
+                        | CfgExpr::Not(_) => {
                             self.expr.conjunctions.push(Conjunction::new(vec![conj]));
                         }
                         CfgExpr::Any(inner_disj) => {
@@ -254,7 +299,11 @@ impl Builder {
 
 fn make_dnf(expr: CfgExpr) -> CfgExpr {
     match expr {
-        CfgExpr::Invalid | CfgExpr::Atom(_) | CfgExpr::Not(_) => expr,
+        CfgExpr::Invalid
+        | CfgExpr::Atom(_)
+        | CfgExpr::Version(_)
+        | CfgExpr::Accessible(_)
+        | CfgExpr::Not(_) => expr,
         CfgExpr::Any(e) => flatten(CfgExpr::Any(e.into_iter().map(make_dnf).collect())),
         CfgExpr::All(e) => {
             let e = e.into_iter().map(make_dnf).collect::<Vec<_>>();
@@ -299,11 +348,13 @@ fn distribute_conj(conj: &[CfgExpr]) -> Vec<CfgExpr> {
 
 fn make_nnf(expr: CfgExpr) -> CfgExpr {
     match expr {
-        CfgExpr::Invalid | CfgExpr::Atom(_) => expr,
+        CfgExpr::Invalid | CfgExpr::Atom(_) | CfgExpr::Version(_) | CfgExpr::Accessible(_) => expr,
         CfgExpr::Any(expr) => CfgExpr::Any(expr.into_iter().map(make_nnf).collect()),
         CfgExpr::All(expr) => CfgExpr::All(expr.into_iter().map(make_nnf).collect()),
         CfgExpr::Not(operand) => match *operand {
-            CfgExpr::Invalid | CfgExpr::Atom(_) => CfgExpr::Not(operand.clone()), // Original negated expr
+            CfgExpr::Invalid | CfgExpr::Atom(_) | CfgExpr::Version(_) | CfgExpr::Accessible(_) => {
+                CfgExpr::Not(operand.clone()) // Original negated expr
+            }
             CfgExpr::Not(expr) => {
                 // Remove double negation.
                 make_nnf(*expr)
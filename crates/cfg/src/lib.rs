@@ -10,7 +10,7 @@ use std::fmt;
 use rustc_hash::FxHashSet;
 use tt::SmolStr;
 
-pub use cfg_expr::{CfgAtom, CfgExpr};
+pub use cfg_expr::{CfgAtom, CfgExpr, CfgPath, CfgVersion};
 pub use dnf::DnfExpr;
 
 /// Configuration options used for conditional compilation on items with `cfg` attributes.
@@ -26,6 +26,8 @@ pub use dnf::DnfExpr;
 #[derive(Clone, PartialEq, Eq, Default)]
 pub struct CfgOptions {
     enabled: FxHashSet<CfgAtom>,
+    /// The toolchain version `cfg(version(..))` predicates are checked against.
+    rustc_version: Option<CfgVersion>,
 }
 
 impl fmt::Debug for CfgOptions {
@@ -39,13 +41,39 @@ impl fmt::Debug for CfgOptions {
             })
             .collect::<Vec<_>>();
         items.sort();
-        f.debug_tuple("CfgOptions").field(&items).finish()
+        let mut f = f.debug_tuple("CfgOptions");
+        f.field(&items);
+        if let Some(version) = &self.rustc_version {
+            f.field(&format_args!("version={}", version));
+        }
+        f.finish()
     }
 }
 
 impl CfgOptions {
     pub fn check(&self, cfg: &CfgExpr) -> Option<bool> {
-        cfg.fold(&|atom| self.enabled.contains(atom))
+        self.check_with_accessible(cfg, &|_| None)
+    }
+
+    /// Like [`CfgOptions::check`], but evaluates `accessible(..)` predicates with `accessible`.
+    pub fn check_with_accessible(
+        &self,
+        cfg: &CfgExpr,
+        accessible: &dyn Fn(&CfgPath) -> Option<bool>,
+    ) -> Option<bool> {
+        cfg.fold_with(
+            &|atom| self.enabled.contains(atom),
+            &|version| Some(self.rustc_version? >= version),
+            accessible,
+        )
+    }
+
+    pub fn rustc_version(&self) -> Option<CfgVersion> {
+        self.rustc_version
+    }
+
+    pub fn set_rustc_version(&mut self, version: CfgVersion) {
+        self.rustc_version = Some(version);
     }
 
     pub fn insert_atom(&mut self, key: SmolStr) {
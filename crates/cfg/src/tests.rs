@@ -3,16 +3,19 @@ use expect_test::{expect, Expect};
 use mbe::syntax_node_to_token_tree;
 use syntax::{ast, AstNode};
 
-use crate::{CfgAtom, CfgExpr, CfgOptions, DnfExpr};
+use crate::{CfgAtom, CfgExpr, CfgOptions, CfgVersion, DnfExpr};
 
-fn assert_parse_result(input: &str, expected: CfgExpr) {
+fn parse(input: &str) -> CfgExpr {
     let (tt, _) = {
         let source_file = ast::SourceFile::parse(input).ok().unwrap();
         let tt = source_file.syntax().descendants().find_map(ast::TokenTree::cast).unwrap();
         syntax_node_to_token_tree(tt.syntax())
     };
-    let cfg = CfgExpr::parse(&tt);
-    assert_eq!(cfg, expected);
+    CfgExpr::parse(&tt)
+}
+
+fn assert_parse_result(input: &str, expected: CfgExpr) {
+    assert_eq!(parse(input), expected);
 }
 
 fn check_dnf(input: &str, expect: Expect) {
@@ -83,6 +86,41 @@ fn test_cfg_expr_parser() {
     );
 }
 
+#[test]
+fn version_and_accessible_predicates() {
+    assert_parse_result(
+        r#"#![cfg(version("1.70"))]"#,
+        CfgExpr::Version(CfgVersion { major: 1, minor: 70, patch: 0 }),
+    );
+    assert_parse_result(
+        r#"#![cfg(version("1.70.1"))]"#,
+        CfgExpr::Version(CfgVersion { major: 1, minor: 70, patch: 1 }),
+    );
+    assert_parse_result(r#"#![cfg(version("1"))]"#, CfgExpr::Invalid);
+    assert_parse_result(r#"#![cfg(version("1.70.0-nightly"))]"#, CfgExpr::Invalid);
+    assert_parse_result("#![cfg(version(1.70))]", CfgExpr::Invalid);
+    assert_parse_result("#![cfg(accessible(std::mem:take))]", CfgExpr::Invalid);
+
+    let accessible = parse("#![cfg(accessible(::std::mem::take))]");
+    match &accessible {
+        CfgExpr::Accessible(path) => assert_eq!(path.to_string(), "std::mem::take"),
+        _ => panic!("expected an `accessible` predicate, got {:?}", accessible),
+    }
+
+    let mut opts = CfgOptions::default();
+    let new = parse(r#"#![cfg(version("1.70"))]"#);
+    let old = parse(r#"#![cfg(not(version("1.50")))]"#);
+    assert_eq!(opts.check(&new), None);
+    opts.set_rustc_version(CfgVersion { major: 1, minor: 66, patch: 1 });
+    assert_eq!(opts.check(&new), Some(false));
+    assert_eq!(opts.check(&old), Some(false));
+    opts.set_rustc_version(CfgVersion { major: 1, minor: 70, patch: 0 });
+    assert_eq!(opts.check(&new), Some(true));
+
+    assert_eq!(opts.check(&accessible), None);
+    assert_eq!(opts.check_with_accessible(&accessible, &|_| Some(false)), Some(false));
+}
+
 #[test]
 fn smoke() {
     check_dnf("#![cfg(test)]", expect![[r#"#![cfg(test)]"#]]);
@@ -2,7 +2,9 @@
 //! another compatible command (f.x. clippy) in a background thread and provide
 //! LSP diagnostics based on the output of the command.
 
-use std::{fmt, io, process::Command, time::Duration};
+mod output;
+
+use std::{fmt, io, path::PathBuf, process::Command, time::Duration};
 
 use crossbeam_channel::{never, select, unbounded, Receiver, Sender};
 use paths::AbsPathBuf;
@@ -14,6 +16,9 @@ pub use cargo_metadata::diagnostic::{
     DiagnosticSpanMacroExpansion,
 };
 
+use crate::output::OutputParser;
+pub use crate::output::{JsonPointers, OutputFormat};
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum FlycheckConfig {
     CargoCommand {
@@ -24,24 +29,59 @@ pub enum FlycheckConfig {
         all_features: bool,
         features: Vec<String>,
         extra_args: Vec<String>,
+        /// Run clippy after the check command, `None` if disabled.
+        clippy: Option<ClippyConfig>,
     },
     CustomCommand {
         command: String,
         args: Vec<String>,
+        /// How diagnostics are read from the command's stdout.
+        format: OutputFormat,
     },
 }
 
 impl fmt::Display for FlycheckConfig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            FlycheckConfig::CargoCommand { command, .. } if self.runs_clippy_pass() => {
+                write!(f, "cargo {} + clippy", command)
+            }
             FlycheckConfig::CargoCommand { command, .. } => write!(f, "cargo {}", command),
-            FlycheckConfig::CustomCommand { command, args } => {
+            FlycheckConfig::CustomCommand { command, args, .. } => {
                 write!(f, "{} {}", command, args.join(" "))
             }
         }
     }
 }
 
+impl FlycheckConfig {
+    /// Whether clippy runs as a second pass after the check command. It doesn't
+    /// when the check command is clippy itself.
+    fn runs_clippy_pass(&self) -> bool {
+        matches!(
+            self,
+            FlycheckConfig::CargoCommand { command, clippy: Some(_), .. } if command != "clippy"
+        )
+    }
+}
+
+/// Lint levels passed to the clippy pass that runs after the check command.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClippyConfig {
+    pub allow: Vec<String>,
+    pub warn: Vec<String>,
+    pub deny: Vec<String>,
+}
+
+/// A package a check can be restricted to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PackageToCheck {
+    /// The package spec passed to `cargo check -p`.
+    pub spec: String,
+    /// The directory containing the package's `Cargo.toml`.
+    pub root: AbsPathBuf,
+}
+
 /// Flycheck wraps the shared state and communication machinery used for
 /// running `cargo check` (or other compatible command) and providing
 /// diagnostics based on the output.
@@ -49,8 +89,9 @@ impl fmt::Display for FlycheckConfig {
 #[derive(Debug)]
 pub struct FlycheckHandle {
     // XXX: drop order is significant
-    sender: Sender<Restart>,
+    sender: Sender<StateChange>,
     _thread: jod_thread::JoinHandle,
+    id: usize,
 }
 
 impl FlycheckHandle {
@@ -61,17 +102,33 @@ impl FlycheckHandle {
         workspace_root: AbsPathBuf,
     ) -> FlycheckHandle {
         let actor = FlycheckActor::new(id, sender, config, workspace_root);
-        let (sender, receiver) = unbounded::<Restart>();
+        let (sender, receiver) = unbounded::<StateChange>();
         let thread = jod_thread::Builder::new()
             .name("Flycheck".to_owned())
             .spawn(move || actor.run(receiver))
             .expect("failed to spawn thread");
-        FlycheckHandle { sender, _thread: thread }
+        FlycheckHandle { sender, _thread: thread, id }
     }
 
     /// Schedule a re-start of the cargo check worker.
     pub fn update(&self) {
-        self.sender.send(Restart).unwrap();
+        self.sender.send(StateChange::Restart(Restart::Workspace)).unwrap();
+    }
+
+    /// Schedule a re-start of the cargo check worker, checking only `packages`
+    /// instead of the whole workspace. Custom check commands always check
+    /// everything.
+    pub fn update_packages(&self, packages: Vec<PackageToCheck>) {
+        self.sender.send(StateChange::Restart(Restart::Packages(packages))).unwrap();
+    }
+
+    /// Stop this cargo check worker, keeping the diagnostics reported so far.
+    pub fn cancel(&self) {
+        self.sender.send(StateChange::Cancel).unwrap();
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
     }
 }
 
@@ -104,19 +161,54 @@ impl fmt::Debug for Message {
 
 #[derive(Debug)]
 pub enum Progress {
-    DidStart,
+    /// A check started, restricted to the given packages if not `None`.
+    DidStart(Option<Vec<PackageToCheck>>),
+    /// Cargo started checking the given crate.
+    DidStartCrate(String),
     DidCheckCrate(String),
+    /// The check command finished and the clippy pass started.
+    DidStartClippy,
     DidFinish(io::Result<()>),
     DidCancel,
 }
 
-struct Restart;
+enum StateChange {
+    Restart(Restart),
+    Cancel,
+}
+
+enum Restart {
+    Workspace,
+    Packages(Vec<PackageToCheck>),
+}
+
+impl Restart {
+    /// Combines restarts requested in quick succession into a single one.
+    fn merge(self, other: Restart) -> Restart {
+        match (self, other) {
+            (Restart::Packages(mut packages), Restart::Packages(other)) => {
+                for package in other {
+                    if !packages.contains(&package) {
+                        packages.push(package);
+                    }
+                }
+                Restart::Packages(packages)
+            }
+            _ => Restart::Workspace,
+        }
+    }
+}
 
 struct FlycheckActor {
     id: usize,
     sender: Box<dyn Fn(Message) + Send>,
     config: FlycheckConfig,
     workspace_root: AbsPathBuf,
+    /// The packages the current check is restricted to, `None` if it checks
+    /// the whole workspace.
+    packages: Option<Vec<PackageToCheck>>,
+    /// Whether the running command is the clippy pass.
+    clippy_pass: bool,
     /// WatchThread exists to wrap around the communication needed to be able to
     /// run `cargo check` without blocking. Currently the Rust standard library
     /// doesn't provide a way to read sub-process output without blocking, so we
@@ -126,7 +218,7 @@ struct FlycheckActor {
 }
 
 enum Event {
-    Restart(Restart),
+    RequestStateChange(StateChange),
     CheckEvent(Option<CargoMessage>),
 }
 
@@ -137,30 +229,76 @@ impl FlycheckActor {
         config: FlycheckConfig,
         workspace_root: AbsPathBuf,
     ) -> FlycheckActor {
-        FlycheckActor { id, sender, config, workspace_root, cargo_handle: None }
+        FlycheckActor {
+            id,
+            sender,
+            config,
+            workspace_root,
+            packages: None,
+            clippy_pass: false,
+            cargo_handle: None,
+        }
     }
     fn progress(&self, progress: Progress) {
         self.send(Message::Progress { id: self.id, progress });
     }
-    fn next_event(&self, inbox: &Receiver<Restart>) -> Option<Event> {
+    fn next_event(&self, inbox: &Receiver<StateChange>) -> Option<Event> {
         let check_chan = self.cargo_handle.as_ref().map(|cargo| &cargo.receiver);
         select! {
-            recv(inbox) -> msg => msg.ok().map(Event::Restart),
+            recv(inbox) -> msg => msg.ok().map(Event::RequestStateChange),
             recv(check_chan.unwrap_or(&never())) -> msg => Some(Event::CheckEvent(msg.ok())),
         }
     }
-    fn run(mut self, inbox: Receiver<Restart>) {
-        while let Some(event) = self.next_event(&inbox) {
+    fn run(mut self, inbox: Receiver<StateChange>) {
+        'outer: while let Some(event) = self.next_event(&inbox) {
             match event {
-                Event::Restart(Restart) => {
-                    while let Ok(Restart) = inbox.recv_timeout(Duration::from_millis(50)) {}
+                Event::RequestStateChange(StateChange::Cancel) => {
+                    tracing::debug!(flycheck_id = self.id, "flycheck cancelled");
+                    self.cancel_check_process();
+                }
+                Event::RequestStateChange(StateChange::Restart(mut restart)) => {
+                    // Saves arriving while a check is running only restart the saved
+                    // packages, but an interrupted package check has already cleared
+                    // the diagnostics of its packages, so those are checked again too.
+                    if self.cargo_handle.is_some() {
+                        if let Some(running) = self.packages.take() {
+                            restart = Restart::Packages(running).merge(restart);
+                        }
+                    }
+                    while let Ok(next) = inbox.recv_timeout(Duration::from_millis(50)) {
+                        match next {
+                            StateChange::Restart(next) => restart = restart.merge(next),
+                            StateChange::Cancel => {
+                                // We got a cancel request, drop the restart and the
+                                // running check.
+                                self.cancel_check_process();
+                                continue 'outer;
+                            }
+                        }
+                    }
 
                     self.cancel_check_process();
 
+                    self.packages = match restart {
+                        Restart::Packages(packages)
+                            if matches!(self.config, FlycheckConfig::CargoCommand { .. }) =>
+                        {
+                            Some(packages)
+                        }
+                        _ => None,
+                    };
+                    self.clippy_pass = false;
                     let command = self.check_command();
                     tracing::info!("restart flycheck {:?}", command);
-                    self.cargo_handle = Some(CargoHandle::spawn(command));
-                    self.progress(Progress::DidStart);
+                    let parser = match &self.config {
+                        FlycheckConfig::CargoCommand { .. } => Ok(OutputParser::Json),
+                        FlycheckConfig::CustomCommand { format, .. } => OutputParser::new(format),
+                    };
+                    self.progress(Progress::DidStart(self.packages.clone()));
+                    match parser {
+                        Ok(parser) => self.cargo_handle = Some(CargoHandle::spawn(command, parser)),
+                        Err(e) => self.progress(Progress::DidFinish(Err(e))),
+                    }
                 }
                 Event::CheckEvent(None) => {
                     // Watcher finished, replace it with a never channel to
@@ -172,14 +310,32 @@ impl FlycheckActor {
                             "Flycheck failed to run the following command: {:?}",
                             self.check_command()
                         );
+                    } else if !self.clippy_pass && self.config.runs_clippy_pass() {
+                        self.clippy_pass = true;
+                        let command = self.check_command();
+                        tracing::info!("start clippy pass {:?}", command);
+                        self.cargo_handle = Some(CargoHandle::spawn(command, OutputParser::Json));
+                        self.progress(Progress::DidStartClippy);
+                        continue;
                     }
                     self.progress(Progress::DidFinish(res));
                 }
                 Event::CheckEvent(Some(message)) => match message {
+                    CargoMessage::CheckingCrate(name) => {
+                        self.progress(Progress::DidStartCrate(name));
+                    }
                     CargoMessage::CompilerArtifact(msg) => {
                         self.progress(Progress::DidCheckCrate(msg.target.name));
                     }
 
+                    // Everything but the clippy lints duplicates what the check
+                    // command already reported.
+                    CargoMessage::Diagnostic(msg)
+                        if self.clippy_pass
+                            && !msg
+                                .code
+                                .as_ref()
+                                .map_or(false, |it| it.code.starts_with("clippy::")) => {}
                     CargoMessage::Diagnostic(msg) => {
                         self.send(Message::AddDiagnostic {
                             workspace_root: self.workspace_root.clone(),
@@ -197,6 +353,9 @@ impl FlycheckActor {
             self.progress(Progress::DidCancel);
         }
     }
+    /// The command to run, `cargo clippy` during the clippy pass. The clippy
+    /// lint levels apply to the clippy pass, or to the check command if that
+    /// is clippy already.
     fn check_command(&self) -> Command {
         let mut cmd = match &self.config {
             FlycheckConfig::CargoCommand {
@@ -207,11 +366,23 @@ impl FlycheckActor {
                 all_features,
                 extra_args,
                 features,
+                clippy,
             } => {
+                let clippy = clippy.as_ref().filter(|_| self.clippy_pass || command == "clippy");
                 let mut cmd = Command::new(toolchain::cargo());
-                cmd.arg(command);
+                cmd.arg(if clippy.is_some() { "clippy" } else { command.as_str() });
                 cmd.current_dir(&self.workspace_root);
-                cmd.args(&["--workspace", "--message-format=json", "--manifest-path"])
+                match &self.packages {
+                    Some(packages) => {
+                        for package in packages {
+                            cmd.args(&["-p", &package.spec]);
+                        }
+                    }
+                    None => {
+                        cmd.arg("--workspace");
+                    }
+                }
+                cmd.args(&["--message-format=json", "--manifest-path"])
                     .arg(self.workspace_root.join("Cargo.toml").as_os_str());
 
                 if let Some(target) = target_triple {
@@ -232,9 +403,21 @@ impl FlycheckActor {
                     }
                 }
                 cmd.args(extra_args);
+                if let Some(clippy) = clippy {
+                    if !extra_args.iter().any(|it| it == "--") {
+                        cmd.arg("--");
+                    }
+                    let levels =
+                        [("-A", &clippy.allow), ("-W", &clippy.warn), ("-D", &clippy.deny)];
+                    for (flag, lints) in levels {
+                        for lint in lints {
+                            cmd.args(&[flag, lint.as_str()]);
+                        }
+                    }
+                }
                 cmd
             }
-            FlycheckConfig::CustomCommand { command, args } => {
+            FlycheckConfig::CustomCommand { command, args, .. } => {
                 let mut cmd = Command::new(command);
                 cmd.args(args);
                 cmd
@@ -255,9 +438,9 @@ struct CargoHandle {
 }
 
 impl CargoHandle {
-    fn spawn(command: Command) -> CargoHandle {
+    fn spawn(command: Command, parser: OutputParser) -> CargoHandle {
         let (sender, receiver) = unbounded();
-        let actor = CargoActor::new(sender);
+        let actor = CargoActor::new(sender, parser);
         let thread = jod_thread::Builder::new()
             .name("CargoHandle".to_owned())
             .spawn(move || actor.run(command))
@@ -272,11 +455,12 @@ impl CargoHandle {
 
 struct CargoActor {
     sender: Sender<CargoMessage>,
+    parser: OutputParser,
 }
 
 impl CargoActor {
-    fn new(sender: Sender<CargoMessage>) -> CargoActor {
-        CargoActor { sender }
+    fn new(sender: Sender<CargoMessage>, parser: OutputParser) -> CargoActor {
+        CargoActor { sender, parser }
     }
 
     fn run(self, command: Command) -> io::Result<()> {
@@ -296,30 +480,16 @@ impl CargoActor {
             &mut |line| {
                 read_at_least_one_message = true;
 
-                // Try to deserialize a message from Cargo or Rustc.
-                let mut deserializer = serde_json::Deserializer::from_str(line);
-                deserializer.disable_recursion_limit();
-                if let Ok(message) = JsonMessage::deserialize(&mut deserializer) {
-                    match message {
-                        // Skip certain kinds of messages to only spend time on what's useful
-                        JsonMessage::Cargo(message) => match message {
-                            cargo_metadata::Message::CompilerArtifact(artifact)
-                                if !artifact.fresh =>
-                            {
-                                self.sender.send(CargoMessage::CompilerArtifact(artifact)).unwrap();
-                            }
-                            cargo_metadata::Message::CompilerMessage(msg) => {
-                                self.sender.send(CargoMessage::Diagnostic(msg.message)).unwrap();
-                            }
-                            _ => (),
-                        },
-                        JsonMessage::Rustc(message) => {
-                            self.sender.send(CargoMessage::Diagnostic(message)).unwrap();
-                        }
-                    }
+                if let Some(message) = self.parser.parse(line) {
+                    self.sender.send(message).unwrap();
                 }
             },
             &mut |line| {
+                // Cargo reports the crate it starts on in its human-readable
+                // status lines only.
+                if let Some(name) = checking_crate(line) {
+                    self.sender.send(CargoMessage::CheckingCrate(name.to_owned())).unwrap();
+                }
                 error.push_str(line);
                 error.push('\n');
             },
@@ -338,14 +508,170 @@ impl CargoActor {
     }
 }
 
-enum CargoMessage {
+/// Runs `command`, a cargo build emitting JSON messages like
+/// `cargo test --no-run --message-format=json`, and returns the executables it produced, leaving
+/// out build scripts. If the build fails, the error contains the rendered compiler errors.
+pub fn build_executables(command: Command) -> io::Result<Vec<PathBuf>> {
+    let mut executables = Vec::new();
+    let mut errors = String::new();
+    let mut stderr = String::new();
+    let output = streaming_output(
+        command,
+        &mut |line| {
+            let mut deserializer = serde_json::Deserializer::from_str(line);
+            deserializer.disable_recursion_limit();
+            match cargo_metadata::Message::deserialize(&mut deserializer) {
+                Ok(cargo_metadata::Message::CompilerArtifact(artifact)) => {
+                    let is_build_script =
+                        artifact.target.kind.iter().any(|it| it == "custom-build");
+                    if let Some(executable) = artifact.executable.filter(|_| !is_build_script) {
+                        executables.push(executable.into_std_path_buf());
+                    }
+                }
+                Ok(cargo_metadata::Message::CompilerMessage(msg))
+                    if msg.message.level == DiagnosticLevel::Error =>
+                {
+                    errors
+                        .push_str(msg.message.rendered.as_deref().unwrap_or(&msg.message.message));
+                }
+                _ => (),
+            }
+        },
+        &mut |line| {
+            stderr.push_str(line);
+            stderr.push('\n');
+        },
+    )?;
+    if !output.status.success() {
+        let details = if errors.is_empty() { stderr } else { errors };
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("cargo build failed (exit code: {:?}):\n{}", output.status, details),
+        ));
+    }
+    Ok(executables)
+}
+
+/// Extracts the crate name from cargo status lines like `Checking foo v0.1.0 (/foo)`.
+fn checking_crate(line: &str) -> Option<&str> {
+    let mut words = line.split_whitespace();
+    match words.next()? {
+        "Checking" | "Compiling" => (),
+        _ => return None,
+    }
+    let name = words.next()?;
+    words.next()?.strip_prefix('v')?;
+    Some(name)
+}
+
+pub(crate) enum CargoMessage {
+    CheckingCrate(String),
     CompilerArtifact(cargo_metadata::Artifact),
     Diagnostic(Diagnostic),
 }
 
-#[derive(Deserialize)]
-#[serde(untagged)]
-enum JsonMessage {
-    Cargo(cargo_metadata::Message),
-    Rustc(Diagnostic),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checking_crate_status_lines() {
+        assert_eq!(checking_crate("    Checking foo v0.1.0 (/home/user/foo)"), Some("foo"));
+        assert_eq!(checking_crate("   Compiling proc-macro2 v1.0.43"), Some("proc-macro2"));
+        assert_eq!(
+            checking_crate("    Checking my-crate v0.2.0-alpha.1 (/path with spaces/my-crate)"),
+            Some("my-crate")
+        );
+    }
+
+    #[test]
+    fn checking_crate_other_lines() {
+        assert_eq!(
+            checking_crate("    Finished dev [unoptimized + debuginfo] target(s) in 0.52s"),
+            None
+        );
+        assert_eq!(checking_crate("    Blocking waiting for file lock on build directory"), None);
+        assert_eq!(checking_crate("   Compiling"), None);
+        assert_eq!(checking_crate("    Checking 3 targets"), None);
+        assert_eq!(checking_crate("warning: Checking foo v0.1.0"), None);
+        assert_eq!(checking_crate(""), None);
+    }
+
+    fn cargo_config(command: &str, extra_args: &[&str]) -> FlycheckConfig {
+        FlycheckConfig::CargoCommand {
+            command: command.to_string(),
+            target_triple: None,
+            all_targets: false,
+            no_default_features: false,
+            all_features: false,
+            features: Vec::new(),
+            extra_args: extra_args.iter().map(|it| it.to_string()).collect(),
+            clippy: Some(ClippyConfig {
+                allow: Vec::new(),
+                warn: vec!["clippy::pedantic".to_string()],
+                deny: Vec::new(),
+            }),
+        }
+    }
+
+    /// The arguments of the command run in the given pass, without the
+    /// manifest path as it depends on the working directory.
+    fn command_args(config: FlycheckConfig, clippy_pass: bool) -> Vec<String> {
+        let workspace_root = AbsPathBuf::assert(std::env::current_dir().unwrap());
+        let mut actor = FlycheckActor::new(0, Box::new(|_| ()), config, workspace_root);
+        actor.clippy_pass = clippy_pass;
+        let command = actor.check_command();
+        let mut args = command.get_args().map(|it| it.to_str().unwrap().to_string());
+        let mut res = Vec::new();
+        while let Some(arg) = args.next() {
+            if arg == "--manifest-path" {
+                args.next();
+            } else {
+                res.push(arg);
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn clippy_pass_command() {
+        let config = cargo_config("check", &[]);
+        assert!(config.runs_clippy_pass());
+        assert_eq!(
+            command_args(config.clone(), false),
+            ["check", "--workspace", "--message-format=json"]
+        );
+        assert_eq!(
+            command_args(config, true),
+            ["clippy", "--workspace", "--message-format=json", "--", "-W", "clippy::pedantic"]
+        );
+    }
+
+    #[test]
+    fn clippy_pass_reuses_extra_args_separator() {
+        let config = cargo_config("check", &["--", "-Wunused"]);
+        assert_eq!(
+            command_args(config, true),
+            [
+                "clippy",
+                "--workspace",
+                "--message-format=json",
+                "--",
+                "-Wunused",
+                "-W",
+                "clippy::pedantic"
+            ]
+        );
+    }
+
+    #[test]
+    fn clippy_command_skips_clippy_pass() {
+        let config = cargo_config("clippy", &[]);
+        assert!(!config.runs_clippy_pass());
+        assert_eq!(config.to_string(), "cargo clippy");
+        assert_eq!(
+            command_args(config, false),
+            ["clippy", "--workspace", "--message-format=json", "--", "-W", "clippy::pedantic"]
+        );
+    }
 }
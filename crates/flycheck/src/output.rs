@@ -0,0 +1,255 @@
+//! Parsers turning the output of a check command into diagnostics, for custom
+//! commands whose output isn't plain cargo JSON.
+
+use std::io;
+
+use cargo_metadata::diagnostic::Diagnostic;
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::CargoMessage;
+
+/// How the output of a check command is turned into diagnostics.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Cargo or rustc JSON messages, one per line.
+    Json,
+    /// Rustc JSON diagnostics, possibly preceded by other output on the same line.
+    EmbeddedJson,
+    /// Lines matching a regex with the named groups `file`, `line` and `message`, and
+    /// optionally `column`, `end_line`, `end_column`, `severity` and `code`.
+    Pattern(String),
+    /// JSON lines whose diagnostic fields are located with JSON pointers.
+    JsonPointers(JsonPointers),
+}
+
+/// JSON pointers (like `/location/file`) to the fields of a diagnostic.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonPointers {
+    pub file: String,
+    pub line: String,
+    #[serde(default)]
+    pub column: Option<String>,
+    #[serde(default)]
+    pub end_line: Option<String>,
+    #[serde(default)]
+    pub end_column: Option<String>,
+    #[serde(default)]
+    pub severity: Option<String>,
+    #[serde(default)]
+    pub code: Option<String>,
+    pub message: String,
+}
+
+#[derive(Clone)]
+pub(crate) enum OutputParser {
+    Json,
+    EmbeddedJson,
+    Pattern(Regex),
+    JsonPointers(JsonPointers),
+}
+
+impl OutputParser {
+    pub(crate) fn new(format: &OutputFormat) -> io::Result<OutputParser> {
+        let parser = match format {
+            OutputFormat::Json => OutputParser::Json,
+            OutputFormat::EmbeddedJson => OutputParser::EmbeddedJson,
+            OutputFormat::Pattern(pattern) => {
+                let regex = Regex::new(pattern).map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("invalid diagnostic pattern {:?}: {}", pattern, e),
+                    )
+                })?;
+                OutputParser::Pattern(regex)
+            }
+            OutputFormat::JsonPointers(pointers) => OutputParser::JsonPointers(pointers.clone()),
+        };
+        Ok(parser)
+    }
+
+    /// Parses a single line of the command's stdout, returning `None` for lines
+    /// that aren't interesting.
+    pub(crate) fn parse(&self, line: &str) -> Option<CargoMessage> {
+        match self {
+            OutputParser::Json => parse_json(line),
+            OutputParser::EmbeddedJson => {
+                line.match_indices('{').find_map(|(idx, _)| parse_json(&line[idx..]))
+            }
+            OutputParser::Pattern(regex) => {
+                let captures = regex.captures(line)?;
+                let field = |name: &str| captures_str(&captures, name);
+                let diagnostic = make_diagnostic(Fields {
+                    file: field("file")?,
+                    line: field("line")?.parse().ok()?,
+                    column: field("column").and_then(|it| it.parse().ok()),
+                    end_line: field("end_line").and_then(|it| it.parse().ok()),
+                    end_column: field("end_column").and_then(|it| it.parse().ok()),
+                    severity: field("severity"),
+                    code: field("code"),
+                    message: field("message")?,
+                })?;
+                Some(CargoMessage::Diagnostic(diagnostic))
+            }
+            OutputParser::JsonPointers(pointers) => {
+                let value: Value = serde_json::from_str(line.trim()).ok()?;
+                let field =
+                    |pointer: &Option<String>| pointer.as_deref().and_then(|it| value.pointer(it));
+                let diagnostic = make_diagnostic(Fields {
+                    file: value.pointer(&pointers.file)?.as_str()?,
+                    line: value_usize(value.pointer(&pointers.line)?)?,
+                    column: field(&pointers.column).and_then(value_usize),
+                    end_line: field(&pointers.end_line).and_then(value_usize),
+                    end_column: field(&pointers.end_column).and_then(value_usize),
+                    severity: field(&pointers.severity).and_then(Value::as_str),
+                    code: field(&pointers.code).and_then(Value::as_str),
+                    message: value.pointer(&pointers.message)?.as_str()?,
+                })?;
+                Some(CargoMessage::Diagnostic(diagnostic))
+            }
+        }
+    }
+}
+
+fn parse_json(line: &str) -> Option<CargoMessage> {
+    // Try to deserialize a message from Cargo or Rustc.
+    let mut deserializer = serde_json::Deserializer::from_str(line);
+    deserializer.disable_recursion_limit();
+    match JsonMessage::deserialize(&mut deserializer).ok()? {
+        // Skip certain kinds of messages to only spend time on what's useful
+        JsonMessage::Cargo(message) => match message {
+            cargo_metadata::Message::CompilerArtifact(artifact) if !artifact.fresh => {
+                Some(CargoMessage::CompilerArtifact(artifact))
+            }
+            cargo_metadata::Message::CompilerMessage(msg) => {
+                Some(CargoMessage::Diagnostic(msg.message))
+            }
+            _ => None,
+        },
+        JsonMessage::Rustc(message) => Some(CargoMessage::Diagnostic(message)),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonMessage {
+    Cargo(cargo_metadata::Message),
+    Rustc(Diagnostic),
+}
+
+struct Fields<'a> {
+    file: &'a str,
+    line: usize,
+    column: Option<usize>,
+    end_line: Option<usize>,
+    end_column: Option<usize>,
+    severity: Option<&'a str>,
+    code: Option<&'a str>,
+    message: &'a str,
+}
+
+/// Builds a rustc diagnostic with a single primary span, lines and columns being 1-based.
+fn make_diagnostic(fields: Fields<'_>) -> Option<Diagnostic> {
+    let column = fields.column.unwrap_or(1);
+    let level = match fields.severity.map(|it| it.to_ascii_lowercase()).as_deref() {
+        Some(it) if it.starts_with("warn") => "warning",
+        Some(it) if it == "note" || it.starts_with("info") => "note",
+        Some("help" | "hint") => "help",
+        _ => "error",
+    };
+    let diagnostic = json!({
+        "message": fields.message,
+        "code": fields.code.map(|code| json!({ "code": code, "explanation": null })),
+        "level": level,
+        "spans": [{
+            "file_name": fields.file,
+            "byte_start": 0,
+            "byte_end": 0,
+            "line_start": fields.line,
+            "line_end": fields.end_line.unwrap_or(fields.line),
+            "column_start": column,
+            "column_end": fields.end_column.unwrap_or(column),
+            "is_primary": true,
+            "text": [],
+            "label": null,
+            "suggested_replacement": null,
+            "suggestion_applicability": null,
+            "expansion": null,
+        }],
+        "children": [],
+        "rendered": null,
+    });
+    serde_json::from_value(diagnostic).ok()
+}
+
+fn captures_str<'a>(captures: &Captures<'a>, name: &str) -> Option<&'a str> {
+    captures.name(name).map(|it| it.as_str())
+}
+
+fn value_usize(value: &Value) -> Option<usize> {
+    match value {
+        Value::Number(it) => it.as_u64().map(|it| it as usize),
+        Value::String(it) => it.parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(format: OutputFormat, line: &str) -> Option<Diagnostic> {
+        match OutputParser::new(&format).unwrap().parse(line)? {
+            CargoMessage::Diagnostic(it) => Some(it),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn embedded_json() {
+        let line = r#"INFO: From Compiling src/lib.rs: {"message":"unused variable: `x`","code":{"code":"unused_variables","explanation":null},"level":"warning","spans":[],"children":[],"rendered":null}"#;
+        assert!(parse(OutputFormat::Json, line).is_none());
+        let diagnostic = parse(OutputFormat::EmbeddedJson, line).unwrap();
+        assert_eq!(diagnostic.message, "unused variable: `x`");
+        assert_eq!(diagnostic.code.unwrap().code, "unused_variables");
+    }
+
+    #[test]
+    fn pattern() {
+        let format = OutputFormat::Pattern(
+            r"^(?P<file>[^:]+):(?P<line>\d+):(?P<column>\d+): (?P<severity>\w+): (?P<message>.*)$"
+                .to_string(),
+        );
+        let diagnostic = parse(format.clone(), "src/main.rs:3:5: warning: unused import").unwrap();
+        assert_eq!(diagnostic.message, "unused import");
+        assert_eq!(diagnostic.level, cargo_metadata::diagnostic::DiagnosticLevel::Warning);
+        let span = &diagnostic.spans[0];
+        assert_eq!(
+            (span.file_name.as_str(), span.line_start, span.column_start),
+            ("src/main.rs", 3, 5)
+        );
+        assert!(parse(format, "Compiling 3 targets").is_none());
+    }
+
+    #[test]
+    fn json_pointers() {
+        let format = OutputFormat::JsonPointers(JsonPointers {
+            file: "/location/path".to_string(),
+            line: "/location/line".to_string(),
+            column: None,
+            end_line: None,
+            end_column: None,
+            severity: Some("/kind".to_string()),
+            code: Some("/rule".to_string()),
+            message: "/text".to_string(),
+        });
+        let line = r#"{"location":{"path":"src/lib.rs","line":"12"},"kind":"error","rule":"E42","text":"bad"}"#;
+        let diagnostic = parse(format, line).unwrap();
+        assert_eq!(diagnostic.message, "bad");
+        assert_eq!(diagnostic.code.unwrap().code, "E42");
+        assert_eq!(diagnostic.spans[0].line_start, 12);
+        assert_eq!(diagnostic.spans[0].column_start, 1);
+    }
+}
@@ -295,6 +295,58 @@ impl Attrs {
         })
     }
 
+    /// Returns the names given with `#[doc(alias = "name")]` and `#[doc(alias("a", "b"))]`.
+    pub fn doc_aliases(&self) -> Vec<SmolStr> {
+        let unquote = |lit: &tt::Literal| {
+            let text = lit.text.strip_prefix('"')?.strip_suffix('"')?;
+            Some(SmolStr::new(text))
+        };
+        let mut res = Vec::new();
+        for tt in self.by_key("doc").tt_values() {
+            let mut iter = tt.token_trees.iter();
+            while let Some(tt) = iter.next() {
+                match tt {
+                    tt::TokenTree::Leaf(tt::Leaf::Ident(ident)) if ident.text == "alias" => {}
+                    _ => continue,
+                }
+                match iter.next() {
+                    Some(tt::TokenTree::Leaf(tt::Leaf::Punct(punct))) if punct.char == '=' => {
+                        if let Some(tt::TokenTree::Leaf(tt::Leaf::Literal(lit))) = iter.next() {
+                            res.extend(unquote(lit));
+                        }
+                    }
+                    Some(tt::TokenTree::Subtree(subtree)) => {
+                        res.extend(subtree.token_trees.iter().filter_map(|tt| match tt {
+                            tt::TokenTree::Leaf(tt::Leaf::Literal(lit)) => unquote(lit),
+                            _ => None,
+                        }));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        res
+    }
+
+    /// Returns the feature gate named by `#[unstable(feature = "name")]`.
+    pub fn unstable_feature(&self) -> Option<SmolStr> {
+        let feature = self.by_key("unstable").find_string_value_in_tt("feature")?;
+        Some(SmolStr::new(feature.trim_matches('"')))
+    }
+
+    /// Whether `#[allow_internal_unstable]` lets a macro use `feature` in its expansion. The bare
+    /// attribute allows every feature.
+    pub fn allows_internal_unstable(&self, feature: &str) -> bool {
+        self.by_key("allow_internal_unstable").attrs().any(|attr| {
+            match attr.token_tree_value() {
+            Some(subtree) => subtree.token_trees.iter().any(|tt| {
+                matches!(tt, tt::TokenTree::Leaf(tt::Leaf::Ident(ident)) if ident.text == feature)
+            }),
+            None => true,
+        }
+        })
+    }
+
     pub fn is_proc_macro(&self) -> bool {
         self.by_key("proc_macro").exists()
     }
@@ -1,6 +1,7 @@
 //! Defines `Body`: a lowered representation of bodies of functions, statics and
 //! consts.
 mod lower;
+mod pretty;
 #[cfg(test)]
 mod tests;
 pub mod scope;
@@ -374,6 +375,22 @@ impl Body {
         }
     }
 
+    /// Renders this body as Rust-like source text, for debugging purposes.
+    pub fn pretty_print(&self, db: &dyn DefDatabase, owner: DefWithBodyId) -> String {
+        pretty::print_body_hir(db, self, owner, None)
+    }
+
+    /// Like [`Body::pretty_print`], but annotates every expression with its `ExprId` and the
+    /// text range it was lowered from.
+    pub fn pretty_print_with_source_map(
+        &self,
+        db: &dyn DefDatabase,
+        owner: DefWithBodyId,
+        source_map: &BodySourceMap,
+    ) -> String {
+        pretty::print_body_hir(db, self, owner, Some(source_map))
+    }
+
     fn new(
         db: &dyn DefDatabase,
         expander: Expander,
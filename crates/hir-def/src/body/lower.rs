@@ -29,8 +29,8 @@ use crate::{
     builtin_type::{BuiltinFloat, BuiltinInt, BuiltinUint},
     db::DefDatabase,
     expr::{
-        dummy_expr_id, Array, BindingAnnotation, Expr, ExprId, Label, LabelId, Literal, MatchArm,
-        Pat, PatId, RecordFieldPat, RecordLitField, Statement,
+        dummy_expr_id, Array, BindingAnnotation, CaptureBy, Expr, ExprId, Label, LabelId, Literal,
+        MatchArm, Pat, PatId, RecordFieldPat, RecordLitField, Statement,
     },
     intern::Interned,
     item_scope::BuiltinShadowMode,
@@ -451,12 +451,15 @@ impl ExprCollector<'_> {
                     .and_then(|r| r.ty())
                     .map(|it| Interned::new(TypeRef::from_ast(&self.ctx(), it)));
                 let body = self.collect_expr_opt(e.body());
+                let capture_by =
+                    if e.move_token().is_some() { CaptureBy::Value } else { CaptureBy::Ref };
                 self.alloc_expr(
                     Expr::Closure {
                         args: args.into(),
                         arg_types: arg_types.into(),
                         ret_type,
                         body,
+                        capture_by,
                     },
                     syntax_ptr,
                 )
@@ -693,12 +696,12 @@ impl ExprCollector<'_> {
             BlockLoc { ast_id, module: self.expander.def_map.module_id(self.expander.module) };
         let block_id = self.db.intern_block(block_loc);
 
-        let (module, def_map) = match self.db.block_def_map(block_id) {
+        let (block_id, module, def_map) = match self.db.block_def_map(block_id) {
             Some(def_map) => {
                 self.body.block_scopes.push(block_id);
-                (def_map.root(), def_map)
+                (Some(block_id), def_map.root(), def_map)
             }
-            None => (self.expander.module, self.expander.def_map.clone()),
+            None => (None, self.expander.module, self.expander.def_map.clone()),
         };
         let prev_def_map = mem::replace(&mut self.expander.def_map, def_map);
         let prev_local_module = mem::replace(&mut self.expander.module, module);
@@ -868,16 +871,10 @@ impl ExprCollector<'_> {
                     suffix: suffix.into_iter().map(|p| self.collect_pat_(p)).collect(),
                 }
             }
-            ast::Pat::LiteralPat(lit) => {
-                if let Some(ast_lit) = lit.literal() {
-                    let expr = Expr::Literal(ast_lit.kind().into());
-                    let expr_ptr = AstPtr::new(&ast::Expr::Literal(ast_lit));
-                    let expr_id = self.alloc_expr(expr, expr_ptr);
-                    Pat::Lit(expr_id)
-                } else {
-                    Pat::Missing
-                }
-            }
+            ast::Pat::LiteralPat(lit) => match self.collect_literal_pat(&lit) {
+                Some(expr_id) => Pat::Lit(expr_id),
+                None => Pat::Missing,
+            },
             ast::Pat::RestPat(_) => {
                 // `RestPat` requires special handling and should not be mapped
                 // to a Pat. Here we are using `Pat::Missing` as a fallback for
@@ -910,8 +907,14 @@ impl ExprCollector<'_> {
                 }
                 None => Pat::Missing,
             },
-            // FIXME: implement
-            ast::Pat::RangePat(_) => Pat::Missing,
+            ast::Pat::RangePat(p) => match p.op_kind() {
+                Some(range_type) => {
+                    let start = p.start().map(|start| self.collect_range_pat_bound(start));
+                    let end = p.end().map(|end| self.collect_range_pat_bound(end));
+                    Pat::Range { start, end, range_type }
+                }
+                None => Pat::Missing,
+            },
         };
         let ptr = AstPtr::new(&pat);
         self.alloc_pat(pattern, Either::Left(ptr))
@@ -924,6 +927,35 @@ impl ExprCollector<'_> {
         }
     }
 
+    fn collect_literal_pat(&mut self, lit: &ast::LiteralPat) -> Option<ExprId> {
+        let ast_lit = lit.literal()?;
+        let mut literal: Literal = ast_lit.kind().into();
+        if lit.minus_token().is_some() {
+            literal = literal.negate()?;
+        }
+        let expr_ptr = AstPtr::new(&ast::Expr::Literal(ast_lit));
+        Some(self.alloc_expr(Expr::Literal(literal), expr_ptr))
+    }
+
+    /// Lowers the start or end of a range pattern, which may only be a literal or a path to a
+    /// constant.
+    fn collect_range_pat_bound(&mut self, pat: ast::Pat) -> ExprId {
+        let path = match &pat {
+            ast::Pat::LiteralPat(lit) => {
+                return self.collect_literal_pat(lit).unwrap_or_else(|| self.missing_expr())
+            }
+            ast::Pat::PathPat(p) => {
+                p.path().and_then(|path| self.expander.parse_path(self.db, path))
+            }
+            ast::Pat::IdentPat(p) if p.pat().is_none() => p.name().map(|it| it.as_name().into()),
+            _ => None,
+        };
+        match path {
+            Some(path) => self.alloc_expr_desugared(Expr::Path(path)),
+            None => self.missing_expr(),
+        }
+    }
+
     fn collect_tuple_pat(&mut self, args: AstChildren<ast::Pat>) -> (Box<[PatId]>, Option<usize>) {
         // Find the location of the `..`, if there is one. Note that we do not
         // consider the possibility of there being multiple `..` here.
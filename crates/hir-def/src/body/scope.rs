@@ -63,7 +63,8 @@ impl ExprScopes {
         &self.scopes[scope].entries
     }
 
-    /// If `scope` refers to a block expression scope, returns the corresponding `BlockId`.
+    /// If `scope` refers to the scope of a block expression containing items, returns the
+    /// corresponding `BlockId`.
     pub fn block(&self, scope: ScopeId) -> Option<BlockId> {
         self.scopes[scope].block
     }
@@ -110,15 +111,10 @@ impl ExprScopes {
     fn new_block_scope(
         &mut self,
         parent: ScopeId,
-        block: BlockId,
+        block: Option<BlockId>,
         label: Option<(LabelId, Name)>,
     ) -> ScopeId {
-        self.scopes.alloc(ScopeData {
-            parent: Some(parent),
-            block: Some(block),
-            label,
-            entries: vec![],
-        })
+        self.scopes.alloc(ScopeData { parent: Some(parent), block, label, entries: vec![] })
     }
 
     fn add_bindings(&mut self, body: &Body, scope: ScopeId, pat: PatId) {
@@ -1,7 +1,7 @@
 mod block;
 
 use base_db::{fixture::WithFixture, SourceDatabase};
-use expect_test::Expect;
+use expect_test::{expect, Expect};
 
 use crate::ModuleDefId;
 
@@ -25,6 +25,29 @@ fn lower(ra_fixture: &str) -> Arc<Body> {
     db.body(fn_def.unwrap().into())
 }
 
+fn check_pretty_print(ra_fixture: &str, with_source_map: bool, expect: Expect) {
+    let db = crate::test_db::TestDB::with_files(ra_fixture);
+
+    let krate = db.crate_graph().iter().next().unwrap();
+    let def_map = db.crate_def_map(krate);
+    let fn_def = def_map[def_map.root()]
+        .scope
+        .declarations()
+        .find_map(|decl| match decl {
+            ModuleDefId::FunctionId(it) => Some(it),
+            _ => None,
+        })
+        .unwrap();
+
+    let (body, source_map) = db.body_with_source_map(fn_def.into());
+    let actual = if with_source_map {
+        body.pretty_print_with_source_map(&db, fn_def.into(), &source_map)
+    } else {
+        body.pretty_print(&db, fn_def.into())
+    };
+    expect.assert_eq(&actual);
+}
+
 fn block_def_map_at(ra_fixture: &str) -> String {
     let (db, position) = crate::test_db::TestDB::with_position(ra_fixture);
 
@@ -125,3 +148,41 @@ mod m {
 "#,
     );
 }
+
+#[test]
+fn pretty_print_function_body() {
+    check_pretty_print(
+        r#"
+fn foo(a: u32, b: &str) -> bool {
+    let c = a + 1;
+    if c > 2u8 { true } else { b.is_empty() }
+}
+"#,
+        false,
+        expect![[r#"
+            fn foo(a: u32, b: &str) -> bool {
+                let c = (a) + (1);
+                if (c) > (2u8) {
+                    true
+                } else {
+                    b.is_empty()
+                }
+            }
+        "#]],
+    );
+}
+
+#[test]
+fn pretty_print_with_source_map() {
+    check_pretty_print(
+        r#"
+fn f() { 92 }
+"#,
+        true,
+        expect![[r#"
+            fn f() /*e1 7..13*/ {
+                /*e0 9..11*/ 92
+            }
+        "#]],
+    );
+}
@@ -395,3 +395,25 @@ fn foo() {
         "#]],
     )
 }
+
+#[test]
+fn registered_tools_in_block() {
+    let (db, position) = crate::test_db::TestDB::with_position(
+        r#"
+#![register_tool(kani, cxx)]
+#![register_attr(attr)]
+
+fn outer() {
+    #[kani::proof]
+    #[attr]
+    fn inner() {}
+    $0
+}
+"#,
+    );
+
+    let def_map = db.module_at_position(position).def_map(&db);
+    assert!(def_map.block_id().is_some());
+    assert_eq!(def_map.registered_tools(), ["kani", "cxx"]);
+    assert_eq!(def_map.registered_attrs(), ["attr"]);
+}
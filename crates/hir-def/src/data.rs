@@ -6,7 +6,6 @@ use hir_expand::{name::Name, AstId, ExpandResult, HirFileId, InFile, MacroCallId
 use syntax::ast;
 
 use crate::{
-    attr::Attrs,
     body::{Expander, Mark},
     db::DefDatabase,
     intern::Interned,
@@ -25,7 +24,6 @@ pub struct FunctionData {
     pub params: Vec<(Option<Name>, Interned<TypeRef>)>,
     pub ret_type: Interned<TypeRef>,
     pub async_ret_type: Option<Interned<TypeRef>>,
-    pub attrs: Attrs,
     pub visibility: RawVisibility,
     pub abi: Option<Interned<str>>,
     pub legacy_const_generics_indices: Vec<u32>,
@@ -92,7 +90,6 @@ impl FunctionData {
                 .collect(),
             ret_type: func.ret_type.clone(),
             async_ret_type: func.async_ret_type.clone(),
-            attrs: item_tree.attrs(db, krate, ModItem::from(loc.id.value).into()),
             visibility: item_tree[func.visibility].clone(),
             abi: func.abi.clone(),
             legacy_const_generics_indices,
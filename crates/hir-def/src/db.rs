@@ -69,6 +69,13 @@ pub trait DefDatabase: InternDatabase + AstDatabase + Upcast<dyn AstDatabase> {
     #[salsa::invoke(ItemTree::file_item_tree_query)]
     fn file_item_tree(&self, file_id: HirFileId) -> Arc<ItemTree>;
 
+    /// Computes the `ItemTree` of the items directly contained in `block`.
+    ///
+    /// This is cached separately from the containing file's `ItemTree` so that edits elsewhere in
+    /// the file produce an identical tree and don't force `block_def_map` to be recomputed.
+    #[salsa::invoke(ItemTree::block_item_tree_query)]
+    fn block_item_tree(&self, block: BlockId) -> Arc<ItemTree>;
+
     #[salsa::invoke(crate_def_map_wait)]
     #[salsa::transparent]
     fn crate_def_map(&self, krate: CrateId) -> Arc<DefMap>;
@@ -49,6 +49,21 @@ pub enum Literal {
     Float(u64, Option<BuiltinFloat>), // FIXME: f64 is not Eq
 }
 
+impl Literal {
+    /// Negates a numeric literal, as in the pattern `-1`. Returns `None` for literals that cannot
+    /// be negated.
+    pub fn negate(self) -> Option<Self> {
+        match self {
+            Literal::Int(it, builtin) => Some(Literal::Int(it.wrapping_neg(), builtin)),
+            Literal::Uint(it, None) => Some(Literal::Int((it as i128).wrapping_neg(), None)),
+            Literal::Float(it, builtin) => {
+                Some(Literal::Float((-f64::from_bits(it)).to_bits(), builtin))
+            }
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Expr {
     /// This is produced if the syntax tree does not have a required expression piece.
@@ -64,7 +79,9 @@ pub enum Expr {
         expr: ExprId,
     },
     Block {
-        id: BlockId,
+        /// The block's `DefMap`, if it contains items. Blocks without items don't refer to their
+        /// `BlockId`, as its `AstId` changes whenever a block is added before it in the file.
+        id: Option<BlockId>,
         statements: Box<[Statement]>,
         tail: Option<ExprId>,
         label: Option<LabelId>,
@@ -170,6 +187,7 @@ pub enum Expr {
         arg_types: Box<[Option<Interned<TypeRef>>]>,
         ret_type: Option<Interned<TypeRef>>,
         body: ExprId,
+        capture_by: CaptureBy,
     },
     Tuple {
         exprs: Box<[ExprId]>,
@@ -184,6 +202,15 @@ pub enum Expr {
     Literal(Literal),
 }
 
+/// How a closure captures the variables it uses from its environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureBy {
+    /// `move |x| y + x`.
+    Value,
+    /// `move` keyword was not specified.
+    Ref,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Array {
     ElementList(Box<[ExprId]>),
@@ -375,7 +402,7 @@ pub enum Pat {
     Tuple { args: Box<[PatId]>, ellipsis: Option<usize> },
     Or(Box<[PatId]>),
     Record { path: Option<Box<Path>>, args: Box<[RecordFieldPat]>, ellipsis: bool },
-    Range { start: ExprId, end: ExprId },
+    Range { start: Option<ExprId>, end: Option<ExprId>, range_type: RangeOp },
     Slice { prefix: Box<[PatId]>, slice: Option<PatId>, suffix: Box<[PatId]> },
     Path(Box<Path>),
     Lit(ExprId),
@@ -8,6 +8,7 @@ use hir_expand::name::Name;
 use indexmap::{map::Entry, IndexMap};
 use itertools::Itertools;
 use rustc_hash::{FxHashSet, FxHasher};
+use syntax::SmolStr;
 
 use crate::{
     db::DefDatabase, item_scope::ItemInNs, visibility::Visibility, AssocItemId, ModuleDefId,
@@ -25,6 +26,25 @@ pub struct ImportInfo {
     pub container: ModuleId,
     /// Whether the import is a trait associated item or not.
     pub is_trait_assoc_item: bool,
+    /// The names given to the item with `#[doc(alias = "...")]`.
+    pub doc_aliases: Vec<SmolStr>,
+}
+
+impl ImportInfo {
+    /// Returns the text a query is matched against: either the whole path or just the item's
+    /// name, with the name replaced by `alias` if the item is being matched by one of its doc
+    /// aliases.
+    fn match_text(&self, alias: Option<&SmolStr>, name_only: bool) -> String {
+        let name = match alias {
+            Some(alias) => alias.to_string(),
+            None => self.path.segments.last().map(ToString::to_string).unwrap_or_default(),
+        };
+        if name_only {
+            return name;
+        }
+        let qualifier = &self.path.segments[..self.path.segments.len().saturating_sub(1)];
+        qualifier.iter().map(ToString::to_string).chain(Some(name)).join("::")
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -62,7 +82,10 @@ pub struct ImportMap {
     /// Since a path can refer to multiple items due to namespacing, we store all items with the
     /// same path right after each other. This allows us to find all items after the FST gives us
     /// the index of the first one.
-    importables: Vec<ItemInNs>,
+    ///
+    /// Items with doc aliases are stored once more per alias, under their path with the name
+    /// replaced by the alias, which is stored alongside the item.
+    importables: Vec<(ItemInNs, Option<SmolStr>)>,
     fst: fst::Map<Vec<u8>>,
 }
 
@@ -75,7 +98,12 @@ impl ImportMap {
         let mut importables = import_map
             .map
             .iter()
-            .map(|(item, info)| (item, fst_path(&info.path)))
+            .flat_map(|(&item, info)| {
+                let aliases = info.doc_aliases.iter().map(move |alias| {
+                    ((item, Some(alias.clone())), fst_path(&info.match_text(Some(alias), false)))
+                });
+                std::iter::once(((item, None), fst_path(&info.path.to_string()))).chain(aliases)
+            })
             .collect::<Vec<_>>();
         importables.sort_by(|(_, fst_path), (_, fst_path2)| fst_path.cmp(fst_path2));
 
@@ -98,7 +126,8 @@ impl ImportMap {
         }
 
         import_map.fst = builder.into_map();
-        import_map.importables = importables.iter().map(|&(&item, _)| item).collect();
+        import_map.importables =
+            importables.into_iter().map(|(importable, _)| importable).collect();
 
         Arc::new(import_map)
     }
@@ -140,6 +169,7 @@ impl ImportMap {
             let mut assoc_item_info = original_import_info.clone();
             assoc_item_info.path.segments.push(assoc_item_name.to_owned());
             assoc_item_info.is_trait_assoc_item = true;
+            assoc_item_info.doc_aliases = doc_aliases(db, assoc_item);
             self.map.insert(assoc_item, assoc_item_info);
         }
     }
@@ -184,8 +214,12 @@ fn collect_import_map(db: &dyn DefDatabase, krate: CrateId) -> ImportMap {
             for item in per_ns.iter_items() {
                 let path = mk_path();
                 let path_len = path.len();
-                let import_info =
-                    ImportInfo { path, container: module, is_trait_assoc_item: false };
+                let import_info = ImportInfo {
+                    path,
+                    container: module,
+                    is_trait_assoc_item: false,
+                    doc_aliases: doc_aliases(db, item),
+                };
 
                 if let Some(ModuleDefId::TraitId(tr)) = item.as_module_def_id() {
                     import_map.collect_trait_assoc_items(
@@ -223,6 +257,25 @@ fn collect_import_map(db: &dyn DefDatabase, krate: CrateId) -> ImportMap {
     import_map
 }
 
+fn doc_aliases(db: &dyn DefDatabase, item: ItemInNs) -> Vec<SmolStr> {
+    let attr_def_id = match item {
+        ItemInNs::Macros(id) => id.into(),
+        ItemInNs::Types(id) | ItemInNs::Values(id) => match id {
+            ModuleDefId::ModuleId(it) => it.into(),
+            ModuleDefId::FunctionId(it) => it.into(),
+            ModuleDefId::AdtId(it) => it.into(),
+            ModuleDefId::EnumVariantId(it) => it.into(),
+            ModuleDefId::ConstId(it) => it.into(),
+            ModuleDefId::StaticId(it) => it.into(),
+            ModuleDefId::TraitId(it) => it.into(),
+            ModuleDefId::TypeAliasId(it) => it.into(),
+            ModuleDefId::MacroId(it) => it.into(),
+            ModuleDefId::BuiltinType(_) => return Vec::new(),
+        },
+    };
+    db.attrs(attr_def_id).doc_aliases()
+}
+
 impl PartialEq for ImportMap {
     fn eq(&self, other: &Self) -> bool {
         // `fst` and `importables` are built from `map`, so we don't need to compare them.
@@ -252,7 +305,7 @@ impl fmt::Debug for ImportMap {
     }
 }
 
-fn fst_path(path: &ImportPath) -> String {
+fn fst_path(path: &str) -> String {
     let _p = profile::span("fst_path");
     let mut s = path.to_string();
     s.make_ascii_lowercase();
@@ -346,7 +399,12 @@ impl Query {
         self
     }
 
-    fn import_matches(&self, import: &ImportInfo, enforce_lowercase: bool) -> bool {
+    fn import_matches(
+        &self,
+        import: &ImportInfo,
+        alias: Option<&SmolStr>,
+        enforce_lowercase: bool,
+    ) -> bool {
         let _p = profile::span("import_map::Query::import_matches");
         if import.is_trait_assoc_item {
             if self.exclude_import_kinds.contains(&ImportKind::AssociatedItem) {
@@ -356,11 +414,7 @@ impl Query {
             return false;
         }
 
-        let mut input = if import.is_trait_assoc_item || self.name_only {
-            import.path.segments.last().unwrap().to_string()
-        } else {
-            import.path.to_string()
-        };
+        let mut input = import.match_text(alias, import.is_trait_assoc_item || self.name_only);
         if enforce_lowercase || !self.case_sensitive {
             input.make_ascii_lowercase();
         }
@@ -423,27 +477,32 @@ pub fn search_dependencies<'a>(
         let import_map = &import_maps[indexed_value.index];
         let importables = &import_map.importables[indexed_value.value as usize..];
 
-        let common_importable_data = &import_map.map[&importables[0]];
-        if !query.import_matches(common_importable_data, true) {
+        let (common_item, common_alias) = &importables[0];
+        let common_importable_data = &import_map.map[common_item];
+        if !query.import_matches(common_importable_data, common_alias.as_ref(), true) {
             continue;
         }
 
         // Path shared by the importable items in this group.
-        let common_importables_path_fst = fst_path(&common_importable_data.path);
+        let common_importables_path_fst =
+            fst_path(&common_importable_data.match_text(common_alias.as_ref(), false));
         // Add the items from this `ModPath` group. Those are all subsequent items in
         // `importables` whose paths match `path`.
         let iter = importables
             .iter()
-            .copied()
-            .take_while(|item| common_importables_path_fst == fst_path(&import_map.map[item].path))
-            .filter(|&item| match item_import_kind(item) {
+            .take_while(|(item, alias)| {
+                common_importables_path_fst
+                    == fst_path(&import_map.map[item].match_text(alias.as_ref(), false))
+            })
+            .filter(|&&(item, _)| match item_import_kind(item) {
                 Some(import_kind) => !query.exclude_import_kinds.contains(&import_kind),
                 None => true,
             })
-            .filter(|item| {
+            .filter(|(item, alias)| {
                 !query.case_sensitive // we've already checked the common importables path case-insensitively
-                        || query.import_matches(&import_map.map[item], false)
-            });
+                        || query.import_matches(&import_map.map[item], alias.as_ref(), false)
+            })
+            .map(|&(item, _)| item);
         res.extend(iter);
 
         if res.len() >= query.limit {
@@ -1105,4 +1164,49 @@ mod tests {
             expect![[r#""#]],
         );
     }
+
+    #[test]
+    fn search_doc_aliases() {
+        let ra_fixture = r#"
+            //- /main.rs crate:main deps:dep
+            //- /dep.rs crate:dep
+            pub mod vec {
+                #[doc(alias = "pop_back")]
+                pub fn remove() {}
+            }
+
+            #[doc(alias("Dictionary", "Table"))]
+            pub struct HashMap {}
+        "#;
+
+        check_search(
+            ra_fixture,
+            "main",
+            Query::new("pop_back".to_string()),
+            expect![[r#"
+                dep::vec::remove (f)
+            "#]],
+        );
+
+        check_search(
+            ra_fixture,
+            "main",
+            Query::new("dictionary".to_string()).name_only(),
+            expect![[r#"
+                dep::HashMap (t)
+            "#]],
+        );
+
+        check_search(
+            ra_fixture,
+            "main",
+            Query::new("Table".to_string())
+                .name_only()
+                .search_mode(SearchMode::Equals)
+                .case_sensitive(),
+            expect![[r#"
+                dep::HashMap (t)
+            "#]],
+        );
+    }
 }
@@ -37,6 +37,7 @@ pub mod expr;
 pub mod body;
 pub mod resolver;
 
+mod pretty;
 mod trace;
 pub mod nameres;
 
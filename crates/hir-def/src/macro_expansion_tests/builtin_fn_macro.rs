@@ -325,11 +325,36 @@ fn main() { concat_bytes!(b'A', b"BC", [68, b'E', 70]); }
 #[rustc_builtin_macro]
 macro_rules! concat_bytes {}
 
-fn main() { [b'A', 66, 67, 68, b'E', 70]; }
+fn main() { b"ABCDEF"; }
 "##]],
     );
 }
 
+#[test]
+fn test_type_ascribe_expand() {
+    check(
+        r#"
+#[rustc_builtin_macro]
+macro_rules! type_ascribe {}
+
+fn main() {
+    let x = type_ascribe!(foo(1, 2), Vec<u8>);
+}
+"#,
+        expect![[r#"
+#[rustc_builtin_macro]
+macro_rules! type_ascribe {}
+
+fn main() {
+    let x =  {
+        let it: Vec<u8> = foo(1, 2);
+        it
+    };
+}
+"#]],
+    );
+}
+
 #[test]
 fn test_concat_with_captured_expr() {
     check(
@@ -5,6 +5,7 @@ mod tt_conversion;
 mod matching;
 mod meta_syntax;
 mod regression;
+mod metavar_expr;
 
 use expect_test::expect;
 
@@ -0,0 +1,53 @@
+//! Tests for metavariable expressions, like `${count()}` and `${index()}`.
+
+use expect_test::expect;
+
+use crate::macro_expansion_tests::check;
+
+#[test]
+fn test_index_and_len() {
+    check(
+        r#"
+macro_rules! m {
+    ($($a:ident)*) => { [$((${index()}, ${len()}, $a)),*] };
+}
+fn f() {
+    let _ = m!(a b c);
+}
+"#,
+        expect![[r#"
+macro_rules! m {
+    ($($a:ident)*) => { [$((${index()}, ${len()}, $a)),*] };
+}
+fn f() {
+    let _ = [(0, 3, a), (1, 3, b), (2, 3, c)];
+}
+"#]],
+    );
+}
+
+#[test]
+fn test_count_and_ignore() {
+    check(
+        r#"
+macro_rules! m {
+    ($($a:ident: $($b:literal),*);*) => {
+        [${count(a)}, ${count(b)}, ${count(b, 0)}, $(${ignore(a)} ${count(b)}),*]
+    };
+}
+fn f() {
+    let _ = m!(x: 1, 2; y: 3);
+}
+"#,
+        expect![[r#"
+macro_rules! m {
+    ($($a:ident: $($b:literal),*);*) => {
+        [${count(a)}, ${count(b)}, ${count(b, 0)}, $(${ignore(a)} ${count(b)}),*]
+    };
+}
+fn f() {
+    let _ = [2, 3, 2, 2, 1];
+}
+"#]],
+    );
+}
@@ -53,6 +53,35 @@ struct S;
     );
 }
 
+#[test]
+fn derive_censoring_cfg() {
+    check(
+        r#"
+//- proc_macros: derive_identity
+//- minicore:derive
+#[derive(proc_macros::DeriveIdentity)]
+struct S {
+    #[cfg(never)]
+    a: u32,
+    #[cfg_attr(never, attr)]
+    b: u32,
+}
+"#,
+        expect![[r##"
+#[derive(proc_macros::DeriveIdentity)]
+struct S {
+    #[cfg(never)]
+    a: u32,
+    #[cfg_attr(never, attr)]
+    b: u32,
+}
+
+struct S {
+    b: u32,
+}"##]],
+    );
+}
+
 #[test]
 fn attribute_macro_syntax_completion_1() {
     // this is just the case where the input is actually valid
@@ -93,6 +122,26 @@ fn foo() {
     );
 }
 
+#[test]
+fn attribute_macro_panic_keeps_item() {
+    // a failing attribute macro shouldn't make the annotated item disappear
+    check(
+        r#"
+//- proc_macros: panic_attr
+#[proc_macros::panic_attr]
+fn foo() { bar.baz(); blub }
+"#,
+        expect![[r##"
+#[proc_macros::panic_attr]
+fn foo() { bar.baz(); blub }
+
+fn foo() {
+    bar.baz();
+    blub
+}"##]],
+    );
+}
+
 #[test]
 fn float_parsing_panic() {
     // Regression test for https://github.com/rust-lang/rust-analyzer/issues/12211
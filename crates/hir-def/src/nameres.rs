@@ -103,11 +103,15 @@ pub struct DefMap {
     /// Side table for resolving derive helpers.
     exported_derives: FxHashMap<MacroDefId, Box<[Name]>>,
     fn_proc_macro_mapping: FxHashMap<FunctionId, ProcMacroId>,
+    /// Side table for resolving paths in expansions of `macro`s at their definition site.
+    macro2_def_sites: FxHashMap<MacroDefId, LocalModuleId>,
 
     /// Custom attributes registered with `#![register_attr]`.
     registered_attrs: Vec<SmolStr>,
     /// Custom tool modules registered with `#![register_tool]`.
     registered_tools: Vec<SmolStr>,
+    /// Unstable features enabled with `#![feature]`.
+    unstable_features: Vec<SmolStr>,
 
     edition: Edition,
     recursion_limit: Option<u32>,
@@ -248,6 +252,10 @@ impl DefMap {
             ModuleOrigin::BlockExpr { block: block.ast_id },
         );
         def_map.block = Some(block_info);
+        // Tools and attributes registered at the crate root are in scope in blocks too.
+        def_map.registered_attrs = parent_map.registered_attrs.clone();
+        def_map.registered_tools = parent_map.registered_tools.clone();
+        def_map.unstable_features = parent_map.unstable_features.clone();
 
         let def_map = collector::collect_defs(db, def_map, tree_id);
         Some(Arc::new(def_map))
@@ -273,11 +281,13 @@ impl DefMap {
             extern_prelude: FxHashMap::default(),
             exported_derives: FxHashMap::default(),
             fn_proc_macro_mapping: FxHashMap::default(),
+            macro2_def_sites: FxHashMap::default(),
             prelude: None,
             root,
             modules,
             registered_attrs: Vec::new(),
             registered_tools: Vec::new(),
+            unstable_features: Vec::new(),
             diagnostics: Vec::new(),
         }
     }
@@ -298,6 +308,12 @@ impl DefMap {
     pub fn registered_attrs(&self) -> &[SmolStr] {
         &self.registered_attrs
     }
+    pub fn unstable_features(&self) -> &[SmolStr] {
+        &self.unstable_features
+    }
+    pub fn is_unstable_feature_enabled(&self, feature: &str) -> bool {
+        self.unstable_features.iter().any(|it| it == feature)
+    }
     pub fn root(&self) -> LocalModuleId {
         self.root
     }
@@ -459,7 +475,9 @@ impl DefMap {
             modules,
             registered_attrs,
             registered_tools,
+            unstable_features,
             fn_proc_macro_mapping,
+            macro2_def_sites,
             block: _,
             edition: _,
             recursion_limit: _,
@@ -474,7 +492,9 @@ impl DefMap {
         modules.shrink_to_fit();
         registered_attrs.shrink_to_fit();
         registered_tools.shrink_to_fit();
+        unstable_features.shrink_to_fit();
         fn_proc_macro_mapping.shrink_to_fit();
+        macro2_def_sites.shrink_to_fit();
         for (_, module) in modules.iter_mut() {
             module.children.shrink_to_fit();
             module.scope.shrink_to_fit();
@@ -6,7 +6,7 @@
 use std::{iter, mem};
 
 use base_db::{CrateId, Edition, FileId};
-use cfg::{CfgExpr, CfgOptions};
+use cfg::{CfgExpr, CfgOptions, CfgPath};
 use either::Either;
 use hir_expand::{
     ast_id_map::FileAstId,
@@ -264,7 +264,7 @@ impl DefCollector<'_> {
         let module_id = self.def_map.root;
 
         let attrs = item_tree.top_level_attrs(self.db, self.def_map.krate);
-        if attrs.cfg().map_or(true, |cfg| self.cfg_options.check(&cfg) != Some(false)) {
+        if attrs.cfg().map_or(true, |cfg| self.is_cfg_enabled(&cfg)) {
             self.inject_prelude(&attrs);
 
             // Process other crate-level attributes.
@@ -290,23 +290,42 @@ impl DefCollector<'_> {
                     continue;
                 }
 
+                if *attr_name == hir_expand::name![feature] {
+                    let features = attr
+                        .token_tree_value()
+                        .into_iter()
+                        .flat_map(|subtree| subtree.token_trees.iter())
+                        .filter_map(|tt| match tt {
+                            tt::TokenTree::Leaf(tt::Leaf::Ident(ident)) => Some(ident.text.clone()),
+                            _ => None,
+                        });
+                    self.def_map.unstable_features.extend(features);
+                    continue;
+                }
+
                 let attr_is_register_like = *attr_name == hir_expand::name![register_attr]
                     || *attr_name == hir_expand::name![register_tool];
                 if !attr_is_register_like {
                     continue;
                 }
 
-                let registered_name = match attr.single_ident_value() {
-                    Some(ident) => ident.as_name(),
-                    _ => continue,
+                // `#![register_tool(a, b)]` registers several names at once.
+                let registered_names = match attr.token_tree_value() {
+                    Some(subtree) => subtree.token_trees.iter().filter_map(|tt| match tt {
+                        tt::TokenTree::Leaf(tt::Leaf::Ident(ident)) => Some(ident.as_name()),
+                        _ => None,
+                    }),
+                    None => continue,
                 };
 
-                if *attr_name == hir_expand::name![register_attr] {
-                    self.def_map.registered_attrs.push(registered_name.to_smol_str());
-                    cov_mark::hit!(register_attr);
-                } else {
-                    self.def_map.registered_tools.push(registered_name.to_smol_str());
-                    cov_mark::hit!(register_tool);
+                for registered_name in registered_names {
+                    if *attr_name == hir_expand::name![register_attr] {
+                        self.def_map.registered_attrs.push(registered_name.to_smol_str());
+                        cov_mark::hit!(register_attr);
+                    } else {
+                        self.def_map.registered_tools.push(registered_name.to_smol_str());
+                        cov_mark::hit!(register_tool);
+                    }
                 }
             }
 
@@ -322,6 +341,37 @@ impl DefCollector<'_> {
         }
     }
 
+    fn is_cfg_enabled(&self, cfg: &CfgExpr) -> bool {
+        self.cfg_options.check_with_accessible(cfg, &|path| self.is_cfg_path_accessible(path))
+            != Some(false)
+    }
+
+    /// Approximates `cfg(accessible(path))` by resolving `path` in the crate it starts with.
+    ///
+    /// Paths into the crate that is being collected are undecidable, as its items are not all
+    /// known yet.
+    fn is_cfg_path_accessible(&self, path: &CfgPath) -> Option<bool> {
+        let as_name = |text: &SmolStr| {
+            tt::Ident { text: text.clone(), id: tt::TokenId::unspecified() }.as_name()
+        };
+
+        let (krate, rest) = path.segments().split_first()?;
+        let krate = self.def_map.resolve_name_in_extern_prelude(self.db, &as_name(krate))?;
+        if krate.krate == self.def_map.krate {
+            return None;
+        }
+        if rest.is_empty() {
+            return Some(true);
+        }
+
+        let def_map = krate.def_map(self.db);
+        let path = ModPath::from_segments(PathKind::Plain, rest.iter().map(as_name));
+        let (res, remaining) =
+            def_map.resolve_path(self.db, krate.local_id, &path, BuiltinShadowMode::Module);
+        let res = res.filter_visibility(|vis| vis == Visibility::Public);
+        Some(remaining.is_none() && !res.is_none())
+    }
+
     fn seed_with_inner(&mut self, tree_id: TreeId) {
         let item_tree = tree_id.item_tree(self.db);
         let module_id = self.def_map.root;
@@ -329,7 +379,7 @@ impl DefCollector<'_> {
         let is_cfg_enabled = item_tree
             .top_level_attrs(self.db, self.def_map.krate)
             .cfg()
-            .map_or(true, |cfg| self.cfg_options.check(&cfg) != Some(false));
+            .map_or(true, |cfg| self.is_cfg_enabled(&cfg));
         if is_cfg_enabled {
             ModCollector {
                 def_collector: self,
@@ -1972,6 +2022,12 @@ impl ModCollector<'_, '_> {
         let macro_id =
             Macro2Loc { container: module, id: ItemTreeId::new(self.tree_id, id), expander }
                 .intern(self.def_collector.db);
+        if let MacroExpander::Declarative = expander {
+            self.def_collector
+                .def_map
+                .macro2_def_sites
+                .insert(macro_id_to_def_id(self.def_collector.db, macro_id.into()), self.module_id);
+        }
         self.def_collector.define_macro_def(
             self.module_id,
             mac.name.clone(),
@@ -2058,7 +2114,7 @@ impl ModCollector<'_, '_> {
     }
 
     fn is_cfg_enabled(&self, cfg: &CfgExpr) -> bool {
-        self.def_collector.cfg_options.check(cfg) != Some(false)
+        self.def_collector.is_cfg_enabled(cfg)
     }
 
     fn emit_unconfigured_diagnostic(&mut self, item: ModItem, cfg: &CfgExpr) {
@@ -11,7 +11,7 @@
 //! `ReachedFixedPoint` signals about this.
 
 use base_db::Edition;
-use hir_expand::name::Name;
+use hir_expand::{name::Name, MacroDefId};
 
 use crate::{
     db::DefDatabase,
@@ -156,6 +156,29 @@ impl DefMap {
         }
     }
 
+    /// Returns the module the `macro` (2.0) `def` was defined in.
+    fn macro2_def_site(&self, db: &dyn DefDatabase, def: MacroDefId) -> Option<ModuleId> {
+        if def.krate != self.krate {
+            let def_map = db.crate_def_map(def.krate);
+            return def_map.macro2_def_sites.get(&def).map(|&it| def_map.module_id(it));
+        }
+
+        let mut arc;
+        let mut current_map = self;
+        loop {
+            if let Some(&local_id) = current_map.macro2_def_sites.get(&def) {
+                return Some(current_map.module_id(local_id));
+            }
+            match &current_map.block {
+                Some(block) => {
+                    arc = block.parent.def_map(db);
+                    current_map = &*arc;
+                }
+                None => return None,
+            }
+        }
+    }
+
     pub(super) fn resolve_path_fp_with_macro_single(
         &self,
         db: &dyn DefDatabase,
@@ -184,6 +207,28 @@ impl DefMap {
                 }
             }
             PathKind::Crate => PerNs::types(self.crate_root(db).into(), Visibility::Public),
+            PathKind::DefSite(call_id) => {
+                // Resolve the path like a plain one, but in the module the `macro` was defined in.
+                let path = ModPath::from_segments(PathKind::Plain, path.segments().iter().cloned());
+                let def = db.lookup_intern_macro_call(call_id).def;
+                return match self.macro2_def_site(db, def) {
+                    Some(module)
+                        if module.krate == self.krate && module.block == self.block_id() =>
+                    {
+                        self.resolve_path_fp_with_macro(db, mode, module.local_id, &path, shadow)
+                    }
+                    Some(module) => module.def_map(db).resolve_path_fp_with_macro(
+                        db,
+                        mode,
+                        module.local_id,
+                        &path,
+                        shadow,
+                    ),
+                    None => {
+                        self.resolve_path_fp_with_macro(db, mode, original_module, &path, shadow)
+                    }
+                };
+            }
             // plain import or absolute path in 2015: crate-relative with
             // fallback to extern prelude (with the simplification in
             // rust-lang/rust#57745)
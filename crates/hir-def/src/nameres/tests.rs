@@ -626,6 +626,37 @@ pub mod prelude {
     );
 }
 
+#[test]
+fn cfg_version_and_accessible() {
+    check(
+        r#"
+//- /main.rs crate:main deps:lib
+#[cfg(version("1.60"))]
+struct Old;
+#[cfg(version("1.99"))]
+struct New;
+#[cfg(accessible(lib::f))]
+struct Public;
+#[cfg(accessible(lib::g))]
+struct Private;
+#[cfg(accessible(lib::missing))]
+struct Missing;
+#[cfg(accessible(crate::Old))]
+struct Local;
+
+//- /lib.rs crate:lib
+pub fn f() {}
+fn g() {}
+"#,
+        expect![[r#"
+            crate
+            Local: t v
+            Old: t v
+            Public: t v
+        "#]],
+    );
+}
+
 #[test]
 fn infer_multiple_namespace() {
     check(
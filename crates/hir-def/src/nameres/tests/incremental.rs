@@ -166,6 +166,45 @@ m!(Z);
     }
 }
 
+#[test]
+fn typing_inside_a_function_should_not_invalidate_block_def_map() {
+    let (mut db, pos) = TestDB::with_position(
+        r#"
+//- /lib.rs
+fn outer() {
+    fn inner() {}
+
+    1 + 1$0;
+}
+"#,
+    );
+    let block = db.module_at_position(pos).block.unwrap();
+    {
+        let events = db.log_executed(|| {
+            db.block_def_map(block);
+        });
+        assert!(format!("{:?}", events).contains("block_def_map"), "{:#?}", events)
+    }
+
+    let new_text = r#"
+fn outer() {
+    fn inner() {}
+
+    92;
+}
+"#;
+    db.set_file_text(pos.file_id, Arc::new(new_text.to_string()));
+
+    {
+        let events = db.log_executed(|| {
+            let def_map = db.block_def_map(block).unwrap();
+            assert_eq!(def_map[def_map.root()].scope.resolutions().count(), 1);
+        });
+        assert!(format!("{:?}", events).contains("block_item_tree"), "{:#?}", events);
+        assert!(!format!("{:?}", events).contains("block_def_map"), "{:#?}", events)
+    }
+}
+
 #[test]
 fn item_tree_prevents_reparsing() {
     // The `ItemTree` is used by both name resolution and the various queries in `adt.rs` and
@@ -181,7 +181,8 @@ impl Resolver {
         path: &ModPath,
     ) -> Option<(TypeNs, Option<usize>)> {
         let first_name = path.segments().first()?;
-        let skip_to_mod = path.kind != PathKind::Plain;
+        // Paths from the body of a `macro` may still refer to locals and generics introduced by it.
+        let skip_to_mod = !path.kind.is_plain();
         for scope in self.scopes() {
             match scope {
                 Scope::ExprScope(_) => continue,
@@ -249,7 +250,7 @@ impl Resolver {
         let n_segments = path.segments().len();
         let tmp = name![self];
         let first_name = if path.is_self() { &tmp } else { path.segments().first()? };
-        let skip_to_mod = path.kind != PathKind::Plain && !path.is_self();
+        let skip_to_mod = !path.kind.is_plain() && !path.is_self();
         for scope in self.scopes() {
             match scope {
                 Scope::AdtScope(_)
@@ -262,11 +263,10 @@ impl Resolver {
                 }
 
                 Scope::ExprScope(scope) if n_segments <= 1 => {
-                    let entry = scope
-                        .expr_scopes
-                        .entries(scope.scope_id)
-                        .iter()
-                        .find(|entry| entry.name() == first_name);
+                    let entry = scope.expr_scopes.entries(scope.scope_id).iter().find(|entry| {
+                        entry.name() == first_name
+                            && is_local_visible(db, scope.owner, entry.pat(), path.kind)
+                    });
 
                     if let Some(e) = entry {
                         return Some(ResolveValueResult::ValueNs(ValueNs::LocalBinding(e.pat())));
@@ -320,7 +320,7 @@ impl Resolver {
         // If a path of the shape `u16::from_le_bytes` failed to resolve at all, then we fall back
         // to resolving to the primitive type, to allow this to still work in the presence of
         // `use core::u16;`.
-        if path.kind == PathKind::Plain && path.segments().len() > 1 {
+        if path.kind.is_plain() && path.segments().len() > 1 {
             match BuiltinType::by_name(&path.segments()[0]) {
                 Some(builtin) => {
                     return Some(ResolveValueResult::Partial(TypeNs::BuiltinType(builtin), 1));
@@ -671,6 +671,22 @@ impl ModuleItemMap {
     }
 }
 
+/// Whether the local binding `pat` of `owner` can be referred to by a path of the given kind.
+/// Paths resolved at the definition site of a `macro` only see the locals the macro declares.
+fn is_local_visible(
+    db: &dyn DefDatabase,
+    owner: DefWithBodyId,
+    pat: PatId,
+    kind: PathKind,
+) -> bool {
+    let call_id = match kind {
+        PathKind::DefSite(it) => it,
+        _ => return true,
+    };
+    let (_, source_map) = db.body_with_source_map(owner);
+    source_map.pat_syntax(pat).map_or(false, |src| src.file_id == call_id.as_file())
+}
+
 fn to_value_ns(per_ns: PerNs) -> Option<ValueNs> {
     let res = match per_ns.take_values()? {
         ModuleDefId::FunctionId(it) => ValueNs::FunctionId(it),
@@ -8,7 +8,11 @@ use hir_expand::{
 use std::{convert::TryInto, fmt::Write};
 use syntax::ast::{self, HasName};
 
-use crate::{body::LowerCtx, intern::Interned, path::Path};
+use crate::{
+    body::LowerCtx,
+    intern::Interned,
+    path::{GenericArgs, Path},
+};
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Mutability {
@@ -176,8 +180,8 @@ impl TypeRef {
             ast::Type::ArrayType(inner) => {
                 // FIXME: This is a hack. We should probably reuse the machinery of
                 // `hir_def::body::lower` to lower this into an `Expr` and then evaluate it at the
-                // `hir_ty` level, which would allow knowing the type of:
-                // let v: [u8; 2 + 2] = [0u8; 4];
+                // `hir_ty` level. For now only literals, const params and simple arithmetic on
+                // them (e.g. `[u8; N * 2]`) are understood.
                 let len = ConstScalarOrPath::from_expr_opt(inner.expr());
 
                 TypeRef::Array(Box::new(TypeRef::from_ast_opt(ctx, inner.ty())), len)
@@ -286,27 +290,33 @@ impl TypeRef {
             }
             for segment in path.segments().iter() {
                 if let Some(args_and_bindings) = segment.args_and_bindings {
-                    for arg in &args_and_bindings.args {
-                        match arg {
-                            crate::path::GenericArg::Type(type_ref) => {
-                                go(type_ref, f);
-                            }
-                            crate::path::GenericArg::Const(_)
-                            | crate::path::GenericArg::Lifetime(_) => {}
-                        }
+                    go_generic_args(args_and_bindings, f);
+                }
+            }
+        }
+
+        fn go_generic_args(args_and_bindings: &GenericArgs, f: &mut impl FnMut(&TypeRef)) {
+            for arg in &args_and_bindings.args {
+                match arg {
+                    crate::path::GenericArg::Type(type_ref) => {
+                        go(type_ref, f);
                     }
-                    for binding in &args_and_bindings.bindings {
-                        if let Some(type_ref) = &binding.type_ref {
-                            go(type_ref, f);
-                        }
-                        for bound in &binding.bounds {
-                            match bound.as_ref() {
-                                TypeBound::Path(path, _) | TypeBound::ForLifetime(_, path) => {
-                                    go_path(path, f)
-                                }
-                                TypeBound::Lifetime(_) | TypeBound::Error => (),
-                            }
+                    crate::path::GenericArg::Const(_) | crate::path::GenericArg::Lifetime(_) => {}
+                }
+            }
+            for binding in &args_and_bindings.bindings {
+                if let Some(args) = &binding.args {
+                    go_generic_args(args, f);
+                }
+                if let Some(type_ref) = &binding.type_ref {
+                    go(type_ref, f);
+                }
+                for bound in &binding.bounds {
+                    match bound.as_ref() {
+                        TypeBound::Path(path, _) | TypeBound::ForLifetime(_, path) => {
+                            go_path(path, f)
                         }
+                        TypeBound::Lifetime(_) | TypeBound::Error => (),
                     }
                 }
             }
@@ -375,6 +385,9 @@ impl TypeBound {
 pub enum ConstScalarOrPath {
     Scalar(ConstScalar),
     Path(Name),
+    /// An arithmetic expression over other const arguments, e.g. `N * 2`. `hir_ty` evaluates
+    /// these once the operands are known, and keeps them as `ConstScalar::Unevaluated` otherwise.
+    BinOp(Box<ConstScalarOrPath>, ast::ArithOp, Box<ConstScalarOrPath>),
 }
 
 impl std::fmt::Display for ConstScalarOrPath {
@@ -382,6 +395,16 @@ impl std::fmt::Display for ConstScalarOrPath {
         match self {
             ConstScalarOrPath::Scalar(s) => s.fmt(f),
             ConstScalarOrPath::Path(n) => n.fmt(f),
+            ConstScalarOrPath::BinOp(lhs, op, rhs) => {
+                let fmt_operand = |f: &mut std::fmt::Formatter<'_>, it: &ConstScalarOrPath| match it
+                {
+                    ConstScalarOrPath::BinOp(..) => write!(f, "({})", it),
+                    _ => it.fmt(f),
+                };
+                fmt_operand(f, lhs)?;
+                write!(f, " {} ", op)?;
+                fmt_operand(f, rhs)
+            }
         }
     }
 }
@@ -396,7 +419,7 @@ impl ConstScalarOrPath {
 
     // FIXME: as per the comments on `TypeRef::Array`, this evaluation should not happen at this
     // parse stage.
-    fn from_expr(expr: ast::Expr) -> Self {
+    pub(crate) fn from_expr(expr: ast::Expr) -> Self {
         match expr {
             ast::Expr::PathExpr(p) => {
                 match p.path().and_then(|x| x.segment()).and_then(|x| x.name_ref()) {
@@ -420,18 +443,37 @@ impl ConstScalarOrPath {
                     _ => Self::Scalar(ConstScalar::Unknown),
                 }
             }
+            ast::Expr::ParenExpr(e) => Self::from_expr_opt(e.expr()),
+            ast::Expr::BlockExpr(block) if block.modifier().is_none() => match block.stmt_list() {
+                Some(stmts) if stmts.statements().next().is_none() => {
+                    Self::from_expr_opt(stmts.tail_expr())
+                }
+                _ => Self::Scalar(ConstScalar::Unknown),
+            },
+            ast::Expr::BinExpr(e) => match e.op_kind() {
+                Some(ast::BinaryOp::ArithOp(op)) => Self::BinOp(
+                    Box::new(Self::from_expr_opt(e.lhs())),
+                    op,
+                    Box::new(Self::from_expr_opt(e.rhs())),
+                ),
+                _ => Self::Scalar(ConstScalar::Unknown),
+            },
             _ => Self::Scalar(ConstScalar::Unknown),
         }
     }
 }
 
 /// A concrete constant value
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ConstScalar {
     // for now, we only support the trivial case of constant evaluating the length of an array
     // Note that this is u64 because the target usize may be bigger than our usize
     Usize(u64),
 
+    /// A `generic_const_exprs` expression over const parameters, e.g. `N * 2`, which can only be
+    /// evaluated once the parameters are substituted.
+    Unevaluated(Box<ConstScalarOrPath>),
+
     /// Case of an unknown value that rustc might know but we don't
     // FIXME: this is a hack to get around chalk not being able to represent unevaluatable
     // constants
@@ -444,6 +486,7 @@ impl std::fmt::Display for ConstScalar {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         match self {
             ConstScalar::Usize(us) => us.fmt(f),
+            ConstScalar::Unevaluated(expr) => expr.fmt(f),
             ConstScalar::Unknown => f.write_char('_'),
         }
     }
@@ -102,6 +102,7 @@ register_builtin! {
     (unreachable, Unreachable) => unreachable_expand,
     (log_syntax, LogSyntax) => log_syntax_expand,
     (trace_macros, TraceMacros) => trace_macros_expand,
+    (type_ascribe, TypeAscribe) => type_ascribe_expand,
 
     EAGER:
     (compile_error, CompileError) => compile_error_expand,
@@ -157,6 +158,31 @@ fn trace_macros_expand(
     ExpandResult::ok(quote! {})
 }
 
+fn type_ascribe_expand(
+    _db: &dyn AstDatabase,
+    _id: MacroCallId,
+    tt: &tt::Subtree,
+) -> ExpandResult<tt::Subtree> {
+    // We have no type ascription expressions, so `type_ascribe!(expr, ty)` is expanded to
+    // `{ let it: ty = expr; it }` instead, which checks the same thing.
+    let expr = match parse_exprs_with_sep(tt, ',').into_iter().next() {
+        Some(it) => it,
+        None => return ExpandResult::only_err(mbe::ExpandError::NoMatchingRule.into()),
+    };
+    let expr_len = if expr.delimiter.is_some() { 1 } else { expr.token_trees.len() };
+    let ty = match tt.token_trees.get(expr_len) {
+        Some(tt::TokenTree::Leaf(tt::Leaf::Punct(tt::Punct { char: ',', .. }))) => {
+            tt::Subtree { delimiter: None, token_trees: tt.token_trees[expr_len + 1..].to_vec() }
+        }
+        _ => return ExpandResult::only_err(mbe::ExpandError::NoMatchingRule.into()),
+    };
+
+    ExpandResult::ok(quote! {{
+        let it: #ty = #expr;
+        it
+    }})
+}
+
 fn stringify_expand(
     _db: &dyn AstDatabase,
     _id: MacroCallId,
@@ -439,25 +465,36 @@ fn concat_bytes_expand(
 ) -> ExpandResult<ExpandedEager> {
     let mut bytes = Vec::new();
     let mut err = None;
-    for (i, t) in tt.token_trees.iter().enumerate() {
+    for (i, mut t) in tt.token_trees.iter().enumerate() {
+        // FIXME: same hack as in `concat_expand`, skip the parentheses around `$e:expr` captures.
+        if let tt::TokenTree::Subtree(tt::Subtree { delimiter: Some(delim), token_trees }) = t {
+            if let [tt] = &**token_trees {
+                if delim.kind == tt::DelimiterKind::Parenthesis {
+                    t = tt;
+                }
+            }
+        }
+
         match t {
-            tt::TokenTree::Leaf(tt::Leaf::Literal(lit)) => {
+            tt::TokenTree::Leaf(tt::Leaf::Literal(lit)) if i % 2 == 0 => {
                 let token = ast::make::tokens::literal(&lit.to_string());
-                match token.kind() {
-                    syntax::SyntaxKind::BYTE => bytes.push(token.text().to_string()),
-                    syntax::SyntaxKind::BYTE_STRING => {
-                        let components = unquote_byte_string(lit).unwrap_or_else(Vec::new);
-                        components.into_iter().for_each(|x| bytes.push(x.to_string()));
+                let res = match token.kind() {
+                    syntax::SyntaxKind::BYTE => {
+                        ast::Byte::cast(token).and_then(|it| it.value()).map(|it| bytes.push(it))
                     }
-                    _ => {
-                        err.get_or_insert(mbe::ExpandError::UnexpectedToken.into());
-                        break;
+                    syntax::SyntaxKind::BYTE_STRING => {
+                        unquote_byte_string(lit).map(|it| bytes.extend(it))
                     }
+                    _ => None,
+                };
+                if res.is_none() {
+                    err.get_or_insert(mbe::ExpandError::UnexpectedToken.into());
+                    break;
                 }
             }
             tt::TokenTree::Leaf(tt::Leaf::Punct(punct)) if i % 2 == 1 && punct.char == ',' => (),
             tt::TokenTree::Subtree(tree)
-                if tree.delimiter_kind() == Some(tt::DelimiterKind::Bracket) =>
+                if i % 2 == 0 && tree.delimiter_kind() == Some(tt::DelimiterKind::Bracket) =>
             {
                 if let Err(e) = concat_bytes_expand_subtree(tree, &mut bytes) {
                     err.get_or_insert(e);
@@ -470,25 +507,29 @@ fn concat_bytes_expand(
             }
         }
     }
-    let ident = tt::Ident { text: bytes.join(", ").into(), id: tt::TokenId::unspecified() };
-    ExpandResult { value: ExpandedEager::new(quote!([#ident])), err }
+    let text = bytes.into_iter().flat_map(std::ascii::escape_default).map(char::from);
+    let lit = tt::Literal {
+        text: format!("b\"{}\"", text.collect::<String>()).into(),
+        id: tt::TokenId::unspecified(),
+    };
+    ExpandResult { value: ExpandedEager::new(quote!(#lit)), err }
 }
 
-fn concat_bytes_expand_subtree(
-    tree: &tt::Subtree,
-    bytes: &mut Vec<String>,
-) -> Result<(), ExpandError> {
+fn concat_bytes_expand_subtree(tree: &tt::Subtree, bytes: &mut Vec<u8>) -> Result<(), ExpandError> {
     for (ti, tt) in tree.token_trees.iter().enumerate() {
         match tt {
-            tt::TokenTree::Leaf(tt::Leaf::Literal(lit)) => {
+            tt::TokenTree::Leaf(tt::Leaf::Literal(lit)) if ti % 2 == 0 => {
                 let lit = ast::make::tokens::literal(&lit.to_string());
-                match lit.kind() {
-                    syntax::SyntaxKind::BYTE | syntax::SyntaxKind::INT_NUMBER => {
-                        bytes.push(lit.text().to_string())
-                    }
-                    _ => {
-                        return Err(mbe::ExpandError::UnexpectedToken.into());
-                    }
+                let byte = match lit.kind() {
+                    syntax::SyntaxKind::BYTE => ast::Byte::cast(lit).and_then(|it| it.value()),
+                    syntax::SyntaxKind::INT_NUMBER => ast::IntNumber::cast(lit)
+                        .and_then(|it| it.value())
+                        .and_then(|it| u8::try_from(it).ok()),
+                    _ => None,
+                };
+                match byte {
+                    Some(byte) => bytes.push(byte),
+                    None => return Err(mbe::ExpandError::UnexpectedToken.into()),
                 }
             }
             tt::TokenTree::Leaf(tt::Leaf::Punct(punct)) if ti % 2 == 1 && punct.char == ',' => (),
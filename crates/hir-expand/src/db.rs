@@ -2,14 +2,15 @@
 
 use std::sync::Arc;
 
-use base_db::{salsa, SourceDatabase};
+use base_db::{salsa, CrateId, SourceDatabase};
+use cfg::CfgExpr;
 use either::Either;
 use limit::Limit;
 use mbe::syntax_node_to_token_tree;
 use rustc_hash::FxHashSet;
 use syntax::{
     ast::{self, HasAttrs, HasDocComments},
-    AstNode, GreenNode, Parse, SyntaxNode, SyntaxToken, T,
+    AstNode, Direction, GreenNode, Parse, SyntaxElement, SyntaxNode, SyntaxToken, WalkEvent, T,
 };
 
 use crate::{
@@ -148,9 +149,9 @@ pub fn expand_speculative(
     let token_range = token_to_map.text_range();
 
     // Build the subtree and token mapping for the speculative args
-    let censor = censor_for_macro_input(&loc, speculative_args);
+    let censor = censor_for_macro_input(db, &loc, speculative_args);
     let mut fixups = fixup::fixup_syntax(speculative_args);
-    fixups.replace.extend(censor.into_iter().map(|node| (node, Vec::new())));
+    fixups.replace.extend(censor.into_iter().map(|it| (it, Vec::new())));
     let (mut tt, spec_args_tmap, _) = mbe::syntax_node_to_token_tree_with_modifications(
         speculative_args,
         fixups.token_map,
@@ -293,9 +294,9 @@ fn macro_arg(
     let loc = db.lookup_intern_macro_call(id);
 
     let node = SyntaxNode::new_root(arg);
-    let censor = censor_for_macro_input(&loc, &node);
+    let censor = censor_for_macro_input(db, &loc, &node);
     let mut fixups = fixup::fixup_syntax(&node);
-    fixups.replace.extend(censor.into_iter().map(|node| (node, Vec::new())));
+    fixups.replace.extend(censor.into_iter().map(|it| (it, Vec::new())));
     let (mut tt, tmap, _) = mbe::syntax_node_to_token_tree_with_modifications(
         &node,
         fixups.token_map,
@@ -312,30 +313,41 @@ fn macro_arg(
     Some(Arc::new((tt, tmap, fixups.undo_info)))
 }
 
-fn censor_for_macro_input(loc: &MacroCallLoc, node: &SyntaxNode) -> FxHashSet<SyntaxNode> {
+fn censor_for_macro_input(
+    db: &dyn AstDatabase,
+    loc: &MacroCallLoc,
+    node: &SyntaxNode,
+) -> FxHashSet<SyntaxElement> {
     (|| {
         let censor = match loc.kind {
             MacroCallKind::FnLike { .. } => return None,
             MacroCallKind::Derive { derive_attr_index, .. } => {
                 cov_mark::hit!(derive_censoring);
-                ast::Item::cast(node.clone())?
+                let mut censor: FxHashSet<_> = ast::Item::cast(node.clone())?
                     .attrs()
                     .take(derive_attr_index as usize + 1)
                     // FIXME
                     .filter(|attr| attr.simple_name().as_deref() == Some("derive"))
-                    .map(|it| it.syntax().clone())
-                    .collect()
+                    .map(|it| it.syntax().clone().into())
+                    .collect();
+                // Like in rustc, derives see their input with `#[cfg]`s already applied.
+                censor.extend(cfg_eval_censor(db, loc.krate, node));
+                censor
             }
             MacroCallKind::Attr { is_derive: true, .. } => return None,
             MacroCallKind::Attr { invoc_attr_index, .. } => {
                 cov_mark::hit!(attribute_macro_attr_censoring);
-                ast::Item::cast(node.clone())?
+                let mut censor: FxHashSet<_> = ast::Item::cast(node.clone())?
                     .doc_comments_and_attrs()
                     .nth(invoc_attr_index as usize)
                     .and_then(Either::left)
-                    .map(|attr| attr.syntax().clone())
+                    .map(|attr| attr.syntax().clone().into())
                     .into_iter()
-                    .collect()
+                    .collect();
+                if let MacroDefKind::BuiltInAttr(BuiltinAttrExpander::CfgEval, _) = loc.def.kind {
+                    censor.extend(cfg_eval_censor(db, loc.krate, node));
+                }
+                censor
             }
         };
         Some(censor)
@@ -343,6 +355,66 @@ fn censor_for_macro_input(loc: &MacroCallLoc, node: &SyntaxNode) -> FxHashSet<Sy
     .unwrap_or_default()
 }
 
+/// Returns the nodes nested in `node` that are disabled by a `#[cfg]` attribute (together with
+/// their trailing comma, if any), along with the `#[cfg_attr]` attributes whose predicate is false.
+/// Removing these from the input of a macro is what `#[cfg_eval]` does.
+fn cfg_eval_censor(db: &dyn AstDatabase, krate: CrateId, node: &SyntaxNode) -> Vec<SyntaxElement> {
+    let crate_graph = db.crate_graph();
+    let cfg_options = &crate_graph[krate].cfg_options;
+    let is_disabled =
+        |cfg: Option<CfgExpr>| cfg.map_or(false, |cfg| cfg_options.check(&cfg) == Some(false));
+
+    let mut censor = Vec::new();
+    let mut preorder = node.preorder();
+    while let Some(event) = preorder.next() {
+        let it = match event {
+            WalkEvent::Enter(it) => it,
+            WalkEvent::Leave(_) => continue,
+        };
+        let attrs = match ast::AnyHasAttrs::cast(it.clone()) {
+            Some(it) => it.attrs(),
+            None => continue,
+        };
+        // The macro input itself is enabled, otherwise the macro would not have been called.
+        let is_enabled = &it == node
+            || !attrs.clone().any(|attr| {
+                attr.simple_name().as_deref() == Some("cfg") && is_disabled(cfg_of_attr(&attr))
+            });
+        if !is_enabled {
+            // Fields, variants and the like are comma separated, drop the separator too.
+            let comma = it
+                .siblings_with_tokens(Direction::Next)
+                .skip(1)
+                .find(|it| !it.kind().is_trivia())
+                .filter(|it| it.kind() == T![,]);
+            censor.extend(comma);
+            censor.push(it.into());
+            preorder.skip_subtree();
+            continue;
+        }
+        censor.extend(
+            attrs
+                .filter(|attr| {
+                    attr.simple_name().as_deref() == Some("cfg_attr")
+                        && is_disabled(cfg_of_attr(attr))
+                })
+                .map(|attr| attr.syntax().clone().into()),
+        );
+    }
+    censor
+}
+
+/// Parses the predicate of a `#[cfg(pred)]` or `#[cfg_attr(pred, ...)]` attribute.
+fn cfg_of_attr(attr: &ast::Attr) -> Option<CfgExpr> {
+    let (subtree, _) = syntax_node_to_token_tree(attr.token_tree()?.syntax());
+    // For `cfg_attr`, the predicate is everything up to the first comma.
+    let pred = subtree
+        .token_trees
+        .split(|tt| matches!(tt, tt::TokenTree::Leaf(tt::Leaf::Punct(tt::Punct { char: ',', .. }))))
+        .next()?;
+    Some(CfgExpr::parse(&tt::Subtree { delimiter: subtree.delimiter, token_trees: pred.to_vec() }))
+}
+
 fn macro_arg_text(db: &dyn AstDatabase, id: MacroCallId) -> Option<GreenNode> {
     let loc = db.lookup_intern_macro_call(id);
     let arg = loc.kind.arg(db)?;
@@ -460,6 +532,27 @@ fn macro_expand_error(db: &dyn AstDatabase, macro_call: MacroCallId) -> Option<E
 }
 
 fn expand_proc_macro(db: &dyn AstDatabase, id: MacroCallId) -> ExpandResult<tt::Subtree> {
+    run_proc_macro(db, id, false)
+}
+
+/// Expands the proc-macro call `id` again, bypassing the database, to get the full backtrace
+/// if it panics. Returns `None` if `id` doesn't call a proc-macro.
+pub fn expand_proc_macro_with_backtrace(
+    db: &dyn AstDatabase,
+    id: MacroCallId,
+) -> Option<ExpandResult<tt::Subtree>> {
+    let loc: MacroCallLoc = db.lookup_intern_macro_call(id);
+    match loc.def.kind {
+        MacroDefKind::ProcMacro(..) => Some(run_proc_macro(db, id, true)),
+        _ => None,
+    }
+}
+
+fn run_proc_macro(
+    db: &dyn AstDatabase,
+    id: MacroCallId,
+    backtrace: bool,
+) -> ExpandResult<tt::Subtree> {
     let loc: MacroCallLoc = db.lookup_intern_macro_call(id);
     let macro_arg = match db.macro_arg(id) {
         Some(it) => it,
@@ -482,7 +575,11 @@ fn expand_proc_macro(db: &dyn AstDatabase, id: MacroCallId) -> ExpandResult<tt::
         _ => None,
     };
 
-    expander.expand(db, loc.krate, &macro_arg.0, attr_arg.as_ref())
+    if backtrace {
+        expander.expand_with_backtrace(db, loc.krate, &macro_arg.0, attr_arg.as_ref())
+    } else {
+        expander.expand(db, loc.krate, &macro_arg.0, attr_arg.as_ref())
+    }
 }
 
 fn hygiene_frame(db: &dyn AstDatabase, file_id: HirFileId) -> Arc<HygieneFrame> {
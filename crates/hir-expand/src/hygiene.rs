@@ -1,7 +1,8 @@
 //! This modules handles hygiene information.
 //!
 //! Specifically, `ast` + `Hygiene` allows you to create a `Name`. Note that, at
-//! this moment, this is horribly incomplete and handles only `$crate`.
+//! this moment, this is horribly incomplete and handles only `$crate` and the
+//! def-site resolution of paths in `macro` (2.0) expansions.
 use std::sync::Arc;
 
 use base_db::CrateId;
@@ -16,8 +17,10 @@ use syntax::{
 use crate::{
     db::{self, AstDatabase},
     fixup,
+    mod_path::PathKind,
     name::{AsName, Name},
-    HirFileId, HirFileIdRepr, InFile, MacroCallKind, MacroCallLoc, MacroDefKind, MacroFile,
+    HirFileId, HirFileIdRepr, InFile, MacroCallId, MacroCallKind, MacroCallLoc, MacroDefKind,
+    MacroFile,
 };
 
 #[derive(Clone, Debug)]
@@ -69,6 +72,37 @@ impl Hygiene {
             token = mapped.value;
         }
     }
+
+    /// The kind of a plain path whose first segment is `first_segment`. Paths written in the body
+    /// of a `macro` (2.0) definition are resolved at the definition site of the macro.
+    pub fn plain_path_kind(&self, db: &dyn AstDatabase, first_segment: &ast::Path) -> PathKind {
+        match self.def_site(db, first_segment.syntax()) {
+            Some(call_id) => PathKind::DefSite(call_id),
+            None => PathKind::Plain,
+        }
+    }
+
+    /// If `node` was written in the body of a `macro` (2.0) definition, returns the call of that
+    /// macro.
+    fn def_site(&self, db: &dyn AstDatabase, node: &SyntaxNode) -> Option<MacroCallId> {
+        let mut token = node.first_token()?.text_range();
+        let frames = self.frames.as_ref()?;
+        let mut current = &frames.0;
+
+        loop {
+            let expansion = current.expansion.as_ref()?;
+            let (mapped, origin) = expansion.map_ident_up(db, token)?;
+            if origin == Origin::Def {
+                return if current.def_site_hygiene {
+                    Some(expansion.file.macro_call_id)
+                } else {
+                    None
+                };
+            }
+            current = current.call_site.as_ref()?;
+            token = mapped.value;
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -80,6 +114,8 @@ pub struct HygieneFrame {
 
     // Indicate this is a local inner macro
     local_inner: bool,
+    // Indicate this is a `macro` (2.0), whose identifiers have def-site hygiene
+    def_site_hygiene: bool,
     krate: Option<CrateId>,
 
     call_site: Option<Arc<HygieneFrame>>,
@@ -216,6 +252,7 @@ fn make_hygiene_info(
 
 impl HygieneFrame {
     pub(crate) fn new(db: &dyn AstDatabase, file_id: HirFileId) -> HygieneFrame {
+        let mut def_site_hygiene = false;
         let (info, krate, local_inner) = match file_id.0 {
             HirFileIdRepr::FileId(_) => (None, None, false),
             HirFileIdRepr::MacroFile(macro_file) => {
@@ -223,7 +260,8 @@ impl HygieneFrame {
                 let info =
                     make_hygiene_info(db, macro_file, &loc).map(|info| (loc.kind.file_id(), info));
                 match loc.def.kind {
-                    MacroDefKind::Declarative(_) => {
+                    MacroDefKind::Declarative(ast_id) => {
+                        def_site_hygiene = matches!(ast_id.to_node(db), ast::Macro::MacroDef(_));
                         (info, Some(loc.def.krate), loc.def.local_inner)
                     }
                     MacroDefKind::BuiltIn(..) => (info, Some(loc.def.krate), false),
@@ -240,6 +278,7 @@ impl HygieneFrame {
                 return HygieneFrame {
                     expansion: None,
                     local_inner,
+                    def_site_hygiene,
                     krate,
                     call_site: None,
                     def_site: None,
@@ -251,6 +290,13 @@ impl HygieneFrame {
         let def_site = info.attr_input_or_mac_def_start.map(|it| db.hygiene_frame(it.file_id));
         let call_site = Some(db.hygiene_frame(calling_file));
 
-        HygieneFrame { expansion: Some(info), local_inner, krate, call_site, def_site }
+        HygieneFrame {
+            expansion: Some(info),
+            local_inner,
+            def_site_hygiene,
+            krate,
+            call_site,
+            def_site,
+        }
     }
 }
@@ -9,6 +9,7 @@ use crate::{
     db::AstDatabase,
     hygiene::Hygiene,
     name::{known, Name},
+    MacroCallId,
 };
 use base_db::CrateId;
 use either::Either;
@@ -30,6 +31,16 @@ pub enum PathKind {
     Abs,
     /// `$crate` from macro expansion
     DollarCrate(CrateId),
+    /// A path written in the body of a `macro` (2.0) definition, which is resolved at the
+    /// definition site of the macro invoked by the given call
+    DefSite(MacroCallId),
+}
+
+impl PathKind {
+    /// Whether this is a plain path, possibly resolved at the definition site of a `macro`.
+    pub fn is_plain(&self) -> bool {
+        matches!(self, PathKind::Plain | PathKind::DefSite(_))
+    }
 }
 
 impl ModPath {
@@ -69,6 +80,7 @@ impl ModPath {
                 PathKind::Crate => 1,
                 PathKind::Abs => 0,
                 PathKind::DollarCrate(_) => 1,
+                PathKind::DefSite(_) => 0,
             }
     }
 
@@ -82,7 +94,7 @@ impl ModPath {
 
     /// If this path is a single identifier, like `foo`, return its name.
     pub fn as_ident(&self) -> Option<&Name> {
-        if self.kind != PathKind::Plain {
+        if !self.kind.is_plain() {
             return None;
         }
 
@@ -105,7 +117,7 @@ impl Display for ModPath {
             Ok(())
         };
         match self.kind {
-            PathKind::Plain => {}
+            PathKind::Plain | PathKind::DefSite(_) => {}
             PathKind::Super(0) => add_segment("self")?,
             PathKind::Super(n) => {
                 for _ in 0..n {
@@ -205,12 +217,16 @@ fn convert_path(
     // We follow what it did anyway :)
     if mod_path.segments.len() == 1 && mod_path.kind == PathKind::Plain {
         if let Some(_macro_call) = path.syntax().parent().and_then(ast::MacroCall::cast) {
-            if let Some(crate_id) = hygiene.local_inner_macros(db, path) {
+            if let Some(crate_id) = hygiene.local_inner_macros(db, path.clone()) {
                 mod_path.kind = PathKind::DollarCrate(crate_id);
             }
         }
     }
 
+    if path.qualifier().is_none() && mod_path.kind == PathKind::Plain {
+        mod_path.kind = hygiene.plain_path_kind(db, &path);
+    }
+
     Some(mod_path)
 }
 
@@ -183,6 +183,7 @@ pub mod known {
         cfg_attr,
         register_attr,
         register_tool,
+        feature,
         // Components of known path (value or mod name)
         std,
         core,
@@ -253,6 +254,7 @@ pub mod known {
         std_panic,
         stringify,
         trace_macros,
+        type_ascribe,
         unreachable,
         // Builtin derives
         Copy,
@@ -319,6 +321,8 @@ pub mod known {
         gt,
         le,
         lt,
+        branch,
+        poll,
         // lang items
         add_assign,
         add,
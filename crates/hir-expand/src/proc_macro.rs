@@ -30,6 +30,29 @@ impl ProcMacroExpander {
         calling_crate: CrateId,
         tt: &tt::Subtree,
         attr_arg: Option<&tt::Subtree>,
+    ) -> ExpandResult<tt::Subtree> {
+        self.run(db, calling_crate, tt, attr_arg, false)
+    }
+
+    /// Expands the macro again without caching, with the full backtrace in the error of a
+    /// panic.
+    pub fn expand_with_backtrace(
+        self,
+        db: &dyn AstDatabase,
+        calling_crate: CrateId,
+        tt: &tt::Subtree,
+        attr_arg: Option<&tt::Subtree>,
+    ) -> ExpandResult<tt::Subtree> {
+        self.run(db, calling_crate, tt, attr_arg, true)
+    }
+
+    fn run(
+        self,
+        db: &dyn AstDatabase,
+        calling_crate: CrateId,
+        tt: &tt::Subtree,
+        attr_arg: Option<&tt::Subtree>,
+        backtrace: bool,
     ) -> ExpandResult<tt::Subtree> {
         match self.proc_macro_id {
             Some(id) => {
@@ -45,23 +68,29 @@ impl ProcMacroExpander {
 
                 // Proc macros have access to the environment variables of the invoking crate.
                 let env = &krate_graph[calling_crate].env;
-                match proc_macro.expander.expand(tt, attr_arg, env) {
+                let res = if backtrace {
+                    proc_macro.expander.expand_with_backtrace(tt, attr_arg, env)
+                } else {
+                    proc_macro.expander.expand(tt, attr_arg, env)
+                };
+                match res {
                     Ok(t) => ExpandResult::ok(t),
-                    Err(err) => match err {
-                        // Don't discard the item in case something unexpected happened while expanding attributes
-                        ProcMacroExpansionError::System(text)
-                            if proc_macro.kind == ProcMacroKind::Attr =>
-                        {
-                            ExpandResult {
-                                value: tt.clone(),
-                                err: Some(ExpandError::Other(text.into())),
+                    Err(err) => {
+                        let err = match err {
+                            ProcMacroExpansionError::System(text)
+                            | ProcMacroExpansionError::Panic(text) => {
+                                ExpandError::Other(text.into())
                             }
+                        };
+                        match proc_macro.kind {
+                            // Don't discard the annotated item when an attribute macro fails or
+                            // panics, fall back to the item as written instead.
+                            ProcMacroKind::Attr => {
+                                ExpandResult { value: tt.clone(), err: Some(err) }
+                            }
+                            _ => ExpandResult::only_err(err),
                         }
-                        ProcMacroExpansionError::System(text)
-                        | ProcMacroExpansionError::Panic(text) => {
-                            ExpandResult::only_err(ExpandError::Other(text.into()))
-                        }
-                    },
+                    }
                 }
             }
             None => ExpandResult::only_err(ExpandError::UnresolvedProcMacro),
@@ -100,6 +100,7 @@ macro_rules! __quote {
     ( , ) => {$crate::__quote!(@PUNCT ',')};
     ( : ) => {$crate::__quote!(@PUNCT ':')};
     ( ; ) => {$crate::__quote!(@PUNCT ';')};
+    ( = ) => {$crate::__quote!(@PUNCT '=')};
     ( :: ) => {$crate::__quote!(@PUNCT ':', ':')};
     ( . ) => {$crate::__quote!(@PUNCT '.')};
     ( < ) => {$crate::__quote!(@PUNCT '<')};
@@ -265,13 +265,23 @@ impl TyBuilder<TraitId> {
     }
 }
 
-impl TyBuilder<TypeAliasId> {
-    pub fn assoc_type_projection(db: &dyn HirDatabase, def: TypeAliasId) -> TyBuilder<TypeAliasId> {
-        TyBuilder::subst_for_def(db, def).with_data(def)
+/// Builds the projection of an associated type, with the number of parameters of its trait.
+///
+/// The parameters of the trait are pushed first, as in `subst_for_def`. For generic associated
+/// types, the built projection has the associated type's own parameters first, as chalk expects.
+impl TyBuilder<(TypeAliasId, usize)> {
+    pub fn assoc_type_projection(
+        db: &dyn HirDatabase,
+        def: TypeAliasId,
+    ) -> TyBuilder<(TypeAliasId, usize)> {
+        let (_, parent_len, _) = generics(db.upcast(), def.into()).len_split();
+        TyBuilder::subst_for_def(db, def).with_data((def, parent_len))
     }
 
     pub fn build(self) -> ProjectionTy {
-        let (type_alias, substitution) = self.build_internal();
+        let ((type_alias, parent_len), subst) = self.build_internal();
+        let (parent, own) = subst.as_slice(Interner).split_at(parent_len);
+        let substitution = Substitution::from_iter(Interner, own.iter().chain(parent).cloned());
         ProjectionTy { associated_ty_id: to_assoc_type_id(type_alias), substitution }
     }
 }
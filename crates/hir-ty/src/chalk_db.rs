@@ -3,18 +3,26 @@
 use std::sync::Arc;
 
 use cov_mark::hit;
+use rustc_hash::FxHashSet;
 use syntax::SmolStr;
 use tracing::debug;
 
-use chalk_ir::{cast::Cast, fold::shift::Shift, CanonicalVarKinds};
+use chalk_ir::{
+    cast::Cast,
+    fold::{shift::Shift, Fold},
+    interner::HasInterner,
+    CanonicalVarKinds, TypeFlags,
+};
 use chalk_solve::rust_ir::{self, OpaqueTyDatumBound, WellKnownTrait};
 
 use base_db::CrateId;
 use hir_def::{
     lang_item::{lang_attr, LangItemTarget},
-    AssocItemId, GenericDefId, HasModule, ItemContainerId, Lookup, ModuleId, TypeAliasId,
+    AssocItemId, EnumVariantId, GenericDefId, HasModule, ItemContainerId, Lookup, ModuleId,
+    TypeAliasId, VariantId,
 };
 use hir_expand::name::name;
+use itertools::Either;
 
 use crate::{
     db::HirDatabase,
@@ -25,10 +33,10 @@ use crate::{
     method_resolution::{TraitImpls, TyFingerprint, ALL_FLOAT_FPS, ALL_INT_FPS},
     to_assoc_type_id, to_chalk_trait_id,
     traits::ChalkContext,
-    utils::generics,
-    AliasEq, AliasTy, BoundVar, CallableDefId, DebruijnIndex, FnDefId, Interner, ProjectionTy,
-    ProjectionTyExt, QuantifiedWhereClause, Substitution, TraitRef, TraitRefExt, Ty, TyBuilder,
-    TyExt, TyKind, WhereClause,
+    utils::{generics, Generics},
+    AliasEq, AliasTy, Binders, BoundVar, CallableDefId, DebruijnIndex, FnDefId, Interner,
+    ProjectionTy, ProjectionTyExt, QuantifiedWhereClause, Substitution, TraitRef, TraitRefExt, Ty,
+    TyBuilder, TyExt, TyKind, WhereClause,
 };
 
 pub(crate) type AssociatedTyDatum = chalk_solve::rust_ir::AssociatedTyDatum<Interner>;
@@ -46,6 +54,42 @@ pub(crate) type AssociatedTyValue = chalk_solve::rust_ir::AssociatedTyValue<Inte
 pub(crate) type FnDefDatum = chalk_solve::rust_ir::FnDefDatum<Interner>;
 pub(crate) type Variances = chalk_ir::Variances<Interner>;
 
+impl<'a> ChalkContext<'a> {
+    /// The impl maps that may contain impls of `trait_` for a type with fingerprint `self_ty_fp`.
+    fn impl_maps(
+        &self,
+        trait_: hir_def::TraitId,
+        self_ty_fp: Option<TyFingerprint>,
+    ) -> [Option<Arc<TraitImpls>>; 4] {
+        fn local_impls(db: &dyn HirDatabase, module: ModuleId) -> Option<Arc<TraitImpls>> {
+            let block = module.containing_block()?;
+            hit!(block_local_impls);
+            db.trait_impls_in_block(block)
+        }
+
+        // Note: Since we're using impls_for_trait, only impls where the trait
+        // can be resolved should ever reach Chalk. impl_datum relies on that
+        // and will panic if the trait can't be resolved.
+        let in_deps = self.db.trait_impls_in_deps(self.krate);
+        let in_self = self.db.trait_impls_in_crate(self.krate);
+        let trait_module = trait_.module(self.db.upcast());
+        let type_module = match self_ty_fp {
+            Some(TyFingerprint::Adt(adt_id)) => Some(adt_id.module(self.db.upcast())),
+            Some(TyFingerprint::ForeignType(type_id)) => {
+                Some(from_foreign_def_id(type_id).module(self.db.upcast()))
+            }
+            Some(TyFingerprint::Dyn(trait_id)) => Some(trait_id.module(self.db.upcast())),
+            _ => None,
+        };
+        [
+            Some(in_deps),
+            Some(in_self),
+            local_impls(self.db, trait_module),
+            type_module.and_then(|m| local_impls(self.db, m)),
+        ]
+    }
+}
+
 impl<'a> chalk_solve::RustIrDatabase<Interner> for ChalkContext<'a> {
     fn associated_ty_data(&self, id: AssocTypeId) -> Arc<AssociatedTyDatum> {
         self.db.associated_ty_data(id)
@@ -108,32 +152,7 @@ impl<'a> chalk_solve::RustIrDatabase<Interner> for ChalkContext<'a> {
             _ => self_ty_fp.as_ref().map(std::slice::from_ref).unwrap_or(&[]),
         };
 
-        fn local_impls(db: &dyn HirDatabase, module: ModuleId) -> Option<Arc<TraitImpls>> {
-            let block = module.containing_block()?;
-            hit!(block_local_impls);
-            db.trait_impls_in_block(block)
-        }
-
-        // Note: Since we're using impls_for_trait, only impls where the trait
-        // can be resolved should ever reach Chalk. impl_datum relies on that
-        // and will panic if the trait can't be resolved.
-        let in_deps = self.db.trait_impls_in_deps(self.krate);
-        let in_self = self.db.trait_impls_in_crate(self.krate);
-        let trait_module = trait_.module(self.db.upcast());
-        let type_module = match self_ty_fp {
-            Some(TyFingerprint::Adt(adt_id)) => Some(adt_id.module(self.db.upcast())),
-            Some(TyFingerprint::ForeignType(type_id)) => {
-                Some(from_foreign_def_id(type_id).module(self.db.upcast()))
-            }
-            Some(TyFingerprint::Dyn(trait_id)) => Some(trait_id.module(self.db.upcast())),
-            _ => None,
-        };
-        let impl_maps = [
-            Some(in_deps),
-            Some(in_self),
-            local_impls(self.db, trait_module),
-            type_module.and_then(|m| local_impls(self.db, m)),
-        ];
+        let impl_maps = self.impl_maps(trait_, self_ty_fp);
 
         let id_to_chalk = |id: hir_def::ImplId| id.to_chalk(self.db);
 
@@ -161,7 +180,18 @@ impl<'a> chalk_solve::RustIrDatabase<Interner> for ChalkContext<'a> {
     }
     fn impl_provided_for(&self, auto_trait_id: TraitId, kind: &chalk_ir::TyKind<Interner>) -> bool {
         debug!("impl_provided_for {:?}, {:?}", auto_trait_id, kind);
-        false // FIXME
+        // An explicit impl (positive or negative, e.g. `impl<T> !Send for *const T`) for the type
+        // constructor replaces the impl chalk would otherwise derive from the constituent types.
+        let ty = kind.clone().intern(Interner);
+        let fp = match TyFingerprint::for_inherent_impl(&ty) {
+            Some(fp) => fp,
+            None => return false,
+        };
+        let trait_ = from_chalk_trait_id(auto_trait_id);
+        self.impl_maps(trait_, Some(fp))
+            .iter()
+            .filter_map(|o| o.as_ref())
+            .any(|impls| impls.for_trait_and_self_ty(trait_, fp).next().is_some())
     }
     fn associated_ty_value(&self, id: AssociatedTyValueId) -> Arc<AssociatedTyValue> {
         self.db.associated_ty_value(self.krate, id)
@@ -199,13 +229,12 @@ impl<'a> chalk_solve::RustIrDatabase<Interner> for ChalkContext<'a> {
     fn opaque_ty_data(&self, id: chalk_ir::OpaqueTyId<Interner>) -> Arc<OpaqueTyDatum> {
         let full_id = self.db.lookup_intern_impl_trait_id(id.into());
         let bound = match full_id {
-            crate::ImplTraitId::ReturnTypeImplTrait(func, idx) => {
-                let datas = self
-                    .db
-                    .return_type_impl_traits(func)
-                    .expect("impl trait id without impl traits");
+            crate::ImplTraitId::ReturnTypeImplTrait(..)
+            | crate::ImplTraitId::TypeAliasImplTrait(..) => {
+                let (datas, idx) =
+                    full_id.impl_traits(self.db).expect("impl trait id without impl traits");
                 let (datas, binders) = (*datas).as_ref().into_value_and_skipped_binders();
-                let data = &datas.impl_traits[idx as usize];
+                let data = &datas.impl_traits[idx];
                 let bound = OpaqueTyDatumBound {
                     bounds: make_single_type_binders(data.bounds.skip_binders().to_vec()),
                     where_clauses: chalk_ir::Binders::empty(Interner, vec![]),
@@ -389,7 +418,77 @@ pub(crate) fn program_clauses_for_chalk_env_query(
     krate: CrateId,
     environment: chalk_ir::Environment<Interner>,
 ) -> chalk_ir::ProgramClauses<Interner> {
-    chalk_solve::program_clauses_for_env(&ChalkContext { db, krate }, &environment)
+    let _p = profile::span("program_clauses_for_chalk_env_query");
+    let context = ChalkContext { db, krate };
+    if environment.has_compatible_clause(Interner) {
+        return chalk_solve::program_clauses_for_env(&context, &environment);
+    }
+
+    // Chalk elaborates each clause of the environment on its own, and what it adds for a
+    // `FromEnv(T: Trait)` clause doesn't depend on `T`. So instead of elaborating every
+    // environment from scratch, we reuse the elaborated clauses of each trait.
+    let mut clauses = FxHashSet::default();
+    let mut other_clauses = Vec::new();
+    for clause in environment.clauses.iter(Interner) {
+        clauses.insert(clause.clone());
+        match from_env_trait(clause) {
+            Some(trait_id) => clauses
+                .extend(db.program_clauses_for_trait(krate, trait_id).iter(Interner).cloned()),
+            None => other_clauses.push(clause.clone()),
+        }
+    }
+    if !other_clauses.is_empty() {
+        let environment = chalk_ir::Environment::new(Interner).add_clauses(Interner, other_clauses);
+        let elaborated = chalk_solve::program_clauses_for_env(&context, &environment);
+        clauses.extend(elaborated.iter(Interner).cloned());
+    }
+    chalk_ir::ProgramClauses::from_iter(Interner, clauses)
+}
+
+pub(crate) fn program_clauses_for_trait_query(
+    db: &dyn HirDatabase,
+    krate: CrateId,
+    trait_id: TraitId,
+) -> chalk_ir::ProgramClauses<Interner> {
+    let _p = profile::span("program_clauses_for_trait_query");
+    let trait_ = from_chalk_trait_id(trait_id);
+    let generic_params = generics(db.upcast(), trait_.into());
+    let trait_ref = TraitRef {
+        trait_id,
+        substitution: generic_params.bound_vars_subst(db, DebruijnIndex::INNERMOST),
+    };
+    let implication = chalk_ir::ProgramClauseImplication {
+        consequence: chalk_ir::DomainGoal::FromEnv(chalk_ir::FromEnv::Trait(trait_ref)),
+        conditions: chalk_ir::Goals::empty(Interner),
+        constraints: chalk_ir::Constraints::empty(Interner),
+        priority: chalk_ir::ClausePriority::High,
+    };
+    let clause = chalk_ir::ProgramClause::new(
+        Interner,
+        chalk_ir::ProgramClauseData(make_binders(db, &generic_params, implication)),
+    );
+    let environment =
+        chalk_ir::Environment::new(Interner).add_clauses(Interner, Some(clause.clone()));
+    let elaborated =
+        chalk_solve::program_clauses_for_env(&ChalkContext { db, krate }, &environment);
+    chalk_ir::ProgramClauses::from_iter(
+        Interner,
+        elaborated.iter(Interner).filter(|it| **it != clause).cloned(),
+    )
+}
+
+/// Returns the trait of a `FromEnv(T: Trait)` clause without conditions.
+fn from_env_trait(clause: &chalk_ir::ProgramClause<Interner>) -> Option<TraitId> {
+    let implication = clause.data(Interner).0.skip_binders();
+    if !implication.conditions.is_empty(Interner) {
+        return None;
+    }
+    match &implication.consequence {
+        chalk_ir::DomainGoal::FromEnv(chalk_ir::FromEnv::Trait(trait_ref)) => {
+            Some(trait_ref.trait_id)
+        }
+        _ => None,
+    }
 }
 
 pub(crate) fn associated_ty_data_query(
@@ -441,11 +540,45 @@ pub(crate) fn associated_ty_data_query(
         trait_id: to_chalk_trait_id(trait_),
         id,
         name: type_alias,
-        binders: make_binders(db, &generic_params, bound_data),
+        binders: own_params_first_binders(db, &generic_params, bound_data),
     };
     Arc::new(datum)
 }
 
+/// Like `make_binders`, but puts the parameters of a generic associated type before the ones of
+/// its trait or impl, which is the order chalk expects for associated types and their values.
+fn own_params_first_binders<T>(db: &dyn HirDatabase, generics: &Generics, value: T) -> Binders<T>
+where
+    T: HasInterner<Interner = Interner> + Fold<Interner, Result = T>,
+{
+    let (_, parent_len, own_len) = generics.len_split();
+    if own_len == 0 {
+        return make_binders(db, generics, value);
+    }
+    let ids = generics.iter_id().collect::<Vec<_>>();
+    let permutation = Substitution::from_iter(
+        Interner,
+        ids.iter().enumerate().map(|(idx, id)| {
+            let new_idx = if idx < parent_len { own_len + idx } else { idx - parent_len };
+            let bound_var = BoundVar::new(DebruijnIndex::INNERMOST, new_idx);
+            match id {
+                Either::Left(_) => TyKind::BoundVar(bound_var).intern(Interner).cast(Interner),
+                Either::Right(id) => {
+                    bound_var.to_const(Interner, db.const_param_ty(*id)).cast(Interner)
+                }
+            }
+        }),
+    );
+    let value = make_binders(db, generics, value).substitute(Interner, &permutation);
+    crate::make_type_and_const_binders(
+        ids[parent_len..].iter().chain(&ids[..parent_len]).map(|id| match id {
+            Either::Left(_) => None,
+            Either::Right(id) => Some(db.const_param_ty(*id)),
+        }),
+        value,
+    )
+}
+
 pub(crate) fn trait_datum_query(
     db: &dyn HirDatabase,
     krate: CrateId,
@@ -527,25 +660,52 @@ pub(crate) fn struct_datum_query(
     let chalk_ir::AdtId(adt_id) = struct_id;
     let generic_params = generics(db.upcast(), adt_id.into());
     let upstream = adt_id.module(db.upcast()).krate() != krate;
-    let where_clauses = {
-        let generic_params = generics(db.upcast(), adt_id.into());
-        let bound_vars = generic_params.bound_vars_subst(db, DebruijnIndex::INNERMOST);
-        convert_where_clauses(db, adt_id.into(), &bound_vars)
-    };
+    let bound_vars = generic_params.bound_vars_subst(db, DebruijnIndex::INNERMOST);
+    let where_clauses = convert_where_clauses(db, adt_id.into(), &bound_vars);
+    let phantom_data = lang_attr(db.upcast(), adt_id).as_deref() == Some("phantom_data");
     let flags = rust_ir::AdtFlags {
         upstream,
-        // FIXME set fundamental and phantom_data flags correctly
+        // FIXME set fundamental flag correctly
         fundamental: false,
-        phantom_data: false,
+        phantom_data,
     };
-    // FIXME provide enum variants properly (for auto traits)
-    let variant = rust_ir::AdtVariantDatum {
-        fields: Vec::new(), // FIXME add fields (only relevant for auto traits),
+    // The variants' field types are what chalk checks when deriving auto trait impls
+    // structurally, so they need to be provided for e.g. `Send` to be decided correctly.
+    let variant_fields = |variant: VariantId| {
+        let field_types = db.field_types(variant);
+        let fields = variant
+            .variant_data(db.upcast())
+            .fields()
+            .iter()
+            .map(|(idx, _)| field_types[idx].clone().substitute(Interner, &bound_vars))
+            // Unknown field types would make the auto trait look unimplemented.
+            .filter(|ty| !ty.data(Interner).flags.intersects(TypeFlags::HAS_ERROR))
+            .collect();
+        rust_ir::AdtVariantDatum { fields }
     };
-    let struct_datum_bound = rust_ir::AdtDatumBound { variants: vec![variant], where_clauses };
+    let (kind, variants) = match adt_id {
+        // `PhantomData<T>` has no fields, but behaves as if it owned a `T` for auto traits.
+        hir_def::AdtId::StructId(_) if phantom_data => (
+            rust_ir::AdtKind::Struct,
+            vec![rust_ir::AdtVariantDatum {
+                fields: bound_vars.type_parameters(Interner).collect(),
+            }],
+        ),
+        hir_def::AdtId::StructId(id) => (rust_ir::AdtKind::Struct, vec![variant_fields(id.into())]),
+        hir_def::AdtId::UnionId(id) => (rust_ir::AdtKind::Union, vec![variant_fields(id.into())]),
+        hir_def::AdtId::EnumId(id) => {
+            let variants = db
+                .enum_data(id)
+                .variants
+                .iter()
+                .map(|(local_id, _)| variant_fields(EnumVariantId { parent: id, local_id }.into()))
+                .collect();
+            (rust_ir::AdtKind::Enum, variants)
+        }
+    };
+    let struct_datum_bound = rust_ir::AdtDatumBound { variants, where_clauses };
     let struct_datum = StructDatum {
-        // FIXME set ADT kind
-        kind: rust_ir::AdtKind::Struct,
+        kind,
         id: struct_id,
         binders: make_binders(db, &generic_params, struct_datum_bound),
         flags,
@@ -654,12 +814,13 @@ fn type_alias_associated_ty_value(
         .trait_data(trait_ref.hir_trait_id())
         .associated_type_by_name(&type_alias_data.name)
         .expect("assoc ty value should not exist"); // validated when building the impl data as well
-    let (ty, binders) = db.ty(type_alias.into()).into_value_and_skipped_binders();
+    let (ty, _) = db.ty(type_alias.into()).into_value_and_skipped_binders();
+    let generic_params = generics(db.upcast(), type_alias.into());
     let value_bound = rust_ir::AssociatedTyValueBound { ty };
     let value = rust_ir::AssociatedTyValue {
         impl_id: impl_id.to_chalk(db),
         associated_ty_id: to_assoc_type_id(assoc_ty),
-        value: chalk_ir::Binders::new(binders, value_bound),
+        value: own_params_first_binders(db, &generic_params, value_bound),
     };
     Arc::new(value)
 }
@@ -694,26 +855,6 @@ pub(crate) fn fn_def_datum_query(
     Arc::new(datum)
 }
 
-pub(crate) fn fn_def_variance_query(db: &dyn HirDatabase, fn_def_id: FnDefId) -> Variances {
-    let callable_def: CallableDefId = from_chalk(db, fn_def_id);
-    let generic_params = generics(db.upcast(), callable_def.into());
-    Variances::from_iter(
-        Interner,
-        std::iter::repeat(chalk_ir::Variance::Invariant).take(generic_params.len()),
-    )
-}
-
-pub(crate) fn adt_variance_query(
-    db: &dyn HirDatabase,
-    chalk_ir::AdtId(adt_id): AdtId,
-) -> Variances {
-    let generic_params = generics(db.upcast(), adt_id.into());
-    Variances::from_iter(
-        Interner,
-        std::iter::repeat(chalk_ir::Variance::Invariant).take(generic_params.len()),
-    )
-}
-
 pub(super) fn convert_where_clauses(
     db: &dyn HirDatabase,
     def: GenericDefId,
@@ -751,22 +892,22 @@ pub(super) fn generic_predicate_to_inline_bound(
             Some(chalk_ir::Binders::new(binders, rust_ir::InlineBound::TraitBound(trait_bound)))
         }
         WhereClause::AliasEq(AliasEq { alias: AliasTy::Projection(projection_ty), ty }) => {
-            if projection_ty.self_type_parameter(Interner) != self_ty_shifted_in {
+            let trait_ref = projection_ty.trait_ref(db);
+            if trait_ref.self_type_parameter(Interner) != self_ty_shifted_in {
                 return None;
             }
-            let trait_ = projection_ty.trait_(db);
-            let args_no_self = projection_ty.substitution.as_slice(Interner)[1..]
+            let args_no_self = trait_ref.substitution.as_slice(Interner)[1..]
                 .iter()
                 .map(|ty| ty.clone().cast(Interner))
                 .collect();
+            let own_params =
+                projection_ty.substitution.len(Interner) - trait_ref.substitution.len(Interner);
+            let parameters = projection_ty.substitution.as_slice(Interner)[..own_params].to_vec();
             let alias_eq_bound = rust_ir::AliasEqBound {
                 value: ty.clone(),
-                trait_bound: rust_ir::TraitBound {
-                    trait_id: to_chalk_trait_id(trait_),
-                    args_no_self,
-                },
+                trait_bound: rust_ir::TraitBound { trait_id: trait_ref.trait_id, args_no_self },
                 associated_ty_id: projection_ty.associated_ty_id,
-                parameters: Vec::new(), // FIXME we don't support generic associated types yet
+                parameters,
             };
             Some(chalk_ir::Binders::new(
                 binders,
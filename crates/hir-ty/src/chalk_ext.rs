@@ -10,10 +10,11 @@ use hir_def::{
 use syntax::SmolStr;
 
 use crate::{
-    db::HirDatabase, from_assoc_type_id, from_chalk_trait_id, from_foreign_def_id,
-    from_placeholder_idx, to_chalk_trait_id, AdtId, AliasEq, AliasTy, Binders, CallableDefId,
-    CallableSig, FnPointer, ImplTraitId, Interner, Lifetime, ProjectionTy, QuantifiedWhereClause,
-    Substitution, TraitRef, Ty, TyBuilder, TyKind, WhereClause,
+    consteval::eval_unevaluated_consts, db::HirDatabase, from_assoc_type_id, from_chalk_trait_id,
+    from_foreign_def_id, from_placeholder_idx, to_chalk_trait_id, utils::generics, AdtId, AliasEq,
+    AliasTy, Binders, CallableDefId, CallableSig, FnPointer, ImplTraitId, Interner, Lifetime,
+    ProjectionTy, QuantifiedWhereClause, Substitution, TraitRef, Ty, TyBuilder, TyKind,
+    WhereClause,
 };
 
 pub trait TyExt {
@@ -150,8 +151,18 @@ impl TyExt for Ty {
             TyKind::Function(fn_ptr) => Some(CallableSig::from_fn_ptr(fn_ptr)),
             TyKind::FnDef(def, parameters) => {
                 let callable_def = db.lookup_intern_callable_def((*def).into());
-                let sig = db.callable_item_signature(callable_def);
-                Some(sig.substitute(Interner, &parameters))
+                let sig =
+                    db.callable_item_signature(callable_def).substitute(Interner, &parameters);
+                // `generic_const_exprs` in the signature can be evaluated now that the generic
+                // arguments are known.
+                let params_and_return = sig
+                    .params_and_return
+                    .iter()
+                    .map(|ty| {
+                        eval_unevaluated_consts(db, callable_def.into(), parameters, ty.clone())
+                    })
+                    .collect();
+                Some(CallableSig { params_and_return, is_varargs: sig.is_varargs })
             }
             TyKind::Closure(.., substs) => {
                 let sig_param = substs.at(Interner, 0).assert_ty_ref(Interner);
@@ -207,11 +218,11 @@ impl TyExt for Ty {
                             None
                         }
                     }
-                    ImplTraitId::ReturnTypeImplTrait(func, idx) => {
-                        db.return_type_impl_traits(func).map(|it| {
-                            let data = (*it)
-                                .as_ref()
-                                .map(|rpit| rpit.impl_traits[idx as usize].bounds.clone());
+                    id @ (ImplTraitId::ReturnTypeImplTrait(..)
+                    | ImplTraitId::TypeAliasImplTrait(..)) => {
+                        id.impl_traits(db).map(|(it, idx)| {
+                            let data =
+                                (*it).as_ref().map(|rpit| rpit.impl_traits[idx].bounds.clone());
                             data.substitute(Interner, &subst).into_value_and_skipped_binders().0
                         })
                     }
@@ -220,11 +231,11 @@ impl TyExt for Ty {
             TyKind::Alias(AliasTy::Opaque(opaque_ty)) => {
                 let predicates = match db.lookup_intern_impl_trait_id(opaque_ty.opaque_ty_id.into())
                 {
-                    ImplTraitId::ReturnTypeImplTrait(func, idx) => {
-                        db.return_type_impl_traits(func).map(|it| {
-                            let data = (*it)
-                                .as_ref()
-                                .map(|rpit| rpit.impl_traits[idx as usize].bounds.clone());
+                    id @ (ImplTraitId::ReturnTypeImplTrait(..)
+                    | ImplTraitId::TypeAliasImplTrait(..)) => {
+                        id.impl_traits(db).map(|(it, idx)| {
+                            let data =
+                                (*it).as_ref().map(|rpit| rpit.impl_traits[idx].bounds.clone());
                             data.substitute(Interner, &opaque_ty.substitution)
                         })
                     }
@@ -253,7 +264,7 @@ impl TyExt for Ty {
                                     WhereClause::AliasEq(AliasEq {
                                         alias: AliasTy::Projection(proj),
                                         ty: _,
-                                    }) => &proj.self_type_parameter(Interner) == self,
+                                    }) => &proj.trait_ref(db).self_type_parameter(Interner) == self,
                                     _ => false,
                                 })
                                 .collect::<Vec<_>>();
@@ -328,9 +339,18 @@ pub trait ProjectionTyExt {
 
 impl ProjectionTyExt for ProjectionTy {
     fn trait_ref(&self, db: &dyn HirDatabase) -> TraitRef {
+        // The parameters of a generic associated type come before the ones of its trait, as
+        // that's what chalk expects. They are counted from the trait's side, because the ones of
+        // some associated types are ignored (see `utils::generics`) and missing from `self`.
+        let trait_ = self.trait_(db);
+        let trait_params = generics(db.upcast(), trait_.into()).len();
+        let own_params = self.substitution.len(Interner).saturating_sub(trait_params);
         TraitRef {
-            trait_id: to_chalk_trait_id(self.trait_(db)),
-            substitution: self.substitution.clone(),
+            trait_id: to_chalk_trait_id(trait_),
+            substitution: Substitution::from_iter(
+                Interner,
+                self.substitution.iter(Interner).skip(own_params).cloned(),
+            ),
         }
     }
 
@@ -4,25 +4,41 @@ use std::{
     collections::HashMap,
     convert::TryInto,
     fmt::{Display, Write},
+    sync::Arc,
 };
 
-use chalk_ir::{BoundVar, DebruijnIndex, GenericArgData, IntTy, Scalar};
+use base_db::{CrateOrigin, LangCrateOrigin};
+use chalk_ir::{BoundVar, DebruijnIndex, GenericArgData, IntTy, Scalar, UintTy};
 use hir_def::{
-    expr::{ArithOp, BinaryOp, Expr, ExprId, Literal, Pat, PatId},
+    expr::{
+        ArithOp, Array, BinaryOp, CmpOp, Expr, ExprId, Label, LabelId, Literal, LogicOp, Ordering,
+        Pat, PatId, RangeOp,
+    },
+    generics::TypeOrConstParamData,
     path::ModPath,
     resolver::{resolver_for_expr, ResolveValueResult, Resolver, ValueNs},
-    type_ref::ConstScalar,
-    ConstId, DefWithBodyId,
+    type_ref::{ConstScalar, ConstScalarOrPath},
+    ConstId, DefWithBodyId, FunctionId, GenericDefId, HasModule, Lookup, StaticId,
 };
+use hir_expand::name::Name;
+use itertools::Either;
 use la_arena::{Arena, Idx};
 use stdx::never;
 
 use crate::{
-    db::HirDatabase, infer::InferenceContext, lower::ParamLoweringMode, to_placeholder_idx,
-    utils::Generics, Const, ConstData, ConstValue, GenericArg, InferenceResult, Interner, Ty,
-    TyBuilder, TyKind,
+    db::HirDatabase,
+    infer::InferenceContext,
+    layout::{Layout, LayoutError},
+    lower::ParamLoweringMode,
+    to_placeholder_idx,
+    utils::{generics, Generics},
+    CallableDefId, Const, ConstData, ConstValue, GenericArg, InferenceResult, Interner,
+    Substitution, Ty, TyBuilder, TyKind,
 };
 
+/// The maximal number of loop iterations, and of array elements, in a single evaluation.
+const EXECUTION_LIMIT: usize = 100_000;
+
 /// Extension trait for [`Const`]
 pub trait ConstExt {
     /// Is a [`Const`] unknown?
@@ -56,8 +72,13 @@ pub struct ConstEvalCtx<'a> {
     pub owner: DefWithBodyId,
     pub exprs: &'a Arena<Expr>,
     pub pats: &'a Arena<Pat>,
+    pub labels: &'a Arena<Label>,
     pub local_data: HashMap<PatId, ComputedExpr>,
     infer: &'a InferenceResult,
+    /// The number of loop iterations evaluated so far.
+    steps: usize,
+    /// Whether paths to immutable statics evaluate to their initializer.
+    substitute_statics: bool,
 }
 
 impl ConstEvalCtx<'_> {
@@ -73,12 +94,28 @@ pub enum ConstEvalError {
     Loop,
     IncompleteExpr,
     Panic(String),
+    ExecutionLimitExceeded,
+    LayoutError(LayoutError),
+}
+
+/// Leaves the evaluation of an expression early: because of an error, or to jump to a loop.
+enum Interrupt {
+    Error(ConstEvalError),
+    Break { label: Option<Name>, value: ComputedExpr },
+    Continue { label: Option<Name> },
+}
+
+impl From<ConstEvalError> for Interrupt {
+    fn from(e: ConstEvalError) -> Self {
+        Interrupt::Error(e)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ComputedExpr {
     Literal(Literal),
     Tuple(Box<[ComputedExpr]>),
+    Array(Box<[ComputedExpr]>),
 }
 
 impl Display for ComputedExpr {
@@ -113,6 +150,16 @@ impl Display for ComputedExpr {
                 }
                 f.write_char(')')
             }
+            ComputedExpr::Array(a) => {
+                f.write_char('[')?;
+                for (i, x) in a.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(", ")?;
+                    }
+                    x.fmt(f)?;
+                }
+                f.write_char(']')
+            }
         }
     }
 }
@@ -153,25 +200,31 @@ pub fn eval_const(
     expr_id: ExprId,
     ctx: &mut ConstEvalCtx<'_>,
 ) -> Result<ComputedExpr, ConstEvalError> {
+    eval_expr(expr_id, ctx).map_err(|it| match it {
+        Interrupt::Error(e) => e,
+        Interrupt::Break { .. } | Interrupt::Continue { .. } => {
+            ConstEvalError::SemanticError("`break` or `continue` outside of a loop")
+        }
+    })
+}
+
+fn eval_expr(expr_id: ExprId, ctx: &mut ConstEvalCtx<'_>) -> Result<ComputedExpr, Interrupt> {
     let expr = &ctx.exprs[expr_id];
     match expr {
-        Expr::Missing => Err(ConstEvalError::IncompleteExpr),
+        Expr::Missing => Err(ConstEvalError::IncompleteExpr.into()),
         Expr::Literal(l) => Ok(ComputedExpr::Literal(l.clone())),
         &Expr::UnaryOp { expr, op } => {
             let ty = &ctx.expr_ty(expr);
-            let ev = eval_const(expr, ctx)?;
+            let ev = eval_expr(expr, ctx)?;
             match op {
-                hir_def::expr::UnaryOp::Deref => Err(ConstEvalError::NotSupported("deref")),
+                // References are evaluated to the value they point to.
+                hir_def::expr::UnaryOp::Deref => Ok(ev),
                 hir_def::expr::UnaryOp::Not => {
                     let v = match ev {
                         ComputedExpr::Literal(Literal::Bool(b)) => {
                             return Ok(ComputedExpr::Literal(Literal::Bool(!b)))
                         }
-                        ComputedExpr::Literal(Literal::Int(v, _)) => v,
-                        ComputedExpr::Literal(Literal::Uint(v, _)) => v
-                            .try_into()
-                            .map_err(|_| ConstEvalError::NotSupported("too big u128"))?,
-                        _ => return Err(ConstEvalError::NotSupported("this kind of operator")),
+                        ev => to_int(&ev)?,
                     };
                     let r = match ty.kind(Interner) {
                         TyKind::Scalar(Scalar::Uint(x)) => match x {
@@ -180,7 +233,7 @@ pub fn eval_const(
                             chalk_ir::UintTy::U32 => !(v as u32) as i128,
                             chalk_ir::UintTy::U64 => !(v as u64) as i128,
                             chalk_ir::UintTy::U128 => {
-                                return Err(ConstEvalError::NotSupported("negation of u128"))
+                                return Err(ConstEvalError::NotSupported("negation of u128").into())
                             }
                             chalk_ir::UintTy::Usize => !(v as usize) as i128,
                         },
@@ -192,18 +245,12 @@ pub fn eval_const(
                             chalk_ir::IntTy::I128 => !v,
                             chalk_ir::IntTy::Isize => !(v as isize) as i128,
                         },
-                        _ => return Err(ConstEvalError::NotSupported("unreachable?")),
+                        _ => return Err(ConstEvalError::NotSupported("unreachable?").into()),
                     };
                     Ok(ComputedExpr::Literal(Literal::Int(r, None)))
                 }
                 hir_def::expr::UnaryOp::Neg => {
-                    let v = match ev {
-                        ComputedExpr::Literal(Literal::Int(v, _)) => v,
-                        ComputedExpr::Literal(Literal::Uint(v, _)) => v
-                            .try_into()
-                            .map_err(|_| ConstEvalError::NotSupported("too big u128"))?,
-                        _ => return Err(ConstEvalError::NotSupported("this kind of operator")),
-                    };
+                    let v = to_int(&ev)?;
                     Ok(ComputedExpr::Literal(Literal::Int(
                         v.checked_neg().ok_or_else(|| {
                             ConstEvalError::Panic("overflow in negation".to_string())
@@ -214,88 +261,56 @@ pub fn eval_const(
             }
         }
         &Expr::BinaryOp { lhs, rhs, op } => {
-            let ty = &ctx.expr_ty(lhs);
-            let lhs = eval_const(lhs, ctx)?;
-            let rhs = eval_const(rhs, ctx)?;
             let op = op.ok_or(ConstEvalError::IncompleteExpr)?;
-            let v1 = match lhs {
-                ComputedExpr::Literal(Literal::Int(v, _)) => v,
-                ComputedExpr::Literal(Literal::Uint(v, _)) => {
-                    v.try_into().map_err(|_| ConstEvalError::NotSupported("too big u128"))?
+            let ty = &ctx.expr_ty(lhs);
+            match op {
+                BinaryOp::Assignment { op } => {
+                    let value = match op {
+                        Some(op) => {
+                            let v1 = to_int(&eval_expr(lhs, ctx)?)?;
+                            let v2 = to_int(&eval_expr(rhs, ctx)?)?;
+                            eval_arith(op, ty, v1, v2)?
+                        }
+                        None => eval_expr(rhs, ctx)?,
+                    };
+                    assign(lhs, value, ctx)?;
+                    return Ok(ComputedExpr::Tuple(Box::new([])));
                 }
-                _ => return Err(ConstEvalError::NotSupported("this kind of operator")),
-            };
-            let v2 = match rhs {
-                ComputedExpr::Literal(Literal::Int(v, _)) => v,
-                ComputedExpr::Literal(Literal::Uint(v, _)) => {
-                    v.try_into().map_err(|_| ConstEvalError::NotSupported("too big u128"))?
+                BinaryOp::LogicOp(op) => {
+                    let lhs = to_bool(&eval_expr(lhs, ctx)?)?;
+                    let r = match op {
+                        LogicOp::And => lhs && to_bool(&eval_expr(rhs, ctx)?)?,
+                        LogicOp::Or => lhs || to_bool(&eval_expr(rhs, ctx)?)?,
+                    };
+                    return Ok(ComputedExpr::Literal(Literal::Bool(r)));
                 }
-                _ => return Err(ConstEvalError::NotSupported("this kind of operator")),
-            };
+                BinaryOp::ArithOp(_) | BinaryOp::CmpOp(_) => {}
+            }
+            let lhs = eval_expr(lhs, ctx)?;
+            let rhs = eval_expr(rhs, ctx)?;
             match op {
-                BinaryOp::ArithOp(b) => {
-                    let panic_arith = ConstEvalError::Panic(
-                        "attempt to run invalid arithmetic operation".to_string(),
-                    );
-                    let r = match b {
-                        ArithOp::Add => v1.checked_add(v2).ok_or_else(|| panic_arith.clone())?,
-                        ArithOp::Mul => v1.checked_mul(v2).ok_or_else(|| panic_arith.clone())?,
-                        ArithOp::Sub => v1.checked_sub(v2).ok_or_else(|| panic_arith.clone())?,
-                        ArithOp::Div => v1.checked_div(v2).ok_or_else(|| panic_arith.clone())?,
-                        ArithOp::Rem => v1.checked_rem(v2).ok_or_else(|| panic_arith.clone())?,
-                        ArithOp::Shl => v1
-                            .checked_shl(v2.try_into().map_err(|_| panic_arith.clone())?)
-                            .ok_or_else(|| panic_arith.clone())?,
-                        ArithOp::Shr => v1
-                            .checked_shr(v2.try_into().map_err(|_| panic_arith.clone())?)
-                            .ok_or_else(|| panic_arith.clone())?,
-                        ArithOp::BitXor => v1 ^ v2,
-                        ArithOp::BitOr => v1 | v2,
-                        ArithOp::BitAnd => v1 & v2,
-                    };
-                    if let TyKind::Scalar(s) = ty.kind(Interner) {
-                        if !is_valid(s, r) {
-                            return Err(panic_arith);
+                BinaryOp::ArithOp(b) => Ok(eval_arith(b, ty, to_int(&lhs)?, to_int(&rhs)?)?),
+                BinaryOp::CmpOp(op) => {
+                    let ordering = to_comparable(&lhs)?.cmp(&to_comparable(&rhs)?);
+                    let r = match op {
+                        CmpOp::Eq { negated } => ordering.is_eq() != negated,
+                        CmpOp::Ord { ordering: Ordering::Less, strict: true } => ordering.is_lt(),
+                        CmpOp::Ord { ordering: Ordering::Less, strict: false } => ordering.is_le(),
+                        CmpOp::Ord { ordering: Ordering::Greater, strict: true } => {
+                            ordering.is_gt()
                         }
-                    }
-                    Ok(ComputedExpr::Literal(Literal::Int(r, None)))
+                        CmpOp::Ord { ordering: Ordering::Greater, strict: false } => {
+                            ordering.is_ge()
+                        }
+                    };
+                    Ok(ComputedExpr::Literal(Literal::Bool(r)))
                 }
-                BinaryOp::LogicOp(_) => Err(ConstEvalError::SemanticError("logic op on numbers")),
-                _ => Err(ConstEvalError::NotSupported("bin op on this operators")),
+                _ => Err(ConstEvalError::NotSupported("bin op on this operators").into()),
             }
         }
         Expr::Block { statements, tail, .. } => {
             let mut prev_values = HashMap::<PatId, Option<ComputedExpr>>::default();
-            for statement in &**statements {
-                match *statement {
-                    hir_def::expr::Statement::Let { pat: pat_id, initializer, .. } => {
-                        let pat = &ctx.pats[pat_id];
-                        match pat {
-                            Pat::Bind { subpat, .. } if subpat.is_none() => (),
-                            _ => {
-                                return Err(ConstEvalError::NotSupported("complex patterns in let"))
-                            }
-                        };
-                        let value = match initializer {
-                            Some(x) => eval_const(x, ctx)?,
-                            None => continue,
-                        };
-                        if !prev_values.contains_key(&pat_id) {
-                            let prev = ctx.local_data.insert(pat_id, value);
-                            prev_values.insert(pat_id, prev);
-                        } else {
-                            ctx.local_data.insert(pat_id, value);
-                        }
-                    }
-                    hir_def::expr::Statement::Expr { .. } => {
-                        return Err(ConstEvalError::NotSupported("this kind of statement"))
-                    }
-                }
-            }
-            let r = match tail {
-                &Some(x) => eval_const(x, ctx),
-                None => Ok(ComputedExpr::Tuple(Box::new([]))),
-            };
+            let r = eval_block(statements, *tail, &mut prev_values, ctx);
             // clean up local data, so caller will receive the exact map that passed to us
             for (name, val) in prev_values {
                 match val {
@@ -305,6 +320,177 @@ pub fn eval_const(
             }
             r
         }
+        &Expr::If { condition, then_branch, else_branch } => {
+            if to_bool(&eval_expr(condition, ctx)?)? {
+                eval_expr(then_branch, ctx)
+            } else {
+                match else_branch {
+                    Some(else_branch) => eval_expr(else_branch, ctx),
+                    None => Ok(ComputedExpr::Tuple(Box::new([]))),
+                }
+            }
+        }
+        &Expr::Loop { body, label } => loop {
+            if let Some(value) = eval_loop_iteration(body, label, ctx)? {
+                return Ok(value);
+            }
+        },
+        &Expr::While { condition, body, label } => {
+            while to_bool(&eval_expr(condition, ctx)?)? {
+                if eval_loop_iteration(body, label, ctx)?.is_some() {
+                    break;
+                }
+            }
+            Ok(ComputedExpr::Tuple(Box::new([])))
+        }
+        &Expr::For { iterable, pat, body, label } => {
+            let (start, end) = match &ctx.exprs[iterable] {
+                &Expr::Range { lhs: Some(lhs), rhs: Some(rhs), range_type } => {
+                    let start = to_int(&eval_expr(lhs, ctx)?)?;
+                    let end = to_int(&eval_expr(rhs, ctx)?)?;
+                    match range_type {
+                        RangeOp::Exclusive => (start, end),
+                        RangeOp::Inclusive => (start, end.saturating_add(1)),
+                    }
+                }
+                _ => return Err(ConstEvalError::NotSupported("`for` loop over non-range").into()),
+            };
+            match &ctx.pats[pat] {
+                Pat::Bind { subpat: None, .. } | Pat::Wild => (),
+                _ => return Err(ConstEvalError::NotSupported("complex patterns in for").into()),
+            }
+            let prev = ctx.local_data.remove(&pat);
+            let mut r = Ok(ComputedExpr::Tuple(Box::new([])));
+            for i in start..end {
+                ctx.local_data.insert(pat, ComputedExpr::Literal(Literal::Int(i, None)));
+                match eval_loop_iteration(body, label, ctx) {
+                    Ok(None) => (),
+                    Ok(Some(_)) => break,
+                    Err(e) => {
+                        r = Err(e);
+                        break;
+                    }
+                }
+            }
+            match prev {
+                Some(x) => ctx.local_data.insert(pat, x),
+                None => ctx.local_data.remove(&pat),
+            };
+            r
+        }
+        Expr::Break { expr, label } => {
+            let label = label.clone();
+            let value = match *expr {
+                Some(expr) => eval_expr(expr, ctx)?,
+                None => ComputedExpr::Tuple(Box::new([])),
+            };
+            Err(Interrupt::Break { label, value })
+        }
+        Expr::Continue { label } => Err(Interrupt::Continue { label: label.clone() }),
+        Expr::Tuple { exprs } => {
+            let exprs = exprs.clone();
+            let values = exprs.iter().map(|&it| eval_expr(it, ctx)).collect::<Result<_, _>>()?;
+            Ok(ComputedExpr::Tuple(values))
+        }
+        Expr::Array(Array::ElementList(elements)) => {
+            let elements = elements.clone();
+            let values = elements.iter().map(|&it| eval_expr(it, ctx)).collect::<Result<_, _>>()?;
+            Ok(ComputedExpr::Array(values))
+        }
+        &Expr::Array(Array::Repeat { initializer, repeat }) => {
+            let value = eval_expr(initializer, ctx)?;
+            let count = to_int(&eval_expr(repeat, ctx)?)?;
+            if count as usize > EXECUTION_LIMIT {
+                return Err(ConstEvalError::ExecutionLimitExceeded.into());
+            }
+            Ok(ComputedExpr::Array(vec![value; count as usize].into_boxed_slice()))
+        }
+        &Expr::Index { base, index } => {
+            let base = eval_expr(base, ctx)?;
+            if let Expr::Range { lhs, rhs, range_type } = ctx.exprs[index] {
+                let elements = to_array(&base)?;
+                let start = match lhs {
+                    Some(lhs) => to_int(&eval_expr(lhs, ctx)?)?,
+                    None => 0,
+                };
+                let end = match (rhs, range_type) {
+                    (Some(rhs), RangeOp::Exclusive) => to_int(&eval_expr(rhs, ctx)?)?,
+                    (Some(rhs), RangeOp::Inclusive) => {
+                        to_int(&eval_expr(rhs, ctx)?)?.checked_add(1).ok_or_else(|| {
+                            ConstEvalError::Panic("overflow in range end".to_string())
+                        })?
+                    }
+                    (None, _) => elements.len() as i128,
+                };
+                let slice = match (usize::try_from(start), usize::try_from(end)) {
+                    (Ok(start), Ok(end)) if start <= end => elements.get(start..end),
+                    _ => None,
+                };
+                return match slice {
+                    Some(slice) => Ok(ComputedExpr::Array(slice.into())),
+                    None => Err(ConstEvalError::Panic(format!(
+                        "range {}..{} out of range for slice of length {}",
+                        start,
+                        end,
+                        elements.len()
+                    ))
+                    .into()),
+                };
+            }
+            let index = to_int(&eval_expr(index, ctx)?)?;
+            Ok(index_array(&base, index)?.clone())
+        }
+        &Expr::Field { expr, ref name } => {
+            let idx = name.as_tuple_index().ok_or(ConstEvalError::NotSupported("struct field"))?;
+            match eval_expr(expr, ctx)? {
+                ComputedExpr::Tuple(values) => Ok(values
+                    .get(idx)
+                    .ok_or(ConstEvalError::SemanticError("tuple index out of bounds"))?
+                    .clone()),
+                _ => Err(ConstEvalError::NotSupported("field of a non-tuple").into()),
+            }
+        }
+        // References are evaluated to the value they point to.
+        &Expr::Ref { expr, .. } => eval_expr(expr, ctx),
+        &Expr::Cast { expr, .. } => {
+            let target = ctx.expr_ty(expr_id);
+            let value = eval_expr(expr, ctx)?;
+            match target.kind(Interner) {
+                TyKind::Scalar(Scalar::Int(_) | Scalar::Uint(_)) => {
+                    let v = match value {
+                        ComputedExpr::Literal(Literal::Bool(it)) => it as i128,
+                        ComputedExpr::Literal(Literal::Char(it)) => it as i128,
+                        value => to_int(&value)?,
+                    };
+                    let ty = match target.kind(Interner) {
+                        TyKind::Scalar(it) => it,
+                        _ => unreachable!(),
+                    };
+                    Ok(ComputedExpr::Literal(Literal::Int(truncate(v, ty), None)))
+                }
+                _ => Err(ConstEvalError::NotSupported("non-integer cast").into()),
+            }
+        }
+        &Expr::Call { callee, ref args } => {
+            let args = args.clone();
+            let (func, subst) = match ctx.expr_ty(callee).kind(Interner) {
+                TyKind::FnDef(def, subst) => match ctx.db.lookup_intern_callable_def((*def).into())
+                {
+                    CallableDefId::FunctionId(func) => (func, subst.clone()),
+                    _ => return Err(ConstEvalError::NotSupported("constructor call").into()),
+                },
+                _ => return Err(ConstEvalError::NotSupported("call of a non-function").into()),
+            };
+            eval_intrinsic(func, &subst, &args, ctx)
+        }
+        &Expr::MethodCall { receiver, ref args, .. } => {
+            let args = std::iter::once(receiver).chain(args.iter().copied()).collect::<Vec<_>>();
+            let (func, subst) = ctx
+                .infer
+                .method_resolution(expr_id)
+                .ok_or(ConstEvalError::SemanticError("unresolved method"))?;
+            eval_intrinsic(func, &subst, &args, ctx)
+        }
         Expr::Path(p) => {
             let resolver = resolver_for_expr(ctx.db.upcast(), ctx.owner, expr_id);
             let pr = resolver
@@ -319,11 +505,11 @@ pub fn eval_const(
                         .ok_or(ConstEvalError::SemanticError("unresolved assoc item"))?
                     {
                         hir_def::AssocItemId::FunctionId(_) => {
-                            Err(ConstEvalError::NotSupported("assoc function"))
+                            Err(ConstEvalError::NotSupported("assoc function").into())
                         }
-                        hir_def::AssocItemId::ConstId(c) => ctx.db.const_eval(c),
+                        hir_def::AssocItemId::ConstId(c) => Ok(ctx.db.const_eval(c)?),
                         hir_def::AssocItemId::TypeAliasId(_) => {
-                            Err(ConstEvalError::NotSupported("assoc type alias"))
+                            Err(ConstEvalError::NotSupported("assoc type alias").into())
                         }
                     }
                 }
@@ -336,17 +522,260 @@ pub fn eval_const(
                         .ok_or(ConstEvalError::NotSupported("Unexpected missing local"))?;
                     Ok(r.clone())
                 }
-                ValueNs::ConstId(id) => ctx.db.const_eval(id),
+                ValueNs::ConstId(id) => Ok(ctx.db.const_eval(id)?),
+                ValueNs::StaticId(id) if ctx.substitute_statics => Ok(eval_static(ctx.db, id)?),
                 ValueNs::GenericParam(_) => {
-                    Err(ConstEvalError::NotSupported("const generic without substitution"))
+                    Err(ConstEvalError::NotSupported("const generic without substitution").into())
+                }
+                _ => Err(ConstEvalError::NotSupported("path that are not const or local").into()),
+            }
+        }
+        _ => Err(ConstEvalError::NotSupported("This kind of expression").into()),
+    }
+}
+
+fn eval_block(
+    statements: &[hir_def::expr::Statement],
+    tail: Option<ExprId>,
+    prev_values: &mut HashMap<PatId, Option<ComputedExpr>>,
+    ctx: &mut ConstEvalCtx<'_>,
+) -> Result<ComputedExpr, Interrupt> {
+    for statement in statements {
+        match *statement {
+            hir_def::expr::Statement::Let { pat: pat_id, initializer, .. } => {
+                let pat = &ctx.pats[pat_id];
+                match pat {
+                    Pat::Bind { subpat, .. } if subpat.is_none() => (),
+                    _ => return Err(ConstEvalError::NotSupported("complex patterns in let").into()),
+                };
+                let value = match initializer {
+                    Some(x) => eval_expr(x, ctx)?,
+                    None => continue,
+                };
+                if !prev_values.contains_key(&pat_id) {
+                    let prev = ctx.local_data.insert(pat_id, value);
+                    prev_values.insert(pat_id, prev);
+                } else {
+                    ctx.local_data.insert(pat_id, value);
+                }
+            }
+            hir_def::expr::Statement::Expr { expr, .. } => {
+                eval_expr(expr, ctx)?;
+            }
+        }
+    }
+    match tail {
+        Some(x) => eval_expr(x, ctx),
+        None => Ok(ComputedExpr::Tuple(Box::new([]))),
+    }
+}
+
+/// Evaluates the body of a loop once, returning the value of a `break` leaving the loop.
+fn eval_loop_iteration(
+    body: ExprId,
+    label: Option<LabelId>,
+    ctx: &mut ConstEvalCtx<'_>,
+) -> Result<Option<ComputedExpr>, Interrupt> {
+    ctx.steps += 1;
+    if ctx.steps > EXECUTION_LIMIT {
+        return Err(ConstEvalError::ExecutionLimitExceeded.into());
+    }
+    let targets_this_loop = |target: &Option<Name>, ctx: &ConstEvalCtx<'_>| match target {
+        None => true,
+        Some(name) => label.map_or(false, |label| ctx.labels[label].name == *name),
+    };
+    match eval_expr(body, ctx) {
+        Ok(_) => Ok(None),
+        Err(Interrupt::Break { label, value }) if targets_this_loop(&label, ctx) => Ok(Some(value)),
+        Err(Interrupt::Continue { label }) if targets_this_loop(&label, ctx) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Assigns to a local, or to an element or tuple field of one.
+fn assign(lhs: ExprId, value: ComputedExpr, ctx: &mut ConstEvalCtx<'_>) -> Result<(), Interrupt> {
+    // The projections from the local to the assigned value, innermost last.
+    let mut projections = Vec::new();
+    let mut current = lhs;
+    let pat = loop {
+        match &ctx.exprs[current] {
+            &Expr::Index { base, index } => {
+                projections.push(to_int(&eval_expr(index, ctx)?)?);
+                current = base;
+            }
+            &Expr::Field { expr, ref name } => {
+                let idx = name
+                    .as_tuple_index()
+                    .ok_or(ConstEvalError::NotSupported("assignment to struct field"))?;
+                projections.push(idx as i128);
+                current = expr;
+            }
+            Expr::Path(p) => {
+                let resolver = resolver_for_expr(ctx.db.upcast(), ctx.owner, current);
+                match resolver.resolve_path_in_value_ns_fully(ctx.db.upcast(), p.mod_path()) {
+                    Some(ValueNs::LocalBinding(pat)) => break pat,
+                    _ => {
+                        return Err(ConstEvalError::SemanticError("assignment to non-local").into())
+                    }
                 }
-                _ => Err(ConstEvalError::NotSupported("path that are not const or local")),
             }
+            _ => return Err(ConstEvalError::NotSupported("this kind of assignment").into()),
+        }
+    };
+    if projections.is_empty() {
+        ctx.local_data.insert(pat, value);
+        return Ok(());
+    }
+    let mut place = ctx
+        .local_data
+        .get_mut(&pat)
+        .ok_or(ConstEvalError::NotSupported("Unexpected missing local"))?;
+    for &idx in projections.iter().rev() {
+        place = match place {
+            ComputedExpr::Array(values) | ComputedExpr::Tuple(values) => {
+                let len = values.len();
+                values.get_mut(idx as usize).ok_or_else(|| {
+                    ConstEvalError::Panic(format!(
+                        "index out of bounds: the len is {} but the index is {}",
+                        len, idx
+                    ))
+                })?
+            }
+            ComputedExpr::Literal(_) => {
+                return Err(ConstEvalError::SemanticError("projection of a scalar").into())
+            }
+        };
+    }
+    *place = value;
+    Ok(())
+}
+
+/// Evaluates calls of the `core` functions that are supported in constants.
+fn eval_intrinsic(
+    func: FunctionId,
+    subst: &Substitution,
+    args: &[ExprId],
+    ctx: &mut ConstEvalCtx<'_>,
+) -> Result<ComputedExpr, Interrupt> {
+    let krate = func.lookup(ctx.db.upcast()).module(ctx.db.upcast()).krate();
+    if !matches!(
+        ctx.db.crate_graph()[krate].origin,
+        CrateOrigin::Lang(LangCrateOrigin::Core | LangCrateOrigin::Std)
+    ) {
+        return Err(ConstEvalError::NotSupported("call of a non-intrinsic function").into());
+    }
+    let name = ctx.db.function_data(func).name.to_smol_str();
+    let args = args.iter().map(|&it| eval_expr(it, ctx)).collect::<Result<Vec<_>, _>>()?;
+    let type_arg = || {
+        subst
+            .iter(Interner)
+            .find_map(|it| it.ty(Interner))
+            .ok_or(ConstEvalError::SemanticError("missing type argument"))
+    };
+    let r = match (name.as_str(), &*args) {
+        ("size_of", []) => layout_of(ctx, type_arg()?)?.size as i128,
+        ("align_of", []) => layout_of(ctx, type_arg()?)?.align as i128,
+        ("min", [lhs, rhs]) => to_int(lhs)?.min(to_int(rhs)?),
+        ("max", [lhs, rhs]) => to_int(lhs)?.max(to_int(rhs)?),
+        ("len", [it]) => to_array(it)?.len() as i128,
+        _ => return Err(ConstEvalError::NotSupported("call of a non-intrinsic function").into()),
+    };
+    Ok(ComputedExpr::Literal(Literal::Int(r, None)))
+}
+
+fn layout_of(ctx: &ConstEvalCtx<'_>, ty: &Ty) -> Result<Arc<Layout>, ConstEvalError> {
+    let krate = ctx.owner.module(ctx.db.upcast()).krate();
+    ctx.db.layout_of(ty.clone(), krate).map_err(ConstEvalError::LayoutError)
+}
+
+fn eval_arith(op: ArithOp, ty: &Ty, v1: i128, v2: i128) -> Result<ComputedExpr, ConstEvalError> {
+    let panic_arith =
+        ConstEvalError::Panic("attempt to run invalid arithmetic operation".to_string());
+    let r = match op {
+        ArithOp::Add => v1.checked_add(v2).ok_or_else(|| panic_arith.clone())?,
+        ArithOp::Mul => v1.checked_mul(v2).ok_or_else(|| panic_arith.clone())?,
+        ArithOp::Sub => v1.checked_sub(v2).ok_or_else(|| panic_arith.clone())?,
+        ArithOp::Div => v1.checked_div(v2).ok_or_else(|| panic_arith.clone())?,
+        ArithOp::Rem => v1.checked_rem(v2).ok_or_else(|| panic_arith.clone())?,
+        ArithOp::Shl => v1
+            .checked_shl(v2.try_into().map_err(|_| panic_arith.clone())?)
+            .ok_or_else(|| panic_arith.clone())?,
+        ArithOp::Shr => v1
+            .checked_shr(v2.try_into().map_err(|_| panic_arith.clone())?)
+            .ok_or_else(|| panic_arith.clone())?,
+        ArithOp::BitXor => v1 ^ v2,
+        ArithOp::BitOr => v1 | v2,
+        ArithOp::BitAnd => v1 & v2,
+    };
+    if let TyKind::Scalar(s) = ty.kind(Interner) {
+        if !is_valid(s, r) {
+            return Err(panic_arith);
+        }
+    }
+    Ok(ComputedExpr::Literal(Literal::Int(r, None)))
+}
+
+/// Wraps an integer around to the range of the given integer type, like an `as` cast.
+fn truncate(value: i128, scalar: &Scalar) -> i128 {
+    let bits = match scalar {
+        Scalar::Int(IntTy::I8) | Scalar::Uint(UintTy::U8) => 8,
+        Scalar::Int(IntTy::I16) | Scalar::Uint(UintTy::U16) => 16,
+        Scalar::Int(IntTy::I32) | Scalar::Uint(UintTy::U32) => 32,
+        Scalar::Int(IntTy::I64 | IntTy::Isize) | Scalar::Uint(UintTy::U64 | UintTy::Usize) => 64,
+        _ => return value,
+    };
+    let unsigned = value & ((1 << bits) - 1);
+    if matches!(scalar, Scalar::Int(_)) && unsigned >= 1 << (bits - 1) {
+        unsigned - (1 << bits)
+    } else {
+        unsigned
+    }
+}
+
+fn to_int(value: &ComputedExpr) -> Result<i128, ConstEvalError> {
+    match value {
+        ComputedExpr::Literal(Literal::Int(v, _)) => Ok(*v),
+        ComputedExpr::Literal(Literal::Uint(v, _)) => {
+            (*v).try_into().map_err(|_| ConstEvalError::NotSupported("too big u128"))
         }
-        _ => Err(ConstEvalError::NotSupported("This kind of expression")),
+        _ => Err(ConstEvalError::NotSupported("this kind of operator")),
+    }
+}
+
+fn to_bool(value: &ComputedExpr) -> Result<bool, ConstEvalError> {
+    match value {
+        ComputedExpr::Literal(Literal::Bool(b)) => Ok(*b),
+        _ => Err(ConstEvalError::SemanticError("condition is not a bool")),
+    }
+}
+
+/// Maps the values that comparison operators are supported for to integers.
+fn to_comparable(value: &ComputedExpr) -> Result<i128, ConstEvalError> {
+    match value {
+        ComputedExpr::Literal(Literal::Bool(b)) => Ok(*b as i128),
+        ComputedExpr::Literal(Literal::Char(c)) => Ok(*c as i128),
+        _ => to_int(value),
+    }
+}
+
+fn to_array(value: &ComputedExpr) -> Result<&[ComputedExpr], ConstEvalError> {
+    match value {
+        ComputedExpr::Array(values) => Ok(values),
+        _ => Err(ConstEvalError::NotSupported("indexing a non-array")),
     }
 }
 
+fn index_array(value: &ComputedExpr, index: i128) -> Result<&ComputedExpr, ConstEvalError> {
+    let values = to_array(value)?;
+    usize::try_from(index).ok().and_then(|it| values.get(it)).ok_or_else(|| {
+        ConstEvalError::Panic(format!(
+            "index out of bounds: the len is {} but the index is {}",
+            values.len(),
+            index
+        ))
+    })
+}
+
 pub fn eval_usize(expr: Idx<Expr>, mut ctx: ConstEvalCtx<'_>) -> Option<u64> {
     if let Ok(ce) = eval_const(expr, &mut ctx) {
         match ce {
@@ -419,6 +848,99 @@ pub fn usize_const(value: Option<u64>) -> Const {
     )
 }
 
+/// Partially evaluates `lhs op rhs` for usize consts, as used by `generic_const_exprs`.
+///
+/// If both operands are known the result is computed, otherwise a few identities (e.g. `N * 1`
+/// or `N + 0`) are used to keep the symbolic operand, so that it can still be unified with other
+/// consts. Anything else evaluates to an unknown const.
+pub(crate) fn eval_usize_binop(op: ArithOp, lhs: Const, rhs: Const) -> Const {
+    let as_usize = |c: &Const| match &c.data(Interner).value {
+        ConstValue::Concrete(cc) => cc.interned.as_usize(),
+        _ => None,
+    };
+    match (as_usize(&lhs), as_usize(&rhs)) {
+        (Some(l), Some(r)) => {
+            let value = match op {
+                ArithOp::Add => l.checked_add(r),
+                ArithOp::Mul => l.checked_mul(r),
+                ArithOp::Sub => l.checked_sub(r),
+                ArithOp::Div => l.checked_div(r),
+                ArithOp::Rem => l.checked_rem(r),
+                ArithOp::Shl => r.try_into().ok().and_then(|r| l.checked_shl(r)),
+                ArithOp::Shr => r.try_into().ok().and_then(|r| l.checked_shr(r)),
+                ArithOp::BitXor => Some(l ^ r),
+                ArithOp::BitOr => Some(l | r),
+                ArithOp::BitAnd => Some(l & r),
+            };
+            usize_const(value)
+        }
+        (None, Some(0)) => match op {
+            ArithOp::Add
+            | ArithOp::Sub
+            | ArithOp::Shl
+            | ArithOp::Shr
+            | ArithOp::BitOr
+            | ArithOp::BitXor => lhs,
+            ArithOp::Mul | ArithOp::BitAnd => rhs,
+            ArithOp::Div | ArithOp::Rem => usize_const(None),
+        },
+        (None, Some(1)) if matches!(op, ArithOp::Mul | ArithOp::Div) => lhs,
+        (Some(0), None) => match op {
+            ArithOp::Add | ArithOp::BitOr | ArithOp::BitXor => rhs,
+            ArithOp::Mul | ArithOp::BitAnd | ArithOp::Shl | ArithOp::Shr => lhs,
+            _ => usize_const(None),
+        },
+        (Some(1), None) if op == ArithOp::Mul => rhs,
+        _ => usize_const(None),
+    }
+}
+
+/// Evaluates the `generic_const_exprs` in `ty`, which belongs to the generic item `def`, now that
+/// its generic arguments are known to be `subst`.
+pub(crate) fn eval_unevaluated_consts(
+    db: &dyn HirDatabase,
+    def: GenericDefId,
+    subst: &Substitution,
+    ty: Ty,
+) -> Ty {
+    let lookup = |name: &Name| {
+        let generics = generics(db.upcast(), def);
+        let (id, _) = generics.iter().find(|(_, data)| {
+            matches!(data, TypeOrConstParamData::ConstParamData(it) if it.name == *name)
+        })?;
+        subst.as_slice(Interner).get(generics.param_idx(id)?)?.constant(Interner).cloned()
+    };
+    crate::fold_tys_and_consts(
+        ty,
+        |it, _| match it {
+            Either::Left(ty) => Either::Left(ty),
+            Either::Right(c) => {
+                let expr = match &c.data(Interner).value {
+                    ConstValue::Concrete(chalk_ir::ConcreteConst {
+                        interned: ConstScalar::Unevaluated(expr),
+                    }) => Some(expr.clone()),
+                    _ => None,
+                };
+                Either::Right(match expr {
+                    Some(expr) => eval_const_expr(&expr, &lookup),
+                    None => c,
+                })
+            }
+        },
+        DebruijnIndex::INNERMOST,
+    )
+}
+
+fn eval_const_expr(expr: &ConstScalarOrPath, lookup: &dyn Fn(&Name) -> Option<Const>) -> Const {
+    match expr {
+        ConstScalarOrPath::Scalar(it) => intern_scalar_const(it.clone(), TyBuilder::usize()),
+        ConstScalarOrPath::Path(name) => lookup(name).unwrap_or_else(|| usize_const(None)),
+        ConstScalarOrPath::BinOp(lhs, op, rhs) => {
+            eval_usize_binop(*op, eval_const_expr(lhs, lookup), eval_const_expr(rhs, lookup))
+        }
+    }
+}
+
 pub(crate) fn const_eval_recover(
     _: &dyn HirDatabase,
     _: &[String],
@@ -441,13 +963,65 @@ pub(crate) fn const_eval_query(
             owner: const_id.into(),
             exprs: &body.exprs,
             pats: &body.pats,
+            labels: &body.labels,
             local_data: HashMap::default(),
             infer,
+            steps: 0,
+            substitute_statics: false,
         },
     );
     result
 }
 
+/// Evaluates `expr` of the body of `owner` as if it was a constant expression, with paths
+/// to immutable statics evaluating to their initializer. Locals of the body are unknown.
+pub fn eval_expr_in_body(
+    db: &dyn HirDatabase,
+    owner: DefWithBodyId,
+    expr: ExprId,
+) -> Result<ComputedExpr, ConstEvalError> {
+    let body = db.body(owner);
+    let infer = &db.infer(owner);
+    eval_const(
+        expr,
+        &mut ConstEvalCtx {
+            db,
+            owner,
+            exprs: &body.exprs,
+            pats: &body.pats,
+            labels: &body.labels,
+            local_data: HashMap::default(),
+            infer,
+            steps: 0,
+            substitute_statics: true,
+        },
+    )
+}
+
+fn eval_static(db: &dyn HirDatabase, static_id: StaticId) -> Result<ComputedExpr, ConstEvalError> {
+    if db.static_data(static_id).mutable {
+        return Err(ConstEvalError::NotSupported("mutable static"));
+    }
+    let def = static_id.into();
+    let body = db.body(def);
+    let infer = &db.infer(def);
+    // Statics can't refer to each other in constant expressions, which also rules out cycles.
+    eval_const(
+        body.body_expr,
+        &mut ConstEvalCtx {
+            db,
+            owner: def,
+            exprs: &body.exprs,
+            pats: &body.pats,
+            labels: &body.labels,
+            local_data: HashMap::default(),
+            infer,
+            steps: 0,
+            substitute_statics: false,
+        },
+    )
+}
+
 pub(crate) fn eval_to_const<'a>(
     expr: Idx<Expr>,
     mode: ParamLoweringMode,
@@ -468,8 +1042,11 @@ pub(crate) fn eval_to_const<'a>(
         owner: ctx.owner,
         exprs: &body.exprs,
         pats: &body.pats,
+        labels: &body.labels,
         local_data: HashMap::default(),
         infer: &ctx.result,
+        steps: 0,
+        substitute_statics: false,
     };
     usize_const(eval_usize(expr, ctx))
 }
@@ -74,6 +74,131 @@ fn locals() {
     );
 }
 
+#[test]
+fn loops() {
+    check_number(
+        r#"
+    const GOAL: u32 = {
+        let mut n = 0;
+        let mut i = 0;
+        while i < 10 {
+            i += 1;
+            if i % 2 == 0 {
+                continue;
+            }
+            n += i;
+        }
+        n
+    };
+    "#,
+        25,
+    );
+    check_number(
+        r#"
+    const GOAL: usize = {
+        let mut x = 1;
+        loop {
+            x *= 3;
+            if x > 100 {
+                break x - 100;
+            }
+        }
+    };
+    "#,
+        143,
+    );
+    check_number(
+        r#"
+    const GOAL: i32 = {
+        let mut sum = 0;
+        'outer: for i in 1..=5 {
+            for j in 0..i {
+                if j == 3 {
+                    continue 'outer;
+                }
+                sum += j;
+            }
+        }
+        sum
+    };
+    "#,
+        10,
+    );
+    check_fail(
+        r#"
+    const GOAL: i32 = {
+        loop {}
+    };
+    "#,
+        ConstEvalError::ExecutionLimitExceeded,
+    );
+}
+
+#[test]
+fn arrays_and_slices() {
+    check_number(
+        r#"
+    //- minicore: slice
+    const GOAL: u8 = {
+        let mut arr = [1, 2, 3, 4];
+        arr[2] = 10;
+        let mut pairs = [(0, 0); 2];
+        pairs[1].0 = 5;
+        let slice: &[u8] = &arr[1..];
+        slice[1] + slice.len() as u8 + pairs[1].0 + pairs[0].1
+    };
+    "#,
+        18,
+    );
+    check_fail(
+        r#"
+    const GOAL: u8 = {
+        let arr = [1, 2];
+        arr[2]
+    };
+    "#,
+        ConstEvalError::Panic("index out of bounds: the len is 2 but the index is 2".to_string()),
+    );
+    check_fail(
+        r#"
+    //- minicore: slice
+    const GOAL: u8 = {
+        let arr = [1, 2, 3];
+        let slice: &[u8] = &arr[-1..2];
+        slice.len() as u8
+    };
+    "#,
+        ConstEvalError::Panic("range -1..2 out of range for slice of length 3".to_string()),
+    );
+}
+
+#[test]
+fn intrinsics() {
+    check_number(
+        r#"
+    //- minicore: size_of
+    use core::mem::{align_of, size_of};
+    struct Foo { a: u8, b: u32, c: (u16, u8) }
+    const GOAL: usize = size_of::<Foo>() * 100 + align_of::<Foo>() * 10 + size_of::<&[u8]>();
+    "#,
+        1256,
+    );
+    check_number(
+        r#"
+    //- minicore: ord
+    const GOAL: i32 = core::cmp::max(3, core::cmp::min(10, 7)) - 1;
+    "#,
+        6,
+    );
+    check_fail(
+        r#"
+    fn not_an_intrinsic() -> usize { 1 }
+    const GOAL: usize = not_an_intrinsic();
+    "#,
+        ConstEvalError::NotSupported("call of a non-intrinsic function"),
+    );
+}
+
 #[test]
 fn consts() {
     check_number(
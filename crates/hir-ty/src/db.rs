@@ -7,14 +7,17 @@ use arrayvec::ArrayVec;
 use base_db::{impl_intern_key, salsa, CrateId, Upcast};
 use hir_def::{
     db::DefDatabase, expr::ExprId, BlockId, ConstId, ConstParamId, DefWithBodyId, FunctionId,
-    GenericDefId, ImplId, LifetimeParamId, LocalFieldId, TypeOrConstParamId, VariantId,
+    GenericDefId, ImplId, LifetimeParamId, LocalFieldId, TypeAliasId, TypeOrConstParamId,
+    VariantId,
 };
 use la_arena::ArenaMap;
 
 use crate::{
     chalk_db,
     consteval::{ComputedExpr, ConstEvalError},
+    layout::{Layout, LayoutError, TargetDataLayout},
     method_resolution::{InherentImpls, TraitImpls, TyFingerprint},
+    mir::{MirBody, MirLowerError},
     Binders, CallableDefId, FnDefId, GenericArg, ImplTraitId, InferenceResult, Interner, PolyFnSig,
     QuantifiedWhereClause, ReturnTypeImplTraits, TraitRef, Ty, TyDefId, ValueTyDefId,
 };
@@ -47,6 +50,16 @@ pub trait HirDatabase: DefDatabase + Upcast<dyn DefDatabase> {
     #[salsa::cycle(crate::consteval::const_eval_recover)]
     fn const_eval(&self, def: ConstId) -> Result<ComputedExpr, ConstEvalError>;
 
+    #[salsa::invoke(crate::layout::layout_of_query)]
+    #[salsa::cycle(crate::layout::layout_of_recover)]
+    fn layout_of(&self, ty: Ty, krate: CrateId) -> Result<Arc<Layout>, LayoutError>;
+
+    #[salsa::invoke(crate::layout::target_data_layout_query)]
+    fn target_data_layout(&self, krate: CrateId) -> Option<Arc<TargetDataLayout>>;
+
+    #[salsa::invoke(crate::mir::mir_body_query)]
+    fn mir_body(&self, def: DefWithBodyId) -> Result<Arc<MirBody>, MirLowerError>;
+
     #[salsa::invoke(crate::lower::impl_trait_query)]
     fn impl_trait(&self, def: ImplId) -> Option<Binders<TraitRef>>;
 
@@ -62,6 +75,12 @@ pub trait HirDatabase: DefDatabase + Upcast<dyn DefDatabase> {
         def: FunctionId,
     ) -> Option<Arc<Binders<ReturnTypeImplTraits>>>;
 
+    #[salsa::invoke(crate::lower::type_alias_impl_traits)]
+    fn type_alias_impl_traits(
+        &self,
+        def: TypeAliasId,
+    ) -> Option<Arc<Binders<ReturnTypeImplTraits>>>;
+
     #[salsa::invoke(crate::lower::generic_predicates_for_param_query)]
     #[salsa::cycle(crate::lower::generic_predicates_for_param_recover)]
     fn generic_predicates_for_param(
@@ -137,10 +156,11 @@ pub trait HirDatabase: DefDatabase + Upcast<dyn DefDatabase> {
     #[salsa::invoke(chalk_db::fn_def_datum_query)]
     fn fn_def_datum(&self, krate: CrateId, fn_def_id: FnDefId) -> Arc<chalk_db::FnDefDatum>;
 
-    #[salsa::invoke(chalk_db::fn_def_variance_query)]
+    #[salsa::invoke(crate::variance::fn_def_variance_query)]
     fn fn_def_variance(&self, fn_def_id: FnDefId) -> chalk_db::Variances;
 
-    #[salsa::invoke(chalk_db::adt_variance_query)]
+    #[salsa::invoke(crate::variance::adt_variance_query)]
+    #[salsa::cycle(crate::variance::adt_variance_recover)]
     fn adt_variance(&self, adt_id: chalk_db::AdtId) -> chalk_db::Variances;
 
     #[salsa::invoke(chalk_db::associated_ty_value_query)]
@@ -165,12 +185,26 @@ pub trait HirDatabase: DefDatabase + Upcast<dyn DefDatabase> {
         goal: crate::Canonical<crate::InEnvironment<crate::Goal>>,
     ) -> Option<crate::Solution>;
 
+    #[salsa::invoke(crate::traits::trait_solve_stats_query)]
+    fn trait_solve_stats(&self, krate: CrateId) -> crate::traits::TraitSolveStats;
+
+    #[salsa::input]
+    fn trait_solver_config(&self) -> crate::traits::TraitSolverConfig;
+
     #[salsa::invoke(chalk_db::program_clauses_for_chalk_env_query)]
     fn program_clauses_for_chalk_env(
         &self,
         krate: CrateId,
         env: chalk_ir::Environment<Interner>,
     ) -> chalk_ir::ProgramClauses<Interner>;
+
+    /// The clauses implied by `FromEnv(Self: Trait<..>)`, for all instantiations of the trait.
+    #[salsa::invoke(chalk_db::program_clauses_for_trait_query)]
+    fn program_clauses_for_trait(
+        &self,
+        krate: CrateId,
+        trait_id: chalk_db::TraitId,
+    ) -> chalk_ir::ProgramClauses<Interner>;
 }
 
 fn infer_wait(db: &dyn HirDatabase, def: DefWithBodyId) -> Arc<InferenceResult> {
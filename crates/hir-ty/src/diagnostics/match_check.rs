@@ -10,14 +10,19 @@ mod pat_util;
 pub(crate) mod deconstruct_pat;
 pub(crate) mod usefulness;
 
-use hir_def::{body::Body, expr::PatId, EnumVariantId, LocalFieldId, VariantId};
+use hir_def::{
+    body::Body,
+    expr::{ExprId, PatId, RangeOp},
+    EnumVariantId, LocalFieldId, VariantId,
+};
 use stdx::{always, never};
 
 use crate::{
-    db::HirDatabase, infer::BindingMode, InferenceResult, Interner, Substitution, Ty, TyKind,
+    db::HirDatabase, infer::BindingMode, InferenceResult, Interner, Scalar, Substitution, Ty,
+    TyKind,
 };
 
-use self::pat_util::EnumerateAndAdjustIterator;
+use self::{deconstruct_pat::IntRange, pat_util::EnumerateAndAdjustIterator};
 
 pub(crate) use self::usefulness::MatchArm;
 
@@ -28,6 +33,7 @@ pub(crate) enum PatternError {
     UnresolvedVariant,
     MissingField,
     ExtraFields,
+    MalformedRange,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -71,11 +77,38 @@ pub(crate) enum PatKind {
         subpattern: Pat,
     },
 
-    // FIXME: for now, only bool literals are implemented
+    /// A `bool` literal.
     LiteralBool {
         value: bool,
     },
 
+    /// An integer or `char` literal, e.g. `5`, `-1` or `'a'`. The value is stored as the bits of
+    /// the literal in the type of the pattern.
+    LiteralInt {
+        value: u128,
+    },
+
+    /// A string literal. This is the pattern for the `str` behind the reference.
+    LiteralStr {
+        value: Box<str>,
+    },
+
+    /// An integer or `char` range, e.g. `1..=5` or `'a'..`. The bounds are stored like in
+    /// [`PatKind::LiteralInt`]; a missing bound is replaced with the bound of the type.
+    Range {
+        lo: u128,
+        hi: u128,
+        end: RangeOp,
+    },
+
+    /// Matches against a slice or an array, e.g. `[x, .., y]`. `slice` is the pattern for the
+    /// rest, if there is one.
+    Slice {
+        prefix: Vec<Pat>,
+        slice: Option<Pat>,
+        suffix: Vec<Pat>,
+    },
+
     /// An or-pattern, e.g. `p | q`.
     /// Invariant: `pats.len() >= 2`.
     Or {
@@ -118,7 +151,31 @@ impl<'a> PatCtxt<'a> {
         let kind = match self.body[pat] {
             hir_def::expr::Pat::Wild => PatKind::Wild,
 
-            hir_def::expr::Pat::Lit(expr) => self.lower_lit(expr),
+            hir_def::expr::Pat::Lit(expr) => self.lower_lit(expr, ty),
+
+            hir_def::expr::Pat::Range { start, end, range_type } => {
+                self.lower_range(ty, start, end, range_type)
+            }
+
+            hir_def::expr::Pat::Slice { ref prefix, slice, ref suffix } => {
+                match ty.kind(Interner) {
+                    TyKind::Array(..) | TyKind::Slice(..) => PatKind::Slice {
+                        prefix: self.lower_patterns(prefix),
+                        // The rest can only be `..` or `name @ ..`, neither of which constrains the
+                        // matched elements.
+                        slice: slice.map(|slice| Pat {
+                            ty: self.infer[slice].clone(),
+                            kind: Box::new(PatKind::Wild),
+                        }),
+                        suffix: self.lower_patterns(suffix),
+                    },
+                    _ => {
+                        never!("unexpected type for slice pattern: {:?}", ty);
+                        self.errors.push(PatternError::UnexpectedType);
+                        PatKind::Wild
+                    }
+                }
+            }
 
             hir_def::expr::Pat::Path(ref path) => {
                 return self.lower_path(pat, path);
@@ -269,17 +326,89 @@ impl<'a> PatCtxt<'a> {
         }
     }
 
-    fn lower_lit(&mut self, expr: hir_def::expr::ExprId) -> PatKind {
-        use hir_def::expr::{Expr, Literal::Bool};
+    fn lower_lit(&mut self, expr: ExprId, ty: &Ty) -> PatKind {
+        use hir_def::expr::{Expr, Literal};
+
+        match (&self.body[expr], ty.kind(Interner)) {
+            (&Expr::Literal(Literal::Bool(value)), _) => PatKind::LiteralBool { value },
+            (Expr::Literal(Literal::String(value)), TyKind::Ref(.., inner))
+                if matches!(inner.kind(Interner), TyKind::Str) =>
+            {
+                let subpattern = Pat {
+                    ty: inner.clone(),
+                    kind: Box::new(PatKind::LiteralStr { value: value.clone() }),
+                };
+                PatKind::Deref { subpattern }
+            }
+            (_, &TyKind::Scalar(scalar)) => match self.lower_int_lit(expr, scalar) {
+                Some(value) => PatKind::LiteralInt { value },
+                None => {
+                    self.errors.push(PatternError::Unimplemented);
+                    PatKind::Wild
+                }
+            },
+            _ => {
+                self.errors.push(PatternError::Unimplemented);
+                PatKind::Wild
+            }
+        }
+    }
 
-        match self.body[expr] {
-            Expr::Literal(Bool(value)) => PatKind::LiteralBool { value },
+    fn lower_range(
+        &mut self,
+        ty: &Ty,
+        start: Option<ExprId>,
+        end: Option<ExprId>,
+        range_type: RangeOp,
+    ) -> PatKind {
+        let scalar = match ty.kind(Interner) {
+            &TyKind::Scalar(scalar @ (Scalar::Char | Scalar::Int(_) | Scalar::Uint(_))) => scalar,
+            _ => {
+                self.errors.push(PatternError::Unimplemented);
+                return PatKind::Wild;
+            }
+        };
+        let (min, max) = IntRange::type_bounds(scalar);
+        let lo = match start {
+            Some(start) => self.lower_int_lit(start, scalar),
+            None => Some(min),
+        };
+        // `lo..` includes the maximum value of the type.
+        let (hi, end) = match end {
+            Some(end) => (self.lower_int_lit(end, scalar), range_type),
+            None => (Some(max), RangeOp::Inclusive),
+        };
+        match (lo, hi) {
+            (Some(lo), Some(hi)) if IntRange::is_valid_range(lo, hi, scalar, end) => {
+                PatKind::Range { lo, hi, end }
+            }
+            (Some(_), Some(_)) => {
+                self.errors.push(PatternError::MalformedRange);
+                PatKind::Wild
+            }
+            // FIXME: evaluate paths to constants
             _ => {
                 self.errors.push(PatternError::Unimplemented);
                 PatKind::Wild
             }
         }
     }
+
+    /// Returns the bits of an integer or `char` literal of type `scalar`.
+    fn lower_int_lit(&self, expr: ExprId, scalar: Scalar) -> Option<u128> {
+        use hir_def::expr::{Expr, Literal};
+
+        match (&self.body[expr], scalar) {
+            (&Expr::Literal(Literal::Char(c)), Scalar::Char) => Some(c as u128),
+            (&Expr::Literal(Literal::Int(value, _)), Scalar::Int(_) | Scalar::Uint(_)) => {
+                Some(IntRange::truncate(value as u128, scalar))
+            }
+            (&Expr::Literal(Literal::Uint(value, _)), Scalar::Int(_) | Scalar::Uint(_)) => {
+                Some(IntRange::truncate(value, scalar))
+            }
+            _ => None,
+        }
+    }
 }
 
 pub(crate) trait PatternFoldable: Sized {
@@ -372,6 +501,14 @@ impl PatternFoldable for PatKind {
                 PatKind::Deref { subpattern: subpattern.fold_with(folder) }
             }
             &PatKind::LiteralBool { value } => PatKind::LiteralBool { value },
+            &PatKind::LiteralInt { value } => PatKind::LiteralInt { value },
+            PatKind::LiteralStr { value } => PatKind::LiteralStr { value: value.clone() },
+            &PatKind::Range { lo, hi, end } => PatKind::Range { lo, hi, end },
+            PatKind::Slice { prefix, slice, suffix } => PatKind::Slice {
+                prefix: prefix.fold_with(folder),
+                slice: slice.fold_with(folder),
+                suffix: suffix.fold_with(folder),
+            },
             PatKind::Or { pats } => PatKind::Or { pats: pats.fold_with(folder) },
         }
     }
@@ -48,19 +48,22 @@ use std::{
     ops::RangeInclusive,
 };
 
-use hir_def::{EnumVariantId, HasModule, LocalFieldId, VariantId};
+use chalk_ir::{ConstValue, IntTy, UintTy};
+use hir_def::{
+    expr::RangeOp, type_ref::ConstScalar, EnumVariantId, HasModule, LocalFieldId, VariantId,
+};
 use smallvec::{smallvec, SmallVec};
 use stdx::never;
 use syntax::SmolStr;
 
-use crate::{infer::normalize, AdtId, Interner, Scalar, Ty, TyExt, TyKind};
+use crate::{infer::normalize, AdtId, ConcreteConst, Const, Interner, Scalar, Ty, TyExt, TyKind};
 
 use super::{
     usefulness::{helper::Captures, MatchCheckCtx, PatCtxt},
     Pat, PatKind,
 };
 
-use self::Constructor::*;
+use self::{Constructor::*, SliceKind::*};
 
 /// Recursively expand this pattern into its subpatterns. Only useful for or-patterns.
 fn expand_or_pat(pat: &Pat) -> Vec<&Pat> {
@@ -116,6 +119,59 @@ impl IntRange {
         (*self.range.start(), *self.range.end())
     }
 
+    /// The size in bits of an integral type.
+    fn size_in_bits(scalar_ty: Scalar) -> u32 {
+        match scalar_ty {
+            Scalar::Bool | Scalar::Int(IntTy::I8) | Scalar::Uint(UintTy::U8) => 8,
+            Scalar::Int(IntTy::I16) | Scalar::Uint(UintTy::U16) => 16,
+            Scalar::Char | Scalar::Int(IntTy::I32) | Scalar::Uint(UintTy::U32) => 32,
+            // FIXME: use the pointer width of the target. As `isize` and `usize` are never matched
+            // exhaustively, this only needs to be consistent.
+            Scalar::Int(IntTy::I64 | IntTy::Isize) | Scalar::Uint(UintTy::U64 | UintTy::Usize) => {
+                64
+            }
+            Scalar::Int(IntTy::I128) | Scalar::Uint(UintTy::U128) => 128,
+            Scalar::Float(_) => {
+                never!("float type is not integral");
+                128
+            }
+        }
+    }
+
+    /// Truncates `bits` to the size of `scalar_ty`, e.g. to turn the bits of an `i128` into the
+    /// bits of an `i8` with the same value.
+    pub(super) fn truncate(bits: u128, scalar_ty: Scalar) -> u128 {
+        bits & (u128::MAX >> (128 - Self::size_in_bits(scalar_ty)))
+    }
+
+    /// The bits of the minimum and maximum values of `scalar_ty`.
+    pub(super) fn type_bounds(scalar_ty: Scalar) -> (u128, u128) {
+        match scalar_ty {
+            Scalar::Char => (0, char::MAX as u128),
+            _ => {
+                let bias = Self::signed_bias(scalar_ty);
+                (bias, Self::truncate(u128::MAX, scalar_ty) ^ bias)
+            }
+        }
+    }
+
+    /// Whether `lo..hi` (or `lo..=hi`) is a non-empty range of values of `scalar_ty`.
+    pub(super) fn is_valid_range(lo: u128, hi: u128, scalar_ty: Scalar, end: RangeOp) -> bool {
+        let bias = Self::signed_bias(scalar_ty);
+        let (lo, hi) = (lo ^ bias, hi ^ bias);
+        lo < hi || (lo == hi && end == RangeOp::Inclusive)
+    }
+
+    /// The value to flip the sign bit with, so that signed values are encoded in the same order
+    /// as unsigned ones.
+    #[inline]
+    fn signed_bias(scalar_ty: Scalar) -> u128 {
+        match scalar_ty {
+            Scalar::Int(_) => 1u128 << (Self::size_in_bits(scalar_ty) - 1),
+            _ => 0,
+        }
+    }
+
     #[inline]
     fn from_bool(value: bool) -> IntRange {
         let val = value as u128;
@@ -123,11 +179,24 @@ impl IntRange {
     }
 
     #[inline]
-    fn from_range(lo: u128, hi: u128, scalar_ty: Scalar) -> IntRange {
-        match scalar_ty {
-            Scalar::Bool => IntRange { range: lo..=hi },
-            _ => unimplemented!(),
+    fn from_bits(bits: u128, scalar_ty: Scalar) -> IntRange {
+        let val = bits ^ Self::signed_bias(scalar_ty);
+        IntRange { range: val..=val }
+    }
+
+    #[inline]
+    fn from_range(lo: u128, hi: u128, scalar_ty: Scalar, end: RangeOp) -> IntRange {
+        // Perform a shift if the underlying types are signed, which makes the interval arithmetic
+        // simpler.
+        let bias = Self::signed_bias(scalar_ty);
+        let (lo, hi) = (lo ^ bias, hi ^ bias);
+        let offset = (end == RangeOp::Exclusive) as u128;
+        if lo > hi || (lo == hi && end == RangeOp::Exclusive) {
+            // This is checked when lowering the pattern.
+            never!("malformed range pattern: {}..={}", lo, hi.wrapping_sub(offset));
+            return IntRange { range: lo..=lo };
         }
+        IntRange { range: lo..=(hi - offset) }
     }
 
     fn is_subrange(&self, other: &Self) -> bool {
@@ -252,20 +321,198 @@ impl SplitIntRange {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum SliceKind {
+    /// Patterns of length `n` (`[x, y]`).
+    FixedLen(usize),
+    /// Patterns using the `..` notation (`[x, .., y]`).
+    /// Captures any array constructor of `length >= i + j`.
+    /// In the case where `array_len` is `Some(_)`,
+    /// this indicates that we only care about the first `i` and the last `j` values of the array,
+    /// and everything in between is a wildcard `_`.
+    VarLen(usize, usize),
+}
+
+impl SliceKind {
+    fn arity(self) -> usize {
+        match self {
+            FixedLen(length) => length,
+            VarLen(prefix, suffix) => prefix + suffix,
+        }
+    }
+
+    /// Whether this pattern includes patterns of length `other_len`.
+    fn covers_length(self, other_len: usize) -> bool {
+        match self {
+            FixedLen(len) => len == other_len,
+            VarLen(prefix, suffix) => prefix + suffix <= other_len,
+        }
+    }
+}
+
 /// A constructor for array and slice patterns.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub(super) struct Slice {
-    _unimplemented: Void,
+    /// `None` if the matched value is a slice, `Some(n)` if it is an array of size `n`.
+    array_len: Option<usize>,
+    /// The kind of pattern it is: fixed-length `[x, y]` or variable length `[x, .., y]`.
+    kind: SliceKind,
 }
 
 impl Slice {
+    fn new(array_len: Option<usize>, kind: SliceKind) -> Self {
+        let kind = match (array_len, kind) {
+            // If the middle `..` is empty, we effectively have a fixed-length pattern.
+            (Some(len), VarLen(prefix, suffix)) if prefix + suffix >= len => FixedLen(len),
+            _ => kind,
+        };
+        Slice { array_len, kind }
+    }
+
     fn arity(self) -> usize {
-        unimplemented!()
+        self.kind.arity()
     }
 
     /// See `Constructor::is_covered_by`
-    fn is_covered_by(self, _other: Self) -> bool {
-        unimplemented!() // never called as Slice contains Void
+    fn is_covered_by(self, other: Self) -> bool {
+        other.kind.covers_length(self.arity())
+    }
+}
+
+/// This computes constructor splitting for variable-length slices, as explained at the top of the
+/// file.
+///
+/// A slice pattern `[x, .., y]` behaves like the infinite or-pattern `[x, y] | [x, _, y] | [x, _,
+/// _, y] | ...`. The corresponding value constructors are fixed-length array constructors above a
+/// given minimum length. We obviously can't list this infinitude of constructors. Thankfully,
+/// it turns out that for each finite set of slice patterns, all sufficiently large array lengths
+/// are equivalent.
+///
+/// Let's look at an example, where we are trying to split the last pattern:
+/// ```
+/// # fn foo(x: &[bool]) {
+/// match x {
+///     [true, true, ..] => {}
+///     [.., false, false] => {}
+///     [..] => {}
+/// }
+/// # }
+/// ```
+/// Here are the results of specialization for the first few lengths:
+/// ```
+/// # fn foo(x: &[bool]) { match x {
+/// // length 0
+/// [] => {}
+/// // length 1
+/// [_] => {}
+/// // length 2
+/// [true, true] => {}
+/// [false, false] => {}
+/// [_, _] => {}
+/// // length 3
+/// [true, true,  _    ] => {}
+/// [_,    false, false] => {}
+/// [_,    _,     _    ] => {}
+/// // length 4
+/// [true, true, _,     _    ] => {}
+/// [_,    _,    false, false] => {}
+/// [_,    _,    _,     _    ] => {}
+/// // length 5
+/// [true, true, _, _,     _    ] => {}
+/// [_,    _,    _, false, false] => {}
+/// [_,    _,    _, _,     _    ] => {}
+/// # _ => {}
+/// # }}
+/// ```
+///
+/// If we went above length 5, we would simply be inserting more columns full of wildcards in the
+/// middle. This means that the set of witnesses for length `l >= 5` if equivalent to the set for
+/// any other `l' >= 5`: simply add or remove wildcards in the middle to convert between them.
+///
+/// This applies to any set of slice patterns: there will be a length `L` above which all lengths
+/// behave the same. This is exactly what we need for constructor splitting. Therefore a
+/// variable-length slice can be split into a variable-length slice of minimal length `L`, and many
+/// fixed-length slices of lengths `< L`.
+///
+/// For each variable-length pattern `p` with a prefix of length `pl` and suffix of length `sl`,
+/// only the first `pl` and the last `sl` elements are examined. Therefore, as long as `L` is
+/// positive (to avoid concerns about empty types), all elements after the maximum prefix length
+/// and before the maximum suffix length are not examined by any variable-length pattern, and
+/// therefore can be added/removed without affecting them - creating equivalent patterns from any
+/// sufficiently-large length.
+///
+/// Of course, if fixed-length patterns exist, we must be sure that our length is large enough to
+/// miss them all, so we can pick `L = max(max(FIXED_LEN)+1, max(PREFIX_LEN) + max(SUFFIX_LEN))`
+///
+/// `max_slice` below will be made to have arity `L`.
+#[derive(Debug)]
+struct SplitVarLenSlice {
+    /// If the type is an array, this is its size.
+    array_len: Option<usize>,
+    /// The arity of the input slice.
+    arity: usize,
+    /// The smallest slice bigger than any slice seen. `max_slice.arity()` is the length `L`
+    /// described above.
+    max_slice: SliceKind,
+}
+
+impl SplitVarLenSlice {
+    fn new(prefix: usize, suffix: usize, array_len: Option<usize>) -> Self {
+        SplitVarLenSlice { array_len, arity: prefix + suffix, max_slice: VarLen(prefix, suffix) }
+    }
+
+    /// Pass a set of slices relative to which to split this one.
+    fn split(&mut self, slices: impl Iterator<Item = SliceKind>) {
+        let (max_prefix_len, max_suffix_len) = match &mut self.max_slice {
+            VarLen(prefix, suffix) => (prefix, suffix),
+            FixedLen(_) => return, // No need to split
+        };
+        // We grow `self.max_slice` to be larger than all slices encountered, as described above.
+        // For diagnostics, we keep the prefix and suffix lengths separate, but grow them so that
+        // `L = max_prefix_len + max_suffix_len`.
+        let mut max_fixed_len = 0;
+        for slice in slices {
+            match slice {
+                FixedLen(len) => {
+                    max_fixed_len = max(max_fixed_len, len);
+                }
+                VarLen(prefix, suffix) => {
+                    *max_prefix_len = max(*max_prefix_len, prefix);
+                    *max_suffix_len = max(*max_suffix_len, suffix);
+                }
+            }
+        }
+        // We want `L = max(L, max_fixed_len + 1)`, modulo the fact that we keep prefix and
+        // suffix separate.
+        if max_fixed_len + 1 >= *max_prefix_len + *max_suffix_len {
+            // The subtraction can't overflow thanks to the above check.
+            // The new `max_prefix_len` is larger than its previous value.
+            *max_prefix_len = max_fixed_len + 1 - *max_suffix_len;
+        }
+
+        // We cap the arity of `max_slice` at the array size.
+        match self.array_len {
+            Some(len) if self.max_slice.arity() >= len => self.max_slice = FixedLen(len),
+            _ => {}
+        }
+    }
+
+    /// Iterate over the partition of this slice.
+    fn iter(&self) -> impl Iterator<Item = Slice> + '_ {
+        let smaller_lengths = match self.array_len {
+            // The only admissible fixed-length slice is one of the array size. Whether `max_slice`
+            // is fixed-length or variable-length, it will be the only relevant slice to output
+            // here.
+            Some(_) => 0..0, // empty range
+            // We cover all arities in the range `(self.arity..infinity)`. We split that range into
+            // two: lengths smaller than `max_slice.arity()` are treated independently as
+            // fixed-lengths slices, and lengths above are captured by `max_slice`.
+            None => self.arity..self.max_slice.arity(),
+        };
+        smaller_lengths
+            .map(FixedLen)
+            .chain(once(self.max_slice))
+            .map(move |kind| Slice::new(self.array_len, kind))
     }
 }
 
@@ -289,7 +536,7 @@ pub(super) enum Constructor {
     /// Ranges of floating-point literal values (`2.0..=5.2`).
     FloatRange(Void),
     /// String literals. Strings are not quite the same as `&[u8]` so we treat them separately.
-    Str(Void),
+    Str(Box<str>),
     /// Array and slice patterns.
     Slice(Slice),
     /// Constants that must not be matched structurally. They are treated as black
@@ -424,7 +671,12 @@ impl Constructor {
                 split_range.split(int_ranges.cloned());
                 split_range.iter().map(IntRange).collect()
             }
-            Slice(_) => unimplemented!(),
+            &Slice(Slice { kind: VarLen(self_prefix, self_suffix), array_len }) => {
+                let mut split_self = SplitVarLenSlice::new(self_prefix, self_suffix, array_len);
+                let slices = ctors.filter_map(|c| c.as_slice()).map(|s| s.kind);
+                split_self.split(slices);
+                split_self.iter().map(Slice).collect()
+            }
             // Any other constructor can be used unchanged.
             _ => smallvec![self.clone()],
         }
@@ -450,9 +702,7 @@ impl Constructor {
             (FloatRange(..), FloatRange(..)) => {
                 unimplemented!()
             }
-            (Str(..), Str(..)) => {
-                unimplemented!()
-            }
+            (Str(self_val), Str(other_val)) => self_val == other_val,
             (Slice(self_slice), Slice(other_slice)) => self_slice.is_covered_by(*other_slice),
 
             // We are trying to inspect an opaque constant. Thus we skip the row.
@@ -525,7 +775,9 @@ pub(super) struct SplitWildcard {
 impl SplitWildcard {
     pub(super) fn new(pcx: PatCtxt<'_, '_>) -> Self {
         let cx = pcx.cx;
-        let make_range = |start, end, scalar| IntRange(IntRange::from_range(start, end, scalar));
+        let make_range = |start, end, scalar| {
+            IntRange(IntRange::from_range(start, end, scalar, RangeOp::Inclusive))
+        };
 
         // Unhandled types are treated as non-exhaustive. Being explicit here instead of falling
         // to catchall arm to ease further implementation.
@@ -541,8 +793,19 @@ impl SplitWildcard {
         // `cx.is_uninhabited()`).
         let all_ctors = match pcx.ty.kind(Interner) {
             TyKind::Scalar(Scalar::Bool) => smallvec![make_range(0, 1, Scalar::Bool)],
-            // TyKind::Array(..) if ... => unhandled(),
-            TyKind::Array(..) | TyKind::Slice(..) => unhandled(),
+            TyKind::Array(sub_ty, len) if array_len(len).is_some() => {
+                let len = array_len(len).unwrap();
+                if len != 0 && cx.is_uninhabited(sub_ty) {
+                    SmallVec::new()
+                } else {
+                    smallvec![Slice(Slice::new(Some(len), VarLen(0, 0)))]
+                }
+            }
+            // Treat arrays of a constant but unknown length like slices.
+            TyKind::Array(sub_ty, _) | TyKind::Slice(sub_ty) => {
+                let kind = if cx.is_uninhabited(sub_ty) { FixedLen(0) } else { VarLen(0, 0) };
+                smallvec![Slice(Slice::new(None, kind))]
+            }
             &TyKind::Adt(AdtId(hir_def::AdtId::EnumId(enum_id)), ..) => {
                 let enum_data = cx.db.enum_data(enum_id);
 
@@ -592,8 +855,20 @@ impl SplitWildcard {
                 }
                 ctors
             }
-            TyKind::Scalar(Scalar::Char) => unhandled(),
-            TyKind::Scalar(Scalar::Int(..) | Scalar::Uint(..)) => unhandled(),
+            TyKind::Scalar(Scalar::Char) => smallvec![
+                // The valid Unicode Scalar Value ranges.
+                make_range('\u{0000}' as u128, '\u{D7FF}' as u128, Scalar::Char),
+                make_range('\u{E000}' as u128, '\u{10FFFF}' as u128, Scalar::Char),
+            ],
+            TyKind::Scalar(Scalar::Int(IntTy::Isize) | Scalar::Uint(UintTy::Usize)) => {
+                // `usize`/`isize` are not allowed to be matched exhaustively unless the
+                // `precise_pointer_size_matching` feature is enabled.
+                unhandled()
+            }
+            &TyKind::Scalar(scalar @ (Scalar::Int(_) | Scalar::Uint(_))) => {
+                let (min, max) = IntRange::type_bounds(scalar);
+                smallvec![make_range(min, max, scalar)]
+            }
             TyKind::Never if !cx.feature_exhaustive_patterns() && !pcx.is_top_level => {
                 smallvec![NonExhaustive]
             }
@@ -799,9 +1074,16 @@ impl<'p> Fields<'p> {
                     Fields::wildcards_from_tys(cx, once(ty.clone()))
                 }
             },
-            Slice(..) => {
-                unimplemented!()
-            }
+            Slice(slice) => match ty.kind(Interner) {
+                TyKind::Slice(ty) | TyKind::Array(ty, _) => {
+                    let arity = slice.arity();
+                    Fields::wildcards_from_tys(cx, (0..arity).map(|_| ty.clone()))
+                }
+                _ => {
+                    never!("bad slice pattern {:?} {:?}", constructor, ty);
+                    Fields::empty()
+                }
+            },
             Str(..)
             | FloatRange(..)
             | IntRange(..)
@@ -954,6 +1236,43 @@ impl<'p> DeconstructedPat<'p> {
                 ctor = IntRange(IntRange::from_bool(value));
                 fields = Fields::empty();
             }
+            &PatKind::LiteralInt { value } => {
+                ctor = match pat.ty.kind(Interner) {
+                    &TyKind::Scalar(scalar) => IntRange(IntRange::from_bits(value, scalar)),
+                    _ => {
+                        never!("integer literal pattern has unexpected type: {:?}", &pat.ty);
+                        Wildcard
+                    }
+                };
+                fields = Fields::empty();
+            }
+            &PatKind::Range { lo, hi, end } => {
+                ctor = match pat.ty.kind(Interner) {
+                    &TyKind::Scalar(scalar) => IntRange(IntRange::from_range(lo, hi, scalar, end)),
+                    _ => {
+                        never!("range pattern has unexpected type: {:?}", &pat.ty);
+                        Wildcard
+                    }
+                };
+                fields = Fields::empty();
+            }
+            PatKind::LiteralStr { value } => {
+                ctor = Str(value.clone());
+                fields = Fields::empty();
+            }
+            PatKind::Slice { prefix, slice, suffix } => {
+                let array_len = match pat.ty.kind(Interner) {
+                    TyKind::Array(_, len) => array_len(len),
+                    _ => None,
+                };
+                let kind = if slice.is_some() {
+                    VarLen(prefix.len(), suffix.len())
+                } else {
+                    FixedLen(prefix.len() + suffix.len())
+                };
+                ctor = Slice(Slice::new(array_len, kind));
+                fields = Fields::from_iter(cx, prefix.iter().chain(suffix).map(mkpat));
+            }
             PatKind::Or { .. } => {
                 ctor = Or;
                 let pats: SmallVec<[_; 2]> = expand_or_pat(pat).into_iter().map(mkpat).collect();
@@ -999,7 +1318,31 @@ impl<'p> DeconstructedPat<'p> {
             (Slice(self_slice), Slice(other_slice))
                 if self_slice.arity() != other_slice.arity() =>
             {
-                unimplemented!()
+                // The only non-trivial case: two slices of different arity. `other_slice` is
+                // guaranteed to have a larger arity, so we fill the middle part with enough
+                // wildcards to reach the length of the new, larger slice.
+                match self_slice.kind {
+                    FixedLen(_) => {
+                        never!("{:?} doesn't cover {:?}", self_slice, other_slice);
+                        self.fields.iter_patterns().collect()
+                    }
+                    VarLen(prefix, suffix) => {
+                        let inner_ty = match self.ty.kind(Interner) {
+                            TyKind::Slice(ty) | TyKind::Array(ty, _) => ty.clone(),
+                            _ => {
+                                never!("bad slice pattern {:?} {:?}", self.ctor, self.ty);
+                                return self.fields.iter_patterns().collect();
+                            }
+                        };
+                        let prefix = &self.fields.fields[..prefix];
+                        let suffix = &self.fields.fields[self_slice.arity() - suffix..];
+                        let wildcard: &_ =
+                            cx.pattern_arena.alloc(DeconstructedPat::wildcard(inner_ty));
+                        let extra_wildcards = other_slice.arity() - self_slice.arity();
+                        let extra_wildcards = (0..extra_wildcards).map(|_| wildcard);
+                        prefix.iter().chain(extra_wildcards).chain(suffix).collect()
+                    }
+                }
             }
             _ => self.fields.iter_patterns().collect(),
         }
@@ -1024,6 +1367,16 @@ fn is_field_list_non_exhaustive(variant_id: VariantId, cx: &MatchCheckCtx<'_, '_
     cx.db.attrs(attr_def_id).by_key("non_exhaustive").exists()
 }
 
+/// The length of an array type, if it is known.
+fn array_len(len: &Const) -> Option<usize> {
+    match &len.data(Interner).value {
+        ConstValue::Concrete(ConcreteConst { interned: ConstScalar::Usize(len) }) => {
+            Some(*len as usize)
+        }
+        _ => None,
+    }
+}
+
 fn adt_is_box(adt: hir_def::AdtId, cx: &MatchCheckCtx<'_, '_>) -> bool {
     use hir_def::lang_item::LangItemTarget;
     match cx.db.lang_item(cx.module.krate(), SmolStr::new_inline("owned_box")) {
@@ -287,20 +287,37 @@ impl HirDisplay for ProjectionTy {
             return write!(f, "{}", TYPE_HINT_TRUNCATION);
         }
 
-        let trait_ = f.db.trait_data(self.trait_(f.db));
+        let trait_ref = self.trait_ref(f.db);
+        let trait_ = f.db.trait_data(trait_ref.hir_trait_id());
         write!(f, "<")?;
-        self.self_type_parameter(Interner).hir_fmt(f)?;
+        trait_ref.self_type_parameter(Interner).hir_fmt(f)?;
         write!(f, " as {}", trait_.name)?;
-        if self.substitution.len(Interner) > 1 {
+        if trait_ref.substitution.len(Interner) > 1 {
             write!(f, "<")?;
-            f.write_joined(&self.substitution.as_slice(Interner)[1..], ", ")?;
+            f.write_joined(&trait_ref.substitution.as_slice(Interner)[1..], ", ")?;
             write!(f, ">")?;
         }
         write!(f, ">::{}", f.db.type_alias_data(from_assoc_type_id(self.associated_ty_id)).name)?;
-        Ok(())
+        write_projection_own_args(self, f)
     }
 }
 
+/// Writes the generic arguments of a generic associated type, e.g. the `<'a, T>` in
+/// `<X as Trait>::Assoc<'a, T>`.
+fn write_projection_own_args(
+    projection_ty: &ProjectionTy,
+    f: &mut HirFormatter,
+) -> Result<(), HirDisplayError> {
+    let own_params = projection_ty.substitution.len(Interner)
+        - projection_ty.trait_ref(f.db).substitution.len(Interner);
+    if own_params > 0 {
+        write!(f, "<")?;
+        f.write_joined(&projection_ty.substitution.as_slice(Interner)[..own_params], ", ")?;
+        write!(f, ">")?;
+    }
+    Ok(())
+}
+
 impl HirDisplay for OpaqueTy {
     fn hir_fmt(&self, f: &mut HirFormatter) -> Result<(), HirDisplayError> {
         if f.should_truncate() {
@@ -324,11 +341,11 @@ impl HirDisplay for GenericArg {
 impl HirDisplay for Const {
     fn hir_fmt(&self, f: &mut HirFormatter) -> Result<(), HirDisplayError> {
         let data = self.interned();
-        match data.value {
+        match &data.value {
             ConstValue::BoundVar(idx) => idx.hir_fmt(f),
             ConstValue::InferenceVar(..) => write!(f, "#c#"),
             ConstValue::Placeholder(idx) => {
-                let id = from_placeholder_idx(f.db, idx);
+                let id = from_placeholder_idx(f.db, *idx);
                 let generics = generics(f.db.upcast(), id.parent);
                 let param_data = &generics.params.type_or_consts[id.local_id];
                 write!(f, "{}", param_data.name().unwrap())
@@ -414,21 +431,16 @@ impl HirDisplay for Ty {
                     | TyKind::OpaqueType(opaque_ty_id, parameters) => {
                         let impl_trait_id =
                             f.db.lookup_intern_impl_trait_id((*opaque_ty_id).into());
-                        if let ImplTraitId::ReturnTypeImplTrait(func, idx) = impl_trait_id {
-                            let datas =
-                                f.db.return_type_impl_traits(func)
-                                    .expect("impl trait id without data");
-                            let data = (*datas)
-                                .as_ref()
-                                .map(|rpit| rpit.impl_traits[idx as usize].bounds.clone());
+                        if let Some((datas, idx)) = impl_trait_id.impl_traits(f.db) {
+                            let data =
+                                (*datas).as_ref().map(|rpit| rpit.impl_traits[idx].bounds.clone());
                             let bounds = data.substitute(Interner, parameters);
                             let mut len = bounds.skip_binders().len();
 
                             // Don't count Sized but count when it absent
                             // (i.e. when explicit ?Sized bound is set).
-                            let default_sized = SizedByDefault::Sized {
-                                anchor: func.lookup(f.db.upcast()).module(f.db.upcast()).krate(),
-                            };
+                            let default_sized =
+                                SizedByDefault::Sized { anchor: impl_trait_id.krate(f.db) };
                             let sized_bounds = bounds
                                 .skip_binders()
                                 .iter()
@@ -565,8 +577,9 @@ impl HirDisplay for Ty {
                                     {
                                         return true;
                                     }
-                                    if let Some(ConstValue::Concrete(c)) =
-                                        parameter.constant(Interner).map(|x| x.data(Interner).value)
+                                    if let Some(ConstValue::Concrete(c)) = parameter
+                                        .constant(Interner)
+                                        .map(|x| &x.data(Interner).value)
                                     {
                                         if c.interned == ConstScalar::Unknown {
                                             return true;
@@ -653,14 +666,13 @@ impl HirDisplay for Ty {
             TyKind::OpaqueType(opaque_ty_id, parameters) => {
                 let impl_trait_id = f.db.lookup_intern_impl_trait_id((*opaque_ty_id).into());
                 match impl_trait_id {
-                    ImplTraitId::ReturnTypeImplTrait(func, idx) => {
-                        let datas =
-                            f.db.return_type_impl_traits(func).expect("impl trait id without data");
-                        let data = (*datas)
-                            .as_ref()
-                            .map(|rpit| rpit.impl_traits[idx as usize].bounds.clone());
+                    ImplTraitId::ReturnTypeImplTrait(..) | ImplTraitId::TypeAliasImplTrait(..) => {
+                        let (datas, idx) =
+                            impl_trait_id.impl_traits(f.db).expect("impl trait id without data");
+                        let data =
+                            (*datas).as_ref().map(|rpit| rpit.impl_traits[idx].bounds.clone());
                         let bounds = data.substitute(Interner, &parameters);
-                        let krate = func.lookup(f.db.upcast()).module(f.db.upcast()).krate();
+                        let krate = impl_trait_id.krate(f.db);
                         write_bounds_like_dyn_trait_with_prefix(
                             "impl",
                             bounds.skip_binders(),
@@ -711,21 +723,24 @@ impl HirDisplay for Ty {
                         }
                         TypeParamProvenance::ArgumentImplTrait => {
                             let substs = generics.placeholder_subst(f.db);
-                            let bounds =
-                                f.db.generic_predicates(id.parent)
-                                    .iter()
-                                    .map(|pred| pred.clone().substitute(Interner, &substs))
-                                    .filter(|wc| match &wc.skip_binders() {
-                                        WhereClause::Implemented(tr) => {
-                                            &tr.self_type_parameter(Interner) == self
-                                        }
-                                        WhereClause::AliasEq(AliasEq {
-                                            alias: AliasTy::Projection(proj),
-                                            ty: _,
-                                        }) => &proj.self_type_parameter(Interner) == self,
-                                        _ => false,
-                                    })
-                                    .collect::<Vec<_>>();
+                            let bounds = f
+                                .db
+                                .generic_predicates(id.parent)
+                                .iter()
+                                .map(|pred| pred.clone().substitute(Interner, &substs))
+                                .filter(|wc| match &wc.skip_binders() {
+                                    WhereClause::Implemented(tr) => {
+                                        &tr.self_type_parameter(Interner) == self
+                                    }
+                                    WhereClause::AliasEq(AliasEq {
+                                        alias: AliasTy::Projection(proj),
+                                        ty: _,
+                                    }) => {
+                                        &proj.trait_ref(f.db).self_type_parameter(Interner) == self
+                                    }
+                                    _ => false,
+                                })
+                                .collect::<Vec<_>>();
                             let krate = id.parent.module(f.db.upcast()).krate();
                             write_bounds_like_dyn_trait_with_prefix(
                                 "impl",
@@ -753,14 +768,13 @@ impl HirDisplay for Ty {
             TyKind::Alias(AliasTy::Opaque(opaque_ty)) => {
                 let impl_trait_id = f.db.lookup_intern_impl_trait_id(opaque_ty.opaque_ty_id.into());
                 match impl_trait_id {
-                    ImplTraitId::ReturnTypeImplTrait(func, idx) => {
-                        let datas =
-                            f.db.return_type_impl_traits(func).expect("impl trait id without data");
-                        let data = (*datas)
-                            .as_ref()
-                            .map(|rpit| rpit.impl_traits[idx as usize].bounds.clone());
+                    ImplTraitId::ReturnTypeImplTrait(..) | ImplTraitId::TypeAliasImplTrait(..) => {
+                        let (datas, idx) =
+                            impl_trait_id.impl_traits(f.db).expect("impl trait id without data");
+                        let data =
+                            (*datas).as_ref().map(|rpit| rpit.impl_traits[idx].bounds.clone());
                         let bounds = data.substitute(Interner, &opaque_ty.substitution);
-                        let krate = func.lookup(f.db.upcast()).module(f.db.upcast()).krate();
+                        let krate = impl_trait_id.krate(f.db);
                         write_bounds_like_dyn_trait_with_prefix(
                             "impl",
                             bounds.skip_binders(),
@@ -991,9 +1005,11 @@ impl HirDisplay for WhereClause {
                 fmt_trait_ref(&projection_ty.trait_ref(f.db), f, true)?;
                 write!(
                     f,
-                    ">::{} = ",
+                    ">::{}",
                     f.db.type_alias_data(from_assoc_type_id(projection_ty.associated_ty_id)).name,
                 )?;
+                write_projection_own_args(projection_ty, f)?;
+                write!(f, " = ")?;
                 ty.hir_fmt(f)?;
             }
             WhereClause::AliasEq(_) => write!(f, "{{error}}")?,
@@ -1204,7 +1220,7 @@ impl HirDisplay for Path {
                 anchor.hir_fmt(f)?;
                 write!(f, ">")?;
             }
-            (_, PathKind::Plain) => {}
+            (_, PathKind::Plain | PathKind::DefSite(_)) => {}
             (_, PathKind::Abs) => {}
             (_, PathKind::Crate) => write!(f, "crate")?,
             (_, PathKind::Super(0)) => write!(f, "self")?,
@@ -1231,7 +1247,7 @@ impl HirDisplay for Path {
         }
 
         for (seg_idx, segment) in self.segments().iter().enumerate() {
-            if !matches!(self.kind(), PathKind::Plain) || seg_idx > 0 {
+            if !self.kind().is_plain() || seg_idx > 0 {
                 write!(f, "::")?;
             }
             write!(f, "{}", segment.name)?;
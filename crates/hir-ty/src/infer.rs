@@ -13,6 +13,7 @@
 //! to certain types. To record this, we use the union-find implementation from
 //! the `ena` crate, which is extracted from rustc.
 
+use std::iter;
 use std::ops::Index;
 use std::sync::Arc;
 
@@ -49,6 +50,8 @@ pub use coerce::could_coerce;
 #[allow(unreachable_pub)]
 pub use unify::could_unify;
 
+pub use closure::{CaptureKind, CapturedItem, HirPlace, PlaceProjection};
+
 pub(crate) mod unify;
 mod path;
 mod expr;
@@ -70,6 +73,7 @@ pub(crate) fn infer_query(db: &dyn HirDatabase, def: DefWithBodyId) -> Arc<Infer
     }
 
     ctx.infer_body();
+    ctx.infer_closure_captures();
 
     Arc::new(ctx.resolve_all())
 }
@@ -300,6 +304,8 @@ pub struct InferenceResult {
     pub pat_adjustments: FxHashMap<PatId, Vec<Ty>>,
     pub pat_binding_modes: FxHashMap<PatId, BindingMode>,
     pub expr_adjustments: FxHashMap<ExprId, Vec<Adjustment>>,
+    /// For each closure expression, the places it captures from its environment.
+    closure_captures: FxHashMap<ExprId, Vec<CapturedItem>>,
 }
 
 impl InferenceResult {
@@ -339,6 +345,9 @@ impl InferenceResult {
             _ => None,
         })
     }
+    pub fn closure_captures(&self, closure: ExprId) -> &[CapturedItem] {
+        self.closure_captures.get(&closure).map(|it| it.as_slice()).unwrap_or(&[])
+    }
 }
 
 impl Index<ExprId> for InferenceResult {
@@ -447,6 +456,9 @@ impl<'a> InferenceContext<'a> {
         for adjustment in result.pat_adjustments.values_mut().flatten() {
             *adjustment = table.resolve_completely(adjustment.clone());
         }
+        for capture in result.closure_captures.values_mut().flatten() {
+            capture.ty = table.resolve_completely(capture.ty.clone());
+        }
         result
     }
 
@@ -466,6 +478,7 @@ impl<'a> InferenceContext<'a> {
             data.params.iter().map(|(_, type_ref)| ctx.lower_ty(type_ref)).collect::<Vec<_>>();
         for (ty, pat) in param_tys.into_iter().zip(self.body.params.iter()) {
             let ty = self.insert_type_vars(ty);
+            let ty = self.insert_inference_vars_for_tait(ty);
             let ty = self.normalize_associated_types_in(ty);
 
             self.infer_pat(*pat, &ty, BindingMode::default());
@@ -491,7 +504,7 @@ impl<'a> InferenceContext<'a> {
                     };
                     let idx = match self.db.lookup_intern_impl_trait_id(opaque_ty_id.into()) {
                         ImplTraitId::ReturnTypeImplTrait(_, idx) => idx,
-                        _ => unreachable!(),
+                        _ => return ty,
                     };
                     let bounds = (*rpits).map_ref(|rpits| {
                         rpits.impl_traits[idx as usize].bounds.map_ref(|it| it.into_iter())
@@ -556,9 +569,59 @@ impl<'a> InferenceContext<'a> {
             .with_impl_trait_mode(impl_trait_mode);
         let ty = ctx.lower_ty(type_ref);
         let ty = self.insert_type_vars(ty);
+        let ty = self.insert_inference_vars_for_tait(ty);
         self.normalize_associated_types_in(ty)
     }
 
+    /// Replaces the opaque types of `type Alias = impl Trait;` aliases whose defining scope
+    /// contains this body with inference variables bounded by the alias' bounds, so that the
+    /// body's uses can define the hidden type.
+    fn insert_inference_vars_for_tait(&mut self, ty: Ty) -> Ty {
+        let owner_module = self.owner.module(self.db.upcast());
+        fold_tys(
+            ty,
+            |ty, _| {
+                let (opaque_ty_id, parameters) = match ty.kind(Interner) {
+                    TyKind::OpaqueType(opaque_ty_id, parameters) => {
+                        (*opaque_ty_id, parameters.clone())
+                    }
+                    _ => return ty,
+                };
+                let (alias, idx) = match self.db.lookup_intern_impl_trait_id(opaque_ty_id.into()) {
+                    ImplTraitId::TypeAliasImplTrait(alias, idx) => (alias, idx),
+                    _ => return ty,
+                };
+                // The defining scope of a type alias `impl Trait` is the module the alias is
+                // declared in, including its submodules.
+                let alias_module = alias.lookup(self.db.upcast()).module(self.db.upcast());
+                let in_defining_scope =
+                    iter::successors(Some(owner_module), |m| m.containing_module(self.db.upcast()))
+                        .any(|m| m == alias_module);
+                if !in_defining_scope {
+                    return ty;
+                }
+                let taits = match self.db.type_alias_impl_traits(alias) {
+                    Some(it) => it,
+                    None => return ty,
+                };
+                let bounds = (*taits).map_ref(|taits| {
+                    taits.impl_traits[idx as usize].bounds.map_ref(|it| it.into_iter())
+                });
+                let var = self.table.new_type_var();
+                let var_subst = Substitution::from1(Interner, var.clone());
+                for bound in bounds {
+                    let predicate = bound.map(|it| it.cloned()).substitute(Interner, &parameters);
+                    let (var_predicate, binders) =
+                        predicate.substitute(Interner, &var_subst).into_value_and_skipped_binders();
+                    always!(binders.len(Interner) == 0); // quantified where clauses not yet handled
+                    self.push_obligation(var_predicate.cast(Interner));
+                }
+                var
+            },
+            DebruijnIndex::INNERMOST,
+        )
+    }
+
     fn make_ty(&mut self, type_ref: &TypeRef) -> Ty {
         self.make_ty_with_mode(type_ref, ImplTraitLoweringMode::Disallowed)
     }
@@ -570,9 +633,10 @@ impl<'a> InferenceContext<'a> {
     /// Replaces ConstScalar::Unknown by a new type var, so we can maybe still infer it.
     fn insert_const_vars_shallow(&mut self, c: Const) -> Const {
         let data = c.data(Interner);
-        match data.value {
+        match &data.value {
             ConstValue::Concrete(cc) => match cc.interned {
-                hir_def::type_ref::ConstScalar::Usize(_) => c,
+                hir_def::type_ref::ConstScalar::Usize(_)
+                | hir_def::type_ref::ConstScalar::Unevaluated(_) => c,
                 hir_def::type_ref::ConstScalar::Unknown => {
                     self.table.new_const_var(data.ty.clone())
                 }
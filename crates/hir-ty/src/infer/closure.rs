@@ -1,15 +1,68 @@
-//! Inference of closure parameter types based on the closure's expected type.
+//! Inference of closure parameter types based on the closure's expected type, and analysis of
+//! the places a closure captures from its environment (RFC 2229).
 
-use chalk_ir::{cast::Cast, AliasEq, AliasTy, FnSubst, WhereClause};
-use hir_def::{expr::ExprId, HasModule};
+use chalk_ir::{cast::Cast, AliasEq, AliasTy, FnSubst, Mutability, WhereClause};
+use hir_def::{
+    expr::{BinaryOp, CaptureBy, Expr, ExprId, Pat, PatId, Statement, UnaryOp},
+    resolver::{resolver_for_expr, ValueNs},
+    type_ref::Mutability as HirMutability,
+    FieldId, HasModule,
+};
+use rustc_hash::FxHashSet;
 use smallvec::SmallVec;
+use syntax::SmolStr;
 
 use crate::{
-    to_chalk_trait_id, utils, ChalkTraitId, DynTy, FnPointer, FnSig, Interner, Substitution, Ty,
-    TyExt, TyKind,
+    to_chalk_trait_id, utils, ChalkTraitId, DynTy, FnPointer, FnSig, Goal, Interner, Substitution,
+    Ty, TyBuilder, TyExt, TyKind,
 };
 
-use super::{Expectation, InferenceContext};
+use super::{Adjust, AutoBorrow, BindingMode, Expectation, InferenceContext, OverloadedDeref};
+
+/// A place rooted at a local variable, e.g. `a.b.0` or `*a.b`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HirPlace {
+    pub local: PatId,
+    pub projections: Vec<PlaceProjection>,
+}
+
+impl HirPlace {
+    /// Whether `self` is `other` or one of the places `other` is a projection of.
+    fn is_ancestor_or_equal(&self, other: &HirPlace) -> bool {
+        self.local == other.local && other.projections.starts_with(&self.projections)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlaceProjection {
+    /// A builtin dereference of a reference or raw pointer.
+    Deref,
+    Field(FieldId),
+    TupleField(usize),
+}
+
+/// How a place is captured by a closure. Ordered from the least to the most restrictive kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CaptureKind {
+    SharedRef,
+    MutableRef,
+    ByValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedItem {
+    pub place: HirPlace,
+    pub kind: CaptureKind,
+    /// The type of the captured place, i.e. not including the reference for by-ref captures.
+    pub ty: Ty,
+}
+
+struct CaptureCtx {
+    capture_by: CaptureBy,
+    /// Locals bound inside of the closure, which therefore aren't captured.
+    locals: FxHashSet<PatId>,
+    captures: Vec<CapturedItem>,
+}
 
 impl InferenceContext<'_> {
     pub(super) fn deduce_closure_type_from_expectations(
@@ -80,3 +133,332 @@ impl InferenceContext<'_> {
         None
     }
 }
+
+impl InferenceContext<'_> {
+    /// Computes the captures of all closures in the body, see [`CapturedItem`].
+    pub(super) fn infer_closure_captures(&mut self) {
+        self.table.resolve_obligations_as_possible();
+        let closures: Vec<_> = self
+            .body
+            .exprs
+            .iter()
+            .filter(|(_, expr)| matches!(expr, Expr::Closure { .. }))
+            .map(|(id, _)| id)
+            .collect();
+        for closure in closures {
+            let captures = self.closure_captures(closure);
+            self.result.closure_captures.insert(closure, captures);
+        }
+    }
+
+    fn closure_captures(&mut self, closure: ExprId) -> Vec<CapturedItem> {
+        let (args, body, capture_by) = match &self.body[closure] {
+            Expr::Closure { args, body, capture_by, .. } => (args, *body, *capture_by),
+            _ => return Vec::new(),
+        };
+        let mut locals = FxHashSet::default();
+        args.iter().for_each(|&pat| self.collect_bindings(pat, &mut locals));
+        self.collect_locals_in_expr(body, &mut locals);
+
+        let mut ctx = CaptureCtx { capture_by, locals, captures: Vec::new() };
+        self.walk_expr_for_captures(body, &mut ctx);
+
+        // Merge captures of places that are projections of other captured places into their
+        // ancestor, keeping the most restrictive capture kind.
+        let mut captures: Vec<(usize, CapturedItem)> =
+            ctx.captures.into_iter().enumerate().collect();
+        captures.sort_by_key(|(_, it)| it.place.projections.len());
+        let mut merged: Vec<(usize, CapturedItem)> = Vec::new();
+        for (idx, capture) in captures {
+            match merged.iter_mut().find(|(_, it)| it.place.is_ancestor_or_equal(&capture.place)) {
+                Some((first_idx, ancestor)) => {
+                    *first_idx = (*first_idx).min(idx);
+                    ancestor.kind = ancestor.kind.max(capture.kind);
+                }
+                None => merged.push((idx, capture)),
+            }
+        }
+        merged.sort_by_key(|&(idx, _)| idx);
+        merged.into_iter().map(|(_, it)| it).collect()
+    }
+
+    fn collect_bindings(&self, pat: PatId, locals: &mut FxHashSet<PatId>) {
+        if let Pat::Bind { .. } = &self.body[pat] {
+            locals.insert(pat);
+        }
+        self.body[pat].walk_child_pats(|pat| self.collect_bindings(pat, locals));
+    }
+
+    fn collect_locals_in_expr(&self, expr: ExprId, locals: &mut FxHashSet<PatId>) {
+        match &self.body[expr] {
+            Expr::Let { pat, .. } | Expr::For { pat, .. } => self.collect_bindings(*pat, locals),
+            Expr::Block { statements, .. } => {
+                for stmt in statements.iter() {
+                    if let Statement::Let { pat, .. } = stmt {
+                        self.collect_bindings(*pat, locals);
+                    }
+                }
+            }
+            Expr::Match { arms, .. } => {
+                for arm in arms.iter() {
+                    self.collect_bindings(arm.pat, locals);
+                    if let Some(guard) = arm.guard {
+                        self.collect_locals_in_expr(guard, locals);
+                    }
+                }
+            }
+            Expr::Closure { args, .. } => {
+                args.iter().for_each(|&pat| self.collect_bindings(pat, locals));
+            }
+            _ => {}
+        }
+        self.body[expr].walk_child_exprs(|expr| self.collect_locals_in_expr(expr, locals));
+    }
+
+    fn walk_expr_for_captures(&mut self, expr: ExprId, ctx: &mut CaptureCtx) {
+        match &self.body[expr] {
+            &Expr::Ref { expr, mutability, .. } => {
+                let kind = match mutability {
+                    HirMutability::Shared => CaptureKind::SharedRef,
+                    HirMutability::Mut => CaptureKind::MutableRef,
+                };
+                self.borrow_for_capture(expr, kind, ctx);
+            }
+            &Expr::BinaryOp { lhs, rhs, op: Some(BinaryOp::Assignment { .. }) } => {
+                self.borrow_for_capture(lhs, CaptureKind::MutableRef, ctx);
+                self.consume_for_capture(rhs, ctx);
+            }
+            // Builtin derefs of captured places are handled by `place_of_expr`, so this is an
+            // overloaded deref, which only borrows its operand.
+            &Expr::UnaryOp { expr, op: UnaryOp::Deref } => {
+                self.borrow_for_capture(expr, CaptureKind::SharedRef, ctx);
+            }
+            Expr::MethodCall { receiver, args, .. } => {
+                self.consume_for_capture(*receiver, ctx);
+                args.iter().for_each(|&arg| self.consume_for_capture(arg, ctx));
+            }
+            &Expr::Let { pat, expr } => self.match_on_for_capture(expr, &[pat], ctx),
+            Expr::Match { expr, arms } => {
+                let pats: Vec<_> = arms.iter().map(|arm| arm.pat).collect();
+                self.match_on_for_capture(*expr, &pats, ctx);
+                for arm in arms.iter() {
+                    if let Some(guard) = arm.guard {
+                        self.consume_for_capture(guard, ctx);
+                    }
+                    self.consume_for_capture(arm.expr, ctx);
+                }
+            }
+            Expr::Block { statements, tail, .. } => {
+                for stmt in statements.iter() {
+                    match stmt {
+                        &Statement::Let { pat, initializer, else_branch, .. } => {
+                            if let Some(init) = initializer {
+                                self.match_on_for_capture(init, &[pat], ctx);
+                            }
+                            if let Some(else_branch) = else_branch {
+                                self.consume_for_capture(else_branch, ctx);
+                            }
+                        }
+                        &Statement::Expr { expr, .. } => self.consume_for_capture(expr, ctx),
+                    }
+                }
+                if let &Some(tail) = tail {
+                    self.consume_for_capture(tail, ctx);
+                }
+            }
+            expr => {
+                let mut children = Vec::new();
+                expr.walk_child_exprs(|child| children.push(child));
+                children.into_iter().for_each(|child| self.consume_for_capture(child, ctx));
+            }
+        }
+    }
+
+    /// Records the use of `expr` by value, taking its adjustments into account.
+    fn consume_for_capture(&mut self, expr: ExprId, ctx: &mut CaptureCtx) {
+        let adjustments = self.result.expr_adjustments.get(&expr).cloned().unwrap_or_default();
+        // Autoref and reborrows only borrow the (dereferenced) place.
+        let borrow = adjustments.iter().find_map(|adj| match adj.kind {
+            Adjust::Borrow(AutoBorrow::Ref(m) | AutoBorrow::RawPtr(m)) => Some(m),
+            Adjust::Deref(Some(OverloadedDeref(m))) => Some(m),
+            _ => None,
+        });
+        if let Some(m) = borrow {
+            let kind = match m {
+                Mutability::Not => CaptureKind::SharedRef,
+                Mutability::Mut => CaptureKind::MutableRef,
+            };
+            match self.place_of_expr(expr, ctx) {
+                Some(mut place) => {
+                    for adj in &adjustments {
+                        match adj.kind {
+                            Adjust::Deref(None) => place.projections.push(PlaceProjection::Deref),
+                            _ => break,
+                        }
+                    }
+                    self.record_capture(place, kind, ctx);
+                }
+                None => self.walk_expr_for_captures(expr, ctx),
+            }
+            return;
+        }
+        match self.place_of_expr(expr, ctx) {
+            Some(place) => {
+                let ty = self.place_ty(&place);
+                let kind =
+                    if self.is_copy(&ty) { CaptureKind::SharedRef } else { CaptureKind::ByValue };
+                self.record_capture(place, kind, ctx);
+            }
+            None => self.walk_expr_for_captures(expr, ctx),
+        }
+    }
+
+    fn borrow_for_capture(&mut self, expr: ExprId, kind: CaptureKind, ctx: &mut CaptureCtx) {
+        match self.place_of_expr(expr, ctx) {
+            Some(place) => self.record_capture(place, kind, ctx),
+            None => self.walk_expr_for_captures(expr, ctx),
+        }
+    }
+
+    /// Records the use of `scrutinee` as matched against `pats`, which depends on the binding
+    /// modes of the patterns.
+    fn match_on_for_capture(&mut self, scrutinee: ExprId, pats: &[PatId], ctx: &mut CaptureCtx) {
+        // A place that is only matched against `_` isn't read, so it isn't captured either.
+        if pats.iter().all(|&pat| matches!(self.body[pat], Pat::Wild)) {
+            if self.place_of_expr(scrutinee, ctx).is_none() {
+                self.walk_expr_for_captures(scrutinee, ctx);
+            }
+            return;
+        }
+        let mut kind = None;
+        for &pat in pats {
+            self.pat_capture_kind(pat, &mut kind);
+        }
+        match kind {
+            Some(CaptureKind::ByValue) => self.consume_for_capture(scrutinee, ctx),
+            // Matching on a place without moving out of it only borrows it.
+            kind => self.borrow_for_capture(scrutinee, kind.unwrap_or(CaptureKind::SharedRef), ctx),
+        }
+    }
+
+    fn pat_capture_kind(&mut self, pat: PatId, kind: &mut Option<CaptureKind>) {
+        if let Pat::Bind { .. } = &self.body[pat] {
+            let pat_kind = match self.result.pat_binding_modes.get(&pat) {
+                Some(BindingMode::Ref(Mutability::Mut)) => CaptureKind::MutableRef,
+                Some(BindingMode::Ref(Mutability::Not)) => CaptureKind::SharedRef,
+                Some(BindingMode::Move) | None => {
+                    let ty = self.result.type_of_pat.get(pat).cloned();
+                    match ty {
+                        Some(ty) if !self.is_copy(&ty) => CaptureKind::ByValue,
+                        _ => CaptureKind::SharedRef,
+                    }
+                }
+            };
+            *kind = Some(kind.map_or(pat_kind, |kind| kind.max(pat_kind)));
+        }
+        let mut children = Vec::new();
+        self.body[pat].walk_child_pats(|pat| children.push(pat));
+        children.into_iter().for_each(|pat| self.pat_capture_kind(pat, kind));
+    }
+
+    fn record_capture(&mut self, mut place: HirPlace, mut kind: CaptureKind, ctx: &mut CaptureCtx) {
+        if ctx.capture_by == CaptureBy::Value {
+            kind = CaptureKind::ByValue;
+        }
+        if kind == CaptureKind::ByValue {
+            // Moving out of a dereference is not possible, so the pointer itself is captured.
+            if let Some(idx) = place.projections.iter().position(|it| *it == PlaceProjection::Deref)
+            {
+                place.projections.truncate(idx);
+            }
+        }
+        let ty = self.place_ty(&place);
+        ctx.captures.push(CapturedItem { place, kind, ty });
+    }
+
+    /// Returns the captured place `expr` refers to, if any.
+    fn place_of_expr(&mut self, expr: ExprId, ctx: &CaptureCtx) -> Option<HirPlace> {
+        match &self.body[expr] {
+            Expr::Path(p) => {
+                let resolver = resolver_for_expr(self.db.upcast(), self.owner, expr);
+                match resolver.resolve_path_in_value_ns_fully(self.db.upcast(), p.mod_path())? {
+                    ValueNs::LocalBinding(pat) if !ctx.locals.contains(&pat) => {
+                        Some(HirPlace { local: pat, projections: Vec::new() })
+                    }
+                    _ => None,
+                }
+            }
+            &Expr::Field { expr: base, ref name } => {
+                let projection = match self.result.field_resolution(expr) {
+                    Some(field) => PlaceProjection::Field(field),
+                    None => PlaceProjection::TupleField(name.as_tuple_index()?),
+                };
+                let mut place = self.place_of_expr_adjusted(base, ctx)?;
+                place.projections.push(projection);
+                Some(place)
+            }
+            &Expr::UnaryOp { expr: base, op: UnaryOp::Deref } => {
+                let base_ty = self.table.resolve_ty_shallow(&self.result[base]);
+                base_ty.as_reference_or_ptr()?;
+                let mut place = self.place_of_expr_adjusted(base, ctx)?;
+                place.projections.push(PlaceProjection::Deref);
+                Some(place)
+            }
+            _ => None,
+        }
+    }
+
+    /// Like `place_of_expr`, but also applies builtin autoderefs of `expr`.
+    fn place_of_expr_adjusted(&mut self, expr: ExprId, ctx: &CaptureCtx) -> Option<HirPlace> {
+        let mut place = self.place_of_expr(expr, ctx)?;
+        if let Some(adjustments) = self.result.expr_adjustments.get(&expr) {
+            for adj in adjustments {
+                match adj.kind {
+                    Adjust::Deref(None) => place.projections.push(PlaceProjection::Deref),
+                    _ => return None,
+                }
+            }
+        }
+        Some(place)
+    }
+
+    fn place_ty(&mut self, place: &HirPlace) -> Ty {
+        let mut ty = self.result[place.local].clone();
+        for projection in &place.projections {
+            ty = self.table.resolve_ty_shallow(&ty);
+            let projected = match projection {
+                PlaceProjection::Deref => ty.as_reference_or_ptr().map(|(ty, ..)| ty.clone()),
+                PlaceProjection::Field(field) => match ty.kind(Interner) {
+                    TyKind::Adt(_, subst) => Some(
+                        self.db.field_types(field.parent)[field.local_id]
+                            .clone()
+                            .substitute(Interner, subst),
+                    ),
+                    _ => None,
+                },
+                PlaceProjection::TupleField(idx) => ty
+                    .as_tuple()
+                    .and_then(|subst| subst.as_slice(Interner).get(*idx))
+                    .and_then(|arg| arg.ty(Interner))
+                    .cloned(),
+            };
+            ty = projected.unwrap_or_else(|| self.err_ty());
+        }
+        ty
+    }
+
+    fn is_copy(&mut self, ty: &Ty) -> bool {
+        let krate = self.resolver.krate();
+        let copy_trait = match self
+            .db
+            .lang_item(krate, SmolStr::new_inline("copy"))
+            .and_then(|it| it.as_trait())
+        {
+            Some(it) => it,
+            None => return false,
+        };
+        let goal: Goal =
+            TyBuilder::trait_ref(self.db, copy_trait).push(ty.clone()).build().cast(Interner);
+        self.table.try_obligation(goal).is_some()
+    }
+}
@@ -218,7 +218,7 @@ impl<'a> InferenceContext<'a> {
                 self.diverges = Diverges::Maybe;
                 TyBuilder::unit()
             }
-            Expr::Closure { body, args, ret_type, arg_types } => {
+            Expr::Closure { body, args, ret_type, arg_types, .. } => {
                 assert_eq!(args.len(), arg_types.len());
 
                 let mut sig_tys = Vec::new();
@@ -957,6 +957,7 @@ impl<'a> InferenceContext<'a> {
             VisibleFromModule::Filter(self.resolver.module()),
             method_name,
         );
+        let func = resolved.as_ref().map(|&(_, func)| func);
         let (receiver_ty, method_ty, substs) = match resolved {
             Some((adjust, func)) => {
                 let (ty, adjustments) = adjust.apply(&mut self.table, receiver_ty);
@@ -996,9 +997,36 @@ impl<'a> InferenceContext<'a> {
             self.expected_inputs_for_expected_output(expected, ret_ty.clone(), param_tys.clone());
 
         self.check_call_arguments(tgt_expr, args, &expected_inputs, &param_tys, &[], is_varargs);
+        let ret_ty = match func {
+            Some(func) => self.refine_trait_method_ret_ty(func, &substs).unwrap_or(ret_ty),
+            None => ret_ty,
+        };
         self.normalize_associated_types_in(ret_ty)
     }
 
+    /// If `func` is a trait method returning `impl Trait` (including `async fn`s) and the impl it
+    /// dispatches to is known, returns the return type of the impl method instead, which may be
+    /// more precise than the opaque type of the trait.
+    fn refine_trait_method_ret_ty(
+        &mut self,
+        func: FunctionId,
+        substs: &Substitution,
+    ) -> Option<Ty> {
+        if !matches!(func.lookup(self.db.upcast()).container, ItemContainerId::TraitId(_)) {
+            return None;
+        }
+        self.db.return_type_impl_traits(func)?;
+        let substs = self.table.resolve_completely(substs.clone());
+        if substs.iter(Interner).any(|arg| arg.ty(Interner).map_or(false, |ty| ty.is_unknown())) {
+            return None;
+        }
+        let (impl_func, impl_substs) =
+            method_resolution::lookup_impl_method(self.db, self.trait_env.clone(), func, substs)?;
+        let sig =
+            self.db.callable_item_signature(impl_func.into()).substitute(Interner, &impl_substs);
+        Some(sig.ret().clone())
+    }
+
     fn expected_inputs_for_expected_output(
         &mut self,
         expected_output: &Expectation,
@@ -265,9 +265,17 @@ impl<'a> InferenceContext<'a> {
                 .intern(Interner)
             }
             Pat::Wild => expected.clone(),
-            Pat::Range { start, end } => {
-                let start_ty = self.infer_expr(*start, &Expectation::has_type(expected.clone()));
-                self.infer_expr(*end, &Expectation::has_type(start_ty))
+            Pat::Range { start, end, .. } => {
+                let start_ty = match start {
+                    Some(start) => {
+                        self.infer_expr(*start, &Expectation::has_type(expected.clone()))
+                    }
+                    None => expected.clone(),
+                };
+                match end {
+                    Some(end) => self.infer_expr(*end, &Expectation::has_type(start_ty)),
+                    None => start_ty,
+                }
             }
             Pat::Lit(expr) => self.infer_expr(*expr, &Expectation::has_type(expected.clone())),
             Pat::Box { inner } => match self.resolve_boxed_box() {
@@ -259,6 +259,7 @@ impl chalk_ir::interner::Interner for Interner {
     ) -> bool {
         match (c1, c2) {
             (&ConstScalar::Usize(a), &ConstScalar::Usize(b)) => a == b,
+            (ConstScalar::Unevaluated(a), ConstScalar::Unevaluated(b)) => a == b,
             // we were previously assuming this to be true, I'm not whether true or false on
             // unknown values is safer.
             (_, _) => true,
@@ -0,0 +1,441 @@
+//! Computes the memory layout of types, following the rules rustc uses for the target of a crate.
+//!
+//! Only the parts of a layout that are needed by the IDE and the constant evaluator are computed:
+//! the size and alignment of a type, the offsets of its fields, and the niche enums can use to
+//! store their discriminant in.
+
+use std::{cmp::Reverse, sync::Arc};
+
+use base_db::CrateId;
+use chalk_ir::{ConcreteConst, ConstValue, FloatTy, IntTy, UintTy};
+use hir_def::{adt::ReprKind, type_ref::ConstScalar, AdtId, EnumId, EnumVariantId, VariantId};
+
+use crate::{db::HirDatabase, Interner, Scalar, Substitution, Ty, TyKind};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+/// The parts of an LLVM data layout string that are needed to lay out types. All sizes and
+/// alignments are in bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetDataLayout {
+    pub endian: Endian,
+    pub pointer_size: u64,
+    pub pointer_align: u64,
+    pub i8_align: u64,
+    pub i16_align: u64,
+    pub i32_align: u64,
+    pub i64_align: u64,
+    pub i128_align: u64,
+    pub f32_align: u64,
+    pub f64_align: u64,
+    pub aggregate_align: u64,
+}
+
+impl Default for TargetDataLayout {
+    /// The defaults LLVM (and rustc) assume for the entries missing from a data layout string.
+    fn default() -> TargetDataLayout {
+        TargetDataLayout {
+            endian: Endian::Big,
+            pointer_size: 8,
+            pointer_align: 8,
+            i8_align: 1,
+            i16_align: 2,
+            i32_align: 4,
+            i64_align: 4,
+            i128_align: 4,
+            f32_align: 4,
+            f64_align: 8,
+            aggregate_align: 1,
+        }
+    }
+}
+
+impl TargetDataLayout {
+    /// Parses a data layout string like `e-m:e-i64:64-f80:128-n8:16:32:64-S128`, as found in the
+    /// `data-layout` entry of target specifications.
+    pub fn parse(input: &str) -> Result<TargetDataLayout, String> {
+        let bits = |s: &str, kind: &str| {
+            s.parse::<u64>().map_err(|_| format!("invalid {} `{}` in data layout", kind, s))
+        };
+        let align = |s: &[&str], spec: &str| match s.first() {
+            Some(abi) => Ok((bits(abi, "alignment")? / 8).max(1)),
+            None => Err(format!("missing alignment for `{}` in data layout", spec)),
+        };
+
+        let mut dl = TargetDataLayout::default();
+        // Like rustc, the alignment of `i128` is taken from the largest integer of 64 to 128 bits
+        // the data layout mentions.
+        let mut i128_align_src = 64;
+        for spec in input.split('-') {
+            let parts = spec.split(':').collect::<Vec<_>>();
+            match &*parts {
+                ["e"] => dl.endian = Endian::Little,
+                ["E"] => dl.endian = Endian::Big,
+                ["a", rest @ ..] => dl.aggregate_align = align(rest, spec)?,
+                ["p" | "p0", size, rest @ ..] => {
+                    dl.pointer_size = bits(size, "size")? / 8;
+                    dl.pointer_align = align(rest, spec)?;
+                }
+                [kind, rest @ ..] if kind.starts_with('i') => {
+                    let size = bits(&kind[1..], "size")?;
+                    let a = align(rest, spec)?;
+                    match size {
+                        8 => dl.i8_align = a,
+                        16 => dl.i16_align = a,
+                        32 => dl.i32_align = a,
+                        64 => dl.i64_align = a,
+                        _ => {}
+                    }
+                    if (i128_align_src..=128).contains(&size) {
+                        i128_align_src = size;
+                        dl.i128_align = a;
+                    }
+                }
+                [kind, rest @ ..] if kind.starts_with('f') => {
+                    let size = bits(&kind[1..], "size")?;
+                    let a = align(rest, spec)?;
+                    match size {
+                        32 => dl.f32_align = a,
+                        64 => dl.f64_align = a,
+                        _ => {}
+                    }
+                }
+                // Mangling, native integer widths, stack alignment, vectors and other address
+                // spaces don't matter for the layout of types.
+                _ => {}
+            }
+        }
+        Ok(dl)
+    }
+
+    fn int_align(&self, size: u64) -> u64 {
+        match size {
+            1 => self.i8_align,
+            2 => self.i16_align,
+            4 => self.i32_align,
+            8 => self.i64_align,
+            _ => self.i128_align,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub size: u64,
+    pub align: u64,
+    /// The offsets of the fields of a struct, tuple or union, in declaration order.
+    pub field_offsets: Vec<u64>,
+    /// The bytes of the type that can hold values that are invalid for it.
+    pub niche: Option<Niche>,
+}
+
+impl Layout {
+    fn primitive(size: u64, align: u64) -> Layout {
+        Layout { size, align, field_offsets: Vec::new(), niche: None }
+    }
+}
+
+/// A range of bytes which, when the type is valid, never holds some of the values it could hold.
+/// Enums use these invalid values to encode their discriminant without a separate tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Niche {
+    pub offset: u64,
+    pub size: u64,
+    /// The number of invalid values.
+    pub available: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutError {
+    /// The type mentions generic parameters or inference variables.
+    HasPlaceholder,
+    HasErrorType,
+    Unsized,
+    SizeOverflow,
+    /// The type contains itself without an indirection.
+    Recursive,
+    TargetLayoutNotAvailable,
+    NotImplemented,
+}
+
+pub(crate) fn target_data_layout_query(
+    db: &dyn HirDatabase,
+    krate: CrateId,
+) -> Option<Arc<TargetDataLayout>> {
+    let target_layout = db.crate_graph()[krate].target_layout.clone()?;
+    match TargetDataLayout::parse(&target_layout) {
+        Ok(it) => Some(Arc::new(it)),
+        Err(e) => {
+            tracing::error!("failed to parse target data layout: {}", e);
+            None
+        }
+    }
+}
+
+pub(crate) fn layout_of_recover(
+    _: &dyn HirDatabase,
+    _: &[String],
+    _: &Ty,
+    _: &CrateId,
+) -> Result<Arc<Layout>, LayoutError> {
+    Err(LayoutError::Recursive)
+}
+
+pub(crate) fn layout_of_query(
+    db: &dyn HirDatabase,
+    ty: Ty,
+    krate: CrateId,
+) -> Result<Arc<Layout>, LayoutError> {
+    let dl = db.target_data_layout(krate).ok_or(LayoutError::TargetLayoutNotAvailable)?;
+    let layout = match ty.kind(Interner) {
+        TyKind::Scalar(scalar) => scalar_layout(&dl, scalar),
+        TyKind::Ref(_, _, pointee) => Layout {
+            niche: Some(Niche { offset: 0, size: dl.pointer_size, available: 1 }),
+            ..pointer_layout(&dl, pointee)
+        },
+        TyKind::Raw(_, pointee) => pointer_layout(&dl, pointee),
+        TyKind::Function(_) => Layout {
+            niche: Some(Niche { offset: 0, size: dl.pointer_size, available: 1 }),
+            ..Layout::primitive(dl.pointer_size, dl.pointer_align)
+        },
+        TyKind::FnDef(..) | TyKind::Never => Layout::primitive(0, 1),
+        TyKind::Array(elem, len) => {
+            let len = match &len.data(Interner).value {
+                ConstValue::Concrete(ConcreteConst { interned: ConstScalar::Usize(len) }) => *len,
+                ConstValue::Concrete(_) => return Err(LayoutError::HasErrorType),
+                _ => return Err(LayoutError::HasPlaceholder),
+            };
+            let elem = db.layout_of(elem.clone(), krate)?;
+            Layout {
+                size: elem.size.checked_mul(len).ok_or(LayoutError::SizeOverflow)?,
+                align: elem.align,
+                field_offsets: Vec::new(),
+                niche: if len == 0 { None } else { elem.niche },
+            }
+        }
+        TyKind::Tuple(_, subst) => {
+            let fields = subst
+                .iter(Interner)
+                .filter_map(|it| it.ty(Interner))
+                .map(|ty| db.layout_of(ty.clone(), krate))
+                .collect::<Result<Vec<_>, _>>()?;
+            univariant(&dl, &fields, false, None)?
+        }
+        TyKind::Adt(chalk_ir::AdtId(adt), subst) => match *adt {
+            AdtId::StructId(id) => {
+                let packed = is_packed(&db.struct_data(id).repr)?;
+                let fields = field_layouts(db, krate, id.into(), subst)?;
+                univariant(&dl, &fields, packed, None)?
+            }
+            AdtId::UnionId(id) => {
+                let packed = is_packed(&db.union_data(id).repr)?;
+                let fields = field_layouts(db, krate, id.into(), subst)?;
+                let size = fields.iter().map(|it| it.size).max().unwrap_or(0);
+                let align = if packed {
+                    1
+                } else {
+                    fields.iter().map(|it| it.align).fold(dl.aggregate_align, u64::max)
+                };
+                Layout {
+                    size: align_to(size, align),
+                    align,
+                    field_offsets: vec![0; fields.len()],
+                    niche: None,
+                }
+            }
+            AdtId::EnumId(id) => enum_layout(db, krate, &dl, id, subst)?,
+        },
+        TyKind::Str | TyKind::Slice(_) | TyKind::Dyn(_) | TyKind::Foreign(_) => {
+            return Err(LayoutError::Unsized)
+        }
+        TyKind::Placeholder(_) | TyKind::BoundVar(_) | TyKind::InferenceVar(..) => {
+            return Err(LayoutError::HasPlaceholder)
+        }
+        TyKind::Error => return Err(LayoutError::HasErrorType),
+        TyKind::Alias(_)
+        | TyKind::AssociatedType(..)
+        | TyKind::OpaqueType(..)
+        | TyKind::Closure(..)
+        | TyKind::Generator(..)
+        | TyKind::GeneratorWitness(..) => return Err(LayoutError::NotImplemented),
+    };
+    Ok(Arc::new(layout))
+}
+
+fn scalar_layout(dl: &TargetDataLayout, scalar: &Scalar) -> Layout {
+    let (size, align) = match scalar {
+        Scalar::Bool => {
+            return Layout {
+                niche: Some(Niche { offset: 0, size: 1, available: 254 }),
+                ..Layout::primitive(1, dl.i8_align)
+            }
+        }
+        Scalar::Char => {
+            // Everything above `char::MAX` is invalid.
+            return Layout {
+                niche: Some(Niche { offset: 0, size: 4, available: (1 << 32) - 0x110000 }),
+                ..Layout::primitive(4, dl.i32_align)
+            };
+        }
+        Scalar::Int(IntTy::Isize) | Scalar::Uint(UintTy::Usize) => {
+            (dl.pointer_size, dl.pointer_align)
+        }
+        Scalar::Int(IntTy::I8) | Scalar::Uint(UintTy::U8) => (1, dl.i8_align),
+        Scalar::Int(IntTy::I16) | Scalar::Uint(UintTy::U16) => (2, dl.i16_align),
+        Scalar::Int(IntTy::I32) | Scalar::Uint(UintTy::U32) => (4, dl.i32_align),
+        Scalar::Int(IntTy::I64) | Scalar::Uint(UintTy::U64) => (8, dl.i64_align),
+        Scalar::Int(IntTy::I128) | Scalar::Uint(UintTy::U128) => (16, dl.i128_align),
+        Scalar::Float(FloatTy::F32) => (4, dl.f32_align),
+        Scalar::Float(FloatTy::F64) => (8, dl.f64_align),
+    };
+    Layout::primitive(size, align)
+}
+
+fn pointer_layout(dl: &TargetDataLayout, pointee: &Ty) -> Layout {
+    match pointee.kind(Interner) {
+        // Pointers to unsized types carry the length or the vtable along.
+        TyKind::Str | TyKind::Slice(_) | TyKind::Dyn(_) => Layout {
+            field_offsets: vec![0, dl.pointer_size],
+            ..Layout::primitive(2 * dl.pointer_size, dl.pointer_align)
+        },
+        _ => Layout::primitive(dl.pointer_size, dl.pointer_align),
+    }
+}
+
+fn is_packed(repr: &Option<ReprKind>) -> Result<bool, LayoutError> {
+    match repr {
+        None => Ok(false),
+        Some(ReprKind::Packed) => Ok(true),
+        // FIXME: `repr(C)`, `repr(transparent)` and `repr(align)` need to be told apart first.
+        Some(ReprKind::Other) => Err(LayoutError::NotImplemented),
+    }
+}
+
+fn field_layouts(
+    db: &dyn HirDatabase,
+    krate: CrateId,
+    variant: VariantId,
+    subst: &Substitution,
+) -> Result<Vec<Arc<Layout>>, LayoutError> {
+    db.field_types(variant)
+        .iter()
+        .map(|(_, ty)| db.layout_of(ty.clone().substitute(Interner, subst), krate))
+        .collect()
+}
+
+/// Lays out the fields of a struct, a tuple or an enum variant. The fields of enum variants come
+/// after the `tag` storing the discriminant.
+fn univariant(
+    dl: &TargetDataLayout,
+    fields: &[Arc<Layout>],
+    packed: bool,
+    tag: Option<&Layout>,
+) -> Result<Layout, LayoutError> {
+    let field_align = |field: &Layout| if packed { 1 } else { field.align };
+    let mut order = (0..fields.len()).collect::<Vec<_>>();
+    if !packed {
+        // Sorting the fields by alignment avoids padding between them. Variant fields start with
+        // the least aligned ones, which can be packed right after the tag.
+        if tag.is_some() {
+            order.sort_by_key(|&idx| field_align(&fields[idx]));
+        } else {
+            order.sort_by_key(|&idx| Reverse(field_align(&fields[idx])));
+        }
+    }
+
+    let mut align = tag.map_or(dl.aggregate_align, |it| it.align.max(dl.aggregate_align));
+    let mut offset = tag.map_or(0, |it| it.size);
+    let mut field_offsets = vec![0; fields.len()];
+    let mut niche: Option<Niche> = None;
+    for idx in order {
+        let field = &fields[idx];
+        offset = align_to(offset, field_align(field));
+        field_offsets[idx] = offset;
+        if let Some(field_niche) = field.niche {
+            if niche.map_or(true, |it| field_niche.available > it.available) {
+                niche = Some(Niche { offset: offset + field_niche.offset, ..field_niche });
+            }
+        }
+        offset = offset.checked_add(field.size).ok_or(LayoutError::SizeOverflow)?;
+        align = align.max(field_align(field));
+    }
+    if packed {
+        align = 1;
+    }
+    Ok(Layout { size: align_to(offset, align), align, field_offsets, niche })
+}
+
+fn enum_layout(
+    db: &dyn HirDatabase,
+    krate: CrateId,
+    dl: &TargetDataLayout,
+    id: EnumId,
+    subst: &Substitution,
+) -> Result<Layout, LayoutError> {
+    let variants = db
+        .enum_data(id)
+        .variants
+        .iter()
+        .map(|(local_id, _)| {
+            field_layouts(db, krate, EnumVariantId { parent: id, local_id }.into(), subst)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match &*variants {
+        [] => return Ok(Layout::primitive(0, 1)),
+        [fields] => return univariant(dl, fields, false, None),
+        _ => {}
+    }
+    let variant_count = variants.len() as u128;
+
+    // If only one variant holds data, the other variants can be encoded as invalid values of it.
+    let is_zst = |fields: &[Arc<Layout>]| fields.iter().all(|it| it.size == 0);
+    let mut dataful = variants.iter().filter(|fields| !is_zst(fields));
+    if let (Some(fields), None) = (dataful.next(), dataful.next()) {
+        let layout = univariant(dl, fields, false, None)?;
+        if let Some(niche) = layout.niche {
+            if niche.available >= variant_count - 1 {
+                return Ok(Layout {
+                    field_offsets: Vec::new(),
+                    niche: Some(Niche {
+                        available: niche.available - (variant_count - 1),
+                        ..niche
+                    }),
+                    ..layout
+                });
+            }
+        }
+    }
+
+    // Otherwise the discriminant is stored in a tag in front of the fields of each variant.
+    let tag_size =
+        [1, 2, 4, 8].into_iter().find(|&size| variant_count <= 1 << (8 * size)).unwrap_or(16);
+    let tag = Layout::primitive(tag_size, dl.int_align(tag_size));
+    let mut size = tag.size;
+    let mut align = tag.align;
+    for fields in &variants {
+        let variant = univariant(dl, fields, false, Some(&tag))?;
+        size = size.max(variant.size);
+        align = align.max(variant.align);
+    }
+    let available = match tag_size {
+        16 => u128::MAX - variant_count + 1,
+        _ => (1 << (8 * tag_size)) - variant_count,
+    };
+    Ok(Layout {
+        size: align_to(size, align),
+        align,
+        field_offsets: Vec::new(),
+        niche: Some(Niche { offset: 0, size: tag_size, available }),
+    })
+}
+
+fn align_to(offset: u64, align: u64) -> u64 {
+    (offset + align - 1) / align * align
+}
@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use base_db::fixture::WithFixture;
+use hir_def::{db::DefDatabase, AdtId, ModuleDefId};
+
+use crate::{db::HirDatabase, test_db::TestDB, Interner, Substitution};
+
+use super::{Endian, Layout, LayoutError, TargetDataLayout};
+
+fn eval_goal(ra_fixture: &str) -> Result<Arc<Layout>, LayoutError> {
+    let (db, file_id) = TestDB::with_single_file(ra_fixture);
+    let module_id = db.module_for_file(file_id);
+    let def_map = module_id.def_map(&db);
+    let scope = &def_map[module_id.local_id].scope;
+    let adt_id = scope
+        .declarations()
+        .into_iter()
+        .find_map(|x| match x {
+            ModuleDefId::AdtId(x) => {
+                let name = match x {
+                    AdtId::StructId(x) => db.struct_data(x).name.to_string(),
+                    AdtId::UnionId(x) => db.union_data(x).name.to_string(),
+                    AdtId::EnumId(x) => db.enum_data(x).name.to_string(),
+                };
+                if name == "Goal" {
+                    Some(x)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        })
+        .unwrap();
+    let goal_ty = db.ty(adt_id.into()).substitute(Interner, &Substitution::empty(Interner));
+    db.layout_of(goal_ty, module_id.krate())
+}
+
+fn check_size_and_align(ra_fixture: &str, size: u64, align: u64) {
+    let l = eval_goal(ra_fixture).unwrap();
+    assert_eq!((l.size, l.align), (size, align));
+}
+
+#[test]
+fn parse_data_layout() {
+    let dl =
+        TargetDataLayout::parse("e-m:e-p:32:32-p270:32:32-i64:64-i128:128-f80:32-n8:16:32-S128")
+            .unwrap();
+    assert_eq!(dl.endian, Endian::Little);
+    assert_eq!((dl.pointer_size, dl.pointer_align), (4, 4));
+    assert_eq!((dl.i64_align, dl.i128_align, dl.f64_align), (8, 16, 8));
+
+    let dl = TargetDataLayout::parse("E-m:e-i64:64-n32:64-S128").unwrap();
+    assert_eq!(dl.endian, Endian::Big);
+    assert_eq!((dl.pointer_size, dl.i64_align, dl.i128_align), (8, 8, 8));
+
+    assert!(TargetDataLayout::parse("e-p:x:64").is_err());
+}
+
+#[test]
+fn scalars_and_pointers() {
+    check_size_and_align("struct Goal(u8);", 1, 1);
+    check_size_and_align("struct Goal(char);", 4, 4);
+    check_size_and_align("struct Goal(u128);", 16, 8);
+    check_size_and_align("struct Goal(&'static u8);", 8, 8);
+    check_size_and_align("struct Goal(&'static [u8]);", 16, 8);
+    check_size_and_align("struct Goal(*const str);", 16, 8);
+    check_size_and_align("struct Goal(fn(u8) -> u8);", 8, 8);
+}
+
+#[test]
+fn structs_and_tuples() {
+    check_size_and_align("struct Goal;", 0, 1);
+    check_size_and_align("struct Goal(u8, u32, u8);", 8, 4);
+    check_size_and_align("struct Goal { x: (u8, u16, u8), y: [u16; 3] }", 10, 2);
+    check_size_and_align(
+        r#"
+struct Wrapper<T>(T, u8);
+struct Goal(Wrapper<u64>, Wrapper<()>);
+"#,
+        24,
+        8,
+    );
+    check_size_and_align("#[repr(packed)] struct Goal(u8, u32, u16);", 7, 1);
+}
+
+#[test]
+fn field_offsets() {
+    let l = eval_goal("struct Goal { a: u8, b: u32, c: u16 }").unwrap();
+    assert_eq!(l.field_offsets, vec![6, 0, 4]);
+    let l = eval_goal("#[repr(packed)] struct Goal { a: u8, b: u32, c: u16 }").unwrap();
+    assert_eq!(l.field_offsets, vec![0, 1, 5]);
+}
+
+#[test]
+fn unions() {
+    check_size_and_align("union Goal { a: u8, b: [u16; 3] }", 6, 2);
+    check_size_and_align("union Goal { a: u32, b: [u8; 5] }", 8, 4);
+}
+
+#[test]
+fn enums() {
+    check_size_and_align("enum Goal {}", 0, 1);
+    check_size_and_align("enum Goal { A, B, C }", 1, 1);
+    check_size_and_align("enum Goal { A(u8), B(u16) }", 4, 2);
+    check_size_and_align("enum Goal { A(u64), B(u8, u32) }", 16, 8);
+    check_size_and_align("enum Goal { A(u8, u32, u8) }", 8, 4);
+}
+
+#[test]
+fn niche_optimization() {
+    check_size_and_align(
+        r#"
+enum Option<T> { None, Some(T) }
+struct Goal(Option<&'static u64>, Option<bool>, Option<Option<char>>, Option<u32>);
+"#,
+        24,
+        8,
+    );
+    check_size_and_align(
+        r#"
+enum Option<T> { None, Some(T) }
+enum Inner { A, B, C }
+struct Goal(Option<Option<Inner>>);
+"#,
+        1,
+        1,
+    );
+    check_size_and_align(
+        r#"
+enum Option<T> { None, Some(T) }
+struct Goal(Option<(u32, bool)>);
+"#,
+        8,
+        4,
+    );
+}
+
+#[test]
+fn unsupported() {
+    assert_eq!(eval_goal("struct Goal([u8]);"), Err(LayoutError::Unsized));
+    assert_eq!(eval_goal("struct Goal(Goal);"), Err(LayoutError::Recursive));
+    assert_eq!(eval_goal("#[repr(C)] struct Goal(u8);"), Err(LayoutError::NotImplemented));
+}
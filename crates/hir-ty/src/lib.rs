@@ -17,11 +17,14 @@ mod lower;
 mod mapping;
 mod tls;
 mod utils;
+mod variance;
 mod walk;
 pub mod db;
 pub mod diagnostics;
 pub mod display;
+pub mod layout;
 pub mod method_resolution;
+pub mod mir;
 pub mod primitive;
 pub mod traits;
 
@@ -32,12 +35,13 @@ mod test_db;
 
 use std::sync::Arc;
 
+use base_db::CrateId;
 use chalk_ir::{
     fold::{Fold, Shift},
     interner::HasInterner,
     NoSolution,
 };
-use hir_def::{expr::ExprId, type_ref::Rawness, TypeOrConstParamId};
+use hir_def::{expr::ExprId, type_ref::Rawness, HasModule, Lookup, TypeOrConstParamId};
 use itertools::Either;
 use utils::Generics;
 
@@ -47,8 +51,9 @@ pub use autoderef::autoderef;
 pub use builder::{ParamKind, TyBuilder};
 pub use chalk_ext::*;
 pub use infer::{
-    could_coerce, could_unify, Adjust, Adjustment, AutoBorrow, BindingMode, InferenceDiagnostic,
-    InferenceResult,
+    could_coerce, could_unify, Adjust, Adjustment, AutoBorrow, BindingMode, CaptureKind,
+    CapturedItem, HirPlace, InferenceDiagnostic, InferenceResult, OverloadedDeref, PlaceProjection,
+    PointerCast,
 };
 pub use interner::Interner;
 pub use lower::{
@@ -60,8 +65,8 @@ pub use mapping::{
     lt_from_placeholder_idx, to_assoc_type_id, to_chalk_trait_id, to_foreign_def_id,
     to_placeholder_idx,
 };
-pub use traits::TraitEnvironment;
-pub use utils::{all_super_traits, is_fn_unsafe_to_call};
+pub use traits::{TraitEnvironment, TraitSolverConfig};
+pub use utils::{all_super_traits, direct_super_traits, is_fn_unsafe_to_call};
 pub use walk::TypeWalk;
 
 pub use chalk_ir::{
@@ -281,9 +286,39 @@ impl Fold<Interner> for CallableSig {
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
 pub enum ImplTraitId {
     ReturnTypeImplTrait(hir_def::FunctionId, u16),
+    /// An `impl Trait` in the type of a type alias, e.g. `type Foo = impl Trait;`.
+    TypeAliasImplTrait(hir_def::TypeAliasId, u16),
     AsyncBlockTypeImplTrait(hir_def::DefWithBodyId, ExprId),
 }
 
+impl ImplTraitId {
+    /// Returns the `impl Trait`s of the function or type alias this opaque type belongs to, and
+    /// the index of this one among them. Async blocks don't have any.
+    pub(crate) fn impl_traits(
+        self,
+        db: &dyn HirDatabase,
+    ) -> Option<(Arc<Binders<ReturnTypeImplTraits>>, usize)> {
+        match self {
+            ImplTraitId::ReturnTypeImplTrait(func, idx) => {
+                Some((db.return_type_impl_traits(func)?, idx as usize))
+            }
+            ImplTraitId::TypeAliasImplTrait(alias, idx) => {
+                Some((db.type_alias_impl_traits(alias)?, idx as usize))
+            }
+            ImplTraitId::AsyncBlockTypeImplTrait(..) => None,
+        }
+    }
+
+    pub(crate) fn krate(self, db: &dyn HirDatabase) -> CrateId {
+        let db = db.upcast();
+        match self {
+            ImplTraitId::ReturnTypeImplTrait(func, _) => func.lookup(db).module(db).krate(),
+            ImplTraitId::TypeAliasImplTrait(alias, _) => alias.lookup(db).module(db).krate(),
+            ImplTraitId::AsyncBlockTypeImplTrait(def, _) => def.module(db).krate(),
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct ReturnTypeImplTraits {
     pub(crate) impl_traits: Vec<ReturnTypeImplTrait>,
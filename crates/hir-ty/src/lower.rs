@@ -16,7 +16,7 @@ use hir_def::generics::TypeOrConstParamData;
 use hir_def::intern::Interned;
 use hir_def::lang_item::lang_attr;
 use hir_def::path::{ModPath, PathKind};
-use hir_def::type_ref::ConstScalarOrPath;
+use hir_def::type_ref::{ConstScalar, ConstScalarOrPath};
 use hir_def::{
     adt::StructKind,
     body::{Expander, LowerCtx},
@@ -39,10 +39,10 @@ use stdx::{impl_from, never};
 use syntax::{ast, SmolStr};
 
 use crate::consteval::{
-    intern_scalar_const, path_to_const, unknown_const, unknown_const_as_generic,
+    eval_usize_binop, intern_scalar_const, path_to_const, unknown_const, unknown_const_as_generic,
 };
 use crate::utils::Generics;
-use crate::{all_super_traits, make_binders, Const, GenericArgData, ParamKind};
+use crate::{all_super_traits, make_binders, Const, ConstValue, GenericArgData, ParamKind};
 use crate::{
     db::HirDatabase,
     mapping::ToChalk,
@@ -252,9 +252,16 @@ impl<'a> TyLoweringContext<'a> {
                     ImplTraitLoweringMode::Opaque => {
                         let idx = self.impl_trait_counter.get();
                         self.impl_trait_counter.set(idx + 1);
-                        let func = match self.resolver.generic_def() {
-                            Some(GenericDefId::FunctionId(f)) => f,
-                            _ => panic!("opaque impl trait lowering in non-function"),
+                        let (impl_trait_id, owner) = match self.resolver.generic_def() {
+                            Some(GenericDefId::FunctionId(f)) => {
+                                (ImplTraitId::ReturnTypeImplTrait(f, idx), f.into())
+                            }
+                            Some(GenericDefId::TypeAliasId(t)) => {
+                                (ImplTraitId::TypeAliasImplTrait(t, idx), t.into())
+                            }
+                            _ => panic!(
+                                "opaque impl trait lowering outside of a function or type alias"
+                            ),
                         };
 
                         assert!(idx as usize == self.opaque_type_data.borrow().len());
@@ -275,13 +282,12 @@ impl<'a> TyLoweringContext<'a> {
                         // away instead of two.
                         let actual_opaque_type_data = self
                             .with_debruijn(DebruijnIndex::INNERMOST, |ctx| {
-                                ctx.lower_impl_trait(bounds, func)
+                                ctx.lower_impl_trait(bounds, self.resolver.krate())
                             });
                         self.opaque_type_data.borrow_mut()[idx as usize] = actual_opaque_type_data;
 
-                        let impl_trait_id = ImplTraitId::ReturnTypeImplTrait(func, idx);
                         let opaque_ty_id = self.db.intern_impl_trait_id(impl_trait_id).into();
-                        let generics = generics(self.db.upcast(), func.into());
+                        let generics = generics(self.db.upcast(), owner);
                         let parameters = generics.bound_vars_subst(self.db, self.in_binders);
                         TyKind::OpaqueType(opaque_ty_id, parameters).intern(Interner)
                     }
@@ -447,11 +453,11 @@ impl<'a> TyLoweringContext<'a> {
                             .associated_type_by_name(segment.name);
                         match found {
                             Some(associated_ty) => {
-                                // FIXME handle type parameters on the segment
-                                TyKind::Alias(AliasTy::Projection(ProjectionTy {
-                                    associated_ty_id: to_assoc_type_id(associated_ty),
-                                    substitution: trait_ref.substitution,
-                                }))
+                                TyKind::Alias(AliasTy::Projection(self.lower_projection_ty(
+                                    segment,
+                                    associated_ty,
+                                    trait_ref.substitution,
+                                )))
                                 .intern(Interner)
                             }
                             None => {
@@ -593,12 +599,12 @@ impl<'a> TyLoweringContext<'a> {
                     // We need to shift in the bound vars, since
                     // associated_type_shorthand_candidates does not do that
                     let substs = substs.shifted_in_from(Interner, self.in_binders);
-                    // FIXME handle type parameters on the segment
                     Some(
-                        TyKind::Alias(AliasTy::Projection(ProjectionTy {
-                            associated_ty_id: to_assoc_type_id(associated_ty),
-                            substitution: substs,
-                        }))
+                        TyKind::Alias(AliasTy::Projection(self.lower_projection_ty(
+                            segment.clone(),
+                            associated_ty,
+                            substs,
+                        )))
                         .intern(Interner),
                     )
                 } else {
@@ -792,6 +798,29 @@ impl<'a> TyLoweringContext<'a> {
         Substitution::from_iter(Interner, substs)
     }
 
+    /// Lowers the projection of `associated_ty` given the substitution of its trait. For generic
+    /// associated types, the arguments of the associated type itself are taken from `segment` and
+    /// come first in the projection's substitution.
+    fn lower_projection_ty(
+        &self,
+        segment: PathSegment<'_>,
+        associated_ty: TypeAliasId,
+        trait_substs: Substitution,
+    ) -> ProjectionTy {
+        let (_, parent_len, own_len) = generics(self.db.upcast(), associated_ty.into()).len_split();
+        let substitution = if own_len == 0 {
+            trait_substs
+        } else {
+            let substs =
+                self.substs_from_path_segment(segment, Some(associated_ty.into()), false, None);
+            Substitution::from_iter(
+                Interner,
+                substs.iter(Interner).skip(parent_len).chain(trait_substs.iter(Interner)).cloned(),
+            )
+        };
+        ProjectionTy { associated_ty_id: to_assoc_type_id(associated_ty), substitution }
+    }
+
     fn lower_trait_ref_from_path(
         &self,
         path: &Path,
@@ -956,10 +985,10 @@ impl<'a> TyLoweringContext<'a> {
                     None => return SmallVec::new(),
                     Some(t) => t,
                 };
-                let projection_ty = ProjectionTy {
-                    associated_ty_id: to_assoc_type_id(associated_ty),
-                    substitution: super_trait_ref.substitution,
-                };
+                let segment =
+                    PathSegment { name: &binding.name, args_and_bindings: binding.args.as_deref() };
+                let projection_ty =
+                    self.lower_projection_ty(segment, associated_ty, super_trait_ref.substitution);
                 let mut preds: SmallVec<[_; 1]> = SmallVec::with_capacity(
                     binding.type_ref.as_ref().map_or(0, |_| 1) + binding.bounds.len(),
                 );
@@ -983,7 +1012,7 @@ impl<'a> TyLoweringContext<'a> {
     fn lower_impl_trait(
         &self,
         bounds: &[Interned<TypeBound>],
-        func: FunctionId,
+        krate: CrateId,
     ) -> ReturnTypeImplTrait {
         cov_mark::hit!(lower_rpit);
         let self_ty = TyKind::BoundVar(BoundVar::new(DebruijnIndex::INNERMOST, 0)).intern(Interner);
@@ -994,7 +1023,6 @@ impl<'a> TyLoweringContext<'a> {
                 .collect();
 
             if !ctx.unsized_types.borrow().contains(&self_ty) {
-                let krate = func.lookup(ctx.db.upcast()).module(ctx.db.upcast()).krate();
                 let sized_trait = ctx
                     .db
                     .lang_item(krate, SmolStr::new_inline("sized"))
@@ -1340,11 +1368,24 @@ pub(crate) fn generic_defaults_query(
         .map(|(idx, (id, p))| {
             let p = match p {
                 TypeOrConstParamData::TypeParamData(p) => p,
-                TypeOrConstParamData::ConstParamData(_) => {
-                    // FIXME: implement const generic defaults
-                    let val = unknown_const_as_generic(
-                        db.const_param_ty(ConstParamId::from_unchecked(id)),
-                    );
+                TypeOrConstParamData::ConstParamData(p) => {
+                    let const_ty = db.const_param_ty(ConstParamId::from_unchecked(id));
+                    let val = match &p.default {
+                        Some(default) => {
+                            let c = const_or_path_to_chalk(
+                                db,
+                                &resolver,
+                                const_ty,
+                                default,
+                                ParamLoweringMode::Variable,
+                                || generics(db.upcast(), def),
+                                DebruijnIndex::INNERMOST,
+                            );
+                            // Defaults can only refer to previous parameters.
+                            GenericArgData::Const(fallback_bound_vars(c, idx)).intern(Interner)
+                        }
+                        None => unknown_const_as_generic(const_ty),
+                    };
                     return crate::make_binders_with_count(db, idx, &generic_params, val);
                 }
             };
@@ -1501,8 +1542,9 @@ fn type_for_adt(db: &dyn HirDatabase, adt: AdtId) -> Binders<Ty> {
 fn type_for_type_alias(db: &dyn HirDatabase, t: TypeAliasId) -> Binders<Ty> {
     let generics = generics(db.upcast(), t.into());
     let resolver = t.resolver(db.upcast());
-    let ctx =
-        TyLoweringContext::new(db, &resolver).with_type_param_mode(ParamLoweringMode::Variable);
+    let ctx = TyLoweringContext::new(db, &resolver)
+        .with_impl_trait_mode(ImplTraitLoweringMode::Opaque)
+        .with_type_param_mode(ParamLoweringMode::Variable);
     if db.type_alias_data(t).is_extern {
         Binders::empty(Interner, TyKind::Foreign(crate::to_foreign_def_id(t)).intern(Interner))
     } else {
@@ -1667,6 +1709,27 @@ pub(crate) fn return_type_impl_traits(
     }
 }
 
+pub(crate) fn type_alias_impl_traits(
+    db: &dyn HirDatabase,
+    def: TypeAliasId,
+) -> Option<Arc<Binders<ReturnTypeImplTraits>>> {
+    let data = db.type_alias_data(def);
+    let type_ref = data.type_ref.as_deref()?;
+    let resolver = def.resolver(db.upcast());
+    let ctx = TyLoweringContext::new(db, &resolver)
+        .with_impl_trait_mode(ImplTraitLoweringMode::Opaque)
+        .with_type_param_mode(ParamLoweringMode::Variable);
+    let _ty = (&ctx).lower_ty(type_ref);
+    let generics = generics(db.upcast(), def.into());
+    let type_alias_impl_traits =
+        ReturnTypeImplTraits { impl_traits: ctx.opaque_type_data.into_inner() };
+    if type_alias_impl_traits.impl_traits.is_empty() {
+        None
+    } else {
+        Some(Arc::new(make_binders(db, &generics, type_alias_impl_traits)))
+    }
+}
+
 pub(crate) fn lower_to_chalk_mutability(m: hir_def::type_ref::Mutability) -> Mutability {
     match m {
         hir_def::type_ref::Mutability::Shared => Mutability::Not,
@@ -1731,9 +1794,10 @@ pub(crate) fn const_or_path_to_chalk(
     expected_ty: Ty,
     value: &ConstScalarOrPath,
     mode: ParamLoweringMode,
-    args: impl FnOnce() -> Generics,
+    args: impl Fn() -> Generics,
     debruijn: DebruijnIndex,
 ) -> Const {
+    let args: &dyn Fn() -> Generics = &args;
     match value {
         ConstScalarOrPath::Scalar(s) => intern_scalar_const(s.clone(), expected_ty),
         ConstScalarOrPath::Path(n) => {
@@ -1741,9 +1805,38 @@ pub(crate) fn const_or_path_to_chalk(
             path_to_const(db, resolver, &path, mode, args, debruijn)
                 .unwrap_or_else(|| unknown_const(expected_ty))
         }
+        ConstScalarOrPath::BinOp(lhs, op, rhs) => {
+            // FIXME: we only know how to evaluate arithmetic on `usize`s
+            if expected_ty != TyBuilder::usize() {
+                return unknown_const(expected_ty);
+            }
+            let operand = |it: &ConstScalarOrPath| {
+                const_or_path_to_chalk(db, resolver, expected_ty.clone(), it, mode, args, debruijn)
+            };
+            let (lhs, rhs) = (operand(lhs), operand(rhs));
+            if is_unknown_const(&lhs) || is_unknown_const(&rhs) {
+                return unknown_const(expected_ty);
+            }
+            match eval_usize_binop(*op, lhs, rhs) {
+                // Operands depending on const parameters, keep the expression around to
+                // evaluate it when they are substituted.
+                it if is_unknown_const(&it) => intern_scalar_const(
+                    ConstScalar::Unevaluated(Box::new(value.clone())),
+                    expected_ty,
+                ),
+                it => it,
+            }
+        }
     }
 }
 
+fn is_unknown_const(c: &Const) -> bool {
+    matches!(
+        &c.data(Interner).value,
+        ConstValue::Concrete(cc) if cc.interned == ConstScalar::Unknown
+    )
+}
+
 /// This replaces any 'free' Bound vars in `s` (i.e. those with indices past
 /// num_vars_to_keep) by `TyKind::Unknown`.
 fn fallback_bound_vars<T: Fold<Interner> + HasInterner<Interner = Interner>>(
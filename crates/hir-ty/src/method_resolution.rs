@@ -21,10 +21,10 @@ use crate::{
     from_foreign_def_id,
     infer::{unify::InferenceTable, Adjust, Adjustment, AutoBorrow, OverloadedDeref, PointerCast},
     primitive::{FloatTy, IntTy, UintTy},
-    static_lifetime,
-    utils::all_super_traits,
+    static_lifetime, to_chalk_trait_id,
+    utils::{all_super_traits, generics},
     AdtId, Canonical, CanonicalVarKinds, DebruijnIndex, ForeignDefId, InEnvironment, Interner,
-    Scalar, TraitEnvironment, TraitRefExt, Ty, TyBuilder, TyExt, TyKind,
+    Scalar, Substitution, TraitEnvironment, TraitRef, TraitRefExt, Ty, TyBuilder, TyExt, TyKind,
 };
 
 /// This is used as a key for indexing impls.
@@ -443,6 +443,66 @@ pub(crate) fn lookup_method(
     )
 }
 
+/// Looks up the impl method that a call to the trait method `func` with the (fully resolved)
+/// substitution `fn_subst` dispatches to. Returns `None` if `func` is not a trait method, if no
+/// single impl applies or if the impl doesn't override the method.
+pub fn lookup_impl_method(
+    db: &dyn HirDatabase,
+    env: Arc<TraitEnvironment>,
+    func: FunctionId,
+    fn_subst: Substitution,
+) -> Option<(FunctionId, Substitution)> {
+    let trait_id = match func.lookup(db.upcast()).container {
+        ItemContainerId::TraitId(id) => id,
+        _ => return None,
+    };
+    let trait_params = generics(db.upcast(), trait_id.into()).len();
+    let trait_ref = TraitRef {
+        trait_id: to_chalk_trait_id(trait_id),
+        substitution: Substitution::from_iter(
+            Interner,
+            fn_subst.iter(Interner).take(trait_params).cloned(),
+        ),
+    };
+    let self_ty_fp = TyFingerprint::for_trait_impl(&trait_ref.self_type_parameter(Interner))?;
+
+    let mut table = InferenceTable::new(db, env.clone());
+    let mut candidates = db
+        .trait_impls_in_deps(env.krate)
+        .for_trait_and_self_ty(trait_id, self_ty_fp)
+        .filter_map(|impl_| {
+            table.run_in_snapshot(|table| {
+                let impl_subst =
+                    TyBuilder::subst_for_def(db, impl_).fill_with_inference_vars(table).build();
+                let impl_trait_ref = db.impl_trait(impl_)?.substitute(Interner, &impl_subst);
+                for (impl_arg, arg) in impl_trait_ref
+                    .substitution
+                    .iter(Interner)
+                    .zip(trait_ref.substitution.iter(Interner))
+                {
+                    table.try_unify(impl_arg, arg).ok()?;
+                }
+                Some((impl_, table.resolve_completely(impl_subst)))
+            })
+        })
+        .collect::<Vec<_>>();
+    if candidates.len() != 1 {
+        return None;
+    }
+    let (impl_, impl_subst) = candidates.pop()?;
+
+    let name = &db.function_data(func).name;
+    let impl_func = db.impl_data(impl_).items.iter().find_map(|&item| match item {
+        AssocItemId::FunctionId(f) if db.function_data(f).name == *name => Some(f),
+        _ => None,
+    })?;
+    let impl_func_subst = Substitution::from_iter(
+        Interner,
+        impl_subst.iter(Interner).chain(fn_subst.iter(Interner).skip(trait_params)).cloned(),
+    );
+    Some((impl_func, impl_func_subst))
+}
+
 /// Whether we're looking up a dotted method call (like `v.len()`) or a path
 /// (like `Vec::new`).
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
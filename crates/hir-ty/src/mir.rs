@@ -0,0 +1,170 @@
+//! MIR definitions and implementation
+//!
+//! The MIR is a control flow graph of basic blocks, similar to (but much simpler than) rustc's
+//! MIR. It is lowered from the HIR body of a function after type inference, and is currently only
+//! used for debugging the analyzer itself.
+
+use chalk_ir::Mutability;
+use hir_def::{
+    expr::{Literal, PatId},
+    DefWithBodyId, FieldId, VariantId,
+};
+use la_arena::{Arena, Idx};
+
+use crate::{Substitution, Ty};
+
+mod eval;
+mod lower;
+mod pretty;
+#[cfg(test)]
+mod tests;
+
+pub use eval::{interpret_mir, MirEvalError, Pointer, Value};
+pub use lower::{mir_body_query, MirLowerError};
+
+pub type LocalId = Idx<Local>;
+pub type BasicBlockId = Idx<BasicBlock>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Local {
+    pub ty: Ty,
+    /// The binding this local was created for, `None` for the return place and temporaries.
+    pub binding: Option<PatId>,
+}
+
+/// A memory location, a local with a (possibly empty) path into it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Place {
+    pub local: LocalId,
+    pub projection: Vec<ProjectionElem>,
+}
+
+impl From<LocalId> for Place {
+    fn from(local: LocalId) -> Self {
+        Place { local, projection: Vec::new() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjectionElem {
+    Deref,
+    Field(FieldId),
+    TupleField(usize),
+    Index(LocalId),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operand {
+    Copy(Place),
+    Move(Place),
+    Constant(Constant),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constant {
+    pub kind: ConstantKind,
+    pub ty: Ty,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConstantKind {
+    Literal(Literal),
+    /// The only value of a zero sized type, like `()` or a function item.
+    ZeroSized,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    BitXor,
+    BitAnd,
+    BitOr,
+    Shl,
+    Shr,
+    Eq,
+    Lt,
+    Le,
+    Ne,
+    Ge,
+    Gt,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnOp {
+    Not,
+    Neg,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AggregateKind {
+    /// The type is the element type.
+    Array(Ty),
+    Tuple,
+    Adt(VariantId, Substitution),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rvalue {
+    Use(Operand),
+    Ref(Mutability, Place),
+    BinaryOp(BinOp, Operand, Operand),
+    UnaryOp(UnOp, Operand),
+    Cast(Operand, Ty),
+    Aggregate(AggregateKind, Vec<Operand>),
+    /// Reads the index of the variant of the enum value at the place, as an `isize`.
+    Discriminant(Place),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Statement {
+    Assign(Place, Rvalue),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwitchTargets {
+    pub values: Vec<(u128, BasicBlockId)>,
+    pub otherwise: BasicBlockId,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Terminator {
+    Goto {
+        target: BasicBlockId,
+    },
+    SwitchInt {
+        discr: Operand,
+        targets: SwitchTargets,
+    },
+    Return,
+    Call {
+        func: Operand,
+        args: Vec<Operand>,
+        destination: Place,
+        /// The block to continue in after the call returns, `None` if the callee diverges.
+        target: Option<BasicBlockId>,
+    },
+    /// Marks code that can't be reached, like the fallthrough of an exhaustive `match`.
+    Unreachable,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub statements: Vec<Statement>,
+    /// Only `None` while the block is being built.
+    pub terminator: Option<Terminator>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MirBody {
+    pub basic_blocks: Arena<BasicBlock>,
+    /// The locals of the body. The first one is the return place, followed by one local for each
+    /// parameter.
+    pub locals: Arena<Local>,
+    pub start_block: BasicBlockId,
+    pub owner: DefWithBodyId,
+    pub arg_count: usize,
+}
@@ -0,0 +1,601 @@
+//! This module provides a MIR interpreter, which is used for running functions inside the
+//! analyzer, without compiling them.
+
+use base_db::CrateId;
+use chalk_ir::{FloatTy, IntTy, Scalar, UintTy};
+use hir_def::{
+    adt::StructKind, expr::Literal, lang_item::lang_attr, AdtId, AssocItemId, FunctionId,
+    HasModule, ItemContainerId, Lookup, VariantId,
+};
+use la_arena::ArenaMap;
+
+use crate::{
+    db::HirDatabase, method_resolution::TyFingerprint, CallableDefId, Interner, Substitution,
+    TyKind,
+};
+
+use super::{
+    AggregateKind, BasicBlockId, BinOp, Constant, ConstantKind, LocalId, MirBody, MirLowerError,
+    Operand, Place, ProjectionElem, Rvalue, Statement, Terminator, UnOp,
+};
+
+const STACK_LIMIT: usize = 256;
+/// The functions the `panic!` macros and the compiler's checks call to panic.
+const PANIC_LANG_ITEMS: &[&str] = &[
+    "panic",
+    "panic_fmt",
+    "panic_str",
+    "panic_display",
+    "panic_bounds_check",
+    "panic_nounwind",
+    "const_panic_fmt",
+    "begin_panic",
+];
+const EXECUTION_LIMIT: usize = 1_000_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MirEvalError {
+    /// Lowering the body of a called function failed.
+    LoweringError(FunctionId, MirLowerError),
+    /// The evaluated code panicked, e.g. because of an arithmetic overflow or a `panic!()` call.
+    Panic(String),
+    NotSupported(String),
+    TypeError(&'static str),
+    ExecutionLimitExceeded,
+    StackOverflow,
+}
+
+type Result<T> = std::result::Result<T, MirEvalError>;
+
+/// A value computed by the interpreter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Char(char),
+    /// Integers of all sizes and signedness, the scalar is their type.
+    Int(i128, Scalar),
+    Float(f64),
+    Str(String),
+    Tuple(Vec<Value>),
+    Array(Vec<Value>),
+    Adt(VariantId, Vec<Value>),
+    Ref(Pointer),
+    Fn(CallableDefId, Substitution),
+}
+
+/// The location of a value on the interpreter's stack, a path of field indices into a local.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pointer {
+    frame: usize,
+    local: LocalId,
+    path: Vec<usize>,
+}
+
+/// Runs the given body, which must not take any parameters, and returns its result.
+pub fn interpret_mir(db: &dyn HirDatabase, body: &MirBody) -> Result<Value> {
+    if body.arg_count != 0 {
+        return Err(MirEvalError::NotSupported("function with parameters".to_string()));
+    }
+    let krate = body.owner.module(db.upcast()).krate();
+    Evaluator { db, krate, stack: Vec::new(), steps: 0 }.call(body, Vec::new())
+}
+
+struct Evaluator<'a> {
+    db: &'a dyn HirDatabase,
+    /// The crate of the interpreted function, impls are looked up in it and its dependencies.
+    krate: CrateId,
+    stack: Vec<ArenaMap<LocalId, Value>>,
+    steps: usize,
+}
+
+impl Evaluator<'_> {
+    fn call(&mut self, body: &MirBody, args: Vec<Value>) -> Result<Value> {
+        if self.stack.len() >= STACK_LIMIT {
+            return Err(MirEvalError::StackOverflow);
+        }
+        if args.len() != body.arg_count {
+            return Err(MirEvalError::TypeError("wrong number of arguments"));
+        }
+        let mut locals = body.locals.iter().map(|(id, _)| id);
+        let return_local = locals.next().ok_or(MirEvalError::TypeError("missing return place"))?;
+        let mut frame = ArenaMap::default();
+        for (local, arg) in locals.zip(args) {
+            frame.insert(local, arg);
+        }
+        self.stack.push(frame);
+        let result = self.run(body, return_local);
+        self.stack.pop();
+        result
+    }
+
+    fn run(&mut self, body: &MirBody, return_local: LocalId) -> Result<Value> {
+        let mut current: BasicBlockId = body.start_block;
+        loop {
+            let block = &body.basic_blocks[current];
+            for statement in &block.statements {
+                self.step()?;
+                match statement {
+                    Statement::Assign(place, rvalue) => {
+                        let value = self.eval_rvalue(rvalue)?;
+                        self.write_place(place, value)?;
+                    }
+                }
+            }
+            self.step()?;
+            let terminator =
+                block.terminator.as_ref().ok_or(MirEvalError::TypeError("unterminated block"))?;
+            current = match terminator {
+                Terminator::Goto { target } => *target,
+                Terminator::SwitchInt { discr, targets } => {
+                    let value = match self.eval_operand(discr)? {
+                        Value::Bool(it) => it as u128,
+                        Value::Char(it) => it as u128,
+                        Value::Int(it, _) => it as u128,
+                        _ => return Err(MirEvalError::TypeError("switch on a non-integer")),
+                    };
+                    targets
+                        .values
+                        .iter()
+                        .find(|(it, _)| *it == value)
+                        .map_or(targets.otherwise, |(_, target)| *target)
+                }
+                Terminator::Return => return self.read_place(&return_local.into()),
+                Terminator::Unreachable => {
+                    return Err(MirEvalError::TypeError("entered unreachable code"))
+                }
+                Terminator::Call { func, args, destination, target } => {
+                    let func = self.eval_operand(func)?;
+                    let args =
+                        args.iter().map(|it| self.eval_operand(it)).collect::<Result<_>>()?;
+                    let result = self.call_value(func, args)?;
+                    self.write_place(destination, result)?;
+                    target.ok_or(MirEvalError::TypeError("diverging function returned"))?
+                }
+            };
+        }
+    }
+
+    fn step(&mut self) -> Result<()> {
+        self.steps += 1;
+        if self.steps > EXECUTION_LIMIT {
+            return Err(MirEvalError::ExecutionLimitExceeded);
+        }
+        Ok(())
+    }
+
+    fn call_value(&mut self, func: Value, args: Vec<Value>) -> Result<Value> {
+        let (def, subst) = match func {
+            Value::Fn(def, subst) => (def, subst),
+            _ => return Err(MirEvalError::TypeError("call of a non-function")),
+        };
+        let func = match def {
+            CallableDefId::FunctionId(it) => it,
+            CallableDefId::StructId(it) => return Ok(Value::Adt(it.into(), args)),
+            CallableDefId::EnumVariantId(it) => return Ok(Value::Adt(it.into(), args)),
+        };
+        let is_panic = lang_attr(self.db.upcast(), func)
+            .map_or(false, |it| PANIC_LANG_ITEMS.contains(&it.as_str()));
+        if is_panic {
+            let message = match args.first().map(|it| self.deref_value(it)).transpose()? {
+                Some(Value::Str(it)) => it,
+                _ => "explicit panic".to_string(),
+            };
+            return Err(MirEvalError::Panic(message));
+        }
+        let func = self.resolve_trait_method(func, &subst, &args)?;
+        let body =
+            self.db.mir_body(func.into()).map_err(|e| MirEvalError::LoweringError(func, e))?;
+        self.call(&body, args)
+    }
+
+    /// Finds the implementation of a trait method to call, based on the type of the receiver, or
+    /// of `Self` for methods without one.
+    fn resolve_trait_method(
+        &self,
+        func: FunctionId,
+        subst: &Substitution,
+        args: &[Value],
+    ) -> Result<FunctionId> {
+        let trait_ = match func.lookup(self.db.upcast()).container {
+            ItemContainerId::TraitId(it) => it,
+            _ => return Ok(func),
+        };
+        let has_self_param = self.db.function_data(func).has_self_param();
+        let fingerprint = match args.first() {
+            Some(receiver) if has_self_param => self.fingerprint(&self.deref_value(receiver)?),
+            _ => subst
+                .as_slice(Interner)
+                .first()
+                .and_then(|it| it.ty(Interner))
+                .and_then(TyFingerprint::for_trait_impl),
+        };
+        let fingerprint = fingerprint.ok_or_else(|| {
+            MirEvalError::NotSupported("trait method call with unknown `Self` type".to_string())
+        })?;
+        let name = &self.db.function_data(func).name;
+        let impls = self.db.trait_impls_in_deps(self.krate);
+        let impl_func = impls
+            .for_trait_and_self_ty(trait_, fingerprint)
+            .filter(|&impl_| {
+                let self_ty = self.db.impl_self_ty(impl_);
+                TyFingerprint::for_trait_impl(self_ty.skip_binders()) == Some(fingerprint)
+            })
+            .flat_map(|impl_| self.db.impl_data(impl_).items.clone())
+            .find_map(|item| match item {
+                AssocItemId::FunctionId(it) if self.db.function_data(it).name == *name => Some(it),
+                _ => None,
+            });
+        // Without an implementation, the trait's default body is used.
+        Ok(impl_func.unwrap_or(func))
+    }
+
+    fn fingerprint(&self, value: &Value) -> Option<TyFingerprint> {
+        let fp = match value {
+            Value::Bool(_) => TyFingerprint::Scalar(Scalar::Bool),
+            Value::Char(_) => TyFingerprint::Scalar(Scalar::Char),
+            Value::Int(_, scalar) => TyFingerprint::Scalar(*scalar),
+            Value::Float(_) => TyFingerprint::Scalar(Scalar::Float(FloatTy::F64)),
+            Value::Str(_) => TyFingerprint::Str,
+            Value::Tuple(it) if it.is_empty() => TyFingerprint::Unit,
+            Value::Array(_) => TyFingerprint::Array,
+            Value::Adt(variant, _) => TyFingerprint::Adt(variant_adt(*variant)),
+            Value::Tuple(_) | Value::Ref(_) | Value::Fn(..) => return None,
+        };
+        Some(fp)
+    }
+
+    /// Follows references until reaching a value that isn't one.
+    fn deref_value(&self, value: &Value) -> Result<Value> {
+        let mut value = value.clone();
+        while let Value::Ref(pointer) = value {
+            value = self.value_at(&pointer)?.clone();
+        }
+        Ok(value)
+    }
+
+    fn eval_operand(&mut self, operand: &Operand) -> Result<Value> {
+        match operand {
+            Operand::Copy(place) | Operand::Move(place) => self.read_place(place),
+            Operand::Constant(constant) => self.eval_constant(constant),
+        }
+    }
+
+    fn eval_constant(&self, constant: &Constant) -> Result<Value> {
+        let scalar = match constant.ty.kind(Interner) {
+            TyKind::Scalar(it) => Some(*it),
+            _ => None,
+        };
+        let lit = match &constant.kind {
+            ConstantKind::Literal(it) => it,
+            ConstantKind::ZeroSized => {
+                return match constant.ty.kind(Interner) {
+                    TyKind::FnDef(def, subst) => Ok(Value::Fn(
+                        self.db.lookup_intern_callable_def((*def).into()),
+                        subst.clone(),
+                    )),
+                    TyKind::Tuple(..) => Ok(Value::Tuple(Vec::new())),
+                    _ => Err(MirEvalError::NotSupported("zero sized constant".to_string())),
+                }
+            }
+        };
+        let value = match lit {
+            Literal::Bool(it) => Value::Bool(*it),
+            Literal::Char(it) => Value::Char(*it),
+            Literal::String(it) => Value::Str(it.to_string()),
+            Literal::Int(it, _) => Value::Int(*it, scalar.unwrap_or(Scalar::Int(IntTy::I32))),
+            Literal::Uint(it, _) => {
+                Value::Int(*it as i128, scalar.unwrap_or(Scalar::Int(IntTy::I32)))
+            }
+            Literal::Float(it, _) => Value::Float(f64::from_bits(*it)),
+            Literal::ByteString(_) => {
+                return Err(MirEvalError::NotSupported("byte string literal".to_string()))
+            }
+        };
+        Ok(value)
+    }
+
+    fn eval_rvalue(&mut self, rvalue: &Rvalue) -> Result<Value> {
+        match rvalue {
+            Rvalue::Use(operand) => self.eval_operand(operand),
+            Rvalue::Ref(_, place) => Ok(Value::Ref(self.place_pointer(place)?)),
+            Rvalue::BinaryOp(op, lhs, rhs) => {
+                let lhs = self.eval_operand(lhs)?;
+                let rhs = self.eval_operand(rhs)?;
+                eval_bin_op(*op, lhs, rhs)
+            }
+            Rvalue::UnaryOp(op, operand) => match (op, self.eval_operand(operand)?) {
+                (UnOp::Not, Value::Bool(it)) => Ok(Value::Bool(!it)),
+                (UnOp::Not, Value::Int(it, scalar)) => Ok(Value::Int(wrap(!it, scalar), scalar)),
+                (UnOp::Neg, Value::Int(it, scalar)) => {
+                    checked_int(it.checked_neg(), scalar, "negate").map(|it| Value::Int(it, scalar))
+                }
+                (UnOp::Neg, Value::Float(it)) => Ok(Value::Float(-it)),
+                _ => Err(MirEvalError::TypeError("invalid unary operand")),
+            },
+            Rvalue::Cast(operand, ty) => {
+                let target = match ty.kind(Interner) {
+                    TyKind::Scalar(it) => *it,
+                    _ => return Err(MirEvalError::NotSupported("non-primitive cast".to_string())),
+                };
+                let value = self.eval_operand(operand)?;
+                let value = match (value, target) {
+                    (Value::Int(it, _), Scalar::Float(_)) => Value::Float(it as f64),
+                    (Value::Float(it), Scalar::Float(_)) => Value::Float(it),
+                    (Value::Float(it), Scalar::Int(_) | Scalar::Uint(_)) => {
+                        let (min, max) = int_range(target);
+                        Value::Int((it as i128).clamp(min, max), target)
+                    }
+                    (Value::Int(it, _), Scalar::Char) => {
+                        Value::Char(char::from_u32(it as u32).unwrap_or_default())
+                    }
+                    (Value::Int(it, _), _) => Value::Int(wrap(it, target), target),
+                    (Value::Bool(it), _) => Value::Int(it as i128, target),
+                    (Value::Char(it), _) => Value::Int(wrap(it as i128, target), target),
+                    _ => return Err(MirEvalError::TypeError("invalid cast")),
+                };
+                Ok(value)
+            }
+            Rvalue::Discriminant(place) => match self.read_place(place)? {
+                Value::Adt(VariantId::EnumVariantId(it), _) => Ok(Value::Int(
+                    u32::from(it.local_id.into_raw()) as i128,
+                    Scalar::Int(IntTy::Isize),
+                )),
+                _ => Err(MirEvalError::TypeError("discriminant of a non-enum")),
+            },
+            Rvalue::Aggregate(kind, operands) => {
+                let values =
+                    operands.iter().map(|it| self.eval_operand(it)).collect::<Result<Vec<_>>>()?;
+                Ok(match kind {
+                    AggregateKind::Array(_) => Value::Array(values),
+                    AggregateKind::Tuple => Value::Tuple(values),
+                    AggregateKind::Adt(variant, _) => Value::Adt(*variant, values),
+                })
+            }
+        }
+    }
+
+    fn place_pointer(&self, place: &Place) -> Result<Pointer> {
+        let mut pointer =
+            Pointer { frame: self.stack.len() - 1, local: place.local, path: Vec::new() };
+        for elem in &place.projection {
+            match elem {
+                ProjectionElem::Deref => match self.value_at(&pointer)? {
+                    Value::Ref(it) => pointer = it.clone(),
+                    _ => return Err(MirEvalError::TypeError("deref of a non-reference")),
+                },
+                ProjectionElem::Field(field) => {
+                    pointer.path.push(u32::from(field.local_id.into_raw()) as usize)
+                }
+                ProjectionElem::TupleField(idx) => pointer.path.push(*idx),
+                ProjectionElem::Index(local) => {
+                    let index = match self.read_place(&(*local).into())? {
+                        Value::Int(it, _) => it as usize,
+                        _ => return Err(MirEvalError::TypeError("non-integer index")),
+                    };
+                    if let Value::Array(elements) = self.value_at(&pointer)? {
+                        if index >= elements.len() {
+                            return Err(MirEvalError::Panic(format!(
+                                "index out of bounds: the len is {} but the index is {}",
+                                elements.len(),
+                                index
+                            )));
+                        }
+                    }
+                    pointer.path.push(index);
+                }
+            }
+        }
+        Ok(pointer)
+    }
+
+    fn value_at(&self, pointer: &Pointer) -> Result<&Value> {
+        let mut value = self.stack[pointer.frame]
+            .get(pointer.local)
+            .ok_or(MirEvalError::TypeError("use of an uninitialized local"))?;
+        for &idx in &pointer.path {
+            value = match value {
+                Value::Tuple(it) | Value::Array(it) | Value::Adt(_, it) => it.get(idx),
+                _ => None,
+            }
+            .ok_or(MirEvalError::TypeError("invalid projection"))?;
+        }
+        Ok(value)
+    }
+
+    fn read_place(&self, place: &Place) -> Result<Value> {
+        let pointer = self.place_pointer(place)?;
+        self.value_at(&pointer).map(Clone::clone)
+    }
+
+    fn write_place(&mut self, place: &Place, new_value: Value) -> Result<()> {
+        let pointer = self.place_pointer(place)?;
+        let frame = &mut self.stack[pointer.frame];
+        if pointer.path.is_empty() {
+            frame.insert(pointer.local, new_value);
+            return Ok(());
+        }
+        let mut value = frame
+            .get_mut(pointer.local)
+            .ok_or(MirEvalError::TypeError("write into an uninitialized local"))?;
+        for &idx in &pointer.path {
+            value = match value {
+                Value::Tuple(it) | Value::Array(it) | Value::Adt(_, it) => it.get_mut(idx),
+                _ => None,
+            }
+            .ok_or(MirEvalError::TypeError("invalid projection"))?;
+        }
+        *value = new_value;
+        Ok(())
+    }
+}
+
+fn eval_bin_op(op: BinOp, lhs: Value, rhs: Value) -> Result<Value> {
+    let value = match (lhs, rhs) {
+        (Value::Int(lhs, scalar), Value::Int(rhs, _)) => match op {
+            BinOp::Add => Value::Int(checked_int(lhs.checked_add(rhs), scalar, "add")?, scalar),
+            BinOp::Sub => {
+                Value::Int(checked_int(lhs.checked_sub(rhs), scalar, "subtract")?, scalar)
+            }
+            BinOp::Mul => {
+                Value::Int(checked_int(lhs.checked_mul(rhs), scalar, "multiply")?, scalar)
+            }
+            BinOp::Div | BinOp::Rem if rhs == 0 => {
+                return Err(MirEvalError::Panic(match op {
+                    BinOp::Div => "attempt to divide by zero".to_string(),
+                    _ => "attempt to calculate the remainder with a divisor of zero".to_string(),
+                }))
+            }
+            BinOp::Div => Value::Int(checked_int(lhs.checked_div(rhs), scalar, "divide")?, scalar),
+            BinOp::Rem => Value::Int(
+                checked_int(lhs.checked_rem(rhs), scalar, "calculate the remainder")?,
+                scalar,
+            ),
+            BinOp::BitXor => Value::Int(lhs ^ rhs, scalar),
+            BinOp::BitAnd => Value::Int(lhs & rhs, scalar),
+            BinOp::BitOr => Value::Int(lhs | rhs, scalar),
+            BinOp::Shl | BinOp::Shr => {
+                if rhs < 0 || rhs >= int_bits(scalar) as i128 {
+                    let dir = if op == BinOp::Shl { "left" } else { "right" };
+                    return Err(MirEvalError::Panic(format!(
+                        "attempt to shift {} with overflow",
+                        dir
+                    )));
+                }
+                let value = if op == BinOp::Shl { lhs << rhs } else { lhs >> rhs };
+                Value::Int(wrap(value, scalar), scalar)
+            }
+            _ => Value::Bool(compare(op, lhs, rhs)?),
+        },
+        (Value::Float(lhs), Value::Float(rhs)) => match op {
+            BinOp::Add => Value::Float(lhs + rhs),
+            BinOp::Sub => Value::Float(lhs - rhs),
+            BinOp::Mul => Value::Float(lhs * rhs),
+            BinOp::Div => Value::Float(lhs / rhs),
+            BinOp::Rem => Value::Float(lhs % rhs),
+            _ => Value::Bool(compare(op, lhs, rhs)?),
+        },
+        (Value::Bool(lhs), Value::Bool(rhs)) => match op {
+            BinOp::BitXor => Value::Bool(lhs ^ rhs),
+            BinOp::BitAnd => Value::Bool(lhs & rhs),
+            BinOp::BitOr => Value::Bool(lhs | rhs),
+            _ => Value::Bool(compare(op, lhs, rhs)?),
+        },
+        (Value::Char(lhs), Value::Char(rhs)) => Value::Bool(compare(op, lhs, rhs)?),
+        _ => return Err(MirEvalError::TypeError("invalid binary operands")),
+    };
+    Ok(value)
+}
+
+fn compare<T: PartialOrd>(op: BinOp, lhs: T, rhs: T) -> Result<bool> {
+    Ok(match op {
+        BinOp::Eq => lhs == rhs,
+        BinOp::Ne => lhs != rhs,
+        BinOp::Lt => lhs < rhs,
+        BinOp::Le => lhs <= rhs,
+        BinOp::Gt => lhs > rhs,
+        BinOp::Ge => lhs >= rhs,
+        _ => return Err(MirEvalError::TypeError("invalid binary operator")),
+    })
+}
+
+/// Checks that the result of an integer operation fits into its type, panicking like the
+/// compiled code would otherwise.
+fn checked_int(value: Option<i128>, scalar: Scalar, operation: &str) -> Result<i128> {
+    let (min, max) = int_range(scalar);
+    value
+        .filter(|it| (min..=max).contains(it))
+        .ok_or_else(|| MirEvalError::Panic(format!("attempt to {} with overflow", operation)))
+}
+
+fn int_bits(scalar: Scalar) -> u32 {
+    match scalar {
+        Scalar::Int(IntTy::I8) | Scalar::Uint(UintTy::U8) => 8,
+        Scalar::Int(IntTy::I16) | Scalar::Uint(UintTy::U16) => 16,
+        Scalar::Int(IntTy::I32) | Scalar::Uint(UintTy::U32) | Scalar::Char => 32,
+        Scalar::Int(IntTy::I64 | IntTy::Isize) | Scalar::Uint(UintTy::U64 | UintTy::Usize) => 64,
+        Scalar::Int(IntTy::I128) | Scalar::Uint(UintTy::U128) => 128,
+        Scalar::Bool => 1,
+        Scalar::Float(FloatTy::F32) => 32,
+        Scalar::Float(FloatTy::F64) => 64,
+    }
+}
+
+// FIXME: values of `u128` above `i128::MAX` can't be represented.
+fn int_range(scalar: Scalar) -> (i128, i128) {
+    let bits = int_bits(scalar);
+    match scalar {
+        Scalar::Int(_) if bits == 128 => (i128::MIN, i128::MAX),
+        Scalar::Int(_) => (-(1 << (bits - 1)), (1 << (bits - 1)) - 1),
+        _ if bits >= 127 => (0, i128::MAX),
+        _ => (0, (1 << bits) - 1),
+    }
+}
+
+/// Truncates `value` to the size of `scalar`, like an `as` cast.
+fn wrap(value: i128, scalar: Scalar) -> i128 {
+    let bits = int_bits(scalar);
+    if bits >= 128 {
+        return value;
+    }
+    let shift = 128 - bits;
+    match scalar {
+        Scalar::Int(_) => (value << shift) >> shift,
+        _ => ((value << shift) as u128 >> shift) as i128,
+    }
+}
+
+fn variant_adt(variant: VariantId) -> AdtId {
+    match variant {
+        VariantId::StructId(it) => it.into(),
+        VariantId::UnionId(it) => it.into(),
+        VariantId::EnumVariantId(it) => it.parent.into(),
+    }
+}
+
+impl Value {
+    /// Renders the value like its `Debug` implementation would, for the common cases.
+    pub fn display(&self, db: &dyn HirDatabase) -> String {
+        let list = |values: &[Value]| {
+            values.iter().map(|it| it.display(db)).collect::<Vec<_>>().join(", ")
+        };
+        match self {
+            Value::Bool(it) => it.to_string(),
+            Value::Char(it) => format!("{:?}", it),
+            Value::Int(it, _) => it.to_string(),
+            Value::Float(it) => format!("{:?}", it),
+            Value::Str(it) => format!("{:?}", it),
+            Value::Tuple(it) if it.len() == 1 => format!("({},)", list(it)),
+            Value::Tuple(it) => format!("({})", list(it)),
+            Value::Array(it) => format!("[{}]", list(it)),
+            Value::Adt(variant, fields) => {
+                let data = variant.variant_data(db.upcast());
+                let name = match variant {
+                    VariantId::StructId(it) => db.struct_data(*it).name.clone(),
+                    VariantId::UnionId(it) => db.union_data(*it).name.clone(),
+                    VariantId::EnumVariantId(it) => {
+                        db.enum_data(it.parent).variants[it.local_id].name.clone()
+                    }
+                };
+                match data.kind() {
+                    StructKind::Unit => name.to_string(),
+                    StructKind::Tuple => format!("{}({})", name, list(fields)),
+                    StructKind::Record => {
+                        let fields = data
+                            .fields()
+                            .iter()
+                            .zip(fields)
+                            .map(|((_, field), value)| {
+                                format!("{}: {}", field.name, value.display(db))
+                            })
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        format!("{} {{ {} }}", name, fields)
+                    }
+                }
+            }
+            // The pointee isn't available anymore once the function has returned.
+            Value::Ref(_) => "&_".to_string(),
+            Value::Fn(..) => "fn".to_string(),
+        }
+    }
+}
@@ -0,0 +1,1244 @@
+//! This module generates a MIR body from the HIR body of a function, using the results of type
+//! inference.
+
+use std::sync::Arc;
+
+use chalk_ir::{IntTy, Mutability, Scalar};
+use hir_def::{
+    body::Body,
+    expr::{
+        ArithOp, Array, BinaryOp, CmpOp, Expr, ExprId, LabelId, Literal, LogicOp, MatchArm,
+        Ordering, Pat, PatId, Statement as HirStatement, UnaryOp,
+    },
+    resolver::{resolver_for_expr, ResolveValueResult, ValueNs},
+    AssocItemId, DefWithBodyId, FieldId, VariantId,
+};
+use hir_expand::name::Name;
+use la_arena::ArenaMap;
+
+use crate::{
+    db::HirDatabase,
+    infer::{Adjust, Adjustment, AutoBorrow, BindingMode},
+    lower::lower_to_chalk_mutability,
+    InferenceResult, Interner, Ty, TyBuilder, TyExt, TyKind,
+};
+
+use super::{
+    AggregateKind, BasicBlock, BasicBlockId, BinOp, Constant, ConstantKind, Local, LocalId,
+    MirBody, Operand, Place, ProjectionElem, Rvalue, Statement, SwitchTargets, Terminator, UnOp,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MirLowerError {
+    /// The body contains missing expressions or patterns, usually because of syntax errors.
+    IncompleteExpr,
+    UnresolvedName(String),
+    UnresolvedMethod,
+    UnresolvedField,
+    /// A `break` or `continue` outside of a loop, or with an unknown label.
+    LoopOutsideOfLoop,
+    NotSupported(String),
+}
+
+type Result<T> = std::result::Result<T, MirLowerError>;
+
+struct LoopBlocks {
+    begin: BasicBlockId,
+    /// Created lazily, when the first `break` is lowered.
+    end: Option<BasicBlockId>,
+    place: Place,
+    label: Option<LabelId>,
+}
+
+struct MirLowerCtx<'a> {
+    result: MirBody,
+    db: &'a dyn HirDatabase,
+    body: &'a Body,
+    infer: &'a InferenceResult,
+    return_local: LocalId,
+    binding_locals: ArenaMap<PatId, LocalId>,
+    loops: Vec<LoopBlocks>,
+}
+
+pub fn mir_body_query(db: &dyn HirDatabase, def: DefWithBodyId) -> Result<Arc<MirBody>> {
+    let body = db.body(def);
+    let infer = db.infer(def);
+    let mut basic_blocks = la_arena::Arena::new();
+    let start_block = basic_blocks.alloc(BasicBlock::default());
+    let mut locals = la_arena::Arena::new();
+    let return_ty = infer[body.body_expr].clone();
+    let return_local = locals.alloc(Local { ty: return_ty, binding: None });
+    let mut ctx = MirLowerCtx {
+        result: MirBody {
+            basic_blocks,
+            locals,
+            start_block,
+            owner: def,
+            arg_count: body.params.len(),
+        },
+        db,
+        body: &body,
+        infer: &infer,
+        return_local,
+        binding_locals: ArenaMap::default(),
+        loops: Vec::new(),
+    };
+    // Parameters with destructuring patterns get a temporary local, which is matched against the
+    // pattern at the start of the body.
+    let mut destructured_params = Vec::new();
+    for &param in body.params.iter() {
+        if ctx.is_simple_binding(param) {
+            ctx.new_binding_local(param);
+        } else {
+            let temp = ctx.new_temp(ctx.pat_ty_before_adjustments(param));
+            destructured_params.push((temp, param));
+        }
+    }
+    let mut current = start_block;
+    for (temp, param) in destructured_params {
+        current = ctx.pattern_match_irrefutable(current, temp.into(), param)?;
+    }
+    if let Some(current) = ctx.lower_expr_to_place(body.body_expr, return_local.into(), current)? {
+        ctx.set_terminator(current, Terminator::Return);
+    }
+    Ok(Arc::new(ctx.result))
+}
+
+impl<'a> MirLowerCtx<'a> {
+    fn lower_expr_to_place(
+        &mut self,
+        expr_id: ExprId,
+        place: Place,
+        current: BasicBlockId,
+    ) -> Result<Option<BasicBlockId>> {
+        if !self.adjustments(expr_id).is_empty() {
+            let (p, current) = match self.lower_expr_as_place(expr_id, current)? {
+                Some(it) => it,
+                None => return Ok(None),
+            };
+            let operand = self.operand_for_place(p);
+            self.push_assignment(current, place, Rvalue::Use(operand));
+            return Ok(Some(current));
+        }
+        self.lower_expr_to_place_without_adjust(expr_id, place, current)
+    }
+
+    fn lower_expr_to_place_without_adjust(
+        &mut self,
+        expr_id: ExprId,
+        place: Place,
+        mut current: BasicBlockId,
+    ) -> Result<Option<BasicBlockId>> {
+        match &self.body.exprs[expr_id] {
+            Expr::Missing => Err(MirLowerError::IncompleteExpr),
+            Expr::Path(p) => {
+                let resolver = resolver_for_expr(self.db.upcast(), self.result.owner, expr_id);
+                let resolved = resolver
+                    .resolve_path_in_value_ns(self.db.upcast(), p.mod_path())
+                    .ok_or_else(|| MirLowerError::UnresolvedName(p.mod_path().to_string()))?;
+                let resolved = match resolved {
+                    ResolveValueResult::ValueNs(it) => it,
+                    ResolveValueResult::Partial(..) => {
+                        match self.infer.assoc_resolutions_for_expr(expr_id) {
+                            Some(AssocItemId::FunctionId(it)) => ValueNs::FunctionId(it),
+                            _ => {
+                                return Err(MirLowerError::NotSupported(
+                                    "associated constant".to_string(),
+                                ))
+                            }
+                        }
+                    }
+                };
+                let rvalue = match resolved {
+                    ValueNs::LocalBinding(pat) => {
+                        Rvalue::Use(self.operand_for_place(self.binding_local(pat)?.into()))
+                    }
+                    ValueNs::FunctionId(_) | ValueNs::StructId(_) | ValueNs::EnumVariantId(_)
+                        if matches!(self.expr_ty(expr_id).kind(Interner), TyKind::FnDef(..)) =>
+                    {
+                        Rvalue::Use(self.zero_sized_constant(self.expr_ty(expr_id)))
+                    }
+                    ValueNs::StructId(it) => self.unit_adt_aggregate(it.into(), expr_id)?,
+                    ValueNs::EnumVariantId(it) => self.unit_adt_aggregate(it.into(), expr_id)?,
+                    _ => {
+                        return Err(MirLowerError::NotSupported(format!(
+                            "path resolving to {:?}",
+                            resolved
+                        )))
+                    }
+                };
+                self.push_assignment(current, place, rvalue);
+                Ok(Some(current))
+            }
+            Expr::Literal(lit) => {
+                let constant = self.literal_constant(lit.clone(), expr_id);
+                self.push_assignment(current, place, Rvalue::Use(constant));
+                Ok(Some(current))
+            }
+            Expr::Block { statements, tail, label, .. } => {
+                if label.is_some() {
+                    return Err(MirLowerError::NotSupported("labeled block".into()));
+                }
+                for statement in statements.iter() {
+                    current = match self.lower_statement(statement, current)? {
+                        Some(it) => it,
+                        None => return Ok(None),
+                    };
+                }
+                match tail {
+                    Some(tail) => self.lower_expr_to_place(*tail, place, current),
+                    None => {
+                        self.push_unit_assignment(current, place);
+                        Ok(Some(current))
+                    }
+                }
+            }
+            Expr::Unsafe { body } => self.lower_expr_to_place(*body, place, current),
+            Expr::MacroStmts { tail } => self.lower_expr_to_place(*tail, place, current),
+            Expr::If { condition, then_branch, else_branch } => {
+                let (discr, current) = match self.lower_expr_to_operand(*condition, current)? {
+                    Some(it) => it,
+                    None => return Ok(None),
+                };
+                let then_block = self.new_basic_block();
+                let else_block = self.new_basic_block();
+                self.set_terminator(
+                    current,
+                    Terminator::SwitchInt {
+                        discr,
+                        targets: SwitchTargets {
+                            values: vec![(0, else_block)],
+                            otherwise: then_block,
+                        },
+                    },
+                );
+                let then_end = self.lower_expr_to_place(*then_branch, place.clone(), then_block)?;
+                let else_end = match else_branch {
+                    Some(else_branch) => {
+                        self.lower_expr_to_place(*else_branch, place, else_block)?
+                    }
+                    None => {
+                        self.push_unit_assignment(else_block, place);
+                        Some(else_block)
+                    }
+                };
+                Ok(self.merge_blocks(then_end, else_end))
+            }
+            Expr::Loop { body, label } => {
+                let begin = self.new_basic_block();
+                self.set_terminator(current, Terminator::Goto { target: begin });
+                self.loops.push(LoopBlocks { begin, end: None, place, label: *label });
+                let body_end = self.lower_expr_to_temp(*body, begin)?;
+                if let Some(body_end) = body_end {
+                    self.set_terminator(body_end, Terminator::Goto { target: begin });
+                }
+                Ok(self.loops.pop().and_then(|it| it.end))
+            }
+            Expr::While { condition, body, label } => {
+                let begin = self.new_basic_block();
+                self.set_terminator(current, Terminator::Goto { target: begin });
+                let end = self.new_basic_block();
+                self.loops.push(LoopBlocks {
+                    begin,
+                    end: Some(end),
+                    place: place.clone(),
+                    label: *label,
+                });
+                if let Some((discr, after_cond)) = self.lower_expr_to_operand(*condition, begin)? {
+                    let body_block = self.new_basic_block();
+                    self.set_terminator(
+                        after_cond,
+                        Terminator::SwitchInt {
+                            discr,
+                            targets: SwitchTargets {
+                                values: vec![(0, end)],
+                                otherwise: body_block,
+                            },
+                        },
+                    );
+                    if let Some(body_end) = self.lower_expr_to_temp(*body, body_block)? {
+                        self.set_terminator(body_end, Terminator::Goto { target: begin });
+                    }
+                }
+                self.loops.pop();
+                self.push_unit_assignment(end, place);
+                Ok(Some(end))
+            }
+            Expr::Break { expr, label } => {
+                let loop_idx = self.find_loop(label.as_ref())?;
+                let loop_place = self.loops[loop_idx].place.clone();
+                let current = match expr {
+                    Some(expr) => match self.lower_expr_to_place(*expr, loop_place, current)? {
+                        Some(it) => it,
+                        None => return Ok(None),
+                    },
+                    None => {
+                        self.push_unit_assignment(current, loop_place);
+                        current
+                    }
+                };
+                let end = match self.loops[loop_idx].end {
+                    Some(it) => it,
+                    None => {
+                        let end = self.new_basic_block();
+                        self.loops[loop_idx].end = Some(end);
+                        end
+                    }
+                };
+                self.set_terminator(current, Terminator::Goto { target: end });
+                Ok(None)
+            }
+            Expr::Continue { label } => {
+                let loop_idx = self.find_loop(label.as_ref())?;
+                let begin = self.loops[loop_idx].begin;
+                self.set_terminator(current, Terminator::Goto { target: begin });
+                Ok(None)
+            }
+            Expr::Return { expr } => {
+                let current = match expr {
+                    Some(expr) => {
+                        match self.lower_expr_to_place(*expr, self.return_local.into(), current)? {
+                            Some(it) => it,
+                            None => return Ok(None),
+                        }
+                    }
+                    None => {
+                        self.push_unit_assignment(current, self.return_local.into());
+                        current
+                    }
+                };
+                self.set_terminator(current, Terminator::Return);
+                Ok(None)
+            }
+            Expr::Call { callee, args } => {
+                let callee_ty = self.expr_ty_after_adjustments(*callee);
+                if !matches!(callee_ty.kind(Interner), TyKind::FnDef(..) | TyKind::Function(_)) {
+                    return Err(MirLowerError::NotSupported(
+                        "call of a closure or an `Fn` trait object".to_string(),
+                    ));
+                }
+                let (func, current) = match self.lower_expr_to_operand(*callee, current)? {
+                    Some(it) => it,
+                    None => return Ok(None),
+                };
+                self.lower_call(func, args.iter().copied(), place, current)
+            }
+            Expr::MethodCall { receiver, args, .. } => {
+                let (func, subst) =
+                    self.infer.method_resolution(expr_id).ok_or(MirLowerError::UnresolvedMethod)?;
+                let func_ty = self.db.value_ty(func.into()).substitute(Interner, &subst);
+                let func = self.zero_sized_constant(func_ty);
+                let args = std::iter::once(*receiver).chain(args.iter().copied());
+                self.lower_call(func, args, place, current)
+            }
+            Expr::BinaryOp { lhs, rhs, op } => {
+                let op = op.ok_or(MirLowerError::IncompleteExpr)?;
+                match op {
+                    BinaryOp::Assignment { op } => {
+                        let (lhs_place, current) = match self.lower_expr_as_place(*lhs, current)? {
+                            Some(it) => it,
+                            None => return Ok(None),
+                        };
+                        let current = match op {
+                            None => match self.lower_expr_to_place(*rhs, lhs_place, current)? {
+                                Some(it) => it,
+                                None => return Ok(None),
+                            },
+                            Some(op) => {
+                                self.check_builtin_operand(*lhs)?;
+                                let (rhs, current) =
+                                    match self.lower_expr_to_operand(*rhs, current)? {
+                                        Some(it) => it,
+                                        None => return Ok(None),
+                                    };
+                                let lhs = self.operand_for_place(lhs_place.clone());
+                                self.push_assignment(
+                                    current,
+                                    lhs_place,
+                                    Rvalue::BinaryOp(arith_bin_op(op), lhs, rhs),
+                                );
+                                current
+                            }
+                        };
+                        self.push_unit_assignment(current, place);
+                        Ok(Some(current))
+                    }
+                    BinaryOp::LogicOp(op) => {
+                        let current =
+                            match self.lower_expr_to_place(*lhs, place.clone(), current)? {
+                                Some(it) => it,
+                                None => return Ok(None),
+                            };
+                        // `lhs` already is the result if evaluating `rhs` is short circuited.
+                        let rhs_block = self.new_basic_block();
+                        let end = self.new_basic_block();
+                        let values = match op {
+                            LogicOp::And => vec![(0, end)],
+                            LogicOp::Or => vec![(0, rhs_block)],
+                        };
+                        let otherwise = match op {
+                            LogicOp::And => rhs_block,
+                            LogicOp::Or => end,
+                        };
+                        self.set_terminator(
+                            current,
+                            Terminator::SwitchInt {
+                                discr: self.operand_for_place(place.clone()),
+                                targets: SwitchTargets { values, otherwise },
+                            },
+                        );
+                        if let Some(rhs_end) = self.lower_expr_to_place(*rhs, place, rhs_block)? {
+                            self.set_terminator(rhs_end, Terminator::Goto { target: end });
+                        }
+                        Ok(Some(end))
+                    }
+                    BinaryOp::ArithOp(_) | BinaryOp::CmpOp(_) => {
+                        self.check_builtin_operand(*lhs)?;
+                        let (lhs, current) = match self.lower_expr_to_operand(*lhs, current)? {
+                            Some(it) => it,
+                            None => return Ok(None),
+                        };
+                        let (rhs, current) = match self.lower_expr_to_operand(*rhs, current)? {
+                            Some(it) => it,
+                            None => return Ok(None),
+                        };
+                        let op = match op {
+                            BinaryOp::ArithOp(op) => arith_bin_op(op),
+                            BinaryOp::CmpOp(op) => cmp_bin_op(op),
+                            _ => unreachable!(),
+                        };
+                        self.push_assignment(current, place, Rvalue::BinaryOp(op, lhs, rhs));
+                        Ok(Some(current))
+                    }
+                }
+            }
+            Expr::Field { .. } | Expr::Index { .. } | Expr::UnaryOp { op: UnaryOp::Deref, .. } => {
+                let (p, current) =
+                    match self.lower_expr_as_place_without_adjust(expr_id, current)? {
+                        Some(it) => it,
+                        None => return Ok(None),
+                    };
+                let operand = self.operand_for_place(p);
+                self.push_assignment(current, place, Rvalue::Use(operand));
+                Ok(Some(current))
+            }
+            Expr::UnaryOp { expr, op } => {
+                self.check_builtin_operand(*expr)?;
+                let (operand, current) = match self.lower_expr_to_operand(*expr, current)? {
+                    Some(it) => it,
+                    None => return Ok(None),
+                };
+                let op = match op {
+                    UnaryOp::Not => UnOp::Not,
+                    UnaryOp::Neg => UnOp::Neg,
+                    UnaryOp::Deref => unreachable!(),
+                };
+                self.push_assignment(current, place, Rvalue::UnaryOp(op, operand));
+                Ok(Some(current))
+            }
+            Expr::Ref { expr, mutability, .. } => {
+                let (p, current) = match self.lower_expr_as_place(*expr, current)? {
+                    Some(it) => it,
+                    None => return Ok(None),
+                };
+                let mutability = lower_to_chalk_mutability(*mutability);
+                self.push_assignment(current, place, Rvalue::Ref(mutability, p));
+                Ok(Some(current))
+            }
+            Expr::Cast { expr, .. } => {
+                let (operand, current) = match self.lower_expr_to_operand(*expr, current)? {
+                    Some(it) => it,
+                    None => return Ok(None),
+                };
+                let target = self.expr_ty(expr_id);
+                self.push_assignment(current, place, Rvalue::Cast(operand, target));
+                Ok(Some(current))
+            }
+            Expr::Tuple { exprs } => {
+                self.lower_aggregate(AggregateKind::Tuple, exprs.iter().copied(), place, current)
+            }
+            Expr::Array(Array::ElementList(elements)) => {
+                let elem_ty = match self.expr_ty(expr_id).kind(Interner) {
+                    TyKind::Array(elem_ty, _) => elem_ty.clone(),
+                    _ => return Err(MirLowerError::NotSupported("array of unknown type".into())),
+                };
+                let kind = AggregateKind::Array(elem_ty);
+                self.lower_aggregate(kind, elements.iter().copied(), place, current)
+            }
+            Expr::RecordLit { fields, spread, .. } => {
+                if spread.is_some() {
+                    return Err(MirLowerError::NotSupported("functional update syntax".into()));
+                }
+                let variant = self
+                    .infer
+                    .variant_resolution_for_expr(expr_id)
+                    .ok_or_else(|| MirLowerError::UnresolvedName("record literal".to_string()))?;
+                let variant_data = variant.variant_data(self.db.upcast());
+                // Operands are ordered like the fields of the variant, not like in the literal.
+                let exprs = variant_data
+                    .fields()
+                    .iter()
+                    .map(|(_, field)| {
+                        fields
+                            .iter()
+                            .find(|it| it.name == field.name)
+                            .map(|it| it.expr)
+                            .ok_or(MirLowerError::IncompleteExpr)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let kind = self.adt_aggregate_kind(variant, expr_id)?;
+                self.lower_aggregate(kind, exprs, place, current)
+            }
+            Expr::Let { pat, expr } => {
+                let (cond_place, current) = match self.lower_expr_as_place(*expr, current)? {
+                    Some(it) => it,
+                    None => return Ok(None),
+                };
+                let (then_target, else_target) =
+                    self.pattern_match(current, None, cond_place, *pat)?;
+                self.push_assignment(then_target, place.clone(), Rvalue::Use(bool_constant(true)));
+                if let Some(else_target) = else_target {
+                    self.push_assignment(else_target, place, Rvalue::Use(bool_constant(false)));
+                }
+                Ok(self.merge_blocks(Some(then_target), else_target))
+            }
+            Expr::Match { expr, arms } => {
+                let (cond_place, mut current) = match self.lower_expr_as_place(*expr, current)? {
+                    Some(it) => it,
+                    None => return Ok(None),
+                };
+                let mut end = None;
+                for MatchArm { pat, guard, expr } in arms.iter() {
+                    let (then, mut otherwise) =
+                        self.pattern_match(current, None, cond_place.clone(), *pat)?;
+                    let then = match guard {
+                        Some(guard) => match self.lower_expr_to_operand(*guard, then)? {
+                            Some((discr, after_guard)) => {
+                                let guard_failed =
+                                    *otherwise.get_or_insert_with(|| self.new_basic_block());
+                                let then = self.new_basic_block();
+                                self.set_terminator(
+                                    after_guard,
+                                    Terminator::SwitchInt {
+                                        discr,
+                                        targets: SwitchTargets {
+                                            values: vec![(0, guard_failed)],
+                                            otherwise: then,
+                                        },
+                                    },
+                                );
+                                Some(then)
+                            }
+                            None => None,
+                        },
+                        None => Some(then),
+                    };
+                    if let Some(then) = then {
+                        if let Some(arm_end) =
+                            self.lower_expr_to_place(*expr, place.clone(), then)?
+                        {
+                            let end = match end {
+                                Some(it) => it,
+                                None => *end.insert(self.new_basic_block()),
+                            };
+                            self.set_terminator(arm_end, Terminator::Goto { target: end });
+                        }
+                    }
+                    current = match otherwise {
+                        Some(it) => it,
+                        // The remaining arms can't be reached.
+                        None => return Ok(end),
+                    };
+                }
+                // Type checking made sure that the arms are exhaustive.
+                self.set_terminator(current, Terminator::Unreachable);
+                Ok(end)
+            }
+            Expr::For { .. } => Err(MirLowerError::NotSupported("`for` loop".into())),
+            Expr::Closure { .. } => Err(MirLowerError::NotSupported("closure".into())),
+            Expr::Range { .. } => Err(MirLowerError::NotSupported("range expression".into())),
+            Expr::Box { .. } => Err(MirLowerError::NotSupported("`box` expression".into())),
+            Expr::Array(Array::Repeat { .. }) => {
+                Err(MirLowerError::NotSupported("array repeat expression".into()))
+            }
+            Expr::Try { .. } | Expr::TryBlock { .. } => {
+                Err(MirLowerError::NotSupported("`?` operator".into()))
+            }
+            Expr::Async { .. } | Expr::Await { .. } | Expr::Yield { .. } => {
+                Err(MirLowerError::NotSupported("async and generators".into()))
+            }
+            Expr::Const { .. } => Err(MirLowerError::NotSupported("const block".into())),
+        }
+    }
+
+    /// Lowers an expression that denotes a memory location, like `a.b` or `*c`, to a place. Other
+    /// expressions are evaluated into a new temporary.
+    fn lower_expr_as_place(
+        &mut self,
+        expr_id: ExprId,
+        current: BasicBlockId,
+    ) -> Result<Option<(Place, BasicBlockId)>> {
+        let (mut place, current) =
+            match self.lower_expr_as_place_without_adjust(expr_id, current)? {
+                Some(it) => it,
+                None => return Ok(None),
+            };
+        for adjustment in self.adjustments(expr_id) {
+            let Adjustment { kind, target } = adjustment;
+            match kind {
+                Adjust::NeverToAny => {}
+                Adjust::Deref(None) => place.projection.push(ProjectionElem::Deref),
+                Adjust::Borrow(AutoBorrow::Ref(mutability)) => {
+                    let temp = self.new_temp(target.clone());
+                    self.push_assignment(current, temp.into(), Rvalue::Ref(*mutability, place));
+                    place = temp.into();
+                }
+                Adjust::Deref(Some(_)) | Adjust::Borrow(AutoBorrow::RawPtr(_)) => {
+                    return Err(MirLowerError::NotSupported(format!("adjustment {:?}", kind)))
+                }
+                Adjust::Pointer(_) => {
+                    let operand = self.operand_for_place(place);
+                    let temp = self.new_temp(target.clone());
+                    self.push_assignment(
+                        current,
+                        temp.into(),
+                        Rvalue::Cast(operand, target.clone()),
+                    );
+                    place = temp.into();
+                }
+            }
+        }
+        Ok(Some((place, current)))
+    }
+
+    fn lower_expr_as_place_without_adjust(
+        &mut self,
+        expr_id: ExprId,
+        current: BasicBlockId,
+    ) -> Result<Option<(Place, BasicBlockId)>> {
+        match &self.body.exprs[expr_id] {
+            Expr::Path(p) => {
+                let resolver = resolver_for_expr(self.db.upcast(), self.result.owner, expr_id);
+                if let Some(ValueNs::LocalBinding(pat)) =
+                    resolver.resolve_path_in_value_ns_fully(self.db.upcast(), p.mod_path())
+                {
+                    return Ok(Some((self.binding_local(pat)?.into(), current)));
+                }
+            }
+            Expr::Field { expr, name } => {
+                let (mut place, current) = match self.lower_expr_as_place(*expr, current)? {
+                    Some(it) => it,
+                    None => return Ok(None),
+                };
+                let elem = match self.infer.field_resolution(expr_id) {
+                    Some(field) => ProjectionElem::Field(field),
+                    None => match name.as_tuple_index() {
+                        Some(idx) => ProjectionElem::TupleField(idx),
+                        None => return Err(MirLowerError::UnresolvedField),
+                    },
+                };
+                place.projection.push(elem);
+                return Ok(Some((place, current)));
+            }
+            Expr::UnaryOp { expr, op: UnaryOp::Deref } => {
+                if !matches!(
+                    self.expr_ty_after_adjustments(*expr).kind(Interner),
+                    TyKind::Ref(..) | TyKind::Raw(..)
+                ) {
+                    return Err(MirLowerError::NotSupported("overloaded deref".to_string()));
+                }
+                let (mut place, current) = match self.lower_expr_as_place(*expr, current)? {
+                    Some(it) => it,
+                    None => return Ok(None),
+                };
+                place.projection.push(ProjectionElem::Deref);
+                return Ok(Some((place, current)));
+            }
+            Expr::Index { base, index } => {
+                if !matches!(
+                    self.expr_ty_after_adjustments(*base).kind(Interner),
+                    TyKind::Array(..) | TyKind::Slice(..)
+                ) {
+                    return Err(MirLowerError::NotSupported("overloaded index".to_string()));
+                }
+                let (mut place, current) = match self.lower_expr_as_place(*base, current)? {
+                    Some(it) => it,
+                    None => return Ok(None),
+                };
+                let index_local = self.new_temp(self.expr_ty(*index));
+                let current = match self.lower_expr_to_place(*index, index_local.into(), current)? {
+                    Some(it) => it,
+                    None => return Ok(None),
+                };
+                place.projection.push(ProjectionElem::Index(index_local));
+                return Ok(Some((place, current)));
+            }
+            _ => {}
+        }
+        let temp = self.new_temp(self.expr_ty(expr_id));
+        Ok(self
+            .lower_expr_to_place_without_adjust(expr_id, temp.into(), current)?
+            .map(|current| (temp.into(), current)))
+    }
+
+    fn lower_expr_to_operand(
+        &mut self,
+        expr_id: ExprId,
+        current: BasicBlockId,
+    ) -> Result<Option<(Operand, BasicBlockId)>> {
+        if self.adjustments(expr_id).is_empty() {
+            if let Expr::Literal(lit) = &self.body.exprs[expr_id] {
+                return Ok(Some((self.literal_constant(lit.clone(), expr_id), current)));
+            }
+            let ty = self.expr_ty(expr_id);
+            if let (Expr::Path(_), TyKind::FnDef(..)) =
+                (&self.body.exprs[expr_id], ty.kind(Interner))
+            {
+                return Ok(Some((self.zero_sized_constant(ty), current)));
+            }
+        }
+        Ok(self
+            .lower_expr_as_place(expr_id, current)?
+            .map(|(place, current)| (self.operand_for_place(place), current)))
+    }
+
+    fn lower_expr_to_temp(
+        &mut self,
+        expr_id: ExprId,
+        current: BasicBlockId,
+    ) -> Result<Option<BasicBlockId>> {
+        let temp = self.new_temp(self.expr_ty_after_adjustments(expr_id));
+        self.lower_expr_to_place(expr_id, temp.into(), current)
+    }
+
+    fn lower_statement(
+        &mut self,
+        statement: &HirStatement,
+        current: BasicBlockId,
+    ) -> Result<Option<BasicBlockId>> {
+        match statement {
+            HirStatement::Let { pat, initializer, else_branch, .. } => {
+                if self.is_simple_binding(*pat) {
+                    let local = self.new_binding_local(*pat);
+                    return match initializer {
+                        Some(init) => self.lower_expr_to_place(*init, local.into(), current),
+                        None => Ok(Some(current)),
+                    };
+                }
+                let initializer = initializer.ok_or_else(|| {
+                    MirLowerError::NotSupported("`let` pattern without initializer".to_string())
+                })?;
+                let (init_place, current) = match self.lower_expr_as_place(initializer, current)? {
+                    Some(it) => it,
+                    None => return Ok(None),
+                };
+                let (then, otherwise) = self.pattern_match(current, None, init_place, *pat)?;
+                if let Some(otherwise) = otherwise {
+                    let else_end = match else_branch {
+                        Some(else_branch) => self.lower_expr_to_temp(*else_branch, otherwise)?,
+                        None => Some(otherwise),
+                    };
+                    // The `else` branch diverges, and without one the pattern is irrefutable.
+                    if let Some(else_end) = else_end {
+                        self.set_terminator(else_end, Terminator::Unreachable);
+                    }
+                }
+                Ok(Some(then))
+            }
+            HirStatement::Expr { expr, .. } => self.lower_expr_to_temp(*expr, current),
+        }
+    }
+
+    fn lower_call(
+        &mut self,
+        func: Operand,
+        args: impl IntoIterator<Item = ExprId>,
+        destination: Place,
+        mut current: BasicBlockId,
+    ) -> Result<Option<BasicBlockId>> {
+        let mut operands = Vec::new();
+        for arg in args {
+            let (operand, next) = match self.lower_expr_to_operand(arg, current)? {
+                Some(it) => it,
+                None => return Ok(None),
+            };
+            operands.push(operand);
+            current = next;
+        }
+        let diverges =
+            matches!(self.result.locals[destination.local].ty.kind(Interner), TyKind::Never)
+                && destination.projection.is_empty();
+        let target = if diverges { None } else { Some(self.new_basic_block()) };
+        self.set_terminator(
+            current,
+            Terminator::Call { func, args: operands, destination, target },
+        );
+        Ok(target)
+    }
+
+    fn lower_aggregate(
+        &mut self,
+        kind: AggregateKind,
+        exprs: impl IntoIterator<Item = ExprId>,
+        place: Place,
+        mut current: BasicBlockId,
+    ) -> Result<Option<BasicBlockId>> {
+        let mut operands = Vec::new();
+        for expr in exprs {
+            let (operand, next) = match self.lower_expr_to_operand(expr, current)? {
+                Some(it) => it,
+                None => return Ok(None),
+            };
+            operands.push(operand);
+            current = next;
+        }
+        self.push_assignment(current, place, Rvalue::Aggregate(kind, operands));
+        Ok(Some(current))
+    }
+
+    /// Lowers the check whether the value at `cond_place` matches `pattern`, and the assignments
+    /// to the bindings in it. Returns the block in which the pattern has matched and, if the
+    /// pattern is refutable, the block that is entered on a mismatch. That block is
+    /// `current_else` if it is given, and a new one otherwise.
+    fn pattern_match(
+        &mut self,
+        mut current: BasicBlockId,
+        mut current_else: Option<BasicBlockId>,
+        mut cond_place: Place,
+        pattern: PatId,
+    ) -> Result<(BasicBlockId, Option<BasicBlockId>)> {
+        // Default binding modes dereference the matched value implicitly.
+        for _ in self.infer.pat_adjustments.get(&pattern).map_or(&[][..], |it| &**it) {
+            cond_place.projection.push(ProjectionElem::Deref);
+        }
+        Ok(match &self.body.pats[pattern] {
+            Pat::Missing => return Err(MirLowerError::IncompleteExpr),
+            Pat::Wild => (current, current_else),
+            Pat::Bind { subpat, .. } => {
+                let rvalue = match self.infer.pat_binding_modes.get(&pattern) {
+                    Some(BindingMode::Ref(mutability)) => {
+                        Rvalue::Ref(*mutability, cond_place.clone())
+                    }
+                    _ => Rvalue::Use(self.operand_for_place(cond_place.clone())),
+                };
+                if let Some(subpat) = subpat {
+                    let (then, otherwise) =
+                        self.pattern_match(current, current_else, cond_place, *subpat)?;
+                    current = then;
+                    current_else = otherwise;
+                }
+                let local = self.new_binding_local(pattern);
+                self.push_assignment(current, local.into(), rvalue);
+                (current, current_else)
+            }
+            Pat::Ref { pat, .. } => {
+                cond_place.projection.push(ProjectionElem::Deref);
+                self.pattern_match(current, current_else, cond_place, *pat)?
+            }
+            Pat::Tuple { args, ellipsis } => {
+                let arity = match self.infer.type_of_pat[pattern].as_tuple() {
+                    Some(subst) => subst.len(Interner),
+                    None => {
+                        return Err(MirLowerError::NotSupported(
+                            "tuple pattern of unknown type".to_string(),
+                        ))
+                    }
+                };
+                let fields = expand_ellipsis(args, *ellipsis, arity)
+                    .map(|(idx, pat)| (ProjectionElem::TupleField(idx), pat));
+                self.pattern_match_fields(current, current_else, &cond_place, fields)?
+            }
+            Pat::TupleStruct { args, ellipsis, .. } => {
+                let variant = self.variant_for_pat(pattern)?;
+                let (current, current_else) =
+                    self.pattern_match_variant(current, current_else, &cond_place, variant);
+                let variant_data = variant.variant_data(self.db.upcast());
+                let field_ids = variant_data.fields().iter().map(|(id, _)| id).collect::<Vec<_>>();
+                let fields = expand_ellipsis(args, *ellipsis, field_ids.len())
+                    .map(|(idx, pat)| {
+                        let local_id = *field_ids.get(idx).ok_or(MirLowerError::UnresolvedField)?;
+                        Ok((ProjectionElem::Field(FieldId { parent: variant, local_id }), pat))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                self.pattern_match_fields(current, current_else, &cond_place, fields)?
+            }
+            Pat::Record { args, .. } => {
+                let variant = self.variant_for_pat(pattern)?;
+                let (current, current_else) =
+                    self.pattern_match_variant(current, current_else, &cond_place, variant);
+                let variant_data = variant.variant_data(self.db.upcast());
+                let fields = args
+                    .iter()
+                    .map(|arg| {
+                        let local_id =
+                            variant_data.field(&arg.name).ok_or(MirLowerError::UnresolvedField)?;
+                        Ok((ProjectionElem::Field(FieldId { parent: variant, local_id }), arg.pat))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                self.pattern_match_fields(current, current_else, &cond_place, fields)?
+            }
+            Pat::Path(_) => match self.infer.variant_resolution_for_pat(pattern) {
+                Some(variant) => {
+                    self.pattern_match_variant(current, current_else, &cond_place, variant)
+                }
+                None => {
+                    return Err(MirLowerError::NotSupported("constant pattern".to_string()));
+                }
+            },
+            Pat::Lit(expr) => {
+                if !matches!(self.infer.type_of_pat[pattern].kind(Interner), TyKind::Scalar(_)) {
+                    return Err(MirLowerError::NotSupported(
+                        "non-primitive literal pattern".to_string(),
+                    ));
+                }
+                let (value, current) = self
+                    .lower_expr_to_operand(*expr, current)?
+                    .ok_or(MirLowerError::IncompleteExpr)?;
+                let is_eq = self.new_temp(TyKind::Scalar(Scalar::Bool).intern(Interner));
+                let lhs = self.operand_for_place(cond_place);
+                self.push_assignment(
+                    current,
+                    is_eq.into(),
+                    Rvalue::BinaryOp(BinOp::Eq, lhs, value),
+                );
+                let then = self.new_basic_block();
+                let else_target = current_else.unwrap_or_else(|| self.new_basic_block());
+                self.set_terminator(
+                    current,
+                    Terminator::SwitchInt {
+                        discr: Operand::Copy(is_eq.into()),
+                        targets: SwitchTargets { values: vec![(0, else_target)], otherwise: then },
+                    },
+                );
+                (then, Some(else_target))
+            }
+            Pat::Or(pats) => {
+                if self.pat_has_bindings(pattern) {
+                    return Err(MirLowerError::NotSupported(
+                        "or-pattern with bindings".to_string(),
+                    ));
+                }
+                let then = self.new_basic_block();
+                let mut next = Some(current);
+                for &pat in pats.iter() {
+                    let current = match next {
+                        Some(it) => it,
+                        // The remaining alternatives can't be reached.
+                        None => break,
+                    };
+                    let (alt_then, alt_else) =
+                        self.pattern_match(current, None, cond_place.clone(), pat)?;
+                    self.set_terminator(alt_then, Terminator::Goto { target: then });
+                    next = alt_else;
+                }
+                let else_target = next.map(|next| match current_else {
+                    Some(current_else) => {
+                        self.set_terminator(next, Terminator::Goto { target: current_else });
+                        current_else
+                    }
+                    None => next,
+                });
+                (then, else_target)
+            }
+            Pat::Range { .. } => {
+                return Err(MirLowerError::NotSupported("range pattern".to_string()))
+            }
+            Pat::Slice { .. } => {
+                return Err(MirLowerError::NotSupported("slice pattern".to_string()))
+            }
+            Pat::Box { .. } => {
+                return Err(MirLowerError::NotSupported("`box` pattern".to_string()))
+            }
+            Pat::ConstBlock(_) => {
+                return Err(MirLowerError::NotSupported("const block pattern".to_string()))
+            }
+        })
+    }
+
+    /// Like [`Self::pattern_match`], for patterns that must match, like the ones of parameters.
+    fn pattern_match_irrefutable(
+        &mut self,
+        current: BasicBlockId,
+        cond_place: Place,
+        pattern: PatId,
+    ) -> Result<BasicBlockId> {
+        let (then, otherwise) = self.pattern_match(current, None, cond_place, pattern)?;
+        if let Some(otherwise) = otherwise {
+            self.set_terminator(otherwise, Terminator::Unreachable);
+        }
+        Ok(then)
+    }
+
+    fn pattern_match_fields(
+        &mut self,
+        mut current: BasicBlockId,
+        mut current_else: Option<BasicBlockId>,
+        cond_place: &Place,
+        fields: impl IntoIterator<Item = (ProjectionElem, PatId)>,
+    ) -> Result<(BasicBlockId, Option<BasicBlockId>)> {
+        for (elem, pat) in fields {
+            let mut place = cond_place.clone();
+            place.projection.push(elem);
+            let (then, otherwise) = self.pattern_match(current, current_else, place, pat)?;
+            current = then;
+            current_else = otherwise;
+        }
+        Ok((current, current_else))
+    }
+
+    /// Checks that the value at `cond_place` is of the given variant. Structs always are.
+    fn pattern_match_variant(
+        &mut self,
+        current: BasicBlockId,
+        current_else: Option<BasicBlockId>,
+        cond_place: &Place,
+        variant: VariantId,
+    ) -> (BasicBlockId, Option<BasicBlockId>) {
+        let variant = match variant {
+            VariantId::EnumVariantId(it) => it,
+            VariantId::StructId(_) | VariantId::UnionId(_) => return (current, current_else),
+        };
+        let discr = self.new_temp(TyKind::Scalar(Scalar::Int(IntTy::Isize)).intern(Interner));
+        self.push_assignment(current, discr.into(), Rvalue::Discriminant(cond_place.clone()));
+        let then = self.new_basic_block();
+        let else_target = current_else.unwrap_or_else(|| self.new_basic_block());
+        let value = u32::from(variant.local_id.into_raw()) as u128;
+        self.set_terminator(
+            current,
+            Terminator::SwitchInt {
+                discr: Operand::Copy(discr.into()),
+                targets: SwitchTargets { values: vec![(value, then)], otherwise: else_target },
+            },
+        );
+        (then, Some(else_target))
+    }
+
+    fn variant_for_pat(&self, pat: PatId) -> Result<VariantId> {
+        self.infer
+            .variant_resolution_for_pat(pat)
+            .ok_or_else(|| MirLowerError::UnresolvedName("pattern".to_string()))
+    }
+
+    fn pat_has_bindings(&self, pat: PatId) -> bool {
+        let pat_data = &self.body.pats[pat];
+        let mut res = matches!(pat_data, Pat::Bind { .. });
+        pat_data.walk_child_pats(|it| res |= self.pat_has_bindings(it));
+        res
+    }
+
+    /// Whether the pattern just binds the whole value by move, which doesn't need any matching.
+    fn is_simple_binding(&self, pat: PatId) -> bool {
+        matches!(self.body.pats[pat], Pat::Bind { subpat: None, .. })
+            && !matches!(self.infer.pat_binding_modes.get(&pat), Some(BindingMode::Ref(_)))
+    }
+
+    fn new_binding_local(&mut self, pat: PatId) -> LocalId {
+        let ty = self.infer.type_of_pat[pat].clone();
+        let local = self.result.locals.alloc(Local { ty, binding: Some(pat) });
+        self.binding_locals.insert(pat, local);
+        local
+    }
+
+    /// The type of the value matched against the pattern, before default binding modes
+    /// dereferenced it.
+    fn pat_ty_before_adjustments(&self, pat: PatId) -> Ty {
+        match self.infer.pat_adjustments.get(&pat).and_then(|it| it.first()) {
+            Some(ty) => ty.clone(),
+            None => self.infer.type_of_pat[pat].clone(),
+        }
+    }
+
+    fn binding_local(&self, pat: PatId) -> Result<LocalId> {
+        self.binding_locals.get(pat).copied().ok_or_else(|| {
+            MirLowerError::UnresolvedName(match &self.body.pats[pat] {
+                Pat::Bind { name, .. } => name.to_string(),
+                _ => "binding".to_string(),
+            })
+        })
+    }
+
+    fn find_loop(&self, label: Option<&Name>) -> Result<usize> {
+        let idx = match label {
+            None => self.loops.len().checked_sub(1),
+            Some(label) => self
+                .loops
+                .iter()
+                .rposition(|it| it.label.map_or(false, |it| self.body.labels[it].name == *label)),
+        };
+        idx.ok_or(MirLowerError::LoopOutsideOfLoop)
+    }
+
+    fn merge_blocks(
+        &mut self,
+        b1: Option<BasicBlockId>,
+        b2: Option<BasicBlockId>,
+    ) -> Option<BasicBlockId> {
+        match (b1, b2) {
+            (None, None) => None,
+            (Some(b), None) | (None, Some(b)) => Some(b),
+            (Some(b1), Some(b2)) => {
+                let end = self.new_basic_block();
+                self.set_terminator(b1, Terminator::Goto { target: end });
+                self.set_terminator(b2, Terminator::Goto { target: end });
+                Some(end)
+            }
+        }
+    }
+
+    fn check_builtin_operand(&self, expr_id: ExprId) -> Result<()> {
+        match self.expr_ty_after_adjustments(expr_id).kind(Interner) {
+            TyKind::Scalar(_) | TyKind::Raw(..) => Ok(()),
+            _ => Err(MirLowerError::NotSupported("overloaded operator".to_string())),
+        }
+    }
+
+    fn adjustments(&self, expr_id: ExprId) -> &'a [Adjustment] {
+        self.infer.expr_adjustments.get(&expr_id).map_or(&[], |it| &**it)
+    }
+
+    fn expr_ty(&self, expr_id: ExprId) -> Ty {
+        self.infer[expr_id].clone()
+    }
+
+    fn expr_ty_after_adjustments(&self, expr_id: ExprId) -> Ty {
+        match self.adjustments(expr_id).last() {
+            Some(adjustment) => adjustment.target.clone(),
+            None => self.expr_ty(expr_id),
+        }
+    }
+
+    fn operand_for_place(&self, place: Place) -> Operand {
+        // FIXME: we don't know which types implement `Copy` here, so only the trivially copyable
+        // ones are copied.
+        let ty = self.place_ty(&place);
+        match ty.kind(Interner) {
+            TyKind::Scalar(_)
+            | TyKind::Ref(Mutability::Not, ..)
+            | TyKind::Raw(..)
+            | TyKind::FnDef(..)
+            | TyKind::Function(_)
+            | TyKind::Never => Operand::Copy(place),
+            _ => Operand::Move(place),
+        }
+    }
+
+    fn place_ty(&self, place: &Place) -> Ty {
+        let mut ty = self.result.locals[place.local].ty.clone();
+        for elem in &place.projection {
+            ty = match elem {
+                ProjectionElem::Deref => match ty.as_reference_or_ptr() {
+                    Some((inner, ..)) => inner.clone(),
+                    None => return TyKind::Error.intern(Interner),
+                },
+                ProjectionElem::Index(_) => match ty.kind(Interner) {
+                    TyKind::Array(inner, _) | TyKind::Slice(inner) => inner.clone(),
+                    _ => return TyKind::Error.intern(Interner),
+                },
+                ProjectionElem::TupleField(idx) => match ty.as_tuple() {
+                    Some(subst) => subst
+                        .as_slice(Interner)
+                        .get(*idx)
+                        .and_then(|it| it.ty(Interner))
+                        .cloned()
+                        .unwrap_or_else(|| TyKind::Error.intern(Interner)),
+                    None => return TyKind::Error.intern(Interner),
+                },
+                ProjectionElem::Field(field) => match ty.kind(Interner) {
+                    TyKind::Adt(_, subst) => self.db.field_types(field.parent)[field.local_id]
+                        .clone()
+                        .substitute(Interner, subst),
+                    _ => return TyKind::Error.intern(Interner),
+                },
+            };
+        }
+        ty
+    }
+
+    fn literal_constant(&self, lit: Literal, expr_id: ExprId) -> Operand {
+        Operand::Constant(Constant { kind: ConstantKind::Literal(lit), ty: self.expr_ty(expr_id) })
+    }
+
+    fn zero_sized_constant(&self, ty: Ty) -> Operand {
+        Operand::Constant(Constant { kind: ConstantKind::ZeroSized, ty })
+    }
+
+    fn adt_aggregate_kind(&self, variant: VariantId, expr_id: ExprId) -> Result<AggregateKind> {
+        match self.expr_ty(expr_id).kind(Interner) {
+            TyKind::Adt(_, subst) => Ok(AggregateKind::Adt(variant, subst.clone())),
+            _ => Err(MirLowerError::NotSupported("ADT literal of unknown type".to_string())),
+        }
+    }
+
+    fn unit_adt_aggregate(&self, variant: VariantId, expr_id: ExprId) -> Result<Rvalue> {
+        Ok(Rvalue::Aggregate(self.adt_aggregate_kind(variant, expr_id)?, Vec::new()))
+    }
+
+    fn push_assignment(&mut self, block: BasicBlockId, place: Place, rvalue: Rvalue) {
+        self.result.basic_blocks[block].statements.push(Statement::Assign(place, rvalue));
+    }
+
+    fn push_unit_assignment(&mut self, block: BasicBlockId, place: Place) {
+        let unit = self.zero_sized_constant(TyBuilder::unit());
+        self.push_assignment(block, place, Rvalue::Use(unit));
+    }
+
+    fn set_terminator(&mut self, block: BasicBlockId, terminator: Terminator) {
+        self.result.basic_blocks[block].terminator = Some(terminator);
+    }
+
+    fn new_basic_block(&mut self) -> BasicBlockId {
+        self.result.basic_blocks.alloc(BasicBlock::default())
+    }
+
+    fn new_temp(&mut self, ty: Ty) -> LocalId {
+        self.result.locals.alloc(Local { ty, binding: None })
+    }
+}
+
+fn bool_constant(value: bool) -> Operand {
+    Operand::Constant(Constant {
+        kind: ConstantKind::Literal(Literal::Bool(value)),
+        ty: TyKind::Scalar(Scalar::Bool).intern(Interner),
+    })
+}
+
+/// Pairs the subpatterns of a tuple or tuple struct pattern with the index of the field they match.
+fn expand_ellipsis(
+    args: &[PatId],
+    ellipsis: Option<usize>,
+    arity: usize,
+) -> impl Iterator<Item = (usize, PatId)> + '_ {
+    let (before, after) = args.split_at(ellipsis.unwrap_or(args.len()).min(args.len()));
+    let after_start = arity.saturating_sub(after.len());
+    before
+        .iter()
+        .copied()
+        .enumerate()
+        .chain(after.iter().copied().enumerate().map(move |(idx, pat)| (after_start + idx, pat)))
+}
+
+fn arith_bin_op(op: ArithOp) -> BinOp {
+    match op {
+        ArithOp::Add => BinOp::Add,
+        ArithOp::Mul => BinOp::Mul,
+        ArithOp::Sub => BinOp::Sub,
+        ArithOp::Div => BinOp::Div,
+        ArithOp::Rem => BinOp::Rem,
+        ArithOp::Shl => BinOp::Shl,
+        ArithOp::Shr => BinOp::Shr,
+        ArithOp::BitXor => BinOp::BitXor,
+        ArithOp::BitOr => BinOp::BitOr,
+        ArithOp::BitAnd => BinOp::BitAnd,
+    }
+}
+
+fn cmp_bin_op(op: CmpOp) -> BinOp {
+    match op {
+        CmpOp::Eq { negated: false } => BinOp::Eq,
+        CmpOp::Eq { negated: true } => BinOp::Ne,
+        CmpOp::Ord { ordering: Ordering::Less, strict: true } => BinOp::Lt,
+        CmpOp::Ord { ordering: Ordering::Less, strict: false } => BinOp::Le,
+        CmpOp::Ord { ordering: Ordering::Greater, strict: true } => BinOp::Gt,
+        CmpOp::Ord { ordering: Ordering::Greater, strict: false } => BinOp::Ge,
+    }
+}
@@ -0,0 +1,208 @@
+//! A pretty-printer for MIR.
+
+use chalk_ir::Mutability;
+use hir_def::{
+    adt::StructKind,
+    body::Body,
+    expr::{Literal, Pat},
+    VariantId,
+};
+use stdx::format_to;
+
+use crate::{db::HirDatabase, display::HirDisplay, CallableDefId, Interner, TyKind};
+
+use super::{
+    AggregateKind, BasicBlockId, ConstantKind, LocalId, MirBody, Operand, Place, ProjectionElem,
+    Rvalue, Statement, Terminator,
+};
+
+impl MirBody {
+    pub fn pretty_print(&self, db: &dyn HirDatabase) -> String {
+        let body = db.body(self.owner);
+        let mut ctx = MirPrettyCtx { body: self, hir_body: &body, db, result: String::new() };
+        ctx.print_locals();
+        for (id, block) in self.basic_blocks.iter() {
+            format_to!(ctx.result, "\n{}: {{\n", ctx.block_name(id));
+            for statement in &block.statements {
+                match statement {
+                    Statement::Assign(place, rvalue) => {
+                        let place = ctx.place(place);
+                        let rvalue = ctx.rvalue(rvalue);
+                        format_to!(ctx.result, "    {} = {};\n", place, rvalue);
+                    }
+                }
+            }
+            if let Some(terminator) = &block.terminator {
+                let terminator = ctx.terminator(terminator);
+                format_to!(ctx.result, "    {};\n", terminator);
+            }
+            ctx.result.push_str("}\n");
+        }
+        ctx.result
+    }
+}
+
+struct MirPrettyCtx<'a> {
+    body: &'a MirBody,
+    hir_body: &'a Body,
+    db: &'a dyn HirDatabase,
+    result: String,
+}
+
+impl MirPrettyCtx<'_> {
+    fn print_locals(&mut self) {
+        for (id, local) in self.body.locals.iter() {
+            format_to!(self.result, "let {}: {};", self.local_name(id), local.ty.display(self.db));
+            if let Some(Pat::Bind { name, .. }) = local.binding.map(|it| &self.hir_body.pats[it]) {
+                format_to!(self.result, " // {}", name);
+            }
+            self.result.push('\n');
+        }
+    }
+
+    fn local_name(&self, local: LocalId) -> String {
+        format!("_{}", u32::from(local.into_raw()))
+    }
+
+    fn block_name(&self, block: BasicBlockId) -> String {
+        format!("bb{}", u32::from(block.into_raw()))
+    }
+
+    fn place(&self, place: &Place) -> String {
+        let mut res = self.local_name(place.local);
+        for elem in &place.projection {
+            match elem {
+                ProjectionElem::Deref => res = format!("(*{})", res),
+                ProjectionElem::Field(field) => {
+                    if let VariantId::EnumVariantId(it) = field.parent {
+                        let name = &self.db.enum_data(it.parent).variants[it.local_id].name;
+                        res = format!("({} as {})", res, name);
+                    }
+                    let data = field.parent.variant_data(self.db.upcast());
+                    format_to!(res, ".{}", data.fields()[field.local_id].name);
+                }
+                ProjectionElem::TupleField(idx) => format_to!(res, ".{}", idx),
+                ProjectionElem::Index(local) => format_to!(res, "[{}]", self.local_name(*local)),
+            }
+        }
+        res
+    }
+
+    fn operand(&self, operand: &Operand) -> String {
+        match operand {
+            Operand::Copy(place) => self.place(place),
+            Operand::Move(place) => format!("move {}", self.place(place)),
+            Operand::Constant(constant) => match &constant.kind {
+                ConstantKind::Literal(lit) => format!("const {}", literal(lit)),
+                ConstantKind::ZeroSized => match constant.ty.kind(Interner) {
+                    TyKind::FnDef(def, _) => {
+                        let name = match self.db.lookup_intern_callable_def((*def).into()) {
+                            CallableDefId::FunctionId(it) => self.db.function_data(it).name.clone(),
+                            CallableDefId::StructId(it) => self.db.struct_data(it).name.clone(),
+                            CallableDefId::EnumVariantId(it) => {
+                                self.db.enum_data(it.parent).variants[it.local_id].name.clone()
+                            }
+                        };
+                        format!("const {}", name)
+                    }
+                    _ => format!("const {}", constant.ty.display(self.db)),
+                },
+            },
+        }
+    }
+
+    fn operands(&self, operands: &[Operand]) -> String {
+        operands.iter().map(|it| self.operand(it)).collect::<Vec<_>>().join(", ")
+    }
+
+    fn rvalue(&self, rvalue: &Rvalue) -> String {
+        match rvalue {
+            Rvalue::Use(operand) => self.operand(operand),
+            Rvalue::Ref(Mutability::Not, place) => format!("&{}", self.place(place)),
+            Rvalue::Ref(Mutability::Mut, place) => format!("&mut {}", self.place(place)),
+            Rvalue::BinaryOp(op, lhs, rhs) => {
+                format!("{:?}({}, {})", op, self.operand(lhs), self.operand(rhs))
+            }
+            Rvalue::UnaryOp(op, operand) => format!("{:?}({})", op, self.operand(operand)),
+            Rvalue::Cast(operand, ty) => {
+                format!("{} as {}", self.operand(operand), ty.display(self.db))
+            }
+            Rvalue::Discriminant(place) => format!("discriminant({})", self.place(place)),
+            Rvalue::Aggregate(AggregateKind::Tuple, operands) => {
+                format!("({})", self.operands(operands))
+            }
+            Rvalue::Aggregate(AggregateKind::Array(_), operands) => {
+                format!("[{}]", self.operands(operands))
+            }
+            Rvalue::Aggregate(AggregateKind::Adt(variant, _), operands) => {
+                let name = match variant {
+                    VariantId::StructId(it) => self.db.struct_data(*it).name.to_string(),
+                    VariantId::UnionId(it) => self.db.union_data(*it).name.to_string(),
+                    VariantId::EnumVariantId(it) => {
+                        let enum_data = self.db.enum_data(it.parent);
+                        format!("{}::{}", enum_data.name, enum_data.variants[it.local_id].name)
+                    }
+                };
+                let data = variant.variant_data(self.db.upcast());
+                match data.kind() {
+                    StructKind::Unit => name,
+                    StructKind::Tuple => format!("{}({})", name, self.operands(operands)),
+                    StructKind::Record => {
+                        let fields = data
+                            .fields()
+                            .iter()
+                            .zip(operands)
+                            .map(|((_, field), operand)| {
+                                format!("{}: {}", field.name, self.operand(operand))
+                            })
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        format!("{} {{ {} }}", name, fields)
+                    }
+                }
+            }
+        }
+    }
+
+    fn terminator(&self, terminator: &Terminator) -> String {
+        match terminator {
+            Terminator::Goto { target } => format!("goto -> {}", self.block_name(*target)),
+            Terminator::SwitchInt { discr, targets } => {
+                let mut arms = targets
+                    .values
+                    .iter()
+                    .map(|(value, target)| format!("{}: {}", value, self.block_name(*target)))
+                    .collect::<Vec<_>>();
+                arms.push(format!("otherwise: {}", self.block_name(targets.otherwise)));
+                format!("switchInt({}) -> [{}]", self.operand(discr), arms.join(", "))
+            }
+            Terminator::Return => "return".to_string(),
+            Terminator::Unreachable => "unreachable".to_string(),
+            Terminator::Call { func, args, destination, target } => {
+                let target = match target {
+                    Some(it) => self.block_name(*it),
+                    None => "!".to_string(),
+                };
+                format!(
+                    "{} = {}({}) -> {}",
+                    self.place(destination),
+                    self.operand(func),
+                    self.operands(args),
+                    target
+                )
+            }
+        }
+    }
+}
+
+fn literal(lit: &Literal) -> String {
+    match lit {
+        Literal::String(it) => format!("{:?}", it),
+        Literal::ByteString(it) => format!("b{:?}", String::from_utf8_lossy(it)),
+        Literal::Char(it) => format!("{:?}", it),
+        Literal::Bool(it) => it.to_string(),
+        Literal::Int(it, _) => it.to_string(),
+        Literal::Uint(it, _) => it.to_string(),
+        Literal::Float(it, _) => f64::from_bits(*it).to_string(),
+    }
+}
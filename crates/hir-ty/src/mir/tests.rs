@@ -0,0 +1,520 @@
+use base_db::fixture::WithFixture;
+use expect_test::{expect, Expect};
+use hir_def::{db::DefDatabase, ModuleDefId};
+
+use crate::{db::HirDatabase, test_db::TestDB};
+
+use super::MirLowerError;
+
+fn lower_goal(ra_fixture: &str) -> (TestDB, Result<std::sync::Arc<super::MirBody>, MirLowerError>) {
+    let (db, file_id) = TestDB::with_single_file(ra_fixture);
+    let module_id = db.module_for_file(file_id);
+    let def_map = module_id.def_map(&db);
+    let scope = &def_map[module_id.local_id].scope;
+    let func = scope
+        .declarations()
+        .find_map(|it| match it {
+            ModuleDefId::FunctionId(it) if db.function_data(it).name.to_string() == "goal" => {
+                Some(it)
+            }
+            _ => None,
+        })
+        .unwrap();
+    let result = db.mir_body(func.into());
+    (db, result)
+}
+
+fn check(ra_fixture: &str, expect: Expect) {
+    let (db, result) = lower_goal(ra_fixture);
+    expect.assert_eq(&result.unwrap().pretty_print(&db));
+}
+
+fn check_fail(ra_fixture: &str, error: MirLowerError) {
+    assert_eq!(lower_goal(ra_fixture).1, Err(error));
+}
+
+#[test]
+fn arithmetic() {
+    check(
+        r#"
+fn goal(a: i32, b: i32) -> i32 {
+    let c = a * 2;
+    c + b
+}
+"#,
+        expect![[r#"
+            let _0: i32;
+            let _1: i32; // a
+            let _2: i32; // b
+            let _3: i32; // c
+
+            bb0: {
+                _3 = Mul(_1, const 2);
+                _0 = Add(_3, _2);
+                return;
+            }
+        "#]],
+    );
+}
+
+#[test]
+fn if_else_and_calls() {
+    check(
+        r#"
+fn double(x: u32) -> u32 { x * 2 }
+fn goal(x: u32) -> u32 {
+    if x > 5 && x < 10 {
+        double(x)
+    } else {
+        return 0;
+    }
+}
+"#,
+        expect![[r#"
+            let _0: u32;
+            let _1: u32; // x
+            let _2: bool;
+            let _3: !;
+
+            bb0: {
+                _2 = Gt(_1, const 5);
+                switchInt(_2) -> [0: bb2, otherwise: bb1];
+            }
+
+            bb1: {
+                _2 = Lt(_1, const 10);
+                goto -> bb2;
+            }
+
+            bb2: {
+                switchInt(_2) -> [0: bb4, otherwise: bb3];
+            }
+
+            bb3: {
+                _0 = const double(_1) -> bb5;
+            }
+
+            bb4: {
+                _0 = const 0;
+                return;
+            }
+
+            bb5: {
+                return;
+            }
+        "#]],
+    );
+}
+
+#[test]
+fn loops() {
+    check(
+        r#"
+fn goal() -> i32 {
+    let mut i = 0;
+    while i < 10 {
+        i += 1;
+    }
+    loop {
+        if i == 0 {
+            break i;
+        }
+        i -= 1;
+    }
+}
+"#,
+        expect![[r#"
+            let _0: i32;
+            let _1: i32; // i
+            let _2: ();
+            let _3: bool;
+            let _4: ();
+            let _5: ();
+            let _6: ();
+            let _7: ();
+            let _8: bool;
+            let _9: !;
+            let _10: ();
+
+            bb0: {
+                _1 = const 0;
+                goto -> bb1;
+            }
+
+            bb1: {
+                _3 = Lt(_1, const 10);
+                switchInt(_3) -> [0: bb2, otherwise: bb3];
+            }
+
+            bb2: {
+                _2 = const ();
+                goto -> bb4;
+            }
+
+            bb3: {
+                _1 = Add(_1, const 1);
+                _5 = const ();
+                _4 = const ();
+                goto -> bb1;
+            }
+
+            bb4: {
+                _8 = Eq(_1, const 0);
+                switchInt(_8) -> [0: bb6, otherwise: bb5];
+            }
+
+            bb5: {
+                _0 = _1;
+                goto -> bb7;
+            }
+
+            bb6: {
+                _7 = const ();
+                _1 = Sub(_1, const 1);
+                _10 = const ();
+                _6 = const ();
+                goto -> bb4;
+            }
+
+            bb7: {
+                return;
+            }
+        "#]],
+    );
+}
+
+#[test]
+fn places_and_method_calls() {
+    check(
+        r#"
+struct Foo { x: (u8, u8), y: [u8; 2] }
+impl Foo {
+    fn first(&self) -> u8 { self.x.0 }
+}
+fn goal(foo: &mut Foo, i: usize) -> u8 {
+    foo.x.1 = foo.y[i];
+    let bar = Foo { y: [1, 2], x: (3, 4) };
+    bar.first()
+}
+"#,
+        expect![[r#"
+            let _0: u8;
+            let _1: &mut Foo; // foo
+            let _2: usize; // i
+            let _3: ();
+            let _4: usize;
+            let _5: Foo; // bar
+            let _6: (u8, u8);
+            let _7: [u8; 2];
+            let _8: &Foo;
+
+            bb0: {
+                _4 = _2;
+                (*_1).x.1 = (*_1).y[_4];
+                _3 = const ();
+                _6 = (const 3, const 4);
+                _7 = [const 1, const 2];
+                _5 = Foo { x: move _6, y: move _7 };
+                _8 = &_5;
+                _0 = const first(_8) -> bb1;
+            }
+
+            bb1: {
+                return;
+            }
+        "#]],
+    );
+}
+
+#[test]
+fn match_and_let_patterns() {
+    check(
+        r#"
+enum Option<T> { None, Some(T) }
+use Option::*;
+fn goal(x: &Option<(u8, bool)>) -> u8 {
+    let y = match x {
+        Some((0, _)) | None => 0,
+        Some((n, flag)) if *flag => *n,
+        _ => 1,
+    };
+    if let Some((n, _)) = *x { n + y } else { y }
+}
+"#,
+        expect![[r#"
+            let _0: u8;
+            let _1: &Option<(u8, bool)>; // x
+            let _2: u8; // y
+            let _3: isize;
+            let _4: bool;
+            let _5: isize;
+            let _6: isize;
+            let _7: &u8; // n
+            let _8: &bool; // flag
+            let _9: bool;
+            let _10: isize;
+            let _11: u8; // n
+
+            bb0: {
+                _3 = discriminant((*_1));
+                switchInt(_3) -> [1: bb2, otherwise: bb3];
+            }
+
+            bb1: {
+                _2 = const 0;
+                goto -> bb7;
+            }
+
+            bb2: {
+                _4 = Eq(((*_1) as Some).0.0, const 0);
+                switchInt(_4) -> [0: bb3, otherwise: bb4];
+            }
+
+            bb3: {
+                _5 = discriminant((*_1));
+                switchInt(_5) -> [0: bb5, otherwise: bb6];
+            }
+
+            bb4: {
+                goto -> bb1;
+            }
+
+            bb5: {
+                goto -> bb1;
+            }
+
+            bb6: {
+                _6 = discriminant((*_1));
+                switchInt(_6) -> [1: bb8, otherwise: bb9];
+            }
+
+            bb7: {
+                _10 = discriminant((*_1));
+                switchInt(_10) -> [1: bb11, otherwise: bb12];
+            }
+
+            bb8: {
+                _7 = &((*_1) as Some).0.0;
+                _8 = &((*_1) as Some).0.1;
+                switchInt((*_8)) -> [0: bb9, otherwise: bb10];
+            }
+
+            bb9: {
+                _2 = const 1;
+                goto -> bb7;
+            }
+
+            bb10: {
+                _2 = (*_7);
+                goto -> bb7;
+            }
+
+            bb11: {
+                _11 = ((*_1) as Some).0.0;
+                _9 = const true;
+                goto -> bb13;
+            }
+
+            bb12: {
+                _9 = const false;
+                goto -> bb13;
+            }
+
+            bb13: {
+                switchInt(_9) -> [0: bb15, otherwise: bb14];
+            }
+
+            bb14: {
+                _0 = Add(_11, _2);
+                goto -> bb16;
+            }
+
+            bb15: {
+                _0 = _2;
+                goto -> bb16;
+            }
+
+            bb16: {
+                return;
+            }
+        "#]],
+    );
+}
+
+#[test]
+fn unsupported() {
+    check_fail(
+        r#"
+fn goal(x: [u8; 2]) -> u8 {
+    match x {
+        [0, y] => y,
+        _ => 0,
+    }
+}
+"#,
+        MirLowerError::NotSupported("slice pattern".to_string()),
+    );
+}
+
+fn check_eval(ra_fixture: &str, expect: Expect) {
+    let (db, result) = lower_goal(ra_fixture);
+    let result = super::interpret_mir(&db, &result.unwrap());
+    let actual = match result {
+        Ok(value) => value.display(&db),
+        Err(e) => format!("{:?}", e),
+    };
+    expect.assert_eq(&actual);
+}
+
+#[test]
+fn eval_recursion() {
+    check_eval(
+        r#"
+fn fib(n: u64) -> u64 {
+    if n < 2 {
+        return n;
+    }
+    fib(n - 1) + fib(n - 2)
+}
+fn goal() -> u64 {
+    fib(20)
+}
+"#,
+        expect![[r#"6765"#]],
+    );
+}
+
+#[test]
+fn eval_aggregates_and_references() {
+    check_eval(
+        r#"
+struct Point { x: i32, y: i32 }
+enum Shape { Dot(Point), Empty }
+fn shift(p: &mut Point, by: i32) {
+    p.x += by;
+    (*p).y -= by;
+}
+fn goal() -> (Shape, [bool; 2], Shape) {
+    let mut p = Point { x: 1, y: 2 };
+    shift(&mut p, 3);
+    let arr = [p.x > 0, p.y > 0];
+    (Shape::Dot(p), arr, Shape::Empty)
+}
+"#,
+        expect![[r#"(Dot(Point { x: 4, y: -1 }), [true, false], Empty)"#]],
+    );
+}
+
+#[test]
+fn eval_trait_methods() {
+    check_eval(
+        r#"
+trait Area {
+    fn area(&self) -> u32;
+    fn double_area(&self) -> u32 { self.area() * 2 }
+}
+struct Square(u32);
+impl Area for Square {
+    fn area(&self) -> u32 { self.0 * self.0 }
+}
+fn goal() -> u32 {
+    let sq = Square(3);
+    sq.double_area()
+}
+"#,
+        expect![[r#"18"#]],
+    );
+}
+
+#[test]
+fn eval_panics() {
+    check_eval(
+        r#"
+fn goal() -> u8 {
+    let x: u8 = 255;
+    x + 1
+}
+"#,
+        expect![[r#"Panic("attempt to add with overflow")"#]],
+    );
+    check_eval(
+        r#"
+fn goal() -> i32 {
+    let arr = [1, 2, 3];
+    let mut i = 0;
+    loop {
+        i += arr[i];
+    }
+}
+"#,
+        expect![[r#"Panic("index out of bounds: the len is 3 but the index is 3")"#]],
+    );
+    check_eval(
+        r#"
+fn goal() -> i32 {
+    loop {}
+}
+"#,
+        expect![[r#"ExecutionLimitExceeded"#]],
+    );
+}
+
+#[test]
+fn eval_panic_lang_items() {
+    check_eval(
+        r#"
+#[lang = "panic"]
+fn panic(expr: &'static str) -> ! {
+    loop {}
+}
+fn goal() -> i32 {
+    panic("boom")
+}
+"#,
+        expect![[r#"Panic("boom")"#]],
+    );
+    check_eval(
+        r#"
+fn panic_free() -> i32 {
+    92
+}
+fn goal() -> i32 {
+    panic_free()
+}
+"#,
+        expect![[r#"92"#]],
+    );
+}
+
+#[test]
+fn eval_pattern_matching() {
+    check_eval(
+        r#"
+enum Shape { Circle { r: u32 }, Rect(u32, u32), Empty }
+struct Pair(u32, u32);
+fn area(shape: &Shape) -> u32 {
+    match shape {
+        Shape::Circle { r } => 3 * *r * *r,
+        Shape::Rect(w, h) if *w == *h => *w * *w,
+        &Shape::Rect(w, h) => w * h,
+        Shape::Empty => 0,
+    }
+}
+fn sum((a, b): (u32, u32), Pair(c, ..): Pair) -> u32 {
+    a + b + c
+}
+fn goal() -> (u32, u32, u32, u32, u32) {
+    let shapes = [Shape::Circle { r: 2 }, Shape::Rect(3, 3), Shape::Rect(2, 5), Shape::Empty];
+    let Shape::Rect(w, _) = shapes[2] else { return (0, 0, 0, 0, 0) };
+    let mut total = 0;
+    let mut i = 0;
+    while let 0 | 1 | 2 = i {
+        total += area(&shapes[i]);
+        i += 1;
+    }
+    (area(&shapes[0]), area(&shapes[1]), area(&shapes[3]), total + w, sum((1, 2), Pair(3, 4)))
+}
+"#,
+        expect![[r#"(12, 9, 0, 33, 6)"#]],
+    );
+}
@@ -14,6 +14,8 @@ use rustc_hash::{FxHashMap, FxHashSet};
 use syntax::TextRange;
 use test_utils::extract_annotations;
 
+use crate::db::HirDatabase;
+
 #[salsa::database(
     base_db::SourceDatabaseExtStorage,
     base_db::SourceDatabaseStorage,
@@ -31,6 +33,7 @@ impl Default for TestDB {
     fn default() -> Self {
         let mut this = Self { storage: Default::default(), events: Default::default() };
         this.set_enable_proc_attr_macros(true);
+        this.set_trait_solver_config(Default::default());
         this
     }
 }
@@ -1,8 +1,8 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use base_db::{fixture::WithFixture, SourceDatabaseExt};
 
-use crate::{db::HirDatabase, test_db::TestDB};
+use crate::{db::HirDatabase, display::HirDisplay, test_db::TestDB, TraitSolverConfig};
 
 use super::visit_module;
 
@@ -49,3 +49,173 @@ fn typing_whitespace_inside_a_function_should_not_invalidate_types() {
         assert!(!format!("{:?}", events).contains("infer"), "{:#?}", events)
     }
 }
+
+#[test]
+fn adding_a_block_to_a_function_does_not_reinfer_other_functions() {
+    let (mut db, pos) = TestDB::with_position(
+        "
+        //- /lib.rs
+        fn foo() -> i32 {
+            $01
+        }
+        fn bar() -> i32 {
+            if true { foo() } else { 0 }
+        }
+    ",
+    );
+    {
+        let events = db.log_executed(|| {
+            let module = db.module_for_file(pos.file_id);
+            let crate_def_map = module.def_map(&db);
+            visit_module(&db, &crate_def_map, module.local_id, &mut |def| {
+                db.infer(def);
+            });
+        });
+        assert_eq!(events.iter().filter(|it| it.contains("infer_query(")).count(), 2);
+    }
+
+    let new_text = "
+        fn foo() -> i32 {
+            { 1 }
+        }
+        fn bar() -> i32 {
+            if true { foo() } else { 0 }
+        }
+    "
+    .to_string();
+
+    db.set_file_text(pos.file_id, Arc::new(new_text));
+
+    {
+        let events = db.log_executed(|| {
+            let module = db.module_for_file(pos.file_id);
+            let crate_def_map = module.def_map(&db);
+            visit_module(&db, &crate_def_map, module.local_id, &mut |def| {
+                db.infer(def);
+            });
+        });
+        let inferred = events.iter().filter(|it| it.contains("infer_query(")).count();
+        assert_eq!(inferred, 1, "{:#?}", events);
+    }
+}
+
+#[test]
+fn editing_docs_of_a_function_does_not_reinfer_callers() {
+    let (mut db, pos) = TestDB::with_position(
+        "
+        //- /lib.rs
+        /// $0Docs.
+        fn foo() -> i32 { 1 }
+        fn bar() -> i32 { foo() }
+    ",
+    );
+    {
+        let events = db.log_executed(|| {
+            let module = db.module_for_file(pos.file_id);
+            let crate_def_map = module.def_map(&db);
+            visit_module(&db, &crate_def_map, module.local_id, &mut |def| {
+                db.infer(def);
+            });
+        });
+        assert_eq!(events.iter().filter(|it| it.contains("infer_query(")).count(), 2);
+    }
+
+    let new_text = "
+        /// Other docs.
+        fn foo() -> i32 { 1 }
+        fn bar() -> i32 { foo() }
+    "
+    .to_string();
+
+    db.set_file_text(pos.file_id, Arc::new(new_text));
+
+    {
+        let events = db.log_executed(|| {
+            let module = db.module_for_file(pos.file_id);
+            let crate_def_map = module.def_map(&db);
+            visit_module(&db, &crate_def_map, module.local_id, &mut |def| {
+                db.infer(def);
+            });
+        });
+        assert!(!events.iter().any(|it| it.contains("infer_query(")), "{:#?}", events);
+    }
+}
+
+#[test]
+fn trait_clauses_are_elaborated_once_per_trait() {
+    let (db, file_id) = TestDB::with_single_file(
+        "
+        trait Base { fn base(&self) -> u32; }
+        trait Derived: Base {}
+        fn foo<T: Derived>(t: T) -> u32 { t.base() }
+        fn bar<T: Derived, U: Derived>(t: T, u: U) -> u32 { t.base() + u.base() }
+    ",
+    );
+    let events = db.log_executed(|| {
+        let module = db.module_for_file(file_id);
+        let crate_def_map = module.def_map(&db);
+        visit_module(&db, &crate_def_map, module.local_id, &mut |def| {
+            db.infer(def);
+        });
+    });
+    let elaborations = events.iter().filter(|it| it.contains("program_clauses_for_trait(")).count();
+    assert_eq!(elaborations, 1, "{:#?}", events);
+}
+
+#[test]
+fn editing_an_impl_changes_trait_solutions() {
+    let (mut db, file_id) = TestDB::with_single_file(
+        "
+        trait Trait { type Out; fn get(&self) -> Self::Out; }
+        struct S;
+        impl Trait for S { type Out = u32; fn get(&self) -> u32 { 0 } }
+        fn foo() { let x = S.get(); }
+    ",
+    );
+    let pat_types = |db: &TestDB| {
+        let module = db.module_for_file(file_id);
+        let crate_def_map = module.def_map(db);
+        let mut types = Vec::new();
+        visit_module(db, &crate_def_map, module.local_id, &mut |def| {
+            let infer = db.infer(def);
+            types.extend(infer.type_of_pat.iter().map(|(_, ty)| ty.display(db).to_string()));
+        });
+        types
+    };
+    assert!(pat_types(&db).contains(&"u32".to_string()));
+
+    let new_text = "
+        trait Trait { type Out; fn get(&self) -> Self::Out; }
+        struct S;
+        impl Trait for S { type Out = i64; fn get(&self) -> i64 { 0 } }
+        fn foo() { let x = S.get(); }
+    "
+    .to_string();
+    db.set_file_text(file_id, Arc::new(new_text));
+
+    let types = pat_types(&db);
+    assert!(types.contains(&"i64".to_string()), "{:?}", types);
+    assert!(!types.contains(&"u32".to_string()), "{:?}", types);
+}
+
+#[test]
+fn trait_goal_timeouts_are_recorded() {
+    let (mut db, file_id) = TestDB::with_single_file(
+        "
+        trait Trait { fn method(&self) -> u32; }
+        struct S;
+        impl Trait for S { fn method(&self) -> u32 { 0 } }
+        fn foo() -> u32 { S.method() }
+    ",
+    );
+    db.set_trait_solver_config(TraitSolverConfig {
+        timeout: Some(Duration::ZERO),
+        ..Default::default()
+    });
+    let module = db.module_for_file(file_id);
+    let crate_def_map = module.def_map(&db);
+    visit_module(&db, &crate_def_map, module.local_id, &mut |def| {
+        db.infer(def);
+    });
+    assert_ne!(db.trait_solve_stats(module.krate()).timed_out_goals(), 0);
+}
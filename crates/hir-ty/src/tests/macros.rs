@@ -655,6 +655,64 @@ macro_rules! bar {
     );
 }
 
+#[test]
+fn infer_macro_2_def_site_paths() {
+    check_types(
+        r#"
+//- /main.rs crate:main deps:foo
+mod inner {
+    pub fn helper() -> &'static str { "" }
+}
+
+fn test() {
+    let x = foo::m!(inner::helper());
+    x;
+} //^ (u32, &str)
+
+//- /lib.rs crate:foo
+mod inner {
+    pub fn helper() -> u32 { 0 }
+}
+
+pub macro m($e:expr) {
+    (inner::helper(), $e)
+}
+"#,
+    );
+}
+
+#[test]
+fn infer_macro_2_def_site_paths_skip_call_site_locals() {
+    check_types(
+        r#"
+//- /main.rs crate:main deps:foo
+fn test() {
+    let VALUE = "";
+    let x = foo::m!();
+    x;
+} //^ u32
+
+fn test_local() {
+    let y = "";
+    let x = foo::n!();
+    x;
+} //^ u32
+
+//- /lib.rs crate:foo
+pub static VALUE: u32 = 0;
+
+pub macro m() {
+    VALUE
+}
+
+pub macro n() {{
+    let y = VALUE;
+    y
+}}
+"#,
+    );
+}
+
 #[test]
 fn infer_builtin_macros_line() {
     check_infer(
@@ -913,6 +971,32 @@ fn bar() -> u32 {0}
     );
 }
 
+#[test]
+fn infer_builtin_macros_include_concat_with_env() {
+    check_types(
+        r#"
+//- /main.rs env:OUT_DIR=/out
+#[rustc_builtin_macro]
+macro_rules! include {() => {}}
+
+#[rustc_builtin_macro]
+macro_rules! concat {() => {}}
+
+#[rustc_builtin_macro]
+macro_rules! env {() => {}}
+
+include!(concat!(env!("OUT_DIR"), "/foo.rs"));
+
+fn main() {
+    bar();
+} //^^^^^ u32
+
+//- /out/foo.rs
+fn bar() -> u32 {0}
+"#,
+    );
+}
+
 #[test]
 fn infer_builtin_macros_include_itself_should_failed() {
     check_types(
@@ -963,7 +1047,7 @@ fn infer_builtin_macros_env() {
         }
         "#,
         expect![[r#"
-            !0..22 '"__RA_...TED__"': &str
+            !0..5 '"bar"': &str
             62..90 '{     ...o"); }': ()
             72..73 'x': &str
         "#]],
@@ -1768,6 +1768,26 @@ fn main() {
     );
 }
 
+#[test]
+fn const_generic_defaults() {
+    check_types(
+        r#"
+struct SmallVec<T, const N: usize = { 2 * 4 }>([T; N]);
+struct Pair<const N: usize = 3, const M: usize = N>([u8; N], [u8; M]);
+
+fn main(a: SmallVec<u8>, b: SmallVec<u8, 2>, c: Pair, d: Pair<5>) {
+    a.0;
+  //^^^ [u8; 8]
+    b.0;
+  //^^^ [u8; 2]
+    c.1;
+  //^^^ [u8; 3]
+    d.1;
+  //^^^ [u8; 5]
+}"#,
+    );
+}
+
 #[test]
 fn const_eval_array_repeat_expr() {
     check_types(
@@ -1780,6 +1800,51 @@ fn main() {
     );
 }
 
+#[test]
+fn const_eval_array_repeat_expr_with_loop() {
+    check_types(
+        r#"
+//- minicore: size_of
+fn main() {
+    const X: usize = {
+        let mut n = 0;
+        for i in 0..4 {
+            n += i;
+        }
+        n + core::mem::size_of::<u32>()
+    };
+    let t = [(); X];
+      //^ [(); 10]
+}"#,
+    );
+}
+
+#[test]
+fn const_eval_array_type_len_exprs() {
+    check_types(
+        r#"
+fn id<const N: usize>(a: [u8; N]) -> [u8; { N * 1 }] { a }
+fn double<const N: usize>(a: [u8; N]) -> [u8; N * 2] {
+    let doubled: [u8; N * 2] = loop {};
+    doubled;
+  //^^^^^^^ [u8; N * 2]
+    doubled
+}
+fn seven() -> [u8; 2 + 3 * 2 - (1)] { loop {} }
+fn main() {
+    let a = seven();
+    a;
+  //^ [u8; 7]
+    let b = id(a);
+    b;
+  //^ [u8; 7]
+    let c = double::<3>([0; 3]);
+    c;
+  //^ [u8; 6]
+}"#,
+    );
+}
+
 #[test]
 fn shadowing_primitive_with_inner_items() {
     check_types(
@@ -1,7 +1,7 @@
 use cov_mark::check;
 use expect_test::expect;
 
-use super::{check, check_infer, check_infer_with_mismatches, check_types};
+use super::{check, check_infer, check_infer_with_mismatches, check_no_mismatches, check_types};
 
 #[test]
 fn infer_await() {
@@ -1281,6 +1281,183 @@ fn foo<const C: u8, T>() -> (impl FnOnce(&str, T), impl Trait<u8>) {
     );
 }
 
+#[test]
+fn type_alias_impl_trait() {
+    check_types(
+        r#"
+//- minicore: sized
+trait Trait<T> {
+    fn foo(&self) -> T;
+}
+struct S;
+impl Trait<u64> for S {
+    fn foo(&self) -> u64 { 0 }
+}
+type Foo = impl Trait<u64>;
+fn defining() -> Foo {
+    let s = S;
+    s
+}
+fn test() {
+    let a = defining();
+    a;
+  //^ impl Trait<u64>
+    a.foo();
+  //^^^^^^^ u64
+}
+"#,
+    );
+}
+
+#[test]
+fn type_alias_impl_trait_defining_use_in_submodule() {
+    check_no_mismatches(
+        r#"
+//- minicore: sized
+trait Trait {}
+struct S;
+impl Trait for S {}
+type Foo = impl Trait;
+mod m {
+    fn defining() -> super::Foo { super::S }
+    fn defining_param(foo: super::Foo) -> super::Foo {
+        let _s: super::Foo = super::S;
+        foo
+    }
+}
+"#,
+    );
+}
+
+#[test]
+fn async_fn_in_trait() {
+    check_types(
+        r#"
+//- minicore: future, sized
+trait Trait {
+    async fn foo(&self) -> u32;
+}
+struct S;
+impl Trait for S {
+    async fn foo(&self) -> u32 { 0 }
+}
+async fn test<T: Trait>(s: S, t: T) {
+    let a = s.foo().await;
+    a;
+  //^ u32
+    let b = t.foo().await;
+    b;
+  //^ u32
+}
+"#,
+    );
+}
+
+#[test]
+fn return_pos_impl_trait_in_trait_uses_impl_signature() {
+    check_types(
+        r#"
+//- minicore: sized
+trait Iter {}
+struct Counter;
+impl Iter for Counter {}
+trait Trait {
+    fn iter(&self) -> impl Iter;
+}
+struct S;
+impl Trait for S {
+    fn iter(&self) -> Counter { Counter }
+}
+fn test<T: Trait>(s: S, t: T) {
+    let a = s.iter();
+    a;
+  //^ Counter
+    let b = t.iter();
+    b;
+  //^ impl Iter
+}
+"#,
+    );
+}
+
+#[test]
+fn generic_associated_types() {
+    check_types(
+        r#"
+//- minicore: sized
+trait Trait {
+    type Assoc<T>;
+    fn get<T>(&self, t: T) -> Self::Assoc<T>;
+}
+struct S;
+struct Wrapper<T>(T);
+impl Trait for S {
+    type Assoc<T> = Wrapper<T>;
+    fn get<T>(&self, t: T) -> Self::Assoc<T> { Wrapper(t) }
+}
+fn test() {
+    let x = S.get(1u8);
+    x;
+  //^ Wrapper<u8>
+}
+"#,
+    );
+}
+
+#[test]
+fn generic_associated_types_in_generic_impl() {
+    check_types(
+        r#"
+//- minicore: sized
+trait Trait {
+    type Assoc<U>;
+    fn get<U>(&self, u: U) -> Self::Assoc<U>;
+}
+struct S<T>(T);
+impl<T> Trait for S<T> {
+    type Assoc<U> = (T, U);
+    fn get<U>(&self, u: U) -> Self::Assoc<U> { loop {} }
+}
+fn test<X: Trait<Assoc<u16> = i64>>(x: X) {
+    let a = S(1u8).get(1u32);
+    a;
+  //^ (u8, u32)
+    let b = x.get(1u16);
+    b;
+  //^ i64
+}
+"#,
+    );
+}
+
+#[test]
+fn lifetime_generic_associated_types() {
+    check_types(
+        r#"
+//- minicore: sized
+trait LendingIterator {
+    type Item<'a>
+    where
+        Self: 'a;
+    fn next<'a>(&'a mut self) -> Self::Item<'a>;
+}
+struct S;
+impl LendingIterator for S {
+    type Item<'a> = &'a u8;
+    fn next<'a>(&'a mut self) -> Self::Item<'a> { loop {} }
+}
+fn test<I: for<'a> LendingIterator<Item<'a> = &'a u32>>(mut s: S, mut i: I) {
+    let x = s.next();
+    x;
+  //^ &u8
+    let y = i.next();
+    y;
+  //^ &u32
+}
+"#,
+    );
+}
+
 #[test]
 fn dyn_trait() {
     check_infer(
@@ -3672,3 +3849,51 @@ fn test() {
 "#,
     )
 }
+
+#[test]
+fn auto_trait_bound_on_method() {
+    check_types(
+        r#"
+//- minicore: sized
+unsafe auto trait Send {}
+impl<T: ?Sized> !Send for *const T {}
+
+#[lang = "phantom_data"]
+struct PhantomData<T: ?Sized>;
+
+struct Local(u32, PhantomData<u8>);
+struct RawPtr(*const u8);
+struct Phantom(PhantomData<RawPtr>);
+enum Either { A(u32), B(Option<RawPtr>) }
+enum Option<T> { Some(T), None }
+struct Explicit(*const u8);
+unsafe impl Send for Explicit {}
+struct Negative(u32);
+impl !Send for Negative {}
+
+trait Spawn {
+    fn spawn(self) -> u32;
+}
+impl<T: Send> Spawn for T {
+    fn spawn(self) -> u32 { 0 }
+}
+
+fn test(a: Local, b: RawPtr, c: Phantom, d: Either, e: Explicit, f: Negative, g: *const u8) {
+    a.spawn();
+  //^^^^^^^^^ u32
+    b.spawn();
+  //^^^^^^^^^ {unknown}
+    c.spawn();
+  //^^^^^^^^^ {unknown}
+    d.spawn();
+  //^^^^^^^^^ {unknown}
+    e.spawn();
+  //^^^^^^^^^ u32
+    f.spawn();
+  //^^^^^^^^^ {unknown}
+    g.spawn();
+  //^^^^^^^^^ {unknown}
+}
+"#,
+    );
+}
@@ -64,7 +64,9 @@ impl DebugContext<'_> {
             _ => panic!("associated type not in trait"),
         };
         let trait_data = self.0.trait_data(trait_);
-        let params = projection_ty.substitution.as_slice(Interner);
+        let own_params = self.0.generic_params(type_alias.into()).type_or_consts.len();
+        let (own_params, params) =
+            projection_ty.substitution.as_slice(Interner).split_at(own_params);
         write!(fmt, "<{:?} as {}", &params[0], trait_data.name,)?;
         if params.len() > 1 {
             write!(
@@ -73,7 +75,15 @@ impl DebugContext<'_> {
                 &params[1..].iter().format_with(", ", |x, f| f(&format_args!("{:?}", x))),
             )?;
         }
-        write!(fmt, ">::{}", type_alias_data.name)
+        write!(fmt, ">::{}", type_alias_data.name)?;
+        if !own_params.is_empty() {
+            write!(
+                fmt,
+                "<{}>",
+                own_params.iter().format_with(", ", |x, f| f(&format_args!("{:?}", x))),
+            )?;
+        }
+        Ok(())
     }
 
     pub(crate) fn debug_fn_def_id(
@@ -1,6 +1,15 @@
 //! Trait solving using Chalk.
 
-use std::env::var;
+use std::{
+    cell::Cell,
+    env::var,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use chalk_ir::GoalData;
 use chalk_recursive::Cache;
@@ -13,7 +22,7 @@ use syntax::SmolStr;
 
 use crate::{
     db::HirDatabase, AliasEq, AliasTy, Canonical, DomainGoal, Goal, Guidance, InEnvironment,
-    Interner, Solution, TraitRefExt, Ty, TyKind, WhereClause,
+    Interner, ProjectionTyExt, Solution, TraitRefExt, Ty, TyKind, WhereClause,
 };
 
 /// This controls how much 'time' we give the Chalk solver before giving up.
@@ -25,11 +34,94 @@ pub(crate) struct ChalkContext<'a> {
     pub(crate) krate: CrateId,
 }
 
-fn create_chalk_solver() -> chalk_recursive::RecursiveSolver<Interner> {
-    let overflow_depth =
-        var("CHALK_OVERFLOW_DEPTH").ok().and_then(|s| s.parse().ok()).unwrap_or(500);
-    let max_size = var("CHALK_SOLVER_MAX_SIZE").ok().and_then(|s| s.parse().ok()).unwrap_or(150);
-    chalk_recursive::RecursiveSolver::new(overflow_depth, max_size, Some(Cache::new()))
+/// The limits the Chalk solver works within.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TraitSolverConfig {
+    /// The maximum size of a type in a goal before the solver gives up on the goal.
+    pub max_size: usize,
+    /// The maximum depth of nested goals before the solver reports an overflow.
+    pub overflow_depth: usize,
+    /// How long the solver may work on a single goal before the goal is treated as ambiguous.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for TraitSolverConfig {
+    fn default() -> Self {
+        TraitSolverConfig {
+            max_size: var("CHALK_SOLVER_MAX_SIZE").ok().and_then(|s| s.parse().ok()).unwrap_or(150),
+            overflow_depth: var("CHALK_OVERFLOW_DEPTH")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(500),
+            timeout: None,
+        }
+    }
+}
+
+/// Each goal gets a solver with a fresh cache. Sharing the solutions of subgoals between
+/// `trait_solve` queries would hide what they depend on from salsa, so that a query reusing a
+/// solution wouldn't be invalidated when the solution changes.
+fn create_chalk_solver(config: &TraitSolverConfig) -> chalk_recursive::RecursiveSolver<Interner> {
+    chalk_recursive::RecursiveSolver::new(
+        config.overflow_depth,
+        config.max_size,
+        Some(Cache::new()),
+    )
+}
+
+/// Statistics of the trait queries of a crate in the current revision.
+#[derive(Clone)]
+pub struct TraitSolveStats {
+    krate: CrateId,
+    timed_out_goals: Arc<AtomicUsize>,
+    solve_time_micros: Arc<AtomicU64>,
+}
+
+/// We need eq for salsa
+impl PartialEq for TraitSolveStats {
+    fn eq(&self, other: &TraitSolveStats) -> bool {
+        self.krate == other.krate
+    }
+}
+
+impl Eq for TraitSolveStats {}
+
+impl fmt::Debug for TraitSolveStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraitSolveStats").field("krate", &self.krate).finish()
+    }
+}
+
+impl TraitSolveStats {
+    /// The number of goals of this revision the solver gave up on because they took too long.
+    pub fn timed_out_goals(&self) -> usize {
+        self.timed_out_goals.load(Ordering::Relaxed)
+    }
+
+    /// The time spent solving goals of this crate in the current revision.
+    pub fn solve_time(&self) -> Duration {
+        Duration::from_micros(self.solve_time_micros.load(Ordering::Relaxed))
+    }
+
+    fn record_solve_time(&self, time: Duration) {
+        self.solve_time_micros.fetch_add(time.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn record_timeout(&self) {
+        self.timed_out_goals.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn trait_solve_stats_query(db: &dyn HirDatabase, krate: CrateId) -> TraitSolveStats {
+    // The statistics are only valid for the revision they were collected in, so each revision
+    // starts afresh. As the stats of the same crate compare equal, this doesn't invalidate
+    // the trait queries depending on it.
+    db.salsa_runtime().report_untracked_read();
+    TraitSolveStats {
+        krate,
+        timed_out_goals: Arc::new(AtomicUsize::new(0)),
+        solve_time_micros: Arc::new(AtomicU64::new(0)),
+    }
 }
 
 /// A set of clauses that we assume to be true. E.g. if we are inside this function:
@@ -84,7 +176,9 @@ pub(crate) fn trait_solve_query(
         ..
     }))) = &goal.value.goal.data(Interner)
     {
-        if let TyKind::BoundVar(_) = projection_ty.self_type_parameter(Interner).kind(Interner) {
+        if let TyKind::BoundVar(_) =
+            projection_ty.trait_ref(db).self_type_parameter(Interner).kind(Interner)
+        {
             // Hack: don't ask Chalk to normalize with an unknown self type, it'll say that's impossible
             return Some(Solution::Ambig(Guidance::Unknown));
         }
@@ -103,12 +197,20 @@ fn solve(
 ) -> Option<chalk_solve::Solution<Interner>> {
     let context = ChalkContext { db, krate };
     tracing::debug!("solve goal: {:?}", goal);
-    let mut solver = create_chalk_solver();
+    let config = db.trait_solver_config();
+    let stats = db.trait_solve_stats(krate);
+    let mut solver = create_chalk_solver(&config);
 
-    let fuel = std::cell::Cell::new(CHALK_SOLVER_FUEL);
+    let fuel = Cell::new(CHALK_SOLVER_FUEL);
+    let deadline = config.timeout.map(|timeout| Instant::now() + timeout);
+    let timed_out = Cell::new(false);
 
     let should_continue = || {
         db.unwind_if_cancelled();
+        if deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+            timed_out.set(true);
+            return false;
+        }
         let remaining = fuel.get();
         fuel.set(remaining - 1);
         if remaining == 0 {
@@ -138,11 +240,19 @@ fn solve(
 
     // don't set the TLS for Chalk unless Chalk debugging is active, to make
     // extra sure we only use it for debugging
-    if is_chalk_debug() {
-        crate::tls::set_current_program(db, solve)
-    } else {
-        solve()
+    let start = Instant::now();
+    let solution =
+        if is_chalk_debug() { crate::tls::set_current_program(db, solve) } else { solve() };
+    stats.record_solve_time(start.elapsed());
+
+    if timed_out.get() {
+        tracing::info!("trait goal timed out: {:?}", goal);
+        stats.record_timeout();
+        // Try the goal again in the next revision instead of keeping the degraded solution.
+        db.salsa_runtime().report_untracked_read();
+        return Some(Solution::Ambig(Guidance::Unknown));
     }
+    solution
 }
 
 struct LoggingRustIrDatabaseLoggingOnDrop<'a>(LoggingRustIrDatabase<Interner, ChalkContext<'a>>);
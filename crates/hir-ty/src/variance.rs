@@ -0,0 +1,269 @@
+//! Computes the variance of the generic parameters of ADTs and function items.
+//!
+//! This follows the approach rustc uses: the variance of a parameter is the combination of the
+//! variances of all positions it appears in within the field types (or, for functions, within the
+//! signature). ADTs that refer to themselves are handled by iterating to a fixpoint, starting with
+//! all parameters bivariant. Parameters that end up unconstrained are reported as invariant, as
+//! chalk has no notion of bivariance.
+
+use chalk_ir::{ConstValue, DebruijnIndex};
+use hir_def::{AdtId, EnumVariantId, VariantId};
+
+use crate::{
+    chalk_db::{self, Variances},
+    db::HirDatabase,
+    mapping::from_chalk,
+    utils::generics,
+    AliasTy, CallableDefId, Const, FnDefId, GenericArg, GenericArgData, Interner, Substitution, Ty,
+    TyKind, WhereClause,
+};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Variance {
+    Covariant,
+    Contravariant,
+    Invariant,
+    /// The parameter does not appear in a position that constrains it (yet).
+    Bivariant,
+}
+
+impl Variance {
+    /// The variance of a position with variance `other` nested in a position with variance `self`.
+    fn xform(self, other: Variance) -> Variance {
+        match (self, other) {
+            (Variance::Invariant, _) => Variance::Invariant,
+            (Variance::Bivariant, _) => Variance::Bivariant,
+            (Variance::Covariant, other) => other,
+            (Variance::Contravariant, Variance::Covariant) => Variance::Contravariant,
+            (Variance::Contravariant, Variance::Contravariant) => Variance::Covariant,
+            (Variance::Contravariant, other) => other,
+        }
+    }
+
+    /// The variance of a parameter appearing in both a position of variance `self` and `other`.
+    fn join(self, other: Variance) -> Variance {
+        match (self, other) {
+            (Variance::Bivariant, other) | (other, Variance::Bivariant) => other,
+            (this, other) if this == other => this,
+            _ => Variance::Invariant,
+        }
+    }
+
+    fn to_chalk(self) -> chalk_ir::Variance {
+        match self {
+            Variance::Covariant => chalk_ir::Variance::Covariant,
+            Variance::Contravariant => chalk_ir::Variance::Contravariant,
+            Variance::Invariant | Variance::Bivariant => chalk_ir::Variance::Invariant,
+        }
+    }
+
+    fn from_chalk(variance: &chalk_ir::Variance) -> Variance {
+        match variance {
+            chalk_ir::Variance::Covariant => Variance::Covariant,
+            chalk_ir::Variance::Contravariant => Variance::Contravariant,
+            chalk_ir::Variance::Invariant => Variance::Invariant,
+        }
+    }
+}
+
+pub(crate) fn adt_variance_query(
+    db: &dyn HirDatabase,
+    chalk_ir::AdtId(adt_id): chalk_db::AdtId,
+) -> Variances {
+    let len = generics(db.upcast(), adt_id.into()).len();
+    let variants: Vec<VariantId> = match adt_id {
+        AdtId::StructId(it) => vec![it.into()],
+        AdtId::UnionId(it) => vec![it.into()],
+        AdtId::EnumId(it) => db
+            .enum_data(it)
+            .variants
+            .iter()
+            .map(|(local_id, _)| EnumVariantId { parent: it, local_id }.into())
+            .collect(),
+    };
+
+    let mut current = vec![Variance::Bivariant; len];
+    loop {
+        let mut ctx = VarianceCtx {
+            db,
+            adt: Some((adt_id, &current)),
+            result: vec![Variance::Bivariant; len],
+        };
+        for &variant in &variants {
+            for (_, field_ty) in db.field_types(variant).iter() {
+                ctx.add_ty(field_ty.skip_binders(), Variance::Covariant, DebruijnIndex::INNERMOST);
+            }
+        }
+        let result = ctx.result;
+        if result == current {
+            break;
+        }
+        current = result;
+    }
+    Variances::from_iter(Interner, current.into_iter().map(Variance::to_chalk))
+}
+
+pub(crate) fn adt_variance_recover(
+    db: &dyn HirDatabase,
+    _cycle: &[String],
+    chalk_ir::AdtId(adt_id): &chalk_db::AdtId,
+) -> Variances {
+    let len = generics(db.upcast(), (*adt_id).into()).len();
+    Variances::from_iter(Interner, std::iter::repeat(chalk_ir::Variance::Invariant).take(len))
+}
+
+pub(crate) fn fn_def_variance_query(db: &dyn HirDatabase, fn_def_id: FnDefId) -> Variances {
+    let callable_def: CallableDefId = from_chalk(db, fn_def_id);
+    let len = generics(db.upcast(), callable_def.into()).len();
+    let sig = db.callable_item_signature(callable_def);
+
+    let mut ctx = VarianceCtx { db, adt: None, result: vec![Variance::Bivariant; len] };
+    let sig = sig.skip_binders();
+    for param in sig.params() {
+        ctx.add_ty(param, Variance::Contravariant, DebruijnIndex::INNERMOST);
+    }
+    ctx.add_ty(sig.ret(), Variance::Covariant, DebruijnIndex::INNERMOST);
+    Variances::from_iter(Interner, ctx.result.into_iter().map(Variance::to_chalk))
+}
+
+struct VarianceCtx<'a> {
+    db: &'a dyn HirDatabase,
+    /// The ADT whose variances are being computed, along with the current approximation of them.
+    adt: Option<(AdtId, &'a [Variance])>,
+    result: Vec<Variance>,
+}
+
+impl VarianceCtx<'_> {
+    fn add_ty(&mut self, ty: &Ty, variance: Variance, binder: DebruijnIndex) {
+        match ty.kind(Interner) {
+            TyKind::BoundVar(bound) => {
+                if bound.debruijn == binder {
+                    self.add_param(bound.index, variance);
+                }
+            }
+            TyKind::Adt(chalk_ir::AdtId(adt), subst) => {
+                let variances = match self.adt {
+                    Some((id, current)) if id == *adt => current.to_vec(),
+                    _ => self
+                        .db
+                        .adt_variance(chalk_ir::AdtId(*adt))
+                        .as_slice(Interner)
+                        .iter()
+                        .map(Variance::from_chalk)
+                        .collect(),
+                };
+                for (arg, &param_variance) in subst.iter(Interner).zip(variances.iter()) {
+                    self.add_arg(arg, variance.xform(param_variance), binder);
+                }
+            }
+            TyKind::Ref(mutability, _, inner) | TyKind::Raw(mutability, inner) => {
+                let inner_variance = match mutability {
+                    chalk_ir::Mutability::Mut => Variance::Invariant,
+                    chalk_ir::Mutability::Not => Variance::Covariant,
+                };
+                self.add_ty(inner, variance.xform(inner_variance), binder);
+            }
+            TyKind::Slice(inner) => self.add_ty(inner, variance, binder),
+            TyKind::Array(inner, len) => {
+                self.add_ty(inner, variance, binder);
+                self.add_const(len, variance.xform(Variance::Invariant), binder);
+            }
+            TyKind::Tuple(_, subst) => {
+                for arg in subst.iter(Interner) {
+                    self.add_arg(arg, variance, binder);
+                }
+            }
+            TyKind::Function(fn_ptr) => {
+                let binder = binder.shifted_in();
+                let params = fn_ptr.substitution.0.as_slice(Interner);
+                if let Some((ret, params)) = params.split_last() {
+                    for param in params {
+                        self.add_arg(param, variance.xform(Variance::Contravariant), binder);
+                    }
+                    self.add_arg(ret, variance, binder);
+                }
+            }
+            TyKind::Dyn(dyn_ty) => {
+                // Parameters of the trait and bindings of associated types are invariant. The
+                // bounds are under the binder for `Self` and the binders of each clause.
+                let binder = binder.shifted_in().shifted_in();
+                for bound in dyn_ty.bounds.skip_binders().iter(Interner) {
+                    match bound.skip_binders() {
+                        WhereClause::Implemented(trait_ref) => {
+                            // Skip `Self`.
+                            for arg in trait_ref.substitution.iter(Interner).skip(1) {
+                                self.add_arg(arg, variance.xform(Variance::Invariant), binder);
+                            }
+                        }
+                        WhereClause::AliasEq(alias_eq) => {
+                            if let AliasTy::Projection(proj) = &alias_eq.alias {
+                                self.add_subst_invariant(&proj.substitution, variance, binder);
+                            }
+                            self.add_ty(&alias_eq.ty, variance.xform(Variance::Invariant), binder);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            TyKind::Alias(AliasTy::Projection(proj)) => {
+                self.add_subst_invariant(&proj.substitution, variance, binder)
+            }
+            TyKind::Alias(AliasTy::Opaque(opaque)) => {
+                self.add_subst_invariant(&opaque.substitution, variance, binder)
+            }
+            TyKind::AssociatedType(_, subst)
+            | TyKind::OpaqueType(_, subst)
+            | TyKind::FnDef(_, subst)
+            | TyKind::Closure(_, subst)
+            | TyKind::Generator(_, subst)
+            | TyKind::GeneratorWitness(_, subst) => {
+                self.add_subst_invariant(subst, variance, binder)
+            }
+            TyKind::Scalar(_)
+            | TyKind::Str
+            | TyKind::Never
+            | TyKind::Foreign(_)
+            | TyKind::Placeholder(_)
+            | TyKind::InferenceVar(..)
+            | TyKind::Error => {}
+        }
+    }
+
+    fn add_subst_invariant(
+        &mut self,
+        subst: &Substitution,
+        variance: Variance,
+        binder: DebruijnIndex,
+    ) {
+        for arg in subst.iter(Interner) {
+            self.add_arg(arg, variance.xform(Variance::Invariant), binder);
+        }
+    }
+
+    fn add_arg(&mut self, arg: &GenericArg, variance: Variance, binder: DebruijnIndex) {
+        match arg.data(Interner) {
+            GenericArgData::Ty(ty) => self.add_ty(ty, variance, binder),
+            GenericArgData::Const(c) => {
+                self.add_const(c, variance.xform(Variance::Invariant), binder)
+            }
+            GenericArgData::Lifetime(_) => {}
+        }
+    }
+
+    fn add_const(&mut self, c: &Const, variance: Variance, binder: DebruijnIndex) {
+        if let ConstValue::BoundVar(bound) = &c.data(Interner).value {
+            if bound.debruijn == binder {
+                self.add_param(bound.index, variance);
+            }
+        }
+    }
+
+    fn add_param(&mut self, index: usize, variance: Variance) {
+        if let Some(slot) = self.result.get_mut(index) {
+            *slot = slot.join(variance);
+        }
+    }
+}
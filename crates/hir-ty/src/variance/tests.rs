@@ -0,0 +1,87 @@
+use base_db::fixture::WithFixture;
+use expect_test::{expect, Expect};
+use hir_def::{db::DefDatabase, AdtId, ModuleDefId};
+use itertools::Itertools;
+
+use crate::{db::HirDatabase, test_db::TestDB, Interner};
+
+fn check(ra_fixture: &str, expect: Expect) {
+    let (db, file_id) = TestDB::with_single_file(ra_fixture);
+    let module_id = db.module_for_file(file_id);
+    let def_map = module_id.def_map(&db);
+    let scope = &def_map[module_id.local_id].scope;
+    let actual = scope
+        .declarations()
+        .filter_map(|it| match it {
+            ModuleDefId::AdtId(it) => Some(it),
+            _ => None,
+        })
+        .map(|adt| {
+            let name = match adt {
+                AdtId::StructId(it) => db.struct_data(it).name.to_string(),
+                AdtId::UnionId(it) => db.union_data(it).name.to_string(),
+                AdtId::EnumId(it) => db.enum_data(it).name.to_string(),
+            };
+            let variances = db.adt_variance(chalk_ir::AdtId(adt));
+            let variances = variances.as_slice(Interner).iter().map(|it| format!("{:?}", it));
+            format!("{}[{}]\n", name, variances.format(", "))
+        })
+        .sorted()
+        .collect::<String>();
+    expect.assert_eq(&actual);
+}
+
+#[test]
+fn variance_of_fields() {
+    check(
+        r#"
+struct Co<T>(T);
+struct Contra<T>(fn(T));
+struct Inv<T>(*mut T);
+struct Mixed<A, B, C> {
+    a: &'static A,
+    b: fn(B) -> B,
+    c: Co<Contra<C>>,
+}
+enum E<T, U> {
+    A(T),
+    B(fn(U)),
+}
+struct Arr<const N: usize, T>([T; N]);
+struct Unused<T>;
+"#,
+        expect![[r#"
+            Arr[Invariant, Covariant]
+            Co[Covariant]
+            Contra[Contravariant]
+            E[Covariant, Contravariant]
+            Inv[Invariant]
+            Mixed[Covariant, Invariant, Contravariant]
+            Unused[Invariant]
+        "#]],
+    );
+}
+
+#[test]
+fn variance_of_recursive_types() {
+    check(
+        r#"
+struct List<T> {
+    head: T,
+    tail: *const List<T>,
+}
+struct Tree<T> {
+    value: fn(T),
+    children: *const [Tree<T>],
+}
+struct A<T>(*const B<T>);
+struct B<T>(*const A<T>, T);
+"#,
+        expect![[r#"
+            A[Invariant]
+            B[Invariant]
+            List[Covariant]
+            Tree[Contravariant]
+        "#]],
+    );
+}
@@ -41,6 +41,7 @@ use hir_def::{
     adt::{ReprKind, VariantData},
     body::{BodyDiagnostic, SyntheticSyntax},
     expr::{BindingAnnotation, LabelId, Pat, PatId},
+    generics::{TypeOrConstParamData, TypeParamProvenance},
     item_tree::ItemTreeNode,
     lang_item::LangItemTarget,
     nameres::{self, diagnostics::DefDiagnostic},
@@ -58,6 +59,7 @@ use hir_ty::{
     consteval::{unknown_const_as_generic, ComputedExpr, ConstEvalError, ConstExt},
     diagnostics::BodyValidationDiagnostic,
     method_resolution::{self, TyFingerprint},
+    mir::interpret_mir,
     primitive::UintTy,
     subst_prefix,
     traits::FnTrait,
@@ -70,7 +72,7 @@ use itertools::Itertools;
 use nameres::diagnostics::DefDiagnosticKind;
 use once_cell::unsync::Lazy;
 use rustc_hash::FxHashSet;
-use stdx::{format_to, impl_from, never};
+use stdx::{impl_from, never};
 use syntax::{
     ast::{self, HasAttrs as _, HasDocComments, HasName},
     AstNode, AstPtr, SmolStr, SyntaxNodePtr, T,
@@ -117,7 +119,7 @@ pub use {
         name::{known, Name},
         ExpandResult, HirFileId, InFile, MacroFile, Origin,
     },
-    hir_ty::display::HirDisplay,
+    hir_ty::{display::HirDisplay, mir::MirEvalError, TraitSolverConfig},
 };
 
 // These are negative re-exports: pub using these names is forbidden, they
@@ -240,6 +242,11 @@ impl Crate {
     pub fn potential_cfg(&self, db: &dyn HirDatabase) -> CfgOptions {
         db.crate_graph()[self.id].potential_cfg_options.clone()
     }
+
+    /// Whether the crate enables `feature` with `#![feature(...)]`.
+    pub fn is_unstable_feature_enabled(self, db: &dyn HirDatabase, feature: &str) -> bool {
+        db.crate_def_map(self.id).is_unstable_feature_enabled(feature)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -1437,9 +1444,7 @@ impl Function {
     }
 
     pub fn as_proc_macro(self, db: &dyn HirDatabase) -> Option<Macro> {
-        let function_data = db.function_data(self.id);
-        let attrs = &function_data.attrs;
-        // FIXME: Store this in FunctionData flags?
+        let attrs = db.attrs(self.id.into());
         if !(attrs.is_proc_macro()
             || attrs.is_proc_macro_attribute()
             || attrs.is_proc_macro_derive())
@@ -1454,14 +1459,23 @@ impl Function {
     /// A textual representation of the HIR of this function for debugging purposes.
     pub fn debug_hir(self, db: &dyn HirDatabase) -> String {
         let body = db.body(self.id.into());
+        body.pretty_print(db.upcast(), self.id.into())
+    }
 
-        let mut result = String::new();
-        format_to!(result, "HIR expressions in the body of `{}`:\n", self.name(db));
-        for (id, expr) in body.exprs.iter() {
-            format_to!(result, "{:?}: {:?}\n", id, expr);
-        }
+    /// Runs the function in the MIR interpreter and renders the value it returns.
+    pub fn eval(self, db: &dyn HirDatabase) -> Result<String, MirEvalError> {
+        let body =
+            db.mir_body(self.id.into()).map_err(|e| MirEvalError::LoweringError(self.id, e))?;
+        interpret_mir(db, &body).map(|value| value.display(db))
+    }
 
-        result
+    pub fn debug_mir(self, db: &dyn HirDatabase) -> String {
+        match db.mir_body(self.id.into()) {
+            Ok(body) => {
+                format!("MIR of the body of `{}`:\n{}", self.name(db), body.pretty_print(db))
+            }
+            Err(e) => format!("Failed to lower the body of `{}` to MIR: {:?}", self.name(db), e),
+        }
     }
 }
 
@@ -1682,6 +1696,11 @@ impl Trait {
         traits.iter().flat_map(|tr| Trait::from(*tr).items(db)).collect()
     }
 
+    /// The traits this trait directly inherits from, not including `self`.
+    pub fn direct_supertraits(self, db: &dyn HirDatabase) -> Vec<Trait> {
+        hir_ty::direct_super_traits(db.upcast(), self.id).into_iter().map(Trait::from).collect()
+    }
+
     pub fn is_auto(self, db: &dyn HirDatabase) -> bool {
         db.trait_data(self.id).is_auto
     }
@@ -2335,6 +2354,14 @@ impl GenericParam {
             GenericParam::LifetimeParam(it) => it.name(db),
         }
     }
+
+    pub fn parent(self, db: &dyn HirDatabase) -> GenericDef {
+        match self {
+            GenericParam::TypeParam(it) => it.parent(db),
+            GenericParam::ConstParam(it) => it.parent(db),
+            GenericParam::LifetimeParam(it) => it.parent(db),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -2833,6 +2860,18 @@ impl Type {
         matches!(&self.ty.kind(Interner), TyKind::Closure { .. })
     }
 
+    pub fn as_closure(&self) -> Option<Closure> {
+        match self.ty.kind(Interner) {
+            TyKind::Closure(id, _) => Some(Closure { id: *id }),
+            _ => None,
+        }
+    }
+
+    /// Whether this is a generic parameter of the enclosing item, like `T` in `fn f<T>(t: T)`.
+    pub fn is_type_param(&self) -> bool {
+        matches!(&self.ty.kind(Interner), TyKind::Placeholder(_))
+    }
+
     pub fn is_fn(&self) -> bool {
         matches!(&self.ty.kind(Interner), TyKind::FnDef(..) | TyKind::Function { .. })
     }
@@ -3356,6 +3395,133 @@ impl Callable {
     pub fn return_type(&self) -> Type {
         self.ty.derived(self.sig.ret().clone())
     }
+    /// Returns the inferred arguments of the type parameters declared by the callee itself, in
+    /// declaration order, skipping implicit `impl Trait` parameters.
+    ///
+    /// Returns `None` if the callee is not a function or declares const parameters.
+    pub fn type_arguments(&self, db: &dyn HirDatabase) -> Option<Vec<Type>> {
+        let (func, substs) = self.fn_def()?;
+        let params = db.generic_params(func.into());
+        let substs = substs.as_slice(Interner);
+        let own = substs.len().checked_sub(params.type_or_consts.len())?;
+        params
+            .type_or_consts
+            .iter()
+            .zip(&substs[own..])
+            .filter_map(|((_, data), arg)| match data {
+                TypeOrConstParamData::TypeParamData(it)
+                    if it.provenance == TypeParamProvenance::ArgumentImplTrait =>
+                {
+                    None
+                }
+                TypeOrConstParamData::TypeParamData(_) => {
+                    Some(arg.ty(Interner).map(|ty| self.ty.derived(ty.clone())))
+                }
+                TypeOrConstParamData::ConstParamData(_) => Some(None),
+            })
+            .collect()
+    }
+    /// Returns the `Self` type the callee was instantiated with, if it is an associated function
+    /// of a trait or an impl.
+    pub fn self_type(&self, db: &dyn HirDatabase) -> Option<Type> {
+        let (func, substs) = self.fn_def()?;
+        let ty = match func.lookup(db.upcast()).container {
+            ItemContainerId::TraitId(trait_) => {
+                let params = db.generic_params(trait_.into());
+                let self_idx =
+                    params.type_or_consts.iter().position(|(_, it)| it.is_trait_self())?;
+                substs.at(Interner, self_idx).ty(Interner)?.clone()
+            }
+            ItemContainerId::ImplId(impl_) => {
+                let own = db.generic_params(func.into()).type_or_consts.len();
+                let parent_len = substs.len(Interner).checked_sub(own)?;
+                let parent_substs = subst_prefix(substs, parent_len);
+                db.impl_self_ty(impl_).substitute(Interner, &parent_substs)
+            }
+            _ => return None,
+        };
+        Some(self.ty.derived(ty))
+    }
+    fn fn_def(&self) -> Option<(FunctionId, &Substitution)> {
+        match (&self.callee, self.ty.ty.kind(Interner)) {
+            (Callee::Def(CallableDefId::FunctionId(func)), TyKind::FnDef(_, substs)) => {
+                Some((*func, substs))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Closure {
+    id: ClosureId,
+}
+
+impl Closure {
+    /// The places this closure captures from its environment, in order of first use.
+    pub fn captured_items(&self, db: &dyn HirDatabase) -> Vec<ClosureCapture> {
+        let (owner, expr) = db.lookup_intern_closure(self.id.into());
+        let infer = db.infer(owner);
+        infer
+            .closure_captures(expr)
+            .iter()
+            .map(|capture| ClosureCapture { owner, capture: capture.clone() })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClosureCapture {
+    owner: DefWithBodyId,
+    capture: hir_ty::CapturedItem,
+}
+
+impl ClosureCapture {
+    /// The local variable the captured place is rooted at.
+    pub fn local(&self) -> Local {
+        Local { parent: self.owner, pat_id: self.capture.place.local }
+    }
+
+    pub fn capture_kind(&self) -> CaptureKind {
+        match self.capture.kind {
+            hir_ty::CaptureKind::SharedRef => CaptureKind::SharedRef,
+            hir_ty::CaptureKind::MutableRef => CaptureKind::MutableRef,
+            hir_ty::CaptureKind::ByValue => CaptureKind::Move,
+        }
+    }
+
+    /// The type of the captured place.
+    pub fn ty(&self, db: &dyn HirDatabase) -> Type {
+        Type::new(db, self.owner, self.capture.ty.clone())
+    }
+
+    /// Renders the captured place as it would be written in source, e.g. `(*a).b.0`.
+    pub fn display_place(&self, db: &dyn HirDatabase) -> String {
+        let mut result = self.local().name(db).to_string();
+        for projection in &self.capture.place.projections {
+            let field = match projection {
+                hir_ty::PlaceProjection::Deref => {
+                    result = format!("*{}", result);
+                    continue;
+                }
+                hir_ty::PlaceProjection::Field(field) => Field::from(*field).name(db).to_string(),
+                hir_ty::PlaceProjection::TupleField(idx) => idx.to_string(),
+            };
+            if result.starts_with('*') {
+                result = format!("({})", result);
+            }
+            result.push('.');
+            result.push_str(&field);
+        }
+        result
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureKind {
+    SharedRef,
+    MutableRef,
+    Move,
 }
 
 fn closure_source(db: &dyn HirDatabase, closure: ClosureId) -> Option<ast::ClosureExpr> {
@@ -3376,6 +3542,59 @@ pub enum BindingMode {
     Ref(Mutability),
 }
 
+/// An implicit conversion applied to an expression's value by type inference.
+#[derive(Debug, Clone)]
+pub struct Adjustment {
+    pub source: Type,
+    pub target: Type,
+    pub kind: Adjust,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Adjust {
+    /// Go from ! to any type.
+    NeverToAny,
+    /// Dereference once, producing a place.
+    Deref(Option<OverloadedDeref>),
+    /// Take the address and produce either a `&` or `*` pointer.
+    Borrow(AutoBorrow),
+    Pointer(PointerCast),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AutoBorrow {
+    /// Converts from T to &T.
+    Ref(Mutability),
+    /// Converts from T to *T.
+    RawPtr(Mutability),
+}
+
+/// An overloaded autoderef step, i.e. a call to `Deref::deref` or `DerefMut::deref_mut`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct OverloadedDeref(pub Mutability);
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PointerCast {
+    /// Go from a fn-item type to a fn-pointer type.
+    ReifyFnPointer,
+    /// Go from a safe fn pointer to an unsafe fn pointer.
+    UnsafeFnPointer,
+    /// Go from a non-capturing closure to an fn pointer or an unsafe fn pointer.
+    ClosureFnPointer(Safety),
+    /// Go from a mut raw pointer to a const raw pointer.
+    MutToConstPointer,
+    /// Go from `*const [T; N]` to `*const T`.
+    ArrayToPointer,
+    /// Unsize a pointer/reference value, e.g., `&[T; n]` to `&[T]`.
+    Unsize,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Safety {
+    Safe,
+    Unsafe,
+}
+
 /// For IDE only
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ScopeDef {
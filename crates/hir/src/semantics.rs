@@ -14,9 +14,12 @@ use hir_def::{
 use hir_expand::{
     db::AstDatabase,
     name::{known, AsName},
-    ExpansionInfo, MacroCallId,
+    ExpandResult, ExpansionInfo, MacroCallId,
+};
+use hir_ty::{
+    consteval::{ComputedExpr, ConstEvalError},
+    Interner,
 };
-use hir_ty::Interner;
 use itertools::Itertools;
 use rustc_hash::{FxHashMap, FxHashSet};
 use smallvec::{smallvec, SmallVec};
@@ -30,9 +33,9 @@ use crate::{
     db::HirDatabase,
     semantics::source_to_def::{ChildContainer, SourceToDefCache, SourceToDefCtx},
     source_analyzer::{resolve_hir_path, SourceAnalyzer},
-    Access, BindingMode, BuiltinAttr, Callable, ConstParam, Crate, Field, Function, HasSource,
-    HirFileId, Impl, InFile, Label, LifetimeParam, Local, Macro, Module, ModuleDef, Name, Path,
-    ScopeDef, ToolModule, Trait, Type, TypeAlias, TypeParam, VariantDef,
+    Access, Adjustment, BindingMode, BuiltinAttr, Callable, ConstParam, Crate, Field, Function,
+    HasSource, HirFileId, Impl, InFile, Label, LifetimeParam, Local, Macro, Module, ModuleDef,
+    Name, Path, ScopeDef, ToolModule, Trait, Type, TypeAlias, TypeParam, VariantDef,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -163,6 +166,25 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         self.imp.is_attr_macro_call(item)
     }
 
+    /// Expands the proc-macro call again with the full backtrace in the error of a panic.
+    /// Returns `None` if `macro_call` doesn't call a proc-macro.
+    pub fn expand_with_backtrace(&self, macro_call: &ast::MacroCall) -> Option<ExpandResult<()>> {
+        self.imp.expand_with_backtrace(macro_call)
+    }
+
+    pub fn expand_attr_macro_with_backtrace(&self, item: &ast::Item) -> Option<ExpandResult<()>> {
+        self.imp.expand_attr_macro_with_backtrace(item)
+    }
+
+    /// Expands the `idx`-th derive of `derive` again, see `expand_with_backtrace`.
+    pub fn expand_derive_macro_with_backtrace(
+        &self,
+        derive: &ast::Attr,
+        idx: usize,
+    ) -> Option<ExpandResult<()>> {
+        self.imp.expand_derive_macro_with_backtrace(derive, idx)
+    }
+
     pub fn is_derive_annotated(&self, item: &ast::Adt) -> bool {
         self.imp.is_derive_annotated(item)
     }
@@ -247,6 +269,12 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         self.imp.original_range_opt(node)
     }
 
+    /// Attempts to map the token out of macro expanded files, returning the token it was written
+    /// as. This is either a token of a macro call's input or of a macro definition.
+    pub fn original_token(&self, token: SyntaxToken) -> Option<InFile<SyntaxToken>> {
+        self.imp.original_token(token)
+    }
+
     /// Attempts to map the node out of macro expanded files.
     /// This only work for attribute expansions, as other ones do not have nodes as input.
     pub fn original_ast_node<N: AstNode>(&self, node: N) -> Option<N> {
@@ -324,6 +352,12 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         self.imp.is_implicit_reborrow(expr)
     }
 
+    /// Returns the implicit conversions (autoderef steps, auto-borrows, pointer casts, ...)
+    /// applied to the value of `expr`, in the order they are applied.
+    pub fn expr_adjustments(&self, expr: &ast::Expr) -> Option<Vec<Adjustment>> {
+        self.imp.expr_adjustments(expr)
+    }
+
     pub fn type_of_expr(&self, expr: &ast::Expr) -> Option<TypeInfo> {
         self.imp.type_of_expr(expr)
     }
@@ -332,6 +366,12 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         self.imp.type_of_pat(pat)
     }
 
+    /// Evaluates `expr` as a constant expression in the scope of its body, see
+    /// `hir_ty::consteval::eval_expr_in_body`.
+    pub fn eval_expr(&self, expr: &ast::Expr) -> Option<Result<ComputedExpr, ConstEvalError>> {
+        self.imp.eval_expr(expr)
+    }
+
     pub fn type_of_self(&self, param: &ast::SelfParam) -> Option<Type> {
         self.imp.type_of_self(param)
     }
@@ -348,6 +388,26 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
         self.imp.resolve_method_call(call).map(Function::from)
     }
 
+    pub fn resolve_await(&self, await_expr: &ast::AwaitExpr) -> Option<Function> {
+        self.imp.resolve_await(await_expr).map(Function::from)
+    }
+
+    pub fn resolve_prefix_expr(&self, prefix_expr: &ast::PrefixExpr) -> Option<Function> {
+        self.imp.resolve_prefix_expr(prefix_expr).map(Function::from)
+    }
+
+    pub fn resolve_index_expr(&self, index_expr: &ast::IndexExpr) -> Option<Function> {
+        self.imp.resolve_index_expr(index_expr).map(Function::from)
+    }
+
+    pub fn resolve_bin_expr(&self, bin_expr: &ast::BinExpr) -> Option<Function> {
+        self.imp.resolve_bin_expr(bin_expr).map(Function::from)
+    }
+
+    pub fn resolve_try_expr(&self, try_expr: &ast::TryExpr) -> Option<Function> {
+        self.imp.resolve_try_expr(try_expr).map(Function::from)
+    }
+
     pub fn resolve_method_call_as_callable(&self, call: &ast::MethodCallExpr) -> Option<Callable> {
         self.imp.resolve_method_call_as_callable(call)
     }
@@ -447,6 +507,12 @@ impl<'db, DB: HirDatabase> Semantics<'db, DB> {
     pub fn is_unsafe_ident_pat(&self, ident_pat: &ast::IdentPat) -> bool {
         self.imp.is_unsafe_ident_pat(ident_pat)
     }
+
+    /// Returns the operations inside `expr` that require an unsafe context, like calls to unsafe
+    /// functions, dereferences of raw pointers and accesses to mutable statics.
+    pub fn unsafe_operations(&self, expr: &ast::Expr) -> Vec<InFile<ast::Expr>> {
+        self.imp.unsafe_operations(expr)
+    }
 }
 
 impl<'db> SemanticsImpl<'db> {
@@ -522,6 +588,40 @@ impl<'db> SemanticsImpl<'db> {
         Some(res)
     }
 
+    fn expand_with_backtrace(&self, macro_call: &ast::MacroCall) -> Option<ExpandResult<()>> {
+        let SourceAnalyzer { file_id, resolver, .. } =
+            self.analyze_no_infer(macro_call.syntax())?;
+        let krate = resolver.krate();
+        let macro_call_id =
+            InFile::new(file_id, macro_call).as_call_id(self.db.upcast(), krate, |path| {
+                resolver
+                    .resolve_path_as_macro(self.db.upcast(), &path)
+                    .map(|it| macro_id_to_def_id(self.db.upcast(), it))
+            })?;
+        self.proc_macro_with_backtrace(macro_call_id)
+    }
+
+    fn expand_attr_macro_with_backtrace(&self, item: &ast::Item) -> Option<ExpandResult<()>> {
+        let src = self.wrap_node_infile(item.clone());
+        let macro_call_id = self.with_ctx(|ctx| ctx.item_to_macro_call(src))?;
+        self.proc_macro_with_backtrace(macro_call_id)
+    }
+
+    fn expand_derive_macro_with_backtrace(
+        &self,
+        attr: &ast::Attr,
+        idx: usize,
+    ) -> Option<ExpandResult<()>> {
+        let macro_call_id = self.derive_macro_calls(attr)?.get(idx).copied().flatten()?;
+        self.proc_macro_with_backtrace(macro_call_id)
+    }
+
+    fn proc_macro_with_backtrace(&self, macro_call_id: MacroCallId) -> Option<ExpandResult<()>> {
+        let res =
+            hir_expand::db::expand_proc_macro_with_backtrace(self.db.upcast(), macro_call_id)?;
+        Some(res.map(|_| ()))
+    }
+
     fn derive_macro_calls(&self, attr: &ast::Attr) -> Option<Vec<Option<MacroCallId>>> {
         let adt = attr.syntax().parent().and_then(ast::Adt::cast)?;
         let file_id = self.find_file(adt.syntax()).file_id;
@@ -853,6 +953,14 @@ impl<'db> SemanticsImpl<'db> {
         node.original_file_range_opt(self.db.upcast())
     }
 
+    fn original_token(&self, token: SyntaxToken) -> Option<InFile<SyntaxToken>> {
+        let mut token = InFile::new(self.find_file(&token.parent()?).file_id, token);
+        while token.file_id.is_macro() {
+            token = token.upmap(self.db.upcast())?;
+        }
+        Some(token)
+    }
+
     fn original_ast_node<N: AstNode>(&self, node: N) -> Option<N> {
         self.wrap_node_infile(node).original_ast_node(self.db.upcast()).map(|it| it.value)
     }
@@ -949,6 +1057,14 @@ impl<'db> SemanticsImpl<'db> {
             .map(|(ty, coerced)| TypeInfo { original: ty, adjusted: coerced })
     }
 
+    fn expr_adjustments(&self, expr: &ast::Expr) -> Option<Vec<Adjustment>> {
+        self.analyze(expr.syntax())?.expr_adjustments(self.db, expr)
+    }
+
+    fn eval_expr(&self, expr: &ast::Expr) -> Option<Result<ComputedExpr, ConstEvalError>> {
+        self.analyze(expr.syntax())?.eval_expr(self.db, expr)
+    }
+
     fn type_of_pat(&self, pat: &ast::Pat) -> Option<TypeInfo> {
         self.analyze(pat.syntax())?
             .type_of_pat(self.db, pat)
@@ -973,6 +1089,26 @@ impl<'db> SemanticsImpl<'db> {
         self.analyze(call.syntax())?.resolve_method_call(self.db, call).map(|(id, _)| id)
     }
 
+    fn resolve_await(&self, await_expr: &ast::AwaitExpr) -> Option<FunctionId> {
+        self.analyze(await_expr.syntax())?.resolve_await(self.db, await_expr)
+    }
+
+    fn resolve_prefix_expr(&self, prefix_expr: &ast::PrefixExpr) -> Option<FunctionId> {
+        self.analyze(prefix_expr.syntax())?.resolve_prefix_expr(self.db, prefix_expr)
+    }
+
+    fn resolve_index_expr(&self, index_expr: &ast::IndexExpr) -> Option<FunctionId> {
+        self.analyze(index_expr.syntax())?.resolve_index_expr(self.db, index_expr)
+    }
+
+    fn resolve_bin_expr(&self, bin_expr: &ast::BinExpr) -> Option<FunctionId> {
+        self.analyze(bin_expr.syntax())?.resolve_bin_expr(self.db, bin_expr)
+    }
+
+    fn resolve_try_expr(&self, try_expr: &ast::TryExpr) -> Option<FunctionId> {
+        self.analyze(try_expr.syntax())?.resolve_try_expr(self.db, try_expr)
+    }
+
     fn resolve_method_call_as_callable(&self, call: &ast::MethodCallExpr) -> Option<Callable> {
         let source_analyzer = self.analyze(call.syntax())?;
         let (func, subst) = source_analyzer.resolve_method_call(self.db, call)?;
@@ -1196,6 +1332,20 @@ impl<'db> SemanticsImpl<'db> {
         InFile::new(file_id, node)
     }
 
+    fn unsafe_operations(&self, expr: &ast::Expr) -> Vec<InFile<ast::Expr>> {
+        let sa = match self.analyze(expr.syntax()) {
+            Some(it) => it,
+            None => return Vec::new(),
+        };
+        sa.unsafe_operations(self.db, expr)
+            .into_iter()
+            .filter_map(|ptr| {
+                let root = self.parse_or_expand(ptr.file_id)?;
+                Some(InFile::new(ptr.file_id, ptr.value.to_node(&root)))
+            })
+            .collect()
+    }
+
     fn is_unsafe_method_call(&self, method_call_expr: &ast::MethodCallExpr) -> bool {
         method_call_expr
             .receiver()
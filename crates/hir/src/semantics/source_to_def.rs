@@ -127,6 +127,13 @@ impl SourceToDefCtx<'_, '_> {
                     .map(|local_id| crate_def_map.module_id(local_id)),
             )
         }
+        // Prefer the crate of the file's active target, or the default one, when the workspace
+        // is analyzed for several targets.
+        if mods.len() > 1 {
+            let active_target = self.db.active_targets().get(&file).cloned();
+            let crate_graph = self.db.crate_graph();
+            mods.sort_by_key(|module| crate_graph[module.krate()].target_triple != active_target);
+        }
         mods
     }
 
@@ -7,6 +7,7 @@
 //! purely for "IDE needs".
 use std::{
     iter::{self, once},
+    mem,
     sync::Arc,
 };
 
@@ -18,26 +19,31 @@ use hir_def::{
     },
     expr::{ExprId, Pat, PatId},
     macro_id_to_def_id,
-    path::{ModPath, Path, PathKind},
+    path::{path, ModPath, Path, PathKind},
     resolver::{resolver_for_scope, Resolver, TypeNs, ValueNs},
     type_ref::Mutability,
-    AsMacroCall, DefWithBodyId, FieldId, FunctionId, LocalFieldId, Lookup, ModuleDefId, VariantId,
+    AsMacroCall, DefWithBodyId, FieldId, FunctionId, LocalFieldId, Lookup, ModuleDefId, TraitId,
+    VariantId,
 };
 use hir_expand::{
-    builtin_fn_macro::BuiltinFnLikeExpander, hygiene::Hygiene, name::AsName, HirFileId, InFile,
+    builtin_fn_macro::BuiltinFnLikeExpander,
+    hygiene::Hygiene,
+    name::{name, AsName, Name},
+    HirFileId, InFile,
 };
 use hir_ty::{
+    consteval::{self, ComputedExpr, ConstEvalError},
     diagnostics::{
         record_literal_missing_fields, record_pattern_missing_fields, unsafe_expressions,
         UnsafeExpr,
     },
-    Adjust, Adjustment, AutoBorrow, InferenceResult, Interner, Substitution, TyExt,
-    TyLoweringContext,
+    method_resolution, Adjust, Adjustment, AutoBorrow, InferenceResult, Interner, PointerCast,
+    Scalar, Substitution, TraitEnvironment, Ty, TyBuilder, TyExt, TyKind, TyLoweringContext,
 };
 use smallvec::SmallVec;
 use syntax::{
     ast::{self, AstNode},
-    SyntaxKind, SyntaxNode, TextRange, TextSize,
+    AstPtr, SyntaxKind, SyntaxNode, TextRange, TextSize,
 };
 
 use crate::{
@@ -157,6 +163,56 @@ impl SourceAnalyzer {
         })
     }
 
+    pub(crate) fn expr_adjustments(
+        &self,
+        db: &dyn HirDatabase,
+        expr: &ast::Expr,
+    ) -> Option<Vec<crate::Adjustment>> {
+        let expr_id = self.expr_id(db, expr)?;
+        let infer = self.infer.as_ref()?;
+        let adjustments = infer.expr_adjustments.get(&expr_id)?;
+        let mk_ty = |ty: &Ty| Type::new_with_resolver(db, &self.resolver, ty.clone());
+        let mutability = |m| match m {
+            hir_ty::Mutability::Not => Mutability::Shared,
+            hir_ty::Mutability::Mut => Mutability::Mut,
+        };
+        let mut source = mk_ty(&infer[expr_id]);
+        let res = adjustments
+            .iter()
+            .map(|adjustment| {
+                let kind = match adjustment.kind {
+                    Adjust::NeverToAny => crate::Adjust::NeverToAny,
+                    Adjust::Deref(overloaded) => crate::Adjust::Deref(
+                        overloaded.map(|it| crate::OverloadedDeref(mutability(it.0))),
+                    ),
+                    Adjust::Borrow(AutoBorrow::Ref(m)) => {
+                        crate::Adjust::Borrow(crate::AutoBorrow::Ref(mutability(m)))
+                    }
+                    Adjust::Borrow(AutoBorrow::RawPtr(m)) => {
+                        crate::Adjust::Borrow(crate::AutoBorrow::RawPtr(mutability(m)))
+                    }
+                    Adjust::Pointer(cast) => crate::Adjust::Pointer(match cast {
+                        PointerCast::ReifyFnPointer => crate::PointerCast::ReifyFnPointer,
+                        PointerCast::UnsafeFnPointer => crate::PointerCast::UnsafeFnPointer,
+                        PointerCast::ClosureFnPointer(safety) => {
+                            crate::PointerCast::ClosureFnPointer(match safety {
+                                hir_ty::Safety::Safe => crate::Safety::Safe,
+                                hir_ty::Safety::Unsafe => crate::Safety::Unsafe,
+                            })
+                        }
+                        PointerCast::MutToConstPointer => crate::PointerCast::MutToConstPointer,
+                        PointerCast::ArrayToPointer => crate::PointerCast::ArrayToPointer,
+                        PointerCast::Unsize => crate::PointerCast::Unsize,
+                    }),
+                };
+                let target = mk_ty(&adjustment.target);
+                let source = mem::replace(&mut source, target.clone());
+                crate::Adjustment { source, target, kind }
+            })
+            .collect();
+        Some(res)
+    }
+
     pub(crate) fn type_of_expr(
         &self,
         db: &dyn HirDatabase,
@@ -173,6 +229,16 @@ impl SourceAnalyzer {
         Some((mk_ty(ty), coerced.map(mk_ty)))
     }
 
+    pub(crate) fn eval_expr(
+        &self,
+        db: &dyn HirDatabase,
+        expr: &ast::Expr,
+    ) -> Option<Result<ComputedExpr, ConstEvalError>> {
+        let expr_id = self.expr_id(db, expr)?;
+        let (def, ..) = self.def.as_ref()?;
+        Some(consteval::eval_expr_in_body(db, *def, expr_id))
+    }
+
     pub(crate) fn type_of_pat(
         &self,
         db: &dyn HirDatabase,
@@ -241,6 +307,111 @@ impl SourceAnalyzer {
         self.infer.as_ref()?.method_resolution(expr_id)
     }
 
+    pub(crate) fn resolve_await(
+        &self,
+        db: &dyn HirDatabase,
+        await_expr: &ast::AwaitExpr,
+    ) -> Option<FunctionId> {
+        let ty = self.ty_of_expr(db, &await_expr.expr()?)?;
+        let future_trait = self.lang_trait(db, name![future_trait])?;
+        let poll_fn = db.trait_data(future_trait).method_by_name(&name![poll])?;
+        let substs = TyBuilder::subst_for_def(db, poll_fn).push(ty.clone()).build();
+        Some(self.resolve_impl_method_or_trait_def(db, poll_fn, substs))
+    }
+
+    pub(crate) fn resolve_prefix_expr(
+        &self,
+        db: &dyn HirDatabase,
+        prefix_expr: &ast::PrefixExpr,
+    ) -> Option<FunctionId> {
+        let lang_item_name = match prefix_expr.op_kind()? {
+            ast::UnaryOp::Deref => name![deref],
+            ast::UnaryOp::Not => name![not],
+            ast::UnaryOp::Neg => name![neg],
+        };
+        let ty = self.ty_of_expr(db, &prefix_expr.expr()?)?;
+        // Builtin operators don't go through their traits.
+        let is_builtin = match (prefix_expr.op_kind()?, ty.kind(Interner)) {
+            (ast::UnaryOp::Deref, TyKind::Ref(..) | TyKind::Raw(..)) => true,
+            (
+                ast::UnaryOp::Not,
+                TyKind::Scalar(Scalar::Bool | Scalar::Int(_) | Scalar::Uint(_)),
+            ) => true,
+            (
+                ast::UnaryOp::Neg,
+                TyKind::Scalar(Scalar::Int(_) | Scalar::Uint(_) | Scalar::Float(_)),
+            ) => true,
+            _ => false,
+        };
+        if is_builtin {
+            return None;
+        }
+        let op_trait = self.lang_trait(db, lang_item_name.clone())?;
+        // The methods are named after the lang items of their traits.
+        let op_fn = db.trait_data(op_trait).method_by_name(&lang_item_name)?;
+        let substs = TyBuilder::subst_for_def(db, op_fn).push(ty.clone()).build();
+        Some(self.resolve_impl_method_or_trait_def(db, op_fn, substs))
+    }
+
+    pub(crate) fn resolve_index_expr(
+        &self,
+        db: &dyn HirDatabase,
+        index_expr: &ast::IndexExpr,
+    ) -> Option<FunctionId> {
+        let base_ty = self.ty_of_expr(db, &index_expr.base()?)?;
+        let index_ty = self.ty_of_expr(db, &index_expr.index()?)?;
+        // Arrays and slices are indexed by integers without going through `Index`.
+        let mut indexed_ty = base_ty;
+        while let TyKind::Ref(.., inner) = indexed_ty.kind(Interner) {
+            indexed_ty = inner;
+        }
+        if matches!(indexed_ty.kind(Interner), TyKind::Array(..) | TyKind::Slice(_))
+            && matches!(index_ty.kind(Interner), TyKind::Scalar(Scalar::Int(_) | Scalar::Uint(_)))
+        {
+            return None;
+        }
+        let index_trait = self.lang_trait(db, name![index])?;
+        let index_fn = db.trait_data(index_trait).method_by_name(&name![index])?;
+        let substs = TyBuilder::subst_for_def(db, index_fn)
+            .push(base_ty.clone())
+            .push(index_ty.clone())
+            .build();
+        Some(self.resolve_impl_method_or_trait_def(db, index_fn, substs))
+    }
+
+    pub(crate) fn resolve_bin_expr(
+        &self,
+        db: &dyn HirDatabase,
+        bin_expr: &ast::BinExpr,
+    ) -> Option<FunctionId> {
+        // Operators on primitive operands are builtin, even though inference resolves them to
+        // their trait method as well.
+        let is_scalar = |expr: Option<ast::Expr>| {
+            expr.and_then(|it| self.ty_of_expr(db, &it))
+                .map_or(false, |ty| matches!(ty.kind(Interner), TyKind::Scalar(_)))
+        };
+        if is_scalar(bin_expr.lhs()) && is_scalar(bin_expr.rhs()) {
+            return None;
+        }
+        // Inference already resolved the operator to its trait method.
+        let expr_id = self.expr_id(db, &bin_expr.clone().into())?;
+        let (op_fn, substs) = self.infer.as_ref()?.method_resolution(expr_id)?;
+        Some(self.resolve_impl_method_or_trait_def(db, op_fn, substs))
+    }
+
+    pub(crate) fn resolve_try_expr(
+        &self,
+        db: &dyn HirDatabase,
+        try_expr: &ast::TryExpr,
+    ) -> Option<FunctionId> {
+        let ty = self.ty_of_expr(db, &try_expr.expr()?)?;
+        // FIXME resolve via lang_item once try v2 is stable
+        let try_trait = self.resolver.resolve_known_trait(db.upcast(), &path![core::ops::Try])?;
+        let branch_fn = db.trait_data(try_trait).method_by_name(&name![branch])?;
+        let substs = TyBuilder::subst_for_def(db, branch_fn).push(ty.clone()).build();
+        Some(self.resolve_impl_method_or_trait_def(db, branch_fn, substs))
+    }
+
     pub(crate) fn resolve_field(
         &self,
         db: &dyn HirDatabase,
@@ -561,6 +732,46 @@ impl SourceAnalyzer {
         }
         false
     }
+
+    pub(crate) fn unsafe_operations(
+        &self,
+        db: &dyn HirDatabase,
+        expr: &ast::Expr,
+    ) -> Vec<InFile<AstPtr<ast::Expr>>> {
+        let (expr_id, (def, body, sm), infer) =
+            match (self.expr_id(db, expr), &self.def, &self.infer) {
+                (Some(expr_id), Some(def), Some(infer)) => (expr_id, def, infer),
+                _ => return Vec::new(),
+            };
+        let mut res = Vec::new();
+        unsafe_expressions(db, infer, *def, body, expr_id, &mut |UnsafeExpr { expr, .. }| {
+            res.extend(sm.expr_syntax(expr).ok())
+        });
+        res
+    }
+
+    fn ty_of_expr(&self, db: &dyn HirDatabase, expr: &ast::Expr) -> Option<&Ty> {
+        self.infer.as_ref()?.type_of_expr.get(self.expr_id(db, expr)?)
+    }
+
+    fn lang_trait(&self, db: &dyn HirDatabase, name: Name) -> Option<TraitId> {
+        db.lang_item(self.resolver.krate(), name.to_smol_str())?.as_trait()
+    }
+
+    /// Returns the impl method a call of the trait method `func` dispatches to, or `func` itself
+    /// if the impl isn't known.
+    fn resolve_impl_method_or_trait_def(
+        &self,
+        db: &dyn HirDatabase,
+        func: FunctionId,
+        substs: Substitution,
+    ) -> FunctionId {
+        let env = self.resolver.generic_def().map_or_else(
+            || Arc::new(TraitEnvironment::empty(self.resolver.krate())),
+            |d| db.trait_environment(d),
+        );
+        method_resolution::lookup_impl_method(db, env, func, substs).map_or(func, |(it, _)| it)
+    }
 }
 
 fn scope_for(
@@ -2,8 +2,9 @@
 
 use base_db::FileRange;
 use hir_def::{
-    item_tree::ItemTreeNode, src::HasSource, AdtId, AssocItemId, AssocItemLoc, DefWithBodyId,
-    HasModule, ImplId, ItemContainerId, Lookup, MacroId, ModuleDefId, ModuleId, TraitId,
+    item_tree::ItemTreeNode, src::HasSource, AdtId, AssocItemId, AssocItemLoc, AttrDefId,
+    DefWithBodyId, HasModule, ImplId, ItemContainerId, Lookup, MacroId, ModuleDefId, ModuleId,
+    TraitId,
 };
 use hir_expand::{HirFileId, InFile};
 use hir_ty::db::HirDatabase;
@@ -19,6 +20,8 @@ pub struct FileSymbol {
     pub loc: DeclarationLocation,
     pub kind: FileSymbolKind,
     pub container_name: Option<SmolStr>,
+    /// Whether `name` is an alias given with `#[doc(alias = "...")]` instead of the item's name.
+    pub is_alias: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -255,7 +258,7 @@ impl<'a> SymbolCollector<'a> {
 
     fn push_decl_assoc<L, T>(&mut self, id: L, kind: FileSymbolKind)
     where
-        L: Lookup<Data = AssocItemLoc<T>>,
+        L: Lookup<Data = AssocItemLoc<T>> + Into<AttrDefId> + Copy,
         T: ItemTreeNode,
         <T as ItemTreeNode>::Source: HasName,
     {
@@ -273,7 +276,7 @@ impl<'a> SymbolCollector<'a> {
             }
         }
 
-        self.push_file_symbol(|s| {
+        self.push_file_symbol(id.into(), |s| {
             let loc = id.lookup(s.db.upcast());
             let source = loc.source(s.db.upcast());
             let name_node = source.value.name()?;
@@ -284,6 +287,7 @@ impl<'a> SymbolCollector<'a> {
                 name: name_node.text().into(),
                 kind,
                 container_name,
+                is_alias: false,
                 loc: DeclarationLocation {
                     hir_file_id: source.file_id,
                     ptr: SyntaxNodePtr::new(source.value.syntax()),
@@ -295,11 +299,11 @@ impl<'a> SymbolCollector<'a> {
 
     fn push_decl<L>(&mut self, id: L, kind: FileSymbolKind)
     where
-        L: Lookup,
+        L: Lookup + Into<AttrDefId> + Copy,
         <L as Lookup>::Data: HasSource,
         <<L as Lookup>::Data as HasSource>::Value: HasName,
     {
-        self.push_file_symbol(|s| {
+        self.push_file_symbol(id.into(), |s| {
             let loc = id.lookup(s.db.upcast());
             let source = loc.source(s.db.upcast());
             let name_node = source.value.name()?;
@@ -308,6 +312,7 @@ impl<'a> SymbolCollector<'a> {
                 name: name_node.text().into(),
                 kind,
                 container_name: s.current_container_name(),
+                is_alias: false,
                 loc: DeclarationLocation {
                     hir_file_id: source.file_id,
                     ptr: SyntaxNodePtr::new(source.value.syntax()),
@@ -318,7 +323,7 @@ impl<'a> SymbolCollector<'a> {
     }
 
     fn push_module(&mut self, module_id: ModuleId) {
-        self.push_file_symbol(|s| {
+        self.push_file_symbol(module_id.into(), |s| {
             let def_map = module_id.def_map(s.db.upcast());
             let module_data = &def_map[module_id.local_id];
             let declaration = module_data.origin.declaration()?;
@@ -329,6 +334,7 @@ impl<'a> SymbolCollector<'a> {
                 name: name_node.text().into(),
                 kind: FileSymbolKind::Module,
                 container_name: s.current_container_name(),
+                is_alias: false,
                 loc: DeclarationLocation {
                     hir_file_id: declaration.file_id,
                     ptr: SyntaxNodePtr::new(module.syntax()),
@@ -338,8 +344,16 @@ impl<'a> SymbolCollector<'a> {
         })
     }
 
-    fn push_file_symbol(&mut self, f: impl FnOnce(&Self) -> Option<FileSymbol>) {
+    fn push_file_symbol(&mut self, def: AttrDefId, f: impl FnOnce(&Self) -> Option<FileSymbol>) {
         if let Some(file_symbol) = f(self) {
+            // Index the item under its doc aliases as well, so searching for them finds it.
+            for alias in self.db.attrs(def).doc_aliases() {
+                self.symbols.push(FileSymbol {
+                    name: alias,
+                    is_alias: true,
+                    ..file_symbol.clone()
+                });
+            }
             self.symbols.push(file_symbol);
         }
     }
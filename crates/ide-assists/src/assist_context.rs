@@ -329,7 +329,19 @@ impl AssistBuilder {
         algo::diff(old.syntax(), new.syntax()).into_text_edit(&mut self.edit)
     }
     pub(crate) fn create_file(&mut self, dst: AnchoredPathBuf, content: impl Into<String>) {
-        let file_system_edit = FileSystemEdit::CreateFile { dst, initial_contents: content.into() };
+        let file_system_edit =
+            FileSystemEdit::CreateFile { dst, initial_contents: content.into(), is_snippet: false };
+        self.source_change.push_file_system_edit(file_system_edit);
+    }
+    /// Creates a new file whose `snippet` contents may place tab stops in it.
+    pub(crate) fn create_file_snippet(
+        &mut self,
+        _cap: SnippetCap,
+        dst: AnchoredPathBuf,
+        snippet: impl Into<String>,
+    ) {
+        let file_system_edit =
+            FileSystemEdit::CreateFile { dst, initial_contents: snippet.into(), is_snippet: true };
         self.source_change.push_file_system_edit(file_system_edit);
     }
     pub(crate) fn move_file(&mut self, src: FileId, dst: AnchoredPathBuf) {
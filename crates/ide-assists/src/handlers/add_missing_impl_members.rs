@@ -290,6 +290,37 @@ impl Foo for S {
         );
     }
 
+    #[test]
+    fn test_async_fn_and_impl_trait_in_trait() {
+        check_assist(
+            add_missing_impl_members,
+            r#"
+trait Iter {}
+trait Foo {
+    async fn foo(&self) -> u32;
+    fn iter(&self) -> impl Iter;
+}
+struct S;
+impl Foo for S { $0 }"#,
+            r#"
+trait Iter {}
+trait Foo {
+    async fn foo(&self) -> u32;
+    fn iter(&self) -> impl Iter;
+}
+struct S;
+impl Foo for S {
+    async fn foo(&self) -> u32 {
+        ${0:todo!()}
+    }
+
+    fn iter(&self) -> impl Iter {
+        todo!()
+    }
+}"#,
+        );
+    }
+
     #[test]
     fn test_impl_def_without_braces() {
         check_assist(
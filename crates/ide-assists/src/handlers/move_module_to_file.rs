@@ -9,7 +9,7 @@ use syntax::{
     AstNode, SmolStr, TextRange,
 };
 
-use crate::{AssistContext, AssistId, AssistKind, Assists};
+use crate::{utils::escape_snippet_text, AssistContext, AssistId, AssistKind, Assists};
 
 // Assist: move_module_to_file
 //
@@ -96,7 +96,14 @@ pub(crate) fn move_module_to_file(acc: &mut Assists, ctx: &AssistContext) -> Opt
             );
 
             let dst = AnchoredPathBuf { anchor: ctx.file_id(), path };
-            builder.create_file(dst, contents);
+            match ctx.config.snippet_cap {
+                Some(cap) => builder.create_file_snippet(
+                    cap,
+                    dst,
+                    format!("$0{}", escape_snippet_text(&contents)),
+                ),
+                None => builder.create_file(dst, contents),
+            }
         },
     )
 }
@@ -120,7 +127,31 @@ mod $0tests {
 //- /main.rs
 mod tests;
 //- /tests.rs
-#[test] fn t() {}
+$0#[test] fn t() {}
+"#,
+        );
+    }
+
+    #[test]
+    fn escapes_snippet_syntax() {
+        check_assist(
+            move_module_to_file,
+            r#"
+mod $0macros {
+    macro_rules! id {
+        ($e:expr) => { $e };
+    }
+    const S: &str = "\\";
+}
+"#,
+            r#"
+//- /main.rs
+mod macros;
+//- /macros.rs
+$0macro_rules! id {
+    (\$e:expr) => { \$e \};
+\}
+const S: &str = "\\\\";
 "#,
         );
     }
@@ -143,7 +174,7 @@ fn g() {}
 mod inner;
 fn g() {}
 //- /submod/inner.rs
-fn f() {}
+$0fn f() {}
 "#,
         );
     }
@@ -166,7 +197,7 @@ fn g() {}
 mod inner;
 fn g() {}
 //- /submodule/inner.rs
-fn f() {}
+$0fn f() {}
 "#,
         );
     }
@@ -184,7 +215,7 @@ pub mod $0tests {
 //- /main.rs
 pub mod tests;
 //- /tests.rs
-#[test] fn t() {}
+$0#[test] fn t() {}
 "#,
         );
     }
@@ -202,7 +233,7 @@ pub(crate) mod $0tests {
 //- /main.rs
 pub(crate) mod tests;
 //- /tests.rs
-#[test] fn t() {}
+$0#[test] fn t() {}
 "#,
         );
     }
@@ -230,7 +261,7 @@ mod $0tests {
 #[attribute]
 mod tests;
 //- /tests.rs
-#[test] fn t() {}
+$0#[test] fn t() {}
 "#,
         );
     }
@@ -257,7 +288,7 @@ mod bar {
     }
 }
 //- /foo/bar/baz/qux.rs
-"#,
+$0"#,
         );
     }
 
@@ -273,7 +304,7 @@ mod $0r#static {}
 //- /main.rs
 mod r#static;
 //- /static.rs
-"#,
+$0"#,
         )
     }
 
@@ -289,7 +320,7 @@ mod $0r#mod {}
 //- /main.rs
 mod r#mod;
 //- /mod/mod.rs
-"#,
+$0"#,
         )
     }
 
@@ -307,7 +338,7 @@ mod $0r#mod {}
 //- /foo/mod.rs
 mod r#mod;
 //- /foo/mod/mod.rs
-"#,
+$0"#,
         )
     }
 
@@ -331,7 +362,7 @@ mod r#mod {
     }
 }
 //- /mod/foo/mod/mod.rs
-"#,
+$0"#,
         )
     }
 }
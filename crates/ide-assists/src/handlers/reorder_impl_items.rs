@@ -1,10 +1,6 @@
-use hir::{PathResolution, Semantics};
-use ide_db::{FxHashMap, RootDatabase};
+use ide_db::traits::{assoc_item_rank, trait_item_ranks};
 use itertools::Itertools;
-use syntax::{
-    ast::{self, HasName},
-    ted, AstNode,
-};
+use syntax::{ast, ted, AstNode};
 
 use crate::{AssistContext, AssistId, AssistKind, Assists};
 
@@ -47,29 +43,9 @@ pub(crate) fn reorder_impl_items(acc: &mut Assists, ctx: &AssistContext) -> Opti
     let items = impl_ast.assoc_item_list()?;
     let assoc_items = items.assoc_items().collect::<Vec<_>>();
 
-    let path = impl_ast
-        .trait_()
-        .and_then(|t| match t {
-            ast::Type::PathType(path) => Some(path),
-            _ => None,
-        })?
-        .path()?;
-
-    let ranks = compute_item_ranks(&path, ctx)?;
-    let sorted: Vec<_> = assoc_items
-        .iter()
-        .cloned()
-        .sorted_by_key(|i| {
-            let name = match i {
-                ast::AssocItem::Const(c) => c.name(),
-                ast::AssocItem::Fn(f) => f.name(),
-                ast::AssocItem::TypeAlias(t) => t.name(),
-                ast::AssocItem::MacroCall(_) => None,
-            };
-
-            name.and_then(|n| ranks.get(&n.to_string()).copied()).unwrap_or(usize::max_value())
-        })
-        .collect();
+    let ranks = trait_item_ranks(&ctx.sema, &impl_ast)?;
+    let sorted: Vec<_> =
+        assoc_items.iter().cloned().sorted_by_key(|it| assoc_item_rank(&ranks, it)).collect();
 
     // Don't edit already sorted methods:
     if assoc_items == sorted {
@@ -93,26 +69,6 @@ pub(crate) fn reorder_impl_items(acc: &mut Assists, ctx: &AssistContext) -> Opti
     )
 }
 
-fn compute_item_ranks(path: &ast::Path, ctx: &AssistContext) -> Option<FxHashMap<String, usize>> {
-    let td = trait_definition(path, &ctx.sema)?;
-
-    Some(
-        td.items(ctx.db())
-            .iter()
-            .flat_map(|i| i.name(ctx.db()))
-            .enumerate()
-            .map(|(idx, name)| (name.to_string(), idx))
-            .collect(),
-    )
-}
-
-fn trait_definition(path: &ast::Path, sema: &Semantics<RootDatabase>) -> Option<hir::Trait> {
-    match sema.resolve_path(path)? {
-        PathResolution::Def(hir::ModuleDef::Trait(trait_)) => Some(trait_),
-        _ => None,
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use crate::tests::{check_assist, check_assist_not_applicable};
@@ -158,7 +158,9 @@ fn check(handler: Handler, before: &str, expected: ExpectedResult, assist_label:
 
             for file_system_edit in source_change.file_system_edits {
                 let (dst, contents) = match file_system_edit {
-                    FileSystemEdit::CreateFile { dst, initial_contents } => (dst, initial_contents),
+                    FileSystemEdit::CreateFile { dst, initial_contents, .. } => {
+                        (dst, initial_contents)
+                    }
                     FileSystemEdit::MoveFile { src, dst } => {
                         (dst, db.file_text(src).as_ref().to_owned())
                     }
@@ -2,7 +2,7 @@
 
 mod format_like;
 
-use hir::{Documentation, HasAttrs};
+use hir::Documentation;
 use ide_db::{imports::insert_use::ImportScope, ty_filter::TryEnum, SnippetCap};
 use syntax::{
     ast::{self, AstNode, AstToken},
@@ -62,7 +62,7 @@ pub(crate) fn complete_postfix(acc: &mut Completions, ctx: &CompletionContext) {
                     "fn drop(&mut self)",
                     &format!("drop($0{})", receiver_text),
                 );
-                item.set_documentation(drop_fn.docs(ctx.db));
+                item.set_documentation(ctx.docs(drop_fn));
                 item.add_to(acc);
             }
         }
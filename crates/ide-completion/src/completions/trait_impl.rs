@@ -31,7 +31,6 @@
 //! }
 //! ```
 
-use hir::{self, HasAttrs};
 use ide_db::{
     path_transform::PathTransform, syntax_helpers::insert_whitespace_into_node,
     traits::get_missing_assoc_items, SymbolKind,
@@ -155,9 +154,11 @@ fn add_function_impl(
     impl_def: hir::Impl,
 ) {
     let fn_name = func.name(ctx.db);
+    let async_ = if func.is_async(ctx.db) { "async " } else { "" };
 
     let label = format!(
-        "fn {}({})",
+        "{}fn {}({})",
+        async_,
         fn_name,
         if func.assoc_fn_params(ctx.db).is_empty() { "" } else { ".." }
     );
@@ -169,8 +170,8 @@ fn add_function_impl(
     };
 
     let mut item = CompletionItem::new(completion_kind, replacement_range, label);
-    item.lookup_by(format!("fn {}", fn_name))
-        .set_documentation(func.docs(ctx.db))
+    item.lookup_by(format!("{}fn {}", async_, fn_name))
+        .set_documentation(ctx.docs(func))
         .set_relevance(CompletionRelevance { is_item_from_trait: true, ..Default::default() });
 
     if let Some(source) = ctx.sema.source(func) {
@@ -234,7 +235,7 @@ fn add_type_alias_impl(
 
     let mut item = CompletionItem::new(SymbolKind::TypeAlias, replacement_range, label);
     item.lookup_by(format!("type {}", alias_name))
-        .set_documentation(type_alias.docs(ctx.db))
+        .set_documentation(ctx.docs(type_alias))
         .set_relevance(CompletionRelevance { is_item_from_trait: true, ..Default::default() });
     match ctx.config.snippet_cap {
         Some(cap) => item
@@ -267,7 +268,7 @@ fn add_const_impl(
 
                 let mut item = CompletionItem::new(SymbolKind::Const, replacement_range, label);
                 item.lookup_by(format!("const {}", const_name))
-                    .set_documentation(const_.docs(ctx.db))
+                    .set_documentation(ctx.docs(const_))
                     .set_relevance(CompletionRelevance {
                         is_item_from_trait: true,
                         ..Default::default()
@@ -637,6 +638,35 @@ impl Test for T {
         );
     }
 
+    #[test]
+    fn async_fn_completion() {
+        check_edit(
+            "async fn test",
+            r#"
+trait Test {
+    async fn test(&self) -> u32;
+}
+struct T;
+
+impl Test for T {
+    t$0
+}
+"#,
+            r#"
+trait Test {
+    async fn test(&self) -> u32;
+}
+struct T;
+
+impl Test for T {
+    async fn test(&self) -> u32 {
+    $0
+}
+}
+"#,
+        );
+    }
+
     #[test]
     fn generic_fn() {
         check_edit(
@@ -18,6 +18,22 @@ pub struct CompletionConfig {
     pub snippet_cap: Option<SnippetCap>,
     pub insert_use: InsertUseConfig,
     pub snippets: Vec<Snippet>,
+    pub fields_to_resolve: CompletionFieldsToResolve,
+}
+
+/// Which parts of a completion item are left out of the initial response, to be computed by
+/// `resolve_completion_item` when the client asks for them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompletionFieldsToResolve {
+    pub resolve_documentation: bool,
+    /// Also moves the name of the trait an associated item comes from out of the label.
+    pub resolve_detail: bool,
+}
+
+impl CompletionFieldsToResolve {
+    pub const fn empty() -> Self {
+        CompletionFieldsToResolve { resolve_documentation: false, resolve_detail: false }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
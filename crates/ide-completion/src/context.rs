@@ -4,7 +4,8 @@ use std::iter;
 
 use base_db::SourceDatabaseExt;
 use hir::{
-    HasAttrs, Local, Name, PathResolution, ScopeDef, Semantics, SemanticsScope, Type, TypeInfo,
+    AsAssocItem, HasAttrs, Local, Name, PathResolution, ScopeDef, Semantics, SemanticsScope, Type,
+    TypeInfo,
 };
 use ide_db::{
     active_parameter::ActiveParameter,
@@ -22,6 +23,7 @@ use syntax::{
 use text_edit::Indel;
 
 use crate::{
+    item::Builder,
     patterns::{
         determine_location, determine_prev_sibling, is_in_loop_body, is_in_token_of_for_loop,
         previous_token, ImmediateLocation, ImmediatePrevSibling,
@@ -257,6 +259,10 @@ pub(crate) struct CompletionContext<'a> {
     pub(super) existing_derives: FxHashSet<hir::Macro>,
 
     pub(super) locals: FxHashMap<Name, Local>,
+
+    /// Whether the completions are computed again to resolve one of them, so the fields left
+    /// out of the initial response have to be filled in.
+    pub(super) resolving: bool,
 }
 
 impl<'a> CompletionContext<'a> {
@@ -496,6 +502,54 @@ impl<'a> CompletionContext<'a> {
         // `doc(hidden)` items are only completed within the defining crate.
         self.krate != defining_crate && attrs.has_doc_hidden()
     }
+
+    /// The documentation of `def`, unless the client resolves documentation lazily.
+    pub(crate) fn docs(&self, def: impl HasAttrs) -> Option<hir::Documentation> {
+        if self.config.fields_to_resolve.resolve_documentation && !self.resolving {
+            return None;
+        }
+        def.docs(self.db)
+    }
+
+    /// Names the trait `assoc` comes from in the label of `item`, and returns whether it did.
+    /// Clients resolving the detail lazily get the trait in the detail instead, which is only
+    /// looked up when resolving the item.
+    pub(crate) fn add_trait_name(&self, item: &mut Builder, assoc: impl AsAssocItem) -> bool {
+        let lazy = self.config.fields_to_resolve.resolve_detail;
+        if lazy && !self.resolving {
+            return false;
+        }
+        let trait_ = match assoc
+            .as_assoc_item(self.db)
+            .and_then(|it| it.containing_trait_or_trait_impl(self.db))
+        {
+            Some(it) => it,
+            None => return false,
+        };
+        let name = trait_.name(self.db).to_smol_str();
+        if lazy {
+            item.detail_trait_name(name);
+            false
+        } else {
+            item.trait_name(name);
+            true
+        }
+    }
+
+    /// Whether an item is `#[unstable]` behind a feature the current crate hasn't enabled.
+    pub(crate) fn is_unstable_feature_disabled(
+        &self,
+        attrs: &hir::Attrs,
+        defining_crate: hir::Crate,
+    ) -> bool {
+        match attrs.unstable_feature() {
+            Some(feature) => {
+                self.krate != defining_crate
+                    && !self.krate.is_unstable_feature_enabled(self.db, &feature)
+            }
+            None => false,
+        }
+    }
 }
 
 // CompletionContext construction
@@ -557,6 +611,7 @@ impl<'a> CompletionContext<'a> {
             pattern_ctx: None,
             existing_derives: Default::default(),
             locals,
+            resolving: false,
         };
         ctx.expand_and_fill(
             original_file.syntax().clone(),
@@ -1,6 +1,10 @@
 //! See `CompletionItem` structure.
 
-use std::fmt;
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt,
+    hash::{Hash, Hasher},
+};
 
 use hir::{Documentation, Mutability};
 use ide_db::{imports::import_assets::LocatedImport, SnippetCap, SymbolKind};
@@ -151,6 +155,8 @@ pub struct CompletionRelevance {
     pub postfix_match: Option<CompletionRelevancePostfixMatch>,
     /// This is set for type inference results
     pub is_definite: bool,
+    /// Set for items that are `#[unstable]` behind a feature the current crate hasn't enabled.
+    pub requires_unstable_feature: bool,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -215,12 +221,17 @@ impl CompletionRelevance {
             is_private_editable,
             postfix_match,
             is_definite,
+            requires_unstable_feature,
         } = self;
 
         // lower rank private things
         if !is_private_editable {
             score += 1;
         }
+        // lower rank items that need a feature gate to be used
+        if !requires_unstable_feature {
+            score += 1;
+        }
         // lower rank trait op methods
         if !is_op_method {
             score += 10;
@@ -268,7 +279,7 @@ impl CompletionRelevance {
 }
 
 /// The type of the completion item.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum CompletionItemKind {
     SymbolKind(SymbolKind),
     Binding,
@@ -337,6 +348,7 @@ impl CompletionItem {
             insert_text: None,
             is_snippet: false,
             trait_name: None,
+            detail_trait_name: None,
             detail: None,
             documentation: None,
             lookup: None,
@@ -408,6 +420,20 @@ impl CompletionItem {
     pub fn imports_to_add(&self) -> &[LocatedImport] {
         &self.import_to_add
     }
+
+    /// Identifies the item across requests, ignoring all fields that are resolved lazily.
+    pub fn resolve_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.label.hash(&mut hasher);
+        self.lookup().hash(&mut hasher);
+        self.kind.hash(&mut hasher);
+        self.source_range.hash(&mut hasher);
+        for indel in self.text_edit.iter() {
+            indel.delete.hash(&mut hasher);
+            indel.insert.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
 }
 
 /// A helper to make `CompletionItem`s.
@@ -417,6 +443,7 @@ pub(crate) struct Builder {
     source_range: TextRange,
     imports_to_add: SmallVec<[LocatedImport; 1]>,
     trait_name: Option<SmolStr>,
+    detail_trait_name: Option<SmolStr>,
     label: SmolStr,
     insert_text: Option<String>,
     is_snippet: bool,
@@ -462,12 +489,18 @@ impl Builder {
             None => TextEdit::replace(self.source_range, insert_text),
         };
 
+        let detail = match (self.detail, self.detail_trait_name) {
+            (Some(detail), Some(trait_name)) => Some(format!("{} (as {})", detail, trait_name)),
+            (None, Some(trait_name)) => Some(format!("(as {})", trait_name)),
+            (detail, None) => detail,
+        };
+
         CompletionItem {
             source_range: self.source_range,
             label,
             text_edit,
             is_snippet: self.is_snippet,
-            detail: self.detail,
+            detail,
             documentation: self.documentation,
             lookup,
             kind: self.kind,
@@ -490,6 +523,10 @@ impl Builder {
         self.trait_name = Some(trait_name);
         self
     }
+    pub(crate) fn detail_trait_name(&mut self, trait_name: SmolStr) -> &mut Builder {
+        self.detail_trait_name = Some(trait_name);
+        self
+    }
     pub(crate) fn insert_text(&mut self, insert_text: impl Into<String>) -> &mut Builder {
         self.insert_text = Some(insert_text.into());
         self
@@ -609,7 +646,10 @@ mod tests {
             vec![Cr { is_op_method: true, is_private_editable: true, ..default }],
             vec![Cr { is_op_method: true, ..default }],
             vec![Cr { postfix_match: Some(CompletionRelevancePostfixMatch::NonExact), ..default }],
-            vec![Cr { is_private_editable: true, ..default }],
+            vec![
+                Cr { is_private_editable: true, ..default },
+                Cr { requires_unstable_feature: true, ..default },
+            ],
             vec![default],
             vec![Cr { is_local: true, ..default }],
             vec![Cr { type_match: Some(CompletionRelevanceTypeMatch::CouldUnify), ..default }],
@@ -27,7 +27,7 @@ use text_edit::TextEdit;
 use crate::{completions::Completions, context::CompletionContext};
 
 pub use crate::{
-    config::{CallableSnippets, CompletionConfig},
+    config::{CallableSnippets, CompletionConfig, CompletionFieldsToResolve},
     item::{
         CompletionItem, CompletionItemKind, CompletionRelevance, CompletionRelevancePostfixMatch,
     },
@@ -146,37 +146,67 @@ pub fn completions(
 ) -> Option<Completions> {
     let ctx = &CompletionContext::new(db, position, config)?;
     let mut acc = Completions::default();
+    complete(&mut acc, ctx, true);
+    Some(acc)
+}
 
-    {
+/// Computes the fields of the completion with the given [`CompletionItem::resolve_hash`] at
+/// `position` that were left out of the initial response. Only the completions of the same kind
+/// are computed again: those that import something come from flyimport and snippets, the others
+/// can't come from flyimport.
+pub fn resolve_completion_item(
+    db: &RootDatabase,
+    config: &CompletionConfig,
+    position: FilePosition,
+    hash: u64,
+    imports_something: bool,
+) -> Option<CompletionItem> {
+    let _p = profile::span("resolve_completion_item");
+    let mut ctx = CompletionContext::new(db, position, config)?;
+    ctx.resolving = true;
+    let ctx = &ctx;
+    let mut acc = Completions::default();
+    if imports_something {
         let acc = &mut acc;
-        completions::attribute::complete_attribute(acc, ctx);
-        completions::attribute::complete_derive(acc, ctx);
-        completions::attribute::complete_known_attribute_input(acc, ctx);
-        completions::dot::complete_dot(acc, ctx);
-        completions::expr::complete_expr_path(acc, ctx);
-        completions::extern_abi::complete_extern_abi(acc, ctx);
         completions::flyimport::import_on_the_fly(acc, ctx);
-        completions::fn_param::complete_fn_param(acc, ctx);
-        completions::format_string::format_string(acc, ctx);
-        completions::item_list::complete_item_list(acc, ctx);
-        completions::keyword::complete_expr_keyword(acc, ctx);
-        completions::lifetime::complete_label(acc, ctx);
-        completions::lifetime::complete_lifetime(acc, ctx);
-        completions::mod_::complete_mod(acc, ctx);
-        completions::pattern::complete_pattern(acc, ctx);
         completions::postfix::complete_postfix(acc, ctx);
-        completions::record::complete_record_literal(acc, ctx);
-        completions::record::complete_record(acc, ctx);
         completions::snippet::complete_expr_snippet(acc, ctx);
         completions::snippet::complete_item_snippet(acc, ctx);
-        completions::trait_impl::complete_trait_impl(acc, ctx);
-        completions::r#type::complete_type_path(acc, ctx);
-        completions::r#type::complete_inferred_type(acc, ctx);
-        completions::use_::complete_use_tree(acc, ctx);
-        completions::vis::complete_vis_path(acc, ctx);
+    } else {
+        complete(&mut acc, ctx, false);
     }
+    let items: Vec<CompletionItem> = acc.into();
+    items.into_iter().find(|item| item.resolve_hash() == hash)
+}
 
-    Some(acc)
+fn complete(acc: &mut Completions, ctx: &CompletionContext, flyimport: bool) {
+    completions::attribute::complete_attribute(acc, ctx);
+    completions::attribute::complete_derive(acc, ctx);
+    completions::attribute::complete_known_attribute_input(acc, ctx);
+    completions::dot::complete_dot(acc, ctx);
+    completions::expr::complete_expr_path(acc, ctx);
+    completions::extern_abi::complete_extern_abi(acc, ctx);
+    if flyimport {
+        completions::flyimport::import_on_the_fly(acc, ctx);
+    }
+    completions::fn_param::complete_fn_param(acc, ctx);
+    completions::format_string::format_string(acc, ctx);
+    completions::item_list::complete_item_list(acc, ctx);
+    completions::keyword::complete_expr_keyword(acc, ctx);
+    completions::lifetime::complete_label(acc, ctx);
+    completions::lifetime::complete_lifetime(acc, ctx);
+    completions::mod_::complete_mod(acc, ctx);
+    completions::pattern::complete_pattern(acc, ctx);
+    completions::postfix::complete_postfix(acc, ctx);
+    completions::record::complete_record_literal(acc, ctx);
+    completions::record::complete_record(acc, ctx);
+    completions::snippet::complete_expr_snippet(acc, ctx);
+    completions::snippet::complete_item_snippet(acc, ctx);
+    completions::trait_impl::complete_trait_impl(acc, ctx);
+    completions::r#type::complete_type_path(acc, ctx);
+    completions::r#type::complete_inferred_type(acc, ctx);
+    completions::use_::complete_use_tree(acc, ctx);
+    completions::vis::complete_vis_path(acc, ctx);
 }
 
 /// Resolves additional completion data at the position given.
@@ -102,9 +102,8 @@ impl<'a> RenderContext<'a> {
                 .unwrap_or(false)
     }
 
-    // FIXME: remove this
     fn docs(&self, def: impl HasAttrs) -> Option<hir::Documentation> {
-        def.docs(self.db())
+        self.completion.docs(def)
     }
 }
 
@@ -127,7 +126,7 @@ pub(crate) fn render_field(
         ..CompletionRelevance::default()
     });
     item.detail(ty.display(ctx.db()).to_string())
-        .set_documentation(field.docs(ctx.db()))
+        .set_documentation(ctx.docs(field))
         .set_deprecated(is_deprecated)
         .lookup_by(name.clone());
     let is_keyword = SyntaxKind::from_keyword(name.as_str()).is_some();
@@ -263,8 +262,15 @@ fn render_resolution_simple_(
     };
 
     let local_name = local_name.to_smol_str();
+    let requires_unstable_feature = match (resolution.attrs(db), resolution.krate(db)) {
+        (Some(attrs), Some(krate)) => ctx.completion.is_unstable_feature_disabled(&attrs, krate),
+        _ => false,
+    };
     let mut item = CompletionItem::new(kind, ctx.source_range(), local_name.clone());
-    item.set_relevance(ctx.completion_relevance());
+    item.set_relevance(CompletionRelevance {
+        requires_unstable_feature,
+        ..ctx.completion_relevance()
+    });
     if let ScopeDef::Local(local) = resolution {
         let ty = local.ty(db);
         if !ty.is_unknown() {
@@ -304,7 +310,7 @@ fn render_resolution_simple_(
             }
         }
     }
-    item.set_documentation(scope_def_docs(db, resolution))
+    item.set_documentation(scope_def_docs(ctx.completion, resolution))
         .set_deprecated(scope_def_is_deprecated(&ctx, resolution));
 
     if let Some(import_to_add) = ctx.import_to_add {
@@ -313,16 +319,16 @@ fn render_resolution_simple_(
     item
 }
 
-fn scope_def_docs(db: &RootDatabase, resolution: ScopeDef) -> Option<hir::Documentation> {
+fn scope_def_docs(ctx: &CompletionContext<'_>, resolution: ScopeDef) -> Option<hir::Documentation> {
     use hir::ModuleDef::*;
     match resolution {
-        ScopeDef::ModuleDef(Module(it)) => it.docs(db),
-        ScopeDef::ModuleDef(Adt(it)) => it.docs(db),
-        ScopeDef::ModuleDef(Variant(it)) => it.docs(db),
-        ScopeDef::ModuleDef(Const(it)) => it.docs(db),
-        ScopeDef::ModuleDef(Static(it)) => it.docs(db),
-        ScopeDef::ModuleDef(Trait(it)) => it.docs(db),
-        ScopeDef::ModuleDef(TypeAlias(it)) => it.docs(db),
+        ScopeDef::ModuleDef(Module(it)) => ctx.docs(it),
+        ScopeDef::ModuleDef(Adt(it)) => ctx.docs(it),
+        ScopeDef::ModuleDef(Variant(it)) => ctx.docs(it),
+        ScopeDef::ModuleDef(Const(it)) => ctx.docs(it),
+        ScopeDef::ModuleDef(Static(it)) => ctx.docs(it),
+        ScopeDef::ModuleDef(Trait(it)) => ctx.docs(it),
+        ScopeDef::ModuleDef(TypeAlias(it)) => ctx.docs(it),
         _ => None,
     }
 }
@@ -465,6 +471,7 @@ mod tests {
                 ),
                 (relevance.is_op_method, "op_method"),
                 (relevance.requires_import, "requires_import"),
+                (relevance.requires_unstable_feature, "unstable"),
             ]
             .into_iter()
             .filter_map(|(cond, desc)| if cond { Some(desc) } else { None })
@@ -650,6 +657,7 @@ fn main() { let _: m::Spam = S$0 }
                             is_private_editable: false,
                             postfix_match: None,
                             is_definite: false,
+                            requires_unstable_feature: false,
                         },
                     },
                     CompletionItem {
@@ -675,6 +683,7 @@ fn main() { let _: m::Spam = S$0 }
                             is_private_editable: false,
                             postfix_match: None,
                             is_definite: false,
+                            requires_unstable_feature: false,
                         },
                     },
                 ]
@@ -752,6 +761,7 @@ fn foo() { A { the$0 } }
                             is_private_editable: false,
                             postfix_match: None,
                             is_definite: false,
+                            requires_unstable_feature: false,
                         },
                     },
                 ]
@@ -1060,6 +1070,37 @@ fn foo(a: A) { B { bar: a.$0 }; }
         )
     }
 
+    #[test]
+    fn unstable_item_relevances() {
+        check_relevance(
+            r#"
+//- /main.rs crate:main deps:std
+fn main() { std::$0 }
+//- /std.rs crate:std
+#[unstable(feature = "new_api", issue = "none")]
+pub fn new_api() {}
+pub fn old_api() {}
+"#,
+            expect![[r#"
+                fn old_api() []
+                fn new_api() [unstable]
+            "#]],
+        );
+        check_relevance(
+            r#"
+//- /main.rs crate:main deps:std
+#![feature(new_api)]
+fn main() { std::$0 }
+//- /std.rs crate:std
+#[unstable(feature = "new_api", issue = "none")]
+pub fn new_api() {}
+"#,
+            expect![[r#"
+                fn new_api() []
+            "#]],
+        );
+    }
+
     #[test]
     fn record_field_and_call_relevances() {
         check_relevance(
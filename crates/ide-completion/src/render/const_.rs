@@ -1,6 +1,6 @@
 //! Renderer for `const` fields.
 
-use hir::{AsAssocItem, HirDisplay};
+use hir::HirDisplay;
 use ide_db::SymbolKind;
 
 use crate::{item::CompletionItem, render::RenderContext};
@@ -21,11 +21,8 @@ fn render(ctx: RenderContext<'_>, const_: hir::Const) -> Option<CompletionItem>
         .detail(detail)
         .set_relevance(ctx.completion_relevance());
 
-    if let Some(actm) = const_.as_assoc_item(db) {
-        if let Some(trt) = actm.containing_trait_or_trait_impl(db) {
-            item.trait_name(trt.name(db).to_smol_str());
-            item.insert_text(name);
-        }
+    if ctx.completion.add_trait_name(&mut item, const_) {
+        item.insert_text(name);
     }
 
     Some(item.build())
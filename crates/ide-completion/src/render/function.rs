@@ -1,6 +1,6 @@
 //! Renderer for function calls.
 
-use hir::{db::HirDatabase, AsAssocItem, HirDisplay};
+use hir::{db::HirDatabase, AsAssocItem, HasAttrs, HirDisplay};
 use ide_db::{SnippetCap, SymbolKind};
 use itertools::Itertools;
 use stdx::{format_to, to_lower_snake_case};
@@ -72,6 +72,8 @@ fn render(
         type_match: compute_type_match(completion, &ret_type),
         exact_name_match: compute_exact_name_match(completion, &call),
         is_op_method,
+        requires_unstable_feature: completion
+            .is_unstable_feature_disabled(&func.attrs(db), func.module(db).krate()),
         ..ctx.completion_relevance()
     });
 
@@ -102,11 +104,7 @@ fn render(
             item.add_import(import_to_add);
         }
         None => {
-            if let Some(actm) = func.as_assoc_item(db) {
-                if let Some(trt) = actm.containing_trait_or_trait_impl(db) {
-                    item.trait_name(trt.name(db).to_smol_str());
-                }
-            }
+            completion.add_trait_name(&mut item, func);
         }
     }
     item
@@ -1,6 +1,6 @@
 //! Renderer for `enum` variants.
 
-use hir::{db::HirDatabase, Documentation, HasAttrs, StructKind};
+use hir::{db::HirDatabase, Documentation, StructKind};
 use ide_db::SymbolKind;
 
 use crate::{
@@ -98,7 +98,7 @@ fn render(
     if qualified {
         item.lookup_by(format_literal_label(&short_qualified_name.to_string(), kind));
     }
-    item.set_documentation(thing.docs(db)).set_deprecated(thing.is_deprecated(&ctx));
+    item.set_documentation(thing.docs(ctx.completion)).set_deprecated(thing.is_deprecated(&ctx));
 
     let ty = thing.ty(db);
     item.set_relevance(CompletionRelevance {
@@ -152,10 +152,10 @@ impl Variant {
         }
     }
 
-    fn docs(self, db: &dyn HirDatabase) -> Option<Documentation> {
+    fn docs(self, ctx: &CompletionContext<'_>) -> Option<Documentation> {
         match self {
-            Variant::Struct(it) => it.docs(db),
-            Variant::EnumVariant(it) => it.docs(db),
+            Variant::Struct(it) => ctx.docs(it),
+            Variant::EnumVariant(it) => ctx.docs(it),
         }
     }
 
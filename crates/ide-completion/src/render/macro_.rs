@@ -1,6 +1,6 @@
 //! Renderer for macro invocations.
 
-use hir::{Documentation, HirDisplay};
+use hir::{Documentation, HasAttrs, HirDisplay};
 use ide_db::SymbolKind;
 use syntax::SmolStr;
 
@@ -28,7 +28,8 @@ fn render(
     };
 
     let name = name.to_smol_str();
-    let docs = ctx.docs(macro_);
+    // The docs are needed to guess the braces even if the client resolves them lazily.
+    let docs = macro_.docs(completion.db);
     let docs_str = docs.as_ref().map(Documentation::as_str).unwrap_or_default();
     let is_fn_like = macro_.is_fn_like(completion.db);
     let (bra, ket) = if is_fn_like { guess_macro_braces(&name, docs_str) } else { ("", "") };
@@ -47,7 +48,9 @@ fn render(
     );
     item.set_deprecated(ctx.is_deprecated(macro_))
         .detail(macro_.display(completion.db).to_string())
-        .set_documentation(docs)
+        .set_documentation(
+            docs.filter(|_| !completion.config.fields_to_resolve.resolve_documentation),
+        )
         .set_relevance(ctx.completion_relevance());
 
     let name = &*name;
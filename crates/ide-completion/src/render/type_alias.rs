@@ -1,6 +1,6 @@
 //! Renderer for type aliases.
 
-use hir::{AsAssocItem, HirDisplay};
+use hir::HirDisplay;
 use ide_db::SymbolKind;
 use syntax::SmolStr;
 
@@ -42,11 +42,8 @@ fn render(
         .detail(detail)
         .set_relevance(ctx.completion_relevance());
 
-    if let Some(actm) = type_alias.as_assoc_item(db) {
-        if let Some(trt) = actm.containing_trait_or_trait_impl(db) {
-            item.trait_name(trt.name(db).to_smol_str());
-            item.insert_text(name);
-        }
+    if ctx.completion.add_trait_name(&mut item, type_alias) {
+        item.insert_text(name);
     }
 
     Some(item.build())
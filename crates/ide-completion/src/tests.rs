@@ -37,8 +37,8 @@ use syntax::{AstNode, NodeOrToken, SyntaxElement};
 use test_utils::assert_eq_text;
 
 use crate::{
-    resolve_completion_edits, CallableSnippets, CompletionConfig, CompletionItem,
-    CompletionItemKind,
+    resolve_completion_edits, resolve_completion_item, CallableSnippets, CompletionConfig,
+    CompletionFieldsToResolve, CompletionItem, CompletionItemKind,
 };
 
 /// Lots of basic item definitions
@@ -76,6 +76,7 @@ pub(crate) const TEST_CONFIG: CompletionConfig = CompletionConfig {
         skip_glob_imports: true,
     },
     snippets: Vec::new(),
+    fields_to_resolve: CompletionFieldsToResolve::empty(),
 };
 
 pub(crate) fn completion_list(ra_fixture: &str) -> String {
@@ -289,3 +290,60 @@ Some multi-line comment$0
         String::new(),
     );
 }
+
+#[test]
+fn lazily_resolved_documentation() {
+    let config = CompletionConfig {
+        fields_to_resolve: CompletionFieldsToResolve {
+            resolve_documentation: true,
+            resolve_detail: false,
+        },
+        ..TEST_CONFIG
+    };
+    let (db, position) = position(
+        r#"
+/// Does the thing.
+fn do_thing() {}
+fn main() { do_$0 }
+"#,
+    );
+    let items: Vec<CompletionItem> =
+        crate::completions(&db, &config, position).map_or_else(Vec::default, Into::into);
+    let item = items.iter().find(|it| it.label() == "do_thing()").unwrap();
+    assert!(item.documentation().is_none());
+
+    let resolved =
+        resolve_completion_item(&db, &config, position, item.resolve_hash(), false).unwrap();
+    assert_eq!(resolved.documentation().unwrap().as_str(), "Does the thing.");
+}
+
+#[test]
+fn lazily_resolved_trait_name() {
+    let config = CompletionConfig {
+        fields_to_resolve: CompletionFieldsToResolve {
+            resolve_documentation: false,
+            resolve_detail: true,
+        },
+        ..TEST_CONFIG
+    };
+    let (db, position) = position(
+        r#"
+trait Trait {
+    fn method(&self);
+}
+struct S;
+impl Trait for S {
+    fn method(&self) {}
+}
+fn main() { S.$0 }
+"#,
+    );
+    let items: Vec<CompletionItem> =
+        crate::completions(&db, &config, position).map_or_else(Vec::default, Into::into);
+    let item = items.iter().find(|it| it.label() == "method()").unwrap();
+    assert_eq!(item.detail(), Some("fn(&self)"));
+
+    let resolved =
+        resolve_completion_item(&db, &config, position, item.resolve_hash(), false).unwrap();
+    assert_eq!(resolved.detail(), Some("fn(&self) (as Trait)"));
+}
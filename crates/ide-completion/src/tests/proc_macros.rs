@@ -68,6 +68,36 @@ fn main() {
     )
 }
 
+#[test]
+fn complete_dot_in_failing_attr() {
+    check(
+        r#"
+//- proc_macros: panic_attr
+pub struct Foo;
+impl Foo {
+    fn foo(&self) {}
+}
+
+#[proc_macros::panic_attr]
+fn main() {
+    Foo.$0
+}
+"#,
+        expect![[r#"
+            me foo() fn(&self)
+            sn box   Box::new(expr)
+            sn call  function(expr)
+            sn dbg   dbg!(expr)
+            sn dbgr  dbg!(&expr)
+            sn let   let
+            sn letm  let mut
+            sn match match expr {}
+            sn ref   &expr
+            sn refm  &mut expr
+        "#]],
+    )
+}
+
 #[test]
 fn complete_dot_in_attr_input() {
     check(
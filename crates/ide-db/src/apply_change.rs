@@ -49,115 +49,167 @@ impl RootDatabase {
     // |===
     // image::https://user-images.githubusercontent.com/48062697/113065592-08559f00-91b1-11eb-8c96-64b88068ec02.gif[]
     pub fn per_query_memory_usage(&mut self) -> Vec<(String, Bytes)> {
-        let mut acc: Vec<(String, Bytes)> = vec![];
+        let mut acc: Vec<(String, Bytes)> =
+            self.purge_queries().into_iter().map(|(_, name, bytes)| (name, bytes)).collect();
+        acc.sort_by_key(|it| std::cmp::Reverse(it.1));
+        acc
+    }
+
+    /// Like [`RootDatabase::per_query_memory_usage`], but sums up the memory used by the queries
+    /// of each query group.
+    pub fn per_query_group_memory_usage(&mut self) -> Vec<(&'static str, Bytes)> {
+        let mut acc: Vec<(&'static str, Bytes)> = vec![];
+        for (group, _, bytes) in self.purge_queries() {
+            match acc.iter_mut().find(|(it, _)| *it == group) {
+                Some((_, total)) => *total += bytes,
+                None => acc.push((group, bytes)),
+            }
+        }
+        acc.sort_by_key(|it| std::cmp::Reverse(it.1));
+        acc
+    }
+
+    /// Evicts caches that are cheap to recompute, returning what each step freed.
+    ///
+    /// The LRU caches of syntax trees and macro expansions are shrunk to a quarter of
+    /// `lru_capacity` until the next call to [`RootDatabase::update_lru_capacity`], which keeps
+    /// the recently used entries of the files being worked on. The symbol indices of library
+    /// crates, which aren't edited, are unloaded until the next workspace symbol search.
+    pub fn evict_caches(&mut self, lru_capacity: Option<usize>) -> Vec<(&'static str, Bytes)> {
+        let _p = profile::span("RootDatabase::evict_caches");
+        self.request_cancellation();
+
+        let mut acc = vec![];
+        let mut before = memory_usage().allocated;
+        let mut record = |step: &'static str, acc: &mut Vec<(&'static str, Bytes)>| {
+            let after = memory_usage().allocated;
+            acc.push((step, before - after));
+            before = after;
+        };
+
+        let lru_capacity = lru_capacity.unwrap_or(base_db::DEFAULT_LRU_CAP);
+        self.update_lru_capacity(Some((lru_capacity / 4).max(1)));
+        record("LRU caches", &mut acc);
+
+        crate::symbol_index::LibrarySymbolsQuery.in_db(self).purge();
+        record("library symbol indices", &mut acc);
+
+        acc
+    }
+
+    /// Purges every query, returning the query group, the name and the freed memory of each.
+    fn purge_queries(&mut self) -> Vec<(&'static str, String, Bytes)> {
+        let mut acc = vec![];
         macro_rules! purge_each_query {
-            ($($q:path)*) => {$(
+            ($($group:literal => [$($q:path)*])*) => {$($(
                 let before = memory_usage().allocated;
                 $q.in_db(self).purge();
                 let after = memory_usage().allocated;
                 let q: $q = Default::default();
                 let name = format!("{:?}", q);
-                acc.push((name, before - after));
-            )*}
+                acc.push(($group, name, before - after));
+            )*)*}
         }
         purge_each_query![
-            // SourceDatabase
-            base_db::ParseQuery
-            base_db::CrateGraphQuery
-
-            // SourceDatabaseExt
-            base_db::FileTextQuery
-            base_db::FileSourceRootQuery
-            base_db::SourceRootQuery
-            base_db::SourceRootCratesQuery
-
-            // AstDatabase
-            hir::db::AstIdMapQuery
-            hir::db::MacroArgTextQuery
-            hir::db::MacroDefQuery
-            hir::db::ParseMacroExpansionQuery
-            hir::db::MacroExpandQuery
-            hir::db::HygieneFrameQuery
-            hir::db::InternMacroCallQuery
-
-            // DefDatabase
-            hir::db::FileItemTreeQuery
-            hir::db::BlockDefMapQuery
-            hir::db::CrateDefMapQueryQuery
-            hir::db::FieldsAttrsQuery
-            hir::db::VariantsAttrsQuery
-            hir::db::FieldsAttrsSourceMapQuery
-            hir::db::VariantsAttrsSourceMapQuery
-            hir::db::StructDataQuery
-            hir::db::UnionDataQuery
-            hir::db::EnumDataQuery
-            hir::db::ImplDataQuery
-            hir::db::TraitDataQuery
-            hir::db::TypeAliasDataQuery
-            hir::db::FunctionDataQuery
-            hir::db::ConstDataQuery
-            hir::db::StaticDataQuery
-            hir::db::BodyWithSourceMapQuery
-            hir::db::BodyQuery
-            hir::db::ExprScopesQuery
-            hir::db::GenericParamsQuery
-            hir::db::AttrsQuery
-            hir::db::CrateLangItemsQuery
-            hir::db::LangItemQuery
-            hir::db::ImportMapQuery
-
-            // HirDatabase
-            hir::db::InferQueryQuery
-            hir::db::TyQuery
-            hir::db::ValueTyQuery
-            hir::db::ImplSelfTyQuery
-            hir::db::ImplTraitQuery
-            hir::db::FieldTypesQuery
-            hir::db::CallableItemSignatureQuery
-            hir::db::GenericPredicatesForParamQuery
-            hir::db::GenericPredicatesQuery
-            hir::db::GenericDefaultsQuery
-            hir::db::InherentImplsInCrateQuery
-            hir::db::TraitEnvironmentQuery
-            hir::db::TraitImplsInCrateQuery
-            hir::db::TraitImplsInDepsQuery
-            hir::db::AssociatedTyDataQuery
-            hir::db::AssociatedTyDataQuery
-            hir::db::TraitDatumQuery
-            hir::db::StructDatumQuery
-            hir::db::ImplDatumQuery
-            hir::db::FnDefDatumQuery
-            hir::db::ReturnTypeImplTraitsQuery
-            hir::db::InternCallableDefQuery
-            hir::db::InternTypeOrConstParamIdQuery
-            hir::db::InternImplTraitIdQuery
-            hir::db::InternClosureQuery
-            hir::db::AssociatedTyValueQuery
-            hir::db::TraitSolveQueryQuery
-            hir::db::InternTypeOrConstParamIdQuery
-
-            // SymbolsDatabase
-            crate::symbol_index::ModuleSymbolsQuery
-            crate::symbol_index::LibrarySymbolsQuery
-            crate::symbol_index::LocalRootsQuery
-            crate::symbol_index::LibraryRootsQuery
-
-            // LineIndexDatabase
-            crate::LineIndexQuery
-
-            // InternDatabase
-            hir::db::InternFunctionQuery
-            hir::db::InternStructQuery
-            hir::db::InternUnionQuery
-            hir::db::InternEnumQuery
-            hir::db::InternConstQuery
-            hir::db::InternStaticQuery
-            hir::db::InternTraitQuery
-            hir::db::InternTypeAliasQuery
-            hir::db::InternImplQuery
+            "SourceDatabase" => [
+                base_db::ParseQuery
+                base_db::CrateGraphQuery
+            ]
+            "SourceDatabaseExt" => [
+                base_db::FileTextQuery
+                base_db::FileSourceRootQuery
+                base_db::SourceRootQuery
+                base_db::SourceRootCratesQuery
+            ]
+            "AstDatabase" => [
+                hir::db::AstIdMapQuery
+                hir::db::MacroArgTextQuery
+                hir::db::MacroDefQuery
+                hir::db::ParseMacroExpansionQuery
+                hir::db::MacroExpandQuery
+                hir::db::HygieneFrameQuery
+                hir::db::InternMacroCallQuery
+            ]
+            "DefDatabase" => [
+                hir::db::FileItemTreeQuery
+                hir::db::BlockItemTreeQuery
+                hir::db::BlockDefMapQuery
+                hir::db::CrateDefMapQueryQuery
+                hir::db::FieldsAttrsQuery
+                hir::db::VariantsAttrsQuery
+                hir::db::FieldsAttrsSourceMapQuery
+                hir::db::VariantsAttrsSourceMapQuery
+                hir::db::StructDataQuery
+                hir::db::UnionDataQuery
+                hir::db::EnumDataQuery
+                hir::db::ImplDataQuery
+                hir::db::TraitDataQuery
+                hir::db::TypeAliasDataQuery
+                hir::db::FunctionDataQuery
+                hir::db::ConstDataQuery
+                hir::db::StaticDataQuery
+                hir::db::BodyWithSourceMapQuery
+                hir::db::BodyQuery
+                hir::db::ExprScopesQuery
+                hir::db::GenericParamsQuery
+                hir::db::AttrsQuery
+                hir::db::CrateLangItemsQuery
+                hir::db::LangItemQuery
+                hir::db::ImportMapQuery
+            ]
+            "HirDatabase" => [
+                hir::db::InferQueryQuery
+                hir::db::TyQuery
+                hir::db::ValueTyQuery
+                hir::db::ImplSelfTyQuery
+                hir::db::ImplTraitQuery
+                hir::db::FieldTypesQuery
+                hir::db::CallableItemSignatureQuery
+                hir::db::GenericPredicatesForParamQuery
+                hir::db::GenericPredicatesQuery
+                hir::db::GenericDefaultsQuery
+                hir::db::InherentImplsInCrateQuery
+                hir::db::TraitEnvironmentQuery
+                hir::db::TraitImplsInCrateQuery
+                hir::db::TraitImplsInDepsQuery
+                hir::db::AssociatedTyDataQuery
+                hir::db::AssociatedTyDataQuery
+                hir::db::TraitDatumQuery
+                hir::db::StructDatumQuery
+                hir::db::ImplDatumQuery
+                hir::db::FnDefDatumQuery
+                hir::db::ReturnTypeImplTraitsQuery
+                hir::db::TypeAliasImplTraitsQuery
+                hir::db::InternCallableDefQuery
+                hir::db::InternTypeOrConstParamIdQuery
+                hir::db::InternImplTraitIdQuery
+                hir::db::InternClosureQuery
+                hir::db::AssociatedTyValueQuery
+                hir::db::TraitSolveQueryQuery
+                hir::db::InternTypeOrConstParamIdQuery
+            ]
+            "SymbolsDatabase" => [
+                crate::symbol_index::ModuleSymbolsQuery
+                crate::symbol_index::LibrarySymbolsQuery
+                crate::symbol_index::LocalRootsQuery
+                crate::symbol_index::LibraryRootsQuery
+            ]
+            "LineIndexDatabase" => [
+                crate::LineIndexQuery
+            ]
+            "InternDatabase" => [
+                hir::db::InternFunctionQuery
+                hir::db::InternStructQuery
+                hir::db::InternUnionQuery
+                hir::db::InternEnumQuery
+                hir::db::InternConstQuery
+                hir::db::InternStaticQuery
+                hir::db::InternTraitQuery
+                hir::db::InternTypeAliasQuery
+                hir::db::InternImplQuery
+            ]
         ];
 
-        acc.sort_by_key(|it| std::cmp::Reverse(it.1));
         acc
     }
 }
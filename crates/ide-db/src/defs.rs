@@ -8,13 +8,13 @@
 use arrayvec::ArrayVec;
 use hir::{
     Adt, AsAssocItem, AssocItem, BuiltinAttr, BuiltinType, Const, Crate, Field, Function,
-    GenericParam, HasVisibility, Impl, ItemInNs, Label, Local, Macro, Module, ModuleDef, Name,
-    PathResolution, Semantics, Static, ToolModule, Trait, TypeAlias, Variant, Visibility,
+    GenericParam, HasAttrs, HasVisibility, Impl, ItemInNs, Label, Local, Macro, Module, ModuleDef,
+    Name, PathResolution, Semantics, Static, ToolModule, Trait, TypeAlias, Variant, Visibility,
 };
 use stdx::impl_from;
 use syntax::{
     ast::{self, AstNode},
-    match_ast, SyntaxKind, SyntaxNode, SyntaxToken,
+    match_ast, SmolStr, SyntaxKind, SyntaxNode, SyntaxToken,
 };
 
 use crate::RootDatabase;
@@ -121,12 +121,48 @@ impl Definition {
         };
         Some(name)
     }
+
+    /// Returns the feature `krate` has to enable to use this `#[unstable]` definition, if it
+    /// hasn't done so already.
+    pub fn disabled_unstable_feature(&self, db: &RootDatabase, krate: Crate) -> Option<SmolStr> {
+        // Unstable items can always be used from within the crate that defines them, and
+        // stability attributes only mean something in crates with `#![feature(staged_api)]`.
+        let def_crate = self.krate(db)?;
+        if def_crate == krate || !def_crate.is_unstable_feature_enabled(db, "staged_api") {
+            return None;
+        }
+        let attrs = match self {
+            Definition::Macro(it) => it.attrs(db),
+            Definition::Field(it) => it.attrs(db),
+            Definition::Module(it) => it.attrs(db),
+            Definition::Function(it) => it.attrs(db),
+            Definition::Adt(it) => it.attrs(db),
+            Definition::Variant(it) => it.attrs(db),
+            Definition::Const(it) => it.attrs(db),
+            Definition::Static(it) => it.attrs(db),
+            Definition::Trait(it) => it.attrs(db),
+            Definition::TypeAlias(it) => it.attrs(db),
+            Definition::BuiltinType(_)
+            | Definition::SelfType(_)
+            | Definition::Local(_)
+            | Definition::GenericParam(_)
+            | Definition::Label(_)
+            | Definition::BuiltinAttr(_)
+            | Definition::ToolModule(_) => return None,
+        };
+        let feature = attrs.unstable_feature()?;
+        if krate.is_unstable_feature_enabled(db, &feature) {
+            return None;
+        }
+        Some(feature)
+    }
 }
 
 #[derive(Debug)]
 pub enum IdentClass {
     NameClass(NameClass),
     NameRefClass(NameRefClass),
+    Operator(OperatorClass),
 }
 
 impl IdentClass {
@@ -140,6 +176,11 @@ impl IdentClass {
                         .map(IdentClass::NameClass)
                         .or_else(|| NameRefClass::classify_lifetime(sema, &lifetime).map(IdentClass::NameRefClass))
                 },
+                ast::AwaitExpr(await_expr) => OperatorClass::classify_await(sema, &await_expr).map(IdentClass::Operator),
+                ast::BinExpr(bin_expr) => OperatorClass::classify_bin(sema, &bin_expr).map(IdentClass::Operator),
+                ast::IndexExpr(index_expr) => OperatorClass::classify_index(sema, &index_expr).map(IdentClass::Operator),
+                ast::PrefixExpr(prefix_expr) => OperatorClass::classify_prefix(sema, &prefix_expr).map(IdentClass::Operator),
+                ast::TryExpr(try_expr) => OperatorClass::classify_try(sema, &try_expr).map(IdentClass::Operator),
                 _ => None,
             }
         }
@@ -150,6 +191,11 @@ impl IdentClass {
         token: &SyntaxToken,
     ) -> Option<IdentClass> {
         let parent = token.parent()?;
+        // Operator expressions are only classified through their operator tokens, not through
+        // whitespace or comments inside of them.
+        if token.kind().is_trivia() {
+            return None;
+        }
         Self::classify_node(sema, &parent)
     }
 
@@ -177,11 +223,67 @@ impl IdentClass {
                 res.push(Definition::Local(local_ref));
                 res.push(Definition::Field(field_ref));
             }
+            IdentClass::Operator(
+                OperatorClass::Await(func)
+                | OperatorClass::Bin(func)
+                | OperatorClass::Index(func)
+                | OperatorClass::Prefix(func)
+                | OperatorClass::Try(func),
+            ) => res.push(Definition::Function(func)),
         }
         res
     }
 }
 
+/// An operator expression, classified by the trait method it desugars to, e.g. `Add::add` for
+/// `a + b`. The method is the one of the impl if it is known, else the one of the trait.
+/// Builtin operators, like the deref of a reference, aren't classified.
+#[derive(Debug)]
+pub enum OperatorClass {
+    Await(Function),
+    Bin(Function),
+    Index(Function),
+    Prefix(Function),
+    Try(Function),
+}
+
+impl OperatorClass {
+    pub fn classify_await(
+        sema: &Semantics<RootDatabase>,
+        await_expr: &ast::AwaitExpr,
+    ) -> Option<OperatorClass> {
+        sema.resolve_await(await_expr).map(OperatorClass::Await)
+    }
+
+    pub fn classify_bin(
+        sema: &Semantics<RootDatabase>,
+        bin_expr: &ast::BinExpr,
+    ) -> Option<OperatorClass> {
+        sema.resolve_bin_expr(bin_expr).map(OperatorClass::Bin)
+    }
+
+    pub fn classify_index(
+        sema: &Semantics<RootDatabase>,
+        index_expr: &ast::IndexExpr,
+    ) -> Option<OperatorClass> {
+        sema.resolve_index_expr(index_expr).map(OperatorClass::Index)
+    }
+
+    pub fn classify_prefix(
+        sema: &Semantics<RootDatabase>,
+        prefix_expr: &ast::PrefixExpr,
+    ) -> Option<OperatorClass> {
+        sema.resolve_prefix_expr(prefix_expr).map(OperatorClass::Prefix)
+    }
+
+    pub fn classify_try(
+        sema: &Semantics<RootDatabase>,
+        try_expr: &ast::TryExpr,
+    ) -> Option<OperatorClass> {
+        sema.resolve_try_expr(try_expr).map(OperatorClass::Try)
+    }
+}
+
 /// On a first blush, a single `ast::Name` defines a single definition at some
 /// scope. That is, that, by just looking at the syntactical category, we can
 /// unambiguously define the semantic category.
@@ -210,7 +210,7 @@ pub fn remove_path_if_in_use_stmt(path: &ast::Path) {
 }
 
 #[derive(Eq, PartialEq, PartialOrd, Ord)]
-enum ImportGroup {
+pub enum ImportGroup {
     // the order here defines the order of new group inserts
     Std,
     ExternCrate,
@@ -220,7 +220,7 @@ enum ImportGroup {
 }
 
 impl ImportGroup {
-    fn new(path: &ast::Path) -> ImportGroup {
+    pub fn new(path: &ast::Path) -> ImportGroup {
         let default = ImportGroup::ExternCrate;
 
         let first_segment = match path.first_segment() {
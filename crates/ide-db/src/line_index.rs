@@ -10,11 +10,20 @@ pub struct LineIndex {
     /// Offset the the beginning of each line, zero-based
     pub(crate) newlines: Vec<TextSize>,
     /// List of non-ASCII characters on each line
-    pub(crate) utf16_lines: FxHashMap<u32, Vec<Utf16Char>>,
+    pub(crate) line_wide_chars: FxHashMap<u32, Vec<WideChar>>,
 }
 
+/// The encodings besides UTF-8 that LSP clients may count columns in.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-pub struct LineColUtf16 {
+pub enum WideEncoding {
+    Utf16,
+    Utf32,
+}
+
+/// Line and column of a position, with the column counted in the code units of a
+/// [`WideEncoding`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WideLineCol {
     /// Zero-based
     pub line: u32,
     /// Zero-based
@@ -30,33 +39,32 @@ pub struct LineCol {
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
-pub(crate) struct Utf16Char {
+pub(crate) struct WideChar {
     /// Start offset of a character inside a line, zero-based
     pub(crate) start: TextSize,
     /// End offset of a character inside a line, zero-based
     pub(crate) end: TextSize,
 }
 
-impl Utf16Char {
+impl WideChar {
     /// Returns the length in 8-bit UTF-8 code units.
     fn len(&self) -> TextSize {
         self.end - self.start
     }
 
-    /// Returns the length in 16-bit UTF-16 code units.
-    fn len_utf16(&self) -> usize {
-        if self.len() == TextSize::from(4) {
-            2
-        } else {
-            1
+    /// Returns the length in the code units of `enc`.
+    fn wide_len(&self, enc: WideEncoding) -> usize {
+        match enc {
+            WideEncoding::Utf16 if self.len() == TextSize::from(4) => 2,
+            WideEncoding::Utf16 | WideEncoding::Utf32 => 1,
         }
     }
 }
 
 impl LineIndex {
     pub fn new(text: &str) -> LineIndex {
-        let mut utf16_lines = FxHashMap::default();
-        let mut utf16_chars = Vec::new();
+        let mut line_wide_chars = FxHashMap::default();
+        let mut wide_chars = Vec::new();
 
         let mut newlines = vec![0.into()];
         let mut curr_row @ mut curr_col = 0.into();
@@ -67,9 +75,9 @@ impl LineIndex {
             if c == '\n' {
                 newlines.push(curr_row);
 
-                // Save any utf-16 characters seen in the previous line
-                if !utf16_chars.is_empty() {
-                    utf16_lines.insert(line, mem::take(&mut utf16_chars));
+                // Save any wide characters seen in the previous line
+                if !wide_chars.is_empty() {
+                    line_wide_chars.insert(line, mem::take(&mut wide_chars));
                 }
 
                 // Prepare for processing the next line
@@ -79,18 +87,18 @@ impl LineIndex {
             }
 
             if !c.is_ascii() {
-                utf16_chars.push(Utf16Char { start: curr_col, end: curr_col + c_len });
+                wide_chars.push(WideChar { start: curr_col, end: curr_col + c_len });
             }
 
             curr_col += c_len;
         }
 
-        // Save any utf-16 characters seen in the last line
-        if !utf16_chars.is_empty() {
-            utf16_lines.insert(line, utf16_chars);
+        // Save any wide characters seen in the last line
+        if !wide_chars.is_empty() {
+            line_wide_chars.insert(line, wide_chars);
         }
 
-        LineIndex { newlines, utf16_lines }
+        LineIndex { newlines, line_wide_chars }
     }
 
     pub fn line_col(&self, offset: TextSize) -> LineCol {
@@ -106,13 +114,13 @@ impl LineIndex {
             .map(|offset| offset + TextSize::from(line_col.col))
     }
 
-    pub fn to_utf16(&self, line_col: LineCol) -> LineColUtf16 {
-        let col = self.utf8_to_utf16_col(line_col.line, line_col.col.into());
-        LineColUtf16 { line: line_col.line, col: col as u32 }
+    pub fn to_wide(&self, enc: WideEncoding, line_col: LineCol) -> WideLineCol {
+        let col = self.utf8_to_wide_col(enc, line_col.line, line_col.col.into());
+        WideLineCol { line: line_col.line, col: col as u32 }
     }
 
-    pub fn to_utf8(&self, line_col: LineColUtf16) -> LineCol {
-        let col = self.utf16_to_utf8_col(line_col.line, line_col.col);
+    pub fn to_utf8(&self, enc: WideEncoding, line_col: WideLineCol) -> LineCol {
+        let col = self.wide_to_utf8_col(enc, line_col.line, line_col.col);
         LineCol { line: line_col.line, col: col.into() }
     }
 
@@ -129,14 +137,14 @@ impl LineIndex {
             .filter(|it| !it.is_empty())
     }
 
-    fn utf8_to_utf16_col(&self, line: u32, col: TextSize) -> usize {
+    fn utf8_to_wide_col(&self, enc: WideEncoding, line: u32, col: TextSize) -> usize {
         let mut res: usize = col.into();
-        if let Some(utf16_chars) = self.utf16_lines.get(&line) {
-            for c in utf16_chars {
+        if let Some(wide_chars) = self.line_wide_chars.get(&line) {
+            for c in wide_chars {
                 if c.end <= col {
-                    res -= usize::from(c.len()) - c.len_utf16();
+                    res -= usize::from(c.len()) - c.wide_len(enc);
                 } else {
-                    // From here on, all utf16 characters come *after* the character we are mapping,
+                    // From here on, all wide characters come *after* the character we are mapping,
                     // so we don't need to take them into account
                     break;
                 }
@@ -145,13 +153,13 @@ impl LineIndex {
         res
     }
 
-    fn utf16_to_utf8_col(&self, line: u32, mut col: u32) -> TextSize {
-        if let Some(utf16_chars) = self.utf16_lines.get(&line) {
-            for c in utf16_chars {
+    fn wide_to_utf8_col(&self, enc: WideEncoding, line: u32, mut col: u32) -> TextSize {
+        if let Some(wide_chars) = self.line_wide_chars.get(&line) {
+            for c in wide_chars {
                 if col > u32::from(c.start) {
-                    col += u32::from(c.len()) - c.len_utf16() as u32;
+                    col += u32::from(c.len()) - c.wide_len(enc) as u32;
                 } else {
-                    // From here on, all utf16 characters come *after* the character we are mapping,
+                    // From here on, all wide characters come *after* the character we are mapping,
                     // so we don't need to take them into account
                     break;
                 }
@@ -207,7 +215,7 @@ mod tests {
 const C: char = 'x';
 ",
         );
-        assert_eq!(col_index.utf16_lines.len(), 0);
+        assert_eq!(col_index.line_wide_chars.len(), 0);
     }
 
     #[test]
@@ -218,24 +226,41 @@ const C: char = 'メ';
 ",
         );
 
-        assert_eq!(col_index.utf16_lines.len(), 1);
-        assert_eq!(col_index.utf16_lines[&1].len(), 1);
-        assert_eq!(col_index.utf16_lines[&1][0], Utf16Char { start: 17.into(), end: 20.into() });
+        assert_eq!(col_index.line_wide_chars.len(), 1);
+        assert_eq!(col_index.line_wide_chars[&1].len(), 1);
+        assert_eq!(col_index.line_wide_chars[&1][0], WideChar { start: 17.into(), end: 20.into() });
 
         // UTF-8 to UTF-16, no changes
-        assert_eq!(col_index.utf8_to_utf16_col(1, 15.into()), 15);
+        assert_eq!(col_index.utf8_to_wide_col(WideEncoding::Utf16, 1, 15.into()), 15);
 
         // UTF-8 to UTF-16
-        assert_eq!(col_index.utf8_to_utf16_col(1, 22.into()), 20);
+        assert_eq!(col_index.utf8_to_wide_col(WideEncoding::Utf16, 1, 22.into()), 20);
 
         // UTF-16 to UTF-8, no changes
-        assert_eq!(col_index.utf16_to_utf8_col(1, 15), TextSize::from(15));
+        assert_eq!(col_index.wide_to_utf8_col(WideEncoding::Utf16, 1, 15), TextSize::from(15));
 
         // UTF-16 to UTF-8
-        assert_eq!(col_index.utf16_to_utf8_col(1, 19), TextSize::from(21));
+        assert_eq!(col_index.wide_to_utf8_col(WideEncoding::Utf16, 1, 19), TextSize::from(21));
 
         let col_index = LineIndex::new("a𐐏b");
-        assert_eq!(col_index.utf16_to_utf8_col(0, 3), TextSize::from(5));
+        assert_eq!(col_index.wide_to_utf8_col(WideEncoding::Utf16, 0, 3), TextSize::from(5));
+    }
+
+    #[test]
+    fn test_utf32() {
+        let col_index = LineIndex::new("a𐐏bメ");
+
+        // UTF-8 to UTF-32
+        assert_eq!(col_index.utf8_to_wide_col(WideEncoding::Utf32, 0, 5.into()), 2);
+        assert_eq!(col_index.utf8_to_wide_col(WideEncoding::Utf32, 0, 9.into()), 4);
+
+        // UTF-32 to UTF-8
+        assert_eq!(col_index.wide_to_utf8_col(WideEncoding::Utf32, 0, 2), TextSize::from(5));
+        assert_eq!(col_index.wide_to_utf8_col(WideEncoding::Utf32, 0, 3), TextSize::from(6));
+        assert_eq!(col_index.wide_to_utf8_col(WideEncoding::Utf32, 0, 4), TextSize::from(9));
+
+        // The same character is two UTF-16 code units wide
+        assert_eq!(col_index.utf8_to_wide_col(WideEncoding::Utf16, 0, 9.into()), 5);
     }
 
     #[test]
@@ -246,28 +271,28 @@ const C: char = \"メ メ\";
 ",
         );
 
-        assert_eq!(col_index.utf16_lines.len(), 1);
-        assert_eq!(col_index.utf16_lines[&1].len(), 2);
-        assert_eq!(col_index.utf16_lines[&1][0], Utf16Char { start: 17.into(), end: 20.into() });
-        assert_eq!(col_index.utf16_lines[&1][1], Utf16Char { start: 21.into(), end: 24.into() });
+        assert_eq!(col_index.line_wide_chars.len(), 1);
+        assert_eq!(col_index.line_wide_chars[&1].len(), 2);
+        assert_eq!(col_index.line_wide_chars[&1][0], WideChar { start: 17.into(), end: 20.into() });
+        assert_eq!(col_index.line_wide_chars[&1][1], WideChar { start: 21.into(), end: 24.into() });
 
         // UTF-8 to UTF-16
-        assert_eq!(col_index.utf8_to_utf16_col(1, 15.into()), 15);
+        assert_eq!(col_index.utf8_to_wide_col(WideEncoding::Utf16, 1, 15.into()), 15);
 
-        assert_eq!(col_index.utf8_to_utf16_col(1, 21.into()), 19);
-        assert_eq!(col_index.utf8_to_utf16_col(1, 25.into()), 21);
+        assert_eq!(col_index.utf8_to_wide_col(WideEncoding::Utf16, 1, 21.into()), 19);
+        assert_eq!(col_index.utf8_to_wide_col(WideEncoding::Utf16, 1, 25.into()), 21);
 
-        assert!(col_index.utf8_to_utf16_col(2, 15.into()) == 15);
+        assert!(col_index.utf8_to_wide_col(WideEncoding::Utf16, 2, 15.into()) == 15);
 
         // UTF-16 to UTF-8
-        assert_eq!(col_index.utf16_to_utf8_col(1, 15), TextSize::from(15));
+        assert_eq!(col_index.wide_to_utf8_col(WideEncoding::Utf16, 1, 15), TextSize::from(15));
 
         // メ UTF-8: 0xE3 0x83 0xA1, UTF-16: 0x30E1
-        assert_eq!(col_index.utf16_to_utf8_col(1, 17), TextSize::from(17)); // first メ at 17..20
-        assert_eq!(col_index.utf16_to_utf8_col(1, 18), TextSize::from(20)); // space
-        assert_eq!(col_index.utf16_to_utf8_col(1, 19), TextSize::from(21)); // second メ at 21..24
+        assert_eq!(col_index.wide_to_utf8_col(WideEncoding::Utf16, 1, 17), TextSize::from(17)); // first メ at 17..20
+        assert_eq!(col_index.wide_to_utf8_col(WideEncoding::Utf16, 1, 18), TextSize::from(20)); // space
+        assert_eq!(col_index.wide_to_utf8_col(WideEncoding::Utf16, 1, 19), TextSize::from(21)); // second メ at 21..24
 
-        assert_eq!(col_index.utf16_to_utf8_col(2, 15), TextSize::from(15));
+        assert_eq!(col_index.wide_to_utf8_col(WideEncoding::Utf16, 2, 15), TextSize::from(15));
     }
 
     #[test]
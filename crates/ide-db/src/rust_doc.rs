@@ -1,5 +1,19 @@
 //! Rustdoc specific doc comment handling
 
+use std::iter::successors;
+
+use either::Either;
+use hir::{db::HirDatabase, AttrsWithOwner, HasAttrs, HirFileId, InFile, Semantics};
+use syntax::{
+    ast::{self, HasName, IsString, QuoteOffsets},
+    match_ast, AstNode, AstToken, NodeOrToken, SyntaxKind, SyntaxNode, TextRange, TextSize,
+};
+
+use crate::{defs::Definition, RootDatabase};
+
+const RUSTDOC_FENCE_LENGTH: usize = 3;
+const RUSTDOC_FENCES: [&str; 2] = ["```", "~~~"];
+
 // stripped down version of https://github.com/rust-lang/rust/blob/392ba2ba1a7d6c542d2459fb8133bebf62a4a423/src/librustdoc/html/markdown.rs#L810-L933
 pub fn is_rust_fence(s: &str) -> bool {
     let mut seen_rust_tags = false;
@@ -32,3 +46,315 @@ pub fn is_rust_fence(s: &str) -> bool {
 
     !seen_other_tags || seen_rust_tags
 }
+
+/// Returns the doc attributes of the item defined by `node`, along with the item.
+pub fn doc_attributes(
+    sema: &Semantics<RootDatabase>,
+    node: &SyntaxNode,
+) -> Option<(AttrsWithOwner, Definition)> {
+    match_ast! {
+        match node {
+            ast::SourceFile(it)  => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Module(def))),
+            ast::Module(it)      => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Module(def))),
+            ast::Fn(it)          => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Function(def))),
+            ast::Struct(it)      => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Adt(hir::Adt::Struct(def)))),
+            ast::Union(it)       => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Adt(hir::Adt::Union(def)))),
+            ast::Enum(it)        => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Adt(hir::Adt::Enum(def)))),
+            ast::Variant(it)     => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Variant(def))),
+            ast::Trait(it)       => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Trait(def))),
+            ast::Static(it)      => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Static(def))),
+            ast::Const(it)       => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Const(def))),
+            ast::TypeAlias(it)   => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::TypeAlias(def))),
+            ast::Impl(it)        => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::SelfType(def))),
+            ast::RecordField(it) => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Field(def))),
+            ast::TupleField(it)  => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Field(def))),
+            ast::Macro(it)       => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Macro(def))),
+            // ast::Use(it) => sema.to_def(&it).map(|def| (Box::new(it) as _, def.attrs(sema.db))),
+            _ => None
+        }
+    }
+}
+
+/// Resolves an intra-doc link in the scope of the documented item `def`.
+pub fn resolve_doc_path_for_def(
+    db: &dyn HirDatabase,
+    def: Definition,
+    link: &str,
+    ns: Option<hir::Namespace>,
+) -> Option<Definition> {
+    match def {
+        Definition::Module(it) => it.resolve_doc_path(db, link, ns),
+        Definition::Function(it) => it.resolve_doc_path(db, link, ns),
+        Definition::Adt(it) => it.resolve_doc_path(db, link, ns),
+        Definition::Variant(it) => it.resolve_doc_path(db, link, ns),
+        Definition::Const(it) => it.resolve_doc_path(db, link, ns),
+        Definition::Static(it) => it.resolve_doc_path(db, link, ns),
+        Definition::Trait(it) => it.resolve_doc_path(db, link, ns),
+        Definition::TypeAlias(it) => it.resolve_doc_path(db, link, ns),
+        Definition::Macro(it) => it.resolve_doc_path(db, link, ns),
+        Definition::Field(it) => it.resolve_doc_path(db, link, ns),
+        Definition::BuiltinAttr(_)
+        | Definition::ToolModule(_)
+        | Definition::BuiltinType(_)
+        | Definition::SelfType(_)
+        | Definition::Local(_)
+        | Definition::GenericParam(_)
+        | Definition::Label(_) => None,
+    }
+    .map(Definition::from)
+}
+
+/// A Rust code block of a doc comment. Like rustdoc does for doctests, the code is wrapped into a
+/// function, making up a pseudo-module that can be parsed and analyzed on its own.
+#[derive(Debug)]
+pub struct DocCodeBlock {
+    /// The text of the pseudo-module.
+    pub text: String,
+    lines: Vec<DocCodeLine>,
+}
+
+#[derive(Debug)]
+struct DocCodeLine {
+    /// The range of the line in the pseudo-module.
+    range: TextRange,
+    /// The start of the line in the original file.
+    source_start: TextSize,
+    /// The range of the whitespace and hidden line marker before the code in the original file.
+    prefix: TextRange,
+}
+
+impl DocCodeBlock {
+    fn new() -> DocCodeBlock {
+        DocCodeBlock { text: "fn doctest() {\n".to_string(), lines: Vec::new() }
+    }
+
+    fn push_line(&mut self, line: &str, source_start: TextSize, prefix: TextRange) {
+        let range = TextRange::at(TextSize::of(&self.text), TextSize::of(line));
+        self.lines.push(DocCodeLine { range, source_start, prefix });
+        self.text.push_str(line);
+        self.text.push('\n');
+    }
+
+    fn finish(mut self) -> DocCodeBlock {
+        self.text.push('}');
+        self
+    }
+
+    pub fn parse(&self) -> ast::SourceFile {
+        ast::SourceFile::parse(&self.text).tree()
+    }
+
+    /// The ranges of the original file that belong to the doc comment syntax rather than to the
+    /// code, that is the whitespace after the comment prefix and the `#` of hidden lines.
+    pub fn prefixes(&self) -> impl Iterator<Item = TextRange> + '_ {
+        self.lines.iter().map(|it| it.prefix)
+    }
+
+    /// Maps a range of the pseudo-module to the original file, split at line boundaries.
+    pub fn map_range_up(&self, range: TextRange) -> impl Iterator<Item = TextRange> + '_ {
+        self.lines.iter().filter_map(move |line| {
+            let intersection = line.range.intersect(range)?;
+            if intersection.is_empty() && !range.is_empty() {
+                return None;
+            }
+            Some(intersection - line.range.start() + line.source_start)
+        })
+    }
+
+    /// Maps an offset of the original file into the pseudo-module.
+    pub fn map_offset_down(&self, offset: TextSize) -> Option<TextSize> {
+        let line = self
+            .lines
+            .iter()
+            .find(|it| TextRange::at(it.source_start, it.range.len()).contains_inclusive(offset))?;
+        Some(offset - line.source_start + line.range.start())
+    }
+}
+
+/// Collects the Rust code blocks of the given doc attributes that are written in `file_id`.
+pub fn doc_code_blocks(
+    db: &dyn HirDatabase,
+    attributes: &AttrsWithOwner,
+    file_id: HirFileId,
+) -> Vec<DocCodeBlock> {
+    let attrs_source_map = attributes.source_map(db);
+
+    let mut res = Vec::new();
+    let mut block = None;
+    let mut is_codeblock = false;
+    let mut string;
+
+    for attr in attributes.by_key("doc").attrs() {
+        let InFile { file_id: src_file_id, value: src } = attrs_source_map.source_of(attr);
+        if src_file_id != file_id {
+            continue;
+        }
+        let (text, range) = match &src {
+            Either::Left(it) => {
+                string = match find_doc_string_in_attr(attr, it) {
+                    Some(it) => it,
+                    None => continue,
+                };
+                let text = string.text();
+                let text_range = string.syntax().text_range();
+                match string.quote_offsets() {
+                    Some(QuoteOffsets { contents, .. }) => {
+                        (&text[contents - text_range.start()], contents)
+                    }
+                    None => (text, text_range),
+                }
+            }
+            Either::Right(comment) => {
+                let value = comment.prefix().len();
+                let range = comment.syntax().text_range();
+                (
+                    &comment.text()[value..],
+                    TextRange::new(range.start() + TextSize::try_from(value).unwrap(), range.end()),
+                )
+            }
+        };
+
+        let mut line_start = range.start();
+        for line in text.split('\n') {
+            let line_len = TextSize::of(line);
+            let start = line_start;
+            line_start += line_len + TextSize::from(1);
+
+            if let Some(idx) = RUSTDOC_FENCES.into_iter().find_map(|fence| line.find(fence)) {
+                is_codeblock = !is_codeblock;
+                res.extend(block.take().map(DocCodeBlock::finish));
+                // Check whether code is rust by inspecting fence guards
+                let guards = &line[idx + RUSTDOC_FENCE_LENGTH..];
+                if is_codeblock && is_rust_fence(guards) {
+                    block = Some(DocCodeBlock::new());
+                }
+                continue;
+            }
+            let block = match &mut block {
+                Some(it) => it,
+                None => continue,
+            };
+
+            let mut pos = TextSize::from(0);
+            // whitespace after comment is ignored
+            if let Some(ws) = line[pos.into()..].chars().next().filter(|c| c.is_whitespace()) {
+                pos += TextSize::of(ws);
+            }
+            // lines marked with `#` should be ignored in output, we skip the `#` char
+            if line[pos.into()..].starts_with('#') {
+                pos += TextSize::of('#');
+            }
+
+            block.push_line(&line[pos.into()..], start + pos, TextRange::at(start, pos));
+        }
+    }
+    res.extend(block.map(DocCodeBlock::finish));
+    res
+}
+
+/// Returns whether `node` has doc comments or doc attributes that might contain a code block. This
+/// is a cheap syntactic check to avoid looking up the doc attributes of every item.
+pub fn may_have_doc_code_blocks(node: &SyntaxNode) -> bool {
+    node.children_with_tokens().any(|it| match it {
+        NodeOrToken::Token(token) => {
+            token.kind() == SyntaxKind::COMMENT
+                && RUSTDOC_FENCES.iter().any(|fence| token.text().contains(fence))
+        }
+        NodeOrToken::Node(node) => node.kind() == SyntaxKind::ATTR,
+    })
+}
+
+/// Resolves a path written in a doc code block to an item of the documented crate.
+pub fn resolve_doc_code_path(
+    db: &RootDatabase,
+    def: Definition,
+    path: &ast::Path,
+) -> Option<Definition> {
+    let link = doc_code_crate_link(db, def, path)?;
+    resolve_doc_path_for_def(db, def, &link, None)
+}
+
+/// Turns a path written in a doc code block into an intra-doc link starting with `crate`, if it
+/// refers to the documented crate.
+///
+/// Doctests are compiled as separate crates depending on the documented one, so only paths
+/// starting with the name of the documented crate, possibly through an import of the code block,
+/// refer to it.
+pub fn doc_code_crate_link(db: &RootDatabase, def: Definition, path: &ast::Path) -> Option<String> {
+    let crate_name = def.krate(db)?.display_name(db)?.crate_name().to_string();
+    let mut segments = path_segments(path)?;
+    if *segments.first()? != crate_name {
+        let file = path.syntax().ancestors().last().and_then(ast::SourceFile::cast)?;
+        let (_, import) =
+            doc_code_imports(&file).into_iter().find(|(name, _)| *name == segments[0])?;
+        segments.splice(0..1, import);
+        if segments[0] != crate_name {
+            return None;
+        }
+    }
+    segments[0] = "crate".to_string();
+    Some(segments.join("::"))
+}
+
+/// Returns the segments of `path`, including the prefixes of the enclosing use trees.
+fn path_segments(path: &ast::Path) -> Option<Vec<String>> {
+    let mut paths = vec![path.clone()];
+    // The innermost use tree is the one `path` belongs to.
+    paths.extend(
+        path.syntax().ancestors().filter_map(ast::UseTree::cast).skip(1).filter_map(|it| it.path()),
+    );
+    let mut res = Vec::new();
+    for path in paths.iter().rev() {
+        let mut segments = successors(Some(path.clone()), ast::Path::qualifier)
+            .filter_map(|it| it.segment())
+            // `self` in a use tree refers to the prefix of the tree
+            .filter(|it| it.self_token().is_none() || paths.len() == 1)
+            .map(|it| Some(it.name_ref()?.text().to_string()))
+            .collect::<Option<Vec<_>>>()?;
+        segments.reverse();
+        res.extend(segments);
+    }
+    Some(res)
+}
+
+/// Returns the names imported by the `use` items of a doc code block, with the path they refer to.
+fn doc_code_imports(file: &ast::SourceFile) -> Vec<(String, Vec<String>)> {
+    file.syntax()
+        .descendants()
+        .filter_map(ast::UseTree::cast)
+        .filter(|it| it.use_tree_list().is_none() && it.star_token().is_none())
+        .filter_map(|it| {
+            let path = it.path()?;
+            let name = match it.rename() {
+                Some(rename) => rename.name()?.text().to_string(),
+                None => path.segment()?.name_ref()?.text().to_string(),
+            };
+            Some((name, path_segments(&path)?))
+        })
+        .collect()
+}
+
+fn find_doc_string_in_attr(attr: &hir::Attr, it: &ast::Attr) -> Option<ast::String> {
+    match it.expr() {
+        // #[doc = lit]
+        Some(ast::Expr::Literal(lit)) => match lit.kind() {
+            ast::LiteralKind::String(it) => Some(it),
+            _ => None,
+        },
+        // #[cfg_attr(..., doc = "", ...)]
+        None => {
+            // We gotta hunt the string token manually here
+            let text = attr.string_value()?;
+            // FIXME: We just pick the first string literal that has the same text as the doc attribute
+            // This means technically we might highlight the wrong one
+            it.syntax()
+                .descendants_with_tokens()
+                .filter_map(NodeOrToken::into_token)
+                .filter_map(ast::String::cast)
+                .find(|string| {
+                    string.text().get(1..string.text().len() - 1).map_or(false, |it| it == text)
+                })
+        }
+        _ => None,
+    }
+}
@@ -66,6 +66,8 @@ pub enum ReferenceCategory {
     // Create
     Write,
     Read,
+    /// The reference is part of a `use` item, either an import or a re-export.
+    Import,
     // FIXME: Some day should be able to search in doc comments. Would probably
     // need to switch from enum to bitflags then?
     // DocComment
@@ -573,7 +575,7 @@ impl<'a> FindUsages<'a> {
                 let reference = FileReference {
                     range,
                     name: ast::NameLike::NameRef(name_ref.clone()),
-                    category: None,
+                    category: ReferenceCategory::new(&def, name_ref),
                 };
                 sink(file_id, reference)
             }
@@ -750,6 +752,10 @@ fn def_to_ty(sema: &Semantics<RootDatabase>, def: &Definition) -> Option<hir::Ty
 
 impl ReferenceCategory {
     fn new(def: &Definition, r: &ast::NameRef) -> Option<ReferenceCategory> {
+        if r.syntax().ancestors().any(|it| ast::Use::can_cast(it.kind())) {
+            return Some(ReferenceCategory::Import);
+        }
+
         // Only Locals and Fields have accesses for now.
         if !matches!(def, Definition::Local(_) | Definition::Field(_)) {
             return None;
@@ -770,6 +776,16 @@ impl ReferenceCategory {
                     }
                     Some(ReferenceCategory::Read)
                 },
+                ast::RefExpr(expr) => {
+                    // Taking a mutable borrow of the variable or field counts as a Write.
+                    let is_borrowed = expr
+                        .expr()
+                        .map_or(false, |it| it.syntax().text_range().end() == r.syntax().text_range().end());
+                    if expr.mut_token().is_some() && is_borrowed {
+                        return Some(ReferenceCategory::Write);
+                    }
+                    None
+                },
                 _ => None
             }
         }
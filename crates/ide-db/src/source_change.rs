@@ -55,6 +55,15 @@ impl SourceChange {
         self.source_file_edits.get(&file_id)
     }
 
+    /// Whether any of the edits, including the contents of created files, contain snippets.
+    pub fn has_snippets(&self) -> bool {
+        self.is_snippet
+            || self
+                .file_system_edits
+                .iter()
+                .any(|edit| matches!(edit, FileSystemEdit::CreateFile { is_snippet: true, .. }))
+    }
+
     pub fn merge(mut self, other: SourceChange) -> SourceChange {
         self.extend(other.source_file_edits);
         self.extend(other.file_system_edits);
@@ -83,7 +92,7 @@ impl From<FxHashMap<FileId, TextEdit>> for SourceChange {
 
 #[derive(Debug, Clone)]
 pub enum FileSystemEdit {
-    CreateFile { dst: AnchoredPathBuf, initial_contents: String },
+    CreateFile { dst: AnchoredPathBuf, initial_contents: String, is_snippet: bool },
     MoveFile { src: FileId, dst: AnchoredPathBuf },
     MoveDir { src: AnchoredPathBuf, src_id: FileId, dst: AnchoredPathBuf },
 }
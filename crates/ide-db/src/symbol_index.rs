@@ -34,12 +34,16 @@ use base_db::{
 };
 use fst::{self, Streamer};
 use hir::{
-    db::HirDatabase,
-    symbols::{FileSymbol, SymbolCollector},
+    db::{AstDatabase, HirDatabase},
+    symbols::{FileSymbol, FileSymbolKind, SymbolCollector},
     Crate, Module,
 };
 use rayon::prelude::*;
 use rustc_hash::FxHashSet;
+use syntax::{
+    ast::{self, HasAttrs},
+    AstNode,
+};
 
 use crate::RootDatabase;
 
@@ -51,6 +55,8 @@ pub struct Query {
     libs: bool,
     exact: bool,
     case_sensitive: bool,
+    kind: Option<FileSymbolKind>,
+    attr: Option<String>,
     limit: usize,
 }
 
@@ -64,6 +70,8 @@ impl Query {
             libs: false,
             exact: false,
             case_sensitive: false,
+            kind: None,
+            attr: None,
             limit: usize::max_value(),
         }
     }
@@ -84,6 +92,17 @@ impl Query {
         self.case_sensitive = true;
     }
 
+    /// Only return symbols of the given kind.
+    pub fn kind(&mut self, kind: FileSymbolKind) {
+        self.kind = Some(kind);
+    }
+
+    /// Only return symbols whose declaration carries an attribute with the given path, like
+    /// `test` or `tokio::main`.
+    pub fn attr(&mut self, attr: String) {
+        self.attr = Some(attr);
+    }
+
     pub fn limit(&mut self, limit: usize) {
         self.limit = limit
     }
@@ -168,6 +187,14 @@ impl<DB> std::ops::Deref for Snap<DB> {
 // That is, `#` switches from "types" to all symbols, `*` switches from the current
 // workspace to dependencies.
 //
+// The query can also start with a filter, which searches all symbols of the given kind:
+//
+// - `fn foo` searches for `foo` functions, `struct`, `enum`, `union`, `trait`, `type`,
+//   `const`, `static`, `mod` and `macro` work the same way
+// - `#test foo` searches for `foo` items annotated with `#[test]`
+//
+// Items are also found by the names given to them with `#[doc(alias = "...")]`.
+//
 // Note that filtering does not currently work in VSCode due to the editor never
 // sending the special symbols to the language server. Instead, you can configure
 // the filtering via the `rust-analyzer.workspace.symbol.search.scope` and
@@ -178,31 +205,54 @@ impl<DB> std::ops::Deref for Snap<DB> {
 //
 // | VS Code | kbd:[Ctrl+T]
 // |===
-pub fn world_symbols(db: &RootDatabase, query: Query) -> Vec<FileSymbol> {
+pub fn world_symbols(db: &RootDatabase, mut query: Query) -> Vec<FileSymbol> {
     let _p = profile::span("world_symbols").detail(|| query.query.clone());
 
-    let indices: Vec<_> = if query.libs {
-        db.library_roots()
-            .par_iter()
-            .map_with(Snap::new(db), |snap, &root| snap.library_symbols(root))
-            .collect()
-    } else {
-        let mut modules = Vec::new();
-
-        for &root in db.local_roots().iter() {
-            let crates = db.source_root_crates(root);
-            for &krate in crates.iter() {
-                modules.extend(Crate::from(krate).modules(db));
-            }
+    let mut modules = Vec::new();
+    for &root in db.local_roots().iter() {
+        let crates = db.source_root_crates(root);
+        for &krate in crates.iter() {
+            modules.extend(Crate::from(krate).modules(db));
         }
+    }
+    let mut indices: Vec<_> = modules
+        .par_iter()
+        .map_with(Snap::new(db), |snap, &module| snap.module_symbols(module))
+        .collect();
+
+    if query.libs {
+        indices.extend(
+            db.library_roots()
+                .par_iter()
+                .map_with(Snap::new(db), |snap, &root| snap.library_symbols(root))
+                .collect::<Vec<_>>(),
+        );
+    }
 
-        modules
-            .par_iter()
-            .map_with(Snap::new(db), |snap, &module| snap.module_symbols(module))
-            .collect()
+    let attr = match query.attr.take() {
+        Some(it) => it,
+        None => return query.search(&indices),
     };
+    // Attributes aren't part of the index, so we have to look at the declaration of every
+    // candidate and can only apply the limit afterwards.
+    let limit = mem::replace(&mut query.limit, usize::MAX);
+    let mut res = query.search(&indices);
+    res.retain(|symbol| has_attr(db, symbol, &attr));
+    res.truncate(limit);
+    res
+}
 
-    query.search(&indices)
+fn has_attr(db: &RootDatabase, symbol: &FileSymbol, attr: &str) -> bool {
+    let root = match db.parse_or_expand(symbol.loc.hir_file_id) {
+        Some(it) => it,
+        None => return false,
+    };
+    let node = symbol.loc.ptr.to_node(&root);
+    let item = match ast::AnyHasAttrs::cast(node) {
+        Some(it) => it,
+        None => return false,
+    };
+    item.attrs().filter_map(|it| it.path()).any(|path| path.syntax().text() == attr)
 }
 
 pub fn crate_symbols(db: &RootDatabase, krate: Crate, query: Query) -> Vec<FileSymbol> {
@@ -319,6 +369,9 @@ impl Query {
                     if self.only_types && !symbol.kind.is_type() {
                         continue;
                     }
+                    if self.kind.map_or(false, |kind| kind != symbol.kind) {
+                        continue;
+                    }
                     if self.exact {
                         if symbol.name != self.query {
                             continue;
@@ -2,8 +2,11 @@
 
 use crate::RootDatabase;
 use hir::Semantics;
-use rustc_hash::FxHashSet;
-use syntax::{ast, AstNode};
+use rustc_hash::{FxHashMap, FxHashSet};
+use syntax::{
+    ast::{self, HasName},
+    AstNode,
+};
 
 /// Given the `impl` block, attempts to find the trait this `impl` corresponds to.
 pub fn resolve_target_trait(
@@ -19,6 +22,36 @@ pub fn resolve_target_trait(
     }
 }
 
+/// Given the `impl` block, returns the position of each associated item of the implemented trait
+/// in the trait definition, keyed by the item name.
+pub fn trait_item_ranks(
+    sema: &Semantics<RootDatabase>,
+    impl_def: &ast::Impl,
+) -> Option<FxHashMap<String, usize>> {
+    let trait_ = resolve_target_trait(sema, impl_def)?;
+    Some(
+        trait_
+            .items(sema.db)
+            .iter()
+            .flat_map(|it| it.name(sema.db))
+            .enumerate()
+            .map(|(idx, name)| (name.to_string(), idx))
+            .collect(),
+    )
+}
+
+/// Returns the rank of an associated item of a trait `impl` according to `ranks`, items unknown
+/// to the trait are ranked last.
+pub fn assoc_item_rank(ranks: &FxHashMap<String, usize>, item: &ast::AssocItem) -> usize {
+    let name = match item {
+        ast::AssocItem::Const(it) => it.name(),
+        ast::AssocItem::Fn(it) => it.name(),
+        ast::AssocItem::TypeAlias(it) => it.name(),
+        ast::AssocItem::MacroCall(_) => None,
+    };
+    name.and_then(|it| ranks.get(&it.to_string()).copied()).unwrap_or(usize::MAX)
+}
+
 /// Given the `impl` block, returns the list of associated items (e.g. functions or types) that are
 /// missing in this `impl` block.
 pub fn get_missing_assoc_items(
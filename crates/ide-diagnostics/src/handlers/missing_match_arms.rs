@@ -708,11 +708,11 @@ fn main() {
 
         check_diagnostics(
             r#"
-struct S { a: char}
+struct S { a: f32 }
 fn main(v: S) {
     match v { S{ a }      => {} }
     match v { S{ a: _x }  => {} }
-    match v { S{ a: 'a' } => {} }
+    match v { S{ a: 1.0 } => {} }
     match v { S{..}       => {} }
     match v { _           => {} }
     match v { }
@@ -947,6 +947,156 @@ fn f() {
         );
     }
 
+    #[test]
+    fn integers() {
+        check_diagnostics_no_bails(
+            r#"
+fn main() {
+    match 5 {
+        //^ error: missing match arm
+        10 => (),
+        11..20 => (),
+    }
+    match 5u8 {
+        0..=127 => (),
+        128..=255 => (),
+    }
+    match 5u8 {
+        //^^^ error: missing match arm
+        0..=100 => (),
+        100..255 => (),
+    }
+    match (5u8,) {
+        (0,) => (),
+        (1..,) => (),
+    }
+    match 5i8 {
+        -128..=-1 => (),
+        0 => (),
+        1..=127 => (),
+    }
+    match 5i8 {
+        //^^^ error: missing match arm
+        -128..0 => (),
+        1..=127 => (),
+    }
+    match (5u8, true) {
+        (0..=254, _) => (),
+        (255, true) => (),
+        (_, false) => (),
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn chars() {
+        check_diagnostics_no_bails(
+            r#"
+fn main() {
+    match 'a' {
+        //^^^ error: missing match arm
+        'a'..='z' => (),
+        'A'..='Z' => (),
+    }
+    match 'a' {
+        '\0'..='\u{D7FF}' => (),
+        '\u{E000}'..='\u{10FFFF}' => (),
+    }
+    match 'a' {
+        'a' => (),
+        _ => (),
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn strings() {
+        check_diagnostics_no_bails(
+            r#"
+fn main() {
+    match "foo" {
+        //^^^^^ error: missing match arm
+        "foo" => (),
+        "bar" => (),
+    }
+    match "foo" {
+        "foo" => (),
+        _ => (),
+    }
+    match (true, "foo") {
+        //^^^^^^^^^^^^^ error: missing match arm
+        (true, "foo") => (),
+        (false, _) => (),
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn slices() {
+        check_diagnostics_no_bails(
+            r#"
+fn main(s: &[bool]) {
+    match s {
+        //^ error: missing match arm
+        [] => (),
+        [true, ..] => (),
+    }
+    match s {
+        [] => (),
+        [true, ..] => (),
+        [false, ..] => (),
+    }
+    match s {
+        [..] => (),
+    }
+    match s {
+        //^ error: missing match arm
+        [] | [_] => (),
+        [_, _] => (),
+    }
+    match s {
+        [] | [_] => (),
+        [_, .., _] => (),
+    }
+    match s {
+        //^ error: missing match arm
+        [true, ..] => (),
+        [.., false] => (),
+        [] => (),
+    }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn arrays() {
+        check_diagnostics_no_bails(
+            r#"
+fn main() {
+    match [true, false] {
+        //^^^^^^^^^^^^^ error: missing match arm
+        [true, _] => (),
+        [_, true] => (),
+    }
+    match [true, false] {
+        [true, ..] => (),
+        [false, _] => (),
+    }
+    match [true, false] {
+        [first, rest @ ..] => (),
+    }
+}
+"#,
+        );
+    }
+
     mod false_negatives {
         //! The implementation of match checking here is a work in progress. As we roll this out, we
         //! prefer false negatives to false positives (ideally there would be no false positives). This
@@ -959,23 +1109,6 @@ fn f() {
         //!   2. It ensures the code doesn't panic when handling these cases.
         use super::*;
 
-        #[test]
-        fn integers() {
-            cov_mark::check_count!(validate_match_bailed_out, 1);
-
-            // We don't currently check integer exhaustiveness.
-            check_diagnostics(
-                r#"
-fn main() {
-    match 5 {
-        10 => (),
-        11..20 => (),
-    }
-}
-"#,
-            );
-        }
-
         #[test]
         fn reference_patterns_at_top_level() {
             cov_mark::check_count!(validate_match_bailed_out, 1);
@@ -0,0 +1,131 @@
+use std::iter::successors;
+
+use ide_db::{
+    base_db::FileId,
+    defs::Definition,
+    rust_doc::{
+        doc_attributes, doc_code_blocks, doc_code_crate_link, may_have_doc_code_blocks,
+        resolve_doc_path_for_def,
+    },
+    RootDatabase,
+};
+use syntax::{ast, AstNode, SyntaxNode};
+
+use crate::{Diagnostic, DiagnosticsContext};
+
+// Diagnostic: unresolved-doc-path
+//
+// This diagnostic is triggered if a path in a Rust code block of a doc comment refers to an item
+// of the documented crate that does not exist.
+pub(crate) fn unresolved_doc_path(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext<'_>,
+    file_id: FileId,
+    node: &SyntaxNode,
+) -> Option<()> {
+    if !may_have_doc_code_blocks(node) {
+        return None;
+    }
+    let (attributes, def) = doc_attributes(&ctx.sema, node)?;
+    for block in doc_code_blocks(ctx.sema.db, &attributes, file_id.into()) {
+        let block_file = block.parse();
+        let paths = block_file.syntax().descendants().filter_map(ast::Path::cast).filter(|path| {
+            // Only look at whole paths, and at the prefixes of use trees through their leaves.
+            match path.syntax().parent() {
+                Some(parent) => {
+                    !ast::Path::can_cast(parent.kind())
+                        && ast::UseTree::cast(parent)
+                            .map_or(true, |it| it.use_tree_list().is_none())
+                }
+                None => true,
+            }
+        });
+        for path in paths {
+            let unresolved = match unresolved_prefix(ctx.sema.db, def, &path) {
+                Some(it) => it,
+                None => continue,
+            };
+            let message = match path.syntax().parent().and_then(ast::UseTree::cast) {
+                Some(_) => "unresolved import",
+                None => "unresolved path",
+            };
+            if let Some(range) = block.map_range_up(unresolved.syntax().text_range()).next() {
+                acc.push(Diagnostic::new("unresolved-doc-path", message, range));
+            }
+        }
+    }
+    Some(())
+}
+
+/// Returns the shortest prefix of `path` that refers to a missing item of the documented crate.
+/// Only prefixes following a module are checked, as the segments following another item may
+/// name associated items.
+fn unresolved_prefix(db: &RootDatabase, def: Definition, path: &ast::Path) -> Option<ast::Path> {
+    let mut prefixes = successors(Some(path.clone()), ast::Path::qualifier).collect::<Vec<_>>();
+    prefixes.reverse();
+    for prefix in prefixes {
+        let link = doc_code_crate_link(db, def, &prefix)?;
+        if link == "crate" {
+            continue;
+        }
+        match resolve_doc_path_for_def(db, def, &link, None) {
+            Some(Definition::Module(_)) => continue,
+            Some(_) => return None,
+            None => return Some(prefix),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check_diagnostics;
+
+    #[test]
+    fn unresolved_doc_import() {
+        check_diagnostics(
+            r#"
+//- /lib.rs crate:foo
+#[doc = "```"]
+#[doc = "use foo::{inner::Exists, DoesntExist};"]
+                                //^^^^^^^^^^^ error: unresolved import
+#[doc = "use foo::inner::{self, *};"]
+#[doc = "use std::collections::HashMap;"]
+#[doc = "use foo::inner::Missing as _;"]
+           //^^^^^^^^^^^^^^^^^^^ error: unresolved import
+#[doc = "```"]
+pub mod inner {
+    pub struct Exists;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn unresolved_doc_path() {
+        check_diagnostics(
+            r#"
+//- /lib.rs crate:foo
+#[doc = "```"]
+#[doc = "use foo::inner;"]
+#[doc = "let _ = foo::inner::Exists::new();"]
+#[doc = "let _ = inner::Exists;"]
+#[doc = "let _ = foo::inner::Missing::new();"]
+               //^^^^^^^^^^^^^^^^^^^ error: unresolved path
+#[doc = "let _: inner::Missing = foo::nowhere::f();"]
+              //^^^^^^^^^^^^^^ error: unresolved path
+                               //^^^^^^^^^^^^ error: unresolved path
+#[doc = "let _ = std::mem::take(&mut Local);"]
+#[doc = "```"]
+pub mod inner {
+    pub struct Exists;
+    impl Exists {
+        pub fn new() -> Self {
+            Exists
+        }
+    }
+}
+"#,
+        );
+    }
+}
@@ -46,6 +46,7 @@ fn fixes(ctx: &DiagnosticsContext<'_>, d: &hir::UnresolvedModule) -> Option<Vec<
                             path: candidate.clone(),
                         },
                         initial_contents: "".to_string(),
+                        is_snippet: false,
                     }
                     .into(),
                     unresolved_module.syntax().text_range(),
@@ -112,6 +113,7 @@ mod baz {}
                                                         path: "foo.rs",
                                                     },
                                                     initial_contents: "",
+                                                    is_snippet: false,
                                                 },
                                             ],
                                             is_snippet: false,
@@ -139,6 +141,7 @@ mod baz {}
                                                         path: "foo/mod.rs",
                                                     },
                                                     initial_contents: "",
+                                                    is_snippet: false,
                                                 },
                                             ],
                                             is_snippet: false,
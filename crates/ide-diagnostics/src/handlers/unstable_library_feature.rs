@@ -0,0 +1,298 @@
+use hir::{Crate, HasAttrs};
+use ide_db::{assists::Assist, defs::NameRefClass, source_change::SourceChange};
+use syntax::{ast, AstNode, SmolStr, SyntaxNode, TextRange, TextSize};
+use text_edit::TextEdit;
+
+use crate::{fix, Diagnostic, DiagnosticsContext, Severity};
+
+// Diagnostic: unstable-library-feature
+//
+// This diagnostic is triggered if an item marked `#[unstable]` is used from a crate that does
+// not enable the item's feature with `#![feature(...)]`.
+pub(crate) fn unstable_library_feature(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext<'_>,
+    krate: Crate,
+    node: &SyntaxNode,
+) -> Option<()> {
+    if ctx.config.disable_unstable_features {
+        return None;
+    }
+    if let Some(macro_call) = ast::MacroCall::cast(node.clone()) {
+        return macro_expansion(acc, ctx, krate, &macro_call);
+    }
+    let name_ref = ast::NameRef::cast(node.clone())?;
+    let feature = disabled_feature(ctx, krate, &name_ref)?;
+    acc.push(diagnostic(ctx, krate, &feature, name_ref.syntax().text_range()));
+    Some(())
+}
+
+/// Checks the code `macro_call` expands to. Like rustc, uses that come from the macro definition
+/// rather than from the caller are fine if the macro has `#[allow_internal_unstable]`.
+fn macro_expansion(
+    acc: &mut Vec<Diagnostic>,
+    ctx: &DiagnosticsContext<'_>,
+    krate: Crate,
+    macro_call: &ast::MacroCall,
+) -> Option<()> {
+    let call_site = ctx.sema.original_range(macro_call.token_tree()?.syntax());
+    let attrs = ctx.sema.resolve_macro_call(macro_call)?.attrs(ctx.sema.db);
+    let expansion = ctx.sema.expand(macro_call)?;
+    for node in expansion.descendants() {
+        if let Some(macro_call) = ast::MacroCall::cast(node.clone()) {
+            macro_expansion(acc, ctx, krate, &macro_call);
+            continue;
+        }
+        let name_ref = match ast::NameRef::cast(node) {
+            Some(it) => it,
+            None => continue,
+        };
+        let feature = match disabled_feature(ctx, krate, &name_ref) {
+            Some(it) => it,
+            None => continue,
+        };
+        let range = ctx.sema.original_range(name_ref.syntax());
+        if range.file_id != call_site.file_id {
+            continue;
+        }
+        let from_caller = call_site.range.contains_range(range.range);
+        if !from_caller && attrs.allows_internal_unstable(&feature) {
+            continue;
+        }
+        acc.push(diagnostic(ctx, krate, &feature, range.range));
+    }
+    Some(())
+}
+
+fn disabled_feature(
+    ctx: &DiagnosticsContext<'_>,
+    krate: Crate,
+    name_ref: &ast::NameRef,
+) -> Option<SmolStr> {
+    let def = match NameRefClass::classify(&ctx.sema, name_ref)? {
+        NameRefClass::Definition(def) => def,
+        NameRefClass::FieldShorthand { field_ref, .. } => field_ref.into(),
+    };
+    def.disabled_unstable_feature(ctx.sema.db, krate)
+}
+
+fn diagnostic(
+    ctx: &DiagnosticsContext<'_>,
+    krate: Crate,
+    feature: &str,
+    range: TextRange,
+) -> Diagnostic {
+    // rustc reports this as an error, but stability attributes are only approximated here.
+    Diagnostic::new(
+        "unstable-library-feature",
+        format!("use of unstable library feature `{}`", feature),
+        range,
+    )
+    .severity(Severity::WeakWarning)
+    .with_fixes(fixes(ctx, krate, feature, range))
+}
+
+fn fixes(
+    ctx: &DiagnosticsContext<'_>,
+    krate: Crate,
+    feature: &str,
+    range: TextRange,
+) -> Option<Vec<Assist>> {
+    let root_file = krate.root_file(ctx.sema.db);
+    let source_file = ctx.sema.parse(root_file);
+    let feature_attr = format!("#![feature({})]", feature);
+    // Keep the crate attributes together by inserting after the last existing one.
+    let edit = match source_file.attrs().filter(|attr| attr.kind().is_inner()).last() {
+        Some(attr) => {
+            TextEdit::insert(attr.syntax().text_range().end(), format!("\n{}", feature_attr))
+        }
+        None => TextEdit::insert(TextSize::from(0), format!("{}\n", feature_attr)),
+    };
+    Some(vec![fix(
+        "add_feature_attr",
+        &format!("Add `{}` to the crate root", feature_attr),
+        SourceChange::from_text_edit(root_file, edit),
+        range,
+    )])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        tests::{check_diagnostics, check_diagnostics_with_config, check_fix},
+        DiagnosticsConfig,
+    };
+
+    #[test]
+    fn unstable_library_feature() {
+        check_diagnostics(
+            r#"
+//- /main.rs crate:main deps:std
+use std::Unstable;
+       //^^^^^^^^ 💡 weak: use of unstable library feature `new_api`
+
+fn main() {
+    std::stable();
+    std::unstable();
+       //^^^^^^^^ 💡 weak: use of unstable library feature `new_api`
+}
+//- /std.rs crate:std
+#![feature(staged_api)]
+#[unstable(feature = "new_api", issue = "none")]
+pub struct Unstable;
+#[stable(feature = "rust1", since = "1.0.0")]
+pub fn stable() {}
+#[unstable(feature = "new_api", issue = "none")]
+pub fn unstable() {}
+"#,
+        );
+    }
+
+    #[test]
+    fn enabled_feature() {
+        check_diagnostics(
+            r#"
+//- /main.rs crate:main deps:std
+#![feature(new_api)]
+
+fn main() {
+    std::unstable();
+}
+//- /std.rs crate:std
+#![feature(staged_api)]
+#[unstable(feature = "new_api", issue = "none")]
+pub fn unstable() {}
+"#,
+        );
+    }
+
+    #[test]
+    fn unstable_within_defining_crate() {
+        check_diagnostics(
+            r#"
+#![feature(staged_api)]
+#[unstable(feature = "new_api", issue = "none")]
+pub fn unstable() {}
+
+fn main() {
+    unstable();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn ignores_crates_without_staged_api() {
+        check_diagnostics(
+            r#"
+//- /main.rs crate:main deps:lib
+fn main() {
+    lib::unstable();
+}
+//- /lib.rs crate:lib
+#[unstable(feature = "new_api", issue = "none")]
+pub fn unstable() {}
+"#,
+        );
+    }
+
+    #[test]
+    fn disabled_by_config() {
+        let mut config = DiagnosticsConfig::default();
+        config.disable_unstable_features = true;
+        check_diagnostics_with_config(
+            config,
+            r#"
+//- /main.rs crate:main deps:std
+fn main() {
+    std::unstable();
+}
+//- /std.rs crate:std
+#![feature(staged_api)]
+#[unstable(feature = "new_api", issue = "none")]
+pub fn unstable() {}
+"#,
+        );
+    }
+
+    #[test]
+    fn macro_expansion() {
+        check_diagnostics(
+            r#"
+//- /main.rs crate:main deps:std
+fn main() {
+    let _ = std::internal!();
+    let _ = std::call!(std::unstable());
+                          //^^^^^^^^ 💡 weak: use of unstable library feature `new_api`
+    let _ = std::external!();
+          //^^^^^^^^^^^^^^^^ 💡 weak: use of unstable library feature `new_api`
+}
+//- /std.rs crate:std
+#![feature(staged_api)]
+#[unstable(feature = "new_api", issue = "none")]
+pub fn unstable() {}
+
+#[macro_export]
+#[allow_internal_unstable(new_api)]
+macro_rules! internal {
+    () => { $crate::unstable() };
+}
+
+#[macro_export]
+#[allow_internal_unstable(new_api)]
+macro_rules! call {
+    ($e:expr) => { $e };
+}
+
+#[macro_export]
+macro_rules! external {
+    () => { $crate::unstable() };
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn add_feature_attr() {
+        check_fix(
+            r#"
+//- /main.rs crate:main deps:std
+#![allow(unused)]
+
+fn main() {
+    std::unstable$0();
+}
+//- /std.rs crate:std
+#![feature(staged_api)]
+#[unstable(feature = "new_api", issue = "none")]
+pub fn unstable() {}
+"#,
+            r#"
+#![allow(unused)]
+#![feature(new_api)]
+
+fn main() {
+    std::unstable();
+}
+"#,
+        );
+        check_fix(
+            r#"
+//- /main.rs crate:main deps:std
+fn main() {
+    std::unstable$0();
+}
+//- /std.rs crate:std
+#![feature(staged_api)]
+#[unstable(feature = "new_api", issue = "none")]
+pub fn unstable() {}
+"#,
+            r#"
+#![feature(new_api)]
+fn main() {
+    std::unstable();
+}
+"#,
+        );
+    }
+}
@@ -38,11 +38,13 @@ mod handlers {
     pub(crate) mod replace_filter_map_next_with_find_map;
     pub(crate) mod type_mismatch;
     pub(crate) mod unimplemented_builtin_macro;
+    pub(crate) mod unresolved_doc_path;
     pub(crate) mod unresolved_extern_crate;
     pub(crate) mod unresolved_import;
     pub(crate) mod unresolved_macro_call;
     pub(crate) mod unresolved_module;
     pub(crate) mod unresolved_proc_macro;
+    pub(crate) mod unstable_library_feature;
 
     // The handlers below are unusual, the implement the diagnostics as well.
     pub(crate) mod field_shorthand;
@@ -59,7 +61,7 @@ use ide_db::{
     base_db::{FileId, SourceDatabase},
     label::Label,
     source_change::SourceChange,
-    FxHashSet, RootDatabase,
+    FxHashMap, FxHashSet, RootDatabase,
 };
 use syntax::{ast::AstNode, TextRange};
 
@@ -140,6 +142,7 @@ impl Default for ExprFillDefaultMode {
 #[derive(Default, Debug, Clone)]
 pub struct DiagnosticsConfig {
     pub disable_experimental: bool,
+    pub disable_unstable_features: bool,
     pub disabled: FxHashSet<String>,
     pub expr_fill_default: ExprFillDefaultMode,
 }
@@ -173,47 +176,77 @@ pub fn diagnostics(
         handlers::field_shorthand::field_shorthand(&mut res, file_id, &node);
     }
 
-    let module = sema.to_module_def(file_id);
+    // When the workspace is analyzed for several targets, the file belongs to one crate per target.
+    let modules: Vec<_> = sema.to_module_defs(file_id).collect();
+    let module = modules.first().copied();
 
     let ctx = DiagnosticsContext { config, sema, resolve };
     if module.is_none() {
         handlers::unlinked_file::unlinked_file(&ctx, &mut res, file_id);
     }
 
-    let mut diags = Vec::new();
     if let Some(m) = module {
-        m.diagnostics(db, &mut diags)
+        let krate = m.krate();
+        for node in ctx.sema.parse(file_id).syntax().descendants() {
+            handlers::unresolved_doc_path::unresolved_doc_path(&mut res, &ctx, file_id, &node);
+            handlers::unstable_library_feature::unstable_library_feature(
+                &mut res, &ctx, krate, &node,
+            );
+        }
     }
 
-    for diag in diags {
-        #[rustfmt::skip]
-        let d = match diag {
-            AnyDiagnostic::BreakOutsideOfLoop(d) => handlers::break_outside_of_loop::break_outside_of_loop(&ctx, &d),
-            AnyDiagnostic::IncorrectCase(d) => handlers::incorrect_case::incorrect_case(&ctx, &d),
-            AnyDiagnostic::MacroError(d) => handlers::macro_error::macro_error(&ctx, &d),
-            AnyDiagnostic::MalformedDerive(d) => handlers::malformed_derive::malformed_derive(&ctx, &d),
-            AnyDiagnostic::MismatchedArgCount(d) => handlers::mismatched_arg_count::mismatched_arg_count(&ctx, &d),
-            AnyDiagnostic::MissingFields(d) => handlers::missing_fields::missing_fields(&ctx, &d),
-            AnyDiagnostic::MissingMatchArms(d) => handlers::missing_match_arms::missing_match_arms(&ctx, &d),
-            AnyDiagnostic::MissingUnsafe(d) => handlers::missing_unsafe::missing_unsafe(&ctx, &d),
-            AnyDiagnostic::NoSuchField(d) => handlers::no_such_field::no_such_field(&ctx, &d),
-            AnyDiagnostic::ReplaceFilterMapNextWithFindMap(d) => handlers::replace_filter_map_next_with_find_map::replace_filter_map_next_with_find_map(&ctx, &d),
-            AnyDiagnostic::TypeMismatch(d) => handlers::type_mismatch::type_mismatch(&ctx, &d),
-            AnyDiagnostic::UnimplementedBuiltinMacro(d) => handlers::unimplemented_builtin_macro::unimplemented_builtin_macro(&ctx, &d),
-            AnyDiagnostic::UnresolvedExternCrate(d) => handlers::unresolved_extern_crate::unresolved_extern_crate(&ctx, &d),
-            AnyDiagnostic::UnresolvedImport(d) => handlers::unresolved_import::unresolved_import(&ctx, &d),
-            AnyDiagnostic::UnresolvedMacroCall(d) => handlers::unresolved_macro_call::unresolved_macro_call(&ctx, &d),
-            AnyDiagnostic::UnresolvedModule(d) => handlers::unresolved_module::unresolved_module(&ctx, &d),
-            AnyDiagnostic::UnresolvedProcMacro(d) => handlers::unresolved_proc_macro::unresolved_proc_macro(&ctx, &d),
-            AnyDiagnostic::InvalidDeriveTarget(d) => handlers::invalid_derive_target::invalid_derive_target(&ctx, &d),
-
-            AnyDiagnostic::InactiveCode(d) => match handlers::inactive_code::inactive_code(&ctx, &d) {
-                Some(it) => it,
-                None => continue,
+    // Report what any of the crates finds, but code is only inactive if it's inactive in all of
+    // them.
+    let mut seen = FxHashSet::default();
+    let mut inactive = FxHashMap::default();
+    for m in &modules {
+        let mut diags = Vec::new();
+        m.diagnostics(db, &mut diags);
+
+        let mut inactive_in_module = FxHashSet::default();
+        for diag in diags {
+            #[rustfmt::skip]
+            let d = match diag {
+                AnyDiagnostic::BreakOutsideOfLoop(d) => handlers::break_outside_of_loop::break_outside_of_loop(&ctx, &d),
+                AnyDiagnostic::IncorrectCase(d) => handlers::incorrect_case::incorrect_case(&ctx, &d),
+                AnyDiagnostic::MacroError(d) => handlers::macro_error::macro_error(&ctx, &d),
+                AnyDiagnostic::MalformedDerive(d) => handlers::malformed_derive::malformed_derive(&ctx, &d),
+                AnyDiagnostic::MismatchedArgCount(d) => handlers::mismatched_arg_count::mismatched_arg_count(&ctx, &d),
+                AnyDiagnostic::MissingFields(d) => handlers::missing_fields::missing_fields(&ctx, &d),
+                AnyDiagnostic::MissingMatchArms(d) => handlers::missing_match_arms::missing_match_arms(&ctx, &d),
+                AnyDiagnostic::MissingUnsafe(d) => handlers::missing_unsafe::missing_unsafe(&ctx, &d),
+                AnyDiagnostic::NoSuchField(d) => handlers::no_such_field::no_such_field(&ctx, &d),
+                AnyDiagnostic::ReplaceFilterMapNextWithFindMap(d) => handlers::replace_filter_map_next_with_find_map::replace_filter_map_next_with_find_map(&ctx, &d),
+                AnyDiagnostic::TypeMismatch(d) => handlers::type_mismatch::type_mismatch(&ctx, &d),
+                AnyDiagnostic::UnimplementedBuiltinMacro(d) => handlers::unimplemented_builtin_macro::unimplemented_builtin_macro(&ctx, &d),
+                AnyDiagnostic::UnresolvedExternCrate(d) => handlers::unresolved_extern_crate::unresolved_extern_crate(&ctx, &d),
+                AnyDiagnostic::UnresolvedImport(d) => handlers::unresolved_import::unresolved_import(&ctx, &d),
+                AnyDiagnostic::UnresolvedMacroCall(d) => handlers::unresolved_macro_call::unresolved_macro_call(&ctx, &d),
+                AnyDiagnostic::UnresolvedModule(d) => handlers::unresolved_module::unresolved_module(&ctx, &d),
+                AnyDiagnostic::UnresolvedProcMacro(d) => handlers::unresolved_proc_macro::unresolved_proc_macro(&ctx, &d),
+                AnyDiagnostic::InvalidDeriveTarget(d) => handlers::invalid_derive_target::invalid_derive_target(&ctx, &d),
+
+                AnyDiagnostic::InactiveCode(d) => {
+                    if let Some(it) = handlers::inactive_code::inactive_code(&ctx, &d) {
+                        if inactive_in_module.insert(it.range) {
+                            inactive.entry(it.range).or_insert((0, it)).0 += 1;
+                        }
+                    }
+                    continue;
+                }
+            };
+            if seen.insert((d.code.0, d.range, d.message.clone())) {
+                res.push(d)
             }
-        };
-        res.push(d)
+        }
     }
+    let mut inactive: Vec<_> = inactive
+        .into_values()
+        .filter(|(n_modules, _)| *n_modules == modules.len())
+        .map(|(_, d)| d)
+        .collect();
+    inactive.sort_by_key(|d| d.range.start());
+    res.extend(inactive);
 
     res.retain(|d| {
         !ctx.config.disabled.contains(d.code.as_str())
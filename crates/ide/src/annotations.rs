@@ -1,9 +1,9 @@
-use hir::{HasSource, InFile, Semantics};
+use hir::{HasSource, HasVisibility, InFile, Semantics, Visibility};
 use ide_db::{
     base_db::{FileId, FilePosition, FileRange},
     defs::Definition,
     helpers::visit_file_defs,
-    RootDatabase,
+    FxHashSet, RootDatabase,
 };
 use syntax::{ast::HasName, AstNode, TextRange};
 
@@ -39,6 +39,8 @@ pub struct AnnotationConfig {
     pub annotate_runnables: bool,
     pub annotate_impls: bool,
     pub annotate_references: bool,
+    pub annotate_trait_references: bool,
+    pub annotate_public_item_references: bool,
     pub annotate_method_references: bool,
     pub annotate_enum_variant_references: bool,
 }
@@ -63,23 +65,25 @@ pub(crate) fn annotations(
     }
 
     visit_file_defs(&Semantics::new(db), file_id, &mut |def| {
-        let range = match def {
-            Definition::Const(konst) if config.annotate_references => {
-                konst.source(db).and_then(|node| name_range(db, node, file_id))
-            }
-            Definition::Trait(trait_) if config.annotate_references || config.annotate_impls => {
-                trait_.source(db).and_then(|node| name_range(db, node, file_id))
+        let annotate_public_item =
+            |vis: Visibility| config.annotate_public_item_references && vis == Visibility::Public;
+        let (annotate_impls, annotate_references) = match def {
+            Definition::Const(konst) => {
+                (false, config.annotate_references || annotate_public_item(konst.visibility(db)))
             }
-            Definition::Adt(adt) => match adt {
-                hir::Adt::Enum(enum_) => {
+            Definition::Function(func) => (false, annotate_public_item(func.visibility(db))),
+            Definition::Static(static_) => (false, annotate_public_item(static_.visibility(db))),
+            Definition::TypeAlias(alias) => (false, annotate_public_item(alias.visibility(db))),
+            Definition::Trait(_) => (config.annotate_impls, config.annotate_trait_references),
+            Definition::Adt(adt) => {
+                if let hir::Adt::Enum(enum_) = adt {
                     if config.annotate_enum_variant_references {
                         enum_
                             .variants(db)
                             .into_iter()
-                            .map(|variant| {
+                            .filter_map(|variant| {
                                 variant.source(db).and_then(|node| name_range(db, node, file_id))
                             })
-                            .flatten()
                             .for_each(|range| {
                                 annotations.push(Annotation {
                                     range,
@@ -87,33 +91,38 @@ pub(crate) fn annotations(
                                 })
                             })
                     }
-                    if config.annotate_references || config.annotate_impls {
-                        enum_.source(db).and_then(|node| name_range(db, node, file_id))
-                    } else {
-                        None
-                    }
                 }
-                _ => {
-                    if config.annotate_references || config.annotate_impls {
-                        adt.source(db).and_then(|node| name_range(db, node, file_id))
-                    } else {
-                        None
-                    }
-                }
-            },
-            _ => None,
+                (config.annotate_impls, config.annotate_references)
+            }
+            _ => return,
         };
+        if !annotate_impls && !annotate_references {
+            return;
+        }
 
+        let range = match def {
+            Definition::Const(it) => it.source(db).and_then(|node| name_range(db, node, file_id)),
+            Definition::Function(it) => {
+                it.source(db).and_then(|node| name_range(db, node, file_id))
+            }
+            Definition::Static(it) => it.source(db).and_then(|node| name_range(db, node, file_id)),
+            Definition::TypeAlias(it) => {
+                it.source(db).and_then(|node| name_range(db, node, file_id))
+            }
+            Definition::Trait(it) => it.source(db).and_then(|node| name_range(db, node, file_id)),
+            Definition::Adt(it) => it.source(db).and_then(|node| name_range(db, node, file_id)),
+            _ => None,
+        };
         let range = match range {
             Some(range) => range,
             None => return,
         };
 
-        if config.annotate_impls && !matches!(def, Definition::Const(_)) {
+        if annotate_impls {
             annotations
                 .push(Annotation { range, kind: AnnotationKind::HasImpls { file_id, data: None } });
         }
-        if config.annotate_references {
+        if annotate_references {
             annotations.push(Annotation {
                 range,
                 kind: AnnotationKind::HasReferences { file_id, data: None },
@@ -143,6 +152,14 @@ pub(crate) fn annotations(
         ));
     }
 
+    // Public functions can be annotated by the method references lens as well, don't show two
+    // reference counts for them.
+    let mut seen_references = FxHashSet::default();
+    annotations.retain(|annotation| match annotation.kind {
+        AnnotationKind::HasReferences { .. } => seen_references.insert(annotation.range),
+        _ => true,
+    });
+
     annotations
 }
 
@@ -198,6 +215,8 @@ mod tests {
                     annotate_runnables: true,
                     annotate_impls: true,
                     annotate_references: true,
+                    annotate_trait_references: true,
+                    annotate_public_item_references: true,
                     annotate_method_references: true,
                     annotate_enum_variant_references: true,
                 },
@@ -786,4 +805,102 @@ m!();
             "#]],
         );
     }
+
+    #[test]
+    fn public_item_references_annotations() {
+        check(
+            r#"
+//- /lib.rs
+pub static STATIC: i32 = 0;
+pub type Alias = i32;
+pub fn func() {}
+fn private() {}
+
+fn usage(_: Alias) {
+    func();
+    let _ = STATIC;
+}
+            "#,
+            expect![[r#"
+                [
+                    Annotation {
+                        range: 11..17,
+                        kind: HasReferences {
+                            file_id: FileId(
+                                0,
+                            ),
+                            data: Some(
+                                [
+                                    FileRange {
+                                        file_id: FileId(
+                                            0,
+                                        ),
+                                        range: 129..135,
+                                    },
+                                ],
+                            ),
+                        },
+                    },
+                    Annotation {
+                        range: 37..42,
+                        kind: HasReferences {
+                            file_id: FileId(
+                                0,
+                            ),
+                            data: Some(
+                                [
+                                    FileRange {
+                                        file_id: FileId(
+                                            0,
+                                        ),
+                                        range: 96..101,
+                                    },
+                                ],
+                            ),
+                        },
+                    },
+                    Annotation {
+                        range: 57..61,
+                        kind: HasReferences {
+                            file_id: FileId(
+                                0,
+                            ),
+                            data: Some(
+                                [
+                                    FileRange {
+                                        file_id: FileId(
+                                            0,
+                                        ),
+                                        range: 109..113,
+                                    },
+                                ],
+                            ),
+                        },
+                    },
+                    Annotation {
+                        range: 70..77,
+                        kind: HasReferences {
+                            file_id: FileId(
+                                0,
+                            ),
+                            data: Some(
+                                [],
+                            ),
+                        },
+                    },
+                    Annotation {
+                        range: 87..92,
+                        kind: HasReferences {
+                            file_id: FileId(
+                                0,
+                            ),
+                            data: Some(
+                                [],
+                            ),
+                        },
+                    },
+                ]
+            "#]],
+        );
+    }
 }
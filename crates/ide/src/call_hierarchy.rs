@@ -1,13 +1,19 @@
 //! Entry point for call-hierarchy
 
-use hir::Semantics;
+use hir::{AsAssocItem, Semantics};
 use ide_db::{
     defs::{Definition, NameClass, NameRefClass},
     helpers::pick_best_token,
     search::FileReference,
     FxIndexMap, RootDatabase,
 };
-use syntax::{ast, AstNode, SyntaxKind::NAME, TextRange};
+use itertools::Itertools;
+use syntax::{
+    ast::{self, HasArgList},
+    AstNode,
+    SyntaxKind::NAME,
+    SyntaxNode, TextRange,
+};
 
 use crate::{goto_definition, FilePosition, NavigationTarget, RangeInfo, TryToNav};
 
@@ -15,12 +21,16 @@ use crate::{goto_definition, FilePosition, NavigationTarget, RangeInfo, TryToNav
 pub struct CallItem {
     pub target: NavigationTarget,
     pub ranges: Vec<TextRange>,
+    /// Set for calls of trait methods on trait objects or generic types, which might dispatch to
+    /// `target` but can't be resolved statically.
+    pub potential: bool,
 }
 
 impl CallItem {
     #[cfg(test)]
     pub(crate) fn debug_render(&self) -> String {
-        format!("{} : {:?}", self.target.debug_render(), self.ranges)
+        let potential = if self.potential { " (potential)" } else { "" };
+        format!("{} : {:?}{}", self.target.debug_render(), self.ranges, potential)
     }
 }
 
@@ -28,6 +38,19 @@ pub(crate) fn call_hierarchy(
     db: &RootDatabase,
     position: FilePosition,
 ) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    // Goto definition maps the names of trait impl methods to the trait's method, but here we
+    // want the hierarchy of the implementation itself.
+    let sema = Semantics::new(db);
+    let file = sema.parse(position.file_id);
+    let name = sema.find_node_at_offset_with_descend::<ast::Name>(file.syntax(), position.offset);
+    if let Some(name) = name {
+        if let Some(NameClass::Definition(def @ Definition::Function(_))) =
+            NameClass::classify(&sema, &name)
+        {
+            let nav = def.try_to_nav(db)?;
+            return Some(RangeInfo::new(sema.original_range(name.syntax()).range, vec![nav]));
+        }
+    }
     goto_definition::goto_definition(db, position)
 }
 
@@ -41,31 +64,55 @@ pub(crate) fn incoming_calls(
     let file = file.syntax();
     let mut calls = CallLocations::default();
 
-    let references = sema
-        .find_nodes_at_offset_with_descend(file, offset)
-        .filter_map(move |node| match node {
+    let funcs =
+        sema.find_nodes_at_offset_with_descend(file, offset).filter_map(move |node| match node {
             ast::NameLike::NameRef(name_ref) => match NameRefClass::classify(sema, &name_ref)? {
-                NameRefClass::Definition(def @ Definition::Function(_)) => Some(def),
+                NameRefClass::Definition(Definition::Function(func)) => Some(func),
                 _ => None,
             },
             ast::NameLike::Name(name) => match NameClass::classify(sema, &name)? {
-                NameClass::Definition(def @ Definition::Function(_)) => Some(def),
+                NameClass::Definition(Definition::Function(func)) => Some(func),
                 _ => None,
             },
             ast::NameLike::Lifetime(_) => None,
-        })
-        .flat_map(|func| func.usages(sema).all());
-
-    for (_, references) in references {
-        let references = references.into_iter().map(|FileReference { name, .. }| name);
+        });
+
+    for func in funcs {
+        // Calls of the trait's declaration of the method may dispatch to the implementation.
+        let trait_func = trait_item_of_impl_fn(db, func);
+        let references = std::iter::once(func)
+            .chain(trait_func)
+            .flat_map(|func| Definition::Function(func).usages(sema).all())
+            .flat_map(|(_, references)| references)
+            // Searching for a trait method also finds the names of its implementations.
+            .filter_map(|FileReference { name, .. }| name.as_name_ref().cloned())
+            .unique();
         for name in references {
+            // Usages of the trait method include calls resolving to its other implementations.
+            let potential = match NameRefClass::classify(sema, &name) {
+                Some(NameRefClass::Definition(Definition::Function(it))) if it == func => false,
+                Some(NameRefClass::Definition(Definition::Function(it)))
+                    if Some(it) == trait_func =>
+                {
+                    let receiver_ty = call_receiver(&name)
+                        .filter(|_| it.self_param(db).is_some())
+                        .and_then(|it| sema.type_of_expr(&it))
+                        .map(|it| it.adjusted());
+                    match dispatch(db, it, receiver_ty) {
+                        Dispatch::Static(it) if it == func => false,
+                        Dispatch::Dynamic(impl_fns) if impl_fns.contains(&func) => true,
+                        _ => continue,
+                    }
+                }
+                _ => continue,
+            };
             // This target is the containing function
             let nav = sema.ancestors_with_macros(name.syntax().clone()).find_map(|node| {
                 let def = ast::Fn::cast(node).and_then(|fn_| sema.to_def(&fn_))?;
                 def.try_to_nav(sema.db)
             });
             if let Some(nav) = nav {
-                calls.add(nav, sema.original_range(name.syntax()).range);
+                calls.add(nav, sema.original_range(name.syntax()).range, potential);
             }
         }
     }
@@ -88,51 +135,172 @@ pub(crate) fn outgoing_calls(db: &RootDatabase, position: FilePosition) -> Optio
         .into_iter()
         .filter_map(|it| it.ancestors().nth(1).and_then(ast::Item::cast))
         .filter_map(|item| match item {
-            ast::Item::Const(c) => c.body().map(|it| it.syntax().descendants()),
-            ast::Item::Fn(f) => f.body().map(|it| it.syntax().descendants()),
-            ast::Item::Static(s) => s.body().map(|it| it.syntax().descendants()),
+            ast::Item::Const(c) => c.body().map(|it| it.syntax().clone()),
+            ast::Item::Fn(f) => f.body().map(|it| it.syntax().clone()),
+            ast::Item::Static(s) => s.body().map(|it| it.syntax().clone()),
             _ => None,
         })
-        .flatten()
+        .flat_map(|body| descendants_with_expansions(&sema, body))
         .filter_map(ast::CallableExpr::cast)
         .filter_map(|call_node| {
-            let (nav_target, range) = match call_node {
+            let (func, receiver, range) = match call_node {
                 ast::CallableExpr::Call(call) => {
                     let expr = call.expr()?;
                     let callable = sema.type_of_expr(&expr)?.original.as_callable(db)?;
                     match callable.kind() {
                         hir::CallableKind::Function(it) => {
-                            let range = expr.syntax().text_range();
-                            it.try_to_nav(db).zip(Some(range))
+                            // In `Trait::method(receiver)` calls, the receiver is the first argument.
+                            let receiver = match it.self_param(db) {
+                                Some(_) => call.arg_list()?.args().next(),
+                                None => None,
+                            };
+                            (it, receiver, sema.original_range(expr.syntax()).range)
                         }
-                        _ => None,
+                        _ => return None,
                     }
                 }
                 ast::CallableExpr::MethodCall(expr) => {
-                    let range = expr.name_ref()?.syntax().text_range();
+                    let range = sema.original_range(expr.name_ref()?.syntax()).range;
                     let function = sema.resolve_method_call(&expr)?;
-                    function.try_to_nav(db).zip(Some(range))
+                    (function, expr.receiver(), range)
                 }
-            }?;
-            Some((nav_target, range))
+            };
+            Some((func, receiver, range))
         })
-        .for_each(|(nav, range)| calls.add(nav, range));
+        .for_each(|(func, receiver, range)| {
+            let receiver_ty =
+                receiver.and_then(|it| sema.type_of_expr(&it)).map(|it| it.adjusted());
+            let impl_fns = match dispatch(db, func, receiver_ty) {
+                Dispatch::Static(it) => {
+                    if let Some(nav) = it.try_to_nav(db) {
+                        calls.add(nav, range, false);
+                    }
+                    return;
+                }
+                Dispatch::Dynamic(impl_fns) => impl_fns,
+            };
+            if let Some(nav) = func.try_to_nav(db) {
+                calls.add(nav, range, false);
+            }
+            for target in impl_fns {
+                if let Some(nav) = target.try_to_nav(db) {
+                    calls.add(nav, range, true);
+                }
+            }
+        });
 
     Some(calls.into_items())
 }
 
+/// Returns the nodes of `node`'s subtree, descending into the expansions of the macro calls in it.
+fn descendants_with_expansions(
+    sema: &Semantics<RootDatabase>,
+    node: SyntaxNode,
+) -> Vec<SyntaxNode> {
+    let mut res = Vec::new();
+    let mut stack = vec![node];
+    while let Some(node) = stack.pop() {
+        for node in node.descendants() {
+            if let Some(expansion) =
+                ast::MacroCall::cast(node.clone()).and_then(|it| sema.expand(&it))
+            {
+                stack.push(expansion);
+            }
+            res.push(node);
+        }
+    }
+    res
+}
+
+/// Where a call of a function goes.
+enum Dispatch {
+    /// The called function is known statically.
+    Static(hir::Function),
+    /// A trait method is called on a trait object or on a generic type, so any of these
+    /// implementations of it may run.
+    Dynamic(Vec<hir::Function>),
+}
+
+/// Resolves a call of `func` with a receiver of type `receiver_ty`. Calls of trait methods on a
+/// concrete type go to the implementation for that type, or to the trait's default method.
+fn dispatch(db: &RootDatabase, func: hir::Function, receiver_ty: Option<hir::Type>) -> Dispatch {
+    let trait_ = match func.as_assoc_item(db).and_then(|it| it.containing_trait(db)) {
+        Some(it) => it,
+        None => return Dispatch::Static(func),
+    };
+    let receiver_ty = match receiver_ty {
+        Some(it) => it.strip_references(),
+        None => return Dispatch::Static(func),
+    };
+    let name = func.name(db);
+    let impl_fn = |imp: hir::Impl| {
+        imp.items(db).into_iter().find_map(|item| match item {
+            hir::AssocItem::Function(it) if it.name(db) == name => Some(it),
+            _ => None,
+        })
+    };
+    let impls = hir::Impl::all_for_trait(db, trait_);
+    if receiver_ty.as_dyn_trait().is_some()
+        || receiver_ty.is_type_param()
+        || receiver_ty.as_impl_traits(db).is_some()
+    {
+        return Dispatch::Dynamic(impls.into_iter().filter_map(impl_fn).collect());
+    }
+    let for_receiver = impls.into_iter().find(|imp| {
+        let self_ty = imp.self_ty(db);
+        match receiver_ty.as_adt() {
+            Some(adt) => self_ty.as_adt() == Some(adt),
+            None => {
+                receiver_ty.as_builtin().is_some()
+                    && self_ty.as_builtin() == receiver_ty.as_builtin()
+            }
+        }
+    });
+    Dispatch::Static(for_receiver.and_then(impl_fn).unwrap_or(func))
+}
+
+/// The receiver of the call `name_ref` names the callee of, which is the first argument in
+/// `Trait::method(receiver)` calls.
+fn call_receiver(name_ref: &ast::NameRef) -> Option<ast::Expr> {
+    let parent = name_ref.syntax().parent()?;
+    if let Some(call) = ast::MethodCallExpr::cast(parent.clone()) {
+        return call.receiver();
+    }
+    let segment = ast::PathSegment::cast(parent)?;
+    let path = segment.parent_path();
+    if path.segment()? != segment {
+        return None;
+    }
+    let path_expr = ast::PathExpr::cast(path.syntax().parent()?)?;
+    let call = ast::CallExpr::cast(path_expr.syntax().parent()?)?;
+    call.arg_list()?.args().next()
+}
+
+/// For a method in a trait impl, returns the declaration of the method in the trait.
+fn trait_item_of_impl_fn(db: &RootDatabase, func: hir::Function) -> Option<hir::Function> {
+    let trait_ = func.as_assoc_item(db)?.containing_trait_impl(db)?;
+    let name = func.name(db);
+    trait_.items(db).into_iter().find_map(|item| match item {
+        hir::AssocItem::Function(it) if it.name(db) == name => Some(it),
+        _ => None,
+    })
+}
+
 #[derive(Default)]
 struct CallLocations {
-    funcs: FxIndexMap<NavigationTarget, Vec<TextRange>>,
+    funcs: FxIndexMap<(NavigationTarget, bool), Vec<TextRange>>,
 }
 
 impl CallLocations {
-    fn add(&mut self, target: NavigationTarget, range: TextRange) {
-        self.funcs.entry(target).or_default().push(range);
+    fn add(&mut self, target: NavigationTarget, range: TextRange, potential: bool) {
+        self.funcs.entry((target, potential)).or_default().push(range);
     }
 
     fn into_items(self) -> Vec<CallItem> {
-        self.funcs.into_iter().map(|(target, ranges)| CallItem { target, ranges }).collect()
+        self.funcs
+            .into_iter()
+            .map(|((target, potential), ranges)| CallItem { target, ranges, potential })
+            .collect()
     }
 }
 
@@ -453,8 +621,146 @@ fn caller$0() {
 "#,
             expect![[r#"caller Function FileId(0) 160..194 163..169"#]],
             expect![[]],
-            // FIXME
+            expect![[r#"callee Function FileId(0) 144..159 152..158 : [184..190]"#]],
+        );
+    }
+
+    #[test]
+    fn test_call_hierarchy_in_closures() {
+        check_hierarchy(
+            r#"
+fn callee() {}
+fn call$0er() {
+    let f = || callee();
+    f();
+}
+"#,
+            expect![[r#"caller Function FileId(0) 15..64 18..24"#]],
+            expect![[]],
+            expect![[r#"callee Function FileId(0) 0..14 3..9 : [44..50]"#]],
+        );
+        check_hierarchy(
+            r#"
+fn call$0ee() {}
+fn caller() {
+    let f = || callee();
+    f();
+}
+"#,
+            expect![[r#"callee Function FileId(0) 0..14 3..9"#]],
+            expect![[r#"caller Function FileId(0) 15..64 18..24 : [44..50]"#]],
             expect![[]],
         );
     }
+
+    #[test]
+    fn test_call_hierarchy_trait_method_outgoing() {
+        check_hierarchy(
+            r#"
+trait Trait {
+    fn method(&self);
+}
+struct Foo;
+impl Trait for Foo {
+    fn method(&self) {}
+}
+struct Bar;
+impl Trait for Bar {
+    fn method(&self) {}
+}
+fn generic<T: Trait>(t: &T) {
+    t.method();
+}
+fn call$0er(foo: Foo) {
+    generic(&foo);
+    Trait::method(&foo);
+}
+"#,
+            expect![[r#"caller Function FileId(0) 204..271 207..213"#]],
+            expect![[]],
+            expect![[r#"
+                generic Function FileId(0) 156..203 159..166 : [230..237]
+                method Function FileId(0) 75..94 78..84 : [249..262]"#]],
+        );
+        check_hierarchy(
+            r#"
+trait Trait {
+    fn method(&self);
+}
+struct Foo;
+impl Trait for Foo {
+    fn method(&self) {}
+}
+struct Bar;
+impl Trait for Bar {
+    fn method(&self) {}
+}
+fn gene$0ric<T: Trait>(t: &T) {
+    t.method();
+}
+"#,
+            expect![[r#"generic Function FileId(0) 156..203 159..166"#]],
+            expect![[]],
+            expect![[r#"
+                method Function FileId(0) 18..35 21..27 : [192..198]
+                method Function FileId(0) 75..94 78..84 : [192..198] (potential)
+                method Function FileId(0) 134..153 137..143 : [192..198] (potential)"#]],
+        );
+    }
+
+    #[test]
+    fn test_call_hierarchy_trait_method_incoming() {
+        check_hierarchy(
+            r#"
+trait Trait {
+    fn method(&self);
+}
+struct Foo;
+impl Trait for Foo {
+    fn met$0hod(&self) {}
+}
+fn generic<T: Trait>(t: &T) {
+    t.method();
+}
+struct Bar;
+impl Trait for Bar {
+    fn method(&self) {}
+}
+fn caller(foo: Foo, bar: Bar) {
+    foo.method();
+    bar.method();
+}
+"#,
+            expect![[r#"method Function FileId(0) 75..94 78..84"#]],
+            expect![[r#"
+                generic Function FileId(0) 97..144 100..107 : [133..139] (potential)
+                caller Function FileId(0) 204..273 207..213 : [244..250]"#]],
+            expect![[]],
+        );
+    }
+
+    #[test]
+    fn test_call_hierarchy_trait_object_outgoing() {
+        check_hierarchy(
+            r#"
+trait Trait {
+    fn method(&self);
+}
+struct Foo;
+impl Trait for Foo {
+    fn method(&self) {}
+}
+fn dyn$0amic(t: &dyn Trait, foo: &Foo) {
+    t.method();
+    foo.method();
+}
+"#,
+            expect![[r#"dynamic Function FileId(0) 97..171 100..107"#]],
+            expect![[]],
+            expect![[r#"
+                method Function FileId(0) 18..35 21..27 : [142..148]
+                method Function FileId(0) 75..94 78..84 : [142..148] (potential)
+                method Function FileId(0) 75..94 78..84 : [160..166]"#]],
+        );
+    }
 }
@@ -0,0 +1,130 @@
+use ide_db::{
+    base_db::{CrateId, FileId, SourceDatabase, SourceDatabaseExt},
+    FxHashMap, RootDatabase,
+};
+
+use crate::parent_module::crate_for;
+
+/// The crates owning a file together with all their transitive dependencies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyTree {
+    /// Indices into `crates` of the crates containing the file.
+    pub roots: Vec<usize>,
+    pub crates: Vec<CrateInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrateInfo {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub root_file_id: FileId,
+    /// The enabled cargo features.
+    pub features: Vec<String>,
+    /// Whether the crate lives outside of the workspace, like crates.io and sysroot crates.
+    pub is_library: bool,
+    pub dependencies: Vec<CrateDependency>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrateDependency {
+    /// The name the dependency is known by in the depending crate.
+    pub name: String,
+    /// Index into [`DependencyTree::crates`].
+    pub krate: usize,
+    pub is_dev: bool,
+}
+
+// Feature: Dependency Tree
+//
+// Lists the dependencies of the crate the current file belongs to, so that clients can render
+// them as a tree and open their sources.
+pub(crate) fn dependency_tree(db: &RootDatabase, file_id: FileId) -> DependencyTree {
+    let crate_graph = db.crate_graph();
+    let mut indices = FxHashMap::default();
+    let mut order: Vec<CrateId> = Vec::new();
+    let mut index_of = |krate: CrateId, order: &mut Vec<CrateId>| {
+        *indices.entry(krate).or_insert_with(|| {
+            order.push(krate);
+            order.len() - 1
+        })
+    };
+
+    let roots =
+        crate_for(db, file_id).into_iter().map(|krate| index_of(krate, &mut order)).collect();
+    let mut crates = Vec::new();
+    // `order` grows while we walk it, every crate is visited once.
+    while let Some(&krate) = order.get(crates.len()) {
+        let data = &crate_graph[krate];
+        let dependencies = data
+            .dependencies
+            .iter()
+            .map(|dep| CrateDependency {
+                name: dep.name.to_string(),
+                krate: index_of(dep.crate_id, &mut order),
+                is_dev: dep.is_dev(),
+            })
+            .collect();
+        let mut features: Vec<String> =
+            data.cfg_options.get_cfg_values("feature").map(ToString::to_string).collect();
+        features.sort();
+        crates.push(CrateInfo {
+            name: data.display_name.as_ref().map(ToString::to_string),
+            version: data.version.clone(),
+            root_file_id: data.root_file_id,
+            features,
+            is_library: db.source_root(db.file_source_root(data.root_file_id)).is_library,
+            dependencies,
+        });
+    }
+    DependencyTree { roots, crates }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use stdx::format_to;
+
+    use crate::fixture;
+
+    fn check(ra_fixture: &str, expect: Expect) {
+        let (analysis, file_id) = fixture::file(ra_fixture);
+        let tree = analysis.dependency_tree(file_id).unwrap();
+        let mut actual = format!("roots: {:?}\n", tree.roots);
+        for (idx, krate) in tree.crates.iter().enumerate() {
+            format_to!(
+                actual,
+                "{} {} {:?} library:{} features:{:?}\n",
+                idx,
+                krate.name.as_deref().unwrap_or("?"),
+                krate.root_file_id,
+                krate.is_library,
+                krate.features
+            );
+            for dep in &krate.dependencies {
+                format_to!(actual, "    {} -> {} dev:{}\n", dep.name, dep.krate, dep.is_dev);
+            }
+        }
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn transitive_dependencies() {
+        check(
+            r#"
+//- /main.rs crate:main deps:foo,dep
+//- /foo.rs crate:foo deps:dep cfg:feature=std,feature=alloc
+//- /dep.rs crate:dep new_source_root:library
+//- /other.rs crate:other deps:dep
+"#,
+            expect![[r#"
+                roots: [0]
+                0 main FileId(0) library:false features:[]
+                    foo -> 1 dev:false
+                    dep -> 2 dev:false
+                1 foo FileId(1) library:false features:["alloc", "std"]
+                    dep -> 2 dev:false
+                2 dep FileId(2) library:true features:[]
+            "#]],
+        );
+    }
+}
@@ -12,9 +12,10 @@ use url::Url;
 
 use hir::{db::HirDatabase, Adt, AsAssocItem, AssocItem, AssocItemContainer, HasAttrs};
 use ide_db::{
-    base_db::{CrateOrigin, LangCrateOrigin, SourceDatabase},
+    base_db::{CrateOrigin, LangCrateOrigin, SourceDatabase, SourceDatabaseExt},
     defs::{Definition, NameClass, NameRefClass},
     helpers::pick_best_token,
+    rust_doc::{doc_attributes, doc_code_blocks, resolve_doc_code_path, resolve_doc_path_for_def},
     RootDatabase,
 };
 use syntax::{
@@ -32,6 +33,15 @@ use crate::{
 /// Weblink to an item's documentation.
 pub(crate) type DocumentationLink = String;
 
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExternalDocsConfig {
+    /// The `doc` directory generated by `cargo doc`, linked to for the workspace crates.
+    pub local_docs_url: Option<Url>,
+    /// The base URL for the documentation of dependencies without an `html_root_url`, linked to
+    /// instead of docs.rs.
+    pub docs_base_url: Option<Url>,
+}
+
 const MARKDOWN_OPTIONS: Options =
     Options::ENABLE_FOOTNOTES.union(Options::ENABLE_TABLES).union(Options::ENABLE_TASKLISTS);
 
@@ -111,6 +121,7 @@ pub(crate) fn remove_links(markdown: &str) -> String {
 pub(crate) fn external_docs(
     db: &RootDatabase,
     position: &FilePosition,
+    config: &ExternalDocsConfig,
 ) -> Option<DocumentationLink> {
     let sema = &Semantics::new(db);
     let file = sema.parse(position.file_id).syntax().clone();
@@ -139,7 +150,7 @@ pub(crate) fn external_docs(
         }
     };
 
-    get_doc_link(db, definition)
+    get_doc_link(db, definition, config)
 }
 
 /// Extracts all links from a given markdown text returning the definition text range, link-text
@@ -167,61 +178,6 @@ pub(crate) fn extract_definitions_from_docs(
     .collect()
 }
 
-pub(crate) fn resolve_doc_path_for_def(
-    db: &dyn HirDatabase,
-    def: Definition,
-    link: &str,
-    ns: Option<hir::Namespace>,
-) -> Option<Definition> {
-    match def {
-        Definition::Module(it) => it.resolve_doc_path(db, link, ns),
-        Definition::Function(it) => it.resolve_doc_path(db, link, ns),
-        Definition::Adt(it) => it.resolve_doc_path(db, link, ns),
-        Definition::Variant(it) => it.resolve_doc_path(db, link, ns),
-        Definition::Const(it) => it.resolve_doc_path(db, link, ns),
-        Definition::Static(it) => it.resolve_doc_path(db, link, ns),
-        Definition::Trait(it) => it.resolve_doc_path(db, link, ns),
-        Definition::TypeAlias(it) => it.resolve_doc_path(db, link, ns),
-        Definition::Macro(it) => it.resolve_doc_path(db, link, ns),
-        Definition::Field(it) => it.resolve_doc_path(db, link, ns),
-        Definition::BuiltinAttr(_)
-        | Definition::ToolModule(_)
-        | Definition::BuiltinType(_)
-        | Definition::SelfType(_)
-        | Definition::Local(_)
-        | Definition::GenericParam(_)
-        | Definition::Label(_) => None,
-    }
-    .map(Definition::from)
-}
-
-pub(crate) fn doc_attributes(
-    sema: &Semantics<RootDatabase>,
-    node: &SyntaxNode,
-) -> Option<(hir::AttrsWithOwner, Definition)> {
-    match_ast! {
-        match node {
-            ast::SourceFile(it)  => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Module(def))),
-            ast::Module(it)      => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Module(def))),
-            ast::Fn(it)          => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Function(def))),
-            ast::Struct(it)      => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Adt(hir::Adt::Struct(def)))),
-            ast::Union(it)       => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Adt(hir::Adt::Union(def)))),
-            ast::Enum(it)        => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Adt(hir::Adt::Enum(def)))),
-            ast::Variant(it)     => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Variant(def))),
-            ast::Trait(it)       => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Trait(def))),
-            ast::Static(it)      => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Static(def))),
-            ast::Const(it)       => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Const(def))),
-            ast::TypeAlias(it)   => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::TypeAlias(def))),
-            ast::Impl(it)        => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::SelfType(def))),
-            ast::RecordField(it) => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Field(def))),
-            ast::TupleField(it)  => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Field(def))),
-            ast::Macro(it)       => sema.to_def(&it).map(|def| (def.attrs(sema.db), Definition::Macro(def))),
-            // ast::Use(it) => sema.to_def(&it).map(|def| (Box::new(it) as _, def.attrs(sema.db))),
-            _ => None
-        }
-    }
-}
-
 pub(crate) struct DocCommentToken {
     doc_token: SyntaxToken,
     prefix_len: TextSize,
@@ -239,6 +195,39 @@ pub(crate) fn token_as_doc_comment(doc_token: &SyntaxToken) -> Option<DocComment
 }
 
 impl DocCommentToken {
+    /// Resolves the path at `offset` if it is written in a Rust code block of the doc comment,
+    /// returning its definition along with the range of the path segment in the original file.
+    pub(crate) fn get_code_block_definition_at(
+        &self,
+        sema: &Semantics<RootDatabase>,
+        offset: TextSize,
+    ) -> Option<(Definition, TextRange)> {
+        let node = match_ast! {
+            match (self.doc_token) {
+                ast::Comment(_) => self.doc_token.parent()?,
+                ast::String(_) => self.doc_token.ancestors().skip_while(|n| n.kind() != ATTR).nth(1)?,
+                _ => return None,
+            }
+        };
+        let (attributes, def) = doc_attributes(sema, &node)?;
+        let file_id = sema.hir_file_for(&node);
+        doc_code_blocks(sema.db, &attributes, file_id).into_iter().find_map(|block| {
+            let offset = block.map_offset_down(offset)?;
+            let file = block.parse();
+            let token =
+                pick_best_token(file.syntax().token_at_offset(offset), |kind| match kind {
+                    IDENT => 2,
+                    kind if kind.is_trivia() => 0,
+                    _ => 1,
+                })?;
+            let name_ref = ast::NameRef::cast(token.parent()?)?;
+            let path = ast::PathSegment::cast(name_ref.syntax().parent()?)?.parent_path();
+            let def = resolve_doc_code_path(sema.db, def, &path)?;
+            let range = block.map_range_up(token.text_range()).next()?;
+            Some((def, range))
+        })
+    }
+
     pub(crate) fn get_definition_with_descend_at<T>(
         self,
         sema: &Semantics<RootDatabase>,
@@ -290,10 +279,10 @@ fn broken_link_clone_cb<'a>(link: BrokenLink<'a>) -> Option<(CowStr<'a>, CowStr<
 //
 // This should cease to be a problem if RFC2988 (Stable Rustdoc URLs) is implemented
 // https://github.com/rust-lang/rfcs/pull/2988
-fn get_doc_link(db: &RootDatabase, def: Definition) -> Option<String> {
+fn get_doc_link(db: &RootDatabase, def: Definition, config: &ExternalDocsConfig) -> Option<String> {
     let (target, file, frag) = filename_and_frag_for_def(db, def)?;
 
-    let mut url = get_doc_base_url(db, target)?;
+    let mut url = get_doc_base_url(db, target, config)?;
 
     if let Some(path) = mod_path_of_def(db, target) {
         url = url.join(&path).ok()?;
@@ -314,7 +303,7 @@ fn rewrite_intra_doc_link(
     let (link, ns) = parse_intra_doc_link(target);
 
     let resolved = resolve_doc_path_for_def(db, def, link, ns)?;
-    let mut url = get_doc_base_url(db, resolved)?;
+    let mut url = get_doc_base_url(db, resolved, &ExternalDocsConfig::default())?;
 
     let (_, file, frag) = filename_and_frag_for_def(db, resolved)?;
     if let Some(path) = mod_path_of_def(db, resolved) {
@@ -333,7 +322,7 @@ fn rewrite_url_link(db: &RootDatabase, def: Definition, target: &str) -> Option<
         return None;
     }
 
-    let mut url = get_doc_base_url(db, def)?;
+    let mut url = get_doc_base_url(db, def, &ExternalDocsConfig::default())?;
     let (def, file, frag) = filename_and_frag_for_def(db, def)?;
 
     if let Some(path) = mod_path_of_def(db, def) {
@@ -404,7 +393,11 @@ fn map_links<'e>(
 /// https://doc.rust-lang.org/std/iter/trait.Iterator.html#tymethod.next
 /// ^^^^^^^^^^^^^^^^^^^^^^^^^^
 /// ```
-fn get_doc_base_url(db: &RootDatabase, def: Definition) -> Option<Url> {
+fn get_doc_base_url(
+    db: &RootDatabase,
+    def: Definition,
+    config: &ExternalDocsConfig,
+) -> Option<Url> {
     // special case base url of `BuiltinType` to core
     // https://github.com/rust-lang/rust-analyzer/issues/12250
     if let Definition::BuiltinType(..) = def {
@@ -426,21 +419,28 @@ fn get_doc_base_url(db: &RootDatabase, def: Definition) -> Option<Url> {
         ) => {
             format!("https://doc.rust-lang.org/nightly/{origin}")
         }
-        _ => {
-            krate.get_html_root_url(db).or_else(|| {
-                let version = krate.version(db);
-                // Fallback to docs.rs. This uses `display_name` and can never be
-                // correct, but that's what fallbacks are about.
-                //
-                // FIXME: clicking on the link should just open the file in the editor,
-                // instead of falling back to external urls.
-                Some(format!(
-                    "https://docs.rs/{krate}/{version}/",
-                    krate = display_name,
-                    version = version.as_deref().unwrap_or("*")
-                ))
-            })?
-        }
+        _ => krate.get_html_root_url(db).or_else(|| {
+            // Workspace crates usually aren't published, so link to the docs built locally.
+            let source_root = db.file_source_root(krate.root_file(db));
+            if !db.source_root(source_root).is_library {
+                if let Some(url) = &config.local_docs_url {
+                    return Some(url.to_string());
+                }
+            }
+            let version = krate.version(db);
+            // Fallback to docs.rs, or the configured docs host. This uses `display_name` and can
+            // never be correct, but that's what fallbacks are about.
+            //
+            // FIXME: clicking on the link should just open the file in the editor,
+            // instead of falling back to external urls.
+            let base = config.docs_base_url.as_ref().map_or("https://docs.rs/", Url::as_str);
+            Some(format!(
+                "{base}{krate}/{version}/",
+                base = base,
+                krate = display_name,
+                version = version.as_deref().unwrap_or("*")
+            ))
+        })?,
     };
     Url::parse(&base).ok()?.join(&format!("{}/", display_name)).ok()
 }
@@ -7,15 +7,21 @@ use ide_db::{
 };
 use itertools::Itertools;
 use syntax::{ast, match_ast, AstNode, SyntaxNode};
+use url::Url;
 
 use crate::{
     doc_links::{extract_definitions_from_docs, resolve_doc_path_for_def, rewrite_links},
-    fixture, TryToNav,
+    fixture, ExternalDocsConfig, TryToNav,
 };
 
 fn check_external_docs(ra_fixture: &str, expect: Expect) {
+    check_external_docs_with_config(ra_fixture, &ExternalDocsConfig::default(), expect)
+}
+
+fn check_external_docs_with_config(ra_fixture: &str, config: &ExternalDocsConfig, expect: Expect) {
     let (analysis, position) = fixture::position(ra_fixture);
-    let url = analysis.external_docs(position).unwrap().expect("could not find url for symbol");
+    let url =
+        analysis.external_docs(position, config).unwrap().expect("could not find url for symbol");
 
     expect.assert_eq(&url)
 }
@@ -109,6 +115,42 @@ pub struct Foo;
     );
 }
 
+#[test]
+fn external_docs_doc_url_local_crate() {
+    let config = ExternalDocsConfig {
+        local_docs_url: Some(Url::parse("file:///ws/target/doc/").unwrap()),
+        docs_base_url: None,
+    };
+    check_external_docs_with_config(
+        r#"
+//- /main.rs crate:main deps:foo
+use foo::Foo$0;
+//- /lib.rs crate:foo
+pub struct Foo;
+"#,
+        &config,
+        expect![[r#"file:///ws/target/doc/foo/struct.Foo.html"#]],
+    );
+}
+
+#[test]
+fn external_docs_doc_url_custom_base_url() {
+    let config = ExternalDocsConfig {
+        local_docs_url: Some(Url::parse("file:///ws/target/doc/").unwrap()),
+        docs_base_url: Some(Url::parse("https://docs.example.com/").unwrap()),
+    };
+    check_external_docs_with_config(
+        r#"
+//- /main.rs crate:main deps:foo
+use foo::Foo$0;
+//- /lib.rs crate:foo new_source_root:library
+pub struct Foo;
+"#,
+        &config,
+        expect![[r#"https://docs.example.com/foo/*/foo/struct.Foo.html"#]],
+    );
+}
+
 #[test]
 fn external_docs_doc_url_std_crate() {
     check_external_docs(
@@ -0,0 +1,89 @@
+use hir::Semantics;
+use ide_db::{base_db::FileRange, RootDatabase};
+use syntax::{
+    algo::{find_node_at_offset, find_node_at_range},
+    ast, AstNode,
+};
+
+// Feature: Evaluate Expression
+//
+// Evaluates the selected expression as a constant expression in its scope and shows its value,
+// or the reason it couldn't be evaluated. Paths to constants and immutable statics are
+// substituted by their values, locals are unknown. Without a selection, the innermost expression
+// at the cursor is evaluated.
+//
+// |===
+// | Editor  | Action Name
+//
+// | VS Code | **Rust Analyzer: Evaluate Expression**
+// |===
+pub(crate) fn evaluate_expression(db: &RootDatabase, frange: FileRange) -> String {
+    let sema = Semantics::new(db);
+    let source_file = sema.parse(frange.file_id);
+    let expr = if frange.range.is_empty() {
+        find_node_at_offset::<ast::Expr>(source_file.syntax(), frange.range.start())
+    } else {
+        find_node_at_range::<ast::Expr>(source_file.syntax(), frange.range)
+    };
+    let expr = match expr {
+        Some(it) => it,
+        None => return "Not an expression".to_string(),
+    };
+    match sema.eval_expr(&expr) {
+        Some(Ok(value)) => format!("`{}` = {}", expr, value),
+        Some(Err(e)) => format!("Failed to evaluate `{}`: {:?}", expr, e),
+        None => format!("`{}` is not inside a body", expr),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+
+    use crate::fixture;
+
+    fn check(ra_fixture: &str, expect: Expect) {
+        let (analysis, frange) = fixture::range(ra_fixture);
+        expect.assert_eq(&analysis.evaluate_expression(frange).unwrap());
+    }
+
+    #[test]
+    fn evaluate_with_consts_and_statics() {
+        check(
+            r#"
+const WIDTH: u32 = 4;
+static HEIGHT: u32 = 3;
+fn main() {
+    let area = $0WIDTH * HEIGHT + 1$0;
+}
+"#,
+            expect![[r#"`WIDTH * HEIGHT + 1` = 13 (0xD)"#]],
+        );
+    }
+
+    #[test]
+    fn evaluate_with_locals() {
+        check(
+            r#"
+fn main() {
+    let x = 2;
+    let y = $0x * 2$0;
+}
+"#,
+            expect![[r#"Failed to evaluate `x * 2`: NotSupported("Unexpected missing local")"#]],
+        );
+    }
+
+    #[test]
+    fn evaluate_mutable_static() {
+        check(
+            r#"
+static mut COUNTER: u32 = 0;
+fn main() {
+    let y = unsafe { $0COUNTER + 1$0 };
+}
+"#,
+            expect![[r#"Failed to evaluate `COUNTER + 1`: NotSupported("mutable static")"#]],
+        );
+    }
+}
@@ -1,9 +1,11 @@
 use hir::Semantics;
 use ide_db::{
-    base_db::FileId, helpers::pick_best_token,
-    syntax_helpers::insert_whitespace_into_node::insert_ws_into, RootDatabase,
+    base_db::{FileId, FileRange},
+    helpers::pick_best_token,
+    syntax_helpers::insert_whitespace_into_node::insert_ws_into,
+    RootDatabase,
 };
-use syntax::{ast, ted, AstNode, NodeOrToken, SyntaxKind, SyntaxNode, T};
+use syntax::{ast, ted, AstNode, NodeOrToken, SyntaxKind, SyntaxNode, TextRange, T};
 
 use crate::FilePosition;
 
@@ -12,6 +14,24 @@ pub struct ExpandedMacro {
     pub expansion: String,
 }
 
+pub struct ExpandedMacroStep {
+    pub name: String,
+    pub expansion: String,
+    /// The macro calls in `expansion` which can be expanded further.
+    pub sub_invocations: Vec<MacroSubInvocation>,
+}
+
+pub struct MacroSubInvocation {
+    pub name: String,
+    /// The path to pass to `expand_macro_step` to expand this invocation.
+    pub path: Vec<usize>,
+    /// The range of the invocation in the expansion text.
+    pub range: TextRange,
+    /// The range in the original source this invocation was written at, if its tokens all come
+    /// from there.
+    pub original_range: Option<FileRange>,
+}
+
 // Feature: Expand Macro Recursively
 //
 // Shows the full macro expansion of the macro at current cursor.
@@ -25,6 +45,135 @@ pub struct ExpandedMacro {
 // image::https://user-images.githubusercontent.com/48062697/113020648-b3973180-917a-11eb-84a9-ecb921293dc5.gif[]
 pub(crate) fn expand_macro(db: &RootDatabase, position: FilePosition) -> Option<ExpandedMacro> {
     let sema = Semantics::new(db);
+    let (name, expanded, kind) = match find_macro_call(&sema, position)? {
+        MacroCallAtCursor::Derive { name, expansion, .. } => {
+            let expansion = format(db, SyntaxKind::MACRO_ITEMS, position.file_id, expansion);
+            return Some(ExpandedMacro { name, expansion });
+        }
+        // FIXME: Intermix attribute and bang! expansions
+        // currently we only recursively expand one of the two types
+        MacroCallAtCursor::Attr { name, item } => {
+            (name, expand_attr_macro_recur(&sema, &item)?, SyntaxKind::MACRO_ITEMS)
+        }
+        MacroCallAtCursor::Bang { name, call } => {
+            (name, expand_macro_recur(&sema, &call)?, macro_call_kind(&call))
+        }
+    };
+
+    // FIXME:
+    // macro expansion may lose all white space information
+    // But we hope someday we can use ra_fmt for that
+    let expansion = format(db, kind, position.file_id, expanded);
+
+    Some(ExpandedMacro { name, expansion })
+}
+
+// Feature: Expand Macro Stepwise
+//
+// Shows a single level of the expansion of the macro at current cursor. The macro calls in the
+// expansion can then be expanded one at a time, with the calls whose tokens were written in the
+// original file linking back to it.
+//
+// |===
+// | Editor  | Action Name
+//
+// | VS Code | **Rust Analyzer: Expand macro step by step**
+// |===
+pub(crate) fn expand_macro_step(
+    db: &RootDatabase,
+    position: FilePosition,
+    path: &[usize],
+) -> Option<ExpandedMacroStep> {
+    let sema = Semantics::new(db);
+    let (mut name, mut expanded) = match find_macro_call(&sema, position)? {
+        MacroCallAtCursor::Derive { name, expansion, .. } => (name, expansion),
+        MacroCallAtCursor::Attr { name, item } => (name, sema.expand_attr_macro(&item)?),
+        MacroCallAtCursor::Bang { name, call } => (name, sema.expand(&call)?),
+    };
+    for &idx in path {
+        let call = expanded.descendants().filter_map(ast::MacroCall::cast).nth(idx)?;
+        name = macro_call_name(&call)?;
+        expanded = sema.expand(&call)?;
+    }
+
+    // Only the whitespace is touched, so the macro calls line up with the ones in `expanded`.
+    let rendered = insert_ws_into(expanded.clone());
+    let sub_invocations = expanded
+        .descendants()
+        .filter_map(ast::MacroCall::cast)
+        .zip(rendered.descendants().filter_map(ast::MacroCall::cast))
+        .enumerate()
+        .filter_map(|(idx, (call, rendered_call))| {
+            Some(MacroSubInvocation {
+                name: macro_call_name(&call)?,
+                path: path.iter().copied().chain(Some(idx)).collect(),
+                range: rendered_call.syntax().text_range(),
+                original_range: sema.original_range_opt(call.syntax()),
+            })
+        })
+        .collect();
+
+    Some(ExpandedMacroStep { name, expansion: rendered.to_string(), sub_invocations })
+}
+
+pub struct ProcMacroBacktrace {
+    pub name: String,
+    /// The error of the expansion, `None` if the macro didn't fail this time.
+    pub error: Option<String>,
+}
+
+// Feature: Rerun Proc Macro With Backtrace
+//
+// Expands the proc-macro at current cursor again in a new proc-macro server process with
+// `RUST_BACKTRACE=full`, showing the panic message and the full backtrace, e.g. for a bug
+// report to the author of the macro.
+//
+// |===
+// | Editor  | Action Name
+//
+// | VS Code | **Rust Analyzer: Rerun proc macro with backtrace**
+// |===
+pub(crate) fn rerun_proc_macro_with_backtrace(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<ProcMacroBacktrace> {
+    let sema = Semantics::new(db);
+    let (name, res) = match find_macro_call(&sema, position)? {
+        MacroCallAtCursor::Derive { name, attr, idx, .. } => {
+            (name, sema.expand_derive_macro_with_backtrace(&attr, idx)?)
+        }
+        MacroCallAtCursor::Attr { name, item } => {
+            (name, sema.expand_attr_macro_with_backtrace(&item)?)
+        }
+        MacroCallAtCursor::Bang { name, call } => (name, sema.expand_with_backtrace(&call)?),
+    };
+    Some(ProcMacroBacktrace { name, error: res.err.map(|it| it.to_string()) })
+}
+
+enum MacroCallAtCursor {
+    /// A derive macro, which is expanded right away as it can't be found from the `ast::Attr`.
+    Derive {
+        name: String,
+        expansion: SyntaxNode,
+        attr: ast::Attr,
+        /// The index of the derive in `attr`.
+        idx: usize,
+    },
+    Attr {
+        name: String,
+        item: ast::Item,
+    },
+    Bang {
+        name: String,
+        call: ast::MacroCall,
+    },
+}
+
+fn find_macro_call(
+    sema: &Semantics<RootDatabase>,
+    position: FilePosition,
+) -> Option<MacroCallAtCursor> {
+    let db = sema.db;
     let file = sema.parse(position.file_id);
 
     let tok = pick_best_token(file.syntax().token_at_offset(position.offset), |kind| match kind {
@@ -58,45 +207,35 @@ pub(crate) fn expand_macro(db: &RootDatabase, position: FilePosition) -> Option<
             .take_while(|it| it != &token)
             .filter(|it| it.kind() == T![,])
             .count();
-        let expansion =
-            format(db, SyntaxKind::MACRO_ITEMS, position.file_id, expansions.get(idx).cloned()?);
-        Some(ExpandedMacro { name, expansion })
+        Some(MacroCallAtCursor::Derive {
+            name,
+            expansion: expansions.get(idx).cloned()?,
+            attr,
+            idx,
+        })
     });
 
     if derive.is_some() {
         return derive;
     }
 
-    // FIXME: Intermix attribute and bang! expansions
-    // currently we only recursively expand one of the two types
-    let mut anc = tok.ancestors();
-    let (name, expanded, kind) = loop {
-        let node = anc.next()?;
-
+    tok.ancestors().find_map(|node| {
         if let Some(item) = ast::Item::cast(node.clone()) {
             if let Some(def) = sema.resolve_attr_macro_call(&item) {
-                break (
-                    def.name(db).to_string(),
-                    expand_attr_macro_recur(&sema, &item)?,
-                    SyntaxKind::MACRO_ITEMS,
-                );
+                return Some(MacroCallAtCursor::Attr { name: def.name(db).to_string(), item });
             }
         }
-        if let Some(mac) = ast::MacroCall::cast(node) {
-            break (
-                mac.path()?.segment()?.name_ref()?.to_string(),
-                expand_macro_recur(&sema, &mac)?,
-                mac.syntax().parent().map(|it| it.kind()).unwrap_or(SyntaxKind::MACRO_ITEMS),
-            );
-        }
-    };
+        let call = ast::MacroCall::cast(node)?;
+        Some(MacroCallAtCursor::Bang { name: macro_call_name(&call)?, call })
+    })
+}
 
-    // FIXME:
-    // macro expansion may lose all white space information
-    // But we hope someday we can use ra_fmt for that
-    let expansion = format(db, kind, position.file_id, expanded);
+fn macro_call_name(call: &ast::MacroCall) -> Option<String> {
+    Some(call.path()?.segment()?.name_ref()?.to_string())
+}
 
-    Some(ExpandedMacro { name, expansion })
+fn macro_call_kind(call: &ast::MacroCall) -> SyntaxKind {
+    call.syntax().parent().map(|it| it.kind()).unwrap_or(SyntaxKind::MACRO_ITEMS)
 }
 
 fn expand_macro_recur(
@@ -220,6 +359,23 @@ mod tests {
         expect.assert_eq(&actual);
     }
 
+    #[track_caller]
+    fn check_step(ra_fixture: &str, path: &[usize], expect: Expect) {
+        let (analysis, pos) = fixture::position(ra_fixture);
+        let step = analysis.expand_macro_step(pos, path).unwrap().unwrap();
+        let mut actual = format!("{}\n{}\n", step.name, step.expansion);
+        for sub in step.sub_invocations {
+            actual += &format!(
+                "{} {:?} {:?} {:?}\n",
+                sub.name,
+                sub.path,
+                &step.expansion[sub.range],
+                sub.original_range.map(|it| it.range)
+            );
+        }
+        expect.assert_eq(&actual);
+    }
+
     #[test]
     fn macro_expand_as_keyword() {
         check(
@@ -483,4 +639,95 @@ struct Foo {}
             "#]],
         );
     }
+
+    #[test]
+    fn macro_expand_step() {
+        let fixture = r#"
+macro_rules! foo {
+    ($e:expr) => { bar!($e) + baz!() };
+}
+macro_rules! bar {
+    ($e:expr) => { $e * 2 };
+}
+macro_rules! baz {
+    () => { bar!(1) };
+}
+fn main() {
+    let x = fo$0o!(3);
+}
+"#;
+        check_step(
+            fixture,
+            &[],
+            expect![[r#"
+                foo
+                bar!(3)+baz!()
+                bar [0] "bar!(3)" None
+                baz [1] "baz!()" None
+            "#]],
+        );
+        check_step(
+            fixture,
+            &[1],
+            expect![[r#"
+                baz
+                bar!(1)
+                bar [1, 0] "bar!(1)" None
+            "#]],
+        );
+        check_step(
+            fixture,
+            &[1, 0],
+            expect![[r#"
+                bar
+                1*2
+            "#]],
+        );
+    }
+
+    #[test]
+    fn macro_expand_step_maps_to_original() {
+        check_step(
+            r#"
+macro_rules! id {
+    ($($t:tt)*) => { $($t)* };
+}
+macro_rules! one {
+    () => { 1 };
+}
+fn main() {
+    let x = i$0d!(one!());
+}
+"#,
+            &[],
+            expect![[r#"
+                id
+                one!()
+                one [0] "one!()" Some(117..123)
+            "#]],
+        );
+    }
+
+    #[test]
+    fn rerun_proc_macro_with_backtrace() {
+        let (analysis, pos) = fixture::position(
+            r#"
+//- proc_macros: panic_attr
+#[proc_macros::panic_a$0ttr]
+fn foo() {}
+"#,
+        );
+        let res = analysis.rerun_proc_macro_with_backtrace(pos).unwrap().unwrap();
+        assert_eq!((res.name.as_str(), res.error.as_deref()), ("panic_attr", Some("panic_attr")));
+
+        let (analysis, pos) = fixture::position(
+            r#"
+macro_rules! id {
+    ($($t:tt)*) => { $($t)* };
+}
+i$0d!(fn foo() {});
+"#,
+        );
+        assert!(analysis.rerun_proc_macro_with_backtrace(pos).unwrap().is_none());
+    }
 }
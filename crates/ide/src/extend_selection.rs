@@ -32,6 +32,26 @@ pub(crate) fn extend_selection(db: &RootDatabase, frange: FileRange) -> TextRang
     try_extend_selection(&sema, src.syntax(), frange).unwrap_or(frange.range)
 }
 
+const LIST_KINDS: &[SyntaxKind] = &[
+    RECORD_PAT_FIELD_LIST,
+    MATCH_ARM_LIST,
+    RECORD_FIELD_LIST,
+    TUPLE_FIELD_LIST,
+    RECORD_EXPR_FIELD_LIST,
+    VARIANT_LIST,
+    USE_TREE_LIST,
+    GENERIC_PARAM_LIST,
+    GENERIC_ARG_LIST,
+    TYPE_BOUND_LIST,
+    PARAM_LIST,
+    ARG_LIST,
+    ARRAY_EXPR,
+    TUPLE_EXPR,
+    TUPLE_TYPE,
+    TUPLE_PAT,
+    WHERE_CLAUSE,
+];
+
 fn try_extend_selection(
     sema: &Semantics<RootDatabase>,
     root: &SyntaxNode,
@@ -40,25 +60,6 @@ fn try_extend_selection(
     let range = frange.range;
 
     let string_kinds = [COMMENT, STRING, BYTE_STRING];
-    let list_kinds = [
-        RECORD_PAT_FIELD_LIST,
-        MATCH_ARM_LIST,
-        RECORD_FIELD_LIST,
-        TUPLE_FIELD_LIST,
-        RECORD_EXPR_FIELD_LIST,
-        VARIANT_LIST,
-        USE_TREE_LIST,
-        GENERIC_PARAM_LIST,
-        GENERIC_ARG_LIST,
-        TYPE_BOUND_LIST,
-        PARAM_LIST,
-        ARG_LIST,
-        ARRAY_EXPR,
-        TUPLE_EXPR,
-        TUPLE_TYPE,
-        TUPLE_PAT,
-        WHERE_CLAUSE,
-    ];
 
     if range.is_empty() {
         let offset = range.start();
@@ -98,7 +99,9 @@ fn try_extend_selection(
     // if we are in single token_tree, we maybe live in macro or attr
     if node.kind() == TOKEN_TREE {
         if let Some(macro_call) = node.ancestors().find_map(ast::MacroCall::cast) {
-            if let Some(range) = extend_tokens_from_range(sema, macro_call, range) {
+            if let Some(range) = extend_list_item_in_macro(sema, frange, &macro_call)
+                .or_else(|| extend_tokens_from_range(sema, macro_call, range))
+            {
                 return Some(range);
             }
         }
@@ -110,7 +113,7 @@ fn try_extend_selection(
 
     let node = shallowest_node(&node);
 
-    if node.parent().map(|n| list_kinds.contains(&n.kind())) == Some(true) {
+    if node.parent().map(|n| LIST_KINDS.contains(&n.kind())) == Some(true) {
         if let Some(range) = extend_list_item(&node) {
             return Some(range);
         }
@@ -186,6 +189,55 @@ fn extend_tokens_from_range(
     }
 }
 
+/// Extends a list item written inside of a macro call to include its delimiter, the same way
+/// [`extend_list_item`] does outside of macros.
+fn extend_list_item_in_macro(
+    sema: &Semantics<RootDatabase>,
+    frange: FileRange,
+    macro_call: &ast::MacroCall,
+) -> Option<TextRange> {
+    let range = frange.range;
+    let first_token = skip_trivia_token(
+        macro_call.syntax().token_at_offset(range.start()).right_biased()?,
+        Direction::Next,
+    )?;
+    let last_token = skip_trivia_token(
+        macro_call.syntax().token_at_offset(range.end()).left_biased()?,
+        Direction::Prev,
+    )?;
+    let fst_expanded = sema.descend_into_macros_single(first_token);
+    let lst_expanded = sema.descend_into_macros_single(last_token);
+    let lca = algo::least_common_ancestor(&fst_expanded.parent()?, &lst_expanded.parent()?)?;
+    let node = shallowest_node(&lca);
+    if !node.parent().map_or(false, |it| LIST_KINDS.contains(&it.kind())) {
+        return None;
+    }
+    // Only extend to the delimiter once the whole item is selected.
+    if sema.original_range_opt(&node)? != frange {
+        return None;
+    }
+
+    let (delimiter, dir) = list_item_delimiter(&node)?;
+    let delimiter = sema.original_token(delimiter)?;
+    let delimiter_range = delimiter.value.text_range();
+    if delimiter.file_id != frange.file_id.into()
+        || !macro_call.syntax().text_range().contains_range(delimiter_range)
+    {
+        return None;
+    }
+    match dir {
+        Direction::Next => {
+            let end = delimiter
+                .value
+                .next_token()
+                .filter(is_single_line_ws)
+                .map_or(delimiter_range.end(), |ws| ws.text_range().end());
+            Some(TextRange::new(range.start(), end))
+        }
+        Direction::Prev => Some(TextRange::new(delimiter_range.start(), range.end())),
+    }
+}
+
 /// Find the shallowest node with same range, which allows us to traverse siblings.
 fn shallowest_node(node: &SyntaxNode) -> SyntaxNode {
     node.ancestors().take_while(|n| n.text_range() == node.text_range()).last().unwrap()
@@ -254,10 +306,26 @@ fn pick_best(l: SyntaxToken, r: SyntaxToken) -> SyntaxToken {
 
 /// Extend list item selection to include nearby delimiter and whitespace.
 fn extend_list_item(node: &SyntaxNode) -> Option<TextRange> {
-    fn is_single_line_ws(node: &SyntaxToken) -> bool {
-        node.kind() == WHITESPACE && !node.text().contains('\n')
+    let (delimiter_node, dir) = list_item_delimiter(node)?;
+    match dir {
+        Direction::Next => {
+            // Include any following whitespace when delimiter is after list item.
+            let final_node = delimiter_node
+                .next_sibling_or_token()
+                .and_then(|it| it.into_token())
+                .filter(is_single_line_ws)
+                .unwrap_or(delimiter_node);
+
+            Some(TextRange::new(node.text_range().start(), final_node.text_range().end()))
+        }
+        Direction::Prev => {
+            Some(TextRange::new(delimiter_node.text_range().start(), node.text_range().end()))
+        }
     }
+}
 
+/// Finds the delimiter separating a list item from its neighbour, preferring the one after it.
+fn list_item_delimiter(node: &SyntaxNode) -> Option<(SyntaxToken, Direction)> {
     fn nearby_delimiter(
         delimiter_kind: SyntaxKind,
         node: &SyntaxNode,
@@ -278,21 +346,13 @@ fn extend_list_item(node: &SyntaxNode) -> Option<TextRange> {
         _ => T![,],
     };
 
-    if let Some(delimiter_node) = nearby_delimiter(delimiter, node, Direction::Next) {
-        // Include any following whitespace when delimiter is after list item.
-        let final_node = delimiter_node
-            .next_sibling_or_token()
-            .and_then(|it| it.into_token())
-            .filter(is_single_line_ws)
-            .unwrap_or(delimiter_node);
-
-        return Some(TextRange::new(node.text_range().start(), final_node.text_range().end()));
-    }
-    if let Some(delimiter_node) = nearby_delimiter(delimiter, node, Direction::Prev) {
-        return Some(TextRange::new(delimiter_node.text_range().start(), node.text_range().end()));
-    }
+    nearby_delimiter(delimiter, node, Direction::Next).map(|it| (it, Direction::Next)).or_else(
+        || nearby_delimiter(delimiter, node, Direction::Prev).map(|it| (it, Direction::Prev)),
+    )
+}
 
-    None
+fn is_single_line_ws(node: &SyntaxToken) -> bool {
+    node.kind() == WHITESPACE && !node.text().contains('\n')
 }
 
 fn extend_comments(comment: ast::Comment) -> Option<TextRange> {
@@ -659,4 +719,18 @@ fn main() { let (
             ],
         );
     }
+
+    #[test]
+    fn extend_selection_list_item_inside_macros() {
+        do_check(
+            r#"macro_rules! foo { ($($t:tt)*) => {$($t)*} }
+                foo!{fn hello() { let x = [1, 2$0, 3]; }}"#,
+            &["2", "2, ", "[1, 2, 3]"],
+        );
+        do_check(
+            r#"macro_rules! assert_eq { ($l:expr, $r:expr) => { if !($l == $r) { panic!() } } }
+                fn hello() { assert_eq!(foo(1 + $02, 3), x); }"#,
+            &["2", "1 + 2", "1 + 2, ", "(1 + 2, 3)", "foo(1 + 2, 3)"],
+        );
+    }
 }
@@ -1,4 +1,4 @@
-use ide_db::{syntax_helpers::node_ext::vis_eq, FxHashSet};
+use ide_db::{imports::insert_use::ImportGroup, syntax_helpers::node_ext::vis_eq, FxHashSet};
 use syntax::{
     ast::{self, AstNode, AstToken},
     match_ast, Direction, NodeOrToken, SourceFile,
@@ -24,6 +24,7 @@ pub enum FoldKind {
     Array,
     WhereClause,
     ReturnType,
+    MatchArm,
 }
 
 #[derive(Debug)]
@@ -35,7 +36,10 @@ pub struct Fold {
 // Feature: Folding
 //
 // Defines folding regions for curly braced blocks, runs of consecutive use, mod, const or static
-// items, and `region` / `endregion` comment markers.
+// items, multi-line where clauses and match arm bodies, and `region` / `endregion` comment markers.
+//
+// Runs of `use` items are split where the kind of import changes, the same way imports are grouped
+// when inserting new ones (`std`, external crates, `crate`, `self` and `super`).
 pub(crate) fn folding_ranges(file: &SourceFile) -> Vec<Fold> {
     let mut res = vec![];
     let mut visited_comments = FxHashSet::default();
@@ -92,23 +96,24 @@ pub(crate) fn folding_ranges(file: &SourceFile) -> Vec<Fold> {
                                 if let Some(range) = contiguous_range_for_item_group(
                                     module,
                                     &mut visited_mods,
+                                    |_, _| true,
                                 ) {
                                     res.push(Fold { range, kind: FoldKind::Mods })
                                 }
                             }
                         },
                         ast::Use(use_) => {
-                            if let Some(range) = contiguous_range_for_item_group(use_, &mut visited_imports) {
+                            if let Some(range) = contiguous_range_for_item_group(use_, &mut visited_imports, same_import_group) {
                                 res.push(Fold { range, kind: FoldKind::Imports })
                             }
                         },
                         ast::Const(konst) => {
-                            if let Some(range) = contiguous_range_for_item_group(konst, &mut visited_consts) {
+                            if let Some(range) = contiguous_range_for_item_group(konst, &mut visited_consts, |_, _| true) {
                                 res.push(Fold { range, kind: FoldKind::Consts })
                             }
                         },
                         ast::Static(statik) => {
-                            if let Some(range) = contiguous_range_for_item_group(statik, &mut visited_statics) {
+                            if let Some(range) = contiguous_range_for_item_group(statik, &mut visited_statics, |_, _| true) {
                                 res.push(Fold { range, kind: FoldKind::Statics })
                             }
                         },
@@ -117,6 +122,11 @@ pub(crate) fn folding_ranges(file: &SourceFile) -> Vec<Fold> {
                                 res.push(Fold { range, kind: FoldKind::WhereClause })
                             }
                         },
+                        ast::MatchArm(match_arm) => {
+                            if let Some(range) = fold_range_for_match_arm(match_arm) {
+                                res.push(Fold { range, kind: FoldKind::MatchArm })
+                            }
+                        },
                         _ => (),
                     }
                 }
@@ -148,7 +158,11 @@ fn fold_kind(kind: SyntaxKind) -> Option<FoldKind> {
     }
 }
 
-fn contiguous_range_for_item_group<N>(first: N, visited: &mut FxHashSet<N>) -> Option<TextRange>
+fn contiguous_range_for_item_group<N>(
+    first: N,
+    visited: &mut FxHashSet<N>,
+    same_group: impl Fn(&N, &N) -> bool,
+) -> Option<TextRange>
 where
     N: ast::HasVisibility + Clone + Hash + Eq,
 {
@@ -175,14 +189,15 @@ where
 
         if let Some(next) = N::cast(node) {
             let next_vis = next.visibility();
-            if eq_visibility(next_vis.clone(), last_vis) {
+            if eq_visibility(next_vis.clone(), last_vis) && same_group(&last, &next) {
                 visited.insert(next.clone());
                 last_vis = next_vis;
                 last = next;
                 continue;
             }
         }
-        // Stop if we find an item of a different kind or with a different visibility.
+        // Stop if we find an item of a different kind, with a different visibility or belonging
+        // to another group.
         break;
     }
 
@@ -194,6 +209,11 @@ where
     }
 }
 
+fn same_import_group(a: &ast::Use, b: &ast::Use) -> bool {
+    let group = |use_: &ast::Use| use_.use_tree()?.path().map(|path| ImportGroup::new(&path));
+    group(a) == group(b)
+}
+
 fn eq_visibility(vis0: Option<ast::Visibility>, vis1: Option<ast::Visibility>) -> bool {
     match (vis0, vis1) {
         (None, None) => true,
@@ -253,10 +273,14 @@ fn contiguous_range_for_comment(
 }
 
 fn fold_range_for_where_clause(where_clause: ast::WhereClause) -> Option<TextRange> {
-    let first_where_pred = where_clause.predicates().next();
-    let last_where_pred = where_clause.predicates().last();
-
-    if first_where_pred != last_where_pred {
+    let first_where_pred = where_clause.predicates().next()?;
+    let last_where_pred = where_clause.predicates().last()?;
+
+    // Only fold where clauses whose predicates span multiple lines
+    let predicates_range =
+        first_where_pred.syntax().text_range().cover(last_where_pred.syntax().text_range())
+            - where_clause.syntax().text_range().start();
+    if where_clause.syntax().text().slice(predicates_range).contains_char('\n') {
         let start = where_clause.where_token()?.text_range().end();
         let end = where_clause.syntax().text_range().end();
         return Some(TextRange::new(start, end));
@@ -264,6 +288,16 @@ fn fold_range_for_where_clause(where_clause: ast::WhereClause) -> Option<TextRan
     None
 }
 
+fn fold_range_for_match_arm(match_arm: ast::MatchArm) -> Option<TextRange> {
+    let expr = match_arm.expr()?;
+    // Block bodies are already folded as blocks
+    if matches!(expr, ast::Expr::BlockExpr(_)) || !expr.syntax().text().contains_char('\n') {
+        return None;
+    }
+    let start = match_arm.fat_arrow_token()?.text_range().end();
+    Some(TextRange::new(start, match_arm.syntax().text_range().end()))
+}
+
 #[cfg(test)]
 mod tests {
     use test_utils::extract_tags;
@@ -299,6 +333,7 @@ mod tests {
                 FoldKind::Array => "array",
                 FoldKind::WhereClause => "whereclause",
                 FoldKind::ReturnType => "returntype",
+                FoldKind::MatchArm => "matcharm",
             };
             assert_eq!(kind, &attr.unwrap());
         }
@@ -391,6 +426,21 @@ use std::collections::VecDeque;</fold>
         );
     }
 
+    #[test]
+    fn test_fold_import_groups_by_kind() {
+        check(
+            r#"
+<fold imports>use std::str;
+use core::mem;</fold>
+<fold imports>use itertools::Itertools;
+use rustc_hash::FxHashMap;</fold>
+use crate::foo;
+<fold imports>use super::bar;
+use super::baz;</fold>
+"#,
+        );
+    }
+
     #[test]
     fn test_fold_import_and_groups() {
         check(
@@ -456,6 +506,25 @@ fn main() <fold block>{
         );
     }
 
+    #[test]
+    fn fold_long_match_arm_bodies() {
+        check(
+            r#"
+fn main() <fold block>{
+    match 0 <fold block>{
+        0 => 0,
+        1 =><fold matcharm> foo
+            .bar()
+            .baz(),</fold>
+        _ => <fold block>{
+            1
+        }</fold>
+    }</fold>
+}</fold>
+"#,
+        );
+    }
+
     #[test]
     fn fold_big_calls() {
         check(
@@ -559,6 +628,14 @@ where<fold whereclause>
 fn bar()
 where
     A: Bar, {}
+
+fn baz() where A: Baz, B: Baz {}
+
+fn qux()
+where<fold whereclause>
+    F: Fn<fold arglist>(
+        u32,
+    )</fold> -> u32,</fold> {}
 "#,
         )
     }
@@ -1,15 +1,28 @@
-use hir::Semantics;
+use hir::{AsAssocItem, Semantics};
 use ide_db::{
     defs::{Definition, NameClass, NameRefClass},
-    RootDatabase,
+    RootDatabase, SymbolKind,
+};
+use syntax::{
+    ast::{self, HasModuleItem, HasName},
+    match_ast, AstNode,
+    SyntaxKind::*,
+    T,
 };
-use syntax::{ast, match_ast, AstNode, SyntaxKind::*, T};
 
-use crate::{FilePosition, NavigationTarget, RangeInfo};
+use crate::{
+    goto_definition::goto_definition, FilePosition, NavigationTarget, RangeInfo, TryToNav,
+};
 
 // Feature: Go to Declaration
 //
 // Navigates to the declaration of an identifier.
+//
+// This is the same as `Go to Definition` with the following exceptions:
+// - outline modules will navigate to the `mod name;` item declaration
+// - names imported under an alias will navigate to the `use` item or `extern crate` item
+//   introducing the alias
+// - trait assoc items will navigate to the assoc item of the trait declaration as opposed to the trait impl
 pub(crate) fn goto_declaration(
     db: &RootDatabase,
     position: FilePosition,
@@ -25,29 +38,145 @@ pub(crate) fn goto_declaration(
         .iter()
         .filter_map(|token| {
             let parent = token.parent()?;
-            let def = match_ast! {
+            let (def, name_ref) = match_ast! {
                 match parent {
                     ast::NameRef(name_ref) => match NameRefClass::classify(&sema, &name_ref)? {
-                        NameRefClass::Definition(it) => Some(it),
-                        _ => None
+                        NameRefClass::Definition(it) => (it, Some(name_ref)),
+                        _ => return None,
                     },
                     ast::Name(name) => match NameClass::classify(&sema, &name)? {
-                        NameClass::Definition(it) => Some(it),
-                        _ => None
+                        NameClass::Definition(it) => (it, None),
+                        _ => return None,
                     },
-                    _ => None
+                    _ => return None,
                 }
             };
-            match def? {
+            if let Some(nav) = name_ref.and_then(|it| alias_declaration(&sema, &it, def)) {
+                return Some(nav);
+            }
+            let assoc = match def {
                 Definition::Module(module) => {
-                    Some(NavigationTarget::from_module_to_decl(db, module))
+                    return Some(NavigationTarget::from_module_to_decl(db, module))
                 }
+                Definition::Const(c) => c.as_assoc_item(db),
+                Definition::TypeAlias(ta) => ta.as_assoc_item(db),
+                Definition::Function(f) => f.as_assoc_item(db),
                 _ => None,
-            }
+            }?;
+
+            let trait_ = assoc.containing_trait_impl(db)?;
+            let name = Some(assoc.name(db)?);
+            let item = trait_.items(db).into_iter().find(|it| it.name(db) == name)?;
+            item.try_to_nav(db)
         })
         .collect();
 
-    Some(RangeInfo::new(range, info))
+    if info.is_empty() {
+        goto_definition(db, position)
+    } else {
+        Some(RangeInfo::new(range, info))
+    }
+}
+
+/// Finds the `use` alias or `extern crate` item that brought `def` into scope under the name
+/// `name_ref`, if that name differs from the name of the definition itself.
+fn alias_declaration(
+    sema: &Semantics<RootDatabase>,
+    name_ref: &ast::NameRef,
+    def: Definition,
+) -> Option<NavigationTarget> {
+    let db = sema.db;
+    let alias = name_ref.text();
+    if def.name(db).map_or(false, |it| it.to_smol_str() == alias.as_str()) {
+        return None;
+    }
+    let path = name_ref.syntax().parent().and_then(ast::PathSegment::cast)?.parent_path();
+    if path.qualifier().is_some()
+        || path.syntax().ancestors().any(|it| ast::Use::can_cast(it.kind()))
+    {
+        return None;
+    }
+
+    // Aliases are declared in the enclosing block expressions or in the enclosing module, with
+    // the exception of `extern crate` items in the crate root, which are visible everywhere.
+    let mut items: Vec<ast::Item> = Vec::new();
+    for node in name_ref.syntax().ancestors() {
+        match_ast! {
+            match node {
+                ast::StmtList(it) => items.extend(it.statements().filter_map(|stmt| match stmt {
+                    ast::Stmt::Item(item) => Some(item),
+                    _ => None,
+                })),
+                ast::ItemList(it) => {
+                    items.extend(it.items());
+                    break;
+                },
+                ast::SourceFile(it) => {
+                    items.extend(it.items());
+                    break;
+                },
+                _ => (),
+            }
+        }
+    }
+    if let Some(scope) = sema.scope(name_ref.syntax()) {
+        let root_file = scope.krate().root_file(db);
+        items.extend(
+            sema.parse(root_file).items().filter(|it| matches!(it, ast::Item::ExternCrate(_))),
+        );
+    }
+
+    let resolves_to_def = |name_ref: Option<ast::NameRef>| {
+        matches!(
+            name_ref.and_then(|it| NameRefClass::classify(sema, &it)),
+            Some(NameRefClass::Definition(it)) if it == def
+        )
+    };
+    items.into_iter().find_map(|item| {
+        let (node, rename, kind) = match item {
+            ast::Item::Use(use_) => {
+                let use_tree =
+                    use_.syntax().descendants().filter_map(ast::UseTree::cast).find(|it| {
+                        it.rename().and_then(|it| it.name()).map_or(false, |it| it.text() == alias)
+                            && resolves_to_def(it.path().and_then(|it| it.segment()?.name_ref()))
+                    })?;
+                let kind = def.try_to_nav(db).and_then(|it| it.kind).unwrap_or(SymbolKind::Module);
+                (use_tree.syntax().clone(), use_tree.rename()?, kind)
+            }
+            ast::Item::ExternCrate(extern_crate) => {
+                let rename = extern_crate.rename();
+                let name = match rename.as_ref().and_then(|it| it.name()) {
+                    Some(name) => name.text().to_string(),
+                    None => extern_crate.name_ref()?.text().to_string(),
+                };
+                if name != alias.as_str() || !resolves_to_def(extern_crate.name_ref()) {
+                    return None;
+                }
+                let range = sema.original_range(extern_crate.syntax());
+                let focus_range = match rename.and_then(|it| it.name()) {
+                    Some(name) => sema.original_range(name.syntax()).range,
+                    None => sema.original_range(extern_crate.name_ref()?.syntax()).range,
+                };
+                return Some(NavigationTarget::from_syntax(
+                    range.file_id,
+                    name.into(),
+                    Some(focus_range),
+                    range.range,
+                    SymbolKind::Module,
+                ));
+            }
+            _ => return None,
+        };
+        let range = sema.original_range(&node);
+        let focus_range = rename.name().map(|it| sema.original_range(it.syntax()).range);
+        Some(NavigationTarget::from_syntax(
+            range.file_id,
+            alias.as_str().into(),
+            focus_range,
+            range.range,
+            kind,
+        ))
+    })
 }
 
 #[cfg(test)]
@@ -106,4 +235,129 @@ mod foo {
 "#,
         )
     }
+
+    #[test]
+    fn goto_decl_use_alias() {
+        check(
+            r#"
+mod foo {
+    pub struct Foo;
+}
+use foo::Foo as Bar;
+             // ^^^
+fn main() {
+    let _: Bar$0;
+}
+"#,
+        )
+    }
+
+    #[test]
+    fn goto_decl_use_alias_in_block() {
+        check(
+            r#"
+mod foo {
+    pub fn foo() {}
+}
+fn main() {
+    use foo::{foo as bar};
+                  // ^^^
+    bar$0();
+}
+"#,
+        )
+    }
+
+    #[test]
+    fn goto_decl_extern_crate_alias() {
+        check(
+            r#"
+//- /main.rs crate:main deps:std
+extern crate std as core2;
+                 // ^^^^^
+mod foo {
+    fn f() {
+        core2$0::S;
+    }
+}
+//- /std/lib.rs crate:std
+pub struct S;
+"#,
+        )
+    }
+
+    #[test]
+    fn goto_decl_unaliased_use_falls_back_to_definition() {
+        check(
+            r#"
+mod foo {
+    pub struct Foo;
+             //^^^
+}
+use foo::Foo;
+fn main() {
+    let _: Foo$0;
+}
+"#,
+        )
+    }
+
+    #[test]
+    fn goto_decl_trait_impl_method() {
+        check(
+            r#"
+trait Trait {
+    fn method(&self);
+    // ^^^^^^
+}
+struct Foo;
+impl Trait for Foo {
+    fn method$0(&self) {}
+}
+"#,
+        );
+        check(
+            r#"
+trait Trait {
+    fn method(&self);
+    // ^^^^^^
+}
+struct Foo;
+impl Trait for Foo {
+    fn method(&self) {}
+}
+fn main() {
+    Foo.method$0();
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_decl_trait_impl_assoc_type_and_const() {
+        check(
+            r#"
+trait Trait {
+    type Assoc;
+      // ^^^^^
+}
+struct Foo;
+impl Trait for Foo {
+    type Assoc$0 = ();
+}
+"#,
+        );
+        check(
+            r#"
+trait Trait {
+    const C: u32;
+       // ^
+}
+struct Foo;
+impl Trait for Foo {
+    const C$0: u32 = 0;
+}
+"#,
+        );
+    }
 }
@@ -42,6 +42,10 @@ pub(crate) fn goto_definition(
             _ => 1,
         })?;
     if let Some(doc_comment) = token_as_doc_comment(&original_token) {
+        if let Some((def, range)) = doc_comment.get_code_block_definition_at(sema, position.offset)
+        {
+            return Some(RangeInfo::new(range, def_to_nav(db, def)));
+        }
         return doc_comment.get_definition_with_descend_at(sema, position.offset, |def, _, _| {
             let nav = def.try_to_nav(db)?;
             Some(RangeInfo::new(original_token.text_range(), vec![nav]))
@@ -58,11 +62,18 @@ pub(crate) fn goto_definition(
                     return Some(vec![x]);
                 }
             }
+            let class = IdentClass::classify_token(sema, &token)?;
+            // Operators resolve to the impl method they dispatch to, which is where they should
+            // lead instead of the trait method.
+            let is_operator = matches!(class, IdentClass::Operator(_));
             Some(
-                IdentClass::classify_token(sema, &token)?
+                class
                     .definitions()
                     .into_iter()
                     .flat_map(|def| {
+                        if is_operator {
+                            return def_to_nav(sema.db, def);
+                        }
                         try_find_trait_item_definition(sema.db, &def)
                             .unwrap_or_else(|| def_to_nav(sema.db, def))
                     })
@@ -1224,6 +1235,24 @@ pub fn foo() { }
         )
     }
 
+    #[test]
+    fn goto_def_in_doc_code_block() {
+        check(
+            r#"
+//- /lib.rs crate:foo
+/// ```
+/// use foo::inner::Bar;
+/// let bar = Bar$0::default();
+/// ```
+pub fn foo() {}
+pub mod inner {
+    pub struct Bar;
+             //^^^
+}
+"#,
+        )
+    }
+
     #[test]
     fn goto_def_for_intra_doc_link_inner() {
         check(
@@ -1466,6 +1495,178 @@ macro_rules! foo {
 }
 
 foo!(bar$0);
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_def_for_bin_op() {
+        check(
+            r#"
+//- minicore: add
+struct Struct;
+
+impl core::ops::Add for Struct {
+    type Output = Struct;
+    fn add(self, _: Struct) -> Struct { Struct }
+     //^^^
+}
+
+fn f() {
+    Struct $0+ Struct;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_def_for_prefix_op() {
+        check(
+            r#"
+#[lang = "neg"]
+trait Neg {
+    type Output;
+    fn neg(self) -> Self::Output;
+}
+
+struct Struct;
+
+impl Neg for Struct {
+    type Output = Struct;
+    fn neg(self) -> Struct { Struct }
+     //^^^
+}
+
+fn f() {
+    $0-Struct;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_def_for_index() {
+        check(
+            r#"
+//- minicore: index
+struct Struct;
+
+impl core::ops::Index<usize> for Struct {
+    type Output = ();
+    fn index(&self, _: usize) -> &() { &() }
+     //^^^^^
+}
+
+fn f() {
+    (Struct)$0[0];
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_def_for_try() {
+        check(
+            r#"
+//- minicore: try
+use core::ops::{ControlFlow, FromResidual, Try};
+
+struct Struct;
+
+impl Try for Struct {
+    type Output = ();
+    type Residual = ();
+    fn from_output(_: ()) -> Struct { Struct }
+    fn branch(self) -> ControlFlow<(), ()> { ControlFlow::Continue(()) }
+     //^^^^^^
+}
+
+impl FromResidual for Struct {
+    fn from_residual(_: ()) -> Struct { Struct }
+}
+
+fn f() -> Struct {
+    (Struct)$0?;
+    Struct
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_def_for_await() {
+        check(
+            r#"
+//- minicore: future
+use core::{future::Future, pin::Pin, task::{Context, Poll}};
+
+struct Struct;
+
+impl Future for Struct {
+    type Output = ();
+    fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> { Poll::Ready(()) }
+     //^^^^
+}
+
+async fn f() {
+    Struct.$0await;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_def_for_overloaded_deref() {
+        check(
+            r#"
+//- minicore: deref
+struct Struct;
+
+impl core::ops::Deref for Struct {
+    type Target = ();
+    fn deref(&self) -> &() { &() }
+     //^^^^^
+}
+
+fn f() {
+    $0*Struct;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_def_for_builtin_deref() {
+        check_unresolved(
+            r#"
+//- minicore: deref
+fn f(x: &u32) {
+    $0*x;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_def_for_builtin_bin_op() {
+        check_unresolved(
+            r#"
+//- minicore: add
+fn f() {
+    1u32 $0+ 2u32;
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn goto_def_for_builtin_index() {
+        check_unresolved(
+            r#"
+//- minicore: index, slice
+fn f(x: &[u32]) {
+    x$0[0];
+}
 "#,
         );
     }
@@ -31,6 +31,7 @@ pub struct HighlightRelatedConfig {
     pub exit_points: bool,
     pub break_points: bool,
     pub yield_points: bool,
+    pub unsafe_operations: bool,
 }
 
 // Feature: Highlight Related
@@ -40,6 +41,7 @@ pub struct HighlightRelatedConfig {
 // - if on an `async` or `await token, highlights all yield points for that async context
 // - if on a `return` or `fn` keyword, `?` character or `->` return type arrow, highlights all exit points for that context
 // - if on a `break`, `loop`, `while` or `for` token, highlights all break points for that loop or block context
+// - if on the `unsafe` keyword of a block or function, highlights all operations in it that require `unsafe`
 //
 // Note: `?` and `->` do not currently trigger this behavior in the VSCode editor.
 pub(crate) fn highlight_related(
@@ -69,6 +71,7 @@ pub(crate) fn highlight_related(
         T![break] | T![loop] | T![while] | T![continue] if config.break_points => {
             highlight_break_points(token)
         }
+        T![unsafe] if config.unsafe_operations => highlight_unsafe_operations(sema, token, file_id),
         _ if config.references => highlight_references(sema, &syntax, token, file_id),
         _ => None,
     }
@@ -320,6 +323,35 @@ fn highlight_yield_points(token: SyntaxToken) -> Option<Vec<HighlightedRange>> {
     None
 }
 
+fn highlight_unsafe_operations(
+    sema: &Semantics<RootDatabase>,
+    token: SyntaxToken,
+    file_id: FileId,
+) -> Option<Vec<HighlightedRange>> {
+    let body = match_ast! {
+        match (token.parent()?) {
+            ast::BlockExpr(block) => ast::Expr::BlockExpr(block),
+            ast::Fn(func) => ast::Expr::BlockExpr(func.body()?),
+            _ => return None,
+        }
+    };
+    let mut highlights = vec![HighlightedRange { range: token.text_range(), category: None }];
+    for op in sema.unsafe_operations(&body) {
+        // Only highlight what makes the operation unsafe, not the arguments of a call.
+        let node = match &op.value {
+            ast::Expr::CallExpr(call) => call.expr().map(|it| it.syntax().clone()),
+            ast::Expr::MethodCallExpr(call) => call.name_ref().map(|it| it.syntax().clone()),
+            _ => None,
+        }
+        .unwrap_or_else(|| op.value.syntax().clone());
+        let range = op.with_value(&node).original_file_range(sema.db);
+        if range.file_id == file_id {
+            highlights.push(HighlightedRange { range: range.range, category: None });
+        }
+    }
+    Some(highlights)
+}
+
 fn cover_range(r0: Option<TextRange>, r1: Option<TextRange>) -> Option<TextRange> {
     match (r0, r1) {
         (Some(r0), Some(r1)) => Some(r0.cover(r1)),
@@ -350,6 +382,7 @@ mod tests {
             exit_points: true,
             references: true,
             yield_points: true,
+            unsafe_operations: true,
         };
 
         check_with_config(ra_fixture, config);
@@ -375,6 +408,7 @@ mod tests {
                         match it {
                             ReferenceCategory::Read => "read",
                             ReferenceCategory::Write => "write",
+                            ReferenceCategory::Import => "import",
                         }
                         .to_string()
                     }),
@@ -421,12 +455,12 @@ struct Foo;
         check(
             r#"
 use crate$0;
-  //^^^^^
+  //^^^^^ import
 use self;
-  //^^^^
+  //^^^^ import
 mod __ {
     use super;
-      //^^^^^
+      //^^^^^ import
 }
 "#,
         );
@@ -434,7 +468,7 @@ mod __ {
             r#"
 //- /main.rs crate:main deps:lib
 use lib$0;
-  //^^^
+  //^^^ import
 //- /lib.rs crate:lib
 "#,
         );
@@ -448,7 +482,7 @@ use lib$0;
 mod foo;
 //- /foo.rs
 use self$0;
- // ^^^^
+ // ^^^^ import
 "#,
         );
     }
@@ -467,6 +501,22 @@ fn foo() {
         );
     }
 
+    #[test]
+    fn test_hl_local_mut_borrow() {
+        check(
+            r#"
+fn foo() {
+    let mut bar = 3;
+         // ^^^ write
+    let _ = &mut bar$0;
+              // ^^^ write
+    let _ = &bar;
+          // ^^^ read
+}
+"#,
+        );
+    }
+
     #[test]
     fn test_hl_local_in_attr() {
         check(
@@ -1064,6 +1114,7 @@ fn function(field: u32) {
             break_points: true,
             exit_points: true,
             yield_points: true,
+            unsafe_operations: true,
         };
 
         check_with_config(
@@ -1084,6 +1135,7 @@ fn foo() {
             break_points: true,
             exit_points: true,
             yield_points: true,
+            unsafe_operations: true,
         };
 
         check_with_config(
@@ -1124,6 +1176,7 @@ fn foo() {
             break_points: true,
             exit_points: true,
             yield_points: true,
+            unsafe_operations: true,
         };
 
         check_with_config(
@@ -1160,6 +1213,7 @@ async fn foo() {
             break_points: true,
             exit_points: true,
             yield_points: true,
+            unsafe_operations: true,
         };
 
         check_with_config(
@@ -1203,6 +1257,7 @@ fn foo() ->$0 i32 {
             break_points: false,
             exit_points: true,
             yield_points: true,
+            unsafe_operations: true,
         };
 
         check_with_config(
@@ -1224,6 +1279,7 @@ fn foo() {
             break_points: true,
             exit_points: true,
             yield_points: false,
+            unsafe_operations: true,
         };
 
         check_with_config(
@@ -1243,6 +1299,7 @@ async$0 fn foo() {
             break_points: true,
             exit_points: false,
             yield_points: true,
+            unsafe_operations: true,
         };
 
         check_with_config(
@@ -1306,6 +1363,51 @@ fn foo((
   //^^^read
     let foo;
 }
+"#,
+        );
+    }
+
+    #[test]
+    fn test_hl_unsafe_block() {
+        check(
+            r#"
+static mut COUNTER: u32 = 0;
+unsafe fn dangerous(_: u32) {}
+fn foo(ptr: *const u32) {
+    let x = 0;
+    unsafe$0 {
+ // ^^^^^^
+        dangerous(x + 1);
+     // ^^^^^^^^^
+        let y = *ptr;
+             // ^^^^
+        COUNTER += y;
+     // ^^^^^^^
+        dangerous(0);
+     // ^^^^^^^^^
+    }
+    dangerous(1);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn test_hl_unsafe_fn() {
+        check(
+            r#"
+struct S;
+impl S {
+    unsafe fn method(&self) {}
+
+    unsafe$0 fn foo(&self, ptr: *mut u32) {
+ // ^^^^^^
+        self.method();
+          // ^^^^^^
+        *ptr = 0;
+     // ^^^^
+    }
+}
 "#,
         );
     }
@@ -9,7 +9,7 @@ use either::Either;
 use hir::{HasSource, Semantics};
 use ide_db::{
     base_db::FileRange,
-    defs::{Definition, IdentClass},
+    defs::{Definition, IdentClass, OperatorClass},
     famous_defs::FamousDefs,
     helpers::pick_best_token,
     FxIndexSet, RootDatabase,
@@ -131,6 +131,10 @@ pub(crate) fn hover(
         .filter_map(|token| {
             let node = token.parent()?;
             let class = IdentClass::classify_token(sema, token)?;
+            if let IdentClass::Operator(OperatorClass::Await(_)) = class {
+                // The keyword hover of `await` is more helpful than the docs of `Future::poll`.
+                return None;
+            }
             Some(class.definitions().into_iter().zip(iter::once(node).cycle()))
         })
         .flatten()
@@ -174,6 +178,13 @@ pub(crate) fn hover_for_definition(
         _ => None,
     };
     if let Some(markup) = render::definition(sema.db, definition, famous_defs.as_ref(), config) {
+        let markup = match sema
+            .scope(node)
+            .and_then(|scope| definition.disabled_unstable_feature(sema.db, scope.krate()))
+        {
+            Some(feature) => render::unstable_feature(markup, &feature),
+            None => markup,
+        };
         let mut res = HoverResult::default();
         res.markup = render::process_markup(sema.db, definition, &markup, config);
         if let Some(action) = show_implementations_action(sema.db, definition) {
@@ -219,6 +230,9 @@ fn hover_ranged(
         {
             render::deref_expr(sema, config, prefix_expr)
         }
+        Either::Left(ast::Expr::ClosureExpr(closure)) => {
+            render::closure_expr(sema, config, closure)
+        }
         _ => None,
     };
     let res = res.or_else(|| render::type_info(sema, config, &expr_or_pat));
@@ -253,7 +267,13 @@ fn hover_type_fallback(
         }
     };
 
-    let res = render::type_info(sema, config, &expr_or_pat)?;
+    let res = match &expr_or_pat {
+        Either::Left(ast::Expr::ClosureExpr(closure)) => {
+            render::closure_expr(sema, config, closure)
+        }
+        _ => None,
+    };
+    let res = res.or_else(|| render::type_info(sema, config, &expr_or_pat))?;
     let range = sema
         .original_range_opt(&node)
         .map(|frange| frange.range)
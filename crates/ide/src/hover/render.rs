@@ -70,6 +70,48 @@ pub(super) fn type_info(
     Some(res)
 }
 
+pub(super) fn closure_expr(
+    sema: &Semantics<RootDatabase>,
+    config: &HoverConfig,
+    closure: &ast::ClosureExpr,
+) -> Option<HoverResult> {
+    let ty = sema.type_of_expr(&closure.clone().into())?.original;
+    let captures = ty
+        .as_closure()?
+        .captured_items(sema.db)
+        .into_iter()
+        .map(|capture| {
+            let kind = match capture.capture_kind() {
+                hir::CaptureKind::SharedRef => "immutable borrow",
+                hir::CaptureKind::MutableRef => "mutable borrow",
+                hir::CaptureKind::Move => "move",
+            };
+            format!("* `{}` by {}", capture.display_place(sema.db), kind)
+        })
+        .join("\n");
+    let captures = if captures.is_empty() {
+        "This closure captures nothing".to_owned()
+    } else {
+        format!("## Captures\n{}", captures)
+    };
+
+    let mut targets: Vec<hir::ModuleDef> = Vec::new();
+    walk_and_push_ty(sema.db, &ty, &mut |item| {
+        if !targets.contains(&item) {
+            targets.push(item);
+        }
+    });
+
+    let mut res = HoverResult::default();
+    res.markup = if config.markdown() {
+        format!("{}\n{}", Markup::fenced_block(&ty.display(sema.db)), captures).into()
+    } else {
+        format!("{}\n{}", ty.display(sema.db), captures).into()
+    };
+    res.actions.push(HoverAction::goto_type_from_targets(sema.db, targets));
+    Some(res)
+}
+
 pub(super) fn try_expr(
     sema: &Semantics<RootDatabase>,
     config: &HoverConfig,
@@ -311,16 +353,21 @@ fn definition_owner_name(db: &RootDatabase, def: &Definition) -> Option<String>
     match def {
         Definition::Field(f) => Some(f.parent_def(db).name(db)),
         Definition::Local(l) => l.parent(db).name(db),
-        Definition::Function(f) => match f.as_assoc_item(db)?.container(db) {
-            hir::AssocItemContainer::Trait(t) => Some(t.name(db)),
-            hir::AssocItemContainer::Impl(i) => i.self_ty(db).as_adt().map(|adt| adt.name(db)),
-        },
+        Definition::Function(f) => assoc_item_owner_name(db, f.as_assoc_item(db)?),
+        Definition::TypeAlias(t) => assoc_item_owner_name(db, t.as_assoc_item(db)?),
         Definition::Variant(e) => Some(e.parent_enum(db).name(db)),
         _ => None,
     }
     .map(|name| name.to_string())
 }
 
+fn assoc_item_owner_name(db: &RootDatabase, item: hir::AssocItem) -> Option<hir::Name> {
+    match item.container(db) {
+        hir::AssocItemContainer::Trait(t) => Some(t.name(db)),
+        hir::AssocItemContainer::Impl(i) => i.self_ty(db).as_adt().map(|adt| adt.name(db)),
+    }
+}
+
 pub(super) fn path(db: &RootDatabase, module: hir::Module, item_name: Option<String>) -> String {
     let crate_name =
         db.crate_graph()[module.krate().into()].display_name.as_ref().map(|it| it.to_string());
@@ -375,6 +422,13 @@ pub(super) fn definition(
     markup(docs.filter(|_| config.documentation.is_some()).map(Into::into), label, mod_path)
 }
 
+/// Appends a note that using the definition requires enabling the unstable `feature`.
+pub(super) fn unstable_feature(markup: Markup, feature: &str) -> Markup {
+    let mut buf = String::from(markup);
+    format_to!(buf, "\n___\n\nThis is a nightly-only API that requires `#![feature({})]`", feature);
+    buf.into()
+}
+
 fn render_builtin_attr(db: &RootDatabase, attr: hir::BuiltinAttr) -> Option<Markup> {
     let name = attr.name(db);
     let desc = format!("#[{}]", name);
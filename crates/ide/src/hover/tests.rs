@@ -467,6 +467,57 @@ fn main() { }
     );
 }
 
+#[test]
+fn hover_shows_disabled_unstable_feature() {
+    check(
+        r#"
+//- /main.rs crate:main deps:std
+fn main() { std::foo$0(); }
+//- /std.rs crate:std
+#![feature(staged_api)]
+#[unstable(feature = "foo_api", issue = "none")]
+pub fn foo() {}
+"#,
+        expect![[r#"
+                *foo*
+
+                ```rust
+                std
+                ```
+
+                ```rust
+                pub fn foo()
+                ```
+
+                ---
+
+                This is a nightly-only API that requires `#![feature(foo_api)]`
+            "#]],
+    );
+    check(
+        r#"
+//- /main.rs crate:main deps:std
+#![feature(foo_api)]
+fn main() { std::foo$0(); }
+//- /std.rs crate:std
+#![feature(staged_api)]
+#[unstable(feature = "foo_api", issue = "none")]
+pub fn foo() {}
+"#,
+        expect![[r#"
+                *foo*
+
+                ```rust
+                std
+                ```
+
+                ```rust
+                pub fn foo()
+                ```
+            "#]],
+    );
+}
+
 #[test]
 fn hover_shows_fn_doc_attr_raw_string() {
     check(
@@ -3382,6 +3433,32 @@ type Fo$0o2 = Foo<2>;
     );
 }
 
+#[test]
+fn hover_generic_associated_type() {
+    check(
+        r#"
+trait LendingIterator {
+    type It$0em<'a, T>
+    where
+        Self: 'a;
+}
+"#,
+        expect![[r#"
+                *Item*
+
+                ```rust
+                test::LendingIterator
+                ```
+
+                ```rust
+                type Item<'a, T>
+                where
+                    Self: 'a,
+                ```
+            "#]],
+    );
+}
+
 #[test]
 fn hover_const_param() {
     check(
@@ -3922,7 +3999,7 @@ impl T1 for Foo {
 *Bar*
 
 ```rust
-test::t2
+test::t2::T2
 ```
 
 ```rust
@@ -3944,7 +4021,7 @@ trait A {
                 *Assoc*
 
                 ```rust
-                test
+                test::A
                 ```
 
                 ```rust
@@ -3965,7 +4042,7 @@ trait A {
                 *Assoc*
 
                 ```rust
-                test
+                test::A
                 ```
 
                 ```rust
@@ -3984,7 +4061,7 @@ trait A where
                 *Assoc*
 
                 ```rust
-                test
+                test::A
                 ```
 
                 ```rust
@@ -4822,3 +4899,92 @@ enum Enum {
         "#]],
     );
 }
+
+#[test]
+fn hover_closure_captures() {
+    check(
+        r#"
+//- minicore: copy
+struct NonCopy;
+struct Foo { a: i32, b: NonCopy, c: (u32, NonCopy) }
+fn main() {
+    let mut x = 5;
+    let foo = Foo { a: 1, b: NonCopy, c: (2, NonCopy) };
+    let r = &foo;
+    let c = $0|| { x += 1; let _a = foo.a; let _c = foo.c.0; let _b = foo.b; let _ = r.a; };
+}
+"#,
+        expect![[r#"
+            *|| { x += 1; let _a = foo.a; let _c = foo.c.0; let _b = foo.b; let _ = r.a; }*
+            ```rust
+            || -> ()
+            ```
+            ## Captures
+            * `x` by mutable borrow
+            * `foo.a` by immutable borrow
+            * `foo.c.0` by immutable borrow
+            * `foo.b` by move
+        "#]],
+    );
+}
+
+#[test]
+fn hover_closure_captures_through_deref() {
+    check(
+        r#"
+//- minicore: copy
+struct Foo { a: i32 }
+fn main() {
+    let foo = Foo { a: 1 };
+    let r = &foo;
+    let c = $0|| { let _a = r.a; };
+}
+"#,
+        expect![[r#"
+            *|| { let _a = r.a; }*
+            ```rust
+            || -> ()
+            ```
+            ## Captures
+            * `(*r).a` by immutable borrow
+        "#]],
+    );
+}
+
+#[test]
+fn hover_move_closure_captures() {
+    check(
+        r#"
+//- minicore: copy
+struct NonCopy;
+fn main() {
+    let x = 5;
+    let s = NonCopy;
+    let c = $0move || (x, s);
+}
+"#,
+        expect![[r#"
+            *move || (x, s)*
+            ```rust
+            || -> (i32, NonCopy)
+            ```
+            ## Captures
+            * `x` by move
+            * `s` by move
+        "#]],
+    );
+    check(
+        r#"
+fn main() {
+    let c = $0|| 5;
+}
+"#,
+        expect![[r#"
+            *|| 5*
+            ```rust
+            || -> i32
+            ```
+            This closure captures nothing
+        "#]],
+    );
+}
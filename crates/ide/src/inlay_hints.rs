@@ -1,3 +1,9 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt,
+    hash::{Hash, Hasher},
+};
+
 use either::Either;
 use hir::{known, Callable, HasVisibility, HirDisplay, Mutability, Semantics, TypeInfo};
 use ide_db::{
@@ -11,6 +17,7 @@ use syntax::{
     match_ast, Direction, NodeOrToken, SmolStr, SyntaxKind, SyntaxNode, SyntaxToken, TextRange,
     TextSize, T,
 };
+use text_edit::TextEdit;
 
 use crate::FileId;
 
@@ -20,7 +27,9 @@ pub struct InlayHintsConfig {
     pub type_hints: bool,
     pub parameter_hints: bool,
     pub chaining_hints: bool,
+    pub generic_arg_hints: GenericArgHints,
     pub reborrow_hints: ReborrowHints,
+    pub receiver_borrow_hints: ReborrowHints,
     pub closure_return_type_hints: bool,
     pub binding_mode_hints: bool,
     pub lifetime_elision_hints: LifetimeElisionHints,
@@ -29,6 +38,7 @@ pub struct InlayHintsConfig {
     pub hide_closure_initialization_hints: bool,
     pub max_length: Option<usize>,
     pub closing_brace_hints_min_lines: Option<usize>,
+    pub fields_to_resolve: InlayFieldsToResolve,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -45,16 +55,48 @@ pub enum ReborrowHints {
     Never,
 }
 
+/// Which kinds of calls get hints for their inferred generic arguments.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GenericArgHints {
+    /// `iter.collect()` becomes `iter.collect::<Vec<i32>>()`.
+    pub method_calls: bool,
+    /// `Vec::new()` becomes `Vec::<i32>::new()`, `mem::take(x)` becomes `mem::take::<i32>(x)`.
+    pub path_calls: bool,
+    /// `Default::default()` becomes `<Vec<i32> as Default>::default()`.
+    pub trait_calls: bool,
+}
+
+/// Which parts of an inlay hint are left out of the initial response, to be computed by
+/// `inlay_hints_resolve` when the client asks for them.
 #[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InlayFieldsToResolve {
+    pub resolve_text_edits: bool,
+    pub resolve_label_location: bool,
+    pub resolve_tooltip: bool,
+}
+
+impl InlayFieldsToResolve {
+    pub const fn empty() -> Self {
+        InlayFieldsToResolve {
+            resolve_text_edits: false,
+            resolve_label_location: false,
+            resolve_tooltip: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum InlayKind {
     BindingModeHint,
     ChainingHint,
     ClosingBraceHint,
     ClosureReturnTypeHint,
+    GenericArgListHint,
     GenericParamListHint,
     ImplicitReborrowHint,
     LifetimeHint,
     ParameterHint,
+    ReceiverBorrowHint,
     TypeHint,
 }
 
@@ -62,11 +104,78 @@ pub enum InlayKind {
 pub struct InlayHint {
     pub range: TextRange,
     pub kind: InlayKind,
-    pub label: String,
+    pub label: InlayHintLabel,
     pub tooltip: Option<InlayTooltip>,
+    /// An edit that writes the hint out into the source code, e.g. an inferred type annotation.
+    pub text_edit: Option<TextEdit>,
+    /// Whether some of the fields listed in [`InlayFieldsToResolve`] were left out.
+    pub needs_resolve: bool,
 }
 
-#[derive(Debug)]
+impl InlayHint {
+    /// Identifies the hint across requests, ignoring all fields that are resolved lazily.
+    pub fn resolve_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.range.hash(&mut hasher);
+        self.kind.hash(&mut hasher);
+        self.label.to_string().hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct InlayHintLabel {
+    pub parts: Vec<InlayHintLabelPart>,
+}
+
+impl InlayHintLabel {
+    pub fn simple(text: impl Into<String>, linked_location: Option<FileRange>) -> InlayHintLabel {
+        InlayHintLabel { parts: vec![InlayHintLabelPart { text: text.into(), linked_location }] }
+    }
+
+    pub fn as_simple_str(&self) -> Option<&str> {
+        match &*self.parts {
+            [part] if part.linked_location.is_none() => Some(&part.text),
+            _ => None,
+        }
+    }
+}
+
+impl From<String> for InlayHintLabel {
+    fn from(text: String) -> InlayHintLabel {
+        InlayHintLabel::simple(text, None)
+    }
+}
+
+impl From<&str> for InlayHintLabel {
+    fn from(text: &str) -> InlayHintLabel {
+        InlayHintLabel::simple(text, None)
+    }
+}
+
+impl fmt::Display for InlayHintLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.parts.iter().try_for_each(|part| f.write_str(&part.text))
+    }
+}
+
+impl fmt::Debug for InlayHintLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.as_simple_str() {
+            Some(text) => text.fmt(f),
+            None => f.debug_list().entries(&self.parts).finish(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InlayHintLabelPart {
+    pub text: String,
+    /// Source location the part refers to, used by clients for hovers and go to definition.
+    pub linked_location: Option<FileRange>,
+}
+
+#[derive(Clone, Debug)]
 pub enum InlayTooltip {
     String(String),
     HoverRanged(FileId, TextRange),
@@ -89,6 +198,8 @@ pub enum InlayTooltip {
 // * return types of closure expressions with blocks
 // * elided lifetimes
 // * compiler inserted reborrows
+// * how `self` is borrowed by method calls
+// * inferred generic arguments of calls
 //
 // |===
 // | Editor  | Action Name
@@ -122,9 +233,31 @@ pub(crate) fn inlay_hints(
         None => file.descendants().for_each(hints),
     };
 
+    if config.fields_to_resolve.resolve_tooltip {
+        acc.iter_mut().filter(|hint| hint.tooltip.is_some()).for_each(|hint| {
+            hint.tooltip = None;
+            hint.needs_resolve = true;
+        });
+    }
+
     acc
 }
 
+pub(crate) fn inlay_hints_resolve(
+    db: &RootDatabase,
+    file_id: FileId,
+    resolve_range: TextRange,
+    hash: u64,
+    config: &InlayHintsConfig,
+) -> Option<InlayHint> {
+    let _p = profile::span("inlay_hints_resolve");
+    let config =
+        InlayHintsConfig { fields_to_resolve: InlayFieldsToResolve::empty(), ..config.clone() };
+    inlay_hints(db, file_id, Some(FileRange { file_id, range: resolve_range }), &config)
+        .into_iter()
+        .find(|hint| hint.resolve_hash() == hash)
+}
+
 fn hints(
     hints: &mut Vec<InlayHint>,
     sema: &Semantics<RootDatabase>,
@@ -142,9 +275,11 @@ fn hints(
         match node {
             ast::Expr(expr) => {
                 chaining_hints(hints, sema, &famous_defs, config, file_id, &expr);
+                generic_arg_hints(hints, sema, config, file_id, &expr);
                 match expr {
                     ast::Expr::CallExpr(it) => param_name_hints(hints, sema, config, ast::Expr::from(it)),
                     ast::Expr::MethodCallExpr(it) => {
+                        receiver_borrow_hints(hints, sema, config, &it);
                         param_name_hints(hints, sema, config, ast::Expr::from(it))
                     }
                     ast::Expr::ClosureExpr(it) => closure_ret_hints(hints, sema, &famous_defs, config, file_id, it),
@@ -176,10 +311,10 @@ fn closing_brace_hints(
 ) -> Option<()> {
     let min_lines = config.closing_brace_hints_min_lines?;
 
-    let name = |it: ast::Name| it.syntax().text_range().start();
+    let name = |it: ast::Name| it.syntax().text_range();
 
     let mut closing_token;
-    let (label, name_offset) = if let Some(item_list) = ast::AssocItemList::cast(node.clone()) {
+    let (label, name_range) = if let Some(item_list) = ast::AssocItemList::cast(node.clone()) {
         closing_token = item_list.r_curly_token()?;
 
         let parent = item_list.syntax().parent()?;
@@ -237,7 +372,7 @@ fn closing_brace_hints(
 
         (
             format!("{}!", mac.path()?),
-            mac.path().and_then(|it| it.segment()).map(|it| it.syntax().text_range().start()),
+            mac.path().and_then(|it| it.segment()).map(|it| it.syntax().text_range()),
         )
     } else {
         return None;
@@ -262,11 +397,16 @@ fn closing_brace_hints(
         return None;
     }
 
+    let resolve_location = config.fields_to_resolve.resolve_label_location;
+    let linked_location =
+        name_range.filter(|_| !resolve_location).map(|range| FileRange { file_id, range });
     acc.push(InlayHint {
         range: closing_token.text_range(),
         kind: InlayKind::ClosingBraceHint,
-        label,
-        tooltip: name_offset.map(|it| InlayTooltip::HoverOffset(file_id, it)),
+        label: InlayHintLabel::simple(label, linked_location),
+        tooltip: name_range.map(|it| InlayTooltip::HoverOffset(file_id, it.start())),
+        text_edit: None,
+        needs_resolve: resolve_location && name_range.is_some(),
     });
 
     None
@@ -281,11 +421,13 @@ fn lifetime_fn_hints(
         return None;
     }
 
-    let mk_lt_hint = |t: SyntaxToken, label| InlayHint {
+    let mk_lt_hint = |t: SyntaxToken, label: String| InlayHint {
         range: t.text_range(),
         kind: InlayKind::LifetimeHint,
-        label,
+        label: label.into(),
         tooltip: Some(InlayTooltip::String("Elided lifetime".into())),
+        text_edit: None,
+        needs_resolve: false,
     };
 
     let param_list = func.param_list()?;
@@ -438,8 +580,11 @@ fn lifetime_fn_hints(
                     "{}{}",
                     allocated_lifetimes.iter().format(", "),
                     if is_empty { "" } else { ", " }
-                ),
+                )
+                .into(),
                 tooltip: Some(InlayTooltip::String("Elided lifetimes".into())),
+                text_edit: None,
+                needs_resolve: false,
             });
         }
         (None, allocated_lifetimes) => acc.push(InlayHint {
@@ -447,6 +592,8 @@ fn lifetime_fn_hints(
             kind: InlayKind::GenericParamListHint,
             label: format!("<{}>", allocated_lifetimes.iter().format(", "),).into(),
             tooltip: Some(InlayTooltip::String("Elided lifetimes".into())),
+            text_edit: None,
+            needs_resolve: false,
         }),
     }
     Some(())
@@ -475,18 +622,27 @@ fn closure_ret_hints(
     let param_list = closure.param_list()?;
 
     let closure = sema.descend_node_into_attributes(closure.clone()).pop()?;
-    let ty = sema.type_of_expr(&ast::Expr::ClosureExpr(closure))?.adjusted();
+    let ty = sema.type_of_expr(&ast::Expr::ClosureExpr(closure.clone()))?.adjusted();
     let callable = ty.as_callable(sema.db)?;
     let ty = callable.return_type();
     if ty.is_unit() {
         return None;
     }
+    let resolve_edit = config.fields_to_resolve.resolve_text_edits;
+    let text_edit = if resolve_edit {
+        None
+    } else {
+        ty_to_text_edit(sema, closure.syntax(), &ty, param_list.syntax().text_range().end(), " -> ")
+    };
     acc.push(InlayHint {
         range: param_list.syntax().text_range(),
         kind: InlayKind::ClosureReturnTypeHint,
         label: hint_iterator(sema, &famous_defs, config, &ty)
-            .unwrap_or_else(|| ty.display_truncated(sema.db, config.max_length).to_string()),
+            .unwrap_or_else(|| ty.display_truncated(sema.db, config.max_length).to_string())
+            .into(),
         tooltip: Some(InlayTooltip::HoverRanged(file_id, param_list.syntax().text_range())),
+        text_edit,
+        needs_resolve: resolve_edit,
     });
     Some(())
 }
@@ -512,8 +668,54 @@ fn reborrow_hints(
     acc.push(InlayHint {
         range: expr.syntax().text_range(),
         kind: InlayKind::ImplicitReborrowHint,
-        label: label.to_string(),
+        label: label.into(),
         tooltip: Some(InlayTooltip::String("Compiler inserted reborrow".into())),
+        text_edit: None,
+        needs_resolve: false,
+    });
+    Some(())
+}
+
+fn receiver_borrow_hints(
+    acc: &mut Vec<InlayHint>,
+    sema: &Semantics<RootDatabase>,
+    config: &InlayHintsConfig,
+    call: &ast::MethodCallExpr,
+) -> Option<()> {
+    if config.receiver_borrow_hints == ReborrowHints::Never {
+        return None;
+    }
+
+    let receiver = call.receiver()?;
+    let descended = sema.descend_node_into_attributes(call.clone()).pop();
+    let desc_receiver = descended.as_ref().unwrap_or(call).receiver()?;
+    // Receivers that already are references are reborrowed, not borrowed.
+    let receiver_ty = sema.type_of_expr(&desc_receiver)?.original;
+    if receiver_ty.is_reference() || receiver_ty.is_unknown() {
+        return None;
+    }
+    // The autoref of method resolution, which may come after some autoderef steps.
+    let adjustments = sema.expr_adjustments(&desc_receiver)?;
+    let derefs = adjustments.iter().filter(|it| matches!(it.kind, hir::Adjust::Deref(_))).count();
+    let mutability = adjustments.iter().find_map(|it| match it.kind {
+        hir::Adjust::Borrow(hir::AutoBorrow::Ref(mutability)) => Some(mutability),
+        _ => None,
+    })?;
+    let label = match mutability {
+        hir::Mutability::Shared if config.receiver_borrow_hints != ReborrowHints::MutableOnly => {
+            format!("&{}", "*".repeat(derefs))
+        }
+        hir::Mutability::Mut if derefs == 0 => "&mut".to_string(),
+        hir::Mutability::Mut => format!("&mut {}", "*".repeat(derefs)),
+        _ => return None,
+    };
+    acc.push(InlayHint {
+        range: receiver.syntax().text_range(),
+        kind: InlayKind::ReceiverBorrowHint,
+        label: label.into(),
+        tooltip: Some(InlayTooltip::String("Receiver borrowed by method call".into())),
+        text_edit: None,
+        needs_resolve: false,
     });
     Some(())
 }
@@ -570,10 +772,12 @@ fn chaining_hints(
             acc.push(InlayHint {
                 range: expr.syntax().text_range(),
                 kind: InlayKind::ChainingHint,
-                label: hint_iterator(sema, &famous_defs, config, &ty).unwrap_or_else(|| {
-                    ty.display_truncated(sema.db, config.max_length).to_string()
-                }),
+                label: hint_iterator(sema, &famous_defs, config, &ty)
+                    .unwrap_or_else(|| ty.display_truncated(sema.db, config.max_length).to_string())
+                    .into(),
                 tooltip: Some(InlayTooltip::HoverRanged(file_id, expr.syntax().text_range())),
+                text_edit: None,
+                needs_resolve: false,
             });
         }
     }
@@ -621,11 +825,14 @@ fn param_name_hints(
                 }
             }
 
+            let resolve_location = config.fields_to_resolve.resolve_label_location;
             InlayHint {
                 range,
                 kind: InlayKind::ParameterHint,
-                label: param_name,
+                label: InlayHintLabel::simple(param_name, tooltip.filter(|_| !resolve_location)),
                 tooltip: tooltip.map(|it| InlayTooltip::HoverOffset(it.file_id, it.range.start())),
+                text_edit: None,
+                needs_resolve: resolve_location && tooltip.is_some(),
             }
         });
 
@@ -633,6 +840,115 @@ fn param_name_hints(
     Some(())
 }
 
+fn generic_arg_hints(
+    acc: &mut Vec<InlayHint>,
+    sema: &Semantics<RootDatabase>,
+    config: &InlayHintsConfig,
+    file_id: FileId,
+    expr: &ast::Expr,
+) -> Option<()> {
+    let GenericArgHints { method_calls, path_calls, trait_calls } = config.generic_arg_hints;
+    if !(method_calls || path_calls || trait_calls) {
+        return None;
+    }
+
+    let descended = sema.descend_node_into_attributes(expr.clone()).pop();
+    let desc_expr = descended.as_ref().unwrap_or(expr);
+    let tooltip = Some(InlayTooltip::HoverRanged(file_id, expr.syntax().text_range()));
+    let render = |types: Vec<hir::Type>| -> Option<String> {
+        if types.is_empty() || types.iter().any(|ty| ty.is_unknown()) {
+            return None;
+        }
+        Some(types.iter().map(|ty| ty.display_truncated(sema.db, config.max_length)).join(", "))
+    };
+
+    match (expr, desc_expr) {
+        (ast::Expr::MethodCallExpr(call), ast::Expr::MethodCallExpr(desc_call)) => {
+            if !method_calls || call.generic_arg_list().is_some() {
+                return None;
+            }
+            let callable = sema.resolve_method_call_as_callable(desc_call)?;
+            let label = render(callable.type_arguments(sema.db)?)?;
+            acc.push(InlayHint {
+                range: call.name_ref()?.syntax().text_range(),
+                kind: InlayKind::GenericArgListHint,
+                label: format!("::<{}>", label).into(),
+                tooltip,
+                text_edit: None,
+                needs_resolve: false,
+            });
+        }
+        (ast::Expr::CallExpr(call), ast::Expr::CallExpr(desc_call)) => {
+            let path = match call.expr()? {
+                ast::Expr::PathExpr(it) => it.path()?,
+                _ => return None,
+            };
+            let callable = sema.type_of_expr(&desc_call.expr()?)?.original.as_callable(sema.db)?;
+
+            let segment = path.segment()?;
+            if path_calls && segment.generic_arg_list().is_none() {
+                if let Some(label) = callable.type_arguments(sema.db).and_then(render) {
+                    acc.push(InlayHint {
+                        range: segment.syntax().text_range(),
+                        kind: InlayKind::GenericArgListHint,
+                        label: format!("::<{}>", label).into(),
+                        tooltip: tooltip.clone(),
+                        text_edit: None,
+                        needs_resolve: false,
+                    });
+                }
+            }
+
+            let qualifier = path.qualifier()?;
+            let qualifier_segment = qualifier.segment()?;
+            if qualifier_segment.generic_arg_list().is_some() {
+                return None;
+            }
+            match sema.resolve_path(&qualifier)? {
+                hir::PathResolution::Def(hir::ModuleDef::Adt(_)) if path_calls => {
+                    let self_ty = callable.self_type(sema.db)?;
+                    let label = render(self_ty.type_arguments().collect())?;
+                    acc.push(InlayHint {
+                        range: qualifier_segment.syntax().text_range(),
+                        kind: InlayKind::GenericArgListHint,
+                        label: format!("::<{}>", label).into(),
+                        tooltip,
+                        text_edit: None,
+                        needs_resolve: false,
+                    });
+                }
+                hir::PathResolution::Def(hir::ModuleDef::Trait(_))
+                    if trait_calls && qualifier_segment.name_ref().is_some() =>
+                {
+                    let self_ty = callable.self_type(sema.db)?;
+                    let label = render(vec![self_ty])?;
+                    let qualifier_range = qualifier.syntax().text_range();
+                    // The opening half goes in front of the path, so it gets an empty range there.
+                    acc.push(InlayHint {
+                        range: TextRange::empty(qualifier_range.start()),
+                        kind: InlayKind::GenericArgListHint,
+                        label: format!("<{} as ", label).into(),
+                        tooltip: tooltip.clone(),
+                        text_edit: None,
+                        needs_resolve: false,
+                    });
+                    acc.push(InlayHint {
+                        range: qualifier_range,
+                        kind: InlayKind::GenericArgListHint,
+                        label: ">".into(),
+                        tooltip,
+                        text_edit: None,
+                        needs_resolve: false,
+                    });
+                }
+                _ => (),
+            }
+        }
+        _ => (),
+    }
+    Some(())
+}
+
 fn binding_mode_hints(
     acc: &mut Vec<InlayHint>,
     sema: &Semantics<RootDatabase>,
@@ -655,8 +971,10 @@ fn binding_mode_hints(
         acc.push(InlayHint {
             range,
             kind: InlayKind::BindingModeHint,
-            label: r.to_string(),
+            label: r.into(),
             tooltip: Some(InlayTooltip::String("Inferred binding mode".into())),
+            text_edit: None,
+            needs_resolve: false,
         });
     });
     match pat {
@@ -670,8 +988,10 @@ fn binding_mode_hints(
             acc.push(InlayHint {
                 range,
                 kind: InlayKind::BindingModeHint,
-                label: bm.to_string(),
+                label: bm.into(),
                 tooltip: Some(InlayTooltip::String("Inferred binding mode".into())),
+                text_edit: None,
+                needs_resolve: false,
             });
         }
         _ => (),
@@ -716,22 +1036,57 @@ fn bind_pat_hints(
         }
     };
 
+    // Only bindings that make up the whole pattern of a `let` or closure parameter can be
+    // annotated with a type directly.
+    let annotatable = pat.syntax().parent().map_or(false, |it| {
+        ast::LetStmt::can_cast(it.kind())
+            || ast::Param::cast(it).map_or(false, |it| {
+                it.syntax()
+                    .ancestors()
+                    .nth(2)
+                    .map_or(false, |it| ast::ClosureExpr::can_cast(it.kind()))
+            })
+    });
+    let resolve_edit = config.fields_to_resolve.resolve_text_edits;
+    let text_edit = if annotatable && !resolve_edit {
+        ty_to_text_edit(sema, desc_pat.syntax(), &ty, pat.syntax().text_range().end(), ": ")
+    } else {
+        None
+    };
+
     acc.push(InlayHint {
         range: match pat.name() {
             Some(name) => name.syntax().text_range(),
             None => pat.syntax().text_range(),
         },
         kind: InlayKind::TypeHint,
-        label,
+        label: label.into(),
         tooltip: pat
             .name()
             .map(|it| it.syntax().text_range())
             .map(|it| InlayTooltip::HoverRanged(file_id, it)),
+        text_edit,
+        needs_resolve: annotatable && resolve_edit,
     });
 
     Some(())
 }
 
+/// Renders `ty` as source code for an edit that turns the hint into an actual annotation.
+fn ty_to_text_edit(
+    sema: &Semantics<RootDatabase>,
+    node_for_scope: &SyntaxNode,
+    ty: &hir::Type,
+    offset: TextSize,
+    prefix: &str,
+) -> Option<TextEdit> {
+    let module = sema.scope(node_for_scope)?.module();
+    let rendered = ty.display_source_code(sema.db, module.into()).ok()?;
+    let mut builder = TextEdit::builder();
+    builder.insert(offset, format!("{}{}", prefix, rendered));
+    Some(builder.finish())
+}
+
 fn is_named_constructor(
     sema: &Semantics<RootDatabase>,
     pat: &ast::IdentPat,
@@ -1089,7 +1444,7 @@ mod tests {
     use syntax::{TextRange, TextSize};
     use test_utils::extract_annotations;
 
-    use crate::inlay_hints::ReborrowHints;
+    use crate::inlay_hints::{GenericArgHints, InlayFieldsToResolve, InlayKind, ReborrowHints};
     use crate::{fixture, inlay_hints::InlayHintsConfig, LifetimeElisionHints};
 
     const DISABLED_CONFIG: InlayHintsConfig = InlayHintsConfig {
@@ -1097,15 +1452,22 @@ mod tests {
         type_hints: false,
         parameter_hints: false,
         chaining_hints: false,
+        generic_arg_hints: GenericArgHints {
+            method_calls: false,
+            path_calls: false,
+            trait_calls: false,
+        },
         lifetime_elision_hints: LifetimeElisionHints::Never,
         closure_return_type_hints: false,
         reborrow_hints: ReborrowHints::Always,
+        receiver_borrow_hints: ReborrowHints::Never,
         binding_mode_hints: false,
         hide_named_constructor_hints: false,
         hide_closure_initialization_hints: false,
         param_names_for_lifetime_elision_hints: false,
         max_length: None,
         closing_brace_hints_min_lines: None,
+        fields_to_resolve: InlayFieldsToResolve::empty(),
     };
     const TEST_CONFIG: InlayHintsConfig = InlayHintsConfig {
         type_hints: true,
@@ -2121,6 +2483,294 @@ fn main() {
         );
     }
 
+    // Generic argument hint tests
+
+    #[track_caller]
+    fn check_generic_args(ra_fixture: &str) {
+        check_with_config(
+            InlayHintsConfig {
+                generic_arg_hints: GenericArgHints {
+                    method_calls: true,
+                    path_calls: true,
+                    trait_calls: false,
+                },
+                ..DISABLED_CONFIG
+            },
+            ra_fixture,
+        );
+    }
+
+    #[test]
+    fn generic_arg_hints_method_calls() {
+        check_generic_args(
+            r#"
+//- minicore: from
+struct Wrapper<T>(T);
+impl<T> Wrapper<T> {
+    fn convert<U: From<T>>(self) -> U { loop {} }
+    fn convert_explicit<U>(self) -> U { loop {} }
+}
+struct Foo;
+impl From<u32> for Foo { fn from(_: u32) -> Foo { Foo } }
+
+fn main() {
+    let foo: Foo = Wrapper(0u32).convert();
+                               //^^^^^^^ ::<Foo>
+    let foo = Wrapper(0u32).convert_explicit::<Foo>();
+}"#,
+        );
+    }
+
+    #[test]
+    fn generic_arg_hints_path_calls() {
+        check_generic_args(
+            r#"
+struct Vec<T>(T);
+impl<T> Vec<T> {
+    fn new() -> Self { loop {} }
+}
+fn take<T, U>(_: T) -> U { loop {} }
+fn with_impl<T>(_: impl Sized) -> T { loop {} }
+
+fn main() {
+    let v: Vec<u8> = Vec::new();
+                   //^^^ ::<u8>
+    let v: Vec<u8> = Vec::<u8>::new();
+    let s: bool = take(0u32);
+                //^^^^ ::<u32, bool>
+    let s: bool = take::<u32, _>(0u32);
+    let s: bool = with_impl(0u32);
+                //^^^^^^^^^ ::<bool>
+    let s = take(0u32);
+}"#,
+        );
+    }
+
+    #[test]
+    fn generic_arg_hints_trait_calls() {
+        check_expect(
+            InlayHintsConfig {
+                generic_arg_hints: GenericArgHints {
+                    method_calls: false,
+                    path_calls: false,
+                    trait_calls: true,
+                },
+                ..DISABLED_CONFIG
+            },
+            r#"
+//- minicore: default
+struct Foo;
+impl Default for Foo { fn default() -> Self { Foo } }
+
+fn main() {
+    let foo: Foo = Default::default();
+    let foo: Foo = <Foo as Default>::default();
+}"#,
+            expect![[r#"
+                [
+                    InlayHint {
+                        range: 98..98,
+                        kind: GenericArgListHint,
+                        label: "<Foo as ",
+                        tooltip: Some(
+                            HoverRanged(
+                                FileId(
+                                    0,
+                                ),
+                                98..116,
+                            ),
+                        ),
+                        text_edit: None,
+                        needs_resolve: false,
+                    },
+                    InlayHint {
+                        range: 98..105,
+                        kind: GenericArgListHint,
+                        label: ">",
+                        tooltip: Some(
+                            HoverRanged(
+                                FileId(
+                                    0,
+                                ),
+                                98..116,
+                            ),
+                        ),
+                        text_edit: None,
+                        needs_resolve: false,
+                    },
+                ]
+            "#]],
+        );
+    }
+
+    // Receiver borrow hint tests
+
+    #[test]
+    fn receiver_borrow_hints() {
+        check_with_config(
+            InlayHintsConfig { receiver_borrow_hints: ReborrowHints::Always, ..DISABLED_CONFIG },
+            r#"
+//- minicore: deref
+struct Foo;
+impl Foo {
+    fn by_ref(&self) {}
+    fn by_mut(&mut self) {}
+    fn by_val(self) {}
+}
+trait ByValOnRef {
+    fn by_val_on_ref(self);
+}
+impl ByValOnRef for &Foo {
+    fn by_val_on_ref(self) {}
+}
+struct Wrapper(Foo);
+impl core::ops::Deref for Wrapper {
+    type Target = Foo;
+    fn deref(&self) -> &Foo { &self.0 }
+}
+
+fn main() {
+    let mut foo = Foo;
+    foo.by_ref();
+  //^^^ &
+    foo.by_mut();
+  //^^^ &mut
+    foo.by_val_on_ref();
+  //^^^ &
+    (&foo).by_ref();
+    let foo_ref = &mut foo;
+    foo_ref.by_mut();
+    let wrapper = Wrapper(Foo);
+    wrapper.by_ref();
+  //^^^^^^^ &*
+    foo.by_val();
+}"#,
+        );
+    }
+
+    #[test]
+    fn receiver_borrow_hints_mutable_only() {
+        check_with_config(
+            InlayHintsConfig {
+                receiver_borrow_hints: ReborrowHints::MutableOnly,
+                ..DISABLED_CONFIG
+            },
+            r#"
+struct Foo;
+impl Foo {
+    fn by_ref(&self) {}
+    fn by_mut(&mut self) {}
+}
+
+fn main() {
+    let mut foo = Foo;
+    foo.by_ref();
+    foo.by_mut();
+  //^^^ &mut
+}"#,
+        );
+    }
+
+    // Lazy resolution tests
+
+    #[track_caller]
+    fn check_edit(config: InlayHintsConfig, ra_fixture: &str, expect: Expect) {
+        let (analysis, file_id) = fixture::file(ra_fixture);
+        let inlay_hints = analysis.inlay_hints(&config, file_id, None).unwrap();
+
+        let edits =
+            inlay_hints.into_iter().filter_map(|hint| hint.text_edit).reduce(|mut acc, next| {
+                acc.union(next).expect("merging text edits failed");
+                acc
+            });
+
+        let mut actual = analysis.file_text(file_id).unwrap().to_string();
+        if let Some(edit) = edits {
+            edit.apply(&mut actual);
+        }
+        expect.assert_eq(&actual);
+    }
+
+    #[test]
+    fn type_hints_text_edits() {
+        check_edit(
+            InlayHintsConfig {
+                type_hints: true,
+                closure_return_type_hints: true,
+                ..DISABLED_CONFIG
+            },
+            r#"
+struct Foo<T>(T);
+fn main() {
+    let foo = Foo(0u32);
+    let (a, b) = (Foo(0u8), 1);
+    let closure = |x| {
+        Foo(x)
+    };
+    closure(1u32);
+}
+"#,
+            expect![[r#"
+                struct Foo<T>(T);
+                fn main() {
+                    let foo: Foo<u32> = Foo(0u32);
+                    let (a, b) = (Foo(0u8), 1);
+                    let closure = |x: u32| -> Foo<u32> {
+                        Foo(x)
+                    };
+                    closure(1u32);
+                }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn lazy_fields_are_resolved() {
+        let config = InlayHintsConfig {
+            type_hints: true,
+            parameter_hints: true,
+            fields_to_resolve: InlayFieldsToResolve {
+                resolve_text_edits: true,
+                resolve_label_location: true,
+                resolve_tooltip: true,
+            },
+            ..DISABLED_CONFIG
+        };
+        let (analysis, file_id) = fixture::file(
+            r#"
+fn foo(param: u32) -> u32 { param }
+fn main() {
+    let x = foo(1);
+}
+"#,
+        );
+        let full_range = TextRange::up_to(TextSize::of(&*analysis.file_text(file_id).unwrap()));
+        let range = Some(FileRange { file_id, range: full_range });
+        let hints = analysis.inlay_hints(&config, file_id, range).unwrap();
+        assert_eq!(hints.len(), 2);
+        for hint in hints {
+            assert!(hint.needs_resolve);
+            assert!(hint.text_edit.is_none());
+            assert!(hint.tooltip.is_none());
+            assert!(hint.label.as_simple_str().is_some());
+
+            let resolved = analysis
+                .inlay_hints_resolve(&config, file_id, full_range, hint.resolve_hash())
+                .unwrap()
+                .unwrap();
+            assert!(!resolved.needs_resolve);
+            assert_eq!(resolved.label.to_string(), hint.label.to_string());
+            assert!(resolved.tooltip.is_some());
+            match resolved.kind {
+                InlayKind::TypeHint => assert!(resolved.text_edit.is_some()),
+                InlayKind::ParameterHint => {
+                    assert!(resolved.label.parts[0].linked_location.is_some())
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
     // Chaining hint tests
 
     #[test]
@@ -2155,6 +2805,8 @@ fn main() {
                                 147..172,
                             ),
                         ),
+                        text_edit: None,
+                        needs_resolve: false,
                     },
                     InlayHint {
                         range: 147..154,
@@ -2168,6 +2820,8 @@ fn main() {
                                 147..154,
                             ),
                         ),
+                        text_edit: None,
+                        needs_resolve: false,
                     },
                 ]
             "#]],
@@ -2226,6 +2880,8 @@ fn main() {
                                 143..190,
                             ),
                         ),
+                        text_edit: None,
+                        needs_resolve: false,
                     },
                     InlayHint {
                         range: 143..179,
@@ -2239,6 +2895,8 @@ fn main() {
                                 143..179,
                             ),
                         ),
+                        text_edit: None,
+                        needs_resolve: false,
                     },
                 ]
             "#]],
@@ -2282,6 +2940,8 @@ fn main() {
                                 246..283,
                             ),
                         ),
+                        text_edit: None,
+                        needs_resolve: false,
                     },
                     InlayHint {
                         range: 246..265,
@@ -2295,6 +2955,8 @@ fn main() {
                                 246..265,
                             ),
                         ),
+                        text_edit: None,
+                        needs_resolve: false,
                     },
                 ]
             "#]],
@@ -2340,6 +3002,8 @@ fn main() {
                                 174..241,
                             ),
                         ),
+                        text_edit: None,
+                        needs_resolve: false,
                     },
                     InlayHint {
                         range: 174..224,
@@ -2353,6 +3017,8 @@ fn main() {
                                 174..224,
                             ),
                         ),
+                        text_edit: None,
+                        needs_resolve: false,
                     },
                     InlayHint {
                         range: 174..206,
@@ -2366,6 +3032,8 @@ fn main() {
                                 174..206,
                             ),
                         ),
+                        text_edit: None,
+                        needs_resolve: false,
                     },
                     InlayHint {
                         range: 174..189,
@@ -2379,6 +3047,8 @@ fn main() {
                                 174..189,
                             ),
                         ),
+                        text_edit: None,
+                        needs_resolve: false,
                     },
                 ]
             "#]],
@@ -2421,6 +3091,17 @@ fn main() {
                                 124..130,
                             ),
                         ),
+                        text_edit: Some(
+                            TextEdit {
+                                indels: [
+                                    Indel {
+                                        insert: ": Struct",
+                                        delete: 130..130,
+                                    },
+                                ],
+                            },
+                        ),
+                        needs_resolve: false,
                     },
                     InlayHint {
                         range: 145..185,
@@ -2434,6 +3115,8 @@ fn main() {
                                 145..185,
                             ),
                         ),
+                        text_edit: None,
+                        needs_resolve: false,
                     },
                     InlayHint {
                         range: 145..168,
@@ -2447,11 +3130,25 @@ fn main() {
                                 145..168,
                             ),
                         ),
+                        text_edit: None,
+                        needs_resolve: false,
                     },
                     InlayHint {
                         range: 222..228,
                         kind: ParameterHint,
-                        label: "self",
+                        label: [
+                            InlayHintLabelPart {
+                                text: "self",
+                                linked_location: Some(
+                                    FileRange {
+                                        file_id: FileId(
+                                            0,
+                                        ),
+                                        range: 42..46,
+                                    },
+                                ),
+                            },
+                        ],
                         tooltip: Some(
                             HoverOffset(
                                 FileId(
@@ -2460,6 +3157,8 @@ fn main() {
                                 42,
                             ),
                         ),
+                        text_edit: None,
+                        needs_resolve: false,
                     },
                 ]
             "#]],
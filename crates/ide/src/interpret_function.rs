@@ -0,0 +1,89 @@
+use hir::{Function, MirEvalError, Semantics};
+use ide_db::base_db::FilePosition;
+use ide_db::RootDatabase;
+use syntax::{algo::find_node_at_offset, ast, AstNode};
+
+// Feature: Interpret Function
+//
+// Runs the function containing the cursor in rust-analyzer's MIR interpreter and shows the value
+// it returns or the message it panics with. Only functions without parameters can be run.
+//
+// |===
+// | Editor  | Action Name
+//
+// | VS Code | **Rust Analyzer: Interpret Function**
+// |===
+pub(crate) fn interpret_function(db: &RootDatabase, position: FilePosition) -> String {
+    let sema = Semantics::new(db);
+    let source_file = sema.parse(position.file_id);
+    let function = find_node_at_offset::<ast::Fn>(source_file.syntax(), position.offset)
+        .and_then(|it| sema.to_def(&it));
+    let function: Function = match function {
+        Some(it) => it,
+        None => return "Not inside a function".to_string(),
+    };
+    let name = function.name(db);
+    if !function.assoc_fn_params(db).is_empty() {
+        return format!(
+            "`{}` takes parameters, only functions without parameters can be interpreted",
+            name
+        );
+    }
+    match function.eval(db) {
+        Ok(value) => format!("`{}` returned {}", name, value),
+        Err(MirEvalError::Panic(message)) => format!("`{}` panicked: {}", name, message),
+        Err(e) => format!("Failed to interpret `{}`: {:?}", name, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+
+    use crate::fixture;
+
+    fn check(ra_fixture: &str, expect: Expect) {
+        let (analysis, position) = fixture::position(ra_fixture);
+        expect.assert_eq(&analysis.interpret_function(position).unwrap());
+    }
+
+    #[test]
+    fn interpret_function() {
+        check(
+            r#"
+fn square(x: i32) -> i32 { x * x }
+fn ma$0in() -> (i32, bool) {
+    (square(4), true)
+}
+"#,
+            expect![[r#"`main` returned (16, true)"#]],
+        );
+    }
+
+    #[test]
+    fn interpret_panicking_function() {
+        check(
+            r#"
+fn main() -> u32 {
+    let zero = 0;
+    1 / ze$0ro
+}
+"#,
+            expect![[r#"`main` panicked: attempt to divide by zero"#]],
+        );
+    }
+
+    #[test]
+    fn interpret_function_with_parameters() {
+        check(
+            r#"
+fn id(x: u32) -> u32 {
+    x$0
+}
+"#,
+            expect![[
+                r#"`id` takes parameters, only functions without parameters can be interpreted"#
+            ]],
+        );
+    }
+}
@@ -25,8 +25,10 @@ mod navigation_target;
 mod annotations;
 mod call_hierarchy;
 mod signature_help;
+mod dependency_tree;
 mod doc_links;
 mod highlight_related;
+mod evaluate_expression;
 mod expand_macro;
 mod extend_selection;
 mod file_structure;
@@ -38,6 +40,7 @@ mod goto_implementation;
 mod goto_type_definition;
 mod hover;
 mod inlay_hints;
+mod interpret_function;
 mod join_lines;
 mod markdown_remove;
 mod matching_brace;
@@ -52,13 +55,16 @@ mod static_index;
 mod status;
 mod syntax_highlighting;
 mod syntax_tree;
+mod test_explorer;
+mod type_hierarchy;
 mod typing;
 mod view_crate_graph;
 mod view_hir;
+mod view_mir;
 mod view_item_tree;
 mod shuffle_crate_graph;
 
-use std::sync::Arc;
+use std::sync::{atomic::AtomicBool, Arc};
 
 use cfg::CfgOptions;
 use ide_db::{
@@ -66,7 +72,7 @@ use ide_db::{
         salsa::{self, ParallelDatabase},
         CrateOrigin, Env, FileLoader, FileSet, SourceDatabase, VfsPath,
     },
-    symbol_index, LineIndexDatabase,
+    symbol_index, FxHashMap, LineIndexDatabase,
 };
 use syntax::SourceFile;
 
@@ -75,17 +81,22 @@ use crate::navigation_target::{ToNav, TryToNav};
 pub use crate::{
     annotations::{Annotation, AnnotationConfig, AnnotationKind},
     call_hierarchy::CallItem,
-    expand_macro::ExpandedMacro,
+    dependency_tree::{CrateDependency, CrateInfo, DependencyTree},
+    doc_links::ExternalDocsConfig,
+    expand_macro::{ExpandedMacro, ExpandedMacroStep, MacroSubInvocation, ProcMacroBacktrace},
     file_structure::{StructureNode, StructureNodeKind},
     folding_ranges::{Fold, FoldKind},
     highlight_related::{HighlightRelatedConfig, HighlightedRange},
     hover::{HoverAction, HoverConfig, HoverDocFormat, HoverGotoTypeData, HoverResult},
     inlay_hints::{
-        InlayHint, InlayHintsConfig, InlayKind, InlayTooltip, LifetimeElisionHints, ReborrowHints,
+        GenericArgHints, InlayFieldsToResolve, InlayHint, InlayHintLabel, InlayHintLabelPart,
+        InlayHintsConfig, InlayKind, InlayTooltip, LifetimeElisionHints, ReborrowHints,
     },
     join_lines::JoinLinesConfig,
     markup::Markup,
-    moniker::{MonikerKind, MonikerResult, PackageInformation},
+    moniker::{
+        MonikerDescriptor, MonikerDescriptorKind, MonikerKind, MonikerResult, PackageInformation,
+    },
     move_item::Direction,
     navigation_target::NavigationTarget,
     prime_caches::ParallelPrimeCachesProgress,
@@ -98,14 +109,16 @@ pub use crate::{
         tags::{Highlight, HlMod, HlMods, HlOperator, HlPunct, HlTag},
         HlRange,
     },
+    test_explorer::{TestItem, TestItemKind},
+    view_crate_graph::{CrateGraphConfig, CrateGraphFormat},
 };
 pub use hir::{Documentation, Semantics};
 pub use ide_assists::{
     Assist, AssistConfig, AssistId, AssistKind, AssistResolveStrategy, SingleResolve,
 };
 pub use ide_completion::{
-    CallableSnippets, CompletionConfig, CompletionItem, CompletionItemKind, CompletionRelevance,
-    Snippet, SnippetScope,
+    CallableSnippets, CompletionConfig, CompletionFieldsToResolve, CompletionItem,
+    CompletionItemKind, CompletionRelevance, Snippet, SnippetScope,
 };
 pub use ide_db::{
     base_db::{
@@ -113,7 +126,7 @@ pub use ide_db::{
         SourceRoot, SourceRootId,
     },
     label::Label,
-    line_index::{LineCol, LineColUtf16, LineIndex},
+    line_index::{LineCol, LineIndex, WideEncoding, WideLineCol},
     search::{ReferenceCategory, SearchScope},
     source_change::{FileSystemEdit, SourceChange},
     symbol_index::Query,
@@ -170,6 +183,17 @@ impl AnalysisHost {
     pub fn per_query_memory_usage(&mut self) -> Vec<(String, profile::Bytes)> {
         self.db.per_query_memory_usage()
     }
+    /// NB: this clears the database
+    pub fn per_query_group_memory_usage(&mut self) -> Vec<(&'static str, profile::Bytes)> {
+        self.db.per_query_group_memory_usage()
+    }
+    /// Evicts caches that are cheap to recompute, see [`RootDatabase::evict_caches`].
+    pub fn evict_caches(
+        &mut self,
+        lru_capacity: Option<usize>,
+    ) -> Vec<(&'static str, profile::Bytes)> {
+        self.db.evict_caches(lru_capacity)
+    }
     pub fn request_cancellation(&mut self) {
         self.db.request_cancellation();
     }
@@ -183,6 +207,17 @@ impl AnalysisHost {
     pub fn shuffle_crate_graph(&mut self) {
         shuffle_crate_graph::shuffle_crate_graph(&mut self.db);
     }
+
+    /// Sets the target `file_id` is analyzed for when the workspace is analyzed for several
+    /// targets, `None` meaning the default one.
+    pub fn set_active_target(&mut self, file_id: FileId, target: Option<String>) {
+        let mut active_targets = FxHashMap::clone(&self.db.active_targets());
+        match target {
+            Some(target) => active_targets.insert(file_id, Arc::from(target)),
+            None => active_targets.remove(&file_id),
+        };
+        self.db.set_active_targets(Arc::new(active_targets));
+    }
 }
 
 impl Default for AnalysisHost {
@@ -244,51 +279,59 @@ impl Analysis {
 
     /// Debug info about the current state of the analysis.
     pub fn status(&self, file_id: Option<FileId>) -> Cancellable<String> {
-        self.with_db(|db| status::status(&*db, file_id))
+        self.with_db("status", |db| status::status(&*db, file_id))
     }
 
-    pub fn parallel_prime_caches<F>(&self, num_worker_threads: u8, cb: F) -> Cancellable<()>
+    /// Primes the caches of the workspace, or only of the given crates. Returns early once
+    /// `cancel` is set.
+    pub fn parallel_prime_caches<F>(
+        &self,
+        num_worker_threads: u8,
+        only: Option<&[CrateId]>,
+        cancel: &AtomicBool,
+        cb: F,
+    ) -> Cancellable<()>
     where
         F: Fn(ParallelPrimeCachesProgress) + Sync + std::panic::UnwindSafe,
     {
-        self.with_db(move |db| prime_caches::parallel_prime_caches(db, num_worker_threads, &cb))
+        self.with_db("parallel_prime_caches", move |db| {
+            prime_caches::parallel_prime_caches(db, num_worker_threads, only, cancel, &cb)
+        })
     }
 
     /// Gets the text of the source file.
     pub fn file_text(&self, file_id: FileId) -> Cancellable<Arc<String>> {
-        self.with_db(|db| db.file_text(file_id))
+        self.with_db("file_text", |db| db.file_text(file_id))
     }
 
     /// Gets the syntax tree of the file.
     pub fn parse(&self, file_id: FileId) -> Cancellable<SourceFile> {
-        self.with_db(|db| db.parse(file_id).tree())
+        self.with_db("parse", |db| db.parse(file_id).tree())
     }
 
     /// Returns true if this file belongs to an immutable library.
     pub fn is_library_file(&self, file_id: FileId) -> Cancellable<bool> {
         use ide_db::base_db::SourceDatabaseExt;
-        self.with_db(|db| db.source_root(db.file_source_root(file_id)).is_library)
+        self.with_db("is_library_file", |db| {
+            db.source_root(db.file_source_root(file_id)).is_library
+        })
     }
 
     /// Gets the file's `LineIndex`: data structure to convert between absolute
     /// offsets and line/column representation.
     pub fn file_line_index(&self, file_id: FileId) -> Cancellable<Arc<LineIndex>> {
-        self.with_db(|db| db.line_index(file_id))
+        self.with_db("file_line_index", |db| db.line_index(file_id))
     }
 
     /// Selects the next syntactic nodes encompassing the range.
     pub fn extend_selection(&self, frange: FileRange) -> Cancellable<TextRange> {
-        self.with_db(|db| extend_selection::extend_selection(db, frange))
+        self.with_db("extend_selection", |db| extend_selection::extend_selection(db, frange))
     }
 
     /// Returns position of the matching brace (all types of braces are
     /// supported).
     pub fn matching_brace(&self, position: FilePosition) -> Cancellable<Option<TextSize>> {
-        self.with_db(|db| {
-            let parse = db.parse(position.file_id);
-            let file = parse.tree();
-            matching_brace::matching_brace(&file, position.offset)
-        })
+        self.with_db("matching_brace", |db| matching_brace::matching_brace(db, position))
     }
 
     /// Returns a syntax tree represented as `String`, for debug purposes.
@@ -298,30 +341,70 @@ impl Analysis {
         file_id: FileId,
         text_range: Option<TextRange>,
     ) -> Cancellable<String> {
-        self.with_db(|db| syntax_tree::syntax_tree(db, file_id, text_range))
+        self.with_db("syntax_tree", |db| syntax_tree::syntax_tree(db, file_id, text_range))
     }
 
     pub fn view_hir(&self, position: FilePosition) -> Cancellable<String> {
-        self.with_db(|db| view_hir::view_hir(db, position))
+        self.with_db("view_hir", |db| view_hir::view_hir(db, position))
+    }
+
+    pub fn view_mir(&self, position: FilePosition) -> Cancellable<String> {
+        self.with_db("view_mir", |db| view_mir::view_mir(db, position))
+    }
+
+    pub fn interpret_function(&self, position: FilePosition) -> Cancellable<String> {
+        self.with_db("interpret_function", |db| {
+            interpret_function::interpret_function(db, position)
+        })
+    }
+
+    pub fn evaluate_expression(&self, frange: FileRange) -> Cancellable<String> {
+        self.with_db("evaluate_expression", |db| {
+            evaluate_expression::evaluate_expression(db, frange)
+        })
     }
 
     pub fn view_item_tree(&self, file_id: FileId) -> Cancellable<String> {
-        self.with_db(|db| view_item_tree::view_item_tree(db, file_id))
+        self.with_db("view_item_tree", |db| view_item_tree::view_item_tree(db, file_id))
     }
 
-    /// Renders the crate graph to GraphViz "dot" syntax.
-    pub fn view_crate_graph(&self, full: bool) -> Cancellable<Result<String, String>> {
-        self.with_db(|db| view_crate_graph::view_crate_graph(db, full))
+    /// Renders the crate graph to GraphViz "dot" or Mermaid syntax.
+    pub fn view_crate_graph(
+        &self,
+        config: CrateGraphConfig,
+    ) -> Cancellable<Result<String, String>> {
+        self.with_db("view_crate_graph", |db| view_crate_graph::view_crate_graph(db, config))
     }
 
     pub fn expand_macro(&self, position: FilePosition) -> Cancellable<Option<ExpandedMacro>> {
-        self.with_db(|db| expand_macro::expand_macro(db, position))
+        self.with_db("expand_macro", |db| expand_macro::expand_macro(db, position))
+    }
+
+    /// Expands a single level of the macro at `position`, or of the nested macro call selected by
+    /// `path` in it.
+    pub fn expand_macro_step(
+        &self,
+        position: FilePosition,
+        path: &[usize],
+    ) -> Cancellable<Option<ExpandedMacroStep>> {
+        self.with_db("expand_macro_step", |db| expand_macro::expand_macro_step(db, position, path))
+    }
+
+    /// Expands the proc-macro at `position` again, with the full backtrace in its error if it
+    /// panics.
+    pub fn rerun_proc_macro_with_backtrace(
+        &self,
+        position: FilePosition,
+    ) -> Cancellable<Option<ProcMacroBacktrace>> {
+        self.with_db("rerun_proc_macro_with_backtrace", |db| {
+            expand_macro::rerun_proc_macro_with_backtrace(db, position)
+        })
     }
 
     /// Returns an edit to remove all newlines in the range, cleaning up minor
     /// stuff like trailing commas.
     pub fn join_lines(&self, config: &JoinLinesConfig, frange: FileRange) -> Cancellable<TextEdit> {
-        self.with_db(|db| {
+        self.with_db("join_lines", |db| {
             let parse = db.parse(frange.file_id);
             join_lines::join_lines(config, &parse.tree(), frange.range)
         })
@@ -331,7 +414,7 @@ impl Analysis {
     /// up minor stuff like continuing the comment.
     /// The edit will be a snippet (with `$0`).
     pub fn on_enter(&self, position: FilePosition) -> Cancellable<Option<TextEdit>> {
-        self.with_db(|db| typing::on_enter(db, position))
+        self.with_db("on_enter", |db| typing::on_enter(db, position))
     }
 
     /// Returns an edit which should be applied after a character was typed.
@@ -352,13 +435,15 @@ impl Analysis {
             return Ok(None);
         }
 
-        self.with_db(|db| typing::on_char_typed(db, position, char_typed))
+        self.with_db("on_char_typed", |db| typing::on_char_typed(db, position, char_typed))
     }
 
     /// Returns a tree representation of symbols in the file. Useful to draw a
     /// file outline.
     pub fn file_structure(&self, file_id: FileId) -> Cancellable<Vec<StructureNode>> {
-        self.with_db(|db| file_structure::file_structure(&db.parse(file_id).tree()))
+        self.with_db("file_structure", |db| {
+            file_structure::file_structure(&db.parse(file_id).tree())
+        })
     }
 
     /// Returns a list of the places in the file where type hints can be displayed.
@@ -368,17 +453,33 @@ impl Analysis {
         file_id: FileId,
         range: Option<FileRange>,
     ) -> Cancellable<Vec<InlayHint>> {
-        self.with_db(|db| inlay_hints::inlay_hints(db, file_id, range, config))
+        self.with_db("inlay_hints", |db| inlay_hints::inlay_hints(db, file_id, range, config))
+    }
+
+    /// Recomputes the inlay hint with the given [`InlayHint::resolve_hash`] in `resolve_range`,
+    /// filling in the fields that were left for resolving.
+    pub fn inlay_hints_resolve(
+        &self,
+        config: &InlayHintsConfig,
+        file_id: FileId,
+        resolve_range: TextRange,
+        hash: u64,
+    ) -> Cancellable<Option<InlayHint>> {
+        self.with_db("inlay_hints_resolve", |db| {
+            inlay_hints::inlay_hints_resolve(db, file_id, resolve_range, hash, config)
+        })
     }
 
     /// Returns the set of folding ranges.
     pub fn folding_ranges(&self, file_id: FileId) -> Cancellable<Vec<Fold>> {
-        self.with_db(|db| folding_ranges::folding_ranges(&db.parse(file_id).tree()))
+        self.with_db("folding_ranges", |db| {
+            folding_ranges::folding_ranges(&db.parse(file_id).tree())
+        })
     }
 
     /// Fuzzy searches for a symbol.
     pub fn symbol_search(&self, query: Query) -> Cancellable<Vec<NavigationTarget>> {
-        self.with_db(|db| {
+        self.with_db("symbol_search", |db| {
             symbol_index::world_symbols(db, query)
                 .into_iter() // xx: should we make this a par iter?
                 .filter_map(|s| s.try_to_nav(db))
@@ -391,7 +492,7 @@ impl Analysis {
         &self,
         position: FilePosition,
     ) -> Cancellable<Option<RangeInfo<Vec<NavigationTarget>>>> {
-        self.with_db(|db| goto_definition::goto_definition(db, position))
+        self.with_db("goto_definition", |db| goto_definition::goto_definition(db, position))
     }
 
     /// Returns the declaration from the symbol at `position`.
@@ -399,7 +500,7 @@ impl Analysis {
         &self,
         position: FilePosition,
     ) -> Cancellable<Option<RangeInfo<Vec<NavigationTarget>>>> {
-        self.with_db(|db| goto_declaration::goto_declaration(db, position))
+        self.with_db("goto_declaration", |db| goto_declaration::goto_declaration(db, position))
     }
 
     /// Returns the impls from the symbol at `position`.
@@ -407,7 +508,9 @@ impl Analysis {
         &self,
         position: FilePosition,
     ) -> Cancellable<Option<RangeInfo<Vec<NavigationTarget>>>> {
-        self.with_db(|db| goto_implementation::goto_implementation(db, position))
+        self.with_db("goto_implementation", |db| {
+            goto_implementation::goto_implementation(db, position)
+        })
     }
 
     /// Returns the type definitions for the symbol at `position`.
@@ -415,7 +518,9 @@ impl Analysis {
         &self,
         position: FilePosition,
     ) -> Cancellable<Option<RangeInfo<Vec<NavigationTarget>>>> {
-        self.with_db(|db| goto_type_definition::goto_type_definition(db, position))
+        self.with_db("goto_type_definition", |db| {
+            goto_type_definition::goto_type_definition(db, position)
+        })
     }
 
     /// Finds all usages of the reference at point.
@@ -424,12 +529,14 @@ impl Analysis {
         position: FilePosition,
         search_scope: Option<SearchScope>,
     ) -> Cancellable<Option<Vec<ReferenceSearchResult>>> {
-        self.with_db(|db| references::find_all_refs(&Semantics::new(db), position, search_scope))
+        self.with_db("find_all_refs", |db| {
+            references::find_all_refs(&Semantics::new(db), position, search_scope)
+        })
     }
 
     /// Finds all methods and free functions for the file. Does not return tests!
     pub fn find_all_methods(&self, file_id: FileId) -> Cancellable<Vec<FileRange>> {
-        self.with_db(|db| fn_references::find_all_methods(db, file_id))
+        self.with_db("find_all_methods", |db| fn_references::find_all_methods(db, file_id))
     }
 
     /// Returns a short text describing element at position.
@@ -438,7 +545,7 @@ impl Analysis {
         config: &HoverConfig,
         range: FileRange,
     ) -> Cancellable<Option<RangeInfo<HoverResult>>> {
-        self.with_db(|db| hover::hover(db, range, config))
+        self.with_db("hover", |db| hover::hover(db, range, config))
     }
 
     /// Returns moniker of symbol at position.
@@ -446,20 +553,21 @@ impl Analysis {
         &self,
         position: FilePosition,
     ) -> Cancellable<Option<RangeInfo<Vec<moniker::MonikerResult>>>> {
-        self.with_db(|db| moniker::moniker(db, position))
+        self.with_db("moniker", |db| moniker::moniker(db, position))
     }
 
     /// Return URL(s) for the documentation of the symbol under the cursor.
     pub fn external_docs(
         &self,
         position: FilePosition,
+        config: &ExternalDocsConfig,
     ) -> Cancellable<Option<doc_links::DocumentationLink>> {
-        self.with_db(|db| doc_links::external_docs(db, &position))
+        self.with_db("external_docs", |db| doc_links::external_docs(db, &position, config))
     }
 
     /// Computes parameter information at the given position.
     pub fn signature_help(&self, position: FilePosition) -> Cancellable<Option<SignatureHelp>> {
-        self.with_db(|db| signature_help::signature_help(db, position))
+        self.with_db("signature_help", |db| signature_help::signature_help(db, position))
     }
 
     /// Computes call hierarchy candidates for the given file position.
@@ -467,42 +575,65 @@ impl Analysis {
         &self,
         position: FilePosition,
     ) -> Cancellable<Option<RangeInfo<Vec<NavigationTarget>>>> {
-        self.with_db(|db| call_hierarchy::call_hierarchy(db, position))
+        self.with_db("call_hierarchy", |db| call_hierarchy::call_hierarchy(db, position))
     }
 
     /// Computes incoming calls for the given file position.
     pub fn incoming_calls(&self, position: FilePosition) -> Cancellable<Option<Vec<CallItem>>> {
-        self.with_db(|db| call_hierarchy::incoming_calls(db, position))
+        self.with_db("incoming_calls", |db| call_hierarchy::incoming_calls(db, position))
     }
 
     /// Computes outgoing calls for the given file position.
     pub fn outgoing_calls(&self, position: FilePosition) -> Cancellable<Option<Vec<CallItem>>> {
-        self.with_db(|db| call_hierarchy::outgoing_calls(db, position))
+        self.with_db("outgoing_calls", |db| call_hierarchy::outgoing_calls(db, position))
+    }
+
+    /// Computes type hierarchy candidates for the given file position.
+    pub fn type_hierarchy(
+        &self,
+        position: FilePosition,
+    ) -> Cancellable<Option<RangeInfo<Vec<NavigationTarget>>>> {
+        self.with_db("type_hierarchy", |db| type_hierarchy::type_hierarchy(db, position))
+    }
+
+    /// Computes the direct supertypes of the type at the given file position.
+    pub fn supertypes(&self, position: FilePosition) -> Cancellable<Option<Vec<NavigationTarget>>> {
+        self.with_db("supertypes", |db| type_hierarchy::supertypes(db, position))
+    }
+
+    /// Computes the direct subtypes of the type at the given file position.
+    pub fn subtypes(&self, position: FilePosition) -> Cancellable<Option<Vec<NavigationTarget>>> {
+        self.with_db("subtypes", |db| type_hierarchy::subtypes(db, position))
     }
 
     /// Returns a `mod name;` declaration which created the current module.
     pub fn parent_module(&self, position: FilePosition) -> Cancellable<Vec<NavigationTarget>> {
-        self.with_db(|db| parent_module::parent_module(db, position))
+        self.with_db("parent_module", |db| parent_module::parent_module(db, position))
     }
 
     /// Returns crates this file belongs too.
     pub fn crate_for(&self, file_id: FileId) -> Cancellable<Vec<CrateId>> {
-        self.with_db(|db| parent_module::crate_for(db, file_id))
+        self.with_db("crate_for", |db| parent_module::crate_for(db, file_id))
+    }
+
+    /// Returns the crates owning the file and all of their dependencies.
+    pub fn dependency_tree(&self, file_id: FileId) -> Cancellable<DependencyTree> {
+        self.with_db("dependency_tree", |db| dependency_tree::dependency_tree(db, file_id))
     }
 
     /// Returns the edition of the given crate.
     pub fn crate_edition(&self, crate_id: CrateId) -> Cancellable<Edition> {
-        self.with_db(|db| db.crate_graph()[crate_id].edition)
+        self.with_db("crate_edition", |db| db.crate_graph()[crate_id].edition)
     }
 
     /// Returns the root file of the given crate.
     pub fn crate_root(&self, crate_id: CrateId) -> Cancellable<FileId> {
-        self.with_db(|db| db.crate_graph()[crate_id].root_file_id)
+        self.with_db("crate_root", |db| db.crate_graph()[crate_id].root_file_id)
     }
 
     /// Returns the set of possible targets to run for the current file.
     pub fn runnables(&self, file_id: FileId) -> Cancellable<Vec<Runnable>> {
-        self.with_db(|db| runnables::runnables(db, file_id))
+        self.with_db("runnables", |db| runnables::runnables(db, file_id))
     }
 
     /// Returns the set of tests for the given file position.
@@ -511,12 +642,34 @@ impl Analysis {
         position: FilePosition,
         search_scope: Option<SearchScope>,
     ) -> Cancellable<Vec<Runnable>> {
-        self.with_db(|db| runnables::related_tests(db, position, search_scope))
+        self.with_db("related_tests", |db| runnables::related_tests(db, position, search_scope))
+    }
+
+    /// Returns the workspace crates as the roots of the test tree.
+    pub fn discover_test_roots(&self) -> Cancellable<Vec<TestItem>> {
+        self.with_db("discover_test_roots", test_explorer::discover_test_roots)
+    }
+
+    /// Returns all tests of the crate with the given test id.
+    pub fn discover_tests_in_crate_by_test_id(
+        &self,
+        crate_test_id: &str,
+    ) -> Cancellable<Vec<TestItem>> {
+        self.with_db("discover_tests_in_crate_by_test_id", |db| {
+            test_explorer::discover_tests_in_crate_by_test_id(db, crate_test_id)
+        })
+    }
+
+    /// Returns the tests defined in the given file, along with their parents in the test tree.
+    pub fn discover_tests_in_file(&self, file_id: FileId) -> Cancellable<Vec<TestItem>> {
+        self.with_db("discover_tests_in_file", |db| {
+            test_explorer::discover_tests_in_file(db, file_id)
+        })
     }
 
     /// Computes syntax highlighting for the given file
     pub fn highlight(&self, file_id: FileId) -> Cancellable<Vec<HlRange>> {
-        self.with_db(|db| syntax_highlighting::highlight(db, file_id, None, false))
+        self.with_db("highlight", |db| syntax_highlighting::highlight(db, file_id, None, false))
     }
 
     /// Computes all ranges to highlight for a given item in a file.
@@ -525,21 +678,23 @@ impl Analysis {
         config: HighlightRelatedConfig,
         position: FilePosition,
     ) -> Cancellable<Option<Vec<HighlightedRange>>> {
-        self.with_db(|db| {
+        self.with_db("highlight_related", |db| {
             highlight_related::highlight_related(&Semantics::new(db), config, position)
         })
     }
 
     /// Computes syntax highlighting for the given file range.
     pub fn highlight_range(&self, frange: FileRange) -> Cancellable<Vec<HlRange>> {
-        self.with_db(|db| {
+        self.with_db("highlight_range", |db| {
             syntax_highlighting::highlight(db, frange.file_id, Some(frange.range), false)
         })
     }
 
     /// Computes syntax highlighting for the given file.
     pub fn highlight_as_html(&self, file_id: FileId, rainbow: bool) -> Cancellable<String> {
-        self.with_db(|db| syntax_highlighting::highlight_as_html(db, file_id, rainbow))
+        self.with_db("highlight_as_html", |db| {
+            syntax_highlighting::highlight_as_html(db, file_id, rainbow)
+        })
     }
 
     /// Computes completions at the given position.
@@ -548,7 +703,23 @@ impl Analysis {
         config: &CompletionConfig,
         position: FilePosition,
     ) -> Cancellable<Option<Vec<CompletionItem>>> {
-        self.with_db(|db| ide_completion::completions(db, config, position).map(Into::into))
+        self.with_db("completions", |db| {
+            ide_completion::completions(db, config, position).map(Into::into)
+        })
+    }
+
+    /// Recomputes the completion with the given [`CompletionItem::resolve_hash`] at `position`,
+    /// filling in the fields that were left for resolving.
+    pub fn resolve_completion_item(
+        &self,
+        config: &CompletionConfig,
+        position: FilePosition,
+        hash: u64,
+        imports_something: bool,
+    ) -> Cancellable<Option<CompletionItem>> {
+        self.with_db("resolve_completion_item", |db| {
+            ide_completion::resolve_completion_item(db, config, position, hash, imports_something)
+        })
     }
 
     /// Resolves additional completion data at the position given.
@@ -559,7 +730,9 @@ impl Analysis {
         imports: impl IntoIterator<Item = (String, String)> + std::panic::UnwindSafe,
     ) -> Cancellable<Vec<TextEdit>> {
         Ok(self
-            .with_db(|db| ide_completion::resolve_completion_edits(db, config, position, imports))?
+            .with_db("resolve_completion_edits", |db| {
+                ide_completion::resolve_completion_edits(db, config, position, imports)
+            })?
             .unwrap_or_default())
     }
 
@@ -570,7 +743,9 @@ impl Analysis {
         resolve: AssistResolveStrategy,
         file_id: FileId,
     ) -> Cancellable<Vec<Diagnostic>> {
-        self.with_db(|db| ide_diagnostics::diagnostics(db, config, &resolve, file_id))
+        self.with_db("diagnostics", |db| {
+            ide_diagnostics::diagnostics(db, config, &resolve, file_id)
+        })
     }
 
     /// Convenience function to return assists + quick fixes for diagnostics
@@ -586,7 +761,7 @@ impl Analysis {
             None => true,
         };
 
-        self.with_db(|db| {
+        self.with_db("assists_with_fixes", |db| {
             let diagnostic_assists = if include_fixes {
                 ide_diagnostics::diagnostics(db, diagnostics_config, &resolve, frange.file_id)
                     .into_iter()
@@ -614,14 +789,14 @@ impl Analysis {
         position: FilePosition,
         new_name: &str,
     ) -> Cancellable<Result<SourceChange, RenameError>> {
-        self.with_db(|db| rename::rename(db, position, new_name))
+        self.with_db("rename", |db| rename::rename(db, position, new_name))
     }
 
     pub fn prepare_rename(
         &self,
         position: FilePosition,
     ) -> Cancellable<Result<RangeInfo<()>, RenameError>> {
-        self.with_db(|db| rename::prepare_rename(db, position))
+        self.with_db("prepare_rename", |db| rename::prepare_rename(db, position))
     }
 
     pub fn will_rename_file(
@@ -629,7 +804,15 @@ impl Analysis {
         file_id: FileId,
         new_name_stem: &str,
     ) -> Cancellable<Option<SourceChange>> {
-        self.with_db(|db| rename::will_rename_file(db, file_id, new_name_stem))
+        self.with_db("will_rename_file", |db| rename::will_rename_file(db, file_id, new_name_stem))
+    }
+
+    pub fn will_move_file(
+        &self,
+        file_id: FileId,
+        new_path: &VfsPath,
+    ) -> Cancellable<Option<SourceChange>> {
+        self.with_db("will_move_file", |db| rename::will_move_file(db, file_id, new_path))
     }
 
     pub fn structural_search_replace(
@@ -639,7 +822,7 @@ impl Analysis {
         resolve_context: FilePosition,
         selections: Vec<FileRange>,
     ) -> Cancellable<Result<SourceChange, SsrError>> {
-        self.with_db(|db| {
+        self.with_db("structural_search_replace", |db| {
             let rule: ide_ssr::SsrRule = query.parse()?;
             let mut match_finder =
                 ide_ssr::MatchFinder::in_context(db, resolve_context, selections)?;
@@ -654,19 +837,22 @@ impl Analysis {
         config: &AnnotationConfig,
         file_id: FileId,
     ) -> Cancellable<Vec<Annotation>> {
-        self.with_db(|db| annotations::annotations(db, config, file_id))
+        self.with_db("annotations", |db| annotations::annotations(db, config, file_id))
     }
 
     pub fn resolve_annotation(&self, annotation: Annotation) -> Cancellable<Annotation> {
-        self.with_db(|db| annotations::resolve_annotation(db, annotation))
+        self.with_db("resolve_annotation", |db| annotations::resolve_annotation(db, annotation))
     }
 
     pub fn move_item(
         &self,
         range: FileRange,
         direction: Direction,
+        respect_trait_order: bool,
     ) -> Cancellable<Option<TextEdit>> {
-        self.with_db(|db| move_item::move_item(db, range, direction))
+        self.with_db("move_item", |db| {
+            move_item::move_item(db, range, direction, respect_trait_order)
+        })
     }
 
     /// Performs an operation on the database that may be canceled.
@@ -682,10 +868,14 @@ impl Analysis {
     ///
     /// Salsa implements cancelation by unwinding with a special value and
     /// catching it on the API boundary.
-    fn with_db<F, T>(&self, f: F) -> Cancellable<T>
+    ///
+    /// The work is recorded in a span named after the `query`, so that traces
+    /// show which entry points a request spent its time in.
+    fn with_db<F, T>(&self, query: &'static str, f: F) -> Cancellable<T>
     where
         F: FnOnce(&RootDatabase) -> T + std::panic::UnwindSafe,
     {
+        let _span = tracing::info_span!("analysis", query).entered();
         Cancelled::catch(|| f(&self.db))
     }
 }
@@ -1,13 +1,17 @@
+use hir::Semantics;
+use ide_db::{base_db::FileId, RootDatabase};
 use syntax::{
     ast::{self, AstNode},
-    SourceFile, SyntaxKind, TextSize, T,
+    SyntaxKind, SyntaxToken, TextSize, T,
 };
 
+use crate::FilePosition;
+
 // Feature: Matching Brace
 //
 // If the cursor is on any brace (`<>(){}[]||`) which is a part of a brace-pair,
 // moves cursor to the matching brace. It uses the actual parser to determine
-// braces, so it won't confuse generics with comparisons.
+// braces, so it won't confuse generics with comparisons, not even inside of macro calls.
 //
 // |===
 // | Editor  | Action Name
@@ -16,23 +20,32 @@ use syntax::{
 // |===
 //
 // image::https://user-images.githubusercontent.com/48062697/113065573-04298180-91b1-11eb-8dec-d4e2a202f304.gif[]
-pub(crate) fn matching_brace(file: &SourceFile, offset: TextSize) -> Option<TextSize> {
+pub(crate) fn matching_brace(db: &RootDatabase, position: FilePosition) -> Option<TextSize> {
     const BRACES: &[SyntaxKind] =
         &[T!['{'], T!['}'], T!['['], T![']'], T!['('], T![')'], T![<], T![>], T![|], T![|]];
+    let sema = Semantics::new(db);
+    let file = sema.parse(position.file_id);
     let (brace_token, brace_idx) = file
         .syntax()
-        .token_at_offset(offset)
+        .token_at_offset(position.offset)
         .filter_map(|node| {
             let idx = BRACES.iter().position(|&brace| brace == node.kind())?;
             Some((node, idx))
         })
         .last()?;
+    let matching_kind = BRACES[brace_idx ^ 1];
     let parent = brace_token.parent()?;
+    if parent.kind() == SyntaxKind::TOKEN_TREE
+        && matches!(brace_token.kind(), T![<] | T![>] | T![|])
+    {
+        // Inside of a macro call only the delimiters of token trees are known to pair up, so
+        // look at how the tokens were parsed in the expansion instead.
+        return matching_brace_in_expansion(&sema, position.file_id, &brace_token, matching_kind);
+    }
     if brace_token.kind() == T![|] && !ast::ParamList::can_cast(parent.kind()) {
         cov_mark::hit!(pipes_not_braces);
         return None;
     }
-    let matching_kind = BRACES[brace_idx ^ 1];
     let matching_node = parent
         .children_with_tokens()
         .filter_map(|it| it.into_token())
@@ -40,19 +53,45 @@ pub(crate) fn matching_brace(file: &SourceFile, offset: TextSize) -> Option<Text
     Some(matching_node.text_range().start())
 }
 
+fn matching_brace_in_expansion(
+    sema: &Semantics<RootDatabase>,
+    file_id: FileId,
+    brace_token: &SyntaxToken,
+    matching_kind: SyntaxKind,
+) -> Option<TextSize> {
+    let call_range = brace_token.ancestors().find_map(ast::MacroCall::cast)?.syntax().text_range();
+    let expanded = sema.descend_into_macros_single(brace_token.clone());
+    if &expanded == brace_token {
+        return None;
+    }
+    let parent = expanded.parent()?;
+    if expanded.kind() == T![|] && !ast::ParamList::can_cast(parent.kind()) {
+        return None;
+    }
+    let matching = parent
+        .children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .find(|it| it.kind() == matching_kind && it != &expanded)?;
+
+    let token = sema.original_token(matching)?;
+    // The matching brace might have been written in the macro definition instead of the call.
+    let range = token.value.text_range();
+    (token.file_id == file_id.into() && call_range.contains_range(range)).then(|| range.start())
+}
+
 #[cfg(test)]
 mod tests {
-    use test_utils::{add_cursor, assert_eq_text, extract_offset};
+    use test_utils::{add_cursor, assert_eq_text};
 
-    use super::*;
+    use crate::fixture;
 
     #[test]
     fn test_matching_brace() {
         fn do_check(before: &str, after: &str) {
-            let (pos, before) = extract_offset(before);
-            let parse = SourceFile::parse(&before);
-            let new_pos = match matching_brace(&parse.tree(), pos) {
-                None => pos,
+            let (analysis, position) = fixture::position(before);
+            let before = analysis.file_text(position.file_id).unwrap();
+            let new_pos = match analysis.matching_brace(position).unwrap() {
+                None => position.offset,
                 Some(pos) => pos,
             };
             let actual = add_cursor(&before, new_pos);
@@ -75,4 +114,29 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_matching_brace_inside_macros() {
+        fn do_check(before: &str, after: &str) {
+            let (analysis, position) = fixture::position(before);
+            let before = analysis.file_text(position.file_id).unwrap();
+            let new_pos = analysis.matching_brace(position).unwrap().unwrap_or(position.offset);
+            assert_eq_text!(after, &add_cursor(&before, new_pos));
+        }
+
+        let id = "macro_rules! id { ($($t:tt)*) => { $($t)* } }\n";
+        do_check(
+            &format!("{}id! {{ fn f(x: Vec$0<Option<u8>>) {{}} }}", id),
+            &format!("{}id! {{ fn f(x: Vec<Option<u8>$0>) {{}} }}", id),
+        );
+        do_check(
+            &format!("{}id! {{ fn f() {{ let g = |x|$0 x < 2; }} }}", id),
+            &format!("{}id! {{ fn f() {{ let g = $0|x| x < 2; }} }}", id),
+        );
+        // `<` is a comparison here, so there's nothing to match
+        do_check(
+            &format!("{}id! {{ fn f() {{ a $0< b; c > d; }} }}", id),
+            &format!("{}id! {{ fn f() {{ a $0< b; c > d; }} }}", id),
+        );
+    }
 }
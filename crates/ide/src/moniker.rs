@@ -1,7 +1,7 @@
 //! This module generates [moniker](https://microsoft.github.io/language-server-protocol/specifications/lsif/0.6.0/specification/#exportsImports)
 //! for LSIF and LSP.
 
-use hir::{db::DefDatabase, AsAssocItem, AssocItemContainer, Crate, Name, Semantics};
+use hir::{db::DefDatabase, AsAssocItem, AssocItemContainer, Crate, GenericDef, Name, Semantics};
 use ide_db::{
     base_db::{CrateOrigin, FileId, FileLoader, FilePosition, LangCrateOrigin},
     defs::{Definition, IdentClass},
@@ -15,15 +15,36 @@ use crate::{doc_links::token_as_doc_comment, RangeInfo};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct MonikerIdentifier {
-    crate_name: String,
-    path: Vec<Name>,
+    pub crate_name: String,
+    pub description: Vec<MonikerDescriptor>,
+}
+
+/// One segment of a moniker's path, together with what kind of item it names.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MonikerDescriptor {
+    pub name: Name,
+    pub desc: MonikerDescriptorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MonikerDescriptorKind {
+    Namespace,
+    Type,
+    Term,
+    Method,
+    TypeParameter,
+    Macro,
 }
 
 impl ToString for MonikerIdentifier {
     fn to_string(&self) -> String {
         match self {
-            MonikerIdentifier { path, crate_name } => {
-                format!("{}::{}", crate_name, path.iter().map(|x| x.to_string()).join("::"))
+            MonikerIdentifier { description, crate_name } => {
+                format!(
+                    "{}::{}",
+                    crate_name,
+                    description.iter().map(|x| x.name.to_string()).join("::")
+                )
             }
         }
     }
@@ -105,13 +126,31 @@ pub(crate) fn def_to_moniker(
     def: Definition,
     from_crate: Crate,
 ) -> Option<MonikerResult> {
-    if matches!(def, Definition::GenericParam(_) | Definition::SelfType(_) | Definition::Local(_)) {
+    if matches!(def, Definition::SelfType(_) | Definition::Local(_)) {
         return None;
     }
+    if let Definition::GenericParam(it) = def {
+        // Generic parameters are only unique within the item declaring them.
+        let parent = match it.parent(db) {
+            GenericDef::Function(it) => Definition::Function(it),
+            GenericDef::Adt(it) => Definition::Adt(it),
+            GenericDef::Trait(it) => Definition::Trait(it),
+            GenericDef::TypeAlias(it) => Definition::TypeAlias(it),
+            GenericDef::Impl(_) | GenericDef::Variant(_) | GenericDef::Const(_) => return None,
+        };
+        let mut moniker = def_to_moniker(db, parent, from_crate)?;
+        moniker.identifier.description.push(MonikerDescriptor {
+            name: it.name(db),
+            desc: MonikerDescriptorKind::TypeParameter,
+        });
+        return Some(moniker);
+    }
     let module = def.module(db)?;
     let krate = module.krate();
-    let mut path = vec![];
-    path.extend(module.path_to_root(db).into_iter().filter_map(|x| x.name(db)));
+    let mut description = vec![];
+    description.extend(module.path_to_root(db).into_iter().filter_map(|x| {
+        Some(MonikerDescriptor { name: x.name(db)?, desc: MonikerDescriptorKind::Namespace })
+    }));
 
     // Handle associated items within a trait
     if let Some(assoc) = def.as_assoc_item(db) {
@@ -120,31 +159,54 @@ pub(crate) fn def_to_moniker(
             AssocItemContainer::Trait(trait_) => {
                 // Because different traits can have functions with the same name,
                 // we have to include the trait name as part of the moniker for uniqueness.
-                path.push(trait_.name(db));
+                description.push(MonikerDescriptor {
+                    name: trait_.name(db),
+                    desc: MonikerDescriptorKind::Type,
+                });
             }
             AssocItemContainer::Impl(impl_) => {
                 // Because a struct can implement multiple traits, for implementations
                 // we add both the struct name and the trait name to the path
                 if let Some(adt) = impl_.self_ty(db).as_adt() {
-                    path.push(adt.name(db));
+                    description.push(MonikerDescriptor {
+                        name: adt.name(db),
+                        desc: MonikerDescriptorKind::Type,
+                    });
                 }
 
                 if let Some(trait_) = impl_.trait_(db) {
-                    path.push(trait_.name(db));
+                    description.push(MonikerDescriptor {
+                        name: trait_.name(db),
+                        desc: MonikerDescriptorKind::Type,
+                    });
                 }
             }
         }
     }
 
     if let Definition::Field(it) = def {
-        path.push(it.parent_def(db).name(db));
+        description.push(MonikerDescriptor {
+            name: it.parent_def(db).name(db),
+            desc: MonikerDescriptorKind::Type,
+        });
     }
 
-    path.push(def.name(db)?);
+    let desc = match def {
+        Definition::Module(_) => MonikerDescriptorKind::Namespace,
+        Definition::Function(_) => MonikerDescriptorKind::Method,
+        Definition::Adt(_)
+        | Definition::Variant(_)
+        | Definition::Trait(_)
+        | Definition::TypeAlias(_)
+        | Definition::BuiltinType(_) => MonikerDescriptorKind::Type,
+        Definition::Macro(_) => MonikerDescriptorKind::Macro,
+        _ => MonikerDescriptorKind::Term,
+    };
+    description.push(MonikerDescriptor { name: def.name(db)?, desc });
     Some(MonikerResult {
         identifier: MonikerIdentifier {
             crate_name: krate.display_name(db)?.crate_name().to_string(),
-            path,
+            description,
         },
         kind: if krate == from_crate { MonikerKind::Export } else { MonikerKind::Import },
         package_information: {
@@ -339,4 +401,21 @@ pub mod module {
 "#,
         );
     }
+
+    #[test]
+    fn generic_param_moniker() {
+        check_moniker(
+            r#"
+//- /lib.rs crate:main deps:foo
+use foo::module::Foo;
+//- /foo/lib.rs crate:foo@CratesIo:0.1.0,https://a.b/foo.git
+pub mod module {
+    pub struct Foo<T>(T$0);
+}
+"#,
+            "foo::module::Foo::T",
+            r#"PackageInformation { name: "foo", repo: "https://a.b/foo.git", version: "0.1.0" }"#,
+            MonikerKind::Export,
+        );
+    }
 }
@@ -1,7 +1,12 @@
 use std::{iter::once, mem};
 
 use hir::Semantics;
-use ide_db::{base_db::FileRange, helpers::pick_best_token, RootDatabase};
+use ide_db::{
+    base_db::FileRange,
+    helpers::pick_best_token,
+    traits::{assoc_item_rank, trait_item_ranks},
+    RootDatabase,
+};
 use itertools::Itertools;
 use syntax::{algo, ast, match_ast, AstNode, SyntaxElement, SyntaxKind, SyntaxNode, TextRange};
 use text_edit::{TextEdit, TextEditBuilder};
@@ -16,6 +21,9 @@ pub enum Direction {
 //
 // Move item under cursor or selection up and down.
 //
+// Optionally, associated items of trait impls jump straight to the position mandated by the
+// order of the trait definition, if that position lies in the direction of the move.
+//
 // |===
 // | Editor  | Action Name
 //
@@ -28,6 +36,7 @@ pub(crate) fn move_item(
     db: &RootDatabase,
     range: FileRange,
     direction: Direction,
+    respect_trait_order: bool,
 ) -> Option<TextEdit> {
     let sema = Semantics::new(db);
     let file = sema.parse(range.file_id);
@@ -45,10 +54,149 @@ pub(crate) fn move_item(
         file.syntax().covering_element(range.range)
     };
 
-    find_ancestors(item, direction, range.range)
+    let in_token_tree = match &item {
+        SyntaxElement::Node(node) => node.kind() == SyntaxKind::TOKEN_TREE,
+        SyntaxElement::Token(token) => token.parent()?.kind() == SyntaxKind::TOKEN_TREE,
+    };
+    if in_token_tree {
+        if let Some(macro_call) = item.ancestors().find_map(ast::MacroCall::cast) {
+            return move_in_macro_call(&sema, &macro_call, item, direction, range.range);
+        }
+    }
+
+    let (first, second) = find_ancestors(item, direction, range.range)?;
+    if respect_trait_order {
+        if let Some(edit) = move_to_trait_position(&sema, &first, direction, range.range) {
+            return Some(edit);
+        }
+    }
+    Some(replace_nodes(range.range, &first, &second))
+}
+
+/// Moves an associated item of a trait impl to the place it has in the trait definition,
+/// relative to the other items of the impl: right after the closest item before it that comes
+/// earlier in the trait when moving up, and right before the closest item after it that comes
+/// later in the trait when moving down. Returns `None` if the item is already in that place.
+fn move_to_trait_position(
+    sema: &Semantics<RootDatabase>,
+    node: &SyntaxNode,
+    direction: Direction,
+    range: TextRange,
+) -> Option<TextEdit> {
+    let item = ast::AssocItem::cast(node.clone())?;
+    let item_list = ast::AssocItemList::cast(node.parent()?)?;
+    let impl_ = ast::Impl::cast(item_list.syntax().parent()?)?;
+    let ranks = trait_item_ranks(sema, &impl_)?;
+    let rank = assoc_item_rank(&ranks, &item);
+    if rank == usize::MAX {
+        return None;
+    }
+
+    let items = item_list.assoc_items().collect::<Vec<_>>();
+    let idx = items.iter().position(|it| *it == item)?;
+    let mut item_text = node.text().to_string();
+    if range.is_empty() && node.text_range().contains_range(range) {
+        // FIXME: `applySnippetTextEdits` does not support non-empty selection ranges
+        item_text.insert_str((range.start() - node.text_range().start()).into(), "$0");
+    }
+
+    let mut edit = TextEditBuilder::default();
+    match direction {
+        Direction::Up => {
+            let pos = items[..idx]
+                .iter()
+                .rposition(|it| assoc_item_rank(&ranks, it) < rank)
+                .map_or(0, |it| it + 1);
+            if pos == idx {
+                return None;
+            }
+            let target = &items[pos];
+            let whitespace = node.prev_sibling_or_token()?.into_token()?;
+            if whitespace.kind() != SyntaxKind::WHITESPACE {
+                return None;
+            }
+            let target_start = target.syntax().text_range().start();
+            edit.insert(target_start, format!("{}{}", item_text, whitespace.text()));
+            edit.delete(TextRange::new(whitespace.text_range().start(), node.text_range().end()));
+        }
+        Direction::Down => {
+            let pos = items[idx + 1..]
+                .iter()
+                .position(|it| assoc_item_rank(&ranks, it) > rank)
+                .map_or(items.len(), |it| idx + 1 + it);
+            if pos == idx + 1 {
+                return None;
+            }
+            let target = &items[pos - 1];
+            let whitespace = node.next_sibling_or_token()?.into_token()?;
+            if whitespace.kind() != SyntaxKind::WHITESPACE {
+                return None;
+            }
+            edit.delete(TextRange::new(node.text_range().start(), whitespace.text_range().end()));
+            let target_end = target.syntax().text_range().end();
+            edit.insert(target_end, format!("{}{}", whitespace.text(), item_text));
+        }
+    }
+    Some(edit.finish())
 }
 
-fn find_ancestors(item: SyntaxElement, direction: Direction, range: TextRange) -> Option<TextEdit> {
+/// Moves an item written inside a macro call, by finding the nodes to swap in the expansion and
+/// mapping them back to the tokens of the call.
+fn move_in_macro_call(
+    sema: &Semantics<RootDatabase>,
+    macro_call: &ast::MacroCall,
+    item: SyntaxElement,
+    direction: Direction,
+    range: TextRange,
+) -> Option<TextEdit> {
+    let token = match item {
+        SyntaxElement::Node(node) => {
+            node.token_at_offset(range.start()).right_biased().unwrap_or(node.first_token()?)
+        }
+        SyntaxElement::Token(token) => token,
+    };
+    let expanded = sema.descend_into_macros_single(token.clone());
+    if expanded == token {
+        return None;
+    }
+    let expanded_range = TextRange::empty(expanded.text_range().start());
+    let (first, second) =
+        find_ancestors(SyntaxElement::Token(expanded), direction, expanded_range)?;
+
+    let call_range = macro_call.token_tree()?.syntax().text_range();
+    let original_range = |node: &SyntaxNode| {
+        let range = sema.original_range_opt(node)?;
+        let file_id = sema.hir_file_for(macro_call.syntax()).original_file(sema.db);
+        (range.file_id == file_id && call_range.contains_range(range.range)).then(|| range.range)
+    };
+    let mut first = original_range(&first)?;
+    let mut second = original_range(&second)?;
+    if first.intersect(second).map_or(false, |it| !it.is_empty()) {
+        return None;
+    }
+    if !first.contains_range(range) {
+        mem::swap(&mut first, &mut second);
+    }
+
+    let text = macro_call.syntax().ancestors().last()?.text();
+    let mut first_text = text.slice(first).to_string();
+    let second_text = text.slice(second).to_string();
+    if range.is_empty() && first.contains_range(range) {
+        // FIXME: `applySnippetTextEdits` does not support non-empty selection ranges
+        first_text.insert_str((range.start() - first.start()).into(), "$0");
+    }
+
+    let mut edit = TextEditBuilder::default();
+    edit.replace(first, second_text);
+    edit.replace(second, first_text);
+    Some(edit.finish())
+}
+
+fn find_ancestors(
+    item: SyntaxElement,
+    direction: Direction,
+    range: TextRange,
+) -> Option<(SyntaxNode, SyntaxNode)> {
     let root = match item {
         SyntaxElement::Node(node) => node,
         SyntaxElement::Token(token) => token.parent()?,
@@ -94,11 +242,12 @@ fn find_ancestors(item: SyntaxElement, direction: Direction, range: TextRange) -
     move_in_direction(&ancestor, direction, range)
 }
 
+/// Returns the node to move along with the sibling it is swapped with.
 fn move_in_direction(
     node: &SyntaxNode,
     direction: Direction,
     range: TextRange,
-) -> Option<TextEdit> {
+) -> Option<(SyntaxNode, SyntaxNode)> {
     match_ast! {
         match node {
             ast::ArgList(it) => swap_sibling_in_list(node, it.args(), range, direction),
@@ -106,7 +255,7 @@ fn move_in_direction(
             ast::GenericArgList(it) => swap_sibling_in_list(node, it.generic_args(), range, direction),
             ast::VariantList(it) => swap_sibling_in_list(node, it.variants(), range, direction),
             ast::TypeBoundList(it) => swap_sibling_in_list(node, it.bounds(), range, direction),
-            _ => Some(replace_nodes(range, node, &match direction {
+            _ => Some((node.clone(), match direction {
                 Direction::Up => node.prev_sibling(),
                 Direction::Down => node.next_sibling(),
             }?))
@@ -119,14 +268,14 @@ fn swap_sibling_in_list<A: AstNode + Clone, I: Iterator<Item = A>>(
     list: I,
     range: TextRange,
     direction: Direction,
-) -> Option<TextEdit> {
+) -> Option<(SyntaxNode, SyntaxNode)> {
     let list_lookup = list.tuple_windows().find(|(l, r)| match direction {
         Direction::Up => r.syntax().text_range().contains_range(range),
         Direction::Down => l.syntax().text_range().contains_range(range),
     });
 
     if let Some((l, r)) = list_lookup {
-        Some(replace_nodes(range, l.syntax(), r.syntax()))
+        Some((l.syntax().clone(), r.syntax().clone()))
     } else {
         // Cursor is beyond any movable list item (for example, on curly brace in enum).
         // It's not necessary, that parent of list is movable (arg list's parent is not, for example),
@@ -180,7 +329,15 @@ mod tests {
 
     fn check(ra_fixture: &str, expect: Expect, direction: Direction) {
         let (analysis, range) = fixture::range(ra_fixture);
-        let edit = analysis.move_item(range, direction).unwrap().unwrap_or_default();
+        let edit = analysis.move_item(range, direction, false).unwrap().unwrap_or_default();
+        let mut file = analysis.file_text(range.file_id).unwrap().to_string();
+        edit.apply(&mut file);
+        expect.assert_eq(&file);
+    }
+
+    fn check_trait_order(ra_fixture: &str, expect: Expect, direction: Direction) {
+        let (analysis, range) = fixture::range(ra_fixture);
+        let edit = analysis.move_item(range, direction, true).unwrap().unwrap_or_default();
         let mut file = analysis.file_text(range.file_id).unwrap().to_string();
         edit.apply(&mut file);
         expect.assert_eq(&file);
@@ -887,4 +1044,217 @@ fn main() {}
     fn handles_empty_file() {
         check(r#"$0$0"#, expect![[r#""#]], Direction::Up);
     }
+
+    #[test]
+    fn moves_statement_inside_macro_call() {
+        check(
+            r#"
+macro_rules! id { ($($t:tt)*) => { $($t)* } }
+id! {
+    fn main() {
+        let a = 1;$0$0
+        let b = 2;
+    }
+}
+"#,
+            expect![[r#"
+                macro_rules! id { ($($t:tt)*) => { $($t)* } }
+                id! {
+                    fn main() {
+                        let b = 2;
+                        let a = 1;$0
+                    }
+                }
+            "#]],
+            Direction::Down,
+        );
+    }
+
+    #[test]
+    fn moves_arg_inside_macro_call() {
+        check(
+            r#"
+macro_rules! assert_eq { ($l:expr, $r:expr) => { if !($l == $r) { panic!() } } }
+fn main() {
+    assert_eq!(foo(1, 2$0$0), 3);
+}
+"#,
+            expect![[r#"
+                macro_rules! assert_eq { ($l:expr, $r:expr) => { if !($l == $r) { panic!() } } }
+                fn main() {
+                    assert_eq!(foo(2$0, 1), 3);
+                }
+            "#]],
+            Direction::Up,
+        );
+    }
+
+    #[test]
+    fn test_moves_assoc_item_up_to_trait_position() {
+        check_trait_order(
+            r#"
+trait Trait {
+    type T;
+    fn a();
+    fn b();
+    fn c();
+}
+struct S;
+impl Trait for S {
+    fn b() {}
+    fn c() {}
+    fn a$0$0() {}
+    type T = ();
+}
+"#,
+            expect![[r#"
+                trait Trait {
+                    type T;
+                    fn a();
+                    fn b();
+                    fn c();
+                }
+                struct S;
+                impl Trait for S {
+                    fn a$0() {}
+                    fn b() {}
+                    fn c() {}
+                    type T = ();
+                }
+            "#]],
+            Direction::Up,
+        );
+    }
+
+    #[test]
+    fn test_moves_assoc_item_down_to_trait_position() {
+        check_trait_order(
+            r#"
+trait Trait {
+    fn a();
+    fn b();
+    fn c();
+}
+struct S;
+impl Trait for S {
+    fn c$0$0() {}
+    fn a() {}
+    fn b() {}
+}
+"#,
+            expect![[r#"
+                trait Trait {
+                    fn a();
+                    fn b();
+                    fn c();
+                }
+                struct S;
+                impl Trait for S {
+                    fn a() {}
+                    fn b() {}
+                    fn c$0() {}
+                }
+            "#]],
+            Direction::Down,
+        );
+    }
+
+    #[test]
+    fn test_moves_sorted_assoc_item_normally() {
+        check_trait_order(
+            r#"
+trait Trait {
+    fn a();
+    fn b();
+}
+struct S;
+impl Trait for S {
+    fn a() {}
+    fn b$0$0() {}
+}
+"#,
+            expect![[r#"
+                trait Trait {
+                    fn a();
+                    fn b();
+                }
+                struct S;
+                impl Trait for S {
+                    fn b$0() {}
+                    fn a() {}
+                }
+            "#]],
+            Direction::Up,
+        );
+    }
+
+    #[test]
+    fn test_moves_assoc_item_up_without_passing_earlier_trait_item() {
+        check_trait_order(
+            r#"
+trait Trait {
+    fn a();
+    fn b();
+    fn c();
+}
+struct S;
+impl Trait for S {
+    fn c() {}
+    fn a() {}
+    fn b$0$0() {}
+}
+"#,
+            expect![[r#"
+                trait Trait {
+                    fn a();
+                    fn b();
+                    fn c();
+                }
+                struct S;
+                impl Trait for S {
+                    fn c() {}
+                    fn b$0() {}
+                    fn a() {}
+                }
+            "#]],
+            Direction::Up,
+        );
+    }
+
+    #[test]
+    fn test_moves_assoc_item_down_without_passing_later_trait_item() {
+        check_trait_order(
+            r#"
+trait Trait {
+    fn a();
+    fn b();
+    fn c();
+    fn d();
+}
+struct S;
+impl Trait for S {
+    fn b$0$0() {}
+    fn d() {}
+    fn a() {}
+    fn c() {}
+}
+"#,
+            expect![[r#"
+                trait Trait {
+                    fn a();
+                    fn b();
+                    fn c();
+                    fn d();
+                }
+                struct S;
+                impl Trait for S {
+                    fn d() {}
+                    fn b$0() {}
+                    fn a() {}
+                    fn c() {}
+                }
+            "#]],
+            Direction::Down,
+        );
+    }
 }
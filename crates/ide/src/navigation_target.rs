@@ -138,7 +138,7 @@ impl NavigationTarget {
         NavigationTarget::from_syntax(file_id, name, focus_range, range, kind)
     }
 
-    fn from_syntax(
+    pub(crate) fn from_syntax(
         file_id: FileId,
         name: SmolStr,
         focus_range: Option<TextRange>,
@@ -162,10 +162,16 @@ impl TryToNav for FileSymbol {
     fn try_to_nav(&self, db: &RootDatabase) -> Option<NavigationTarget> {
         let full_range = self.loc.original_range(db)?;
         let name_range = self.loc.original_name_range(db)?;
+        // Symbols found through a doc alias are still shown under their real name.
+        let name = if self.is_alias {
+            real_name_of_symbol(db, self).unwrap_or_else(|| self.name.clone())
+        } else {
+            self.name.clone()
+        };
 
         Some(NavigationTarget {
             file_id: full_range.file_id,
-            name: self.name.clone(),
+            name,
             kind: Some(self.kind.into()),
             full_range: full_range.range,
             focus_range: Some(name_range.range),
@@ -554,6 +560,14 @@ pub(crate) fn description_from_symbol(db: &RootDatabase, symbol: &FileSymbol) ->
     }
 }
 
+/// The name a symbol is declared with, for symbols found through a doc alias.
+fn real_name_of_symbol(db: &RootDatabase, symbol: &FileSymbol) -> Option<SmolStr> {
+    let sema = Semantics::new(db);
+    let node = symbol.loc.syntax(&sema)?;
+    let name = ast::AnyHasName::cast(node)?.name()?;
+    Some(name.text().into())
+}
+
 fn orig_focus_range(
     db: &RootDatabase,
     file_id: hir::HirFileId,
@@ -618,4 +632,44 @@ struct Foo;
         let navs = analysis.symbol_search(Query::new("foo".to_string())).unwrap();
         assert_eq!(navs.len(), 2)
     }
+
+    #[test]
+    fn test_world_symbols_by_doc_alias() {
+        let (analysis, _) = fixture::file(
+            r#"
+#[doc(alias = "Quux")]
+struct Foo;
+#[doc(alias("quux_fn", "other"))]
+fn foo() {}
+"#,
+        );
+
+        let navs = analysis.symbol_search(Query::new("quux".to_string())).unwrap();
+        let names: Vec<_> = navs.iter().map(|it| it.name.as_str()).collect();
+        assert_eq!(names, ["Foo", "foo"]);
+    }
+
+    #[test]
+    fn test_world_symbols_filtered_by_kind_and_attr() {
+        let (analysis, _) = fixture::file(
+            r#"
+struct foo_struct;
+fn foo_fn() {}
+#[test]
+fn foo_test() {}
+"#,
+        );
+
+        let mut query = Query::new("foo".to_string());
+        query.kind(hir::symbols::FileSymbolKind::Function);
+        let navs = analysis.symbol_search(query).unwrap();
+        let names: Vec<_> = navs.iter().map(|it| it.name.as_str()).collect();
+        assert_eq!(names, ["foo_fn", "foo_test"]);
+
+        let mut query = Query::new("foo".to_string());
+        query.attr("test".to_string());
+        let navs = analysis.symbol_search(query).unwrap();
+        let names: Vec<_> = navs.iter().map(|it| it.name.as_str()).collect();
+        assert_eq!(names, ["foo_test"]);
+    }
 }
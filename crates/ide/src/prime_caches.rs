@@ -4,7 +4,10 @@
 //! various caches, it's not really advanced at the moment.
 mod topologic_sort;
 
-use std::time::Duration;
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
 
 use hir::db::DefDatabase;
 use ide_db::{
@@ -28,16 +31,23 @@ pub struct ParallelPrimeCachesProgress {
     pub crates_done: usize,
 }
 
+/// Primes the caches of the workspace crates' dependencies, or of `only` and their dependencies
+/// when given. Stops early, without an error, once `cancel` is set.
 pub(crate) fn parallel_prime_caches(
     db: &RootDatabase,
     num_worker_threads: u8,
+    only: Option<&[CrateId]>,
+    cancel: &AtomicBool,
     cb: &(dyn Fn(ParallelPrimeCachesProgress) + Sync),
 ) {
     let _p = profile::span("prime_caches");
 
     let graph = db.crate_graph();
     let mut crates_to_prime = {
-        let crate_ids = compute_crates_to_prime(db, &graph);
+        let crate_ids = match only {
+            Some(only) => compute_crates_to_reprime(&graph, only),
+            None => compute_crates_to_prime(db, &graph),
+        };
 
         let mut builder = topologic_sort::TopologicalSortIter::builder();
 
@@ -96,6 +106,10 @@ pub(crate) fn parallel_prime_caches(
 
     while crates_done < crates_total {
         db.unwind_if_cancelled();
+        if cancel.load(Ordering::Relaxed) {
+            // Dropping the work channel makes the workers exit after their current crate.
+            break;
+        }
 
         for crate_id in &mut crates_to_prime {
             work_sender
@@ -156,3 +170,14 @@ fn compute_crates_to_prime(db: &RootDatabase, graph: &CrateGraph) -> FxHashSet<C
         .flat_map(|id| graph[id].dependencies.iter().map(|krate| krate.crate_id))
         .collect()
 }
+
+fn compute_crates_to_reprime(graph: &CrateGraph, only: &[CrateId]) -> FxHashSet<CrateId> {
+    // The ids may come from an older crate graph, skip the ones that are gone.
+    graph
+        .iter()
+        .filter(|id| only.contains(id))
+        .flat_map(|id| {
+            std::iter::once(id).chain(graph[id].dependencies.iter().map(|krate| krate.crate_id))
+        })
+        .collect()
+}
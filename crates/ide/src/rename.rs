@@ -4,16 +4,21 @@
 //! tests. This module also implements a couple of magic tricks, like renaming
 //! `self` and to `self` (to switch between associated function and method).
 
+use std::{iter, ops};
+
 use hir::{AsAssocItem, InFile, Semantics};
 use ide_db::{
-    base_db::FileId,
+    base_db::{FileId, SourceDatabaseExt, VfsPath},
     defs::{Definition, NameClass, NameRefClass},
     rename::{bail, format_err, source_edit_from_references, IdentifierKind},
     RootDatabase,
 };
 use itertools::Itertools;
 use stdx::{always, never};
-use syntax::{ast, AstNode, SyntaxNode};
+use syntax::{
+    ast::{self, HasAttrs, HasModuleItem, HasName, HasVisibility},
+    AstNode, NodeOrToken, SyntaxKind, SyntaxNode,
+};
 
 use text_edit::TextEdit;
 
@@ -119,6 +124,175 @@ pub(crate) fn will_rename_file(
     Some(change)
 }
 
+/// Called by the client when it is about to move a file to another directory.
+///
+/// Moves the `mod` declaration to the module owning the new directory and rewrites the paths
+/// referring to the module. Modules declared with a `#[path]` attribute keep their place in the
+/// module tree, only the attribute is updated.
+pub(crate) fn will_move_file(
+    db: &RootDatabase,
+    file_id: FileId,
+    new_path: &VfsPath,
+) -> Option<SourceChange> {
+    let sema = Semantics::new(db);
+    let module = sema.to_module_def(file_id)?;
+    let decl = module.declaration_source(db)?;
+    if decl.file_id.is_macro() {
+        return None;
+    }
+    let decl_file_id = decl.file_id.original_file(db);
+    let decl = decl.value;
+    let source_root = db.source_root(db.file_source_root(file_id));
+
+    if let Some(path_attr) = decl.attrs().find(|attr| attr.simple_name().as_deref() == Some("path"))
+    {
+        let literal = match path_attr.expr()? {
+            ast::Expr::Literal(it) => it,
+            _ => return None,
+        };
+        let decl_dir = source_root.path_for_file(&decl_file_id)?.parent()?;
+        let new_value = relative_path(&decl_dir, new_path)?;
+        let edit = TextEdit::replace(literal.syntax().text_range(), format!("{:?}", new_value));
+        return Some(SourceChange::from_text_edit(decl_file_id, edit));
+    }
+
+    let (name, ext) = new_path.name_and_extension()?;
+    if ext != Some("rs") {
+        return None;
+    }
+    // `dir/foo.rs` is declared by the module owning `dir`, `dir/foo/mod.rs` as well.
+    let (new_name, parent_dir) = match name {
+        "mod" => {
+            let dir = new_path.parent()?;
+            (dir.name_and_extension()?.0.to_string(), dir.parent()?)
+        }
+        "lib" | "main" => return None,
+        _ => (name.to_string(), new_path.parent()?),
+    };
+    if IdentifierKind::classify(&new_name).ok()? != IdentifierKind::Ident {
+        return None;
+    }
+
+    let sibling = parent_dir
+        .name_and_extension()
+        .and_then(|(name, _)| parent_dir.parent()?.join(&format!("{}.rs", name)));
+    let new_parent = ["mod.rs", "lib.rs", "main.rs"]
+        .iter()
+        .filter_map(|file| parent_dir.join(file))
+        .chain(sibling)
+        .filter_map(|path| source_root.file_for_path(&path).copied())
+        .filter_map(|file_id| sema.to_module_def(file_id))
+        .find(|it| it.krate() == module.krate())?;
+    if Some(new_parent) == module.parent(db) {
+        return will_rename_file(db, file_id, &new_name);
+    }
+    let new_parent_file_id = new_parent.definition_source(db).file_id.original_file(db);
+
+    let def = Definition::Module(module);
+    let usages = def.usages(&sema).all();
+    let mut change = SourceChange::default();
+
+    // Move the declaration, keeping its attributes and visibility.
+    let decl_range = match decl.syntax().next_sibling_or_token() {
+        Some(NodeOrToken::Token(ws)) if ws.kind() == SyntaxKind::WHITESPACE => {
+            decl.syntax().text_range().cover(ws.text_range())
+        }
+        _ => decl.syntax().text_range(),
+    };
+    change.insert_source_edit(decl_file_id, TextEdit::delete(decl_range));
+    let new_decl = {
+        let start = decl.syntax().text_range().start();
+        let name_range = decl.name()?.syntax().text_range() - start;
+        let mut text = decl.syntax().text().to_string();
+        text.replace_range(ops::Range::<usize>::from(name_range), &new_name);
+        // A private module is no longer visible to its old siblings.
+        let used_outside = usages.iter().any(|(&file_id, _)| {
+            sema.to_module_def(file_id).map_or(false, |it| {
+                let path = it.path_to_root(db);
+                !path.contains(&new_parent) && !path.contains(&module)
+            })
+        });
+        if decl.visibility().is_none() && used_outside {
+            let mod_offset = decl.mod_token()?.text_range().start() - start;
+            text.insert_str(mod_offset.into(), "pub(crate) ");
+        }
+        text
+    };
+    let parent_source = sema.parse(new_parent_file_id);
+    let last_mod = parent_source.items().filter(|it| matches!(it, ast::Item::Module(_))).last();
+    let anchor = last_mod
+        .or_else(|| parent_source.items().filter(|it| matches!(it, ast::Item::Use(_))).last());
+    let insert = match anchor {
+        Some(item) => TextEdit::insert(item.syntax().text_range().end(), format!("\n{}", new_decl)),
+        None => match parent_source.attrs().filter(|attr| attr.kind().is_inner()).last() {
+            Some(attr) => {
+                TextEdit::insert(attr.syntax().text_range().end(), format!("\n\n{}", new_decl))
+            }
+            None if parent_source.syntax().text_range().is_empty() => {
+                TextEdit::insert(0.into(), format!("{}\n", new_decl))
+            }
+            None => TextEdit::insert(0.into(), format!("{}\n\n", new_decl)),
+        },
+    };
+    change.insert_source_edit(new_parent_file_id, insert);
+
+    // Point the references to the new location.
+    let new_path_text = new_parent
+        .path_to_root(db)
+        .into_iter()
+        .rev()
+        .filter_map(|it| it.name(db))
+        .map(|it| it.to_string())
+        .chain(iter::once(new_name))
+        .fold(String::from("crate"), |acc, it| format!("{}::{}", acc, it));
+    for (file_id, references) in usages {
+        let mut edit = TextEdit::builder();
+        for reference in references {
+            let name_ref = match &reference.name {
+                ast::NameLike::NameRef(it) if it.syntax().text_range() == reference.range => it,
+                _ => continue,
+            };
+            let path = match name_ref.syntax().ancestors().find_map(ast::Path::cast) {
+                Some(it) => it,
+                None => continue,
+            };
+            match sema.to_module_def(file_id) {
+                Some(it) if it.krate() == module.krate() => (),
+                _ => continue,
+            }
+            // FIXME: nested use trees would need to be split up to be rewritten.
+            if path
+                .top_path()
+                .syntax()
+                .ancestors()
+                .nth(2)
+                .and_then(ast::UseTreeList::cast)
+                .is_some()
+            {
+                continue;
+            }
+            edit.replace(path.syntax().text_range(), new_path_text.clone());
+        }
+        change.insert_source_edit(file_id, edit.finish());
+    }
+
+    Some(change)
+}
+
+fn relative_path(base: &VfsPath, path: &VfsPath) -> Option<String> {
+    let mut ancestor = base.clone();
+    let mut depth = 0;
+    while !path.starts_with(&ancestor) {
+        if !ancestor.pop() {
+            return None;
+        }
+        depth += 1;
+    }
+    let (ancestor, path) = (ancestor.to_string(), path.to_string());
+    let rest = path.strip_prefix(&ancestor)?.trim_start_matches(|c| c == '/' || c == '\\');
+    Some(format!("{}{}", "../".repeat(depth), rest.replace('\\', "/")))
+}
+
 fn find_definitions(
     sema: &Semantics<RootDatabase>,
     syntax: &SyntaxNode,
@@ -326,7 +500,9 @@ fn text_edit_from_self_param(self_param: &ast::SelfParam, new_name: &str) -> Opt
 #[cfg(test)]
 mod tests {
     use expect_test::{expect, Expect};
-    use stdx::trim_indent;
+    use ide_db::base_db::{SourceDatabaseExt, VfsPath};
+    use itertools::Itertools;
+    use stdx::{format_to, trim_indent};
     use test_utils::assert_eq_text;
     use text_edit::TextEdit;
 
@@ -379,6 +555,30 @@ mod tests {
         expect.assert_debug_eq(&source_change)
     }
 
+    #[track_caller]
+    fn check_will_move(new_path: &str, ra_fixture_before: &str, ra_fixture_after: &str) {
+        let (analysis, position) = fixture::position(ra_fixture_before);
+        let new_path = VfsPath::new_virtual_path(new_path.to_string());
+        let source_change = analysis
+            .will_move_file(position.file_id, &new_path)
+            .unwrap()
+            .expect("expected a source change");
+        let mut result = String::new();
+        for (file_id, edit) in source_change.source_file_edits.into_iter().sorted_by_key(|it| it.0)
+        {
+            let path = analysis
+                .with_db("source_root_path", |db| {
+                    let source_root = db.source_root(db.file_source_root(file_id));
+                    source_root.path_for_file(&file_id).unwrap().to_string()
+                })
+                .unwrap();
+            let mut text = analysis.file_text(file_id).unwrap().to_string();
+            edit.apply(&mut text);
+            format_to!(result, "//- {}\n{}", path, text);
+        }
+        assert_eq_text!(&trim_indent(ra_fixture_after), &result);
+    }
+
     fn check_prepare(ra_fixture: &str, expect: Expect) {
         let (analysis, position) = fixture::position(ra_fixture);
         let result = analysis
@@ -2203,6 +2403,86 @@ fn foo((bar | bar | bar): ()) {
     bar;
     let foo;
 }
+"#,
+        );
+    }
+
+    #[test]
+    fn test_will_move_file_to_other_dir() {
+        check_will_move(
+            "/bar/baz.rs",
+            r#"
+//- /main.rs
+mod foo;
+mod bar;
+
+use foo::Foo;
+
+fn main() {
+    let _ = foo::Foo;
+}
+//- /foo.rs
+$0pub struct Foo;
+//- /bar/mod.rs
+pub struct Bar;
+"#,
+            r#"
+//- /main.rs
+mod bar;
+
+use crate::bar::baz::Foo;
+
+fn main() {
+    let _ = crate::bar::baz::Foo;
+}
+//- /bar/mod.rs
+pub(crate) mod baz;
+
+pub struct Bar;
+"#,
+        );
+    }
+
+    #[test]
+    fn test_will_move_file_to_parent_dir() {
+        check_will_move(
+            "/foo.rs",
+            r#"
+//- /main.rs
+mod a;
+
+use a::foo::Foo;
+//- /a/mod.rs
+pub mod foo;
+//- /a/foo.rs
+$0pub struct Foo;
+"#,
+            r#"
+//- /main.rs
+mod a;
+pub mod foo;
+
+use crate::foo::Foo;
+//- /a/mod.rs
+"#,
+        );
+    }
+
+    #[test]
+    fn test_will_move_file_with_path_attr() {
+        check_will_move(
+            "/baz/foo.rs",
+            r#"
+//- /main.rs
+#[path = "foo.rs"]
+mod bar;
+//- /foo.rs
+$0pub struct Foo;
+"#,
+            r#"
+//- /main.rs
+#[path = "baz/foo.rs"]
+mod bar;
 "#,
         );
     }
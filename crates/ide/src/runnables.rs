@@ -2,7 +2,7 @@ use std::fmt;
 
 use ast::HasName;
 use cfg::CfgExpr;
-use hir::{AsAssocItem, HasAttrs, HasSource, HirDisplay, Semantics};
+use hir::{AsAssocItem, HasAttrs, HasSource, Semantics};
 use ide_assists::utils::test_related_attribute;
 use ide_db::{
     base_db::{FilePosition, FileRange},
@@ -14,8 +14,10 @@ use ide_db::{
 use itertools::Itertools;
 use stdx::{always, format_to};
 use syntax::{
-    ast::{self, AstNode, HasAttrs as _},
-    SmolStr, SyntaxNode,
+    ast::{self, AstNode, HasArgList, HasAttrs as _, HasModuleItem},
+    AstToken, SmolStr,
+    SyntaxKind::IDENT,
+    SyntaxNode, T,
 };
 
 use crate::{references, FileId, NavigationTarget, ToNav, TryToNav};
@@ -32,23 +34,40 @@ pub struct Runnable {
 pub enum TestId {
     Name(SmolStr),
     Path(String),
+    /// The path of a function that expands to a module of tests, like the ones annotated with
+    /// `#[test_case]`, matching all tests in that module.
+    Prefix(String),
 }
 
 impl fmt::Display for TestId {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             TestId::Name(name) => name.fmt(f),
-            TestId::Path(path) => path.fmt(f),
+            TestId::Path(path) | TestId::Prefix(path) => path.fmt(f),
         }
     }
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum RunnableKind {
-    Test { test_id: TestId, attr: TestAttr },
-    TestMod { path: String },
-    Bench { test_id: TestId },
-    DocTest { test_id: TestId },
+    Test {
+        test_id: TestId,
+        attr: TestAttr,
+    },
+    TestMod {
+        path: String,
+    },
+    Bench {
+        test_id: TestId,
+    },
+    /// Benchmarks run by a custom harness like criterion, matching benchmark ids against the
+    /// `filter` regex instead of test paths. Runs all benchmarks of the target without a filter.
+    BenchHarness {
+        filter: Option<String>,
+    },
+    DocTest {
+        test_id: TestId,
+    },
     Bin,
 }
 
@@ -69,6 +88,8 @@ impl Runnable {
             RunnableKind::Test { test_id, .. } => format!("test {}", test_id),
             RunnableKind::TestMod { path } => format!("test-mod {}", path),
             RunnableKind::Bench { test_id } => format!("bench {}", test_id),
+            RunnableKind::BenchHarness { filter: Some(filter) } => format!("bench {}", filter),
+            RunnableKind::BenchHarness { filter: None } => "bench".to_string(),
             RunnableKind::DocTest { test_id, .. } => format!("doctest {}", test_id),
             RunnableKind::Bin => {
                 target.map_or_else(|| "run binary".to_string(), |t| format!("run {}", t))
@@ -88,7 +109,8 @@ impl Runnable {
             RunnableKind::TestMod { .. } => "Tests",
             RunnableKind::Test { .. } => "Test",
             RunnableKind::DocTest { .. } => "Doctest",
-            RunnableKind::Bench { .. } => "Bench",
+            RunnableKind::Bench { .. } | RunnableKind::BenchHarness { filter: Some(_) } => "Bench",
+            RunnableKind::BenchHarness { filter: None } => "Benchmarks",
             RunnableKind::Bin => return s,
         };
         s.push_str(suffix);
@@ -101,7 +123,9 @@ impl Runnable {
             RunnableKind::TestMod { .. } => RunnableTestKind::TestMod,
             RunnableKind::Test { .. } => RunnableTestKind::Test,
             RunnableKind::DocTest { .. } => RunnableTestKind::DocTest,
-            RunnableKind::Bench { .. } => RunnableTestKind::Bench,
+            RunnableKind::Bench { .. } | RunnableKind::BenchHarness { .. } => {
+                RunnableTestKind::Bench
+            }
             RunnableKind::Bin => RunnableTestKind::Bin,
         }
     }
@@ -181,6 +205,8 @@ pub(crate) fn runnables(db: &RootDatabase, file_id: FileId) -> Vec<Runnable> {
         .map(|it| runnable_mod_outline_definition(&sema, it))
         .for_each(|it| add_opt(it, None));
 
+    criterion_runnables(&sema, file_id).into_iter().for_each(|it| add_opt(Some(it), None));
+
     res.extend(in_macro_expansion.into_iter().flat_map(|(_, runnables)| {
         let use_name_in_title = runnables.len() != 1;
         runnables.into_iter().map(move |mut r| {
@@ -313,19 +339,21 @@ pub(crate) fn runnable_fn(sema: &Semantics<RootDatabase>, def: hir::Function) ->
     let kind = if name == "main" && def.module(sema.db) == root {
         RunnableKind::Bin
     } else {
-        let test_id = || {
+        let test_id = |path_kind: fn(String) -> TestId| {
             let canonical_path = {
                 let def: hir::ModuleDef = def.into();
                 def.canonical_path(sema.db)
             };
-            canonical_path.map(TestId::Path).unwrap_or(TestId::Name(name))
+            canonical_path.map(path_kind).unwrap_or(TestId::Name(name))
         };
 
-        if test_related_attribute(&func.value).is_some() {
+        if let Some(test_attr) = test_related_attribute(&func.value) {
             let attr = TestAttr::from_fn(&func.value);
-            RunnableKind::Test { test_id: test_id(), attr }
-        } else if func.value.has_atom_attr("bench") {
-            RunnableKind::Bench { test_id: test_id() }
+            let path_kind =
+                if is_parameterized_test_attr(&test_attr) { TestId::Prefix } else { TestId::Path };
+            RunnableKind::Test { test_id: test_id(path_kind), attr }
+        } else if func.value.attrs().any(|it| is_bench_attr(&it)) {
+            RunnableKind::Bench { test_id: test_id(TestId::Path) }
         } else {
             return None;
         }
@@ -336,7 +364,7 @@ pub(crate) fn runnable_fn(sema: &Semantics<RootDatabase>, def: hir::Function) ->
         func.as_ref().map(|it| it as &dyn ast::HasName),
         SymbolKind::Function,
     );
-    let cfg = def.attrs(sema.db).cfg();
+    let cfg = cfg_with_parents(sema.db, def.into(), def.attrs(sema.db).cfg());
     Some(Runnable { use_name_in_title: false, nav, kind, cfg })
 }
 
@@ -347,8 +375,7 @@ pub(crate) fn runnable_mod(sema: &Semantics<RootDatabase>, def: hir::Module) ->
     let path =
         def.path_to_root(sema.db).into_iter().rev().filter_map(|it| it.name(sema.db)).join("::");
 
-    let attrs = def.attrs(sema.db);
-    let cfg = attrs.cfg();
+    let cfg = cfg_with_parents(sema.db, def.into(), def.attrs(sema.db).cfg());
     let nav = NavigationTarget::from_module_to_decl(sema.db, def);
     Some(Runnable { use_name_in_title: false, nav, kind: RunnableKind::TestMod { path }, cfg })
 }
@@ -358,21 +385,158 @@ pub(crate) fn runnable_impl(sema: &Semantics<RootDatabase>, def: &hir::Impl) ->
     if !has_runnable_doc_test(&attrs) {
         return None;
     }
-    let cfg = attrs.cfg();
+    let cfg = cfg_with_parents(sema.db, Definition::SelfType(*def), attrs.cfg());
     let nav = def.try_to_nav(sema.db)?;
-    let ty = def.self_ty(sema.db);
-    let adt_name = ty.as_adt()?.name(sema.db);
-    let mut ty_args = ty.type_arguments().peekable();
-    let params = if ty_args.peek().is_some() {
-        format!("<{}>", ty_args.format_with(", ", |ty, cb| cb(&ty.display(sema.db))))
-    } else {
-        String::new()
-    };
-    let test_id = TestId::Path(format!("{}{}", adt_name, params));
+    let mut path = String::new();
+    def.module(sema.db)
+        .path_to_root(sema.db)
+        .into_iter()
+        .rev()
+        .flat_map(|it| it.name(sema.db))
+        .for_each(|name| format_to!(path, "{}::", name));
+    format_to!(path, "{}", impl_doctest_name(sema.db, *def)?);
+    let test_id = TestId::Path(path);
 
     Some(Runnable { use_name_in_title: false, nav, kind: RunnableKind::DocTest { test_id }, cfg })
 }
 
+/// Creates runnables for the benchmarks registered with criterion's `criterion_group!` and
+/// `criterion_main!` macros, which are run by criterion's own harness instead of libtest.
+fn criterion_runnables(sema: &Semantics<RootDatabase>, file_id: FileId) -> Vec<Runnable> {
+    let source_file = sema.parse(file_id);
+    let mut res = Vec::new();
+    for item in source_file.items() {
+        let call = match item {
+            ast::Item::MacroCall(it) => it,
+            _ => continue,
+        };
+        let macro_name = match call.path().and_then(|it| it.segment()?.name_ref()) {
+            Some(it) => it,
+            None => continue,
+        };
+        match macro_name.text().as_str() {
+            "criterion_main" => {
+                let nav = NavigationTarget::from_syntax(
+                    file_id,
+                    macro_name.text().into(),
+                    Some(macro_name.syntax().text_range()),
+                    call.syntax().text_range(),
+                    SymbolKind::Macro,
+                );
+                let kind = RunnableKind::BenchHarness { filter: None };
+                res.push(Runnable { use_name_in_title: false, nav, kind, cfg: None });
+            }
+            "criterion_group" => {
+                for target in criterion_group_targets(&call) {
+                    let func = source_file.items().find_map(|item| match item {
+                        ast::Item::Fn(it) if it.name()?.text() == target.as_str() => Some(it),
+                        _ => None,
+                    });
+                    if let Some(runnable) = func.and_then(|it| runnable_criterion_fn(sema, &it)) {
+                        res.push(runnable);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    res
+}
+
+/// Returns the names of the benchmark functions registered by a `criterion_group!` call, which
+/// comes in the forms `criterion_group!(benches, a, b)` and
+/// `criterion_group! { name = benches; config = ..; targets = a, b }`.
+fn criterion_group_targets(call: &ast::MacroCall) -> Vec<SmolStr> {
+    let tokens: Vec<_> = match call.token_tree() {
+        Some(tt) => tt
+            .token_trees_and_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|it| !it.kind().is_trivia())
+            .collect(),
+        None => return Vec::new(),
+    };
+    // Skip the delimiters of the token tree.
+    let tokens = match tokens.get(1..tokens.len().saturating_sub(1)) {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    let targets = match tokens.iter().position(|it| it.text() == "targets") {
+        Some(idx) => &tokens[idx + 1..],
+        None => match tokens.iter().position(|it| it.kind() == T![,]) {
+            Some(idx) => &tokens[idx + 1..],
+            None => return Vec::new(),
+        },
+    };
+    targets
+        .iter()
+        .take_while(|it| it.kind() != T![;])
+        .collect::<Vec<_>>()
+        .split(|it| it.kind() == T![,])
+        .filter_map(|path| path.iter().rev().find(|it| it.kind() == IDENT))
+        .map(|it| it.text().into())
+        .collect()
+}
+
+fn runnable_criterion_fn(sema: &Semantics<RootDatabase>, func: &ast::Fn) -> Option<Runnable> {
+    // Criterion filters by the ids the benchmarks are registered under, so look for the
+    // literal ones passed to `bench_function` and friends.
+    let ids: Vec<_> = func
+        .body()?
+        .syntax()
+        .descendants()
+        .filter_map(ast::MethodCallExpr::cast)
+        .filter(|call| {
+            call.name_ref().map_or(false, |it| {
+                matches!(it.text().as_str(), "bench_function" | "benchmark_group")
+            })
+        })
+        .filter_map(|call| match call.arg_list()?.args().next()? {
+            ast::Expr::Literal(lit) => Some(ast::String::cast(lit.token())?.value()?.into_owned()),
+            _ => None,
+        })
+        .map(|id| escape_regex(&id))
+        .collect();
+    if ids.is_empty() {
+        return None;
+    }
+
+    let def = sema.to_def(func)?;
+    let nav = NavigationTarget::from_named(
+        sema.db,
+        def.source(sema.db)?.as_ref().map(|it| it as &dyn ast::HasName),
+        SymbolKind::Function,
+    );
+    let kind = RunnableKind::BenchHarness { filter: Some(ids.join("|")) };
+    let cfg = cfg_with_parents(sema.db, def.into(), def.attrs(sema.db).cfg());
+    Some(Runnable { use_name_in_title: false, nav, kind, cfg })
+}
+
+fn escape_regex(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$#&-~".contains(c) {
+            res.push('\\');
+        }
+        res.push(c);
+    }
+    res
+}
+
+/// Matches `#[bench]` as well as the attributes of benchmark frameworks following libtest's
+/// naming, like `#[divan::bench]`.
+fn is_bench_attr(attr: &ast::Attr) -> bool {
+    attr.path()
+        .and_then(|it| it.segment()?.name_ref())
+        .map_or(false, |name_ref| name_ref.text() == "bench")
+}
+
+/// Test attributes that turn the function into a module containing one test per case.
+fn is_parameterized_test_attr(attr: &ast::Attr) -> bool {
+    attr.path()
+        .and_then(|it| it.segment()?.name_ref())
+        .map_or(false, |name_ref| matches!(name_ref.text().as_str(), "test_case" | "test_matrix"))
+}
+
 /// Creates a test mod runnable for outline modules at the top of their definition.
 fn runnable_mod_outline_definition(
     sema: &Semantics<RootDatabase>,
@@ -384,8 +548,7 @@ fn runnable_mod_outline_definition(
     let path =
         def.path_to_root(sema.db).into_iter().rev().filter_map(|it| it.name(sema.db)).join("::");
 
-    let attrs = def.attrs(sema.db);
-    let cfg = attrs.cfg();
+    let cfg = cfg_with_parents(sema.db, def.into(), def.attrs(sema.db).cfg());
     match def.definition_source(sema.db).value {
         hir::ModuleSource::SourceFile(_) => Some(Runnable {
             use_name_in_title: false,
@@ -423,21 +586,8 @@ fn module_def_doctest(db: &RootDatabase, def: Definition) -> Option<Runnable> {
         // This probably belongs to canonical_path?
         if let Some(assoc_item) = def.as_assoc_item(db) {
             if let hir::AssocItemContainer::Impl(imp) = assoc_item.container(db) {
-                let ty = imp.self_ty(db);
-                if let Some(adt) = ty.as_adt() {
-                    let name = adt.name(db);
-                    let mut ty_args = ty.type_arguments().peekable();
-                    format_to!(path, "{}", name);
-                    if ty_args.peek().is_some() {
-                        format_to!(
-                            path,
-                            "<{}>",
-                            ty_args.format_with(", ", |ty, cb| cb(&ty.display(db)))
-                        );
-                    }
-                    format_to!(path, "::{}", def_name);
-                    return Some(path);
-                }
+                format_to!(path, "{}::{}", impl_doctest_name(db, imp)?, def_name);
+                return Some(path);
             }
         }
         format_to!(path, "{}", def_name);
@@ -458,11 +608,48 @@ fn module_def_doctest(db: &RootDatabase, def: Definition) -> Option<Runnable> {
         use_name_in_title: false,
         nav,
         kind: RunnableKind::DocTest { test_id },
-        cfg: attrs.cfg(),
+        cfg: cfg_with_parents(db, def, attrs.cfg()),
     };
     Some(res)
 }
 
+/// Combines the cfg of an item with the ones of the modules and the impl or trait it is nested
+/// in, as all of them have to be enabled for the item to be compiled.
+fn cfg_with_parents(db: &RootDatabase, def: Definition, cfg: Option<CfgExpr>) -> Option<CfgExpr> {
+    let module = match def {
+        Definition::Module(it) => it.parent(db),
+        _ => def.module(db),
+    };
+    let mut cfgs: Vec<_> = module
+        .into_iter()
+        .flat_map(|it| it.path_to_root(db))
+        .filter_map(|it| it.attrs(db).cfg())
+        .collect();
+    // `path_to_root` starts at the innermost module.
+    cfgs.reverse();
+    if let Some(assoc_item) = def.as_assoc_item(db) {
+        let container_cfg = match assoc_item.container(db) {
+            hir::AssocItemContainer::Impl(it) => it.attrs(db).cfg(),
+            hir::AssocItemContainer::Trait(it) => it.attrs(db).cfg(),
+        };
+        cfgs.extend(container_cfg);
+    }
+    cfgs.extend(cfg);
+    match cfgs.len() {
+        0 | 1 => cfgs.pop(),
+        _ => Some(CfgExpr::All(cfgs)),
+    }
+}
+
+/// Rustdoc names the doctests of an impl and its items after the impl's self type as it is
+/// written in the source, with all whitespace removed, e.g. `Foo<'a,T>::new`.
+fn impl_doctest_name(db: &RootDatabase, imp: hir::Impl) -> Option<String> {
+    let self_ty = imp.source(db)?.value.self_ty()?;
+    let mut name = self_ty.syntax().text().to_string();
+    name.retain(|c| !c.is_whitespace());
+    Some(name)
+}
+
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
 pub struct TestAttr {
     pub ignore: bool,
@@ -1326,6 +1513,89 @@ fn test_foo1() {}
         );
     }
 
+    #[test]
+    fn test_runnables_with_features_of_enclosing_items() {
+        check(
+            r#"
+//- /lib.rs crate:foo cfg:feature=foo,feature=bar
+$0
+#[cfg(feature = "foo")]
+mod tests {
+    #[test]
+    #[cfg(feature = "bar")]
+    fn test_foo1() {}
+}
+"#,
+            &[TestMod, Test],
+            expect![[r#"
+                [
+                    Runnable {
+                        use_name_in_title: false,
+                        nav: NavigationTarget {
+                            file_id: FileId(
+                                0,
+                            ),
+                            full_range: 1..100,
+                            focus_range: 29..34,
+                            name: "tests",
+                            kind: Module,
+                            description: "mod tests",
+                        },
+                        kind: TestMod {
+                            path: "tests",
+                        },
+                        cfg: Some(
+                            Atom(
+                                KeyValue {
+                                    key: "feature",
+                                    value: "foo",
+                                },
+                            ),
+                        ),
+                    },
+                    Runnable {
+                        use_name_in_title: false,
+                        nav: NavigationTarget {
+                            file_id: FileId(
+                                0,
+                            ),
+                            full_range: 41..98,
+                            focus_range: 84..93,
+                            name: "test_foo1",
+                            kind: Function,
+                        },
+                        kind: Test {
+                            test_id: Path(
+                                "tests::test_foo1",
+                            ),
+                            attr: TestAttr {
+                                ignore: false,
+                            },
+                        },
+                        cfg: Some(
+                            All(
+                                [
+                                    Atom(
+                                        KeyValue {
+                                            key: "feature",
+                                            value: "foo",
+                                        },
+                                    ),
+                                    Atom(
+                                        KeyValue {
+                                            key: "feature",
+                                            value: "bar",
+                                        },
+                                    ),
+                                ],
+                            ),
+                        ),
+                    },
+                ]
+            "#]],
+        );
+    }
+
     #[test]
     fn test_runnables_no_test_function_in_module() {
         check(
@@ -2078,7 +2348,67 @@ impl<T, U> Foo<T, U> {
                         },
                         kind: DocTest {
                             test_id: Path(
-                                "Foo<T, U>::t",
+                                "Foo<T,U>::t",
+                            ),
+                        },
+                        cfg: None,
+                    },
+                ]
+            "#]],
+        );
+    }
+
+    #[test]
+    fn doc_test_generic_impl_in_module() {
+        check(
+            r#"
+//- /lib.rs
+$0
+mod foo {
+    struct Foo<'a, T>(&'a T);
+
+    /// ```
+    /// ```
+    impl<'a, T: Clone> Foo<'a, Vec<T>> {
+        /// ```
+        /// ```
+        fn new() {}
+    }
+}
+"#,
+            &[DocTest, DocTest],
+            expect![[r#"
+                [
+                    Runnable {
+                        use_name_in_title: false,
+                        nav: NavigationTarget {
+                            file_id: FileId(
+                                0,
+                            ),
+                            full_range: 46..164,
+                            focus_range: 89..104,
+                            name: "impl",
+                            kind: Impl,
+                        },
+                        kind: DocTest {
+                            test_id: Path(
+                                "foo::Foo<'a,Vec<T>>",
+                            ),
+                        },
+                        cfg: None,
+                    },
+                    Runnable {
+                        use_name_in_title: false,
+                        nav: NavigationTarget {
+                            file_id: FileId(
+                                0,
+                            ),
+                            full_range: 115..158,
+                            name: "new",
+                        },
+                        kind: DocTest {
+                            test_id: Path(
+                                "foo::Foo<'a,Vec<T>>::new",
                             ),
                         },
                         cfg: None,
@@ -2151,4 +2481,164 @@ macro_rules! foo {
             "#]],
         );
     }
+
+    #[test]
+    fn parameterized_tests_and_custom_bench_attrs() {
+        check(
+            r#"
+//- /lib.rs
+$0
+#[test_case(1 ; "one")]
+#[test_case(2 ; "two")]
+fn cases(_: u32) {}
+
+#[divan::bench]
+fn divan_bench() {}
+"#,
+            &[TestMod, Test, Bench],
+            expect![[r#"
+                [
+                    Runnable {
+                        use_name_in_title: false,
+                        nav: NavigationTarget {
+                            file_id: FileId(
+                                0,
+                            ),
+                            full_range: 0..106,
+                            name: "",
+                            kind: Module,
+                        },
+                        kind: TestMod {
+                            path: "",
+                        },
+                        cfg: None,
+                    },
+                    Runnable {
+                        use_name_in_title: false,
+                        nav: NavigationTarget {
+                            file_id: FileId(
+                                0,
+                            ),
+                            full_range: 1..68,
+                            focus_range: 52..57,
+                            name: "cases",
+                            kind: Function,
+                        },
+                        kind: Test {
+                            test_id: Prefix(
+                                "cases",
+                            ),
+                            attr: TestAttr {
+                                ignore: false,
+                            },
+                        },
+                        cfg: None,
+                    },
+                    Runnable {
+                        use_name_in_title: false,
+                        nav: NavigationTarget {
+                            file_id: FileId(
+                                0,
+                            ),
+                            full_range: 70..105,
+                            focus_range: 89..100,
+                            name: "divan_bench",
+                            kind: Function,
+                        },
+                        kind: Bench {
+                            test_id: Path(
+                                "divan_bench",
+                            ),
+                        },
+                        cfg: None,
+                    },
+                ]
+            "#]],
+        );
+    }
+
+    #[test]
+    fn criterion_benches() {
+        check(
+            r#"
+//- /lib.rs
+$0
+fn plain(c: &mut Criterion) {
+    c.bench_function("fib 20", |b| b.iter(|| fib(20)));
+}
+
+fn grouped(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sizes");
+    group.finish();
+}
+
+fn no_ids(c: &mut Criterion) {}
+
+criterion_group!(benches, plain, self::no_ids);
+criterion_group! {
+    name = other;
+    config = Criterion::default();
+    targets = grouped
+}
+criterion_main!(benches, other);
+"#,
+            &[Bench, Bench, Bench],
+            expect![[r#"
+                [
+                    Runnable {
+                        use_name_in_title: false,
+                        nav: NavigationTarget {
+                            file_id: FileId(
+                                0,
+                            ),
+                            full_range: 1..88,
+                            focus_range: 4..9,
+                            name: "plain",
+                            kind: Function,
+                        },
+                        kind: BenchHarness {
+                            filter: Some(
+                                "fib 20",
+                            ),
+                        },
+                        cfg: None,
+                    },
+                    Runnable {
+                        use_name_in_title: false,
+                        nav: NavigationTarget {
+                            file_id: FileId(
+                                0,
+                            ),
+                            full_range: 90..191,
+                            focus_range: 93..100,
+                            name: "grouped",
+                            kind: Function,
+                        },
+                        kind: BenchHarness {
+                            filter: Some(
+                                "sizes",
+                            ),
+                        },
+                        cfg: None,
+                    },
+                    Runnable {
+                        use_name_in_title: false,
+                        nav: NavigationTarget {
+                            file_id: FileId(
+                                0,
+                            ),
+                            full_range: 370..402,
+                            focus_range: 370..384,
+                            name: "criterion_main",
+                            kind: Macro,
+                        },
+                        kind: BenchHarness {
+                            filter: None,
+                        },
+                        cfg: None,
+                    },
+                ]
+            "#]],
+        );
+    }
 }
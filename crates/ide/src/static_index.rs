@@ -109,15 +109,22 @@ impl StaticIndex<'_> {
                     type_hints: true,
                     parameter_hints: true,
                     chaining_hints: true,
+                    generic_arg_hints: crate::GenericArgHints {
+                        method_calls: false,
+                        path_calls: false,
+                        trait_calls: false,
+                    },
                     closure_return_type_hints: true,
                     lifetime_elision_hints: crate::LifetimeElisionHints::Never,
                     reborrow_hints: crate::ReborrowHints::Never,
+                    receiver_borrow_hints: crate::ReborrowHints::Never,
                     hide_named_constructor_hints: false,
                     hide_closure_initialization_hints: false,
                     param_names_for_lifetime_elision_hints: false,
                     binding_mode_hints: false,
                     max_length: Some(25),
                     closing_brace_hints_min_lines: Some(25),
+                    fields_to_resolve: crate::InlayFieldsToResolve::empty(),
                 },
                 file_id,
                 None,
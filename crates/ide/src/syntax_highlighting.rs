@@ -133,7 +133,7 @@ pub struct HlRange {
 // attribute:: Emitted for tokens inside attributes.
 // callable:: Emitted for locals whose types implements one of the `Fn*` traits.
 // constant:: Emitted for consts.
-// consuming:: Emitted for locals that are being consumed when use in a function call.
+// consuming:: Emitted for non-Copy locals that are moved into a function call, a method taking `self` by value or a `move` closure, and for such methods.
 // controlFlow:: Emitted for control-flow related tokens, this includes the `?` operator.
 // crateRoot:: Emitted for crate names, like `serde` and `crate`.
 // declaration:: Emitted for names of definitions, like `foo` in `fn foo() {}`.
@@ -227,7 +227,7 @@ fn highlight_name_ref(
             let mut h = highlight_def(sema, krate, def);
 
             match def {
-                Definition::Local(local) if is_consumed_lvalue(name_ref.syntax(), &local, db) => {
+                Definition::Local(local) if is_consumed_lvalue(sema, name_ref.syntax(), &local) => {
                     h |= HlMod::Consuming;
                 }
                 Definition::Trait(trait_) if trait_.is_unsafe(db) => {
@@ -649,10 +649,47 @@ fn highlight_name_ref_by_syntax(
     }
 }
 
-fn is_consumed_lvalue(node: &SyntaxNode, local: &hir::Local, db: &RootDatabase) -> bool {
-    // When lvalues are passed as arguments and they're not Copy, then mark them as Consuming.
-    parents_match(node.clone().into(), &[PATH_SEGMENT, PATH, PATH_EXPR, ARG_LIST])
-        && !local.ty(db).is_copy(db)
+fn is_consumed_lvalue(
+    sema: &Semantics<RootDatabase>,
+    node: &SyntaxNode,
+    local: &hir::Local,
+) -> bool {
+    // When lvalues are passed as arguments, used as the receiver of a method taking `self` by
+    // value or captured by a `move` closure and they're not Copy, then mark them as Consuming.
+    let is_consumed =
+        parents_match(node.clone().into(), &[PATH_SEGMENT, PATH, PATH_EXPR, ARG_LIST])
+            || is_consuming_receiver(sema, node)
+            || is_captured_by_move(sema, node, local);
+    is_consumed && !local.ty(sema.db).is_copy(sema.db)
+}
+
+fn is_consuming_receiver(sema: &Semantics<RootDatabase>, node: &SyntaxNode) -> bool {
+    // The arguments are inside an `ARG_LIST`, so a path directly below the call is the receiver.
+    if !parents_match(node.clone().into(), &[PATH_SEGMENT, PATH, PATH_EXPR, METHOD_CALL_EXPR]) {
+        return false;
+    }
+    node.ancestors()
+        .find_map(ast::MethodCallExpr::cast)
+        .and_then(|call| sema.resolve_method_call(&call))
+        .and_then(|func| func.self_param(sema.db))
+        .map_or(false, |self_param| self_param.access(sema.db) == hir::Access::Owned)
+}
+
+fn is_captured_by_move(
+    sema: &Semantics<RootDatabase>,
+    node: &SyntaxNode,
+    local: &hir::Local,
+) -> bool {
+    let src = local.source(sema.db);
+    if src.file_id != sema.hir_file_for(node) {
+        return false;
+    }
+    let decl = src.value.either(|it| it.syntax().clone(), |it| it.syntax().clone());
+    // A move closure declared inside the local's scope takes ownership of it.
+    node.ancestors()
+        .filter_map(ast::ClosureExpr::cast)
+        .filter(|closure| closure.move_token().is_some())
+        .any(|closure| !decl.ancestors().any(|it| &it == closure.syntax()))
 }
 
 /// Returns true if the parent nodes of `node` all match the `SyntaxKind`s in `kinds` exactly.
@@ -1,19 +1,19 @@
 //! "Recursive" Syntax highlighting for code in doctests and fixtures.
 
-use std::mem;
-
-use either::Either;
 use hir::{InFile, Semantics};
 use ide_db::{
-    active_parameter::ActiveParameter, defs::Definition, rust_doc::is_rust_fence, SymbolKind,
+    active_parameter::ActiveParameter,
+    defs::Definition,
+    rust_doc::{doc_attributes, doc_code_blocks, doc_code_crate_link, resolve_doc_path_for_def},
+    SymbolKind,
 };
 use syntax::{
-    ast::{self, AstNode, IsString, QuoteOffsets},
+    ast::{self, AstNode, IsString},
     AstToken, NodeOrToken, SyntaxNode, TextRange, TextSize,
 };
 
 use crate::{
-    doc_links::{doc_attributes, extract_definitions_from_docs, resolve_doc_path_for_def},
+    doc_links::extract_definitions_from_docs,
     syntax_highlighting::{highlights::Highlights, injector::Injector},
     Analysis, HlMod, HlRange, HlTag, RootDatabase,
 };
@@ -78,9 +78,6 @@ pub(super) fn ra_fixture(
     Some(())
 }
 
-const RUSTDOC_FENCE_LENGTH: usize = 3;
-const RUSTDOC_FENCES: [&str; 2] = ["```", "~~~"];
-
 /// Injection of syntax highlighting of doctests.
 pub(super) fn doc_comment(
     hl: &mut Highlights,
@@ -115,134 +112,52 @@ pub(super) fn doc_comment(
             });
     }
 
-    // Extract doc-test sources from the docs and calculate highlighting for them.
-
-    let mut inj = Injector::default();
-    inj.add_unmapped("fn doctest() {\n");
-
-    let attrs_source_map = attributes.source_map(sema.db);
-
-    let mut is_codeblock = false;
-    let mut is_doctest = false;
-
-    let mut new_comments = Vec::new();
-    let mut string;
-
-    for attr in attributes.by_key("doc").attrs() {
-        let InFile { file_id, value: src } = attrs_source_map.source_of(attr);
-        if file_id != src_file_id {
-            continue;
-        }
-        let (line, range) = match &src {
-            Either::Left(it) => {
-                string = match find_doc_string_in_attr(attr, it) {
-                    Some(it) => it,
-                    None => continue,
-                };
-                let text = string.text();
-                let text_range = string.syntax().text_range();
-                match string.quote_offsets() {
-                    Some(QuoteOffsets { contents, .. }) => {
-                        (&text[contents - text_range.start()], contents)
+    // Extract doc-test sources from the docs and calculate highlighting for them. Paths into the
+    // documented crate are resolved against it.
+    for block in doc_code_blocks(sema.db, &attributes, src_file_id) {
+        let (analysis, tmp_file_id) = Analysis::from_single_file(block.text.clone());
+        let block_file = block.parse();
+
+        if let Ok(ranges) = analysis.with_db(|db| super::highlight(db, tmp_file_id, None, true)) {
+            for HlRange { range, mut highlight, binding_hash } in ranges {
+                if let Some(resolved) = resolve_in_doc_code(sema, def, block_file.syntax(), range) {
+                    highlight = match resolved {
+                        Some(def) => module_def_to_hl_tag(def),
+                        None => HlTag::UnresolvedReference,
                     }
-                    None => (text, text_range),
+                    .into();
                 }
-            }
-            Either::Right(comment) => {
-                let value = comment.prefix().len();
-                let range = comment.syntax().text_range();
-                (
-                    &comment.text()[value..],
-                    TextRange::new(range.start() + TextSize::try_from(value).unwrap(), range.end()),
-                )
-            }
-        };
-
-        let mut range_start = range.start();
-        for line in line.split('\n') {
-            let line_len = TextSize::from(line.len() as u32);
-            let prev_range_start = {
-                let next_range_start = range_start + line_len + TextSize::from(1);
-                mem::replace(&mut range_start, next_range_start)
-            };
-            let mut pos = TextSize::from(0);
-
-            match RUSTDOC_FENCES.into_iter().find_map(|fence| line.find(fence)) {
-                Some(idx) => {
-                    is_codeblock = !is_codeblock;
-                    // Check whether code is rust by inspecting fence guards
-                    let guards = &line[idx + RUSTDOC_FENCE_LENGTH..];
-                    let is_rust = is_rust_fence(guards);
-                    is_doctest = is_codeblock && is_rust;
-                    continue;
+                for range in block.map_range_up(range) {
+                    hl.add(HlRange { range, highlight: highlight | HlMod::Injected, binding_hash });
                 }
-                None if !is_doctest => continue,
-                None => (),
-            }
-
-            // whitespace after comment is ignored
-            if let Some(ws) = line[pos.into()..].chars().next().filter(|c| c.is_whitespace()) {
-                pos += TextSize::of(ws);
-            }
-            // lines marked with `#` should be ignored in output, we skip the `#` char
-            if line[pos.into()..].starts_with('#') {
-                pos += TextSize::of('#');
             }
-
-            new_comments.push(TextRange::at(prev_range_start, pos));
-            inj.add(&line[pos.into()..], TextRange::new(pos, line_len) + prev_range_start);
-            inj.add_unmapped("\n");
         }
-    }
-
-    if new_comments.is_empty() {
-        return; // no need to run an analysis on an empty file
-    }
-
-    inj.add_unmapped("\n}");
 
-    let (analysis, tmp_file_id) = Analysis::from_single_file(inj.take_text());
-
-    if let Ok(ranges) = analysis.with_db(|db| super::highlight(db, tmp_file_id, None, true)) {
-        for HlRange { range, highlight, binding_hash } in ranges {
-            for range in inj.map_range_up(range) {
-                hl.add(HlRange { range, highlight: highlight | HlMod::Injected, binding_hash });
-            }
+        for range in block.prefixes() {
+            hl.add(HlRange {
+                range,
+                highlight: HlTag::Comment | HlMod::Documentation,
+                binding_hash: None,
+            });
         }
     }
-
-    for range in new_comments {
-        hl.add(HlRange {
-            range,
-            highlight: HlTag::Comment | HlMod::Documentation,
-            binding_hash: None,
-        });
-    }
 }
 
-fn find_doc_string_in_attr(attr: &hir::Attr, it: &ast::Attr) -> Option<ast::String> {
-    match it.expr() {
-        // #[doc = lit]
-        Some(ast::Expr::Literal(lit)) => match lit.kind() {
-            ast::LiteralKind::String(it) => Some(it),
-            _ => None,
-        },
-        // #[cfg_attr(..., doc = "", ...)]
-        None => {
-            // We gotta hunt the string token manually here
-            let text = attr.string_value()?;
-            // FIXME: We just pick the first string literal that has the same text as the doc attribute
-            // This means technically we might highlight the wrong one
-            it.syntax()
-                .descendants_with_tokens()
-                .filter_map(NodeOrToken::into_token)
-                .filter_map(ast::String::cast)
-                .find(|string| {
-                    string.text().get(1..string.text().len() - 1).map_or(false, |it| it == text)
-                })
-        }
-        _ => None,
-    }
+/// Resolves the path segment at `range` of a doc code block, returning `None` if the path does not
+/// refer to the documented crate, and `Some(None)` if it does but cannot be resolved.
+fn resolve_in_doc_code(
+    sema: &Semantics<RootDatabase>,
+    def: Definition,
+    block_file: &SyntaxNode,
+    range: TextRange,
+) -> Option<Option<Definition>> {
+    let name_ref = match block_file.covering_element(range) {
+        NodeOrToken::Token(token) => ast::NameRef::cast(token.parent()?)?,
+        NodeOrToken::Node(_) => return None,
+    };
+    let path = ast::PathSegment::cast(name_ref.syntax().parent()?)?.parent_path();
+    let link = doc_code_crate_link(sema.db, def, &path)?;
+    Some(resolve_doc_path_for_def(sema.db, def, &link, None))
 }
 
 fn module_def_to_hl_tag(def: Definition) -> HlTag {
@@ -18,10 +18,6 @@ impl Injector {
         self.add_impl(text, Some(source_range.start()));
     }
 
-    pub(super) fn add_unmapped(&mut self, text: &str) {
-        self.add_impl(text, None);
-    }
-
     fn add_impl(&mut self, text: &str, source: Option<TextSize>) {
         let len = TextSize::of(text);
         let target_range = TextRange::at(TextSize::of(&self.buf), len);
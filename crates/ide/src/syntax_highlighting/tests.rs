@@ -271,6 +271,14 @@ impl Bool {
 }
 const USAGE_OF_BOOL:bool = Bool::True.to_primitive();
 
+fn move_captures(foo: Foo, foo_copy: FooCopy) {
+    let by_ref = || foo.quop();
+    let by_move = move || {
+        let inner = Foo { x: 0 };
+        (foo.quop(), foo_copy.quop(), inner)
+    };
+}
+
 trait Baz {
     type Qux;
 }
@@ -749,6 +757,25 @@ pub fn block_comments2() {}
     );
 }
 
+#[test]
+fn test_doc_code_block_paths() {
+    check_highlighting(
+        r#"
+//- /lib.rs crate:foo
+/// ```
+/// use foo::{Bar, baz as qux};
+///
+/// let bar: Bar = qux();
+/// foo::Missing;
+/// ```
+pub struct Bar;
+pub fn baz() -> Bar { Bar }
+"#,
+        expect_file!["./test_data/highlight_doc_code_block_paths.html"],
+        false,
+    );
+}
+
 #[test]
 fn test_extern_crate() {
     check_highlighting(
@@ -0,0 +1,296 @@
+//! Discovers the tests of the workspace crates and arranges them into a tree of
+//! crates, modules and test functions, for use by editor test UIs.
+
+use hir::{Crate, Module, ModuleDef, ModuleSource, Semantics};
+use ide_db::{
+    base_db::{FileId, SourceDatabaseExt},
+    RootDatabase,
+};
+use syntax::TextRange;
+
+use crate::{
+    runnables::{runnable_fn, Runnable, RunnableKind},
+    NavigationTarget,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestItemKind {
+    Crate,
+    Module,
+    Function,
+}
+
+#[derive(Debug, Clone)]
+pub struct TestItem {
+    /// A `::` separated path to the item, starting with the crate's display name.
+    pub id: String,
+    pub kind: TestItemKind,
+    pub label: String,
+    /// The id of the enclosing crate or module, `None` for crates.
+    pub parent: Option<String>,
+    pub file: Option<FileId>,
+    pub text_range: Option<TextRange>,
+    /// The runnable executing this test, only set for test functions.
+    pub runnable: Option<Runnable>,
+}
+
+/// Returns the workspace crates, without looking at the tests they contain.
+pub(crate) fn discover_test_roots(db: &RootDatabase) -> Vec<TestItem> {
+    Crate::all(db).into_iter().filter_map(|krate| crate_test_item(db, krate)).collect()
+}
+
+/// Returns all tests of the workspace crate with the given id.
+pub(crate) fn discover_tests_in_crate_by_test_id(
+    db: &RootDatabase,
+    crate_test_id: &str,
+) -> Vec<TestItem> {
+    // We use the display name as the crate id. It isn't necessarily unique, but it is good
+    // enough as only workspace crates are considered.
+    let krate = Crate::all(db)
+        .into_iter()
+        .find(|&krate| crate_test_item(db, krate).map_or(false, |item| item.id == crate_test_id));
+    match krate {
+        Some(krate) => discover_tests_in_crate(db, krate),
+        None => Vec::new(),
+    }
+}
+
+fn discover_tests_in_crate(db: &RootDatabase, krate: Crate) -> Vec<TestItem> {
+    let crate_item = match crate_test_item(db, krate) {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    let sema = Semantics::new(db);
+    let crate_id = crate_item.id.clone();
+    let mut res = vec![crate_item];
+    res.extend(discover_tests_in_module(&sema, krate.root_module(db), crate_id, false));
+    res
+}
+
+/// Returns the tests defined in the given file, together with all of their parent items.
+pub(crate) fn discover_tests_in_file(db: &RootDatabase, file_id: FileId) -> Vec<TestItem> {
+    let sema = Semantics::new(db);
+    let module = match sema.to_module_def(file_id) {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    let (mut res, id) = match module_test_parents(&sema, module) {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    res.extend(discover_tests_in_module(&sema, module, id, true));
+    res
+}
+
+fn crate_test_item(db: &RootDatabase, krate: Crate) -> Option<TestItem> {
+    let source_root = db.file_source_root(krate.root_file(db));
+    if db.source_root(source_root).is_library {
+        return None;
+    }
+    let id = krate.display_name(db)?.to_string();
+    Some(TestItem {
+        label: id.clone(),
+        id,
+        kind: TestItemKind::Crate,
+        parent: None,
+        file: None,
+        text_range: None,
+        runnable: None,
+    })
+}
+
+/// Collects the items from the crate root down to `module`, returning them with `module`'s id.
+fn module_test_parents(
+    sema: &Semantics<RootDatabase>,
+    module: Module,
+) -> Option<(Vec<TestItem>, String)> {
+    let parent = match module.parent(sema.db) {
+        Some(it) => it,
+        None => {
+            let item = crate_test_item(sema.db, module.krate())?;
+            let id = item.id.clone();
+            return Some((vec![item], id));
+        }
+    };
+    let (mut res, parent_id) = module_test_parents(sema, parent)?;
+    let item = module_test_item(sema.db, module, parent_id);
+    let id = item.id.clone();
+    res.push(item);
+    Some((res, id))
+}
+
+fn module_test_item(db: &RootDatabase, module: Module, parent_id: String) -> TestItem {
+    let label =
+        module.name(db).map_or_else(|| "[mod without name]".to_string(), |it| it.to_string());
+    let nav = NavigationTarget::from_module_to_decl(db, module);
+    TestItem {
+        id: format!("{}::{}", parent_id, label),
+        kind: TestItemKind::Module,
+        label,
+        parent: Some(parent_id),
+        file: Some(nav.file_id),
+        text_range: Some(nav.focus_or_full_range()),
+        runnable: None,
+    }
+}
+
+/// Collects the test functions in `module` and its submodules, along with the submodules
+/// containing tests. If `only_in_this_file` is set, the contents of out-of-line submodules
+/// are skipped.
+fn discover_tests_in_module(
+    sema: &Semantics<RootDatabase>,
+    module: Module,
+    module_id: String,
+    only_in_this_file: bool,
+) -> Vec<TestItem> {
+    let db = sema.db;
+    let mut res = Vec::new();
+    for child in module.children(db) {
+        let child_item = module_test_item(db, child, module_id.clone());
+        let child_tests =
+            discover_tests_in_module(sema, child, child_item.id.clone(), only_in_this_file);
+        if child_tests.is_empty() {
+            continue;
+        }
+        res.push(child_item);
+        let is_inline = matches!(child.definition_source(db).value, ModuleSource::Module(_));
+        if !only_in_this_file || is_inline {
+            res.extend(child_tests);
+        }
+    }
+    for def in module.declarations(db) {
+        let func = match def {
+            ModuleDef::Function(it) => it,
+            _ => continue,
+        };
+        let runnable = match runnable_fn(sema, func) {
+            Some(it) if matches!(it.kind, RunnableKind::Test { .. }) => it,
+            _ => continue,
+        };
+        let label = func.name(db).to_string();
+        res.push(TestItem {
+            id: format!("{}::{}", module_id, label),
+            kind: TestItemKind::Function,
+            label,
+            parent: Some(module_id.clone()),
+            file: Some(runnable.nav.file_id),
+            text_range: Some(runnable.nav.focus_or_full_range()),
+            runnable: Some(runnable),
+        });
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use ide_db::base_db::FileId;
+
+    use crate::fixture;
+
+    fn render(items: Vec<super::TestItem>) -> String {
+        items
+            .into_iter()
+            .map(|it| {
+                format!(
+                    "{:?} {} parent={:?} {:?} runnable={}\n",
+                    it.kind,
+                    it.id,
+                    it.parent,
+                    it.file.zip(it.text_range),
+                    it.runnable.is_some(),
+                )
+            })
+            .collect()
+    }
+
+    fn check_crate(ra_fixture: &str, crate_id: &str, expect: Expect) {
+        let (analysis, _) = fixture::file(ra_fixture);
+        expect.assert_eq(&render(analysis.discover_tests_in_crate_by_test_id(crate_id).unwrap()));
+    }
+
+    fn check_file(ra_fixture: &str, file_id: FileId, expect: Expect) {
+        let (analysis, _) = fixture::file(ra_fixture);
+        expect.assert_eq(&render(analysis.discover_tests_in_file(file_id).unwrap()));
+    }
+
+    #[test]
+    fn test_roots() {
+        let (analysis, _) = fixture::file(
+            r#"
+//- /main.rs crate:main deps:dep
+//- /dep.rs crate:dep
+"#,
+        );
+        expect![[r#"
+            Crate main parent=None None runnable=false
+            Crate dep parent=None None runnable=false
+        "#]]
+        .assert_eq(&render(analysis.discover_test_roots().unwrap()));
+    }
+
+    #[test]
+    fn tests_in_crate() {
+        check_crate(
+            r#"
+//- /main.rs crate:main
+mod foo;
+mod no_tests {
+    fn helper() {}
+}
+#[test]
+fn root_test() {}
+//- /foo.rs
+mod inner {
+    #[test]
+    fn inner_test() {}
+}
+#[test]
+fn foo_test() {}
+#[bench]
+fn foo_bench() {}
+"#,
+            "main",
+            expect![[r#"
+                Crate main parent=None None runnable=false
+                Module main::foo parent=Some("main") Some((FileId(0), 4..7)) runnable=false
+                Module main::foo::inner parent=Some("main::foo") Some((FileId(1), 4..9)) runnable=false
+                Function main::foo::inner::inner_test parent=Some("main::foo::inner") Some((FileId(1), 31..41)) runnable=true
+                Function main::foo::foo_test parent=Some("main::foo") Some((FileId(1), 60..68)) runnable=true
+                Function main::root_test parent=Some("main") Some((FileId(0), 56..65)) runnable=true
+            "#]],
+        );
+    }
+
+    #[test]
+    fn tests_in_file() {
+        check_file(
+            r#"
+//- /main.rs crate:main
+mod foo;
+#[test]
+fn root_test() {}
+//- /foo.rs
+mod bar;
+mod inner {
+    #[test]
+    fn inner_test() {}
+}
+#[test]
+fn foo_test() {}
+//- /foo/bar.rs
+#[test]
+fn bar_test() {}
+"#,
+            FileId(1),
+            expect![[r#"
+                Crate main parent=None None runnable=false
+                Module main::foo parent=Some("main") Some((FileId(0), 4..7)) runnable=false
+                Module main::foo::inner parent=Some("main::foo") Some((FileId(1), 13..18)) runnable=false
+                Function main::foo::inner::inner_test parent=Some("main::foo::inner") Some((FileId(1), 40..50)) runnable=true
+                Module main::foo::bar parent=Some("main::foo") Some((FileId(1), 4..7)) runnable=false
+                Function main::foo::foo_test parent=Some("main::foo") Some((FileId(1), 69..77)) runnable=true
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,269 @@
+//! Entry point for type-hierarchy
+
+use hir::{Impl, ModuleDef, Semantics, Trait};
+use ide_db::{
+    defs::{Definition, NameClass, NameRefClass},
+    helpers::pick_best_token,
+    RootDatabase,
+};
+use itertools::Itertools;
+use syntax::{ast, AstNode, SyntaxKind::*, T};
+
+use crate::{FilePosition, NavigationTarget, RangeInfo, TryToNav};
+
+// Feature: Type Hierarchy
+//
+// Shows the supertypes and subtypes of a struct, enum, union or trait.
+//
+// Supertypes of a trait are its supertraits, supertypes of an ADT are the traits it implements.
+// Subtypes of a trait are the types implementing it as well as the traits that inherit from it.
+// Implementations for types that have no definition of their own, like trait objects or
+// blanket implementations, are listed as the `impl` block itself.
+pub(crate) fn type_hierarchy(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    let sema = &Semantics::new(db);
+    let (range, defs) = hierarchy_defs(sema, position)?;
+    let navs = defs.into_iter().filter_map(|def| def.try_to_nav(db)).collect();
+    Some(RangeInfo::new(range, navs))
+}
+
+pub(crate) fn supertypes(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<NavigationTarget>> {
+    let sema = &Semantics::new(db);
+    let (_, defs) = hierarchy_defs(sema, position)?;
+
+    let supertypes = defs
+        .into_iter()
+        .flat_map(|def| match def {
+            Definition::Trait(trait_) => trait_.direct_supertraits(db),
+            Definition::Adt(adt) => Impl::all_for_type(db, adt.ty(db))
+                .into_iter()
+                .filter_map(|impl_| impl_.trait_(db))
+                .collect(),
+            _ => Vec::new(),
+        })
+        .unique()
+        .filter_map(|trait_| trait_.try_to_nav(db))
+        .collect();
+    Some(supertypes)
+}
+
+pub(crate) fn subtypes(db: &RootDatabase, position: FilePosition) -> Option<Vec<NavigationTarget>> {
+    let sema = &Semantics::new(db);
+    let (_, defs) = hierarchy_defs(sema, position)?;
+
+    let mut res = Vec::new();
+    for def in defs {
+        // ADTs can't be inherited from, only traits have subtypes.
+        let trait_ = match def {
+            Definition::Trait(it) => it,
+            _ => continue,
+        };
+        res.extend(subtraits(db, trait_).into_iter().filter_map(|it| it.try_to_nav(db)));
+        res.extend(
+            Impl::all_for_trait(db, trait_)
+                .into_iter()
+                .filter_map(|impl_| match impl_.self_ty(db).as_adt() {
+                    Some(adt) => adt.try_to_nav(db),
+                    None => impl_.try_to_nav(db),
+                })
+                .unique_by(|nav| (nav.file_id, nav.full_range)),
+        );
+    }
+    Some(res)
+}
+
+/// Finds the traits that list `trait_` as one of their direct supertraits.
+fn subtraits(db: &RootDatabase, trait_: Trait) -> Vec<Trait> {
+    let krate = trait_.module(db).krate();
+    krate
+        .transitive_reverse_dependencies(db)
+        .into_iter()
+        .flat_map(|krate| krate.modules(db))
+        .flat_map(|module| module.declarations(db))
+        .filter_map(|def| match def {
+            ModuleDef::Trait(it) if it.direct_supertraits(db).contains(&trait_) => Some(it),
+            _ => None,
+        })
+        .collect()
+}
+
+fn hierarchy_defs(
+    sema: &Semantics<RootDatabase>,
+    FilePosition { file_id, offset }: FilePosition,
+) -> Option<(syntax::TextRange, Vec<Definition>)> {
+    let file = sema.parse(file_id).syntax().clone();
+    let original_token = pick_best_token(file.token_at_offset(offset), |kind| match kind {
+        IDENT | T![Self] => 1,
+        _ => 0,
+    })?;
+
+    let defs = sema
+        .descend_into_macros(original_token.clone())
+        .into_iter()
+        .filter_map(|token| {
+            let node = token.parent()?;
+            let def = match ast::NameLike::cast(node)? {
+                ast::NameLike::NameRef(name_ref) => {
+                    match NameRefClass::classify(sema, &name_ref)? {
+                        NameRefClass::Definition(def) => def,
+                        NameRefClass::FieldShorthand { .. } => return None,
+                    }
+                }
+                ast::NameLike::Name(name) => match NameClass::classify(sema, &name)? {
+                    NameClass::Definition(def) | NameClass::ConstReference(def) => def,
+                    NameClass::PatFieldShorthand { .. } => return None,
+                },
+                ast::NameLike::Lifetime(_) => return None,
+            };
+            match def {
+                Definition::Adt(_) | Definition::Trait(_) => Some(def),
+                Definition::SelfType(impl_) => impl_.self_ty(sema.db).as_adt().map(Definition::Adt),
+                _ => None,
+            }
+        })
+        .unique()
+        .collect::<Vec<_>>();
+    if defs.is_empty() {
+        return None;
+    }
+    Some((original_token.text_range(), defs))
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+
+    use crate::fixture;
+
+    fn check_hierarchy(
+        ra_fixture: &str,
+        expected: Expect,
+        expected_supertypes: Expect,
+        expected_subtypes: Expect,
+    ) {
+        let (analysis, pos) = fixture::position(ra_fixture);
+
+        let mut navs = analysis.type_hierarchy(pos).unwrap().unwrap().info;
+        assert_eq!(navs.len(), 1);
+        let nav = navs.pop().unwrap();
+        expected.assert_eq(&nav.debug_render());
+
+        let item_pos =
+            crate::FilePosition { file_id: nav.file_id, offset: nav.focus_or_full_range().start() };
+        let render = |navs: Vec<crate::NavigationTarget>| {
+            navs.into_iter().map(|it| it.debug_render()).collect::<Vec<_>>()
+        };
+        let supertypes = analysis.supertypes(item_pos).unwrap().unwrap();
+        expected_supertypes.assert_debug_eq(&render(supertypes));
+        let subtypes = analysis.subtypes(item_pos).unwrap().unwrap();
+        expected_subtypes.assert_debug_eq(&render(subtypes));
+    }
+
+    #[test]
+    fn test_type_hierarchy_on_trait() {
+        check_hierarchy(
+            r#"
+trait Base {}
+trait Sub: Base {}
+trait Tr$0ait: Base where Self: Sub {}
+trait Derived: Trait {}
+trait Unrelated {}
+
+struct Foo;
+impl Trait for Foo {}
+impl Trait for dyn Unrelated {}
+"#,
+            expect![[r#"Trait Trait FileId(0) 33..69 39..44"#]],
+            expect![[r#"
+                [
+                    "Base Trait FileId(0) 0..13 6..10",
+                    "Sub Trait FileId(0) 14..32 20..23",
+                ]
+            "#]],
+            expect![[r#"
+                [
+                    "Derived Trait FileId(0) 70..93 76..83",
+                    "Foo Struct FileId(0) 114..125 121..124",
+                    "impl Impl FileId(0) 148..179 163..176",
+                ]
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_type_hierarchy_on_struct() {
+        check_hierarchy(
+            r#"
+trait A {}
+trait B {}
+struct Fo$0o;
+impl A for Foo {}
+impl B for Foo {}
+impl Foo {}
+"#,
+            expect![[r#"Foo Struct FileId(0) 22..33 29..32"#]],
+            expect![[r#"
+                [
+                    "A Trait FileId(0) 0..10 6..7",
+                    "B Trait FileId(0) 11..21 17..18",
+                ]
+            "#]],
+            expect![[r#"
+                []
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_type_hierarchy_on_self_type() {
+        check_hierarchy(
+            r#"
+trait A {}
+struct Foo;
+impl A for Foo {}
+impl Foo {
+    fn new() -> Se$0lf { Foo }
+}
+"#,
+            expect![[r#"Foo Struct FileId(0) 11..22 18..21"#]],
+            expect![[r#"
+                [
+                    "A Trait FileId(0) 0..10 6..7",
+                ]
+            "#]],
+            expect![[r#"
+                []
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_type_hierarchy_across_crates() {
+        check_hierarchy(
+            r#"
+//- /main.rs crate:main deps:lib
+use lib::Trait;
+struct Foo;
+impl Trait for Foo {}
+trait Sub: Trait {}
+//- /lib.rs crate:lib
+pub trait Tr$0ait {}
+"#,
+            expect![[r#"Trait Trait FileId(1) 0..18 10..15"#]],
+            expect![[r#"
+                []
+            "#]],
+            expect![[r#"
+                [
+                    "Sub Trait FileId(0) 50..69 56..59",
+                    "Foo Struct FileId(0) 16..27 23..26",
+                ]
+            "#]],
+        );
+    }
+}
@@ -32,7 +32,7 @@ use crate::SourceChange;
 pub(crate) use on_enter::on_enter;
 
 // Don't forget to add new trigger characters to `server_capabilities` in `caps.rs`.
-pub(crate) const TRIGGER_CHARS: &str = ".=<>{";
+pub(crate) const TRIGGER_CHARS: &str = ".=<>{|";
 
 struct ExtendedTextEdit {
     edit: TextEdit,
@@ -49,6 +49,7 @@ struct ExtendedTextEdit {
 // - typing `.` in a chain method call auto-indents
 // - typing `{` in front of an expression inserts a closing `}` after the expression
 // - typing `{` in a use item adds a closing `}` in the right place
+// - typing `|` to start the parameters of a closure inserts the closing `|`
 //
 // VS Code::
 //
@@ -92,6 +93,7 @@ fn on_char_typed_inner(
         '<' => on_left_angle_typed(&file.tree(), offset),
         '>' => conv(on_right_angle_typed(&file.tree(), offset)),
         '{' => conv(on_opening_brace_typed(file, offset)),
+        '|' => on_pipe_typed(file, offset),
         _ => return None,
     };
 
@@ -355,6 +357,61 @@ fn on_left_angle_typed(file: &SourceFile, offset: TextSize) -> Option<ExtendedTe
     None
 }
 
+/// Add closing `|` for closure parameters.
+fn on_pipe_typed(file: &Parse<SourceFile>, offset: TextSize) -> Option<ExtendedTextEdit> {
+    let file_text = file.tree().syntax().text();
+    if !stdx::always!(file_text.char_at(offset) == Some('|')) {
+        return None;
+    }
+
+    let after_pipe = offset + TextSize::of('|');
+    if file_text.char_at(after_pipe) == Some('|') {
+        return type_over_closing_pipe(file, offset);
+    }
+
+    // Find the next non-whitespace char in the line.
+    let mut next_offset = after_pipe;
+    while file_text.char_at(next_offset) == Some(' ') {
+        next_offset += TextSize::of(' ')
+    }
+    if file_text.char_at(next_offset) == Some('|') {
+        return None;
+    }
+
+    let pipe = file.tree().syntax().token_at_offset(offset).right_biased()?;
+    if pipe.kind() != T![|] {
+        return None;
+    }
+    let param_list = pipe.parent().and_then(ast::ParamList::cast)?;
+    if param_list.syntax().first_token()? != pipe
+        || !ast::ClosureExpr::can_cast(param_list.syntax().parent()?.kind())
+    {
+        return None;
+    }
+
+    Some(ExtendedTextEdit {
+        edit: TextEdit::replace(TextRange::at(offset, TextSize::of('|')), "|$0|".to_string()),
+        is_snippet: true,
+    })
+}
+
+/// Removes the closing `|` inserted by [`on_pipe_typed`] when another `|` is typed in front of
+/// it, so that typing `||` results in an empty parameter list instead of `|||`.
+fn type_over_closing_pipe(file: &Parse<SourceFile>, offset: TextSize) -> Option<ExtendedTextEdit> {
+    // Remove the typed `|` to see what the user typed it into.
+    let file = file.reparse(&Indel::delete(TextRange::at(offset, TextSize::of('|'))));
+    let param_list = find_node_at_offset::<ast::ParamList>(file.tree().syntax(), offset)?;
+    let empty_pipes = TextRange::at(offset.checked_sub(TextSize::of('|'))?, TextSize::of("||"));
+    if param_list.syntax().text_range() != empty_pipes
+        || !ast::ClosureExpr::can_cast(param_list.syntax().parent()?.kind())
+    {
+        return None;
+    }
+
+    let closing_pipe = TextRange::at(offset + TextSize::of('|'), TextSize::of('|'));
+    Some(ExtendedTextEdit { edit: TextEdit::delete(closing_pipe), is_snippet: false })
+}
+
 /// Adds a space after an arrow when `fn foo() { ... }` is turned into `fn foo() -> { ... }`
 fn on_right_angle_typed(file: &SourceFile, offset: TextSize) -> Option<TextEdit> {
     let file_text = file.syntax().text();
@@ -1204,6 +1261,89 @@ fn foo() {
     )
     $0
 }
+"#,
+        );
+    }
+
+    #[test]
+    fn adds_closing_pipe_for_closure_params() {
+        type_char(
+            '|',
+            r#"
+fn f() {
+    let f = $0
+}
+"#,
+            r#"
+fn f() {
+    let f = |$0|
+}
+"#,
+        );
+        type_char(
+            '|',
+            r#"
+fn f() {
+    v.iter().map($0);
+}
+"#,
+            r#"
+fn f() {
+    v.iter().map(|$0|);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn types_over_closing_pipe_of_empty_closure_params() {
+        type_char(
+            '|',
+            r#"
+fn f() {
+    thread::spawn(|$0|);
+}
+"#,
+            r#"
+fn f() {
+    thread::spawn(||);
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn does_not_add_closing_pipe() {
+        type_char_noop(
+            '|',
+            r#"
+fn f() {
+    let f = |x$0
+}
+"#,
+        );
+        type_char_noop(
+            '|',
+            r#"
+fn f() {
+    let f = $0|
+}
+"#,
+        );
+        type_char_noop(
+            '|',
+            r#"
+fn f() {
+    let x = a $0 b;
+}
+"#,
+        );
+        type_char_noop(
+            '|',
+            r#"
+fn f() {
+    let x = a $0| b;
+}
 "#,
         );
     }
@@ -1,5 +1,5 @@
-//! Handles the `Enter` key press. At the momently, this only continues
-//! comments, but should handle indent some time in the future as well.
+//! Handles the `Enter` key press. At the moment, this continues comments and
+//! indents the contents of single-line blocks.
 
 use ide_db::base_db::{FilePosition, SourceDatabase};
 use ide_db::RootDatabase;
@@ -20,6 +20,7 @@ use text_edit::TextEdit;
 // - kbd:[Enter] inside triple-slash comments automatically inserts `///`
 // - kbd:[Enter] in the middle or after a trailing space in `//` inserts `//`
 // - kbd:[Enter] inside `//!` doc comments automatically inserts `//!`
+// - kbd:[Enter] inside `/*!` and `/**` doc comments continues the leading `*` of the lines
 // - kbd:[Enter] after `{` indents contents and closing `}` of single-line block
 //
 // This action needs to be assigned to shortcut explicitly.
@@ -87,7 +88,7 @@ fn on_enter_in_comment(
     offset: TextSize,
 ) -> Option<TextEdit> {
     if comment.kind().shape.is_block() {
-        return None;
+        return on_enter_in_block_doc_comment(comment, file, offset);
     }
 
     let prefix = comment.prefix();
@@ -120,6 +121,52 @@ fn on_enter_in_comment(
     Some(edit)
 }
 
+fn on_enter_in_block_doc_comment(
+    comment: &ast::Comment,
+    file: &ast::SourceFile,
+    offset: TextSize,
+) -> Option<TextEdit> {
+    comment.kind().doc?;
+
+    let text = comment.text();
+    let comment_range = comment.syntax().text_range();
+    let closing_len = if text.ends_with("*/") { TextSize::of("*/") } else { 0.into() };
+    if offset < comment_range.start() + TextSize::of(comment.prefix())
+        || offset > comment_range.end() - closing_len
+    {
+        return None;
+    }
+
+    let before = &text[..usize::from(offset - comment_range.start())];
+    let inserted = match before.rsplit_once('\n') {
+        // On the first line, only continue with a `*` if the other lines have one as well.
+        None => {
+            let indent = node_indent(file, comment.syntax())?;
+            let lines_have_stars = text
+                .lines()
+                .skip(1)
+                .map(str::trim_start)
+                .filter(|line| !line.is_empty() && !line.starts_with("*/"))
+                .all(|line| line.starts_with('*'));
+            if lines_have_stars {
+                format!("\n{} * $0", indent)
+            } else {
+                format!("\n{}$0", indent)
+            }
+        }
+        Some((_, line)) => {
+            let content = line.trim_start();
+            let indent = &line[..line.len() - content.len()];
+            if content.starts_with('*') {
+                format!("\n{}* $0", indent)
+            } else {
+                format!("\n{}$0", indent)
+            }
+        }
+    };
+    Some(TextEdit::insert(offset, inserted))
+}
+
 fn on_enter_in_block(block: ast::BlockExpr, position: FilePosition) -> Option<TextEdit> {
     let contents = block_contents(&block)?;
 
@@ -613,4 +660,75 @@ use path::{$0
             "#,
         );
     }
+
+    #[test]
+    fn continues_block_doc_comment() {
+        do_check(
+            r"
+/*!
+ * Crate docs$0
+ */
+",
+            r"
+/*!
+ * Crate docs
+ * $0
+ */
+",
+        );
+        do_check(
+            r"
+/** Some docs$0 */
+fn foo() {}
+",
+            r"
+/** Some docs
+ * $0 */
+fn foo() {}
+",
+        );
+        do_check(
+            r"
+mod m {
+    /*! Module$0
+        docs
+    */
+}
+",
+            r"
+mod m {
+    /*! Module
+    $0
+        docs
+    */
+}
+",
+        );
+        do_check_noop(r"/* not docs$0 */");
+        do_check_noop(r"/** docs */$0");
+    }
+
+    #[test]
+    fn does_not_complete_match_arms() {
+        // The pattern may continue as an or-pattern on the next line.
+        do_check_noop(
+            r"
+fn f(x: Option<i32>) {
+    match x {
+        Some(_)$0
+    }
+}
+",
+        );
+        // The expression may continue as a method chain on the next line.
+        do_check_noop(
+            r"
+fn f(x: Option<i32>) {
+    match x {
+        Some(x) => x$0
+    }
+}
+",
+        );
+    }
 }
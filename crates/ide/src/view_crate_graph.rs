@@ -1,47 +1,133 @@
 use std::sync::Arc;
 
 use dot::{Id, LabelText};
+use hir::Semantics;
 use ide_db::{
-    base_db::{CrateGraph, CrateId, Dependency, SourceDatabase, SourceDatabaseExt},
+    base_db::{CrateGraph, CrateId, Dependency, FileId, SourceDatabase, SourceDatabaseExt},
     FxHashSet, RootDatabase,
 };
+use stdx::format_to;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrateGraphConfig {
+    /// Include *all* crates, not just crates in the workspace.
+    pub full: bool,
+    /// Only include the crates reachable from the crates containing this file.
+    pub reachable_from: Option<FileId>,
+    pub include_dev_deps: bool,
+    pub format: CrateGraphFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrateGraphFormat {
+    Dot,
+    Mermaid,
+}
 
 // Feature: View Crate Graph
 //
 // Renders the currently loaded crate graph as an SVG graphic. Requires the `dot` tool, which
 // is part of graphviz, to be installed.
 //
-// Only workspace crates are included, no crates.io dependencies or sysroot crates.
+// Only workspace crates are included, no crates.io dependencies or sysroot crates. The graph can
+// also be restricted to the crates reachable from the crate of the current file.
 //
 // |===
 // | Editor  | Action Name
 //
 // | VS Code | **Rust Analyzer: View Crate Graph**
+// | VS Code | **Rust Analyzer: View Crate Graph of Current Crate**
+// | VS Code | **Rust Analyzer: View Crate Graph (Mermaid)**
 // |===
-pub(crate) fn view_crate_graph(db: &RootDatabase, full: bool) -> Result<String, String> {
+pub(crate) fn view_crate_graph(
+    db: &RootDatabase,
+    config: CrateGraphConfig,
+) -> Result<String, String> {
     let crate_graph = db.crate_graph();
-    let crates_to_render = crate_graph
-        .iter()
-        .filter(|krate| {
-            if full {
-                true
-            } else {
-                // Only render workspace crates
-                let root_id = db.file_source_root(crate_graph[*krate].root_file_id);
-                !db.source_root(root_id).is_library
+    let is_included = |krate: CrateId| {
+        // Unless asked for the full graph, only render workspace crates
+        config.full || {
+            let root_id = db.file_source_root(crate_graph[krate].root_file_id);
+            !db.source_root(root_id).is_library
+        }
+    };
+    let crates_to_render = match config.reachable_from {
+        Some(file_id) => {
+            let sema = Semantics::new(db);
+            let mut stack: Vec<CrateId> =
+                sema.to_module_defs(file_id).map(|module| module.krate().into()).collect();
+            if stack.is_empty() {
+                return Err("The file is not part of any crate".to_string());
             }
-        })
-        .collect();
-    let graph = DotCrateGraph { graph: crate_graph, crates_to_render };
+            let mut reachable = FxHashSet::default();
+            while let Some(krate) = stack.pop() {
+                if !is_included(krate) || !reachable.insert(krate) {
+                    continue;
+                }
+                stack.extend(
+                    crate_graph[krate]
+                        .dependencies
+                        .iter()
+                        .filter(|dep| config.include_dev_deps || !dep.is_dev())
+                        .map(|dep| dep.crate_id),
+                );
+            }
+            reachable
+        }
+        None => crate_graph.iter().filter(|&krate| is_included(krate)).collect(),
+    };
+    let graph = DotCrateGraph {
+        graph: crate_graph,
+        crates_to_render,
+        include_dev_deps: config.include_dev_deps,
+    };
 
-    let mut dot = Vec::new();
-    dot::render(&graph, &mut dot).unwrap();
-    Ok(String::from_utf8(dot).unwrap())
+    match config.format {
+        CrateGraphFormat::Dot => {
+            let mut dot = Vec::new();
+            dot::render(&graph, &mut dot).unwrap();
+            Ok(String::from_utf8(dot).unwrap())
+        }
+        CrateGraphFormat::Mermaid => Ok(graph.render_mermaid()),
+    }
 }
 
 struct DotCrateGraph {
     graph: Arc<CrateGraph>,
     crates_to_render: FxHashSet<CrateId>,
+    include_dev_deps: bool,
+}
+
+impl DotCrateGraph {
+    fn dependencies(&self, krate: CrateId) -> impl Iterator<Item = &Dependency> + '_ {
+        self.graph[krate].dependencies.iter().filter(|dep| {
+            (self.include_dev_deps || !dep.is_dev())
+                && self.crates_to_render.contains(&dep.crate_id)
+        })
+    }
+
+    fn crate_name(&self, krate: CrateId) -> &str {
+        self.graph[krate].display_name.as_ref().map_or("(unnamed crate)", |name| &*name)
+    }
+
+    fn render_mermaid(&self) -> String {
+        let mut crates: Vec<_> = self.crates_to_render.iter().copied().collect();
+        crates.sort_by_key(|krate| krate.0);
+        let mut res = String::from("graph TD\n");
+        for &krate in &crates {
+            format_to!(res, "    _{}[\"{}\"]\n", krate.0, self.crate_name(krate));
+        }
+        for &krate in &crates {
+            let mut deps: Vec<_> = self.dependencies(krate).map(|dep| dep.crate_id.0).collect();
+            deps.sort_unstable();
+            // A crate can depend on another one both normally and as a dev-dependency.
+            deps.dedup();
+            for dep in deps {
+                format_to!(res, "    _{} --> _{}\n", krate.0, dep);
+            }
+        }
+        res
+    }
 }
 
 type Edge<'a> = (CrateId, &'a Dependency);
@@ -54,13 +140,7 @@ impl<'a> dot::GraphWalk<'a, CrateId, Edge<'a>> for DotCrateGraph {
     fn edges(&'a self) -> dot::Edges<'a, Edge<'a>> {
         self.crates_to_render
             .iter()
-            .flat_map(|krate| {
-                self.graph[*krate]
-                    .dependencies
-                    .iter()
-                    .filter(|dep| self.crates_to_render.contains(&dep.crate_id))
-                    .map(move |dep| (*krate, dep))
-            })
+            .flat_map(|&krate| self.dependencies(krate).map(move |dep| (krate, dep)))
             .collect()
     }
 
@@ -87,7 +167,88 @@ impl<'a> dot::Labeller<'a, CrateId, Edge<'a>> for DotCrateGraph {
     }
 
     fn node_label(&'a self, n: &CrateId) -> LabelText<'a> {
-        let name = self.graph[*n].display_name.as_ref().map_or("(unnamed crate)", |name| &*name);
-        LabelText::LabelStr(name.into())
+        LabelText::LabelStr(self.crate_name(*n).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+    use ide_db::base_db::FileId;
+
+    use crate::fixture;
+
+    use super::{CrateGraphConfig, CrateGraphFormat};
+
+    fn check(ra_fixture: &str, full: bool, reachable_from: Option<FileId>, expect: Expect) {
+        let (analysis, _) = fixture::file(ra_fixture);
+        let config = CrateGraphConfig {
+            full,
+            reachable_from,
+            include_dev_deps: true,
+            format: CrateGraphFormat::Mermaid,
+        };
+        expect.assert_eq(&analysis.view_crate_graph(config).unwrap().unwrap());
+    }
+
+    #[test]
+    fn workspace_crates() {
+        check(
+            r#"
+//- /main.rs crate:main deps:foo,dep
+//- /foo.rs crate:foo deps:dep
+//- /dep.rs crate:dep new_source_root:library
+"#,
+            false,
+            None,
+            expect![[r#"
+                graph TD
+                    _0["main"]
+                    _1["foo"]
+                    _0 --> _1
+            "#]],
+        );
+    }
+
+    #[test]
+    fn full_graph() {
+        check(
+            r#"
+//- /main.rs crate:main deps:foo,dep
+//- /foo.rs crate:foo deps:dep
+//- /dep.rs crate:dep new_source_root:library
+"#,
+            true,
+            None,
+            expect![[r#"
+                graph TD
+                    _0["main"]
+                    _1["foo"]
+                    _2["dep"]
+                    _0 --> _1
+                    _0 --> _2
+                    _1 --> _2
+            "#]],
+        );
+    }
+
+    #[test]
+    fn reachable_from_file() {
+        check(
+            r#"
+//- /main.rs crate:main deps:foo
+//- /foo.rs crate:foo deps:bar
+//- /bar.rs crate:bar
+//- /other.rs crate:other deps:bar
+"#,
+            false,
+            Some(FileId(1)),
+            expect![[r#"
+                graph TD
+                    _1["foo"]
+                    _2["bar"]
+                    _1 --> _2
+            "#]],
+        );
     }
 }
@@ -142,6 +142,7 @@ fn invocation_fixtures(rules: &FxHashMap<String, DeclarativeMacro>) -> Vec<(Stri
                 Some(kind) => panic!("Unhandled kind {}", kind),
             },
             Op::Leaf(leaf) => parent.token_trees.push(leaf.clone().into()),
+            Op::Ignore { .. } | Op::Index { .. } | Op::Len { .. } | Op::Count { .. } => {}
             Op::Repeat { tokens, kind, separator } => {
                 let max = 10;
                 let cnt = match kind {
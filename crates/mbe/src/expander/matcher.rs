@@ -493,6 +493,11 @@ fn match_loop_inner<'t>(
                     }
                 }
             }
+            OpDelimited::Op(
+                Op::Ignore { .. } | Op::Index { .. } | Op::Len { .. } | Op::Count { .. },
+            ) => {
+                stdx::never!("metavariable expression in lhs found");
+            }
             OpDelimited::Op(Op::Leaf(leaf)) => {
                 if let Err(err) = match_leaf(leaf, &mut src.clone()) {
                     res.add_err(err);
@@ -744,7 +749,11 @@ fn collect_vars(collector_fun: &mut impl FnMut(SmolStr), pattern: &MetaTemplate)
     for op in pattern.iter() {
         match op {
             Op::Var { name, .. } => collector_fun(name.clone()),
-            Op::Leaf(_) => (),
+            Op::Leaf(_)
+            | Op::Ignore { .. }
+            | Op::Index { .. }
+            | Op::Len { .. }
+            | Op::Count { .. } => {}
             Op::Subtree { tokens, .. } => collect_vars(collector_fun, tokens),
             Op::Repeat { tokens, .. } => collect_vars(collector_fun, tokens),
         }
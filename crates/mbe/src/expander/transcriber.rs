@@ -60,6 +60,8 @@ pub(super) fn transcribe(
 #[derive(Debug)]
 struct NestingState {
     idx: usize,
+    /// How many times the repetition is going to be expanded, used by `${len()}`
+    len: usize,
     /// `hit` is currently necessary to tell `expand_repeat` if it should stop
     /// because there is no variable in use by the current repetition
     hit: bool,
@@ -103,6 +105,30 @@ fn expand_subtree(
                 err = err.or(e);
                 push_fragment(arena, fragment)
             }
+            Op::Ignore { name, id } => {
+                // Expand the variable, but ignore the result. This registers the repetition count.
+                let _ = expand_var(ctx, name, *id);
+            }
+            Op::Index { depth } => match ctx.nesting.iter().nth_back(*depth as usize) {
+                Some(nest) => arena.push(usize_literal(nest.idx)),
+                None => {
+                    err = err.or_else(|| {
+                        Some(ExpandError::binding_error("`${index()}` used outside of repetition"))
+                    });
+                }
+            },
+            Op::Len { depth } => match ctx.nesting.iter().nth_back(*depth as usize) {
+                Some(nest) => arena.push(usize_literal(nest.len)),
+                None => {
+                    err = err.or_else(|| {
+                        Some(ExpandError::binding_error("`${len()}` used outside of repetition"))
+                    });
+                }
+            },
+            Op::Count { name, depth } => match count(ctx, name, *depth) {
+                Ok(c) => arena.push(usize_literal(c)),
+                Err(e) => err = err.or(Some(e)),
+            },
         }
     }
     // drain the elements added in this instance of expand_subtree
@@ -153,7 +179,8 @@ fn expand_repeat(
     arena: &mut Vec<tt::TokenTree>,
 ) -> ExpandResult<Fragment> {
     let mut buf: Vec<tt::TokenTree> = Vec::new();
-    ctx.nesting.push(NestingState { idx: 0, at_end: false, hit: false });
+    let len = repeat_len(ctx.bindings, &ctx.nesting, template).unwrap_or(0);
+    ctx.nesting.push(NestingState { idx: 0, len, at_end: false, hit: false });
     // Dirty hack to make macro-expansion terminate.
     // This should be replaced by a proper macro-by-example implementation
     let limit = 65536;
@@ -231,6 +258,75 @@ fn expand_repeat(
     ExpandResult::ok(Fragment::Tokens(tt))
 }
 
+/// Returns how many times the repetition `template` is going to be expanded, judging by the
+/// first metavariable in it that repeats at the current nesting.
+fn repeat_len(
+    bindings: &Bindings,
+    nesting: &[NestingState],
+    template: &MetaTemplate,
+) -> Option<usize> {
+    template.iter().find_map(|op| match op {
+        Op::Var { name, .. } | Op::Ignore { name, .. } => {
+            let mut b = bindings.inner.get(name)?;
+            for nesting_state in nesting {
+                b = match b {
+                    Binding::Nested(bs) => bs.get(nesting_state.idx)?,
+                    Binding::Fragment(_) => return None,
+                    Binding::Empty => return Some(0),
+                };
+            }
+            match b {
+                Binding::Nested(bs) => Some(bs.len()),
+                Binding::Empty => Some(0),
+                Binding::Fragment(_) => None,
+            }
+        }
+        Op::Subtree { tokens, .. } | Op::Repeat { tokens, .. } => {
+            repeat_len(bindings, nesting, tokens)
+        }
+        Op::Leaf(_) | Op::Index { .. } | Op::Len { .. } | Op::Count { .. } => None,
+    })
+}
+
+/// Counts the fragments `name` is bound to in the current repetition. With a `depth`, only the
+/// repetitions that many levels deeper are counted.
+fn count(ctx: &ExpandCtx, name: &str, depth: Option<u32>) -> Result<usize, ExpandError> {
+    fn count_nested(binding: &Binding, depth_curr: u32, depth: Option<u32>) -> usize {
+        match binding {
+            Binding::Nested(bs) if Some(depth_curr) == depth => bs.len(),
+            Binding::Nested(bs) => bs.iter().map(|b| count_nested(b, depth_curr + 1, depth)).sum(),
+            Binding::Empty => 0,
+            Binding::Fragment(_) => 1,
+        }
+    }
+
+    let mut b =
+        ctx.bindings.inner.get(name).ok_or_else(|| {
+            ExpandError::binding_error(format!("could not find binding `{name}`"))
+        })?;
+    for nesting_state in &ctx.nesting {
+        b = match b {
+            Binding::Nested(bs) => match bs.get(nesting_state.idx) {
+                Some(b) => b,
+                None => return Ok(0),
+            },
+            Binding::Fragment(_) => break,
+            Binding::Empty => return Ok(0),
+        };
+    }
+    match b {
+        Binding::Fragment(_) => Err(ExpandError::binding_error(format!(
+            "`${{count()}}` applied to `{name}`, which is not repeated"
+        ))),
+        _ => Ok(count_nested(b, 0, depth)),
+    }
+}
+
+fn usize_literal(n: usize) -> tt::TokenTree {
+    tt::Leaf::from(tt::Literal { text: n.to_string().into(), id: tt::TokenId::unspecified() })
+        .into()
+}
+
 fn push_fragment(buf: &mut Vec<tt::TokenTree>, fragment: Fragment) {
     match fragment {
         Fragment::Tokens(tt::TokenTree::Subtree(tt)) => push_subtree(buf, tt),
@@ -1,5 +1,5 @@
-//! Parser recognizes special macro syntax, `$var` and `$(repeat)*`, in token
-//! trees.
+//! Parser recognizes special macro syntax, `$var`, `$(repeat)*` and
+//! `${metavar_expr()}`, in token trees.
 
 use smallvec::SmallVec;
 use syntax::SmolStr;
@@ -50,10 +50,39 @@ impl MetaTemplate {
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) enum Op {
-    Var { name: SmolStr, kind: Option<SmolStr>, id: tt::TokenId },
-    Repeat { tokens: MetaTemplate, kind: RepeatKind, separator: Option<Separator> },
+    Var {
+        name: SmolStr,
+        kind: Option<SmolStr>,
+        id: tt::TokenId,
+    },
+    Repeat {
+        tokens: MetaTemplate,
+        kind: RepeatKind,
+        separator: Option<Separator>,
+    },
     Leaf(tt::Leaf),
-    Subtree { tokens: MetaTemplate, delimiter: Option<tt::Delimiter> },
+    Subtree {
+        tokens: MetaTemplate,
+        delimiter: Option<tt::Delimiter>,
+    },
+    /// `${ignore(name)}`
+    Ignore {
+        name: SmolStr,
+        id: tt::TokenId,
+    },
+    /// `${index(depth)}`
+    Index {
+        depth: u32,
+    },
+    /// `${len(depth)}`
+    Len {
+        depth: u32,
+    },
+    /// `${count(name, depth)}`
+    Count {
+        name: SmolStr,
+        depth: Option<u32>,
+    },
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -113,6 +142,13 @@ fn next_op<'a>(first: &tt::TokenTree, src: &mut TtIter<'a>, mode: Mode) -> Resul
                 Some(it) => it,
             };
             match second {
+                tt::TokenTree::Subtree(subtree)
+                    if matches!(mode, Mode::Template)
+                        && subtree.delimiter_kind() == Some(tt::DelimiterKind::Brace) =>
+                {
+                    parse_metavar_expr(&mut TtIter::new(subtree))
+                        .map_err(|()| ParseError::unexpected("invalid metavariable expression"))?
+                }
                 tt::TokenTree::Subtree(subtree) => {
                     let (separator, kind) = parse_repeat(src)?;
                     let tokens = MetaTemplate::parse(subtree, mode)?;
@@ -201,3 +237,47 @@ fn parse_repeat(src: &mut TtIter) -> Result<(Option<Separator>, RepeatKind), Par
     }
     Err(ParseError::InvalidRepeat)
 }
+
+fn parse_metavar_expr(src: &mut TtIter) -> Result<Op, ()> {
+    let func = src.expect_ident()?;
+    let args = src.expect_subtree()?;
+    if args.delimiter_kind() != Some(tt::DelimiterKind::Parenthesis) {
+        return Err(());
+    }
+
+    let mut args = TtIter::new(args);
+    let op = match &*func.text {
+        "ignore" => {
+            let ident = args.expect_ident()?;
+            Op::Ignore { name: ident.text.clone(), id: ident.id }
+        }
+        "index" => Op::Index { depth: parse_depth(&mut args)?.unwrap_or(0) },
+        // `length` is the older name of `len`.
+        "len" | "length" => Op::Len { depth: parse_depth(&mut args)?.unwrap_or(0) },
+        "count" => {
+            let ident = args.expect_ident()?;
+            let depth = if args.len() == 0 {
+                None
+            } else {
+                args.expect_char(',')?;
+                Some(args.expect_u32_literal()?)
+            };
+            Op::Count { name: ident.text.clone(), depth }
+        }
+        _ => return Err(()),
+    };
+
+    if args.next().is_some() {
+        return Err(());
+    }
+
+    Ok(op)
+}
+
+fn parse_depth(src: &mut TtIter) -> Result<Option<u32>, ()> {
+    if src.len() == 0 {
+        Ok(None)
+    } else {
+        src.expect_u32_literal().map(Some)
+    }
+}
@@ -0,0 +1,268 @@
+//! On-disk cache of proc-macro expansions, so that restarting the server doesn't expand all
+//! macro calls of a project again.
+//!
+//! The expansions of a dylib are stored as lines of `<key> <json>` in a file in
+//! `rust-analyzer/proc-macro-cache`, next to the `deps` directory containing the dylib. The key
+//! is a stable 128-bit digest of the input of the expansion. The file name contains the cache
+//! format version and a digest of the metadata of the dylib and of the server executable:
+//! rebuilding a macro crate or updating the server starts a new file, and the files of previous
+//! builds are removed.
+
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    io::{self, Write},
+    sync::Mutex,
+    time::UNIX_EPOCH,
+};
+
+use paths::{AbsPath, AbsPathBuf};
+use serde::de::IgnoredAny;
+
+use crate::msg::{ExpandMacro, FlatTree};
+
+/// Bumped when the format of the cache files changes.
+const FORMAT_VERSION: u32 = 1;
+
+pub(crate) struct ExpansionCache {
+    path: AbsPathBuf,
+    inner: Mutex<Inner>,
+}
+
+struct Inner {
+    entries: HashMap<u128, String>,
+    file: fs::File,
+}
+
+impl fmt::Debug for ExpansionCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExpansionCache").field("path", &self.path).finish()
+    }
+}
+
+impl ExpansionCache {
+    /// Opens the cache of the dylib at `dylib_path` expanded by the server at `server_path`,
+    /// removing the caches of its previous builds.
+    pub(crate) fn open(dylib_path: &AbsPath, server_path: &AbsPath) -> io::Result<ExpansionCache> {
+        let _p = profile::span("ExpansionCache::open");
+        let file_name = dylib_path
+            .file_name()
+            .and_then(|it| it.to_str())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid dylib path"))?;
+        let dir = dylib_path
+            .parent()
+            .and_then(AbsPath::parent)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid dylib path"))?
+            .join("rust-analyzer")
+            .join("proc-macro-cache");
+        fs::create_dir_all(&dir)?;
+
+        // The metadata stands in for the contents, which would be too slow to hash.
+        let mut hasher = StableHasher::new();
+        for path in [dylib_path, server_path] {
+            let metadata = fs::metadata(path)?;
+            let mtime = metadata.modified()?.duration_since(UNIX_EPOCH).unwrap_or_default();
+            hasher.write(path.as_os_str().to_string_lossy().as_bytes());
+            hasher.write(&metadata.len().to_le_bytes());
+            hasher.write(&mtime.as_nanos().to_le_bytes());
+        }
+        let name = format!("{}-v{}-{:032x}.jsonl", file_name, FORMAT_VERSION, hasher.finish());
+        let prefix = format!("{}-", file_name);
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let stale = entry
+                .file_name()
+                .to_str()
+                .map_or(false, |it| it.starts_with(&prefix) && it != name);
+            if stale {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+
+        let path = dir.join(name);
+        let (entries, n_lines) = match fs::read_to_string(&path) {
+            // Lines that don't parse were cut off by an interrupted write.
+            Ok(text) => {
+                let entries: HashMap<_, _> = text
+                    .lines()
+                    .filter_map(|line| {
+                        let (key, json) = line.split_once(' ')?;
+                        serde_json::from_str::<IgnoredAny>(json).ok()?;
+                        Some((u128::from_str_radix(key, 16).ok()?, json.to_string()))
+                    })
+                    .collect();
+                (entries, text.lines().count())
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (HashMap::new(), 0),
+            Err(e) => return Err(e),
+        };
+        if n_lines != entries.len() {
+            // Drop the broken and duplicate lines, so that the file doesn't keep growing.
+            let tmp = dir.join(format!("{}.tmp", path.file_name().unwrap().to_string_lossy()));
+            let mut buf = String::new();
+            for (key, json) in &entries {
+                buf.push_str(&format!("{:032x} {}\n", key, json));
+            }
+            fs::write(&tmp, buf)?;
+            fs::rename(&tmp, &path)?;
+        }
+        let file = fs::OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(ExpansionCache { path, inner: Mutex::new(Inner { entries, file }) })
+    }
+
+    pub(crate) fn get(&self, key: u128) -> Option<FlatTree> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        serde_json::from_str(inner.entries.get(&key)?).ok()
+    }
+
+    pub(crate) fn insert(&self, key: u128, tree: &FlatTree) {
+        let json = match serde_json::to_string(tree) {
+            Ok(it) => it,
+            Err(_) => return,
+        };
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = writeln!(inner.file, "{:032x} {}", key, json) {
+            tracing::warn!("failed to write to {}: {}", self.path.display(), e);
+        }
+        inner.entries.insert(key, json);
+    }
+}
+
+/// Digests everything an expansion depends on, except for the dylib itself.
+pub(crate) fn expansion_key(task: &ExpandMacro) -> u128 {
+    // The JSON serialization is stable across builds, unlike `Hash` implementations.
+    let input =
+        (&task.macro_name, &task.macro_body, &task.attributes, &task.env, &task.current_dir);
+    let mut hasher = StableHasher::new();
+    hasher.write(&serde_json::to_vec(&input).unwrap_or_default());
+    hasher.finish()
+}
+
+/// 128-bit FNV-1a, which gives the same digest on every platform and in every build.
+struct StableHasher(u128);
+
+impl StableHasher {
+    const OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    fn new() -> StableHasher {
+        StableHasher(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u128;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn finish(&self) -> u128 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tt::{Ident, Subtree, TokenId, TokenTree};
+
+    struct TempDir(std::path::PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let root = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+            fs::create_dir_all(root.join("debug").join("deps")).unwrap();
+            TempDir(root)
+        }
+
+        fn path(&self, rel: &str) -> AbsPathBuf {
+            AbsPathBuf::assert(self.0.join(rel))
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn foo_tree() -> Subtree {
+        let mut subtree = Subtree::default();
+        subtree
+            .token_trees
+            .push(TokenTree::Leaf(Ident { text: "Foo".into(), id: TokenId(0) }.into()));
+        subtree
+    }
+
+    #[test]
+    fn cache_survives_reopening_until_dylib_changes() {
+        let dir = TempDir::new("ra-expansion-cache");
+        let dylib = dir.path("debug/deps/libfoo_derive.so");
+        let server = dir.path("proc-macro-srv");
+        fs::write(&dylib, "v1").unwrap();
+        fs::write(&server, "srv").unwrap();
+
+        let subtree = foo_tree();
+        ExpansionCache::open(&dylib, &server).unwrap().insert(92, &FlatTree::new(&subtree));
+        let cache = ExpansionCache::open(&dylib, &server).unwrap();
+        assert_eq!(cache.get(92).map(FlatTree::to_subtree), Some(subtree));
+        assert!(cache.get(93).is_none());
+        drop(cache);
+
+        fs::write(&dylib, "v2, rebuilt").unwrap();
+        assert!(ExpansionCache::open(&dylib, &server).unwrap().get(92).is_none());
+        let cache_files = fs::read_dir(dir.path("debug/rust-analyzer/proc-macro-cache")).unwrap();
+        assert_eq!(cache_files.count(), 1);
+    }
+
+    #[test]
+    fn cache_is_invalidated_by_a_new_server() {
+        let dir = TempDir::new("ra-expansion-cache-server");
+        let dylib = dir.path("debug/deps/libfoo_derive.so");
+        let server = dir.path("proc-macro-srv");
+        fs::write(&dylib, "v1").unwrap();
+        fs::write(&server, "srv").unwrap();
+
+        ExpansionCache::open(&dylib, &server).unwrap().insert(92, &FlatTree::new(&foo_tree()));
+        fs::write(&server, "srv, updated").unwrap();
+        assert!(ExpansionCache::open(&dylib, &server).unwrap().get(92).is_none());
+    }
+
+    #[test]
+    fn cache_is_compacted_on_open() {
+        let dir = TempDir::new("ra-expansion-cache-compact");
+        let dylib = dir.path("debug/deps/libfoo_derive.so");
+        let server = dir.path("proc-macro-srv");
+        fs::write(&dylib, "v1").unwrap();
+        fs::write(&server, "srv").unwrap();
+
+        let subtree = foo_tree();
+        let cache = ExpansionCache::open(&dylib, &server).unwrap();
+        cache.insert(92, &FlatTree::new(&subtree));
+        cache.insert(92, &FlatTree::new(&subtree));
+        let path = cache.path.clone();
+        drop(cache);
+        fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"5c {\"sub").unwrap();
+
+        let cache = ExpansionCache::open(&dylib, &server).unwrap();
+        assert_eq!(cache.get(92).map(FlatTree::to_subtree), Some(subtree));
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    fn expansion_key_depends_on_the_input_only() {
+        let mut task = ExpandMacro {
+            macro_body: FlatTree::new(&foo_tree()),
+            macro_name: "foo".to_string(),
+            attributes: None,
+            lib: "libfoo_derive.so".into(),
+            env: Vec::new(),
+            current_dir: None,
+        };
+        let key = expansion_key(&task);
+        task.lib = "libfoo_derive-copy.so".into();
+        assert_eq!(expansion_key(&task), key);
+        task.env.push(("FOO".to_string(), "1".to_string()));
+        assert_ne!(expansion_key(&task), key);
+    }
+}
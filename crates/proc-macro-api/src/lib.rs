@@ -5,21 +5,25 @@
 //! is used to provide basic infrastructure for communication between two
 //! processes: Client (RA itself), Server (the external program)
 
+mod cache;
 pub mod msg;
 mod process;
 mod version;
 
-use paths::AbsPathBuf;
+use paths::{AbsPath, AbsPathBuf};
 use std::{
-    ffi::OsStr,
+    collections::HashMap,
+    ffi::{OsStr, OsString},
     fmt, io,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use serde::{Deserialize, Serialize};
 use tt::Subtree;
 
 use crate::{
+    cache::ExpansionCache,
     msg::{ExpandMacro, FlatTree, PanicMessage},
     process::ProcMacroProcessSrv,
 };
@@ -33,17 +37,80 @@ pub enum ProcMacroKind {
     Attr,
 }
 
-/// A handle to an external process which load dylibs with macros (.so or .dll)
-/// and runs actual macro expansion functions.
+/// A handle to a pool of external processes which load dylibs with macros (.so or .dll)
+/// and run actual macro expansion functions.
+///
+/// Each dylib gets its own worker process, so that a macro crate that hangs or crashes
+/// only takes down its own worker, which is restarted for the next expansion.
 #[derive(Debug)]
 pub struct ProcMacroServer {
-    /// Currently, the proc macro process expands all procedural macros sequentially.
+    process_path: AbsPathBuf,
+    args: Vec<OsString>,
+    expansion_timeout: Duration,
+    /// Whether expansions are cached on disk, see the `cache` module.
+    expansion_cache: bool,
+    /// The process spawned to check that the server runs, handed to the first worker.
+    spare: Mutex<Option<ProcMacroProcessSrv>>,
+    workers: Mutex<HashMap<AbsPathBuf, Arc<Worker>>>,
+}
+
+/// A worker process expanding the macros of a single dylib.
+#[derive(Debug)]
+struct Worker {
+    process_path: AbsPathBuf,
+    args: Vec<OsString>,
+    /// Currently, the proc macro process expands all procedural macros of its dylib
+    /// sequentially.
     ///
     /// That means that concurrent salsa requests may block each other when expanding proc macros,
     /// which is unfortunate, but simple and good enough for the time being.
     ///
-    /// Therefore, we just wrap the `ProcMacroProcessSrv` in a mutex here.
-    process: Arc<Mutex<ProcMacroProcessSrv>>,
+    /// Therefore, we just wrap the `ProcMacroProcessSrv` in a mutex here. It is `None` after
+    /// the process was killed, and spawned again on the next request.
+    process: Mutex<Option<ProcMacroProcessSrv>>,
+}
+
+impl Worker {
+    fn send_task(
+        &self,
+        request: msg::Request,
+        timeout: Option<Duration>,
+    ) -> Result<msg::Response, ServerError> {
+        let mut process = self.process.lock().unwrap_or_else(|e| e.into_inner());
+        let srv = match &mut *process {
+            Some(it) => it,
+            None => {
+                let srv = ProcMacroProcessSrv::run(self.process_path.clone(), &self.args, None)
+                    .map_err(|err| ServerError {
+                        message: "failed to restart proc-macro server".to_string(),
+                        io: Some(err),
+                    })?;
+                process.insert(srv)
+            }
+        };
+        let res = srv.send_task(request, timeout);
+        if res.is_err() {
+            // The process either died or hangs, kill it and start afresh next time.
+            *process = None;
+        }
+        res
+    }
+
+    /// Sends `request` to a new process with `RUST_BACKTRACE=full`, which is dropped
+    /// afterwards. The backtrace style is only read on the first panic of a process, so the
+    /// worker can't be reused for this.
+    fn send_task_with_full_backtrace(
+        &self,
+        request: msg::Request,
+        timeout: Duration,
+    ) -> Result<msg::Response, ServerError> {
+        let mut srv = ProcMacroProcessSrv::run(self.process_path.clone(), &self.args, Some("full"))
+            .map_err(|err| ServerError {
+                message: "failed to start proc-macro server".to_string(),
+                io: Some(err),
+            })?;
+        srv.send_task(request, Some(timeout))
+    }
 }
 
 pub struct MacroDylib {
@@ -68,11 +135,13 @@ impl MacroDylib {
 
 /// A handle to a specific macro (a `#[proc_macro]` annotated function).
 ///
-/// It exists withing a context of a specific [`ProcMacroProcess`] -- currently
-/// we share a single expander process for all macros.
+/// It exists withing a context of a specific [`ProcMacroProcess`] -- the worker
+/// process of its dylib.
 #[derive(Debug, Clone)]
 pub struct ProcMacro {
-    process: Arc<Mutex<ProcMacroProcessSrv>>,
+    worker: Arc<Worker>,
+    expansion_timeout: Duration,
+    cache: Option<Arc<ExpansionCache>>,
     dylib_path: AbsPathBuf,
     name: String,
     kind: ProcMacroKind,
@@ -84,7 +153,7 @@ impl PartialEq for ProcMacro {
         self.name == other.name
             && self.kind == other.kind
             && self.dylib_path == other.dylib_path
-            && Arc::ptr_eq(&self.process, &other.process)
+            && Arc::ptr_eq(&self.worker, &other.worker)
     }
 }
 
@@ -110,12 +179,49 @@ pub struct MacroPanic {
 
 impl ProcMacroServer {
     /// Spawns an external process as the proc macro server and returns a client connected to it.
+    ///
+    /// Expansions taking longer than `expansion_timeout` fail, and their worker process is
+    /// killed.
     pub fn spawn(
         process_path: AbsPathBuf,
         args: impl IntoIterator<Item = impl AsRef<OsStr>>,
+        expansion_timeout: Duration,
     ) -> io::Result<ProcMacroServer> {
-        let process = ProcMacroProcessSrv::run(process_path, args)?;
-        Ok(ProcMacroServer { process: Arc::new(Mutex::new(process)) })
+        let args: Vec<OsString> = args.into_iter().map(|it| it.as_ref().into()).collect();
+        let process = ProcMacroProcessSrv::run(process_path.clone(), &args, None)?;
+        Ok(ProcMacroServer {
+            process_path,
+            args,
+            expansion_timeout,
+            expansion_cache: false,
+            spare: Mutex::new(Some(process)),
+            workers: Mutex::default(),
+        })
+    }
+
+    /// Enables the on-disk cache of the expansions of the dylibs loaded from now on.
+    pub fn set_expansion_cache(&mut self, enabled: bool) {
+        self.expansion_cache = enabled;
+    }
+
+    /// Drops the workers of the dylibs `keep` returns `false` for, like the ones that are no
+    /// longer part of the crate graph. A worker process exits once the macros loaded from its
+    /// dylib are dropped as well.
+    pub fn retain_dylibs(&self, keep: impl Fn(&AbsPath) -> bool) {
+        let mut workers = self.workers.lock().unwrap_or_else(|e| e.into_inner());
+        workers.retain(|path, _| keep(path));
+    }
+
+    fn worker(&self, dylib_path: &AbsPath) -> Arc<Worker> {
+        let mut workers = self.workers.lock().unwrap_or_else(|e| e.into_inner());
+        let worker = workers.entry(dylib_path.to_path_buf()).or_insert_with(|| {
+            Arc::new(Worker {
+                process_path: self.process_path.clone(),
+                args: self.args.clone(),
+                process: Mutex::new(self.spare.lock().unwrap_or_else(|e| e.into_inner()).take()),
+            })
+        });
+        worker.clone()
     }
 
     pub fn load_dylib(
@@ -123,14 +229,38 @@ impl ProcMacroServer {
         dylib: MacroDylib,
     ) -> Result<Result<Vec<ProcMacro>, String>, ServerError> {
         let _p = profile::span("ProcMacroClient::by_dylib_path");
-        let macros =
-            self.process.lock().unwrap_or_else(|e| e.into_inner()).find_proc_macros(&dylib.path)?;
+        let worker = self.worker(&dylib.path);
+        let request = msg::Request::ListMacros { dylib_path: dylib.path.to_path_buf().into() };
+        let macros = match worker.send_task(request, None)? {
+            msg::Response::ListMacros(it) => it,
+            msg::Response::ExpandMacro { .. } => {
+                return Err(ServerError { message: "unexpected response".to_string(), io: None })
+            }
+        };
+
+        let cache = if self.expansion_cache {
+            match ExpansionCache::open(&dylib.path, &self.process_path) {
+                Ok(it) => Some(Arc::new(it)),
+                Err(err) => {
+                    tracing::warn!(
+                        "failed to open the expansion cache of {}: {}",
+                        dylib.path.display(),
+                        err
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
 
         let res = macros.map(|macros| {
             macros
                 .into_iter()
                 .map(|(name, kind)| ProcMacro {
-                    process: self.process.clone(),
+                    worker: worker.clone(),
+                    expansion_timeout: self.expansion_timeout,
+                    cache: cache.clone(),
                     name,
                     kind,
                     dylib_path: dylib.path.clone(),
@@ -151,33 +281,73 @@ impl ProcMacro {
         self.kind
     }
 
+    /// Expands the macro. If it panics, the message contains the location of the panic, and a
+    /// backtrace if `RUST_BACKTRACE` is set.
     pub fn expand(
         &self,
         subtree: &Subtree,
         attr: Option<&Subtree>,
         env: Vec<(String, String)>,
     ) -> Result<Result<Subtree, PanicMessage>, ServerError> {
+        let task = self.expand_task(subtree, attr, env);
+        let cached = self.cache.as_ref().map(|it| (it, cache::expansion_key(&task)));
+        if let Some((cache, key)) = cached {
+            if let Some(tree) = cache.get(key) {
+                return Ok(Ok(tree.to_subtree()));
+            }
+        }
+
+        let request = msg::Request::ExpandMacro(task);
+        let response = self.worker.send_task(request, Some(self.expansion_timeout))?;
+        match response {
+            msg::Response::ExpandMacro(it) => {
+                // Panics aren't cached, they might be caused by the environment.
+                if let (Some((cache, key)), Ok(tree)) = (cached, &it) {
+                    cache.insert(key, tree);
+                }
+                Ok(it.map(FlatTree::to_subtree))
+            }
+            msg::Response::ListMacros { .. } => {
+                Err(ServerError { message: "unexpected response".to_string(), io: None })
+            }
+        }
+    }
+
+    /// Expands the macro in a new process with `RUST_BACKTRACE=full`, bypassing the cache, to
+    /// get the full backtrace of a panic.
+    pub fn expand_with_backtrace(
+        &self,
+        subtree: &Subtree,
+        attr: Option<&Subtree>,
+        env: Vec<(String, String)>,
+    ) -> Result<Result<Subtree, PanicMessage>, ServerError> {
+        let request = msg::Request::ExpandMacro(self.expand_task(subtree, attr, env));
+        match self.worker.send_task_with_full_backtrace(request, self.expansion_timeout)? {
+            msg::Response::ExpandMacro(it) => Ok(it.map(FlatTree::to_subtree)),
+            msg::Response::ListMacros { .. } => {
+                Err(ServerError { message: "unexpected response".to_string(), io: None })
+            }
+        }
+    }
+
+    fn expand_task(
+        &self,
+        subtree: &Subtree,
+        attr: Option<&Subtree>,
+        env: Vec<(String, String)>,
+    ) -> ExpandMacro {
         let current_dir = env
             .iter()
             .find(|(name, _)| name == "CARGO_MANIFEST_DIR")
             .map(|(_, value)| value.clone());
 
-        let task = ExpandMacro {
+        ExpandMacro {
             macro_body: FlatTree::new(subtree),
             macro_name: self.name.to_string(),
             attributes: attr.map(FlatTree::new),
             lib: self.dylib_path.to_path_buf().into(),
             env,
             current_dir,
-        };
-
-        let request = msg::Request::ExpandMacro(task);
-        let response = self.process.lock().unwrap_or_else(|e| e.into_inner()).send_task(request)?;
-        match response {
-            msg::Response::ExpandMacro(it) => Ok(it.map(FlatTree::to_subtree)),
-            msg::Response::ListMacros { .. } => {
-                Err(ServerError { message: "unexpected response".to_string(), io: None })
-            }
         }
     }
 }
@@ -4,6 +4,9 @@ use std::{
     ffi::{OsStr, OsString},
     io::{self, BufRead, BufReader, Write},
     process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
 };
 
 use paths::{AbsPath, AbsPathBuf};
@@ -11,48 +14,129 @@ use stdx::JodChild;
 
 use crate::{
     msg::{Message, Request, Response},
-    ProcMacroKind, ServerError,
+    ServerError,
 };
 
 #[derive(Debug)]
 pub(crate) struct ProcMacroProcessSrv {
-    _process: Process,
     stdin: ChildStdin,
     stdout: BufReader<ChildStdout>,
+    watchdog: Watchdog,
 }
 
 impl ProcMacroProcessSrv {
+    /// Spawns the server. `rust_backtrace` overrides the `RUST_BACKTRACE` of the process, which
+    /// decides whether panic messages contain a backtrace.
     pub(crate) fn run(
         process_path: AbsPathBuf,
         args: impl IntoIterator<Item = impl AsRef<OsStr>>,
+        rust_backtrace: Option<&str>,
     ) -> io::Result<ProcMacroProcessSrv> {
-        let mut process = Process::run(process_path, args)?;
+        let mut process = Process::run(process_path, args, rust_backtrace)?;
         let (stdin, stdout) = process.stdio().expect("couldn't access child stdio");
 
-        let srv = ProcMacroProcessSrv { _process: process, stdin, stdout };
+        let srv = ProcMacroProcessSrv { stdin, stdout, watchdog: Watchdog::spawn(process) };
 
         Ok(srv)
     }
 
-    pub(crate) fn find_proc_macros(
+    /// Sends `req` and waits for the response, killing the process if it takes longer than
+    /// `timeout`.
+    pub(crate) fn send_task(
         &mut self,
-        dylib_path: &AbsPath,
-    ) -> Result<Result<Vec<(String, ProcMacroKind)>, String>, ServerError> {
-        let request = Request::ListMacros { dylib_path: dylib_path.to_path_buf().into() };
+        req: Request,
+        timeout: Option<Duration>,
+    ) -> Result<Response, ServerError> {
+        if let Some(timeout) = timeout {
+            self.watchdog.arm(timeout);
+        }
+        let mut buf = String::new();
+        let res = send_request(&mut self.stdin, &mut self.stdout, req, &mut buf);
+        match timeout {
+            Some(timeout) if self.watchdog.disarm() => Err(ServerError {
+                message: format!("proc-macro expansion timed out after {:?}", timeout),
+                io: None,
+            }),
+            _ => res,
+        }
+    }
+}
 
-        let response = self.send_task(request)?;
+/// Owns the process and kills it when a request takes longer than its timeout. Killing closes
+/// the process' stdout, which unblocks the pending read of the response.
+///
+/// A single thread watches all requests of the process, and exits when the process is dropped.
+#[derive(Debug)]
+struct Watchdog {
+    state: Arc<(Mutex<WatchdogState>, Condvar)>,
+}
+
+#[derive(Debug)]
+struct WatchdogState {
+    process: Option<Process>,
+    deadline: Option<Instant>,
+    fired: bool,
+}
 
-        match response {
-            Response::ListMacros(it) => Ok(it),
-            Response::ExpandMacro { .. } => {
-                Err(ServerError { message: "unexpected response".to_string(), io: None })
+impl Watchdog {
+    fn spawn(process: Process) -> Watchdog {
+        let state = WatchdogState { process: Some(process), deadline: None, fired: false };
+        let state = Arc::new((Mutex::new(state), Condvar::new()));
+        thread::spawn({
+            let state = state.clone();
+            move || {
+                let (state, changed) = &*state;
+                let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                while state.process.is_some() {
+                    state = match state.deadline {
+                        None => changed.wait(state).unwrap_or_else(|e| e.into_inner()),
+                        Some(deadline) => {
+                            let now = Instant::now();
+                            if now < deadline {
+                                changed
+                                    .wait_timeout(state, deadline - now)
+                                    .unwrap_or_else(|e| e.into_inner())
+                                    .0
+                            } else {
+                                state.deadline = None;
+                                state.fired = true;
+                                if let Some(process) = &mut state.process {
+                                    let _ = process.child.kill();
+                                }
+                                state
+                            }
+                        }
+                    };
+                }
             }
-        }
+        });
+        Watchdog { state }
     }
 
-    pub(crate) fn send_task(&mut self, req: Request) -> Result<Response, ServerError> {
-        let mut buf = String::new();
-        send_request(&mut self.stdin, &mut self.stdout, req, &mut buf)
+    /// Kills the process if it isn't disarmed within `timeout`.
+    fn arm(&self, timeout: Duration) {
+        let (state, changed) = &*self.state;
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        state.deadline = Some(Instant::now() + timeout);
+        state.fired = false;
+        changed.notify_one();
+    }
+
+    /// Disarms the watchdog, returning whether it killed the process.
+    fn disarm(&self) -> bool {
+        let (state, _) = &*self.state;
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        state.deadline = None;
+        std::mem::take(&mut state.fired)
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        let (state, changed) = &*self.state;
+        // Dropping the process kills it, and lets the thread exit.
+        state.lock().unwrap_or_else(|e| e.into_inner()).process = None;
+        changed.notify_one();
     }
 }
 
@@ -65,9 +149,10 @@ impl Process {
     fn run(
         path: AbsPathBuf,
         args: impl IntoIterator<Item = impl AsRef<OsStr>>,
+        rust_backtrace: Option<&str>,
     ) -> io::Result<Process> {
         let args: Vec<OsString> = args.into_iter().map(|s| s.as_ref().into()).collect();
-        let child = JodChild(mk_child(&path, &args)?);
+        let child = JodChild(mk_child(&path, &args, rust_backtrace)?);
         Ok(Process { child })
     }
 
@@ -83,13 +168,14 @@ impl Process {
 fn mk_child(
     path: &AbsPath,
     args: impl IntoIterator<Item = impl AsRef<OsStr>>,
+    rust_backtrace: Option<&str>,
 ) -> io::Result<Child> {
-    Command::new(path.as_os_str())
-        .args(args)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::inherit())
-        .spawn()
+    let mut cmd = Command::new(path.as_os_str());
+    cmd.args(args).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::inherit());
+    if let Some(rust_backtrace) = rust_backtrace {
+        cmd.env("RUST_BACKTRACE", rust_backtrace);
+    }
+    cmd.spawn()
 }
 
 fn send_request(
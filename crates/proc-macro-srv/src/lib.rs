@@ -13,6 +13,7 @@
 
 mod dylib;
 mod abis;
+mod stderr;
 
 use std::{
     collections::{hash_map::Entry, HashMap},
@@ -23,6 +24,8 @@ use std::{
     time::SystemTime,
 };
 
+use stderr::StderrCapture;
+
 use proc_macro_api::{
     msg::{ExpandMacro, FlatTree, PanicMessage},
     ProcMacroKind,
@@ -31,10 +34,17 @@ use proc_macro_api::{
 #[derive(Default)]
 pub(crate) struct ProcMacroSrv {
     expanders: HashMap<(PathBuf, SystemTime), dylib::Expander>,
+    stderr: Option<StderrCapture>,
 }
 
 impl ProcMacroSrv {
     pub fn expand(&mut self, task: ExpandMacro) -> Result<FlatTree, PanicMessage> {
+        let mut capture = match self.stderr.take() {
+            Some(it) => Some(it),
+            None => StderrCapture::new()
+                .map_err(|err| eprintln!("Failed to capture the stderr of macros: {}", err))
+                .ok(),
+        };
         let expander = self.expander(task.lib.as_ref()).map_err(|err| {
             debug_assert!(false, "should list macros before asking to expand");
             PanicMessage(format!("failed to load macro: {}", err))
@@ -57,9 +67,26 @@ impl ProcMacroSrv {
 
         let macro_body = task.macro_body.to_subtree();
         let attributes = task.attributes.map(|it| it.to_subtree());
-        let result = expander
-            .expand(&task.macro_name, &macro_body, attributes.as_ref())
-            .map(|it| FlatTree::new(&it));
+        let expand = || expander.expand(&task.macro_name, &macro_body, attributes.as_ref());
+        let (result, output) = match &mut capture {
+            Some(capture) => capture.capture(expand),
+            None => (expand(), Ok(String::new())),
+        };
+        let result = match (result, output) {
+            // The panic hook of the macro's std printed the location and the backtrace.
+            (Err(message), Ok(output)) => match stderr::panic_report(&output) {
+                Some(report) => Err(format!("{}\n\n{}", message, report)),
+                None => Err(message),
+            },
+            (result, Ok(_)) => result,
+            (result, Err(err)) => {
+                eprintln!("Failed to capture the stderr of macros: {}", err);
+                capture = None;
+                result
+            }
+        }
+        .map(|it| FlatTree::new(&it));
+        self.stderr = capture;
 
         prev_env.rollback();
 
@@ -0,0 +1,157 @@
+//! Captures the stderr output of an expansion.
+//!
+//! A proc-macro links its own copy of std, so our panic hook never sees its panics. Its default
+//! panic hook prints the location and the backtrace to the stderr of the process though, which
+//! is redirected to a file while the macro runs.
+
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom},
+    path::PathBuf,
+    process,
+};
+
+pub(crate) struct StderrCapture {
+    file: File,
+    path: PathBuf,
+}
+
+impl StderrCapture {
+    pub(crate) fn new() -> io::Result<StderrCapture> {
+        let path =
+            env::temp_dir().join(format!("rust-analyzer-proc-macro-srv-{}.stderr", process::id()));
+        let file =
+            OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path)?;
+        Ok(StderrCapture { file, path })
+    }
+
+    /// Runs `f` with stderr redirected, and returns everything it printed. The output is
+    /// forwarded to the real stderr afterwards, so it still ends up in the log. `f` runs even
+    /// if stderr can't be redirected.
+    pub(crate) fn capture<R>(&mut self, f: impl FnOnce() -> R) -> (R, io::Result<String>) {
+        let saved = match self.rewind().and_then(|()| imp::redirect(&self.file)) {
+            Ok(it) => it,
+            Err(err) => return (f(), Err(err)),
+        };
+        let res = f();
+        imp::restore(saved);
+        (res, self.read())
+    }
+
+    fn rewind(&mut self) -> io::Result<()> {
+        self.file.set_len(0)?;
+        self.file.seek(SeekFrom::Start(0))?;
+        Ok(())
+    }
+
+    fn read(&mut self) -> io::Result<String> {
+        if self.file.seek(SeekFrom::Current(0))? == 0 {
+            return Ok(String::new());
+        }
+        self.file.seek(SeekFrom::Start(0))?;
+        let mut bytes = Vec::new();
+        self.file.read_to_end(&mut bytes)?;
+        let output = String::from_utf8_lossy(&bytes).into_owned();
+        eprint!("{}", output);
+        Ok(output)
+    }
+}
+
+impl Drop for StderrCapture {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// The output of the panic hook in the stderr output of an expansion, dropping anything the
+/// macro printed before it panicked.
+pub(crate) fn panic_report(output: &str) -> Option<&str> {
+    let start = output
+        .match_indices("thread '")
+        .map(|(idx, _)| idx)
+        .filter(|&idx| idx == 0 || output[..idx].ends_with('\n'))
+        .filter(|&idx| output[idx..].lines().next().map_or(false, |it| it.contains(" panicked at")))
+        .last()?;
+    Some(output[start..].trim_end())
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::{fs::File, io, os::unix::io::AsRawFd};
+
+    pub(super) struct Saved(libc::c_int);
+
+    pub(super) fn redirect(file: &File) -> io::Result<Saved> {
+        // SAFETY: only duplicates file descriptors we own.
+        unsafe {
+            let saved = libc::dup(libc::STDERR_FILENO);
+            if saved == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::dup2(file.as_raw_fd(), libc::STDERR_FILENO) == -1 {
+                let err = io::Error::last_os_error();
+                libc::close(saved);
+                return Err(err);
+            }
+            Ok(Saved(saved))
+        }
+    }
+
+    pub(super) fn restore(saved: Saved) {
+        // SAFETY: `saved` is a duplicate of the original stderr, which we own.
+        unsafe {
+            libc::dup2(saved.0, libc::STDERR_FILENO);
+            libc::close(saved.0);
+        }
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::{fs::File, io, os::windows::io::AsRawHandle};
+
+    use winapi::um::{
+        handleapi::INVALID_HANDLE_VALUE,
+        processenv::{GetStdHandle, SetStdHandle},
+        winbase::STD_ERROR_HANDLE,
+        winnt::HANDLE,
+    };
+
+    pub(super) struct Saved(HANDLE);
+
+    // std looks up the standard handles on every write, so replacing the handle is enough.
+    pub(super) fn redirect(file: &File) -> io::Result<Saved> {
+        // SAFETY: `file` outlives the redirection, `restore` puts the old handle back.
+        unsafe {
+            let saved = GetStdHandle(STD_ERROR_HANDLE);
+            if saved == INVALID_HANDLE_VALUE {
+                return Err(io::Error::last_os_error());
+            }
+            if SetStdHandle(STD_ERROR_HANDLE, file.as_raw_handle() as HANDLE) == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Saved(saved))
+        }
+    }
+
+    pub(super) fn restore(saved: Saved) {
+        // SAFETY: `saved` is the handle stderr had before.
+        unsafe {
+            SetStdHandle(STD_ERROR_HANDLE, saved.0);
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod imp {
+    use std::{fs::File, io};
+
+    pub(super) struct Saved;
+
+    pub(super) fn redirect(_: &File) -> io::Result<Saved> {
+        Err(io::Error::new(io::ErrorKind::Other, "can't redirect stderr on this platform"))
+    }
+
+    pub(super) fn restore(_: Saved) {}
+}
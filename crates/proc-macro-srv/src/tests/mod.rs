@@ -75,6 +75,31 @@ fn test_attr_macro() {
     );
 }
 
+#[test]
+fn panic_message_has_location() {
+    let message = expand_err("fn_like_panic", "foo").unwrap();
+    assert!(message.starts_with("fn_like_panic!(foo)\n\nthread '"), "{}", message);
+    assert!(message.contains(" panicked at "), "{}", message);
+    assert!(message.contains("lib.rs"), "{}", message);
+
+    assert_eq!(expand_err("fn_like_noop", "foo"), None);
+}
+
+#[test]
+fn panic_report_skips_earlier_output() {
+    let output = "\
+warning: thread 'a' panicked at nothing
+thread 'b' panicked at 'oops', src/lib.rs:1:1
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+";
+    expect![[r#"
+        thread 'b' panicked at 'oops', src/lib.rs:1:1
+        note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace"#]]
+    .assert_eq(crate::stderr::panic_report(output).unwrap());
+    assert_eq!(crate::stderr::panic_report("no panic here\n"), None);
+}
+
 /// Tests that we find and classify all proc macros correctly.
 #[test]
 fn list_test_macros() {
@@ -83,6 +108,7 @@ fn list_test_macros() {
     expect![[r#"
         fn_like_noop [FuncLike]
         fn_like_panic [FuncLike]
+        fn_like_loop [FuncLike]
         fn_like_error [FuncLike]
         fn_like_clone_tokens [FuncLike]
         attr_noop [Attr]
@@ -3,6 +3,7 @@
 use crate::dylib;
 use crate::ProcMacroSrv;
 use expect_test::Expect;
+use proc_macro_api::msg::{ExpandMacro, FlatTree};
 use std::str::FromStr;
 
 pub mod fixtures {
@@ -39,6 +40,19 @@ fn assert_expand_impl(macro_name: &str, input: &str, attr: Option<&str>, expect:
     expect.assert_eq(&format!("{:?}", res));
 }
 
+/// Expands `macro_name` through the server, returning the panic message on failure.
+pub(crate) fn expand_err(macro_name: &str, input: &str) -> Option<String> {
+    let task = ExpandMacro {
+        macro_body: FlatTree::new(&parse_string(input).unwrap().into_subtree()),
+        macro_name: macro_name.to_string(),
+        attributes: None,
+        lib: fixtures::proc_macro_test_dylib_path(),
+        env: Vec::new(),
+        current_dir: None,
+    };
+    ProcMacroSrv::default().expand(task).err().map(|it| it.0)
+}
+
 pub(crate) fn list() -> Vec<String> {
     let dylib_path = fixtures::proc_macro_test_dylib_path();
     let mut srv = ProcMacroSrv::default();
@@ -12,6 +12,13 @@ pub fn fn_like_panic(args: TokenStream) -> TokenStream {
     panic!("fn_like_panic!({})", args);
 }
 
+#[proc_macro]
+pub fn fn_like_loop(_args: TokenStream) -> TokenStream {
+    loop {
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+}
+
 #[proc_macro]
 pub fn fn_like_error(args: TokenStream) -> TokenStream {
     format!("compile_error!(\"fn_like_error!({})\");", args).parse().unwrap()
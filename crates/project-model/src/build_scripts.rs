@@ -35,6 +35,9 @@ pub(crate) struct BuildScriptOutput {
     pub(crate) out_dir: Option<AbsPathBuf>,
     /// Path to the proc-macro library file if this package exposes proc-macros.
     pub(crate) proc_macro_dylib_path: Option<AbsPathBuf>,
+    /// Paths to the binaries built for packages depending on them through
+    /// artifact dependencies, by binary name.
+    pub(crate) bin_executables: Vec<(String, AbsPathBuf)>,
 }
 
 impl WorkspaceBuildScripts {
@@ -170,6 +173,18 @@ impl WorkspaceBuildScripts {
                                 res.outputs[package].proc_macro_dylib_path = Some(filename);
                             }
                         }
+
+                        if message.target.kind.iter().any(|k| k == "bin") {
+                            // Only set when the binary was actually built, which `cargo check`
+                            // does for artifact dependencies.
+                            if let Some(executable) = message.executable {
+                                let executable =
+                                    AbsPathBuf::assert(PathBuf::from(executable.into_os_string()));
+                                res.outputs[package]
+                                    .bin_executables
+                                    .push((message.target.name.clone(), executable));
+                            }
+                        }
                     }
                     Message::CompilerMessage(message) => {
                         progress(message.target.name);
@@ -10,7 +10,7 @@ use cargo_metadata::{CargoOpt, MetadataCommand};
 use la_arena::{Arena, Idx};
 use paths::{AbsPath, AbsPathBuf};
 use rustc_hash::FxHashMap;
-use serde::Deserialize;
+use serde::{de, Deserialize, Deserializer};
 use serde_json::from_value;
 
 use crate::CfgOverrides;
@@ -31,6 +31,7 @@ pub struct CargoWorkspace {
     packages: Arena<PackageData>,
     targets: Arena<TargetData>,
     workspace_root: AbsPathBuf,
+    target_directory: AbsPathBuf,
 }
 
 impl ops::Index<Package> for CargoWorkspace {
@@ -70,6 +71,15 @@ impl Default for UnsetTestCrates {
     }
 }
 
+/// Compilation target settings of a single package that differ from the workspace-wide ones.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct CrateTarget {
+    /// rustc target, replacing [`CargoConfig::target`] for this package.
+    pub target: Option<String>,
+    /// Extra cfg atoms to enable, written like `--cfg` arguments (`foo` or `key="value"`).
+    pub cfgs: Vec<String>,
+}
+
 #[derive(Default, Clone, Debug, PartialEq, Eq)]
 pub struct CargoConfig {
     /// Do not activate the `default` feature.
@@ -85,6 +95,12 @@ pub struct CargoConfig {
     /// rustc target
     pub target: Option<String>,
 
+    /// Per-package target and cfg overrides, keyed by package name.
+    pub crate_targets: FxHashMap<String, CrateTarget>,
+
+    /// Further targets to analyze the workspace for, next to `target`.
+    pub extra_targets: Vec<String>,
+
     /// Don't load sysroot crates (`std`, `core` & friends). Might be useful
     /// when debugging isolated issues.
     pub no_sysroot: bool,
@@ -144,6 +160,9 @@ pub struct PackageData {
     pub is_member: bool,
     /// List of packages this package depends on
     pub dependencies: Vec<PackageDependency>,
+    /// List of packages whose binaries this package depends on, through
+    /// `artifact = "bin"` dependencies
+    pub artifact_dependencies: Vec<ArtifactDependency>,
     /// Rust edition for this package
     pub edition: Edition,
     /// Features provided by the crate, mapped to the features required by that feature.
@@ -168,6 +187,20 @@ pub struct PackageDependency {
     pub kind: DepKind,
 }
 
+/// A dependency on the binaries of a package, declared with `artifact = "bin"`.
+/// Cargo builds these binaries before the depending crate and passes their
+/// paths to it as `CARGO_BIN_FILE_*` environment variables.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ArtifactDependency {
+    pub pkg: Package,
+    /// Name of the dependency, as given in the `Cargo.toml`
+    pub name: String,
+    pub kind: DepKind,
+    /// Names of the binaries this package depends on, empty if it depends on
+    /// all of them
+    pub bins: Vec<String>,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord)]
 pub enum DepKind {
     /// Available to the library, binary, and dev targets in the package (but not the build script).
@@ -197,6 +230,15 @@ impl DepKind {
         dep_kinds.dedup();
         dep_kinds.into_iter()
     }
+
+    fn from_metadata_kind(kind: Option<&str>) -> Option<Self> {
+        match kind {
+            None => Some(Self::Normal),
+            Some("dev") => Some(Self::Dev),
+            Some("build") => Some(Self::Build),
+            Some(_) => None,
+        }
+    }
 }
 
 /// Information associated with a package's target
@@ -246,6 +288,52 @@ impl TargetKind {
     }
 }
 
+/// The output of `cargo metadata`, along with the artifact dependencies of
+/// each package, which `cargo_metadata` doesn't know about.
+#[derive(Debug, Clone)]
+pub struct CargoMetadata {
+    meta: cargo_metadata::Metadata,
+    artifacts: Vec<PackageArtifactsData>,
+}
+
+impl<'de> Deserialize<'de> for CargoMetadata {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Packages {
+            packages: Vec<PackageArtifactsData>,
+        }
+
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let artifacts = Packages::deserialize(&value).map_err(de::Error::custom)?.packages;
+        let meta = cargo_metadata::Metadata::deserialize(value).map_err(de::Error::custom)?;
+        Ok(CargoMetadata { meta, artifacts })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+// Deserialise helper for the artifact dependencies in the cargo metadata
+struct PackageArtifactsData {
+    id: String,
+    dependencies: Vec<DependencyArtifactData>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct DependencyArtifactData {
+    name: String,
+    rename: Option<String>,
+    kind: Option<String>,
+    artifact: Option<ArtifactData>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ArtifactData {
+    /// `bin`, `bin:<name>`, `cdylib` or `staticlib`
+    kinds: Vec<String>,
+    /// Whether the library target is a regular dependency as well
+    #[serde(default)]
+    lib: bool,
+}
+
 #[derive(Deserialize, Default)]
 // Deserialise helper for the cargo metadata
 struct PackageMetadata {
@@ -259,7 +347,7 @@ impl CargoWorkspace {
         current_dir: &AbsPath,
         config: &CargoConfig,
         progress: &dyn Fn(String),
-    ) -> Result<cargo_metadata::Metadata> {
+    ) -> Result<CargoMetadata> {
         let target = config
             .target
             .clone()
@@ -292,13 +380,20 @@ impl CargoWorkspace {
         // unclear whether cargo itself supports it.
         progress("metadata".to_string());
 
-        let meta =
-            meta.exec().with_context(|| format!("Failed to run `{:?}`", meta.cargo_command()))?;
+        // `MetadataCommand::exec` drops the artifact dependencies, so parse
+        // the output ourselves.
+        let cmd = meta.cargo_command();
+        let stdout = utf8_stdout(cmd)
+            .with_context(|| format!("Failed to run `{:?}`", meta.cargo_command()))?;
+        let json = stdout.lines().find(|line| line.starts_with('{')).unwrap_or_default();
+        let meta = serde_json::from_str(json)
+            .with_context(|| format!("Failed to parse `{:?}` output", meta.cargo_command()))?;
 
         Ok(meta)
     }
 
-    pub fn new(mut meta: cargo_metadata::Metadata) -> CargoWorkspace {
+    pub fn new(meta: CargoMetadata) -> CargoWorkspace {
+        let CargoMetadata { meta: mut meta, artifacts } = meta;
         let mut pkg_by_id = FxHashMap::default();
         let mut packages = Arena::default();
         let mut targets = Arena::default();
@@ -338,6 +433,7 @@ impl CargoWorkspace {
                 edition,
                 repository: repository.clone(),
                 dependencies: Vec::new(),
+                artifact_dependencies: Vec::new(),
                 features: meta_pkg.features.clone().into_iter().collect(),
                 active_features: Vec::new(),
                 metadata: meta.rust_analyzer.unwrap_or_default(),
@@ -357,6 +453,19 @@ impl CargoWorkspace {
                 pkg_data.targets.push(tgt);
             }
         }
+        // Artifact dependencies, by the id of the depending package and the
+        // name of the package depended upon.
+        let artifact_deps: FxHashMap<(&str, &str), (&DependencyArtifactData, &ArtifactData)> =
+            artifacts
+                .iter()
+                .flat_map(|pkg| {
+                    pkg.dependencies.iter().filter_map(move |dep| {
+                        let artifact = dep.artifact.as_ref()?;
+                        Some(((pkg.id.as_str(), dep.name.as_str()), (dep, artifact)))
+                    })
+                })
+                .collect();
+
         let resolve = meta.resolve.expect("metadata executed with deps");
         for mut node in resolve.nodes {
             let source = match pkg_by_id.get(&node.id) {
@@ -385,16 +494,61 @@ impl CargoWorkspace {
                         continue;
                     }
                 };
+                match artifact_deps.get(&(node.id.repr.as_str(), packages[pkg].name.as_str())) {
+                    // The binary crate is not usable as a library unless `lib = true` is given.
+                    Some((_, artifact)) if !artifact.lib => continue,
+                    _ => {}
+                }
                 let dep = PackageDependency { name: dep_node.name.clone(), pkg, kind };
                 packages[source].dependencies.push(dep);
             }
+            // Dependencies without a library target are only listed here.
+            for dep_id in &node.dependencies {
+                let pkg = match pkg_by_id.get(dep_id) {
+                    Some(&pkg) => pkg,
+                    None => continue,
+                };
+                let (dep, artifact) = match artifact_deps
+                    .get(&(node.id.repr.as_str(), packages[pkg].name.as_str()))
+                {
+                    Some(it) => *it,
+                    None => continue,
+                };
+                let kind = match DepKind::from_metadata_kind(dep.kind.as_deref()) {
+                    Some(it) => it,
+                    None => continue,
+                };
+                if !artifact.kinds.iter().any(|kind| kind == "bin" || kind.starts_with("bin:")) {
+                    continue;
+                }
+                let bins = if artifact.kinds.iter().any(|kind| kind == "bin") {
+                    Vec::new()
+                } else {
+                    artifact
+                        .kinds
+                        .iter()
+                        .filter_map(|kind| kind.strip_prefix("bin:"))
+                        .map(ToOwned::to_owned)
+                        .collect()
+                };
+                let name = dep.rename.clone().unwrap_or_else(|| dep.name.clone());
+                packages[source].artifact_dependencies.push(ArtifactDependency {
+                    pkg,
+                    name,
+                    kind,
+                    bins,
+                });
+            }
             packages[source].active_features.extend(node.features);
         }
 
         let workspace_root =
             AbsPathBuf::assert(PathBuf::from(meta.workspace_root.into_os_string()));
 
-        CargoWorkspace { packages, targets, workspace_root }
+        let target_directory =
+            AbsPathBuf::assert(PathBuf::from(meta.target_directory.into_os_string()));
+
+        CargoWorkspace { packages, targets, workspace_root, target_directory }
     }
 
     pub fn packages<'a>(&'a self) -> impl Iterator<Item = Package> + ExactSizeIterator + 'a {
@@ -412,6 +566,11 @@ impl CargoWorkspace {
         &self.workspace_root
     }
 
+    /// The directory cargo puts its build artifacts in, `target` by default.
+    pub fn target_directory(&self) -> &AbsPath {
+        &self.target_directory
+    }
+
     pub fn package_flag(&self, package: &PackageData) -> String {
         if self.is_unique(&*package.name) {
             package.name.clone()
@@ -22,6 +22,8 @@ mod project_json;
 mod sysroot;
 mod workspace;
 mod rustc_cfg;
+mod rustc_version;
+mod target_data_layout;
 mod build_scripts;
 
 #[cfg(test)]
@@ -40,13 +42,15 @@ use rustc_hash::FxHashSet;
 pub use crate::{
     build_scripts::WorkspaceBuildScripts,
     cargo_workspace::{
-        CargoConfig, CargoWorkspace, Package, PackageData, PackageDependency, RustcSource, Target,
-        TargetData, TargetKind, UnsetTestCrates,
+        ArtifactDependency, CargoConfig, CargoMetadata, CargoWorkspace, CrateTarget, Package,
+        PackageData, PackageDependency, RustcSource, Target, TargetData, TargetKind,
+        UnsetTestCrates,
     },
+    cfg_flag::CfgFlag,
     manifest_path::ManifestPath,
-    project_json::{ProjectJson, ProjectJsonData},
-    sysroot::Sysroot,
-    workspace::{CfgOverrides, PackageRoot, ProjectWorkspace},
+    project_json::{Build, ProjectJson, ProjectJsonData, Runnable, RunnableKind},
+    sysroot::{MissingSysrootSource, Sysroot},
+    workspace::{CfgOverrides, CrateTargetOverride, PackageRoot, ProjectWorkspace},
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
@@ -12,7 +12,7 @@ use paths::{AbsPath, AbsPathBuf};
 use rustc_hash::FxHashMap;
 use serde::{de, Deserialize};
 
-use crate::cfg_flag::CfgFlag;
+use crate::{cfg_flag::CfgFlag, TargetKind};
 
 /// Roots and crates that compose this Rust project.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -20,6 +20,7 @@ pub struct ProjectJson {
     pub(crate) sysroot_src: Option<AbsPathBuf>,
     project_root: AbsPathBuf,
     crates: Vec<Crate>,
+    runnables: Vec<Runnable>,
 }
 
 /// A crate points to the root module of a crate and lists the dependencies of the crate. This is
@@ -40,6 +41,36 @@ pub struct Crate {
     pub(crate) exclude: Vec<AbsPathBuf>,
     pub(crate) is_proc_macro: bool,
     pub(crate) repository: Option<String>,
+    pub(crate) build: Option<Build>,
+}
+
+/// How the build system refers to a crate, used to fill in [`Runnable`] templates.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Build {
+    /// The build system's label of the crate, like `//foo:bar`.
+    pub label: String,
+    /// The build file defining the crate, like a `BUCK` or `BUILD` file.
+    pub build_file: AbsPathBuf,
+    pub target_kind: TargetKind,
+}
+
+/// A command template for checking, running or testing a crate of the project.
+///
+/// `{label}` in the arguments is replaced by the [`Build::label`] of the crate,
+/// and `{test_id}` by the path of the test to run.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Runnable {
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: AbsPathBuf,
+    pub kind: RunnableKind,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RunnableKind {
+    Check,
+    Run,
+    TestOne,
 }
 
 impl ProjectJson {
@@ -51,6 +82,7 @@ impl ProjectJson {
     /// * `data` - The parsed contents of `rust-project.json`, or project json that's passed via
     ///            configuration.
     pub fn new(base: &AbsPath, data: ProjectJsonData) -> ProjectJson {
+        let cfg_groups = data.cfg_groups;
         ProjectJson {
             sysroot_src: data.sysroot_src.map(|it| base.join(it)),
             project_root: base.to_path_buf(),
@@ -76,6 +108,28 @@ impl ProjectJson {
                         None => (vec![root_module.parent().unwrap().to_path_buf()], Vec::new()),
                     };
 
+                    let mut cfg = crate_data.cfg;
+                    for group in &crate_data.cfg_groups {
+                        match cfg_groups.get(group) {
+                            Some(flags) => cfg.extend(flags.iter().cloned()),
+                            None => tracing::error!("unknown cfg group `{}`", group),
+                        }
+                    }
+
+                    let mut env = crate_data.env;
+                    let build = crate_data.build.map(|build| {
+                        // Build systems put the outputs of build scripts into `OUT_DIR`.
+                        if let Some(out_dir) = build.out_dir {
+                            env.entry("OUT_DIR".to_string())
+                                .or_insert_with(|| base.join(out_dir).display().to_string());
+                        }
+                        Build {
+                            label: build.label,
+                            build_file: base.join(build.build_file),
+                            target_kind: build.target_kind.into(),
+                        }
+                    });
+
                     Crate {
                         display_name: crate_data
                             .display_name
@@ -90,9 +144,9 @@ impl ProjectJson {
                                 Dependency::new(dep_data.name, CrateId(dep_data.krate as u32))
                             })
                             .collect::<Vec<_>>(),
-                        cfg: crate_data.cfg,
+                        cfg,
                         target: crate_data.target,
-                        env: crate_data.env,
+                        env,
                         proc_macro_dylib_path: crate_data
                             .proc_macro_dylib_path
                             .map(|it| base.join(it)),
@@ -101,9 +155,20 @@ impl ProjectJson {
                         exclude,
                         is_proc_macro: crate_data.is_proc_macro,
                         repository: crate_data.repository,
+                        build,
                     }
                 })
                 .collect::<Vec<_>>(),
+            runnables: data
+                .runnables
+                .into_iter()
+                .map(|runnable| Runnable {
+                    program: runnable.program,
+                    args: runnable.args,
+                    cwd: base.join(runnable.cwd),
+                    kind: runnable.kind.into(),
+                })
+                .collect(),
         }
     }
     /// Returns the number of crates in the project.
@@ -114,6 +179,21 @@ impl ProjectJson {
     pub fn crates(&self) -> impl Iterator<Item = (CrateId, &Crate)> + '_ {
         self.crates.iter().enumerate().map(|(idx, krate)| (CrateId(idx as u32), krate))
     }
+    /// Returns whether `path` lies in the source directories of one of the project's crates.
+    pub fn includes_file(&self, path: &AbsPath) -> bool {
+        self.crates.iter().any(|krate| {
+            krate.include.iter().any(|dir| path.starts_with(dir))
+                && !krate.exclude.iter().any(|dir| path.starts_with(dir))
+        })
+    }
+    /// Returns the build information of the crate with the given root module.
+    pub fn build_for_root(&self, root_module: &AbsPath) -> Option<&Build> {
+        self.crates.iter().find(|krate| krate.root_module == *root_module)?.build.as_ref()
+    }
+    /// Returns the command templates of the project.
+    pub fn runnables(&self) -> &[Runnable] {
+        &self.runnables
+    }
     /// Returns the path to the project's root folder.
     pub fn path(&self) -> &AbsPath {
         &self.project_root
@@ -124,6 +204,10 @@ impl ProjectJson {
 pub struct ProjectJsonData {
     sysroot_src: Option<PathBuf>,
     crates: Vec<CrateData>,
+    #[serde(default)]
+    cfg_groups: FxHashMap<String, Vec<CfgFlag>>,
+    #[serde(default)]
+    runnables: Vec<RunnableData>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -146,6 +230,10 @@ struct CrateData {
     is_proc_macro: bool,
     #[serde(default)]
     repository: Option<String>,
+    #[serde(default)]
+    cfg_groups: Vec<String>,
+    #[serde(default)]
+    build: Option<BuildData>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -169,6 +257,60 @@ impl From<EditionData> for Edition {
     }
 }
 
+#[derive(Deserialize, Debug, Clone)]
+struct BuildData {
+    label: String,
+    build_file: PathBuf,
+    target_kind: TargetKindData,
+    /// Directory with the outputs of the crate's build script, exposed as `OUT_DIR`.
+    #[serde(default)]
+    out_dir: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+enum TargetKindData {
+    Bin,
+    Lib,
+    Test,
+}
+
+impl From<TargetKindData> for TargetKind {
+    fn from(data: TargetKindData) -> Self {
+        match data {
+            TargetKindData::Bin => TargetKind::Bin,
+            TargetKindData::Lib => TargetKind::Lib,
+            TargetKindData::Test => TargetKind::Test,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct RunnableData {
+    program: String,
+    args: Vec<String>,
+    cwd: PathBuf,
+    kind: RunnableKindData,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+enum RunnableKindData {
+    Check,
+    Run,
+    TestOne,
+}
+
+impl From<RunnableKindData> for RunnableKind {
+    fn from(data: RunnableKindData) -> Self {
+        match data {
+            RunnableKindData::Check => RunnableKind::Check,
+            RunnableKindData::Run => RunnableKind::Run,
+            RunnableKindData::TestOne => RunnableKind::TestOne,
+        }
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 struct DepData {
     /// Identifies a crate by position in the crates array.
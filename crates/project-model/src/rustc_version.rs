@@ -0,0 +1,36 @@
+//! Runs `rustc --version` to get the toolchain version `cfg(version(..))` is checked against.
+
+use std::process::Command;
+
+use anyhow::{format_err, Result};
+use cfg::CfgVersion;
+
+use crate::{utf8_stdout, ManifestPath};
+
+pub(crate) fn get(cargo_toml: Option<&ManifestPath>) -> Option<CfgVersion> {
+    let _p = profile::span("rustc_version::get");
+    match get_rustc_version(cargo_toml) {
+        Ok(it) => Some(it),
+        Err(e) => {
+            tracing::error!("failed to get rustc version: {e:?}");
+            None
+        }
+    }
+}
+
+fn get_rustc_version(cargo_toml: Option<&ManifestPath>) -> Result<CfgVersion> {
+    let mut cmd = Command::new(toolchain::rustc());
+    if let Some(cargo_toml) = cargo_toml {
+        cmd.current_dir(cargo_toml.parent());
+    }
+    cmd.arg("--version");
+    let output = utf8_stdout(cmd)?;
+    // eg. `rustc 1.66.0-nightly (8b705839c 2022-09-26)`. Pre-release toolchains are treated like
+    // the release they precede, which is what rustc itself does.
+    output
+        .split_whitespace()
+        .nth(1)
+        .and_then(|version| version.split('-').next())
+        .and_then(CfgVersion::parse)
+        .ok_or_else(|| format_err!("unexpected `rustc --version` output: {}", output))
+}
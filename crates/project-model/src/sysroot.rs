@@ -4,7 +4,7 @@
 //! but we can't process `.rlib` and need source code instead. The source code
 //! is typically installed with `rustup component add rust-src` command.
 
-use std::{env, fs, iter, ops, path::PathBuf, process::Command};
+use std::{env, fmt, fs, iter, ops, path::PathBuf, process::Command};
 
 use anyhow::{format_err, Result};
 use la_arena::{Arena, Idx};
@@ -20,6 +20,35 @@ pub struct Sysroot {
 
 pub(crate) type SysrootCrate = Idx<SysrootCrateData>;
 
+/// The sysroot was found, but it doesn't contain the standard library sources, usually because
+/// the `rust-src` component isn't installed.
+#[derive(Debug)]
+pub struct MissingSysrootSource {
+    pub sysroot: AbsPathBuf,
+    /// The rustup toolchain the sysroot belongs to, if known.
+    pub toolchain: Option<String>,
+}
+
+impl fmt::Display for MissingSysrootSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "\
+can't load standard library from sysroot
+{}
+(discovered via `rustc --print sysroot`)
+try installing the Rust source the same way you installed rustc",
+            self.sysroot.display(),
+        )?;
+        if let Some(toolchain) = &self.toolchain {
+            write!(f, ", e.g. `rustup component add rust-src --toolchain {}`", toolchain)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MissingSysrootSource {}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct SysrootCrateData {
     pub name: String,
@@ -164,17 +193,24 @@ fn discover_sysroot_src_dir(
             get_rust_src(sysroot_path)
         })
         .ok_or_else(|| {
-            format_err!(
-                "\
-can't load standard library from sysroot
-{}
-(discovered via `rustc --print sysroot`)
-try installing the Rust source the same way you installed rustc",
-                sysroot_path.display(),
-            )
+            // The sysroot tells which toolchain rustup resolved, the files in the
+            // project may be overridden by `RUSTUP_TOOLCHAIN` or `rustup override`.
+            let toolchain = rustup_toolchain_name(sysroot_path)
+                .or_else(|| toolchain::toolchain_override(current_dir.as_ref()));
+            MissingSysrootSource { sysroot: sysroot_path.clone(), toolchain }.into()
         })
 }
 
+/// rustup installs toolchains to `~/.rustup/toolchains/<name>`, which is also
+/// their sysroot.
+fn rustup_toolchain_name(sysroot_path: &AbsPath) -> Option<String> {
+    let parent = sysroot_path.parent()?;
+    if parent.file_name()? != "toolchains" {
+        return None;
+    }
+    Some(sysroot_path.file_name()?.to_str()?.to_string())
+}
+
 fn get_rustc_src(sysroot_path: &AbsPath) -> Option<ManifestPath> {
     let rustc_src = sysroot_path.join("lib/rustlib/rustc-src/rust/compiler/rustc/Cargo.toml");
     let rustc_src = ManifestPath::try_from(rustc_src).ok()?;
@@ -0,0 +1,39 @@
+//! Runs `rustc --print target-spec-json` to get the target's data layout.
+
+use std::process::Command;
+
+use anyhow::{format_err, Result};
+
+use crate::{utf8_stdout, ManifestPath};
+
+pub(crate) fn get(cargo_toml: Option<&ManifestPath>, target: Option<&str>) -> Option<String> {
+    let _p = profile::span("target_data_layout::get");
+    match get_target_data_layout(cargo_toml, target) {
+        Ok(it) => Some(it),
+        Err(e) => {
+            tracing::error!("failed to get target data layout: {e:?}");
+            None
+        }
+    }
+}
+
+fn get_target_data_layout(
+    cargo_toml: Option<&ManifestPath>,
+    target: Option<&str>,
+) -> Result<String> {
+    let mut cmd = Command::new(toolchain::rustc());
+    if let Some(cargo_toml) = cargo_toml {
+        cmd.current_dir(cargo_toml.parent());
+    }
+    cmd.args(&["-Z", "unstable-options", "--print", "target-spec-json"])
+        .env("RUSTC_BOOTSTRAP", "1");
+    if let Some(target) = target {
+        cmd.args(&["--target", target]);
+    }
+    let output = utf8_stdout(cmd)?;
+    let spec: serde_json::Value = serde_json::from_str(&output)?;
+    spec.get("data-layout")
+        .and_then(|it| it.as_str())
+        .map(ToOwned::to_owned)
+        .ok_or_else(|| format_err!("target spec has no `data-layout` entry"))
+}
@@ -4,14 +4,16 @@ use std::{
 };
 
 use base_db::{CrateGraph, FileId};
-use cfg::{CfgAtom, CfgDiff};
+use cfg::{CfgAtom, CfgDiff, CfgExpr};
 use expect_test::{expect, Expect};
 use paths::{AbsPath, AbsPathBuf};
+use rustc_hash::FxHashMap;
 use serde::de::DeserializeOwned;
 
 use crate::{
-    CargoWorkspace, CfgOverrides, ProjectJson, ProjectJsonData, ProjectWorkspace, Sysroot,
-    WorkspaceBuildScripts,
+    build_scripts::BuildScriptOutput, cargo_workspace::DepKind, cfg_flag::CfgFlag, CargoWorkspace,
+    CfgOverrides, CrateTargetOverride, ProjectJson, ProjectJsonData, ProjectWorkspace,
+    RunnableKind, Sysroot, TargetKind, WorkspaceBuildScripts,
 };
 
 fn load_cargo(file: &str) -> CrateGraph {
@@ -28,6 +30,10 @@ fn load_cargo_with_overrides(file: &str, cfg_overrides: CfgOverrides) -> CrateGr
         rustc: None,
         rustc_cfg: Vec::new(),
         cfg_overrides,
+        target_layout: None,
+        crate_targets: FxHashMap::default(),
+        extra_targets: Vec::new(),
+        rustc_version: None,
     };
     to_crate_graph(project_workspace)
 }
@@ -36,7 +42,13 @@ fn load_rust_project(file: &str) -> CrateGraph {
     let data = get_test_json_file(file);
     let project = rooted_project_json(data);
     let sysroot = Some(get_fake_sysroot());
-    let project_workspace = ProjectWorkspace::Json { project, sysroot, rustc_cfg: Vec::new() };
+    let project_workspace = ProjectWorkspace::Json {
+        project,
+        sysroot,
+        rustc_cfg: Vec::new(),
+        target_layout: None,
+        rustc_version: None,
+    };
     to_crate_graph(project_workspace)
 }
 
@@ -170,6 +182,7 @@ fn cargo_hello_world_project_model_with_wildcard_overrides() {
                                     "libc",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                         ],
                         proc_macro: [],
@@ -177,6 +190,8 @@ fn cargo_hello_world_project_model_with_wildcard_overrides() {
                             repo: None,
                         },
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         2,
@@ -234,6 +249,7 @@ fn cargo_hello_world_project_model_with_wildcard_overrides() {
                                     "hello_world",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -243,6 +259,7 @@ fn cargo_hello_world_project_model_with_wildcard_overrides() {
                                     "libc",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                         ],
                         proc_macro: [],
@@ -250,6 +267,8 @@ fn cargo_hello_world_project_model_with_wildcard_overrides() {
                             repo: None,
                         },
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         4,
@@ -315,6 +334,8 @@ fn cargo_hello_world_project_model_with_wildcard_overrides() {
                             ),
                         },
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         1,
@@ -372,6 +393,7 @@ fn cargo_hello_world_project_model_with_wildcard_overrides() {
                                     "hello_world",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -381,6 +403,7 @@ fn cargo_hello_world_project_model_with_wildcard_overrides() {
                                     "libc",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                         ],
                         proc_macro: [],
@@ -388,6 +411,8 @@ fn cargo_hello_world_project_model_with_wildcard_overrides() {
                             repo: None,
                         },
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         3,
@@ -445,6 +470,7 @@ fn cargo_hello_world_project_model_with_wildcard_overrides() {
                                     "hello_world",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -454,6 +480,7 @@ fn cargo_hello_world_project_model_with_wildcard_overrides() {
                                     "libc",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                         ],
                         proc_macro: [],
@@ -461,6 +488,8 @@ fn cargo_hello_world_project_model_with_wildcard_overrides() {
                             repo: None,
                         },
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                 },
             }"#]],
@@ -542,6 +571,7 @@ fn cargo_hello_world_project_model_with_selective_overrides() {
                                     "libc",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                         ],
                         proc_macro: [],
@@ -549,6 +579,8 @@ fn cargo_hello_world_project_model_with_selective_overrides() {
                             repo: None,
                         },
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         2,
@@ -608,6 +640,7 @@ fn cargo_hello_world_project_model_with_selective_overrides() {
                                     "hello_world",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -617,6 +650,7 @@ fn cargo_hello_world_project_model_with_selective_overrides() {
                                     "libc",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                         ],
                         proc_macro: [],
@@ -624,6 +658,8 @@ fn cargo_hello_world_project_model_with_selective_overrides() {
                             repo: None,
                         },
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         4,
@@ -689,6 +725,8 @@ fn cargo_hello_world_project_model_with_selective_overrides() {
                             ),
                         },
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         1,
@@ -748,6 +786,7 @@ fn cargo_hello_world_project_model_with_selective_overrides() {
                                     "hello_world",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -757,6 +796,7 @@ fn cargo_hello_world_project_model_with_selective_overrides() {
                                     "libc",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                         ],
                         proc_macro: [],
@@ -764,6 +804,8 @@ fn cargo_hello_world_project_model_with_selective_overrides() {
                             repo: None,
                         },
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         3,
@@ -823,6 +865,7 @@ fn cargo_hello_world_project_model_with_selective_overrides() {
                                     "hello_world",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -832,6 +875,7 @@ fn cargo_hello_world_project_model_with_selective_overrides() {
                                     "libc",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                         ],
                         proc_macro: [],
@@ -839,6 +883,8 @@ fn cargo_hello_world_project_model_with_selective_overrides() {
                             repo: None,
                         },
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                 },
             }"#]],
@@ -911,6 +957,7 @@ fn cargo_hello_world_project_model() {
                                     "libc",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                         ],
                         proc_macro: [],
@@ -918,6 +965,8 @@ fn cargo_hello_world_project_model() {
                             repo: None,
                         },
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         2,
@@ -977,6 +1026,7 @@ fn cargo_hello_world_project_model() {
                                     "hello_world",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -986,6 +1036,7 @@ fn cargo_hello_world_project_model() {
                                     "libc",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                         ],
                         proc_macro: [],
@@ -993,6 +1044,8 @@ fn cargo_hello_world_project_model() {
                             repo: None,
                         },
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         4,
@@ -1060,6 +1113,8 @@ fn cargo_hello_world_project_model() {
                             ),
                         },
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         1,
@@ -1119,6 +1174,7 @@ fn cargo_hello_world_project_model() {
                                     "hello_world",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -1128,6 +1184,7 @@ fn cargo_hello_world_project_model() {
                                     "libc",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                         ],
                         proc_macro: [],
@@ -1135,6 +1192,8 @@ fn cargo_hello_world_project_model() {
                             repo: None,
                         },
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         3,
@@ -1194,6 +1253,7 @@ fn cargo_hello_world_project_model() {
                                     "hello_world",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -1203,6 +1263,7 @@ fn cargo_hello_world_project_model() {
                                     "libc",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                         ],
                         proc_macro: [],
@@ -1210,6 +1271,8 @@ fn cargo_hello_world_project_model() {
                             repo: None,
                         },
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                 },
             }"#]],
@@ -1258,6 +1321,7 @@ fn rust_project_hello_world_project_model() {
                                     "core",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                         ],
                         proc_macro: [],
@@ -1265,6 +1329,8 @@ fn rust_project_hello_world_project_model() {
                             Alloc,
                         ),
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         10,
@@ -1297,6 +1363,8 @@ fn rust_project_hello_world_project_model() {
                             Other,
                         ),
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         7,
@@ -1329,6 +1397,8 @@ fn rust_project_hello_world_project_model() {
                             Other,
                         ),
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         4,
@@ -1364,6 +1434,7 @@ fn rust_project_hello_world_project_model() {
                                     "std",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                         ],
                         proc_macro: [],
@@ -1371,6 +1442,8 @@ fn rust_project_hello_world_project_model() {
                             Other,
                         ),
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         1,
@@ -1403,6 +1476,8 @@ fn rust_project_hello_world_project_model() {
                             Core,
                         ),
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         11,
@@ -1438,6 +1513,7 @@ fn rust_project_hello_world_project_model() {
                                     "core",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -1447,6 +1523,7 @@ fn rust_project_hello_world_project_model() {
                                     "alloc",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -1456,6 +1533,7 @@ fn rust_project_hello_world_project_model() {
                                     "std",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -1465,6 +1543,7 @@ fn rust_project_hello_world_project_model() {
                                     "test",
                                 ),
                                 prelude: false,
+                                dev: false,
                             },
                         ],
                         proc_macro: [],
@@ -1472,6 +1551,8 @@ fn rust_project_hello_world_project_model() {
                             repo: None,
                         },
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         8,
@@ -1504,6 +1585,8 @@ fn rust_project_hello_world_project_model() {
                             Other,
                         ),
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         5,
@@ -1536,6 +1619,8 @@ fn rust_project_hello_world_project_model() {
                             Other,
                         ),
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         2,
@@ -1568,6 +1653,8 @@ fn rust_project_hello_world_project_model() {
                             Other,
                         ),
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         9,
@@ -1600,6 +1687,8 @@ fn rust_project_hello_world_project_model() {
                             Test,
                         ),
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         6,
@@ -1635,6 +1724,7 @@ fn rust_project_hello_world_project_model() {
                                     "alloc",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -1644,6 +1734,7 @@ fn rust_project_hello_world_project_model() {
                                     "core",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -1653,6 +1744,7 @@ fn rust_project_hello_world_project_model() {
                                     "panic_abort",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -1662,6 +1754,7 @@ fn rust_project_hello_world_project_model() {
                                     "panic_unwind",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -1671,6 +1764,7 @@ fn rust_project_hello_world_project_model() {
                                     "profiler_builtins",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -1680,6 +1774,7 @@ fn rust_project_hello_world_project_model() {
                                     "std_detect",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -1689,6 +1784,7 @@ fn rust_project_hello_world_project_model() {
                                     "term",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -1698,6 +1794,7 @@ fn rust_project_hello_world_project_model() {
                                     "test",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                             Dependency {
                                 crate_id: CrateId(
@@ -1707,6 +1804,7 @@ fn rust_project_hello_world_project_model() {
                                     "unwind",
                                 ),
                                 prelude: true,
+                                dev: false,
                             },
                         ],
                         proc_macro: [],
@@ -1714,6 +1812,8 @@ fn rust_project_hello_world_project_model() {
                             Std,
                         ),
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                     CrateId(
                         3,
@@ -1746,6 +1846,8 @@ fn rust_project_hello_world_project_model() {
                             Other,
                         ),
                         is_proc_macro: false,
+                        target_layout: None,
+                        target_triple: None,
                     },
                 },
             }"#]],
@@ -1763,3 +1865,230 @@ fn rust_project_is_proc_macro_has_proc_macro_dep() {
     // on the proc_macro sysroot crate.
     crate_data.dependencies.iter().find(|&dep| dep.name.deref() == "proc_macro").unwrap();
 }
+
+#[test]
+fn rust_project_cfg_groups_and_build_info() {
+    let project = rooted_project_json(get_test_json_file("build-info-project.json"));
+    let root_module = project.crates().next().unwrap().1.root_module.clone();
+    let build = project.build_for_root(&root_module).unwrap();
+    assert_eq!(build.label, "//build_info:build_info");
+    assert_eq!(build.target_kind, TargetKind::Lib);
+    let runnable = &project.runnables()[0];
+    assert_eq!(runnable.kind, RunnableKind::TestOne);
+    assert_eq!(runnable.args, ["test", "{label}", "--", "{test_id}"]);
+
+    let crate_graph = to_crate_graph(ProjectWorkspace::Json {
+        project,
+        sysroot: None,
+        rustc_cfg: Vec::new(),
+        target_layout: None,
+        rustc_version: None,
+    });
+    let crate_id = crate_graph.iter().max().unwrap();
+    let crate_data = &crate_graph[crate_id];
+    for flag in ["unix", "test", "debug_assertions"] {
+        let cfg = CfgExpr::Atom(CfgAtom::Flag(flag.into()));
+        assert_eq!(crate_data.cfg_options.check(&cfg), Some(true), "{}", flag);
+    }
+    assert!(crate_data.env.get("OUT_DIR").unwrap().ends_with("out"));
+}
+
+#[test]
+fn cargo_hello_world_project_model_with_crate_target() {
+    let meta = get_test_json_file("hello-world-metadata.json");
+    let mut crate_targets = FxHashMap::default();
+    crate_targets.insert(
+        "hello-world".to_owned(),
+        CrateTargetOverride {
+            rustc_cfg: vec![CfgFlag::Atom("firmware".into())],
+            target_layout: Some("e-m:e-p:32:32".into()),
+        },
+    );
+    let project_workspace = ProjectWorkspace::Cargo {
+        cargo: CargoWorkspace::new(meta),
+        build_scripts: WorkspaceBuildScripts::default(),
+        sysroot: None,
+        rustc: None,
+        rustc_cfg: vec![CfgFlag::Atom("host".into())],
+        cfg_overrides: CfgOverrides::default(),
+        target_layout: Some("e-m:e-p:64:64".into()),
+        crate_targets,
+        extra_targets: Vec::new(),
+        rustc_version: None,
+    };
+    let crate_graph = to_crate_graph(project_workspace);
+
+    let firmware = CfgExpr::Atom(CfgAtom::Flag("firmware".into()));
+    let host = CfgExpr::Atom(CfgAtom::Flag("host".into()));
+    for krate in crate_graph.iter() {
+        let data = &crate_graph[krate];
+        let is_hello_world = data.version.as_deref() == Some("0.1.0");
+        assert_eq!(data.cfg_options.check(&firmware), Some(is_hello_world));
+        assert_eq!(data.cfg_options.check(&host), Some(!is_hello_world));
+        let expected_layout = if is_hello_world { "e-m:e-p:32:32" } else { "e-m:e-p:64:64" };
+        assert_eq!(data.target_layout.as_deref(), Some(expected_layout));
+    }
+}
+
+#[test]
+fn cargo_hello_world_project_model_with_extra_target() {
+    let meta = get_test_json_file("hello-world-metadata.json");
+    let wasm = CrateTargetOverride {
+        rustc_cfg: vec![CfgFlag::KeyValue { key: "target_arch".into(), value: "wasm32".into() }],
+        target_layout: Some("e-m:e-p:32:32".into()),
+    };
+    let project_workspace = ProjectWorkspace::Cargo {
+        cargo: CargoWorkspace::new(meta),
+        build_scripts: WorkspaceBuildScripts::default(),
+        sysroot: None,
+        rustc: None,
+        rustc_cfg: vec![CfgFlag::KeyValue { key: "target_arch".into(), value: "x86_64".into() }],
+        cfg_overrides: CfgOverrides::default(),
+        target_layout: Some("e-m:e-p:64:64".into()),
+        crate_targets: FxHashMap::default(),
+        extra_targets: vec![("wasm32-unknown-unknown".into(), wasm)],
+        rustc_version: None,
+    };
+    let crate_graph = to_crate_graph(project_workspace);
+
+    let is_wasm =
+        CfgExpr::Atom(CfgAtom::KeyValue { key: "target_arch".into(), value: "wasm32".into() });
+    let mut n_wasm_crates = 0;
+    let mut n_member_crates = 0;
+    for krate in crate_graph.iter() {
+        let data = &crate_graph[krate];
+        let wasm = data.target_triple.as_deref() == Some("wasm32-unknown-unknown");
+        let is_hello_world = data.version.as_deref() == Some("0.1.0");
+        n_wasm_crates += wasm as usize;
+        n_member_crates += (is_hello_world && !wasm) as usize;
+        // Only the workspace members are copied.
+        assert!(is_hello_world || !wasm);
+        assert_eq!(data.cfg_options.check(&is_wasm), Some(wasm));
+        let expected_layout = if wasm { "e-m:e-p:32:32" } else { "e-m:e-p:64:64" };
+        assert_eq!(data.target_layout.as_deref(), Some(expected_layout));
+        for dep in &data.dependencies {
+            // The copies depend on each other and on the dependencies of the main target.
+            let dep = &crate_graph[dep.crate_id];
+            let dep_is_hello_world = dep.version.as_deref() == Some("0.1.0");
+            if dep_is_hello_world {
+                assert_eq!(dep.target_triple, data.target_triple);
+            } else {
+                assert_eq!(dep.target_triple, None);
+            }
+        }
+    }
+    assert_ne!(n_wasm_crates, 0);
+    assert_eq!(n_wasm_crates, n_member_crates);
+}
+
+#[test]
+fn cargo_hello_world_project_model_with_extra_target_shares_pinned_packages() {
+    let meta = get_test_json_file("hello-world-metadata.json");
+    let mut crate_targets = FxHashMap::default();
+    crate_targets.insert(
+        "hello-world".to_owned(),
+        CrateTargetOverride {
+            rustc_cfg: vec![CfgFlag::Atom("firmware".into())],
+            target_layout: None,
+        },
+    );
+    let wasm = CrateTargetOverride {
+        rustc_cfg: vec![CfgFlag::KeyValue { key: "target_arch".into(), value: "wasm32".into() }],
+        target_layout: None,
+    };
+    let project_workspace = ProjectWorkspace::Cargo {
+        cargo: CargoWorkspace::new(meta),
+        build_scripts: WorkspaceBuildScripts::default(),
+        sysroot: None,
+        rustc: None,
+        rustc_cfg: Vec::new(),
+        cfg_overrides: CfgOverrides::default(),
+        target_layout: None,
+        crate_targets,
+        extra_targets: vec![("wasm32-unknown-unknown".into(), wasm)],
+        rustc_version: None,
+    };
+    let crate_graph = to_crate_graph(project_workspace);
+
+    // The only member has a target of its own, so there is nothing to copy.
+    assert!(crate_graph.iter().all(|krate| crate_graph[krate].target_triple.is_none()));
+}
+
+#[test]
+fn cargo_artifact_dependencies() {
+    let cargo = CargoWorkspace::new(get_test_json_file("artifact-deps-metadata.json"));
+    let package = |name: &str| cargo.packages().find(|&pkg| cargo[pkg].name == name).unwrap();
+    let (app, tool, gen) = (package("app"), package("tool"), package("gen"));
+
+    // Binaries aren't libraries to depend on.
+    assert!(cargo[app].dependencies.is_empty());
+    let artifact_deps = cargo[app]
+        .artifact_dependencies
+        .iter()
+        .map(|dep| (dep.pkg, dep.name.as_str(), dep.kind.clone(), dep.bins.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        artifact_deps,
+        [
+            (gen, "gen", DepKind::Build, vec!["gen".to_owned()]),
+            (tool, "tool", DepKind::Normal, Vec::new()),
+        ]
+    );
+
+    let executable = |name: &str| {
+        let mut path = format!("$ROOT$target/debug/{}", name);
+        replace_root(&mut path, true);
+        (name.to_owned(), AbsPathBuf::assert(PathBuf::from(path)))
+    };
+    let mut build_scripts = WorkspaceBuildScripts::default();
+    for (pkg, bins) in [(tool, ["tool", "tool-helper"]), (gen, ["gen", "other"])] {
+        let bin_executables = bins.into_iter().map(executable).collect();
+        build_scripts
+            .outputs
+            .insert(pkg, BuildScriptOutput { bin_executables, ..Default::default() });
+    }
+    let project_workspace = ProjectWorkspace::Cargo {
+        cargo,
+        build_scripts,
+        sysroot: None,
+        rustc: None,
+        rustc_cfg: Vec::new(),
+        cfg_overrides: CfgOverrides::default(),
+        target_layout: None,
+        crate_targets: FxHashMap::default(),
+        extra_targets: Vec::new(),
+        rustc_version: None,
+    };
+    let mut files = Vec::new();
+    let crate_graph = project_workspace.to_crate_graph(&mut |_, _| Vec::new(), &mut |path| {
+        files.push(path.as_ref().to_path_buf());
+        Some(FileId(files.len() as u32))
+    });
+    let env_of = |root: &str| {
+        let krate = crate_graph
+            .iter()
+            .find(|&krate| files[crate_graph[krate].root_file_id.0 as usize - 1].ends_with(root))
+            .unwrap();
+        let mut env = crate_graph[krate]
+            .env
+            .iter()
+            .filter(|(key, _)| key.starts_with("CARGO_BIN_FILE_"))
+            .map(|(key, value)| {
+                format!("{}={}", key, Path::new(value).file_name().unwrap().to_str().unwrap())
+            })
+            .collect::<Vec<_>>();
+        env.sort();
+        env
+    };
+
+    // Build dependencies are only visible to the build script.
+    assert_eq!(
+        env_of("app/src/lib.rs"),
+        [
+            "CARGO_BIN_FILE_TOOL=tool",
+            "CARGO_BIN_FILE_TOOL_tool-helper=tool-helper",
+            "CARGO_BIN_FILE_TOOL_tool=tool",
+        ]
+    );
+    assert_eq!(env_of("app/build.rs"), ["CARGO_BIN_FILE_GEN=gen", "CARGO_BIN_FILE_GEN_gen=gen"]);
+}
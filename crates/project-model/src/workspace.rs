@@ -2,26 +2,26 @@
 //! metadata` or `rust-project.json`) into representation stored in the salsa
 //! database -- `CrateGraph`.
 
-use std::{collections::VecDeque, fmt, fs, process::Command};
+use std::{collections::VecDeque, fmt, fs, iter, process::Command, sync::Arc};
 
 use anyhow::{format_err, Context, Result};
 use base_db::{
     CrateDisplayName, CrateGraph, CrateId, CrateName, CrateOrigin, Dependency, Edition, Env,
     FileId, LangCrateOrigin, ProcMacro,
 };
-use cfg::{CfgDiff, CfgOptions};
+use cfg::{CfgDiff, CfgOptions, CfgVersion};
 use paths::{AbsPath, AbsPathBuf};
 use rustc_hash::{FxHashMap, FxHashSet};
 use stdx::always;
 
 use crate::{
     build_scripts::BuildScriptOutput,
-    cargo_workspace::{DepKind, PackageData, RustcSource},
+    cargo_workspace::{DepKind, Package, PackageData, RustcSource},
     cfg_flag::CfgFlag,
-    rustc_cfg,
+    rustc_cfg, rustc_version,
     sysroot::SysrootCrate,
-    utf8_stdout, CargoConfig, CargoWorkspace, ManifestPath, ProjectJson, ProjectManifest, Sysroot,
-    TargetKind, WorkspaceBuildScripts,
+    target_data_layout, utf8_stdout, CargoConfig, CargoWorkspace, ManifestPath, ProjectJson,
+    ProjectManifest, Sysroot, TargetKind, WorkspaceBuildScripts,
 };
 
 /// A set of cfg-overrides per crate.
@@ -51,6 +51,16 @@ impl CfgOverrides {
     }
 }
 
+/// Target information for a crate that is analyzed for a different target than the rest of its
+/// workspace, resolved from a [`CrateTarget`](crate::CrateTarget).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CrateTargetOverride {
+    /// Cfg flags of the crate's target, followed by the extra flags requested for it.
+    pub rustc_cfg: Vec<CfgFlag>,
+    /// The data layout of the crate's target.
+    pub target_layout: Option<String>,
+}
+
 /// `PackageRoot` describes a package root folder.
 /// Which may be an external dependency, or a member of
 /// the current workspace.
@@ -77,9 +87,25 @@ pub enum ProjectWorkspace {
         /// different target.
         rustc_cfg: Vec<CfgFlag>,
         cfg_overrides: CfgOverrides,
+        /// The data layout of the current target, obtained from
+        /// `rustc --print target-spec-json`.
+        target_layout: Option<String>,
+        /// Target overrides for specific packages, keyed by package name.
+        crate_targets: FxHashMap<String, CrateTargetOverride>,
+        /// Further targets the whole workspace is analyzed for, each getting its own copy of
+        /// the member crates.
+        extra_targets: Vec<(String, CrateTargetOverride)>,
+        /// The toolchain version, obtained from `rustc --version`.
+        rustc_version: Option<CfgVersion>,
     },
     /// Project workspace was manually specified using a `rust-project.json` file.
-    Json { project: ProjectJson, sysroot: Option<Sysroot>, rustc_cfg: Vec<CfgFlag> },
+    Json {
+        project: ProjectJson,
+        sysroot: Option<Sysroot>,
+        rustc_cfg: Vec<CfgFlag>,
+        target_layout: Option<String>,
+        rustc_version: Option<CfgVersion>,
+    },
 
     // FIXME: The primary limitation of this approach is that the set of detached files needs to be fixed at the beginning.
     // That's not the end user experience we should strive for.
@@ -91,7 +117,13 @@ pub enum ProjectWorkspace {
     // //
     /// Project with a set of disjoint files, not belonging to any particular workspace.
     /// Backed by basic sysroot crates for basic completion and highlighting.
-    DetachedFiles { files: Vec<AbsPathBuf>, sysroot: Sysroot, rustc_cfg: Vec<CfgFlag> },
+    DetachedFiles {
+        files: Vec<AbsPathBuf>,
+        sysroot: Sysroot,
+        rustc_cfg: Vec<CfgFlag>,
+        target_layout: Option<String>,
+        rustc_version: Option<CfgVersion>,
+    },
 }
 
 impl fmt::Debug for ProjectWorkspace {
@@ -105,6 +137,10 @@ impl fmt::Debug for ProjectWorkspace {
                 rustc,
                 rustc_cfg,
                 cfg_overrides,
+                target_layout,
+                crate_targets,
+                extra_targets,
+                rustc_version,
             } => f
                 .debug_struct("Cargo")
                 .field("root", &cargo.workspace_root().file_name())
@@ -116,21 +152,41 @@ impl fmt::Debug for ProjectWorkspace {
                 )
                 .field("n_rustc_cfg", &rustc_cfg.len())
                 .field("n_cfg_overrides", &cfg_overrides.len())
+                .field("target_layout", &target_layout.is_some())
+                .field("n_crate_targets", &crate_targets.len())
+                .field("n_extra_targets", &extra_targets.len())
+                .field("rustc_version", rustc_version)
                 .finish(),
-            ProjectWorkspace::Json { project, sysroot, rustc_cfg } => {
+            ProjectWorkspace::Json {
+                project,
+                sysroot,
+                rustc_cfg,
+                target_layout,
+                rustc_version,
+            } => {
                 let mut debug_struct = f.debug_struct("Json");
                 debug_struct.field("n_crates", &project.n_crates());
                 if let Some(sysroot) = sysroot {
                     debug_struct.field("n_sysroot_crates", &sysroot.crates().len());
                 }
                 debug_struct.field("n_rustc_cfg", &rustc_cfg.len());
+                debug_struct.field("target_layout", &target_layout.is_some());
+                debug_struct.field("rustc_version", rustc_version);
                 debug_struct.finish()
             }
-            ProjectWorkspace::DetachedFiles { files, sysroot, rustc_cfg } => f
+            ProjectWorkspace::DetachedFiles {
+                files,
+                sysroot,
+                rustc_cfg,
+                target_layout,
+                rustc_version,
+            } => f
                 .debug_struct("DetachedFiles")
                 .field("n_files", &files.len())
                 .field("n_sysroot_crates", &sysroot.crates().len())
                 .field("n_rustc_cfg", &rustc_cfg.len())
+                .field("target_layout", &target_layout.is_some())
+                .field("rustc_version", rustc_version)
                 .finish(),
         }
     }
@@ -157,7 +213,7 @@ impl ProjectWorkspace {
             ProjectManifest::CargoToml(cargo_toml) => {
                 let cargo_version = utf8_stdout({
                     let mut cmd = Command::new(toolchain::cargo());
-                    cmd.arg("--version");
+                    cmd.current_dir(cargo_toml.parent()).arg("--version");
                     cmd
                 })?;
 
@@ -210,6 +266,39 @@ impl ProjectWorkspace {
                 };
 
                 let rustc_cfg = rustc_cfg::get(Some(&cargo_toml), config.target.as_deref());
+                let target_layout =
+                    target_data_layout::get(Some(&cargo_toml), config.target.as_deref());
+
+                let crate_targets = config
+                    .crate_targets
+                    .iter()
+                    .map(|(name, krate)| {
+                        let (mut rustc_cfg, target_layout) = match krate.target.as_deref() {
+                            Some(target) => (
+                                rustc_cfg::get(Some(&cargo_toml), Some(target)),
+                                target_data_layout::get(Some(&cargo_toml), Some(target)),
+                            ),
+                            None => (rustc_cfg.clone(), target_layout.clone()),
+                        };
+                        rustc_cfg.extend(krate.cfgs.iter().filter_map(|cfg| {
+                            cfg.parse::<CfgFlag>()
+                                .map_err(|e| tracing::error!("invalid cfg for `{}`: {}", name, e))
+                                .ok()
+                        }));
+                        (name.clone(), CrateTargetOverride { rustc_cfg, target_layout })
+                    })
+                    .collect();
+
+                let extra_targets = config
+                    .extra_targets
+                    .iter()
+                    .map(|target| {
+                        let rustc_cfg = rustc_cfg::get(Some(&cargo_toml), Some(target));
+                        let target_layout =
+                            target_data_layout::get(Some(&cargo_toml), Some(target));
+                        (target.clone(), CrateTargetOverride { rustc_cfg, target_layout })
+                    })
+                    .collect();
 
                 let cfg_overrides = config.cfg_overrides();
                 ProjectWorkspace::Cargo {
@@ -219,6 +308,10 @@ impl ProjectWorkspace {
                     rustc,
                     rustc_cfg,
                     cfg_overrides,
+                    target_layout,
+                    crate_targets,
+                    extra_targets,
+                    rustc_version: rustc_version::get(Some(&cargo_toml)),
                 }
             }
         };
@@ -235,7 +328,15 @@ impl ProjectWorkspace {
             None => None,
         };
         let rustc_cfg = rustc_cfg::get(None, target);
-        Ok(ProjectWorkspace::Json { project: project_json, sysroot, rustc_cfg })
+        let target_layout = target_data_layout::get(None, target);
+        let rustc_version = rustc_version::get(None);
+        Ok(ProjectWorkspace::Json {
+            project: project_json,
+            sysroot,
+            rustc_cfg,
+            target_layout,
+            rustc_version,
+        })
     }
 
     pub fn load_detached_files(detached_files: Vec<AbsPathBuf>) -> Result<ProjectWorkspace> {
@@ -246,7 +347,15 @@ impl ProjectWorkspace {
                 .ok_or_else(|| format_err!("No detached files to load"))?,
         )?;
         let rustc_cfg = rustc_cfg::get(None, None);
-        Ok(ProjectWorkspace::DetachedFiles { files: detached_files, sysroot, rustc_cfg })
+        let target_layout = target_data_layout::get(None, None);
+        let rustc_version = rustc_version::get(None);
+        Ok(ProjectWorkspace::DetachedFiles {
+            files: detached_files,
+            sysroot,
+            rustc_cfg,
+            target_layout,
+            rustc_version,
+        })
     }
 
     pub fn run_build_scripts(
@@ -266,6 +375,40 @@ impl ProjectWorkspace {
         }
     }
 
+    /// Returns the data layout of the target this workspace is compiled for, if known.
+    pub fn target_layout(&self) -> Option<&str> {
+        match self {
+            ProjectWorkspace::Cargo { target_layout, .. }
+            | ProjectWorkspace::Json { target_layout, .. }
+            | ProjectWorkspace::DetachedFiles { target_layout, .. } => target_layout.as_deref(),
+        }
+    }
+
+    /// Returns the version of the toolchain this workspace is compiled with, if known.
+    pub fn rustc_version(&self) -> Option<CfgVersion> {
+        match self {
+            ProjectWorkspace::Cargo { rustc_version, .. }
+            | ProjectWorkspace::Json { rustc_version, .. }
+            | ProjectWorkspace::DetachedFiles { rustc_version, .. } => *rustc_version,
+        }
+    }
+
+    /// Returns the directory of the workspace, `None` for detached files.
+    pub fn workspace_root(&self) -> Option<&AbsPath> {
+        match self {
+            ProjectWorkspace::Cargo { cargo, .. } => Some(cargo.workspace_root()),
+            ProjectWorkspace::Json { project, .. } => Some(project.path()),
+            ProjectWorkspace::DetachedFiles { .. } => None,
+        }
+    }
+
+    pub fn build_scripts(&self) -> Option<&WorkspaceBuildScripts> {
+        match self {
+            ProjectWorkspace::Cargo { build_scripts, .. } => Some(build_scripts),
+            _ => None,
+        }
+    }
+
     pub fn set_build_scripts(&mut self, bs: WorkspaceBuildScripts) {
         match self {
             ProjectWorkspace::Cargo { build_scripts, .. } => *build_scripts = bs,
@@ -280,7 +423,13 @@ impl ProjectWorkspace {
     /// the root is a member of the current workspace
     pub fn to_roots(&self) -> Vec<PackageRoot> {
         match self {
-            ProjectWorkspace::Json { project, sysroot, rustc_cfg: _ } => project
+            ProjectWorkspace::Json {
+                project,
+                sysroot,
+                rustc_cfg: _,
+                target_layout: _,
+                rustc_version: _,
+            } => project
                 .crates()
                 .map(|(_, krate)| PackageRoot {
                     is_local: krate.is_workspace_member,
@@ -303,6 +452,10 @@ impl ProjectWorkspace {
                 rustc,
                 rustc_cfg: _,
                 cfg_overrides: _,
+                target_layout: _,
+                crate_targets: _,
+                extra_targets: _,
+                rustc_version: _,
                 build_scripts,
             } => {
                 cargo
@@ -395,7 +548,13 @@ impl ProjectWorkspace {
         let _p = profile::span("ProjectWorkspace::to_crate_graph");
 
         let mut crate_graph = match self {
-            ProjectWorkspace::Json { project, sysroot, rustc_cfg } => project_json_to_crate_graph(
+            ProjectWorkspace::Json {
+                project,
+                sysroot,
+                rustc_cfg,
+                target_layout: _,
+                rustc_version: _,
+            } => project_json_to_crate_graph(
                 rustc_cfg.clone(),
                 load_proc_macro,
                 load,
@@ -409,20 +568,66 @@ impl ProjectWorkspace {
                 rustc_cfg,
                 cfg_overrides,
                 build_scripts,
-            } => cargo_to_crate_graph(
-                rustc_cfg.clone(),
-                cfg_overrides,
-                load_proc_macro,
-                load,
-                cargo,
-                build_scripts,
-                sysroot.as_ref(),
-                rustc,
-            ),
-            ProjectWorkspace::DetachedFiles { files, sysroot, rustc_cfg } => {
+                target_layout: _,
+                crate_targets,
+                extra_targets,
+                rustc_version: _,
+            } => {
+                let mut crate_graph = CrateGraph::default();
+                let shared = cargo_to_crate_graph(
+                    &mut crate_graph,
+                    None,
+                    rustc_cfg.clone(),
+                    cfg_overrides,
+                    crate_targets,
+                    load_proc_macro,
+                    load,
+                    cargo,
+                    build_scripts,
+                    sysroot.as_ref(),
+                    rustc,
+                );
+                // Every further target gets a copy of the workspace members with its own cfgs, so
+                // that code specific to it is analyzed instead of being inactive. The sysroot and
+                // the dependencies are shared with the crates of the main target.
+                for (target, target_override) in extra_targets {
+                    let existing: FxHashSet<CrateId> = crate_graph.iter().collect();
+                    cargo_to_crate_graph(
+                        &mut crate_graph,
+                        Some(&shared),
+                        target_override.rustc_cfg.clone(),
+                        cfg_overrides,
+                        crate_targets,
+                        load_proc_macro,
+                        load,
+                        cargo,
+                        build_scripts,
+                        sysroot.as_ref(),
+                        rustc,
+                    );
+                    let target = Arc::from(target.as_str());
+                    let target_layout = target_override.target_layout.as_deref().map(Arc::from);
+                    let copies: Vec<_> =
+                        crate_graph.iter().filter(|it| !existing.contains(it)).collect();
+                    for krate in copies {
+                        crate_graph.set_crate_target_triple(krate, target.clone());
+                        if let Some(target_layout) = &target_layout {
+                            crate_graph.set_crate_target_layout(krate, target_layout.clone());
+                        }
+                    }
+                }
+                crate_graph
+            }
+            ProjectWorkspace::DetachedFiles { files, sysroot, rustc_cfg, .. } => {
                 detached_files_to_crate_graph(rustc_cfg.clone(), load, files, sysroot)
             }
         };
+        if let Some(target_layout) = self.target_layout() {
+            crate_graph.set_target_layout(&Arc::from(target_layout));
+        }
+        if let Some(rustc_version) = self.rustc_version() {
+            crate_graph.set_rustc_version(rustc_version);
+        }
         if crate_graph.patch_cfg_if() {
             tracing::debug!("Patched std to depend on cfg-if")
         } else {
@@ -445,6 +650,7 @@ fn project_json_to_crate_graph(
         .map(|sysroot| sysroot_to_crate_graph(&mut crate_graph, sysroot, rustc_cfg.clone(), load));
 
     let mut cfg_cache: FxHashMap<&str, Vec<CfgFlag>> = FxHashMap::default();
+    let mut layout_cache: FxHashMap<&str, Option<Arc<str>>> = FxHashMap::default();
     let crates: FxHashMap<CrateId, CrateId> = project
         .crates()
         .filter_map(|(crate_id, krate)| {
@@ -470,25 +676,31 @@ fn project_json_to_crate_graph(
 
             let mut cfg_options = CfgOptions::default();
             cfg_options.extend(target_cfgs.iter().chain(krate.cfg.iter()).cloned());
-            (
-                crate_id,
-                crate_graph.add_crate_root(
-                    file_id,
-                    krate.edition,
-                    krate.display_name.clone(),
-                    krate.version.clone(),
-                    cfg_options.clone(),
-                    cfg_options,
-                    env,
-                    proc_macro.unwrap_or_default(),
-                    krate.is_proc_macro,
-                    if krate.display_name.is_some() {
-                        CrateOrigin::CratesIo { repo: krate.repository.clone() }
-                    } else {
-                        CrateOrigin::CratesIo { repo: None }
-                    },
-                ),
-            )
+            let root = crate_graph.add_crate_root(
+                file_id,
+                krate.edition,
+                krate.display_name.clone(),
+                krate.version.clone(),
+                cfg_options.clone(),
+                cfg_options,
+                env,
+                proc_macro.unwrap_or_default(),
+                krate.is_proc_macro,
+                if krate.display_name.is_some() {
+                    CrateOrigin::CratesIo { repo: krate.repository.clone() }
+                } else {
+                    CrateOrigin::CratesIo { repo: None }
+                },
+            );
+            if let Some(target) = krate.target.as_deref() {
+                let target_layout = layout_cache
+                    .entry(target)
+                    .or_insert_with(|| target_data_layout::get(None, Some(target)).map(Arc::from));
+                if let Some(target_layout) = target_layout {
+                    crate_graph.set_crate_target_layout(root, target_layout.clone());
+                }
+            }
+            (crate_id, root)
         })
         .collect();
 
@@ -518,21 +730,37 @@ fn project_json_to_crate_graph(
     crate_graph
 }
 
+/// The crates of the main target a copy of a workspace for a further target links against
+/// instead of getting its own: the sysroot and the lib crates of the dependencies and of the
+/// packages that have a target of their own.
+struct SharedCrates {
+    public_deps: SysrootPublicDeps,
+    libproc_macro: Option<CrateId>,
+    pkg_to_lib_crate: FxHashMap<Package, CrateId>,
+}
+
+/// Adds the crates of `cargo` to `crate_graph`, only the ones that aren't `shared` if given, and
+/// returns the ones a copy for a further target can share.
 fn cargo_to_crate_graph(
+    crate_graph: &mut CrateGraph,
+    shared: Option<&SharedCrates>,
     rustc_cfg: Vec<CfgFlag>,
     override_cfg: &CfgOverrides,
+    crate_targets: &FxHashMap<String, CrateTargetOverride>,
     load_proc_macro: &mut dyn FnMut(&str, &AbsPath) -> Vec<ProcMacro>,
     load: &mut dyn FnMut(&AbsPath) -> Option<FileId>,
     cargo: &CargoWorkspace,
     build_scripts: &WorkspaceBuildScripts,
     sysroot: Option<&Sysroot>,
     rustc: &Option<CargoWorkspace>,
-) -> CrateGraph {
+) -> SharedCrates {
     let _p = profile::span("cargo_to_crate_graph");
-    let mut crate_graph = CrateGraph::default();
-    let (public_deps, libproc_macro) = match sysroot {
-        Some(sysroot) => sysroot_to_crate_graph(&mut crate_graph, sysroot, rustc_cfg.clone(), load),
-        None => (SysrootPublicDeps::default(), None),
+    let (public_deps, libproc_macro) = match (shared, sysroot) {
+        (Some(shared), _) => (shared.public_deps.clone(), shared.libproc_macro),
+        (None, Some(sysroot)) => {
+            sysroot_to_crate_graph(crate_graph, sysroot, rustc_cfg.clone(), load)
+        }
+        (None, None) => (SysrootPublicDeps::default(), None),
     };
 
     let mut cfg_options = CfgOptions::default();
@@ -549,9 +777,22 @@ fn cargo_to_crate_graph(
     let mut has_private = false;
     // Next, create crates for each package, target pair
     for pkg in cargo.packages() {
+        if let Some(&lib) = shared.and_then(|it| it.pkg_to_lib_crate.get(&pkg)) {
+            pkg_to_lib_crate.insert(pkg, lib);
+            continue;
+        }
         let mut cfg_options = &cfg_options;
         let mut replaced_cfg_options;
 
+        let crate_target = crate_targets.get(&cargo[pkg].name);
+        if let Some(crate_target) = crate_target {
+            replaced_cfg_options = CfgOptions::default();
+            replaced_cfg_options.extend(crate_target.rustc_cfg.iter().cloned());
+            replaced_cfg_options.insert_atom("test".into());
+            replaced_cfg_options.insert_atom("debug_assertions".into());
+            cfg_options = &replaced_cfg_options;
+        }
+
         let overrides = match override_cfg {
             CfgOverrides::Wildcard(cfg_diff) => Some(cfg_diff),
             CfgOverrides::Selective(cfg_overrides) => cfg_overrides.get(&cargo[pkg].name),
@@ -571,6 +812,7 @@ fn cargo_to_crate_graph(
         };
 
         has_private |= cargo[pkg].metadata.rustc_private;
+        let artifact_env = artifact_dependency_env(cargo, build_scripts, pkg);
         let mut lib_tgt = None;
         for &tgt in cargo[pkg].targets.iter() {
             if cargo[tgt].kind != TargetKind::Lib && !cargo[pkg].is_member {
@@ -583,23 +825,34 @@ fn cargo_to_crate_graph(
             }
 
             if let Some(file_id) = load(&cargo[tgt].root) {
+                // Only build scripts see the binaries of build dependencies.
+                let is_build_script = cargo[tgt].kind == TargetKind::BuildScript;
+                let artifact_env = artifact_env
+                    .iter()
+                    .filter(|(kind, ..)| (*kind == DepKind::Build) == is_build_script)
+                    .map(|(_, key, value)| (key.as_str(), value.as_str()));
                 let crate_id = add_target_crate_root(
-                    &mut crate_graph,
+                    crate_graph,
                     &cargo[pkg],
                     build_scripts.outputs.get(pkg),
+                    artifact_env,
                     cfg_options,
                     &mut |path| load_proc_macro(&cargo[tgt].name, path),
                     file_id,
                     &cargo[tgt].name,
                     cargo[tgt].is_proc_macro,
                 );
+                if let Some(target_layout) = crate_target.and_then(|it| it.target_layout.as_deref())
+                {
+                    crate_graph.set_crate_target_layout(crate_id, Arc::from(target_layout));
+                }
                 if cargo[tgt].kind == TargetKind::Lib {
                     lib_tgt = Some((crate_id, cargo[tgt].name.clone()));
                     pkg_to_lib_crate.insert(pkg, crate_id);
                 }
                 if let Some(proc_macro) = libproc_macro {
                     add_dep_with_prelude(
-                        &mut crate_graph,
+                        crate_graph,
                         crate_id,
                         CrateName::new("proc_macro").unwrap(),
                         proc_macro,
@@ -614,7 +867,7 @@ fn cargo_to_crate_graph(
         // Set deps to the core, std and to the lib target of the current package
         for (from, kind) in pkg_crates.get(&pkg).into_iter().flatten() {
             // Add sysroot deps first so that a lib target named `core` etc. can overwrite them.
-            public_deps.add(*from, &mut crate_graph);
+            public_deps.add(*from, crate_graph);
 
             if let Some((to, name)) = lib_tgt.clone() {
                 if to != *from && *kind != TargetKind::BuildScript {
@@ -624,7 +877,7 @@ fn cargo_to_crate_graph(
                     // cargo metadata does not do any normalization,
                     // so we do it ourselves currently
                     let name = CrateName::normalize_dashes(&name);
-                    add_dep(&mut crate_graph, *from, name, to);
+                    add_dep(crate_graph, *from, name, to);
                 }
             }
         }
@@ -646,12 +899,25 @@ fn cargo_to_crate_graph(
                         continue;
                     }
 
-                    add_dep(&mut crate_graph, *from, name.clone(), to)
+                    if dep.kind == DepKind::Dev {
+                        add_dep_inner(crate_graph, *from, Dependency::new_dev(name.clone(), to))
+                    } else {
+                        add_dep(crate_graph, *from, name.clone(), to)
+                    }
                 }
             }
         }
     }
 
+    // Only the members are specific to the target, unless they have a target of their own.
+    let shared_pkg_to_lib_crate = pkg_to_lib_crate
+        .iter()
+        .filter(|(pkg, _)| {
+            !cargo[**pkg].is_member || crate_targets.contains_key(&cargo[**pkg].name)
+        })
+        .map(|(&pkg, &krate)| (pkg, krate))
+        .collect();
+
     if has_private {
         // If the user provided a path to rustc sources, we add all the rustc_private crates
         // and create dependencies on them for the crates which opt-in to that
@@ -659,7 +925,7 @@ fn cargo_to_crate_graph(
             handle_rustc_crates(
                 rustc_workspace,
                 load,
-                &mut crate_graph,
+                crate_graph,
                 &cfg_options,
                 override_cfg,
                 load_proc_macro,
@@ -670,7 +936,7 @@ fn cargo_to_crate_graph(
             );
         }
     }
-    crate_graph
+    SharedCrates { public_deps, libproc_macro, pkg_to_lib_crate: shared_pkg_to_lib_crate }
 }
 
 fn detached_files_to_crate_graph(
@@ -782,6 +1048,7 @@ fn handle_rustc_crates(
                         crate_graph,
                         &rustc_workspace[pkg],
                         None,
+                        iter::empty(),
                         cfg_options,
                         &mut |path| load_proc_macro(&rustc_workspace[tgt].name, path),
                         file_id,
@@ -837,6 +1104,7 @@ fn add_target_crate_root(
     crate_graph: &mut CrateGraph,
     pkg: &PackageData,
     build_data: Option<&BuildScriptOutput>,
+    artifact_env: impl Iterator<Item = (&str, &str)>,
     cfg_options: &CfgOptions,
     load_proc_macro: &mut dyn FnMut(&AbsPath) -> Vec<ProcMacro>,
     file_id: FileId,
@@ -863,6 +1131,9 @@ fn add_target_crate_root(
             env.set(k, v.clone());
         }
     }
+    for (k, v) in artifact_env {
+        env.set(k, v.to_string());
+    }
 
     let proc_macro = build_data
         .as_ref()
@@ -891,7 +1162,38 @@ fn add_target_crate_root(
     )
 }
 
-#[derive(Default)]
+/// The `CARGO_BIN_FILE_*` variables cargo sets for the binaries `pkg` depends
+/// on through artifact dependencies, along with the kind of each dependency.
+///
+/// The paths are only known once the build scripts have been run, as that's
+/// when cargo builds these binaries.
+fn artifact_dependency_env(
+    cargo: &CargoWorkspace,
+    build_scripts: &WorkspaceBuildScripts,
+    pkg: Package,
+) -> Vec<(DepKind, String, String)> {
+    let mut env = Vec::new();
+    for dep in &cargo[pkg].artifact_dependencies {
+        let executables = match build_scripts.outputs.get(dep.pkg) {
+            Some(it) => &it.bin_executables,
+            None => continue,
+        };
+        let dep_name = dep.name.to_uppercase().replace('-', "_");
+        for (bin, path) in executables {
+            if !dep.bins.is_empty() && !dep.bins.contains(bin) {
+                continue;
+            }
+            let path = path.display().to_string();
+            if *bin == cargo[dep.pkg].name {
+                env.push((dep.kind.clone(), format!("CARGO_BIN_FILE_{}", dep_name), path.clone()));
+            }
+            env.push((dep.kind.clone(), format!("CARGO_BIN_FILE_{}_{}", dep_name, bin), path));
+        }
+    }
+    env
+}
+
+#[derive(Default, Clone)]
 struct SysrootPublicDeps {
     deps: Vec<(CrateName, CrateId, bool)>,
 }
@@ -4,11 +4,16 @@
 mod logger;
 mod rustc_wrapper;
 
-use std::{env, fs, path::Path, process};
+use std::{
+    env, fs,
+    net::TcpListener,
+    path::{Path, PathBuf},
+    process,
+};
 
 use lsp_server::Connection;
 use project_model::ProjectManifest;
-use rust_analyzer::{cli::flags, config::Config, from_json, lsp_ext::supports_utf8, Result};
+use rust_analyzer::{cli::flags, config::Config, from_json, PositionEncoding, Result};
 use vfs::AbsPathBuf;
 
 #[cfg(all(feature = "mimalloc"))]
@@ -69,6 +74,26 @@ fn try_main() -> Result<()> {
                 println!("{:#}", Config::json_schema());
                 return Ok(());
             }
+            if cmd.print_config_warnings {
+                let root_path = AbsPathBuf::assert(env::current_dir()?);
+                let mut config = Config::new(root_path, lsp_types::ClientCapabilities::default());
+                if let Err(e) = config.reload_local_configs() {
+                    println!("{}", e);
+                }
+                if let Some(warning) = config.outdated_keys_warning() {
+                    println!("{}", warning);
+                }
+                return Ok(());
+            }
+            if cmd.print_effective_config {
+                let root_path = AbsPathBuf::assert(env::current_dir()?);
+                let mut config = Config::new(root_path, lsp_types::ClientCapabilities::default());
+                if let Err(e) = config.reload_local_configs() {
+                    eprintln!("{}", e);
+                }
+                println!("{:#}", config.effective_config());
+                return Ok(());
+            }
             if cmd.version {
                 println!("rust-analyzer {}", env!("REV"));
                 return Ok(());
@@ -77,7 +102,15 @@ fn try_main() -> Result<()> {
                 println!("{}", flags::RustAnalyzer::HELP);
                 return Ok(());
             }
-            with_extra_thread("rust-analyzer server thread", run_server)?;
+            let transport = match (cmd.listen, cmd.pipe) {
+                (Some(_), Some(_)) => {
+                    return Err("`--listen` and `--pipe` can't be used together".into())
+                }
+                (Some(addr), None) => Transport::Tcp(addr),
+                (None, Some(path)) => Transport::Pipe(path),
+                (None, None) => Transport::Stdio,
+            };
+            with_extra_thread("rust-analyzer server thread", move || serve(transport))?;
         }
         flags::RustAnalyzerCmd::ProcMacro(flags::ProcMacro) => {
             with_extra_thread("rust-analyzer proc-macro expander", || {
@@ -88,10 +121,12 @@ fn try_main() -> Result<()> {
         flags::RustAnalyzerCmd::Symbols(cmd) => cmd.run()?,
         flags::RustAnalyzerCmd::Highlight(cmd) => cmd.run()?,
         flags::RustAnalyzerCmd::AnalysisStats(cmd) => cmd.run(verbosity)?,
+        flags::RustAnalyzerCmd::AnalysisBench(cmd) => cmd.run()?,
         flags::RustAnalyzerCmd::Diagnostics(cmd) => cmd.run()?,
         flags::RustAnalyzerCmd::Ssr(cmd) => cmd.run()?,
         flags::RustAnalyzerCmd::Search(cmd) => cmd.run()?,
         flags::RustAnalyzerCmd::Lsif(cmd) => cmd.run()?,
+        flags::RustAnalyzerCmd::Scip(cmd) => cmd.run()?,
     }
     Ok(())
 }
@@ -149,13 +184,65 @@ fn with_extra_thread(
     }
 }
 
-fn run_server() -> Result<()> {
-    tracing::info!("server version {} will start", env!("REV"));
+/// How the server talks to the client.
+enum Transport {
+    Stdio,
+    /// Listen on a tcp address, accepting a new client whenever the previous one went away.
+    Tcp(String),
+    /// Connect to a named pipe or unix socket the client is listening on.
+    Pipe(PathBuf),
+}
 
-    let (connection, io_threads) = Connection::stdio();
+fn serve(transport: Transport) -> Result<()> {
+    let (connection, io_threads) = match transport {
+        Transport::Stdio => Connection::stdio(),
+        Transport::Pipe(path) => Connection::pipe(path)?,
+        Transport::Tcp(addr) => {
+            let listener = TcpListener::bind(addr)?;
+            tracing::info!("listening on {}", listener.local_addr()?);
+            loop {
+                let (connection, io_threads) = Connection::accept(&listener)?;
+                match run_server(connection) {
+                    Ok(()) => {
+                        io_threads.join()?;
+                        return Ok(());
+                    }
+                    // The client disconnected without shutting the server down, e.g. because
+                    // the editor or the network went away, so wait for it to reconnect.
+                    Err(err) => {
+                        tracing::error!("session ended unexpectedly: {}", err);
+                        if let Err(err) = io_threads.join() {
+                            tracing::error!("connection closed with an error: {}", err);
+                        }
+                    }
+                }
+            }
+        }
+    };
+    run_server(connection)?;
+    io_threads.join()?;
+    Ok(())
+}
+
+fn run_server(connection: Connection) -> Result<()> {
+    tracing::info!("server version {} will start", env!("REV"));
 
     let (initialize_id, initialize_params) = connection.initialize_start()?;
     tracing::info!("InitializeParams: {}", initialize_params);
+    // FIXME: read these from `InitializeParams` once `lsp_types` supports position encodings.
+    let position_encodings: Vec<String> = initialize_params
+        .pointer("/capabilities/general/positionEncodings")
+        .and_then(|it| serde_json::from_value(it.clone()).ok())
+        .unwrap_or_default();
+    // FIXME: likewise for pull diagnostics.
+    let diagnostic_pull_support = initialize_params
+        .pointer("/capabilities/textDocument/diagnostic/dynamicRegistration")
+        .and_then(|it| it.as_bool())
+        .unwrap_or(false);
+    let diagnostic_refresh_support = initialize_params
+        .pointer("/capabilities/workspace/diagnostics/refreshSupport")
+        .and_then(|it| it.as_bool())
+        .unwrap_or(false);
     let initialize_params =
         from_json::<lsp_types::InitializeParams>("InitializeParams", initialize_params)?;
 
@@ -172,18 +259,23 @@ fn run_server() -> Result<()> {
     };
 
     let mut config = Config::new(root_path, initialize_params.capabilities);
+    config.negotiate_position_encoding(&position_encodings);
+    config.set_diagnostic_capabilities(diagnostic_pull_support, diagnostic_refresh_support);
     if let Some(json) = initialize_params.initialization_options {
-        if let Err(e) = config.update(json) {
-            use lsp_types::{
-                notification::{Notification, ShowMessage},
-                MessageType, ShowMessageParams,
-            };
-            let not = lsp_server::Notification::new(
-                ShowMessage::METHOD.to_string(),
-                ShowMessageParams { typ: MessageType::WARNING, message: e.to_string() },
-            );
-            connection.sender.send(lsp_server::Message::Notification(not)).unwrap();
-        }
+        // Any errors are reported below, once the project-local configuration is merged in.
+        let _ = config.update(json);
+    }
+    let errors = config.reload_local_configs().err().map(|e| e.to_string());
+    for message in errors.into_iter().chain(config.outdated_keys_warning()) {
+        use lsp_types::{
+            notification::{Notification, ShowMessage},
+            MessageType, ShowMessageParams,
+        };
+        let not = lsp_server::Notification::new(
+            ShowMessage::METHOD.to_string(),
+            ShowMessageParams { typ: MessageType::WARNING, message },
+        );
+        connection.sender.send(lsp_server::Message::Notification(not)).unwrap();
     }
 
     let server_capabilities = rust_analyzer::server_capabilities(&config);
@@ -194,14 +286,18 @@ fn run_server() -> Result<()> {
             name: String::from("rust-analyzer"),
             version: Some(String::from(env!("REV"))),
         }),
-        offset_encoding: if supports_utf8(config.caps()) {
-            Some("utf-8".to_string())
-        } else {
-            None
+        offset_encoding: match config.position_encoding() {
+            PositionEncoding::Utf8 => Some("utf-8".to_string()),
+            PositionEncoding::Wide(_) => None,
         },
     };
 
-    let initialize_result = serde_json::to_value(initialize_result).unwrap();
+    let mut initialize_result = serde_json::to_value(initialize_result).unwrap();
+    // FIXME: move this into `server_capabilities` once `lsp_types` supports type hierarchies.
+    initialize_result["capabilities"]["typeHierarchyProvider"] = serde_json::Value::Bool(true);
+    // FIXME: likewise for position encodings.
+    initialize_result["capabilities"]["positionEncoding"] =
+        serde_json::Value::String(config.position_encoding().kind().to_string());
 
     connection.initialize_finish(initialize_id, initialize_result)?;
 
@@ -209,30 +305,29 @@ fn run_server() -> Result<()> {
         tracing::info!("Client '{}' {}", client_info.name, client_info.version.unwrap_or_default());
     }
 
+    config.workspace_roots = initialize_params
+        .workspace_folders
+        .map(|workspaces| {
+            workspaces
+                .into_iter()
+                .filter_map(|it| it.uri.to_file_path().ok())
+                .filter_map(|it| AbsPathBuf::try_from(it).ok())
+                .collect::<Vec<_>>()
+        })
+        .filter(|workspaces| !workspaces.is_empty())
+        .unwrap_or_else(|| vec![config.root_path().clone()]);
+
     if config.linked_projects().is_empty() && config.detached_files().is_empty() {
-        let workspace_roots = initialize_params
-            .workspace_folders
-            .map(|workspaces| {
-                workspaces
-                    .into_iter()
-                    .filter_map(|it| it.uri.to_file_path().ok())
-                    .filter_map(|it| AbsPathBuf::try_from(it).ok())
-                    .collect::<Vec<_>>()
-            })
-            .filter(|workspaces| !workspaces.is_empty())
-            .unwrap_or_else(|| vec![config.root_path().clone()]);
-
-        let discovered = ProjectManifest::discover_all(&workspace_roots);
+        let discovered = ProjectManifest::discover_all(&config.workspace_roots);
         tracing::info!("discovered projects: {:?}", discovered);
         if discovered.is_empty() {
-            tracing::error!("failed to find any projects in {:?}", workspace_roots);
+            tracing::error!("failed to find any projects in {:?}", config.workspace_roots);
         }
         config.discovered_projects = Some(discovered);
     }
 
     rust_analyzer::main_loop(config, connection)?;
 
-    io_threads.join()?;
     tracing::info!("server did shut down");
     Ok(())
 }
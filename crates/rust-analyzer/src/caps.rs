@@ -10,7 +10,7 @@ use lsp_types::{
     SemanticTokensOptions, ServerCapabilities, SignatureHelpOptions, TextDocumentSyncCapability,
     TextDocumentSyncKind, TextDocumentSyncOptions, TypeDefinitionProviderCapability,
     WorkDoneProgressOptions, WorkspaceFileOperationsServerCapabilities,
-    WorkspaceServerCapabilities,
+    WorkspaceFoldersServerCapabilities, WorkspaceServerCapabilities,
 };
 use serde_json::json;
 
@@ -69,7 +69,10 @@ pub fn server_capabilities(config: &Config) -> ServerCapabilities {
         color_provider: None,
         execute_command_provider: None,
         workspace: Some(WorkspaceServerCapabilities {
-            workspace_folders: None,
+            workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+                supported: Some(true),
+                change_notifications: Some(OneOf::Left(true)),
+            }),
             file_operations: Some(WorkspaceFileOperationsServerCapabilities {
                 did_create: None,
                 will_create: None,
@@ -132,22 +135,31 @@ pub fn server_capabilities(config: &Config) -> ServerCapabilities {
                 "kinds": [ "cargo" ],
             },
             "ssr": true,
+            "testExplorer": true,
             "workspaceSymbolScopeKindFiltering": true,
         })),
     }
 }
 
 fn completions_resolve_provider(client_caps: &ClientCapabilities) -> Option<bool> {
-    if completion_item_edit_resolve(client_caps) {
+    let edit_resolve = completion_item_edit_resolve(client_caps);
+    if !edit_resolve {
+        tracing::info!("No `additionalTextEdits` completion resolve capability was found in the client capabilities, autoimport completion is disabled");
+    }
+    if edit_resolve || completion_item_resolve(client_caps, "documentation") {
         Some(true)
     } else {
-        tracing::info!("No `additionalTextEdits` completion resolve capability was found in the client capabilities, autoimport completion is disabled");
         None
     }
 }
 
 /// Parses client capabilities and returns all completion resolve capabilities rust-analyzer supports.
 pub(crate) fn completion_item_edit_resolve(caps: &ClientCapabilities) -> bool {
+    completion_item_resolve(caps, "additionalTextEdits")
+}
+
+/// Whether the client can resolve `property` of a completion item lazily.
+pub(crate) fn completion_item_resolve(caps: &ClientCapabilities, property: &str) -> bool {
     (|| {
         Some(
             caps.text_document
@@ -160,7 +172,7 @@ pub(crate) fn completion_item_edit_resolve(caps: &ClientCapabilities) -> bool {
                 .as_ref()?
                 .properties
                 .iter()
-                .any(|cap_string| cap_string.as_str() == "additionalTextEdits"),
+                .any(|cap_string| cap_string.as_str() == property),
         )
     })() == Some(true)
 }
@@ -194,6 +206,7 @@ fn more_trigger_character(config: &Config) -> Vec<String> {
     let mut res = vec![".".to_string(), ">".to_string(), "{".to_string()];
     if config.snippet_cap() {
         res.push("<".to_string());
+        res.push("|".to_string());
     }
     res
 }
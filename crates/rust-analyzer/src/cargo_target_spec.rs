@@ -36,9 +36,15 @@ impl CargoTargetSpec {
         match kind {
             RunnableKind::Test { test_id, attr } => {
                 args.push("test".to_string());
-                extra_args.push(test_id.to_string());
-                if let TestId::Path(_) = test_id {
-                    extra_args.push("--exact".to_string());
+                match test_id {
+                    TestId::Path(path) => {
+                        extra_args.push(path.clone());
+                        extra_args.push("--exact".to_string());
+                    }
+                    // Only match the tests inside the module, not everything starting with
+                    // the function's name.
+                    TestId::Prefix(path) => extra_args.push(format!("{}::", path)),
+                    TestId::Name(name) => extra_args.push(name.to_string()),
                 }
                 extra_args.push("--nocapture".to_string());
                 if attr.ignore {
@@ -58,6 +64,10 @@ impl CargoTargetSpec {
                 }
                 extra_args.push("--nocapture".to_string());
             }
+            RunnableKind::BenchHarness { filter } => {
+                args.push("bench".to_string());
+                extra_args.extend(filter.clone());
+            }
             RunnableKind::DocTest { test_id } => {
                 args.push("test".to_string());
                 args.push("--doc".to_string());
@@ -98,6 +108,8 @@ impl CargoTargetSpec {
             features.extend(cargo_config.features);
             features.extend(target_required_features);
 
+            // The same feature can be required by the cfgs as well as by the target.
+            features.sort_unstable();
             features.dedup();
             for feature in features {
                 args.push("--features".to_string());
@@ -6,17 +6,19 @@ mod parse;
 mod symbols;
 mod highlight;
 mod analysis_stats;
+mod analysis_bench;
 mod diagnostics;
 mod ssr;
 mod lsif;
+mod scip;
 
 mod progress_report;
 
 use std::io::Read;
 
 use anyhow::Result;
-use ide::AnalysisHost;
-use vfs::Vfs;
+use ide::{AnalysisHost, LineIndex, TextSize, WideEncoding};
+use vfs::{AbsPath, Vfs};
 
 #[derive(Clone, Copy)]
 pub enum Verbosity {
@@ -41,6 +43,18 @@ fn read_stdin() -> Result<String> {
     Ok(buff)
 }
 
+/// The 1-based line and column of `offset`, with columns counting UTF-16 code units like SARIF
+/// does by default.
+fn position(line_index: &LineIndex, offset: TextSize) -> (u32, u32) {
+    let line_col = line_index.to_wide(WideEncoding::Utf16, line_index.line_col(offset));
+    (line_col.line + 1, line_col.col + 1)
+}
+
+/// `path` relative to `root`, with `/` as the separator on all platforms.
+fn relative_path(root: &AbsPath, path: &AbsPath) -> Option<String> {
+    Some(path.strip_prefix(root)?.as_ref().to_str()?.replace('\\', "/"))
+}
+
 fn report_metric(metric: &str, value: u64, unit: &str) {
     if std::env::var("RA_METRICS").is_err() {
         return;
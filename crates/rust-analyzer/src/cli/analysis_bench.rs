@@ -0,0 +1,172 @@
+//! Runs a single IDE feature over and over, and reports how long that takes.
+
+use std::{
+    env,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, format_err};
+use ide::{
+    Analysis, Cancellable, Change, FileId, FilePosition, FileRange, LineCol, TextRange, TextSize,
+};
+use lsp_types::ClientCapabilities;
+use profile::StopWatch;
+use project_model::CargoConfig;
+use syntax::{
+    ast::{self, HasModuleItem},
+    AstNode,
+};
+use vfs::{AbsPathBuf, VfsPath};
+
+use crate::{
+    cli::{
+        flags::{self, Position},
+        load_cargo::{load_workspace_at, LoadCargoConfig},
+        report_metric, Result,
+    },
+    config::Config,
+};
+
+#[derive(Clone, Copy)]
+enum BenchWhat<'a> {
+    Highlight(&'a Path),
+    Complete(&'a Position),
+    Hover(&'a Position),
+}
+
+impl flags::AnalysisBench {
+    pub fn run(self) -> Result<()> {
+        let what = match (&self.highlight, &self.complete, &self.hover) {
+            (Some(path), None, None) => BenchWhat::Highlight(path),
+            (None, Some(position), None) => BenchWhat::Complete(position),
+            (None, None, Some(position)) => BenchWhat::Hover(position),
+            _ => bail!("exactly one of `--highlight`, `--complete` and `--hover` must be given"),
+        };
+
+        let mut db_load_sw = self.stop_watch();
+        let cargo_config = CargoConfig::default();
+        let load_cargo_config = LoadCargoConfig {
+            load_out_dirs_from_check: !self.disable_build_scripts,
+            with_proc_macro: !self.disable_proc_macros,
+            prefill_caches: false,
+        };
+        let (mut host, vfs, _proc_macro) =
+            load_workspace_at(&self.path, &cargo_config, &load_cargo_config, &|_| {})?;
+        eprintln!("{:<20} {}", "Database loaded:", db_load_sw.elapsed());
+
+        let current_dir = env::current_dir()?;
+        let path = match what {
+            BenchWhat::Highlight(path) => path,
+            BenchWhat::Complete(position) | BenchWhat::Hover(position) => &position.path,
+        };
+        let path = AbsPathBuf::assert(current_dir.join(path)).normalize();
+        let file_id = vfs
+            .file_id(&VfsPath::from(path.clone()))
+            .ok_or_else(|| format_err!("can't find {} in the workspace", path.display()))?;
+        let text = host.analysis().file_text(file_id)?;
+        let offset = match what {
+            BenchWhat::Highlight(_) => TextSize::default(),
+            BenchWhat::Complete(position) | BenchWhat::Hover(position) => {
+                if position.line == 0 {
+                    bail!("lines are 1-based, got line 0");
+                }
+                let line_index = host.analysis().file_line_index(file_id)?;
+                let line_col = LineCol { line: position.line - 1, col: position.column };
+                line_index.offset(line_col).ok_or_else(|| {
+                    format_err!("{}:{} is outside of the file", position.line, position.column)
+                })?
+            }
+        };
+
+        // Typing happens in the item that is looked at, so the edits go just before its last
+        // token. Highlighting looks at the whole file, there the item in the middle is edited.
+        let file = host.analysis().parse(file_id)?;
+        let item = match what {
+            BenchWhat::Highlight(_) => {
+                let items = file.items().collect::<Vec<_>>();
+                items.get(items.len() / 2).cloned()
+            }
+            BenchWhat::Complete(_) | BenchWhat::Hover(_) => file
+                .syntax()
+                .token_at_offset(offset)
+                .left_biased()
+                .and_then(|it| it.parent()?.ancestors().find_map(ast::Item::cast)),
+        };
+        let edit_offset = item
+            .and_then(|it| it.syntax().last_token())
+            .map_or(TextSize::of(&*text), |it| it.text_range().start());
+
+        let root = AbsPathBuf::assert(current_dir.join(&self.path)).normalize();
+        let config = Config::new(root, ClientCapabilities::default());
+        let run = |analysis: &Analysis, offset: TextSize| -> Cancellable<()> {
+            match what {
+                BenchWhat::Highlight(_) => {
+                    analysis.highlight(file_id)?;
+                }
+                BenchWhat::Complete(_) => {
+                    analysis.completions(&config.completion(), FilePosition { file_id, offset })?;
+                }
+                BenchWhat::Hover(_) => {
+                    let range = FileRange { file_id, range: TextRange::empty(offset) };
+                    analysis.hover(&config.hover(), range)?;
+                }
+            }
+            Ok(())
+        };
+
+        let mut first_run_sw = self.stop_watch();
+        run(&host.analysis(), offset)?;
+        eprintln!("{:<20} {}", "First run:", first_run_sw.elapsed());
+
+        let iterations = self.iterations.unwrap_or(10);
+        let mut times = Vec::with_capacity(iterations);
+        for i in 0..iterations {
+            // Change the file every time like typing would, so that the feature actually has to
+            // be recomputed instead of coming straight from the caches.
+            let mut new_text = text.to_string();
+            let mut offset = offset;
+            if i % 2 == 0 {
+                new_text.insert(edit_offset.into(), ' ');
+                if edit_offset <= offset {
+                    offset += TextSize::of(' ');
+                }
+            }
+            host.apply_change(file_change(file_id, new_text));
+
+            let start = Instant::now();
+            run(&host.analysis(), offset)?;
+            times.push(start.elapsed());
+        }
+        if times.is_empty() {
+            return Ok(());
+        }
+
+        times.sort();
+        let percentile = |p: usize| times[(times.len() - 1) * p / 100];
+        eprintln!(
+            "{:<20} p50 {:.2?}, p90 {:.2?}, p99 {:.2?}, max {:.2?} ({} runs)",
+            "After a change:",
+            percentile(50),
+            percentile(90),
+            percentile(99),
+            percentile(100),
+            times.len()
+        );
+        report_metric("bench p50", percentile(50).as_millis() as u64, "ms");
+        report_metric("bench p90", percentile(90).as_millis() as u64, "ms");
+        report_metric("bench p99", percentile(99).as_millis() as u64, "ms");
+        Ok(())
+    }
+
+    fn stop_watch(&self) -> StopWatch {
+        StopWatch::start().memory(self.memory_usage)
+    }
+}
+
+fn file_change(file_id: FileId, text: String) -> Change {
+    let mut change = Change::new();
+    change.change_file(file_id, Some(Arc::new(text)));
+    change
+}
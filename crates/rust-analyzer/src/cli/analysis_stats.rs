@@ -3,12 +3,12 @@
 
 use std::{
     env,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use hir::{
     db::{AstDatabase, DefDatabase, HirDatabase},
-    AssocItem, Crate, Function, HasSource, HirDisplay, ModuleDef,
+    AssocItem, Crate, Function, HasSource, HirDisplay, ModuleDef, TraitSolverConfig,
 };
 use hir_def::{
     body::{BodySourceMap, SyntheticSyntax},
@@ -26,7 +26,7 @@ use oorandom::Rand32;
 use profile::{Bytes, StopWatch};
 use project_model::{CargoConfig, ProjectManifest, ProjectWorkspace};
 use rayon::prelude::*;
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
 use stdx::format_to;
 use syntax::{AstNode, SyntaxNode};
 use vfs::{AbsPathBuf, Vfs, VfsPath};
@@ -80,7 +80,13 @@ impl flags::AnalysisStats {
             Some(build_scripts_sw.elapsed())
         };
 
-        let (host, vfs, _proc_macro) = load_workspace(workspace, &load_cargo_config)?;
+        let (mut host, vfs, _proc_macro) = load_workspace(workspace, &load_cargo_config)?;
+        if let Some(timeout) = self.trait_solver_timeout {
+            host.raw_database_mut().set_trait_solver_config(TraitSolverConfig {
+                timeout: Some(Duration::from_millis(timeout)),
+                ..Default::default()
+            });
+        }
         let db = host.raw_database();
         eprint!("{:<20} {}", "Database loaded:", db_load_sw.elapsed());
         eprint!(" (metadata {}", metadata_time);
@@ -89,6 +95,12 @@ impl flags::AnalysisStats {
         }
         eprintln!(")");
 
+        // How long lowering and inferring each function body took the first time.
+        let mut body_times = FxHashMap::default();
+        if self.crate_stats {
+            self.run_crate_stats(db, &mut body_times);
+        }
+
         let mut analysis_sw = self.stop_watch();
         let mut num_crates = 0;
         let mut visited_modules = FxHashSet::default();
@@ -99,14 +111,9 @@ impl flags::AnalysisStats {
             shuffle(&mut rng, &mut krates);
         }
         for krate in krates {
-            let module = krate.root_module(db);
-            let file_id = module.definition_source(db).file_id;
-            let file_id = file_id.original_file(db);
-            let source_root = db.file_source_root(file_id);
-            let source_root = db.source_root(source_root);
-            if !source_root.is_library || self.with_deps {
+            if self.is_analyzed(db, krate) {
                 num_crates += 1;
-                visit_queue.push(module);
+                visit_queue.push(krate.root_module(db));
             }
         }
 
@@ -146,7 +153,16 @@ impl flags::AnalysisStats {
         }
 
         if !self.skip_inference {
-            self.run_inference(&host, db, &vfs, &funcs, verbosity);
+            self.run_inference(&host, db, &vfs, &funcs, &mut body_times, verbosity);
+        }
+
+        if let Some(n) = self.slowest {
+            let mut body_times = body_times.into_iter().collect::<Vec<_>>();
+            body_times.sort_by_key(|&(_, time)| std::cmp::Reverse(time));
+            eprintln!("Slowest bodies:");
+            for (f, time) in body_times.into_iter().take(n) {
+                eprintln!("{:>10.2?} {}", time, full_name(db, f));
+            }
         }
 
         let total_span = analysis_sw.elapsed();
@@ -180,6 +196,11 @@ impl flags::AnalysisStats {
 
         if self.memory_usage && verbosity.is_verbose() {
             print_memory_usage(host, vfs);
+        } else if self.crate_stats {
+            eprintln!("Memory per query group:");
+            for (group, bytes) in host.per_query_group_memory_usage() {
+                eprintln!("{:>8} {}", bytes, group);
+            }
         }
 
         Ok(())
@@ -191,6 +212,7 @@ impl flags::AnalysisStats {
         db: &RootDatabase,
         vfs: &Vfs,
         funcs: &[Function],
+        body_times: &mut FxHashMap<Function, Duration>,
         verbosity: Verbosity,
     ) {
         let mut bar = match verbosity {
@@ -202,14 +224,19 @@ impl flags::AnalysisStats {
         if self.parallel {
             let mut inference_sw = self.stop_watch();
             let snap = Snap(db.snapshot());
-            funcs
+            let times: Vec<_> = funcs
                 .par_iter()
                 .map_with(snap, |snap, &f| {
+                    let start = Instant::now();
                     let f_id = FunctionId::from(f);
                     snap.0.body(f_id.into());
                     snap.0.infer(f_id.into());
+                    (f, start.elapsed())
                 })
-                .count();
+                .collect();
+            for (f, time) in times {
+                body_times.entry(f).or_insert(time);
+            }
             eprintln!("{:<20} {}", "Parallel Inference:", inference_sw.elapsed());
         }
 
@@ -222,14 +249,7 @@ impl flags::AnalysisStats {
         let analysis = host.analysis();
         for f in funcs.iter().copied() {
             let name = f.name(db);
-            let full_name = f
-                .module(db)
-                .path_to_root(db)
-                .into_iter()
-                .rev()
-                .filter_map(|it| it.name(db))
-                .chain(Some(f.name(db)))
-                .join("::");
+            let full_name = full_name(db, f);
             if let Some(only_name) = self.only.as_deref() {
                 if name.to_string() != only_name && full_name != only_name {
                     continue;
@@ -248,9 +268,11 @@ impl flags::AnalysisStats {
                 bar.println(msg.to_string());
             }
             bar.set_message(&msg);
+            let start = Instant::now();
             let f_id = FunctionId::from(f);
             let (body, sm) = db.body_with_source_map(f_id.into());
             let inference_result = db.infer(f_id.into());
+            body_times.entry(f).or_insert_with(|| start.elapsed());
             let (previous_exprs, previous_unknown, previous_partially_unknown) =
                 (num_exprs, num_exprs_unknown, num_exprs_partially_unknown);
             for (expr_id, _) in body.exprs.iter() {
@@ -359,26 +381,122 @@ impl flags::AnalysisStats {
         }
 
         bar.finish_and_clear();
+        let num_trait_timeouts: usize = Crate::all(db)
+            .into_iter()
+            .map(|krate| db.trait_solve_stats(krate.into()).timed_out_goals())
+            .sum();
         eprintln!(
-            "  exprs: {}, ??ty: {} ({}%), ?ty: {} ({}%), !ty: {}",
+            "  exprs: {}, ??ty: {} ({}%), ?ty: {} ({}%), !ty: {}, timed out goals: {}",
             num_exprs,
             num_exprs_unknown,
             percentage(num_exprs_unknown, num_exprs),
             num_exprs_partially_unknown,
             percentage(num_exprs_partially_unknown, num_exprs),
-            num_type_mismatches
+            num_type_mismatches,
+            num_trait_timeouts
         );
         report_metric("unknown type", num_exprs_unknown, "#");
         report_metric("type mismatches", num_type_mismatches, "#");
+        report_metric("trait goal timeouts", num_trait_timeouts as u64, "#");
 
         eprintln!("{:<20} {}", "Inference:", inference_sw.elapsed());
     }
 
+    /// Goes through the analyzed crates in dependency order, and reports the time each of them
+    /// spends in the different phases of the analysis.
+    fn run_crate_stats(&self, db: &RootDatabase, body_times: &mut FxHashMap<Function, Duration>) {
+        eprintln!("Crate stats:");
+        for krate in db.crate_graph().crates_in_topological_order() {
+            let krate = Crate::from(krate);
+            if !self.is_analyzed(db, krate) {
+                // Resolve the names of dependencies upfront, so that they don't count towards
+                // the crates depending on them.
+                db.crate_def_map(krate.into());
+                continue;
+            }
+            let solve_stats = db.trait_solve_stats(krate.into());
+            let solve_time_before = solve_stats.solve_time();
+
+            let mut parse_sw = self.stop_watch();
+            let source_root = db.file_source_root(krate.root_file(db));
+            for file_id in db.source_root(source_root).iter() {
+                db.parse(file_id);
+            }
+            let parse_time = parse_sw.elapsed();
+
+            // Item-level macros are expanded as part of name resolution.
+            let mut nameres_sw = self.stop_watch();
+            let modules = krate.modules(db);
+            let nameres_time = nameres_sw.elapsed();
+
+            let mut funcs = Vec::new();
+            for module in modules {
+                for decl in module.declarations(db) {
+                    if let ModuleDef::Function(f) = decl {
+                        funcs.push(f);
+                    }
+                }
+                for impl_def in module.impl_defs(db) {
+                    for item in impl_def.items(db) {
+                        if let AssocItem::Function(f) = item {
+                            funcs.push(f);
+                        }
+                    }
+                }
+            }
+
+            // Expression-level macros are expanded while lowering bodies.
+            let mut times = Vec::with_capacity(funcs.len());
+            let mut bodies_sw = self.stop_watch();
+            for &f in &funcs {
+                let start = Instant::now();
+                db.body(FunctionId::from(f).into());
+                times.push(start.elapsed());
+            }
+            let bodies_time = bodies_sw.elapsed();
+
+            let mut inference_sw = self.stop_watch();
+            for (&f, time) in funcs.iter().zip(&mut times) {
+                let start = Instant::now();
+                db.infer(FunctionId::from(f).into());
+                *time += start.elapsed();
+            }
+            let inference_time = inference_sw.elapsed();
+            let solve_time = solve_stats.solve_time() - solve_time_before;
+
+            for (f, time) in funcs.into_iter().zip(times) {
+                body_times.entry(f).or_insert(time);
+            }
+
+            let name =
+                krate.display_name(db).map_or_else(|| "<unnamed>".to_string(), |it| it.to_string());
+            eprintln!(
+                "  {}: parse {}; nameres {}; bodies {}; inference {} (trait solving {:.2?})",
+                name, parse_time, nameres_time, bodies_time, inference_time, solve_time
+            );
+        }
+    }
+
+    fn is_analyzed(&self, db: &RootDatabase, krate: Crate) -> bool {
+        let source_root = db.file_source_root(krate.root_file(db));
+        !db.source_root(source_root).is_library || self.with_deps
+    }
+
     fn stop_watch(&self) -> StopWatch {
         StopWatch::start().memory(self.memory_usage)
     }
 }
 
+fn full_name(db: &RootDatabase, f: Function) -> String {
+    f.module(db)
+        .path_to_root(db)
+        .into_iter()
+        .rev()
+        .filter_map(|it| it.name(db))
+        .chain(Some(f.name(db)))
+        .join("::")
+}
+
 fn location_csv(
     db: &RootDatabase,
     analysis: &Analysis,
@@ -1,32 +1,44 @@
 //! Analyze all modules in a project for diagnostics. Exits with a non-zero
 //! status code if any errors are found.
 
+use std::env;
+
 use rustc_hash::FxHashSet;
+use serde_json::{json, Value};
 
 use hir::{db::HirDatabase, Crate, Module};
-use ide::{AssistResolveStrategy, DiagnosticsConfig, Severity};
-use ide_db::base_db::SourceDatabaseExt;
+use ide::{AssistResolveStrategy, Diagnostic, DiagnosticsConfig, LineIndex, Severity};
+use ide_db::{base_db::SourceDatabaseExt, LineIndexDatabase};
+use vfs::{AbsPath, AbsPathBuf};
 
-use crate::cli::{
-    flags,
-    load_cargo::{load_workspace_at, LoadCargoConfig},
+use crate::{
+    cli::{
+        flags::{self, DiagnosticsFormat, DiagnosticsSeverity},
+        load_cargo::{load_workspace_at, LoadCargoConfig},
+        position, relative_path,
+    },
+    config::glob_matches,
 };
 
 impl flags::Diagnostics {
     pub fn run(self) -> anyhow::Result<()> {
+        let format = self.format.unwrap_or(DiagnosticsFormat::Text);
+        let errors_only = self.severity == Some(DiagnosticsSeverity::Error);
         let cargo_config = Default::default();
         let load_cargo_config = LoadCargoConfig {
             load_out_dirs_from_check: !self.disable_build_scripts,
             with_proc_macro: !self.disable_proc_macros,
             prefill_caches: false,
         };
-        let (host, _vfs, _proc_macro) =
+        let root = AbsPathBuf::assert(env::current_dir()?.join(&self.path)).normalize();
+        let (host, vfs, _proc_macro) =
             load_workspace_at(&self.path, &cargo_config, &load_cargo_config, &|_| {})?;
         let db = host.raw_database();
         let analysis = host.analysis();
 
         let mut found_error = false;
         let mut visited_files = FxHashSet::default();
+        let mut reported = Vec::new();
 
         let work = all_modules(db).into_iter().filter(|module| {
             let file_id = module.definition_source(db).file_id.original_file(db);
@@ -37,39 +49,163 @@ impl flags::Diagnostics {
 
         for module in work {
             let file_id = module.definition_source(db).file_id.original_file(db);
-            if !visited_files.contains(&file_id) {
+            if !visited_files.insert(file_id) {
+                continue;
+            }
+            let path = vfs.file_path(file_id);
+            let relative_path = path.as_path().and_then(|path| relative_path(&root, path));
+            if !self.is_included(relative_path.as_deref()) {
+                continue;
+            }
+
+            if format == DiagnosticsFormat::Text {
                 let crate_name =
                     module.krate().display_name(db).as_deref().unwrap_or("unknown").to_string();
-                println!("processing crate: {}, module: {}", crate_name, _vfs.file_path(file_id));
-                for diagnostic in analysis
-                    .diagnostics(
-                        &DiagnosticsConfig::default(),
-                        AssistResolveStrategy::None,
-                        file_id,
-                    )
-                    .unwrap()
-                {
-                    if matches!(diagnostic.severity, Severity::Error) {
-                        found_error = true;
-                    }
-
-                    println!("{:?}", diagnostic);
+                println!("processing crate: {}, module: {}", crate_name, path);
+            }
+            let line_index = db.line_index(file_id);
+            for diagnostic in analysis
+                .diagnostics(&DiagnosticsConfig::default(), AssistResolveStrategy::None, file_id)
+                .unwrap()
+            {
+                let is_error = matches!(diagnostic.severity, Severity::Error);
+                if errors_only && !is_error {
+                    continue;
                 }
+                found_error |= is_error;
 
-                visited_files.insert(file_id);
+                match format {
+                    DiagnosticsFormat::Text => println!("{:?}", diagnostic),
+                    DiagnosticsFormat::Json | DiagnosticsFormat::Sarif => {
+                        let path = relative_path.clone().unwrap_or_else(|| path.to_string());
+                        reported.push(Reported::new(path, &line_index, diagnostic));
+                    }
+                }
             }
         }
 
-        println!();
-        println!("diagnostic scan complete");
+        match format {
+            DiagnosticsFormat::Text => {
+                println!();
+                println!("diagnostic scan complete");
+            }
+            DiagnosticsFormat::Json => {
+                let diagnostics: Vec<Value> = reported.iter().map(Reported::to_json).collect();
+                println!("{}", serde_json::to_string_pretty(&diagnostics)?);
+            }
+            DiagnosticsFormat::Sarif => {
+                println!("{}", serde_json::to_string_pretty(&to_sarif(&root, &reported))?);
+            }
+        }
 
         if found_error {
-            println!();
+            if format == DiagnosticsFormat::Text {
+                println!();
+            }
             anyhow::bail!("diagnostic error detected")
         }
 
         Ok(())
     }
+
+    fn is_included(&self, relative_path: Option<&str>) -> bool {
+        let matches = |globs: &[String]| {
+            relative_path.map_or(false, |path| globs.iter().any(|glob| glob_matches(glob, path)))
+        };
+        (self.include.is_empty() || matches(&self.include)) && !matches(&self.exclude)
+    }
+}
+
+/// A diagnostic in a form that doesn't need the database anymore, see [`position`].
+struct Reported {
+    path: String,
+    start: (u32, u32),
+    end: (u32, u32),
+    severity: Severity,
+    code: &'static str,
+    message: String,
+}
+
+impl Reported {
+    fn new(path: String, line_index: &LineIndex, diagnostic: Diagnostic) -> Reported {
+        Reported {
+            path,
+            start: position(line_index, diagnostic.range.start()),
+            end: position(line_index, diagnostic.range.end()),
+            severity: diagnostic.severity,
+            code: diagnostic.code.0,
+            message: diagnostic.message,
+        }
+    }
+
+    fn severity(&self) -> &'static str {
+        match self.severity {
+            Severity::Error => "error",
+            Severity::WeakWarning => "warning",
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "file": self.path,
+            "start": { "line": self.start.0, "column": self.start.1 },
+            "end": { "line": self.end.0, "column": self.end.1 },
+            "severity": self.severity(),
+            "code": self.code,
+            "message": self.message,
+        })
+    }
+}
+
+/// Renders the diagnostics as a [SARIF](https://sarifweb.azurewebsites.net/) log, which CI
+/// services like GitHub code scanning understand.
+fn to_sarif(root: &AbsPath, reported: &[Reported]) -> Value {
+    let mut rules: Vec<&str> = reported.iter().map(|it| it.code).collect();
+    rules.sort_unstable();
+    rules.dedup();
+    let rules: Vec<Value> = rules.into_iter().map(|id| json!({ "id": id })).collect();
+
+    let results: Vec<Value> = reported
+        .iter()
+        .map(|it| {
+            json!({
+                "ruleId": it.code,
+                "level": match it.severity {
+                    Severity::Error => "error",
+                    Severity::WeakWarning => "note",
+                },
+                "message": { "text": it.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": it.path, "uriBaseId": "SRCROOT" },
+                        "region": {
+                            "startLine": it.start.0,
+                            "startColumn": it.start.1,
+                            "endLine": it.end.0,
+                            "endColumn": it.end.1,
+                        },
+                    },
+                }],
+            })
+        })
+        .collect();
+
+    let root_uri = lsp_types::Url::from_directory_path(root).map(|it| it.to_string()).ok();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "rust-analyzer",
+                    "informationUri": "https://rust-analyzer.github.io/",
+                    "rules": rules,
+                },
+            },
+            "originalUriBaseIds": { "SRCROOT": { "uri": root_uri } },
+            "results": results,
+        }],
+    })
 }
 
 fn all_modules(db: &dyn HirDatabase) -> Vec<Module> {
@@ -32,6 +32,17 @@ xflags::xflags! {
 
             /// Dump a LSP config JSON schema.
             optional --print-config-schema
+            /// Check the `rust-analyzer.toml` files of the current directory and its parents for
+            /// invalid values and outdated keys.
+            optional --print-config-warnings
+            /// Print the configuration resulting from merging the `rust-analyzer.toml` files of the
+            /// current directory and its parents with the defaults.
+            optional --print-effective-config
+
+            /// Listen for clients on a tcp address instead of using stdio.
+            optional --listen addr: String
+            /// Connect to the client over a named pipe (a unix socket on unix) instead of stdio.
+            optional --pipe path: PathBuf
         }
 
         /// Parse stdin.
@@ -40,8 +51,16 @@ xflags::xflags! {
             optional --no-dump
         }
 
-        /// Parse stdin and print the list of symbols.
-        cmd symbols {}
+        /// Parse stdin and print the list of symbols, or dump the symbols of a workspace.
+        cmd symbols
+            /// Directory with Cargo.toml.
+            optional path: PathBuf
+        {
+            /// How to dump the symbols of a workspace: `json` (the default) or `csv`.
+            optional --format format: SymbolsFormat
+            /// Also dump the symbols of all dependencies.
+            optional --with-deps
+        }
 
         /// Highlight stdin as html.
         cmd highlight {
@@ -64,6 +83,11 @@ xflags::xflags! {
             optional --memory-usage
             /// Print the total length of all source and macro files (whitespace is not counted).
             optional --source-stats
+            /// Print the time each crate spends parsing, resolving names (and expanding macros),
+            /// lowering bodies and inferring types, and the memory used by each query group.
+            optional --crate-stats
+            /// Print the `n` function bodies that took the longest to lower and type check.
+            optional --slowest n: usize
 
             /// Only analyze items matching this path.
             optional -o, --only path: String
@@ -78,6 +102,30 @@ xflags::xflags! {
             optional --disable-proc-macros
             /// Only resolve names, don't run type inference.
             optional --skip-inference
+            /// Give up on trait goals that take longer than this many milliseconds.
+            optional --trait-solver-timeout ms: u64
+        }
+
+        /// Run a single IDE feature repeatedly and print how long it takes.
+        cmd analysis-bench
+            /// Directory with Cargo.toml.
+            required path: PathBuf
+        {
+            /// Compute syntax highlighting for this file.
+            optional --highlight path: PathBuf
+            /// Compute completions at `path:line:column` (the line is 1-based, the column 0-based).
+            optional --complete location: Position
+            /// Compute hover at `path:line:column` (the line is 1-based, the column 0-based).
+            optional --hover location: Position
+            /// How often to run the feature again after the first run (10 times by default).
+            optional --iterations n: usize
+            /// Collect memory usage statistics.
+            optional --memory-usage
+
+            /// Don't run build scripts or load `OUT_DIR` values by running `cargo check` before analysis.
+            optional --disable-build-scripts
+            /// Don't use expand proc macros.
+            optional --disable-proc-macros
         }
 
         cmd diagnostics
@@ -88,12 +136,23 @@ xflags::xflags! {
             optional --disable-build-scripts
             /// Don't use expand proc macros.
             optional --disable-proc-macros
+            /// How to print the diagnostics: `text` (the default), `json` or `sarif`.
+            optional --format format: DiagnosticsFormat
+            /// Only report diagnostics at least this severe: `warning` (the default) or `error`.
+            optional --severity severity: DiagnosticsSeverity
+            /// Only check the files matching this glob, relative to `path`.
+            repeated --include glob: String
+            /// Don't check the files matching this glob, relative to `path`.
+            repeated --exclude glob: String
         }
 
         cmd ssr
             /// A structured search replace rule (`$a.foo($b) ==> bar($a, $b)`)
             repeated rule: SsrRule
-        {}
+        {
+            /// Print the changes as a unified diff instead of writing them to disk.
+            optional --dry-run
+        }
 
         cmd search
             /// A structured search replace pattern (`$a.foo($b)`)
@@ -108,6 +167,13 @@ xflags::xflags! {
         cmd lsif
             required path: PathBuf
         {}
+
+        cmd scip
+            required path: PathBuf
+        {
+            /// The file to write the index to, `index.scip` by default.
+            optional --output path: PathBuf
+        }
     }
 }
 
@@ -131,11 +197,13 @@ pub enum RustAnalyzerCmd {
     Symbols(Symbols),
     Highlight(Highlight),
     AnalysisStats(AnalysisStats),
+    AnalysisBench(AnalysisBench),
     Diagnostics(Diagnostics),
     Ssr(Ssr),
     Search(Search),
     ProcMacro(ProcMacro),
     Lsif(Lsif),
+    Scip(Scip),
 }
 
 #[derive(Debug)]
@@ -143,6 +211,10 @@ pub struct LspServer {
     pub version: bool,
     pub help: bool,
     pub print_config_schema: bool,
+    pub print_config_warnings: bool,
+    pub print_effective_config: bool,
+    pub listen: Option<String>,
+    pub pipe: Option<PathBuf>,
 }
 
 #[derive(Debug)]
@@ -151,7 +223,12 @@ pub struct Parse {
 }
 
 #[derive(Debug)]
-pub struct Symbols;
+pub struct Symbols {
+    pub path: Option<PathBuf>,
+
+    pub format: Option<SymbolsFormat>,
+    pub with_deps: bool,
+}
 
 #[derive(Debug)]
 pub struct Highlight {
@@ -167,12 +244,28 @@ pub struct AnalysisStats {
     pub parallel: bool,
     pub memory_usage: bool,
     pub source_stats: bool,
+    pub crate_stats: bool,
+    pub slowest: Option<usize>,
     pub only: Option<String>,
     pub with_deps: bool,
     pub no_sysroot: bool,
     pub disable_build_scripts: bool,
     pub disable_proc_macros: bool,
     pub skip_inference: bool,
+    pub trait_solver_timeout: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct AnalysisBench {
+    pub path: PathBuf,
+
+    pub highlight: Option<PathBuf>,
+    pub complete: Option<Position>,
+    pub hover: Option<Position>,
+    pub iterations: Option<usize>,
+    pub memory_usage: bool,
+    pub disable_build_scripts: bool,
+    pub disable_proc_macros: bool,
 }
 
 #[derive(Debug)]
@@ -181,11 +274,17 @@ pub struct Diagnostics {
 
     pub disable_build_scripts: bool,
     pub disable_proc_macros: bool,
+    pub format: Option<DiagnosticsFormat>,
+    pub severity: Option<DiagnosticsSeverity>,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
 }
 
 #[derive(Debug)]
 pub struct Ssr {
     pub rule: Vec<SsrRule>,
+
+    pub dry_run: bool,
 }
 
 #[derive(Debug)]
@@ -203,6 +302,13 @@ pub struct Lsif {
     pub path: PathBuf,
 }
 
+#[derive(Debug)]
+pub struct Scip {
+    pub path: PathBuf,
+
+    pub output: Option<PathBuf>,
+}
+
 impl RustAnalyzer {
     pub const HELP: &'static str = Self::HELP_;
 
@@ -246,3 +352,85 @@ impl FromStr for OutputFormat {
         }
     }
 }
+
+/// A location in a file, given as `path:line:column`.
+#[derive(Debug)]
+pub struct Position {
+    pub path: PathBuf,
+    /// 1-based
+    pub line: u32,
+    /// 0-based
+    pub column: u32,
+}
+
+impl FromStr for Position {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected `path:line:column`, got `{}`", s);
+        let mut parts = s.rsplitn(3, ':');
+        let column = parts.next().and_then(|it| it.parse().ok()).ok_or_else(invalid)?;
+        let line = parts.next().and_then(|it| it.parse().ok()).ok_or_else(invalid)?;
+        let path = parts.next().filter(|it| !it.is_empty()).ok_or_else(invalid)?;
+        if line == 0 {
+            return Err(format!("lines are 1-based, got line 0 in `{}`", s));
+        }
+        Ok(Position { path: path.into(), line, column })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticsFormat {
+    Text,
+    Json,
+    Sarif,
+}
+
+impl FromStr for DiagnosticsFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "sarif" => Ok(Self::Sarif),
+            _ => Err(format!("unknown diagnostics format `{}`", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticsSeverity {
+    Warning,
+    Error,
+}
+
+impl FromStr for DiagnosticsSeverity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "warning" => Ok(Self::Warning),
+            "error" => Ok(Self::Error),
+            _ => Err(format!("unknown severity `{}`", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolsFormat {
+    Json,
+    Csv,
+}
+
+impl FromStr for SymbolsFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            _ => Err(format!("unknown symbols format `{}`", s)),
+        }
+    }
+}
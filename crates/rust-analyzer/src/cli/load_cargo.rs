@@ -1,6 +1,10 @@
 //! Loads a Cargo project into a static instance of analysis, without support
 //! for incorporating changes.
-use std::{path::Path, sync::Arc};
+use std::{
+    path::Path,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
 
 use anyhow::Result;
 use crossbeam_channel::{unbounded, Receiver};
@@ -13,6 +17,10 @@ use vfs::{loader::Handle, AbsPath, AbsPathBuf};
 
 use crate::reload::{load_proc_macro, ProjectFolders, SourceRootConfig};
 
+/// Batch analyses don't have a user waiting on them, so expansions get more time than the
+/// `procMacro.expansionTimeout` default.
+const PROC_MACRO_EXPANSION_TIMEOUT: Duration = Duration::from_secs(60);
+
 // Note: Since this type is used by external tools that use rust-analyzer as a library
 // what otherwise would be `pub(crate)` has to be `pub` here instead.
 pub struct LoadCargoConfig {
@@ -60,13 +68,18 @@ pub fn load_workspace(
 
     let proc_macro_client = if load_config.with_proc_macro {
         let path = AbsPathBuf::assert(std::env::current_exe()?);
-        Some(ProcMacroServer::spawn(path, &["proc-macro"]).unwrap())
+        Some(ProcMacroServer::spawn(path, &["proc-macro"], PROC_MACRO_EXPANSION_TIMEOUT).unwrap())
     } else {
         None
     };
 
     let crate_graph = ws.to_crate_graph(
-        &mut |_, path: &AbsPath| load_proc_macro(proc_macro_client.as_ref(), path, &[]),
+        &mut |_, path: &AbsPath| {
+            load_proc_macro(proc_macro_client.as_ref(), path, &[]).unwrap_or_else(|err| {
+                tracing::warn!("{}", err);
+                Vec::new()
+            })
+        },
         &mut |path: &AbsPath| {
             let contents = loader.load_sync(path);
             let path = vfs::VfsPath::from(path.to_path_buf());
@@ -75,7 +88,7 @@ pub fn load_workspace(
         },
     );
 
-    let project_folders = ProjectFolders::new(&[ws], &[]);
+    let project_folders = ProjectFolders::new(&[ws], &[], &[]);
     loader.set_config(vfs::loader::Config {
         load: project_folders.load,
         watch: vec![],
@@ -87,7 +100,7 @@ pub fn load_workspace(
         load_crate_graph(crate_graph, project_folders.source_root_config, &mut vfs, &receiver);
 
     if load_config.prefill_caches {
-        host.analysis().parallel_prime_caches(1, |_| {})?;
+        host.analysis().parallel_prime_caches(1, None, &AtomicBool::new(false), |_| {})?;
     }
     Ok((host, vfs, proc_macro_client))
 }
@@ -0,0 +1,248 @@
+//! SCIP generator
+
+use std::{collections::HashMap, env, path::PathBuf, time::Instant};
+
+use ide::{
+    LineCol, MonikerDescriptorKind, MonikerResult, StaticIndex, StaticIndexedFile, TextRange,
+    TokenId,
+};
+use ide_db::LineIndexDatabase;
+use project_model::{CargoConfig, ProjectManifest, ProjectWorkspace};
+use scip::types as scip_types;
+use vfs::{AbsPath, AbsPathBuf, Vfs};
+
+use crate::cli::{
+    flags,
+    load_cargo::{load_workspace, LoadCargoConfig},
+    Result,
+};
+
+impl flags::Scip {
+    pub fn run(self) -> Result<()> {
+        eprintln!("Generating SCIP started...");
+        let now = Instant::now();
+        let cargo_config = CargoConfig::default();
+        let no_progress = &|_| ();
+        let load_cargo_config = LoadCargoConfig {
+            load_out_dirs_from_check: true,
+            with_proc_macro: true,
+            prefill_caches: true,
+        };
+        let path = AbsPathBuf::assert(env::current_dir()?.join(&self.path));
+        let root = path.normalize();
+        let manifest = ProjectManifest::discover_single(&path)?;
+
+        let workspace = ProjectWorkspace::load(manifest, &cargo_config, no_progress)?;
+
+        let (host, vfs, _proc_macro) = load_workspace(workspace, &load_cargo_config)?;
+        let db = host.raw_database();
+        let analysis = host.analysis();
+
+        let si = StaticIndex::compute(&analysis);
+
+        let project_root = lsp_types::Url::from_directory_path(&root)
+            .map_err(|()| anyhow::format_err!("invalid project root {}", root.display()))?;
+        let mut index = scip_types::Index {
+            metadata: Some(scip_types::Metadata {
+                version: scip_types::ProtocolVersion::UnspecifiedProtocolVersion.into(),
+                tool_info: Some(scip_types::ToolInfo {
+                    name: "rust-analyzer".to_string(),
+                    version: env!("REV").to_string(),
+                    arguments: vec![],
+                    ..Default::default()
+                })
+                .into(),
+                project_root: project_root.to_string(),
+                text_document_encoding: scip_types::TextEncoding::UTF8.into(),
+                ..Default::default()
+            })
+            .into(),
+            ..Default::default()
+        };
+
+        let mut tokens_to_symbol: HashMap<TokenId, String> = HashMap::new();
+
+        for StaticIndexedFile { file_id, tokens, .. } in si.files {
+            // Dependencies outside of the project are not part of the index.
+            let relative_path = match relative_path(&vfs, &root, file_id) {
+                Some(it) => it,
+                None => continue,
+            };
+            let line_index = db.line_index(file_id);
+            let mut doc = scip_types::Document {
+                relative_path,
+                language: "rust".to_string(),
+                ..Default::default()
+            };
+
+            // Local symbols are only unique within their document.
+            let mut local_count = 0;
+            let mut local_symbols: HashMap<TokenId, String> = HashMap::new();
+
+            for (range, id) in tokens {
+                let token = si.tokens.get(id).unwrap();
+
+                let symbol = match token.moniker.as_ref() {
+                    Some(moniker) => tokens_to_symbol
+                        .entry(id)
+                        .or_insert_with(|| scip::symbol::format_symbol(moniker_to_symbol(moniker)))
+                        .clone(),
+                    None => local_symbols
+                        .entry(id)
+                        .or_insert_with(|| {
+                            local_count += 1;
+                            scip::symbol::format_symbol(scip_types::Symbol::new_local(local_count))
+                        })
+                        .clone(),
+                };
+
+                let mut occurrence = scip_types::Occurrence {
+                    range: text_range_to_scip_range(&line_index, range),
+                    symbol: symbol.clone(),
+                    ..Default::default()
+                };
+
+                // Symbols are described once, in the document defining them.
+                let is_definition = token
+                    .definition
+                    .map_or(false, |def| def.file_id == file_id && def.range == range);
+                if is_definition {
+                    occurrence.symbol_roles |= scip_types::SymbolRole::Definition as i32;
+                    let documentation = token
+                        .hover
+                        .as_ref()
+                        .map(|hover| hover.markup.as_str().to_string())
+                        .filter(|it| !it.is_empty())
+                        .into_iter()
+                        .collect();
+                    doc.symbols.push(scip_types::SymbolInformation {
+                        symbol,
+                        documentation,
+                        ..Default::default()
+                    });
+                }
+
+                doc.occurrences.push(occurrence);
+            }
+
+            if doc.occurrences.is_empty() {
+                continue;
+            }
+            index.documents.push(doc);
+        }
+
+        let output = self.output.unwrap_or_else(|| PathBuf::from("index.scip"));
+        scip::write_message_to_file(&output, index)
+            .map_err(|err| anyhow::format_err!("failed to write {}: {}", output.display(), err))?;
+
+        eprintln!("Generating SCIP finished in {:?}", now.elapsed());
+        Ok(())
+    }
+}
+
+fn relative_path(vfs: &Vfs, root: &AbsPath, file_id: ide::FileId) -> Option<String> {
+    let path = vfs.file_path(file_id);
+    let relative = path.as_path()?.strip_prefix(root)?;
+    Some(relative.as_ref().to_str()?.replace('\\', "/"))
+}
+
+/// SCIP encodes a range on a single line as `[line, start_col, end_col]`, and any other range as
+/// `[start_line, start_col, end_line, end_col]`.
+fn text_range_to_scip_range(line_index: &ide::LineIndex, range: TextRange) -> Vec<i32> {
+    let LineCol { line: start_line, col: start_col } = line_index.line_col(range.start());
+    let LineCol { line: end_line, col: end_col } = line_index.line_col(range.end());
+
+    if start_line == end_line {
+        vec![start_line as i32, start_col as i32, end_col as i32]
+    } else {
+        vec![start_line as i32, start_col as i32, end_line as i32, end_col as i32]
+    }
+}
+
+fn moniker_to_symbol(moniker: &MonikerResult) -> scip_types::Symbol {
+    use scip_types::descriptor::Suffix;
+
+    let package = &moniker.package_information;
+    let descriptors = moniker
+        .identifier
+        .description
+        .iter()
+        .map(|desc| scip_types::Descriptor {
+            name: desc.name.to_string(),
+            suffix: match desc.desc {
+                MonikerDescriptorKind::Namespace => Suffix::Namespace,
+                MonikerDescriptorKind::Type => Suffix::Type,
+                MonikerDescriptorKind::Term => Suffix::Term,
+                MonikerDescriptorKind::Method => Suffix::Method,
+                MonikerDescriptorKind::TypeParameter => Suffix::TypeParameter,
+                MonikerDescriptorKind::Macro => Suffix::Macro,
+            }
+            .into(),
+            ..Default::default()
+        })
+        .collect();
+
+    scip_types::Symbol {
+        scheme: "rust-analyzer".to_string(),
+        package: Some(scip_types::Package {
+            manager: "cargo".to_string(),
+            name: package.name.clone(),
+            version: package.version.clone(),
+            ..Default::default()
+        })
+        .into(),
+        descriptors,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ide::{AnalysisHost, FilePosition};
+    use ide_db::base_db::fixture::ChangeFixture;
+
+    use super::*;
+
+    #[track_caller]
+    fn check_symbol(ra_fixture: &str, expected: &str) {
+        let ChangeFixture { file_position, change, .. } = ChangeFixture::parse(ra_fixture);
+        let mut host = AnalysisHost::default();
+        host.apply_change(change);
+        let (file_id, range_or_offset) = file_position.expect("expected a marker ($0)");
+        let position = FilePosition { file_id, offset: range_or_offset.expect_offset() };
+        let monikers = host.analysis().moniker(position).unwrap().expect("no moniker found").info;
+        assert_eq!(monikers.len(), 1);
+        assert_eq!(scip::symbol::format_symbol(moniker_to_symbol(&monikers[0])), expected);
+    }
+
+    #[test]
+    fn symbol_kinds() {
+        check_symbol(
+            r#"
+//- /lib.rs crate:main@CratesIo:0.1.0,https://a.b/foo.git
+pub mod module {
+    pub fn func$0() {}
+}
+"#,
+            "rust-analyzer cargo main 0.1.0 module/func().",
+        );
+        check_symbol(
+            r#"
+//- /lib.rs crate:main@CratesIo:0.1.0,https://a.b/foo.git
+pub mod module {
+    pub type Alias$0 = u32;
+}
+"#,
+            "rust-analyzer cargo main 0.1.0 module/Alias#",
+        );
+        check_symbol(
+            r#"
+//- /lib.rs crate:main@CratesIo:0.1.0,https://a.b/foo.git
+pub mod module {
+    pub struct Foo<T$0>(T);
+}
+"#,
+            "rust-analyzer cargo main 0.1.0 module/Foo#[T]",
+        );
+    }
+}
@@ -1,5 +1,8 @@
 //! Applies structured search replace rules from the command line.
 
+use std::{env, iter, path::Path};
+
+use ide::{Indel, TextEdit};
 use ide_ssr::MatchFinder;
 use project_model::CargoConfig;
 
@@ -18,25 +21,41 @@ impl flags::Ssr {
             with_proc_macro: true,
             prefill_caches: false,
         };
-        let (host, vfs, _proc_macro) = load_workspace_at(
-            &std::env::current_dir()?,
-            &cargo_config,
-            &load_cargo_config,
-            &|_| {},
-        )?;
+        let current_dir = env::current_dir()?;
+        let (host, vfs, _proc_macro) =
+            load_workspace_at(&current_dir, &cargo_config, &load_cargo_config, &|_| {})?;
         let db = host.raw_database();
         let mut match_finder = MatchFinder::at_first_file(db)?;
         for rule in self.rule {
             match_finder.add_rule(rule)?;
         }
-        let edits = match_finder.edits();
-        for (file_id, edit) in edits {
-            if let Some(path) = vfs.file_path(file_id).as_path() {
-                let mut contents = db.file_text(file_id).to_string();
+        let mut edits: Vec<_> = match_finder
+            .edits()
+            .into_iter()
+            .filter_map(|(file_id, edit)| {
+                let path = vfs.file_path(file_id).as_path()?.to_path_buf();
+                Some((path, file_id, edit))
+            })
+            .collect();
+        edits.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+
+        for (path, file_id, edit) in &edits {
+            let text = db.file_text(*file_id);
+            if self.dry_run {
+                let path: &Path = path.as_ref();
+                let path = path.strip_prefix(&current_dir).unwrap_or(path);
+                print_diff(&path.display().to_string(), &text, edit);
+            } else {
+                let mut contents = text.to_string();
                 edit.apply(&mut contents);
                 std::fs::write(path, contents)?;
             }
         }
+        eprintln!(
+            "{} {} file(s)",
+            if self.dry_run { "Would change" } else { "Changed" },
+            edits.len()
+        );
         Ok(())
     }
 }
@@ -84,3 +103,69 @@ impl flags::Search {
         Ok(())
     }
 }
+
+/// Prints the changes `edit` makes to `text` as a unified diff with three lines of context.
+fn print_diff(path: &str, text: &str, edit: &TextEdit) {
+    const CONTEXT: usize = 3;
+
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let line_starts: Vec<usize> =
+        iter::once(0).chain(text.match_indices('\n').map(|(idx, _)| idx + 1)).collect();
+    let line_of = |offset: usize| {
+        let line = line_starts.partition_point(|&start| start <= offset) - 1;
+        line.min(lines.len().saturating_sub(1))
+    };
+    let line_end = |line: usize| line_starts.get(line + 1).copied().unwrap_or(text.len());
+
+    // Indels whose contexts would overlap end up in the same hunk.
+    let mut hunks: Vec<(usize, usize, Vec<&Indel>)> = Vec::new();
+    for indel in edit.iter() {
+        let start = line_of(indel.delete.start().into());
+        let end = line_of(indel.delete.end().into());
+        match hunks.last_mut() {
+            Some((_, hunk_end, indels)) if start <= *hunk_end + 2 * CONTEXT => {
+                *hunk_end = end.max(*hunk_end);
+                indels.push(indel);
+            }
+            _ => hunks.push((start, end, vec![indel])),
+        }
+    }
+
+    println!("--- a/{}", path);
+    println!("+++ b/{}", path);
+    let mut line_delta = 0isize;
+    for (start, end, indels) in hunks {
+        let before_start = start.saturating_sub(CONTEXT);
+        let after_end = (end + CONTEXT + 1).min(lines.len());
+
+        let changed_start = line_starts[start];
+        let mut changed = text[changed_start..line_end(end)].to_string();
+        for indel in indels.iter().rev() {
+            let delete_start = usize::from(indel.delete.start()) - changed_start;
+            let delete_end = usize::from(indel.delete.end()) - changed_start;
+            changed.replace_range(delete_start..delete_end, &indel.insert);
+        }
+        let removed = &lines[start..=end];
+        let added: Vec<&str> = changed.split_inclusive('\n').collect();
+
+        let old_len = after_end - before_start;
+        let new_len = old_len - removed.len() + added.len();
+        println!(
+            "@@ -{},{} +{},{} @@",
+            before_start + 1,
+            old_len,
+            (before_start as isize + line_delta) + 1,
+            new_len
+        );
+        line_delta += added.len() as isize - removed.len() as isize;
+
+        let print_line = |prefix: char, line: &str| match line.strip_suffix('\n') {
+            Some(line) => println!("{}{}", prefix, line),
+            None => println!("{}{}\n\\ No newline at end of file", prefix, line),
+        };
+        lines[before_start..start].iter().for_each(|line| print_line(' ', line));
+        removed.iter().for_each(|line| print_line('-', line));
+        added.iter().for_each(|line| print_line('+', line));
+        lines[end + 1..after_end].iter().for_each(|line| print_line(' ', line));
+    }
+}
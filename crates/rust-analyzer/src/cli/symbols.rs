@@ -1,10 +1,27 @@
-//! Read Rust code on stdin, print syntax tree on stdout.
+//! Read Rust code on stdin, print syntax tree on stdout, or dump the symbols of a workspace.
+
+use std::{borrow::Cow, env, path::Path};
+
+use hir::{
+    symbols::{FileSymbol, FileSymbolKind, SymbolCollector},
+    Crate, Module,
+};
 use ide::Analysis;
+use ide_db::{base_db::SourceDatabaseExt, LineIndexDatabase, RootDatabase};
+use serde_json::{json, Value};
+use vfs::{AbsPath, AbsPathBuf, Vfs};
 
-use crate::cli::{flags, read_stdin};
+use crate::cli::{
+    flags::{self, SymbolsFormat},
+    load_cargo::{load_workspace_at, LoadCargoConfig},
+    position, read_stdin, relative_path,
+};
 
 impl flags::Symbols {
     pub fn run(self) -> anyhow::Result<()> {
+        if let Some(path) = &self.path {
+            return self.dump_workspace(path);
+        }
         let text = read_stdin()?;
         let (analysis, file_id) = Analysis::from_single_file(text);
         let structure = analysis.file_structure(file_id).unwrap();
@@ -13,4 +30,159 @@ impl flags::Symbols {
         }
         Ok(())
     }
+
+    fn dump_workspace(&self, path: &Path) -> anyhow::Result<()> {
+        let cargo_config = Default::default();
+        let load_cargo_config = LoadCargoConfig {
+            load_out_dirs_from_check: true,
+            with_proc_macro: true,
+            prefill_caches: false,
+        };
+        let root = AbsPathBuf::assert(env::current_dir()?.join(path)).normalize();
+        let (host, vfs, _proc_macro) =
+            load_workspace_at(path, &cargo_config, &load_cargo_config, &|_| {})?;
+        let db = host.raw_database();
+
+        let mut symbols = Vec::new();
+        for krate in Crate::all(db) {
+            let source_root = db.source_root(db.file_source_root(krate.root_file(db)));
+            if source_root.is_library && !self.with_deps {
+                continue;
+            }
+            for module in krate.modules(db) {
+                let module_path = module_path(db, module);
+                for symbol in SymbolCollector::collect(db, module) {
+                    // Aliases would list the same item once more under a different name.
+                    if symbol.is_alias {
+                        continue;
+                    }
+                    if let Some(symbol) = Dumped::new(db, &vfs, &root, &module_path, symbol) {
+                        symbols.push(symbol);
+                    }
+                }
+            }
+        }
+        symbols.sort_by(|a, b| (&a.file, a.start).cmp(&(&b.file, b.start)));
+
+        match self.format.unwrap_or(SymbolsFormat::Json) {
+            SymbolsFormat::Json => {
+                let symbols: Vec<Value> = symbols.iter().map(Dumped::to_json).collect();
+                println!("{}", serde_json::to_string_pretty(&symbols)?);
+            }
+            SymbolsFormat::Csv => {
+                println!(
+                    "name,kind,path,container,file,start_line,start_column,end_line,end_column"
+                );
+                for symbol in &symbols {
+                    println!("{}", symbol.to_csv());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A symbol in a form that doesn't need the database anymore, see [`position`].
+struct Dumped {
+    name: String,
+    kind: &'static str,
+    path: String,
+    container: Option<String>,
+    file: String,
+    start: (u32, u32),
+    end: (u32, u32),
+}
+
+impl Dumped {
+    fn new(
+        db: &RootDatabase,
+        vfs: &Vfs,
+        root: &AbsPath,
+        module_path: &str,
+        symbol: FileSymbol,
+    ) -> Option<Dumped> {
+        let range = symbol.loc.original_range(db)?;
+        let file = vfs.file_path(range.file_id);
+        let file = file
+            .as_path()
+            .and_then(|path| relative_path(root, path))
+            .unwrap_or_else(|| file.to_string());
+        let line_index = db.line_index(range.file_id);
+
+        let mut path = module_path.to_string();
+        for segment in symbol.container_name.iter().chain(Some(&symbol.name)) {
+            path.push_str("::");
+            path.push_str(segment);
+        }
+        Some(Dumped {
+            name: symbol.name.to_string(),
+            kind: kind_name(symbol.kind),
+            path,
+            container: symbol.container_name.map(|it| it.to_string()),
+            file,
+            start: position(&line_index, range.range.start()),
+            end: position(&line_index, range.range.end()),
+        })
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "kind": self.kind,
+            "path": self.path,
+            "container": self.container,
+            "file": self.file,
+            "start": { "line": self.start.0, "column": self.start.1 },
+            "end": { "line": self.end.0, "column": self.end.1 },
+        })
+    }
+
+    fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{}",
+            csv_field(&self.name),
+            self.kind,
+            csv_field(&self.path),
+            csv_field(self.container.as_deref().unwrap_or_default()),
+            csv_field(&self.file),
+            self.start.0,
+            self.start.1,
+            self.end.0,
+            self.end.1,
+        )
+    }
+}
+
+fn module_path(db: &RootDatabase, module: Module) -> String {
+    let crate_name = module.krate().display_name(db);
+    let crate_name = crate_name.as_deref().unwrap_or("unknown").to_string();
+    let mut segments = module.path_to_root(db);
+    segments.reverse();
+    std::iter::once(crate_name)
+        .chain(segments.into_iter().filter_map(|it| it.name(db)).map(|it| it.to_string()))
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+fn kind_name(kind: FileSymbolKind) -> &'static str {
+    match kind {
+        FileSymbolKind::Const => "const",
+        FileSymbolKind::Enum => "enum",
+        FileSymbolKind::Function => "function",
+        FileSymbolKind::Macro => "macro",
+        FileSymbolKind::Module => "module",
+        FileSymbolKind::Static => "static",
+        FileSymbolKind::Struct => "struct",
+        FileSymbolKind::Trait => "trait",
+        FileSymbolKind::TypeAlias => "type_alias",
+        FileSymbolKind::Union => "union",
+    }
+}
+
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains(|c| matches!(c, ',' | '"' | '\n')) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
 }
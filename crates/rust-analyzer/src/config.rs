@@ -7,32 +7,34 @@
 //! configure the server itself, feature flags are passed into analysis, and
 //! tweak things like automatic insertion of `()` in completions.
 
-use std::{ffi::OsString, fmt, iter, path::PathBuf};
+use std::{ffi::OsString, fmt, iter, path::PathBuf, sync::Arc, time::Duration};
 
-use flycheck::FlycheckConfig;
+use flycheck::{ClippyConfig, FlycheckConfig, JsonPointers, OutputFormat};
+use hir::TraitSolverConfig;
 use ide::{
-    AssistConfig, CallableSnippets, CompletionConfig, DiagnosticsConfig, ExprFillDefaultMode,
-    HighlightRelatedConfig, HoverConfig, HoverDocFormat, InlayHintsConfig, JoinLinesConfig,
-    Snippet, SnippetScope,
+    AssistConfig, CallableSnippets, CompletionConfig, CompletionFieldsToResolve, DiagnosticsConfig,
+    ExprFillDefaultMode, ExternalDocsConfig, HighlightRelatedConfig, HoverConfig, HoverDocFormat,
+    InlayFieldsToResolve, InlayHintsConfig, JoinLinesConfig, Snippet, SnippetScope,
 };
 use ide_db::{
     imports::insert_use::{ImportGranularity, InsertUseConfig, PrefixKind},
     SnippetCap,
 };
 use itertools::Itertools;
-use lsp_types::{ClientCapabilities, MarkupKind};
+use lsp_types::{ClientCapabilities, MarkupKind, Url};
 use project_model::{
-    CargoConfig, ProjectJson, ProjectJsonData, ProjectManifest, RustcSource, UnsetTestCrates,
+    CargoConfig, CrateTarget, ProjectJson, ProjectJsonData, ProjectManifest, RustcSource,
+    UnsetTestCrates,
 };
 use rustc_hash::{FxHashMap, FxHashSet};
 use serde::{de::DeserializeOwned, Deserialize};
-use vfs::AbsPathBuf;
+use vfs::{loader::Glob, AbsPath, AbsPathBuf};
 
 use crate::{
-    caps::completion_item_edit_resolve,
+    caps::{completion_item_edit_resolve, completion_item_resolve},
     diagnostics::DiagnosticsMapConfig,
-    line_index::OffsetEncoding,
-    lsp_ext::{self, supports_utf8, WorkspaceSymbolSearchKind, WorkspaceSymbolSearchScope},
+    line_index::PositionEncoding,
+    lsp_ext::{self, negotiated_encoding, WorkspaceSymbolSearchKind, WorkspaceSymbolSearchScope},
 };
 
 mod patch_old_style;
@@ -83,6 +85,19 @@ config_data! {
         /// Use `RUSTC_WRAPPER=rust-analyzer` when running build scripts to
         /// avoid compiling unnecessary things.
         cargo_buildScripts_useRustcWrapper: bool = "true",
+        /// Extra cfg atoms to enable for the crates of the project, written like `--cfg`
+        /// arguments (`foo` or `key="value"`). Set this in `overrides` to enable them only for
+        /// the crates whose root module matches the glob.
+        cargo_cfgs: Vec<String> = "[]",
+        /// Per-crate compilation target overrides, keyed by package name.
+        /// Each entry may set a `target` triple and a list of extra `cfgs`
+        /// to enable for that crate only.
+        cargo_crateTargets: FxHashMap<String, CrateTargetDef> = "{}",
+        /// Further targets to analyze the workspace for, next to `#rust-analyzer.cargo.target#`,
+        /// e.g. `["wasm32-unknown-unknown"]`. Code of the workspace members behind the `cfg`s of
+        /// these targets is analyzed instead of being inactive, and the target a file is analyzed
+        /// for can be switched with the **Rust Analyzer: Switch Target** command.
+        cargo_extraTargets: Vec<String> = "[]",
         /// List of features to activate.
         ///
         /// Set this to `"all"` to pass `--all-features` to cargo.
@@ -98,6 +113,22 @@ config_data! {
 
         /// Check all targets and tests (`--all-targets`).
         checkOnSave_allTargets: bool                     = "true",
+        /// Clippy lints to allow in the clippy pass.
+        checkOnSave_clippy_allow: Vec<String>            = "[]",
+        /// Clippy lints to deny in the clippy pass.
+        checkOnSave_clippy_deny: Vec<String>             = "[]",
+        /// Run `cargo clippy` after `cargo check`, reporting its lints
+        /// alongside the check diagnostics. Ignored if
+        /// `#rust-analyzer.checkOnSave.command#` is already `clippy`.
+        checkOnSave_clippy_enable: bool                  = "false",
+        /// Clippy lints to warn about in the clippy pass.
+        checkOnSave_clippy_warn: Vec<String>             = "[]",
+        /// List of clippy lints that should be displayed with hint severity,
+        /// in addition to `#rust-analyzer.diagnostics.warningsAsHint#`.
+        checkOnSave_clippy_warningsAsHint: Vec<String>   = "[]",
+        /// List of clippy lints that should be displayed with info severity,
+        /// in addition to `#rust-analyzer.diagnostics.warningsAsInfo#`.
+        checkOnSave_clippy_warningsAsInfo: Vec<String>   = "[]",
         /// Cargo command to use for `cargo check`.
         checkOnSave_command: String                      = "\"check\"",
         /// Run specified `cargo check` command for diagnostics on save.
@@ -112,6 +143,16 @@ config_data! {
         /// Whether to pass `--no-default-features` to cargo. Defaults to
         /// `#rust-analyzer.cargo.noDefaultFeatures#`.
         checkOnSave_noDefaultFeatures: Option<bool>      = "null",
+        /// How diagnostics are read from the output of
+        /// `#rust-analyzer.checkOnSave.overrideCommand#`: `"json"` for cargo or
+        /// rustc JSON messages on separate lines, `"embedded_json"` for rustc JSON
+        /// messages mixed with other output, `{ "pattern": "<regex>" }` for lines
+        /// matching a regex with the named groups `file`, `line` and `message` (and
+        /// optionally `column`, `end_line`, `end_column`, `severity` and `code`), or
+        /// `{ "jsonPointers": { "file": "/path", "line": "/line", "message": "/msg" } }`
+        /// for JSON lines of another tool, which also accepts `column`, `endLine`,
+        /// `endColumn`, `severity` and `code` pointers.
+        checkOnSave_outputFormat: OutputFormatDef        = "\"json\"",
         /// Override the command rust-analyzer uses to  run build scripts and
         /// build procedural macros. The command is required to output json
         /// and should therefor include `--message-format=json` or a similar
@@ -191,6 +232,12 @@ config_data! {
         /// Whether to show experimental rust-analyzer diagnostics that might
         /// have more false positives than usual.
         diagnostics_experimental_enable: bool    = "false",
+        /// Whether to show diagnostics for uses of `#[unstable]` library items whose feature
+        /// the crate doesn't enable.
+        diagnostics_unstableFeatures_enable: bool  = "true",
+        /// Whether to let the client pull diagnostics with `textDocument/diagnostic` instead of
+        /// publishing them. Requires a client supporting LSP 3.17.
+        diagnostics_pull_enable: bool            = "false",
         /// Map of prefixes to be substituted when parsing diagnostic file paths.
         /// This should be the reverse mapping of what is passed to `rustc` as `--remap-path-prefix`.
         diagnostics_remapPrefix: FxHashMap<String, String> = "{}",
@@ -205,10 +252,23 @@ config_data! {
         /// and a blue icon in the `Problems Panel`.
         diagnostics_warningsAsInfo: Vec<String> = "[]",
 
+        /// Base URL of the documentation of dependencies without an `html_root_url`, used instead
+        /// of `https://docs.rs/` when opening external documentation. Useful for crates from internal
+        /// registries that host their documentation elsewhere.
+        externalDocs_baseUrl: Option<String> = "null",
+        /// Whether opening external documentation of workspace crates should open the documentation
+        /// generated by `cargo doc` in the target directory instead of docs.rs.
+        externalDocs_local_enable: bool = "false",
+
         /// These directories will be ignored by rust-analyzer. They are
-        /// relative to the workspace root, and globs are not supported. You may
-        /// also need to add the folders to Code's `files.watcherExclude`.
+        /// relative to the workspace root, and may be glob patterns like
+        /// `**/generated` or `src/**/*.pb.rs`. You may also need to add the
+        /// folders to Code's `files.watcherExclude`.
         files_excludeDirs: Vec<PathBuf> = "[]",
+        /// Generated files, marked with `@generated` or "automatically generated"
+        /// in their first lines, that are larger than this many bytes are excluded
+        /// from the analysis. Disabled by default.
+        files_generatedFileSizeLimit: Option<usize> = "null",
         /// Controls file watching implementation.
         files_watcher: String = "\"client\"",
 
@@ -218,6 +278,8 @@ config_data! {
         highlightRelated_exitPoints_enable: bool = "true",
         /// Enables highlighting of related references while the cursor is on any identifier.
         highlightRelated_references_enable: bool = "true",
+        /// Enables highlighting of all operations requiring `unsafe` while the cursor is on the `unsafe` keyword of a block or function.
+        highlightRelated_unsafeOperations_enable: bool = "true",
         /// Enables highlighting of all break points for a loop or block context while the cursor is on any `async` or `await` keywords.
         highlightRelated_yieldPoints_enable: bool = "true",
 
@@ -266,6 +328,15 @@ config_data! {
         inlayHints_closingBraceHints_minLines: usize               = "25",
         /// Whether to show inlay type hints for return types of closures with blocks.
         inlayHints_closureReturnTypeHints_enable: bool             = "false",
+        /// Whether to show inlay hints for the inferred generic arguments of method calls,
+        /// e.g. `collect::<Vec<i32>>()`.
+        inlayHints_genericArgumentHints_methodCalls: bool          = "false",
+        /// Whether to show inlay hints for the inferred generic arguments of path calls,
+        /// e.g. `Vec::<i32>::new()` or `mem::take::<String>(x)`.
+        inlayHints_genericArgumentHints_pathCalls: bool            = "false",
+        /// Whether to show inlay hints for the inferred `Self` type of trait function calls,
+        /// e.g. `<Vec<i32> as Default>::default()`.
+        inlayHints_genericArgumentHints_traitCalls: bool           = "false",
         /// Whether to show inlay type hints for elided lifetimes in function signatures.
         inlayHints_lifetimeElisionHints_enable: LifetimeElisionDef = "\"never\"",
         /// Whether to prefer using parameter names as the name for elided lifetime hints if possible.
@@ -277,6 +348,8 @@ config_data! {
         inlayHints_parameterHints_enable: bool                     = "true",
         /// Whether to show inlay type hints for compiler inserted reborrows.
         inlayHints_reborrowHints_enable: ReborrowHintsDef          = "\"never\"",
+        /// Whether to show inlay hints for how method calls borrow their receiver.
+        inlayHints_receiverBorrowHints_enable: ReceiverBorrowHintsDef = "\"never\"",
         /// Whether to render leading colons for type hints, and trailing colons for parameter hints.
         inlayHints_renderColons: bool                              = "true",
         /// Whether to show inlay type hints for variables.
@@ -316,6 +389,9 @@ config_data! {
         /// Whether to show `Method References` lens. Only applies when
         /// `#rust-analyzer.lens.enable#` is set.
         lens_references_method_enable: bool = "false",
+        /// Whether to show `References` lens for public functions, constants, statics and type
+        /// aliases. Only applies when `#rust-analyzer.lens.enable#` is set.
+        lens_references_publicItems_enable: bool = "false",
         /// Whether to show `References` lens for Trait.
         /// Only applies when `#rust-analyzer.lens.enable#` is set.
         lens_references_trait_enable: bool = "false",
@@ -333,13 +409,38 @@ config_data! {
         /// Number of syntax trees rust-analyzer keeps in memory. Defaults to 128.
         lru_capacity: Option<usize>                 = "null",
 
+        /// Memory usage, in megabytes, above which rust-analyzer evicts the caches that are
+        /// cheap to recompute: it shrinks the syntax tree and macro expansion caches, keeping the
+        /// recently used entries, and unloads the symbol indices of libraries. Disabled by default.
+        memory_evictionThreshold: Option<usize>     = "null",
+
+        /// Whether moving an associated item of a trait impl up or down should move it straight to
+        /// the position given by the order of the trait definition, when that position lies in the
+        /// direction of the move.
+        moveItem_respectTraitOrder_enable: bool = "false",
+
         /// Whether to show `can't find Cargo.toml` error message.
         notifications_cargoTomlNotFound: bool      = "true",
 
+        /// Settings to use instead of the regular ones for the files matching a glob, relative to
+        /// the workspace root, like `{ "benches/**": { "checkOnSave": { "enable": false } } }`.
+        /// `*` matches within a path segment, `**` matches any number of segments. When several
+        /// globs match a file, the longest one wins.
+        overrides: FxHashMap<String, serde_json::Value> = "{}",
+
         /// Expand attribute macros. Requires `#rust-analyzer.procMacro.enable#` to be set.
         procMacro_attributes_enable: bool = "true",
+        /// Cache proc-macro expansions on disk, in the target directory of the macro crate, so
+        /// that restarting the server doesn't expand all macro calls again. The cache of a
+        /// macro crate is discarded when it is rebuilt. Macros whose output depends on files
+        /// they read may produce stale results with this enabled.
+        procMacro_cache_enable: bool = "false",
         /// Enable support for procedural macros, implies `#rust-analyzer.cargo.buildScripts.enable#`.
         procMacro_enable: bool                     = "true",
+        /// Maximum time in seconds a single proc-macro expansion may take. Expansions running
+        /// longer fail with an error at the macro call, and the server process of their macro
+        /// crate is restarted. Values below 1 are treated as 1.
+        procMacro_expansionTimeout: usize          = "10",
         /// These proc-macros will be ignored when trying to expand them.
         ///
         /// This config takes a map of crate names with the exported proc-macro names to ignore as values.
@@ -360,8 +461,6 @@ config_data! {
         ///
         /// Any project which uses rust-analyzer with the rustcPrivate
         /// crates must set `[package.metadata.rust-analyzer] rustc_private=true` to use it.
-        ///
-        /// This option does not take effect until rust-analyzer is restarted.
         rustc_source: Option<String> = "null",
 
         /// Additional arguments to `rustfmt`.
@@ -381,14 +480,32 @@ config_data! {
         /// their contents.
         semanticHighlighting_strings_enable: bool = "true",
 
+        /// Which events are reported to the client with `rust-analyzer/serverTrace`
+        /// notifications, carrying the request IDs and durations.
+        serverTrace_verbosity: ServerTraceVerbosity = "\"off\"",
+
         /// Show full signature of the callable. Only shows parameters if disabled.
         signatureInfo_detail: SignatureDetail                           = "\"full\"",
         /// Show documentation.
         signatureInfo_documentation_enable: bool                       = "true",
 
+        /// Maximum size of the types in a trait goal before the trait solver gives up on it.
+        traitSolver_maxSize: usize = "150",
+        /// Maximum depth of nested trait goals before the trait solver reports an overflow.
+        traitSolver_overflowDepth: usize = "500",
+        /// Time in milliseconds the trait solver may spend on a single goal before giving up on
+        /// it and treating it as ambiguous. Goals are never timed out if this is `null`.
+        traitSolver_timeout: Option<usize> = "null",
+
         /// Whether to insert closing angle brackets when typing an opening angle bracket of a generic argument list.
         typing_autoClosingAngleBrackets_enable: bool = "false",
 
+        /// Command that generates a `rust-project.json` for build systems other than Cargo,
+        /// such as Buck or Bazel. It is run when a file that isn't part of a discovered project
+        /// is opened, with `{arg}` in `command` replaced by the path of that file, and must print
+        /// the project to stdout. Relative paths in the output are resolved against the workspace
+        /// root. The command runs again whenever one of the `filesToWatch` build files changes.
+        workspace_discoverConfig: Option<DiscoverWorkspaceConfig> = "null",
         /// Workspace symbol search kind.
         workspace_symbol_search_kind: WorkspaceSymbolSearchKindDef = "\"only_types\"",
         /// Limits the number of items returned from a workspace symbol search (Defaults to 128).
@@ -409,13 +526,36 @@ impl Default for ConfigData {
 #[derive(Debug, Clone)]
 pub struct Config {
     pub discovered_projects: Option<Vec<ProjectManifest>>,
-    caps: lsp_types::ClientCapabilities,
+    /// The workspace folders opened in the client.
+    pub workspace_roots: Vec<AbsPathBuf>,
+    caps: Arc<lsp_types::ClientCapabilities>,
     root_path: AbsPathBuf,
-    data: ConfigData,
+    data: Arc<ConfigData>,
+    /// The configuration as last sent by the client.
+    client_config: Arc<serde_json::Value>,
+    /// Contents of `rust-analyzer.toml` files, ordered from the outermost directory to the
+    /// workspace root, so that later entries take precedence.
+    local_configs: Arc<Vec<serde_json::Value>>,
+    /// The configuration the client reported for each workspace folder.
+    folder_client_configs: Arc<Vec<(AbsPathBuf, serde_json::Value)>>,
+    /// Settings of the workspace folders, used for the files within them.
+    folder_data: Arc<Vec<(AbsPathBuf, Arc<ConfigData>, Vec<PathOverride>)>>,
+    /// The `overrides` of the workspace-wide settings.
+    path_overrides: Arc<Vec<PathOverride>>,
     detached_files: Vec<AbsPathBuf>,
     snippets: Vec<Snippet>,
+    /// The outdated keys found in the configuration, together with the keys replacing them.
+    outdated_keys: Vec<(String, String)>,
+    position_encoding: PositionEncoding,
+    /// Whether the client supports registering `textDocument/diagnostic` dynamically.
+    diagnostic_pull_support: bool,
+    /// Whether the client supports `workspace/diagnostic/refresh`.
+    diagnostic_refresh_support: bool,
 }
 
+/// Name of the checked-in configuration file that is merged with the client configuration.
+pub const LOCAL_CONFIG_FILE_NAME: &str = "rust-analyzer.toml";
+
 type ParallelCachePrimingNumThreads = u8;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -452,9 +592,10 @@ pub struct LensConfig {
 
     // references
     pub method_refs: bool,
-    pub refs_adt: bool,   // for Struct, Enum, Union and Trait
-    pub refs_trait: bool, // for Struct, Enum, Union and Trait
+    pub refs_adt: bool,   // for Struct, Enum, Union and Const
+    pub refs_trait: bool, // for Trait
     pub enum_variant_refs: bool,
+    pub public_item_refs: bool,
 }
 
 impl LensConfig {
@@ -466,6 +607,7 @@ impl LensConfig {
             || self.refs_adt
             || self.refs_trait
             || self.enum_variant_refs
+            || self.public_item_refs
     }
 
     pub fn none(&self) -> bool {
@@ -477,7 +619,11 @@ impl LensConfig {
     }
 
     pub fn references(&self) -> bool {
-        self.method_refs || self.refs_adt || self.refs_trait || self.enum_variant_refs
+        self.method_refs
+            || self.refs_adt
+            || self.refs_trait
+            || self.enum_variant_refs
+            || self.public_item_refs
     }
 }
 
@@ -512,13 +658,41 @@ impl HoverActionsConfig {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FilesConfig {
     pub watcher: FilesWatcher,
     pub exclude: Vec<AbsPathBuf>,
+    pub exclude_globs: Vec<Glob>,
+    pub generated_file_size_limit: Option<usize>,
 }
 
-#[derive(Debug, Clone)]
+/// A command generating `rust-project.json` for the files being opened.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoverWorkspaceConfig {
+    /// The command and its arguments, `{arg}` is replaced by the path of the opened file.
+    pub command: Vec<String>,
+    /// The title of the progress shown while the command runs.
+    #[serde(default)]
+    pub progress_label: Option<String>,
+    /// File names of the build files that cause the command to run again when changed.
+    #[serde(default)]
+    pub files_to_watch: Vec<String>,
+}
+
+impl DiscoverWorkspaceConfig {
+    pub fn progress_label(&self) -> &str {
+        self.progress_label.as_deref().unwrap_or("Discovering workspace")
+    }
+
+    /// Whether a change to `path` requires running the command again.
+    pub fn watches(&self, path: &AbsPath) -> bool {
+        path.file_name()
+            .map_or(false, |name| self.files_to_watch.iter().any(|it| name == it.as_str()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FilesWatcher {
     Client,
     Notify,
@@ -561,20 +735,76 @@ pub struct ClientCommandsConfig {
     pub show_reference: bool,
     pub goto_location: bool,
     pub trigger_parameter_hints: bool,
+    pub reload_workspace: bool,
+    pub rebuild_proc_macros: bool,
+    pub install_rust_src: bool,
 }
 
 #[derive(Debug)]
 pub struct ConfigUpdateError {
-    errors: Vec<(String, serde_json::Error)>,
+    errors: Vec<ConfigError>,
+}
+
+impl ConfigUpdateError {
+    pub fn errors(&self) -> &[ConfigError] {
+        &self.errors
+    }
+}
+
+/// An invalid value or a mistyped key in the configuration.
+#[derive(Debug)]
+pub struct ConfigError {
+    /// JSON pointer to the offending key, like `/cargo/features`.
+    pub key: String,
+    /// Where the key was set, when it isn't the global configuration.
+    pub origin: Option<String>,
+    pub message: String,
+    /// The type expected by the configuration schema, for known keys.
+    pub expected: Option<String>,
+    /// The nearest valid key, for unknown keys.
+    pub suggestion: Option<String>,
+}
+
+impl ConfigError {
+    fn new(key: impl Into<String>, message: impl fmt::Display) -> ConfigError {
+        ConfigError {
+            key: key.into(),
+            origin: None,
+            message: message.to_string(),
+            expected: None,
+            suggestion: None,
+        }
+    }
+
+    fn with_origin(mut self, origin: String) -> ConfigError {
+        self.origin = Some(match self.origin {
+            Some(inner) => format!("{}, {}", inner, origin),
+            None => origin,
+        });
+        self
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.key)?;
+        if let Some(origin) = &self.origin {
+            write!(f, " ({})", origin)?;
+        }
+        write!(f, ": {}", self.message)?;
+        if let Some(expected) = &self.expected {
+            write!(f, " (the schema allows {})", expected)?;
+        }
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, ", did you mean `{}`?", suggestion)?;
+        }
+        Ok(())
+    }
 }
 
 impl fmt::Display for ConfigUpdateError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let errors = self.errors.iter().format_with("\n", |(key, e), f| {
-            f(key)?;
-            f(&": ")?;
-            f(e)
-        });
+        let errors = self.errors.iter().format("\n");
         write!(
             f,
             "rust-analyzer found {} invalid config value{}:\n{}",
@@ -588,28 +818,159 @@ impl fmt::Display for ConfigUpdateError {
 impl Config {
     pub fn new(root_path: AbsPathBuf, caps: ClientCapabilities) -> Self {
         Config {
-            caps,
-            data: ConfigData::default(),
+            position_encoding: negotiated_encoding(&caps, &[]),
+            caps: Arc::new(caps),
+            data: Arc::default(),
+            client_config: Arc::new(serde_json::Value::Null),
+            local_configs: Arc::default(),
+            folder_client_configs: Arc::default(),
+            folder_data: Arc::default(),
+            path_overrides: Arc::default(),
             detached_files: Vec::new(),
             discovered_projects: None,
+            workspace_roots: Vec::new(),
             root_path,
             snippets: Default::default(),
+            outdated_keys: Vec::new(),
+            diagnostic_pull_support: false,
+            diagnostic_refresh_support: false,
         }
     }
 
-    pub fn update(&mut self, mut json: serde_json::Value) -> Result<(), ConfigUpdateError> {
+    pub fn update(&mut self, json: serde_json::Value) -> Result<(), ConfigUpdateError> {
         tracing::info!("updating config from JSON: {:#}", json);
         if json.is_null() || json.as_object().map_or(false, |it| it.is_empty()) {
             return Ok(());
         }
+        self.client_config = Arc::new(json);
+        self.apply_changes(Vec::new())
+    }
+
+    /// Sets the configurations the client reported for the individual workspace folders.
+    ///
+    /// Files within a folder use its settings instead of the global ones, see [`Config::for_path`].
+    pub fn update_folders(
+        &mut self,
+        configs: Vec<(AbsPathBuf, serde_json::Value)>,
+    ) -> Result<(), ConfigUpdateError> {
+        tracing::info!("updating workspace folder configs: {:#?}", configs);
+        self.folder_client_configs =
+            Arc::new(configs.into_iter().filter(|(_, json)| !json.is_null()).collect());
+        self.apply_changes(Vec::new())
+    }
+
+    /// Returns the configuration for files under `path`, taking the settings of the innermost
+    /// workspace folder containing it and the matching `overrides` into account.
+    ///
+    /// This is cheap, the returned configuration shares its settings with `self`.
+    pub fn for_path(&self, path: &AbsPath) -> Config {
+        let folder = self
+            .folder_data
+            .iter()
+            .filter(|(root, ..)| path.starts_with(root))
+            .max_by_key(|(root, ..)| root.as_os_str().len());
+        let (root, data, overrides) = match folder {
+            Some((root, data, overrides)) => (root, data, overrides),
+            None => (&self.root_path, &self.data, &*self.path_overrides),
+        };
+        let relative = path.strip_prefix(root).map(|it| {
+            it.as_ref()
+                .components()
+                .map(|it| it.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/")
+        });
+        let path_override = relative.and_then(|relative| {
+            overrides
+                .iter()
+                .filter(|it| glob_matches(&it.glob, &relative))
+                .max_by_key(|it| it.glob.len())
+        });
+        let mut config = self.clone();
+        config.data = Arc::clone(path_override.map_or(data, |it| &it.data));
+        config
+    }
+
+    /// Whether the settings differ from the ones of `other`, in any of the configuration sources.
+    pub fn settings_changed(&self, other: &Config) -> bool {
+        self.client_config != other.client_config
+            || self.local_configs != other.local_configs
+            || self.folder_client_configs != other.folder_client_configs
+    }
+
+    /// Paths of the `rust-analyzer.toml` files that are merged into the client configuration,
+    /// from the outermost directory down to the workspace root.
+    pub fn local_config_paths(&self) -> Vec<AbsPathBuf> {
+        let mut dirs: Vec<&AbsPath> =
+            iter::successors(Some(self.root_path.as_path()), |it| it.parent()).collect();
+        dirs.reverse();
+        dirs.into_iter().map(|dir| dir.join(LOCAL_CONFIG_FILE_NAME)).collect()
+    }
+
+    /// Re-reads the `rust-analyzer.toml` files from disk and recomputes the configuration.
+    ///
+    /// Settings from these files override the ones sent by the client, and files closer to the
+    /// workspace root override the ones in their parent directories.
+    pub fn reload_local_configs(&mut self) -> Result<(), ConfigUpdateError> {
         let mut errors = Vec::new();
+        let mut local_configs = Vec::new();
+        for path in self.local_config_paths() {
+            let text = match std::fs::read_to_string(&path) {
+                Ok(it) => it,
+                Err(_) => continue,
+            };
+            match toml::from_str::<serde_json::Value>(&text) {
+                Ok(json) => {
+                    tracing::info!("loaded local config from {}: {:#}", path.display(), json);
+                    local_configs.push(json);
+                }
+                Err(e) => errors.push(ConfigError::new(path.display().to_string(), e)),
+            }
+        }
+        self.local_configs = Arc::new(local_configs);
+        self.apply_changes(errors)
+    }
+
+    fn apply_changes(&mut self, mut errors: Vec<ConfigError>) -> Result<(), ConfigUpdateError> {
+        let schema = schema_keys();
+        let mut outdated_keys = Vec::new();
+        let mut with_local_configs = |mut json: serde_json::Value| {
+            for local in self.local_configs.iter() {
+                merge_json(&mut json, local.clone());
+            }
+            outdated_keys.extend(patch_old_style::patch_json_for_outdated_configs(&mut json));
+            json
+        };
+        self.folder_data = Arc::new(
+            self.folder_client_configs
+                .iter()
+                .map(|(root, json)| {
+                    let mut folder_errors = Vec::new();
+                    let json = with_local_configs(json.clone());
+                    unknown_keys(&json, &schema, &mut folder_errors);
+                    let overrides = path_overrides(&json, &mut folder_errors);
+                    let data = ConfigData::from_json(json, &mut folder_errors);
+                    errors.extend(
+                        folder_errors
+                            .into_iter()
+                            .map(|e| e.with_origin(root.display().to_string())),
+                    );
+                    (root.clone(), Arc::new(data), overrides)
+                })
+                .collect(),
+        );
+        let mut json = with_local_configs(serde_json::Value::clone(&self.client_config));
+        outdated_keys.sort();
+        outdated_keys.dedup();
+        self.outdated_keys = outdated_keys;
+        unknown_keys(&json, &schema, &mut errors);
+        self.path_overrides = Arc::new(path_overrides(&json, &mut errors));
         self.detached_files =
             get_field::<Vec<PathBuf>>(&mut json, &mut errors, "detachedFiles", None, "[]")
                 .into_iter()
                 .map(AbsPathBuf::assert)
                 .collect();
-        patch_old_style::patch_json_for_outdated_configs(&mut json);
-        self.data = ConfigData::from_json(json, &mut errors);
+        self.data = Arc::new(ConfigData::from_json(json, &mut errors));
         tracing::debug!("deserialized config data: {:#?}", self.data);
         self.snippets.clear();
         for (name, def) in self.data.completion_snippets_custom.iter() {
@@ -630,17 +991,26 @@ impl Config {
                 scope,
             ) {
                 Some(snippet) => self.snippets.push(snippet),
-                None => errors.push((
-                    format!("snippet {name} is invalid"),
-                    <serde_json::Error as serde::de::Error>::custom(
-                        "snippet path is invalid or triggers are missing",
-                    ),
+                None => errors.push(ConfigError::new(
+                    format!("/completion/snippets/custom/{name}"),
+                    "snippet path is invalid or triggers are missing",
                 )),
             }
         }
 
         self.validate(&mut errors);
 
+        // Outdated keys are reported separately, with their new names.
+        errors.retain(|e| {
+            e.suggestion.is_none()
+                || !self.outdated_keys.iter().any(|(old, _)| pointer_to_name(&e.key) == *old)
+        });
+        for error in &mut errors {
+            if let Some((_, props)) = schema.iter().find(|(key, _)| *key == error.key) {
+                error.expected = describe_schema_type(props);
+            }
+        }
+
         if errors.is_empty() {
             Ok(())
         } else {
@@ -648,13 +1018,10 @@ impl Config {
         }
     }
 
-    fn validate(&self, error_sink: &mut Vec<(String, serde_json::Error)>) {
-        use serde::de::Error;
+    fn validate(&self, error_sink: &mut Vec<ConfigError>) {
         if self.data.checkOnSave_command.is_empty() {
-            error_sink.push((
-                "/checkOnSave/command".to_string(),
-                serde_json::Error::custom("expected a non-empty string"),
-            ));
+            error_sink
+                .push(ConfigError::new("/checkOnSave/command", "expected a non-empty string"));
         }
     }
 
@@ -662,6 +1029,31 @@ impl Config {
         ConfigData::json_schema()
     }
 
+    /// The settings in effect outside of workspace folders and path overrides, after merging the
+    /// `rust-analyzer.toml` files into the client configuration.
+    ///
+    /// Every configuration key is present, invalid and missing values are replaced by defaults.
+    pub fn effective_config(&self) -> serde_json::Value {
+        let mut json = serde_json::Value::clone(&self.client_config);
+        for local in self.local_configs.iter() {
+            merge_json(&mut json, local.clone());
+        }
+        patch_old_style::patch_json_for_outdated_configs(&mut json);
+        let mut errors = Vec::new();
+        ConfigData::from_json(json.clone(), &mut errors);
+        schema_keys()
+            .into_iter()
+            .map(|(key, mut props)| {
+                let value = match json.pointer(&key) {
+                    Some(it) if !errors.iter().any(|e| e.key == key) => it.clone(),
+                    _ => props["default"].take(),
+                };
+                (format!("rust-analyzer.{}", pointer_to_name(&key)), value)
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+
     pub fn root_path(&self) -> &AbsPathBuf {
         &self.root_path
     }
@@ -673,6 +1065,28 @@ impl Config {
     pub fn detached_files(&self) -> &[AbsPathBuf] {
         &self.detached_files
     }
+
+    /// The outdated keys found in the configuration, together with the keys replacing them.
+    pub fn outdated_keys(&self) -> &[(String, String)] {
+        &self.outdated_keys
+    }
+
+    /// A warning listing the outdated keys in use, if there are any.
+    pub fn outdated_keys_warning(&self) -> Option<String> {
+        if self.outdated_keys.is_empty() {
+            return None;
+        }
+        let keys = self
+            .outdated_keys
+            .iter()
+            .format_with("\n", |(old, new), f| f(&format_args!("{} -> {}", old, new)));
+        Some(format!(
+            "rust-analyzer found {} outdated config key{}, please use the new names instead:\n{}",
+            self.outdated_keys.len(),
+            if self.outdated_keys.len() == 1 { "" } else { "s" },
+            keys
+        ))
+    }
 }
 
 macro_rules! try_ {
@@ -730,6 +1144,10 @@ impl Config {
         )
     }
 
+    pub fn workspace_configuration(&self) -> bool {
+        try_or_def!(self.caps.workspace.as_ref()?.configuration?)
+    }
+
     pub fn prefill_caches(&self) -> bool {
         self.data.cachePriming_enable
     }
@@ -815,12 +1233,24 @@ impl Config {
         )
     }
 
-    pub fn offset_encoding(&self) -> OffsetEncoding {
-        if supports_utf8(&self.caps) {
-            OffsetEncoding::Utf8
-        } else {
-            OffsetEncoding::Utf16
-        }
+    pub fn position_encoding(&self) -> PositionEncoding {
+        self.position_encoding
+    }
+
+    /// Picks the position encoding among the `general.positionEncodings` the client offered.
+    ///
+    /// `lsp_types` doesn't know about these yet, so they are passed in separately.
+    pub fn negotiate_position_encoding(&mut self, position_encodings: &[String]) {
+        self.position_encoding = negotiated_encoding(&self.caps, position_encodings);
+    }
+
+    /// Records whether `textDocument/diagnostic` can be registered dynamically, and whether the
+    /// client can be asked to pull diagnostics again.
+    ///
+    /// `lsp_types` doesn't know about these yet either, so they are passed in separately.
+    pub fn set_diagnostic_capabilities(&mut self, pull: bool, refresh: bool) {
+        self.diagnostic_pull_support = pull;
+        self.diagnostic_refresh_support = refresh;
     }
 
     fn experimental(&self, index: &'static str) -> bool {
@@ -835,13 +1265,26 @@ impl Config {
         self.experimental("serverStatusNotification")
     }
 
+    pub fn test_explorer(&self) -> bool {
+        self.experimental("testExplorer")
+    }
+
     pub fn publish_diagnostics(&self) -> bool {
         self.data.diagnostics_enable
     }
 
+    pub fn pull_diagnostics(&self) -> bool {
+        self.data.diagnostics_pull_enable && self.diagnostic_pull_support
+    }
+
+    pub fn diagnostics_refresh(&self) -> bool {
+        self.diagnostic_refresh_support
+    }
+
     pub fn diagnostics(&self) -> DiagnosticsConfig {
         DiagnosticsConfig {
             disable_experimental: !self.data.diagnostics_experimental_enable,
+            disable_unstable_features: !self.data.diagnostics_unstableFeatures_enable,
             disabled: self.data.diagnostics_disabled.clone(),
             expr_fill_default: match self.data.assist_expressionFillDefault {
                 ExprFillDefaultDef::Todo => ExprFillDefaultMode::Todo,
@@ -855,6 +1298,8 @@ impl Config {
             remap_prefix: self.data.diagnostics_remapPrefix.clone(),
             warnings_as_info: self.data.diagnostics_warningsAsInfo.clone(),
             warnings_as_hint: self.data.diagnostics_warningsAsHint.clone(),
+            clippy_warnings_as_info: self.data.checkOnSave_clippy_warningsAsInfo.clone(),
+            clippy_warnings_as_hint: self.data.checkOnSave_clippy_warningsAsHint.clone(),
         }
     }
 
@@ -862,7 +1307,23 @@ impl Config {
         self.data.lru_capacity
     }
 
-    pub fn proc_macro_srv(&self) -> Option<(AbsPathBuf, Vec<OsString>)> {
+    pub fn memory_eviction_threshold(&self) -> Option<usize> {
+        self.data.memory_evictionThreshold
+    }
+
+    pub fn trait_solver(&self) -> TraitSolverConfig {
+        TraitSolverConfig {
+            max_size: self.data.traitSolver_maxSize,
+            overflow_depth: self.data.traitSolver_overflowDepth,
+            timeout: self.data.traitSolver_timeout.map(|ms| Duration::from_millis(ms as u64)),
+        }
+    }
+
+    pub fn move_item_respect_trait_order(&self) -> bool {
+        self.data.moveItem_respectTraitOrder_enable
+    }
+
+    pub fn proc_macro_srv(&self) -> Option<(AbsPathBuf, Vec<OsString>, Duration)> {
         if !self.data.procMacro_enable {
             return None;
         }
@@ -870,7 +1331,13 @@ impl Config {
             Some(it) => self.root_path.join(it),
             None => AbsPathBuf::assert(std::env::current_exe().ok()?),
         };
-        Some((path, vec!["proc-macro".into()]))
+        // Without any time, every expansion would fail and restart its server process.
+        let timeout = Duration::from_secs(self.data.procMacro_expansionTimeout.max(1) as u64);
+        Some((path, vec!["proc-macro".into()], timeout))
+    }
+
+    pub fn proc_macro_expansion_cache(&self) -> bool {
+        self.data.procMacro_cache_enable
     }
 
     pub fn dummy_replacements(&self) -> &FxHashMap<Box<str>, Box<[Box<str>]>> {
@@ -882,6 +1349,16 @@ impl Config {
     }
 
     pub fn files(&self) -> FilesConfig {
+        let mut exclude = Vec::new();
+        let mut exclude_globs = Vec::new();
+        for dir in &self.data.files_excludeDirs {
+            let path = self.root_path.join(dir);
+            if Glob::is_glob(&dir.to_string_lossy()) {
+                exclude_globs.push(Glob::new(&path));
+            } else {
+                exclude.push(path);
+            }
+        }
         FilesConfig {
             watcher: match self.data.files_watcher.as_str() {
                 "notify" => FilesWatcher::Notify,
@@ -890,10 +1367,16 @@ impl Config {
                 }
                 _ => FilesWatcher::Notify,
             },
-            exclude: self.data.files_excludeDirs.iter().map(|it| self.root_path.join(it)).collect(),
+            exclude,
+            exclude_globs,
+            generated_file_size_limit: self.data.files_generatedFileSizeLimit,
         }
     }
 
+    pub fn discover_workspace_config(&self) -> Option<&DiscoverWorkspaceConfig> {
+        self.data.workspace_discoverConfig.as_ref()
+    }
+
     pub fn notifications(&self) -> NotificationsConfig {
         NotificationsConfig { cargo_toml_not_found: self.data.notifications_cargoTomlNotFound }
     }
@@ -923,6 +1406,16 @@ impl Config {
                 CargoFeatures::Listed(it) => it.clone(),
             },
             target: self.data.cargo_target.clone(),
+            crate_targets: self
+                .data
+                .cargo_crateTargets
+                .iter()
+                .map(|(name, def)| {
+                    let target = CrateTarget { target: def.target.clone(), cfgs: def.cfgs.clone() };
+                    (name.clone(), target)
+                })
+                .collect(),
+            extra_targets: self.data.cargo_extraTargets.clone(),
             no_sysroot: self.data.cargo_noSysroot,
             rustc_source,
             unset_test_crates: UnsetTestCrates::Only(self.data.cargo_unsetTest.clone()),
@@ -931,6 +1424,26 @@ impl Config {
         }
     }
 
+    /// Extra cfgs for the crates, use [`Config::for_path`] to get the ones of a crate.
+    pub fn cfgs(&self) -> &[String] {
+        &self.data.cargo_cfgs
+    }
+
+    /// The `cargo.cfgs` settings of the workspace, its folders and their `overrides`, to tell
+    /// whether the cfgs of any crate may have changed.
+    pub fn cfgs_by_path(&self) -> Vec<(&AbsPath, Option<&str>, &[String])> {
+        let folders = iter::once((&*self.root_path, &self.data, &self.path_overrides[..])).chain(
+            self.folder_data.iter().map(|(root, data, overrides)| (&**root, data, &overrides[..])),
+        );
+        folders
+            .flat_map(|(root, data, overrides)| {
+                iter::once((root, None, &*data.cargo_cfgs)).chain(
+                    overrides.iter().map(move |it| (root, Some(&*it.glob), &*it.data.cargo_cfgs)),
+                )
+            })
+            .collect()
+    }
+
     pub fn rustfmt(&self) -> RustfmtConfig {
         match &self.data.rustfmt_overrideCommand {
             Some(args) if !args.is_empty() => {
@@ -953,7 +1466,15 @@ impl Config {
             Some(args) if !args.is_empty() => {
                 let mut args = args.clone();
                 let command = args.remove(0);
-                FlycheckConfig::CustomCommand { command, args }
+                let format = match &self.data.checkOnSave_outputFormat {
+                    OutputFormatDef::Json => OutputFormat::Json,
+                    OutputFormatDef::EmbeddedJson => OutputFormat::EmbeddedJson,
+                    OutputFormatDef::Pattern { pattern } => OutputFormat::Pattern(pattern.clone()),
+                    OutputFormatDef::JsonPointers { json_pointers } => {
+                        OutputFormat::JsonPointers(json_pointers.clone())
+                    }
+                };
+                FlycheckConfig::CustomCommand { command, args, format }
             }
             Some(_) | None => FlycheckConfig::CargoCommand {
                 command: self.data.checkOnSave_command.clone(),
@@ -981,6 +1502,13 @@ impl Config {
                     CargoFeatures::Listed(it) => it,
                 },
                 extra_args: self.data.checkOnSave_extraArgs.clone(),
+                clippy: (self.data.checkOnSave_clippy_enable
+                    && self.data.checkOnSave_command != "clippy")
+                    .then(|| ClippyConfig {
+                        allow: self.data.checkOnSave_clippy_allow.clone(),
+                        warn: self.data.checkOnSave_clippy_warn.clone(),
+                        deny: self.data.checkOnSave_clippy_deny.clone(),
+                    }),
             },
         };
         Some(flycheck_config)
@@ -999,6 +1527,11 @@ impl Config {
             type_hints: self.data.inlayHints_typeHints_enable,
             parameter_hints: self.data.inlayHints_parameterHints_enable,
             chaining_hints: self.data.inlayHints_chainingHints_enable,
+            generic_arg_hints: ide::GenericArgHints {
+                method_calls: self.data.inlayHints_genericArgumentHints_methodCalls,
+                path_calls: self.data.inlayHints_genericArgumentHints_pathCalls,
+                trait_calls: self.data.inlayHints_genericArgumentHints_traitCalls,
+            },
             closure_return_type_hints: self.data.inlayHints_closureReturnTypeHints_enable,
             lifetime_elision_hints: match self.data.inlayHints_lifetimeElisionHints_enable {
                 LifetimeElisionDef::Always => ide::LifetimeElisionHints::Always,
@@ -1014,6 +1547,11 @@ impl Config {
                 ReborrowHintsDef::Never => ide::ReborrowHints::Never,
                 ReborrowHintsDef::Mutable => ide::ReborrowHints::MutableOnly,
             },
+            receiver_borrow_hints: match self.data.inlayHints_receiverBorrowHints_enable {
+                ReceiverBorrowHintsDef::Always => ide::ReborrowHints::Always,
+                ReceiverBorrowHintsDef::Never => ide::ReborrowHints::Never,
+                ReceiverBorrowHintsDef::Mutable => ide::ReborrowHints::MutableOnly,
+            },
             binding_mode_hints: self.data.inlayHints_bindingModeHints_enable,
             param_names_for_lifetime_elision_hints: self
                 .data
@@ -1024,9 +1562,29 @@ impl Config {
             } else {
                 None
             },
+            fields_to_resolve: InlayFieldsToResolve {
+                resolve_text_edits: self.inlay_hint_resolve_support("textEdits"),
+                resolve_label_location: self.inlay_hint_resolve_support("label.location"),
+                resolve_tooltip: self.inlay_hint_resolve_support("tooltip"),
+            },
         }
     }
 
+    fn inlay_hint_resolve_support(&self, property: &str) -> bool {
+        try_or_def!(self
+            .caps
+            .text_document
+            .as_ref()?
+            .inlay_hint
+            .as_ref()?
+            .resolve_support
+            .as_ref()?
+            .properties
+            .as_slice())
+        .iter()
+        .any(|it| it == property)
+    }
+
     fn insert_use_config(&self) -> InsertUseConfig {
         InsertUseConfig {
             granularity: match self.data.imports_granularity_group {
@@ -1070,6 +1628,10 @@ impl Config {
                     .snippet_support?
             )),
             snippets: self.snippets.clone(),
+            fields_to_resolve: CompletionFieldsToResolve {
+                resolve_documentation: completion_item_resolve(&self.caps, "documentation"),
+                resolve_detail: completion_item_resolve(&self.caps, "detail"),
+            },
         }
     }
 
@@ -1077,6 +1639,10 @@ impl Config {
         self.experimental("snippetTextEdit")
     }
 
+    pub fn multi_file_snippet_cap(&self) -> bool {
+        self.experimental("multiFileSnippetTextEdit")
+    }
+
     pub fn assist(&self) -> AssistConfig {
         AssistConfig {
             snippet_cap: SnippetCap::new(self.experimental("snippetTextEdit")),
@@ -1111,6 +1677,7 @@ impl Config {
             refs_trait: self.data.lens_enable && self.data.lens_references_trait_enable,
             enum_variant_refs: self.data.lens_enable
                 && self.data.lens_references_enumVariant_enable,
+            public_item_refs: self.data.lens_enable && self.data.lens_references_publicItems_enable,
         }
     }
 
@@ -1129,6 +1696,10 @@ impl Config {
         self.data.semanticHighlighting_strings_enable
     }
 
+    pub fn server_trace(&self) -> ServerTraceVerbosity {
+        self.data.serverTrace_verbosity
+    }
+
     pub fn hover(&self) -> HoverConfig {
         HoverConfig {
             links_in_hover: self.data.hover_links_enable,
@@ -1205,6 +1776,9 @@ impl Config {
             show_reference: get("rust-analyzer.showReferences"),
             goto_location: get("rust-analyzer.gotoLocation"),
             trigger_parameter_hints: get("editor.action.triggerParameterHints"),
+            reload_workspace: get("rust-analyzer.reloadWorkspace"),
+            rebuild_proc_macros: get("rust-analyzer.rebuildProcMacros"),
+            install_rust_src: get("rust-analyzer.installRustSrc"),
         }
     }
 
@@ -1214,9 +1788,28 @@ impl Config {
             break_points: self.data.highlightRelated_breakPoints_enable,
             exit_points: self.data.highlightRelated_exitPoints_enable,
             yield_points: self.data.highlightRelated_yieldPoints_enable,
+            unsafe_operations: self.data.highlightRelated_unsafeOperations_enable,
         }
     }
 
+    /// The configuration for opening external documentation, `target_dir` being the target
+    /// directory of the workspace the documentation is opened from.
+    pub fn external_docs(&self, target_dir: Option<&AbsPath>) -> ExternalDocsConfig {
+        let local_docs_url = target_dir
+            .filter(|_| self.data.externalDocs_local_enable)
+            .and_then(|it| Url::from_directory_path(it.join("doc")).ok());
+        let docs_base_url = self.data.externalDocs_baseUrl.as_deref().and_then(|it| {
+            // The crate name is appended to the base URL, so make sure it is treated as a
+            // directory.
+            if it.ends_with('/') {
+                Url::parse(it).ok()
+            } else {
+                Url::parse(&format!("{}/", it)).ok()
+            }
+        });
+        ExternalDocsConfig { local_docs_url, docs_base_url }
+    }
+
     pub fn prime_caches_num_threads(&self) -> u8 {
         match self.data.cachePriming_numThreads {
             0 => num_cpus::get_physical().try_into().unwrap_or(u8::MAX),
@@ -1327,6 +1920,8 @@ mod de_unit_v {
     named_unit_variant!(all);
     named_unit_variant!(skip_trivial);
     named_unit_variant!(mutable);
+    named_unit_variant!(json);
+    named_unit_variant!(embedded_json);
 }
 
 #[derive(Deserialize, Debug, Clone, Copy)]
@@ -1343,6 +1938,13 @@ impl Default for SnippetScopeDef {
     }
 }
 
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+struct CrateTargetDef {
+    target: Option<String>,
+    cfgs: Vec<String>,
+}
+
 #[derive(Deserialize, Debug, Clone, Default)]
 #[serde(default)]
 struct SnippetDef {
@@ -1428,6 +2030,22 @@ enum CargoFeatures {
     Listed(Vec<String>),
 }
 
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+enum OutputFormatDef {
+    #[serde(deserialize_with = "de_unit_v::json")]
+    Json,
+    #[serde(deserialize_with = "de_unit_v::embedded_json")]
+    EmbeddedJson,
+    Pattern {
+        pattern: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    JsonPointers {
+        json_pointers: JsonPointers,
+    },
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(untagged)]
 enum LifetimeElisionDef {
@@ -1450,6 +2068,17 @@ enum ReborrowHintsDef {
     Mutable,
 }
 
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+enum ReceiverBorrowHintsDef {
+    #[serde(deserialize_with = "true_or_always")]
+    Always,
+    #[serde(deserialize_with = "false_or_never")]
+    Never,
+    #[serde(deserialize_with = "de_unit_v::mutable")]
+    Mutable,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "snake_case")]
 enum ImportPrefixDef {
@@ -1467,6 +2096,14 @@ enum WorkspaceSymbolSearchScopeDef {
     WorkspaceAndDependencies,
 }
 
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerTraceVerbosity {
+    Off,
+    Requests,
+    Verbose,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "snake_case")]
 enum SignatureDetail {
@@ -1492,7 +2129,7 @@ macro_rules! _config_data {
         #[derive(Debug, Clone)]
         struct $name { $($field: $ty,)* }
         impl $name {
-            fn from_json(mut json: serde_json::Value, error_sink: &mut Vec<(String, serde_json::Error)>) -> $name {
+            fn from_json(mut json: serde_json::Value, error_sink: &mut Vec<ConfigError>) -> $name {
                 $name {$(
                     $field: get_field(
                         &mut json,
@@ -1536,9 +2173,213 @@ macro_rules! _config_data {
 }
 use _config_data as config_data;
 
+/// Settings replacing the regular ones for the files matching a glob.
+#[derive(Debug, Clone)]
+struct PathOverride {
+    glob: String,
+    data: Arc<ConfigData>,
+}
+
+/// Computes the settings of each entry of `overrides` in `json`, on top of the rest of `json`.
+fn path_overrides(
+    json: &serde_json::Value,
+    error_sink: &mut Vec<ConfigError>,
+) -> Vec<PathOverride> {
+    let overrides = match json.get("overrides").and_then(|it| it.as_object()) {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    overrides
+        .iter()
+        .map(|(glob, settings)| {
+            let mut json = json.clone();
+            merge_json(&mut json, settings.clone());
+            let mut errors = Vec::new();
+            let data = ConfigData::from_json(json, &mut errors);
+            error_sink
+                .extend(errors.into_iter().map(|e| e.with_origin(format!("overrides.{}", glob))));
+            PathOverride { glob: glob.clone(), data: Arc::new(data) }
+        })
+        .collect()
+}
+
+/// Matches a `/`-separated relative path against a glob, where `*` and `?` match within a
+/// segment and a `**` segment matches any number of segments.
+pub(crate) fn glob_matches(glob: &str, path: &str) -> bool {
+    fn segments_match(glob: &[&str], path: &[&str]) -> bool {
+        match glob.split_first() {
+            None => path.is_empty(),
+            Some((&"**", rest)) => (0..=path.len()).any(|skip| segments_match(rest, &path[skip..])),
+            Some((segment, rest)) => match path.split_first() {
+                Some((first, path)) => {
+                    let chars = |it: &str| it.chars().collect::<Vec<_>>();
+                    segment_matches(&chars(segment), &chars(first)) && segments_match(rest, path)
+                }
+                None => false,
+            },
+        }
+    }
+    fn segment_matches(glob: &[char], text: &[char]) -> bool {
+        match glob.split_first() {
+            None => text.is_empty(),
+            Some(('*', rest)) => (0..=text.len()).any(|skip| segment_matches(rest, &text[skip..])),
+            Some(('?', rest)) => !text.is_empty() && segment_matches(rest, &text[1..]),
+            Some((c, rest)) => text.first() == Some(c) && segment_matches(rest, &text[1..]),
+        }
+    }
+    let glob: Vec<_> = glob.trim_matches('/').split('/').collect();
+    let path: Vec<_> = path.split('/').filter(|it| !it.is_empty()).collect();
+    segments_match(&glob, &path)
+}
+
+/// The configuration keys as JSON pointers, like `/cargo/features`, with their schema.
+fn schema_keys() -> Vec<(String, serde_json::Value)> {
+    match ConfigData::json_schema() {
+        serde_json::Value::Object(map) => map
+            .into_iter()
+            .map(|(name, props)| {
+                let name = name.trim_start_matches("rust-analyzer.");
+                (format!("/{}", name.replace('.', "/")), props)
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn pointer_to_name(pointer: &str) -> String {
+    pointer.trim_start_matches('/').replace('/', ".")
+}
+
+/// Reports the keys of `json` and of its path overrides that aren't configuration keys, but are
+/// close to one.
+///
+/// Keys without a close match are ignored, clients send their own settings along with ours.
+fn unknown_keys(
+    json: &serde_json::Value,
+    schema: &[(String, serde_json::Value)],
+    error_sink: &mut Vec<ConfigError>,
+) {
+    fn walk(
+        json: &serde_json::Value,
+        pointer: &str,
+        schema: &[(String, serde_json::Value)],
+        acc: &mut Vec<String>,
+    ) {
+        let object = match json.as_object() {
+            Some(it) => it,
+            None => return,
+        };
+        for (key, value) in object {
+            if pointer.is_empty() && matches!(key.as_str(), "overrides" | "detachedFiles") {
+                continue;
+            }
+            let pointer = format!("{}/{}", pointer, key);
+            if schema.iter().any(|(it, _)| *it == pointer) {
+                continue;
+            }
+            let prefix = format!("{}/", pointer);
+            if value.is_object() && schema.iter().any(|(it, _)| it.starts_with(&prefix)) {
+                walk(value, &pointer, schema, acc);
+            } else {
+                leaves(value, pointer, acc);
+            }
+        }
+    }
+    fn leaves(json: &serde_json::Value, pointer: String, acc: &mut Vec<String>) {
+        match json.as_object() {
+            Some(object) if !object.is_empty() => {
+                for (key, value) in object {
+                    leaves(value, format!("{}/{}", pointer, key), acc);
+                }
+            }
+            _ => acc.push(pointer),
+        }
+    }
+    let mut report = |json: &serde_json::Value, origin: Option<String>| {
+        let mut unknown = Vec::new();
+        walk(json, "", schema, &mut unknown);
+        for key in unknown {
+            let nearest = schema
+                .iter()
+                .map(|(it, _)| (edit_distance(&key, it), it))
+                .min_by_key(|(distance, _)| *distance);
+            let suggestion = match nearest {
+                Some((distance, it)) if distance <= (key.len() / 4).max(1) => it.clone(),
+                _ => continue,
+            };
+            let mut error = ConfigError::new(key, "unknown config key");
+            error.suggestion = Some(suggestion);
+            if let Some(origin) = &origin {
+                error = error.with_origin(origin.clone());
+            }
+            error_sink.push(error);
+        }
+    };
+    report(json, None);
+    if let Some(overrides) = json.get("overrides").and_then(|it| it.as_object()) {
+        for (glob, settings) in overrides {
+            report(settings, Some(format!("overrides.{}", glob)));
+        }
+    }
+}
+
+/// Describes the values allowed by a schema, like `null or integer`.
+fn describe_schema_type(props: &serde_json::Value) -> Option<String> {
+    if let Some(variants) = props.get("enum").and_then(|it| it.as_array()) {
+        return Some(variants.iter().join(" or "));
+    }
+    if let Some(any_of) = props.get("anyOf").and_then(|it| it.as_array()) {
+        return Some(any_of.iter().filter_map(describe_schema_type).join(" or "));
+    }
+    let describe = |ty: &str| match (ty, props.get("items").and_then(describe_schema_type)) {
+        ("array", Some(items)) => format!("array of {}", items),
+        _ => ty.to_string(),
+    };
+    match props.get("type")? {
+        serde_json::Value::String(ty) => Some(describe(ty)),
+        serde_json::Value::Array(tys) => {
+            Some(tys.iter().filter_map(|it| it.as_str()).map(describe).join(" or "))
+        }
+        _ => None,
+    }
+}
+
+/// The Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Recursively merges `other` into `json`, with values from `other` taking precedence.
+fn merge_json(json: &mut serde_json::Value, other: serde_json::Value) {
+    match (json, other) {
+        (serde_json::Value::Object(json), serde_json::Value::Object(other)) => {
+            for (key, value) in other {
+                match json.get_mut(&key) {
+                    Some(it) => merge_json(it, value),
+                    None => {
+                        json.insert(key, value);
+                    }
+                }
+            }
+        }
+        (json, other) => *json = other,
+    }
+}
+
 fn get_field<T: DeserializeOwned>(
     json: &mut serde_json::Value,
-    error_sink: &mut Vec<(String, serde_json::Error)>,
+    error_sink: &mut Vec<ConfigError>,
     field: &'static str,
     alias: Option<&'static str>,
     default: &str,
@@ -1556,7 +2397,7 @@ fn get_field<T: DeserializeOwned>(
                 Ok(it) => Some(it),
                 Err(e) => {
                     tracing::warn!("Failed to deserialize config field at {}: {:?}", pointer, e);
-                    error_sink.push((pointer, e));
+                    error_sink.push(ConfigError::new(pointer, e));
                     None
                 }
             })
@@ -1630,6 +2471,12 @@ fn field_props(field: &str, ty: &str, doc: &[&str], default: &str) -> serde_json
         "FxHashMap<String, String>" => set! {
             "type": "object",
         },
+        "FxHashMap<String, CrateTargetDef>" => set! {
+            "type": "object",
+        },
+        "FxHashMap<String, serde_json::Value>" => set! {
+            "type": "object",
+        },
         "Option<usize>" => set! {
             "type": ["null", "integer"],
             "minimum": 0,
@@ -1738,6 +2585,19 @@ fn field_props(field: &str, ty: &str, doc: &[&str], default: &str) -> serde_json
                 "Only show mutable reborrow hints."
             ]
         },
+        "ReceiverBorrowHintsDef" => set! {
+            "type": "string",
+            "enum": [
+                "always",
+                "never",
+                "mutable"
+            ],
+            "enumDescriptions": [
+                "Always show how method calls borrow their receiver.",
+                "Never show how method calls borrow their receiver.",
+                "Only show receivers that are borrowed mutably."
+            ]
+        },
         "CargoFeatures" => set! {
             "anyOf": [
                 {
@@ -1773,6 +2633,65 @@ fn field_props(field: &str, ty: &str, doc: &[&str], default: &str) -> serde_json
                 { "type": "null" }
             ],
         },
+        "OutputFormatDef" => set! {
+            "anyOf": [
+                {
+                    "type": "string",
+                    "enum": ["json", "embedded_json"],
+                    "enumDescriptions": [
+                        "Cargo or rustc JSON messages, one per line.",
+                        "Rustc JSON messages mixed with other output."
+                    ]
+                },
+                {
+                    "type": "object",
+                    "properties": {
+                        "pattern": { "type": "string" }
+                    },
+                    "required": ["pattern"]
+                },
+                {
+                    "type": "object",
+                    "properties": {
+                        "jsonPointers": {
+                            "type": "object",
+                            "properties": {
+                                "file": { "type": "string" },
+                                "line": { "type": "string" },
+                                "column": { "type": "string" },
+                                "endLine": { "type": "string" },
+                                "endColumn": { "type": "string" },
+                                "severity": { "type": "string" },
+                                "code": { "type": "string" },
+                                "message": { "type": "string" }
+                            },
+                            "required": ["file", "line", "message"]
+                        }
+                    },
+                    "required": ["jsonPointers"]
+                }
+            ],
+        },
+        "Option<DiscoverWorkspaceConfig>" => set! {
+            "anyOf": [
+                { "type": "null" },
+                {
+                    "type": "object",
+                    "properties": {
+                        "command": {
+                            "type": "array",
+                            "items": { "type": "string" }
+                        },
+                        "progressLabel": { "type": "string" },
+                        "filesToWatch": {
+                            "type": "array",
+                            "items": { "type": "string" }
+                        }
+                    },
+                    "required": ["command"]
+                }
+            ],
+        },
         "CallableCompletionDef" => set! {
             "type": "string",
             "enum": [
@@ -1786,6 +2705,15 @@ fn field_props(field: &str, ty: &str, doc: &[&str], default: &str) -> serde_json
                 "Do no snippet completions for callables."
             ]
         },
+        "ServerTraceVerbosity" => set! {
+            "type": "string",
+            "enum": ["off", "requests", "verbose"],
+            "enumDescriptions": [
+                "Don't report any events.",
+                "Report the duration of every request.",
+                "Also report every turn of the main loop."
+            ],
+        },
         "SignatureDetail" => set! {
             "type": "string",
             "enum": ["full", "parameters"],
@@ -1901,6 +2829,162 @@ mod tests {
         ensure_file_contents(&docs_path, &expected);
     }
 
+    #[test]
+    fn local_config_overrides_client_config() {
+        let mut json = serde_json::json!({
+            "cargo": { "features": ["client"], "noDefaultFeatures": true },
+            "checkOnSave": { "enable": true },
+        });
+        let local: serde_json::Value =
+            toml::from_str("[cargo]\nfeatures = [\"local\"]\n[procMacro]\nenable = false\n")
+                .unwrap();
+        merge_json(&mut json, local);
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "cargo": { "features": ["local"], "noDefaultFeatures": true },
+                "checkOnSave": { "enable": true },
+                "procMacro": { "enable": false },
+            })
+        );
+    }
+
+    #[test]
+    fn workspace_folder_config_applies_to_its_files() {
+        let root = AbsPathBuf::assert(project_root());
+        let folder = root.join("crates");
+        let mut config = Config::new(root.clone(), ClientCapabilities::default());
+        config.update(serde_json::json!({ "cargo": { "noDefaultFeatures": false } })).unwrap();
+        config
+            .update_folders(vec![(
+                folder.clone(),
+                serde_json::json!({ "cargo": { "noDefaultFeatures": true } }),
+            )])
+            .unwrap();
+
+        assert!(!config.cargo().no_default_features);
+        assert!(!config.for_path(&root.join("xtask")).cargo().no_default_features);
+        assert!(config.for_path(&folder.join("ide")).cargo().no_default_features);
+    }
+
+    #[test]
+    fn outdated_keys_are_reported() {
+        let root = AbsPathBuf::assert(project_root());
+        let mut config = Config::new(root, ClientCapabilities::default());
+        config
+            .update(serde_json::json!({
+                "lruCapacity": 64,
+                "cargo": { "allFeatures": true },
+                "hover": { "documentation": { "enable": false } },
+            }))
+            .unwrap();
+
+        assert_eq!(config.lru_capacity(), Some(64));
+        assert_eq!(
+            config.outdated_keys(),
+            [
+                ("cargo.allFeatures".to_string(), "cargo.features".to_string()),
+                ("lruCapacity".to_string(), "lru.capacity".to_string()),
+            ]
+        );
+        config.update(serde_json::json!({ "lru": { "capacity": 64 } })).unwrap();
+        assert!(config.outdated_keys().is_empty());
+    }
+
+    #[test]
+    fn config_errors_name_the_key() {
+        let root = AbsPathBuf::assert(project_root());
+        let mut config = Config::new(root, ClientCapabilities::default());
+        let error = config
+            .update(serde_json::json!({
+                "cargo": { "featres": ["a"], "noDefaultFeatures": "yes" },
+                "imports": { "granularity": { "group": "everything" } },
+                "server": { "path": "/usr/bin/rust-analyzer" },
+            }))
+            .unwrap_err();
+
+        let errors: Vec<_> = error
+            .errors()
+            .iter()
+            .map(|e| (e.key.as_str(), e.expected.as_deref(), e.suggestion.as_deref()))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                ("/cargo/featres", None, Some("/cargo/features")),
+                ("/cargo/noDefaultFeatures", Some("boolean"), None),
+                (
+                    "/imports/granularity/group",
+                    Some(r#""preserve" or "crate" or "module" or "item""#),
+                    None
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn effective_config_fills_in_defaults() {
+        let root = AbsPathBuf::assert(project_root());
+        let mut config = Config::new(root, ClientCapabilities::default());
+        let _ = config.update(serde_json::json!({
+            "lruCapacity": 64,
+            "checkOnSave": { "enable": "sometimes" },
+        }));
+
+        let effective = config.effective_config();
+        assert_eq!(effective["rust-analyzer.lru.capacity"], 64);
+        assert_eq!(effective["rust-analyzer.checkOnSave.enable"], true);
+        assert_eq!(effective["rust-analyzer.cargo.features"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn globs() {
+        assert!(glob_matches("benches/**", "benches"));
+        assert!(glob_matches("benches/**", "benches/foo/bar.rs"));
+        assert!(!glob_matches("benches/**", "src/benches.rs"));
+        assert!(glob_matches("**/tests/*.rs", "crates/foo/tests/it.rs"));
+        assert!(!glob_matches("**/tests/*.rs", "crates/foo/tests/data/it.rs"));
+        assert!(glob_matches("vendor/lib?/*", "vendor/lib2/Cargo.toml"));
+    }
+
+    #[test]
+    fn path_overrides_apply_to_matching_files() {
+        let root = AbsPathBuf::assert(project_root());
+        let mut config = Config::new(root.clone(), ClientCapabilities::default());
+        config
+            .update(serde_json::json!({
+                "checkOnSave": { "enable": true },
+                "overrides": {
+                    "bench/**": { "checkOnSave": { "enable": false } },
+                    "bench/keep/**": { "checkOnSave": { "enable": true } },
+                },
+            }))
+            .unwrap();
+
+        assert!(config.flycheck().is_some());
+        assert!(config.for_path(&root.join("src/lib.rs")).flycheck().is_some());
+        assert!(config.for_path(&root.join("bench/lib.rs")).flycheck().is_none());
+        assert!(config.for_path(&root.join("bench/keep/lib.rs")).flycheck().is_some());
+    }
+
+    #[test]
+    fn path_overrides_set_cfgs() {
+        let root = AbsPathBuf::assert(project_root());
+        let mut config = Config::new(root.clone(), ClientCapabilities::default());
+        let before = config.cfgs_by_path().into_iter().map(|(_, _, it)| it.to_vec()).collect_vec();
+        config
+            .update(serde_json::json!({
+                "cargo": { "cfgs": ["foo"] },
+                "overrides": { "vendor/**": { "cargo": { "cfgs": ["bar", "baz=\"1\""] } } },
+            }))
+            .unwrap();
+
+        assert_eq!(config.for_path(&root.join("src/lib.rs")).cfgs(), ["foo"]);
+        assert_eq!(config.for_path(&root.join("vendor/a/src/lib.rs")).cfgs(), ["bar", "baz=\"1\""]);
+        let after = config.cfgs_by_path().into_iter().map(|(_, _, it)| it.to_vec()).collect_vec();
+        assert_ne!(before, after);
+    }
+
     fn remove_ws(text: &str) -> String {
         text.replace(char::is_whitespace, "")
     }
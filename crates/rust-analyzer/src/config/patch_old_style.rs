@@ -4,8 +4,11 @@ use serde_json::{json, Value};
 /// This function patches the json config to the new expected keys.
 /// That is we try to load old known config keys here and convert them to the new ones.
 /// See https://github.com/rust-lang/rust-analyzer/pull/12010
-pub(super) fn patch_json_for_outdated_configs(json: &mut Value) {
+///
+/// Returns the outdated keys that were found, together with the keys replacing them.
+pub(super) fn patch_json_for_outdated_configs(json: &mut Value) -> Vec<(String, String)> {
     let copy = json.clone();
+    let mut patched = Vec::new();
 
     macro_rules! patch {
         ($(
@@ -14,6 +17,10 @@ pub(super) fn patch_json_for_outdated_configs(json: &mut Value) {
             match copy.pointer(concat!($("/", stringify!($src)),+)).cloned() {
                 Some(Value::Object(_)) | None => (),
                 Some(it) => {
+                    patched.push((
+                        [$(stringify!($src)),+].join("."),
+                        [$(stringify!($dst)),+].join("."),
+                    ));
                     let mut last = it;
                     for segment in [$(stringify!($dst)),+].into_iter().rev() {
                         last = Value::Object(serde_json::Map::from_iter(std::iter::once((segment.to_string(), last))));
@@ -72,6 +79,7 @@ pub(super) fn patch_json_for_outdated_configs(json: &mut Value) {
     // completion.snippets -> completion.snippets.custom;
     if let Some(Value::Object(obj)) = copy.pointer("/completion/snippets").cloned() {
         if obj.len() != 1 || obj.get("custom").is_none() {
+            patched.push(("completion.snippets".into(), "completion.snippets.custom".into()));
             merge(
                 json,
                 json! {{
@@ -87,6 +95,10 @@ pub(super) fn patch_json_for_outdated_configs(json: &mut Value) {
 
     // callInfo_full -> signatureInfo_detail, signatureInfo_documentation_enable
     if let Some(Value::Bool(b)) = copy.pointer("/callInfo/full") {
+        patched.push((
+            "callInfo.full".into(),
+            "signatureInfo.detail, signatureInfo.documentation.enable".into(),
+        ));
         let sig_info = match b {
             true => json!({ "signatureInfo": {
                 "documentation": {"enable": true}},
@@ -102,11 +114,13 @@ pub(super) fn patch_json_for_outdated_configs(json: &mut Value) {
 
     // cargo_allFeatures, cargo_features -> cargo_features
     if let Some(Value::Bool(true)) = copy.pointer("/cargo/allFeatures") {
+        patched.push(("cargo.allFeatures".into(), "cargo.features".into()));
         merge(json, json!({ "cargo": { "features": "all" } }));
     }
 
     // checkOnSave_allFeatures, checkOnSave_features -> checkOnSave_features
     if let Some(Value::Bool(true)) = copy.pointer("/checkOnSave/allFeatures") {
+        patched.push(("checkOnSave.allFeatures".into(), "checkOnSave.features".into()));
         merge(json, json!({ "checkOnSave": { "features": "all" } }));
     }
 
@@ -118,9 +132,14 @@ pub(super) fn patch_json_for_outdated_configs(json: &mut Value) {
         (Some(Value::Bool(true)), Some(Value::Bool(true))) => json!("fill_arguments"),
         (Some(Value::Bool(true)), _) => json!("add_parentheses"),
         (Some(Value::Bool(false)), Some(Value::Bool(false))) => json!("add_parentheses"),
-        (_, _) => return,
+        (_, _) => return patched,
     };
+    patched.push((
+        "completion.addCallArgumentSnippets, completion.addCallParenthesis".into(),
+        "completion.callable.snippets".into(),
+    ));
     merge(json, json!({ "completion": { "callable": {"snippets": res }} }));
+    patched
 }
 
 fn merge(dst: &mut Value, src: Value) {
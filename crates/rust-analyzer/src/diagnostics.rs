@@ -9,12 +9,32 @@ use rustc_hash::{FxHashMap, FxHashSet};
 use crate::lsp_ext;
 
 pub(crate) type CheckFixes = Arc<FxHashMap<FileId, Vec<Fix>>>;
+pub(crate) type CheckDiagnostics = Arc<FxHashMap<FileId, Vec<lsp_types::Diagnostic>>>;
 
 #[derive(Debug, Default, Clone)]
 pub struct DiagnosticsMapConfig {
     pub remap_prefix: FxHashMap<String, String>,
     pub warnings_as_info: Vec<String>,
     pub warnings_as_hint: Vec<String>,
+    /// Severity overrides applying to clippy lints only.
+    pub clippy_warnings_as_info: Vec<String>,
+    pub clippy_warnings_as_hint: Vec<String>,
+}
+
+/// The diagnostic on a generated file of `size` bytes left out of the analysis because of
+/// `files.generatedFileSizeLimit`.
+pub(crate) fn excluded_generated_file(size: usize) -> lsp_types::Diagnostic {
+    lsp_types::Diagnostic {
+        range: lsp_types::Range::default(),
+        severity: Some(lsp_types::DiagnosticSeverity::INFORMATION),
+        source: Some("rust-analyzer".to_string()),
+        message: format!(
+            "generated file of {} bytes is excluded from the analysis, \
+             it exceeds `rust-analyzer.files.generatedFileSizeLimit`",
+            size
+        ),
+        ..Default::default()
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -22,7 +42,7 @@ pub(crate) struct DiagnosticCollection {
     // FIXME: should be FxHashMap<FileId, Vec<ra_id::Diagnostic>>
     pub(crate) native: FxHashMap<FileId, Vec<lsp_types::Diagnostic>>,
     // FIXME: should be Vec<flycheck::Diagnostic>
-    pub(crate) check: FxHashMap<FileId, Vec<lsp_types::Diagnostic>>,
+    pub(crate) check: CheckDiagnostics,
     pub(crate) check_fixes: CheckFixes,
     changes: FxHashSet<FileId>,
 }
@@ -37,7 +57,23 @@ pub(crate) struct Fix {
 impl DiagnosticCollection {
     pub(crate) fn clear_check(&mut self) {
         Arc::make_mut(&mut self.check_fixes).clear();
-        self.changes.extend(self.check.drain().map(|(key, _value)| key))
+        self.changes.extend(Arc::make_mut(&mut self.check).drain().map(|(key, _value)| key))
+    }
+
+    /// Clears the check diagnostics of the files `filter` returns `true` for.
+    pub(crate) fn clear_check_for(&mut self, filter: impl Fn(FileId) -> bool) {
+        Arc::make_mut(&mut self.check_fixes).retain(|&file_id, _| !filter(file_id));
+        let check = Arc::make_mut(&mut self.check);
+        let cleared: Vec<_> = check.keys().copied().filter(|&file_id| filter(file_id)).collect();
+        for file_id in cleared {
+            check.remove(&file_id);
+            self.changes.insert(file_id);
+        }
+    }
+
+    /// Marks all files with diagnostics as changed, so that they are sent to the client again.
+    pub(crate) fn mark_all_changed(&mut self) {
+        self.changes.extend(self.native.keys().chain(self.check.keys()).copied());
     }
 
     pub(crate) fn clear_native_for(&mut self, file_id: FileId) {
@@ -51,7 +87,7 @@ impl DiagnosticCollection {
         diagnostic: lsp_types::Diagnostic,
         fix: Option<Fix>,
     ) {
-        let diagnostics = self.check.entry(file_id).or_default();
+        let diagnostics = Arc::make_mut(&mut self.check).entry(file_id).or_default();
         for existing_diagnostic in diagnostics.iter() {
             if are_diagnostics_equal(existing_diagnostic, &diagnostic) {
                 return;
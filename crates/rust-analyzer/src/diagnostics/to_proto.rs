@@ -3,12 +3,13 @@
 use std::collections::HashMap;
 
 use flycheck::{Applicability, DiagnosticLevel, DiagnosticSpan};
+use ide::WideEncoding;
 use itertools::Itertools;
 use stdx::format_to;
 use vfs::{AbsPath, AbsPathBuf};
 
 use crate::{
-    global_state::GlobalStateSnapshot, line_index::OffsetEncoding, lsp_ext,
+    global_state::GlobalStateSnapshot, line_index::PositionEncoding, lsp_ext,
     to_proto::url_from_abs_path,
 };
 
@@ -24,6 +25,24 @@ fn diagnostic_severity(
         DiagnosticLevel::Ice => lsp_types::DiagnosticSeverity::ERROR,
         DiagnosticLevel::Error => lsp_types::DiagnosticSeverity::ERROR,
         DiagnosticLevel::Warning => match &code {
+            Some(code)
+                if code.code.starts_with("clippy::")
+                    && config
+                        .clippy_warnings_as_hint
+                        .iter()
+                        .any(|lint| ide_db::helpers::lint_eq_or_in_group(&code.code, lint)) =>
+            {
+                lsp_types::DiagnosticSeverity::HINT
+            }
+            Some(code)
+                if code.code.starts_with("clippy::")
+                    && config
+                        .clippy_warnings_as_info
+                        .iter()
+                        .any(|lint| ide_db::helpers::lint_eq_or_in_group(&code.code, lint)) =>
+            {
+                lsp_types::DiagnosticSeverity::INFORMATION
+            }
             // HACK: special case for `warnings` rustc lint.
             Some(code)
                 if config.warnings_as_hint.iter().any(|lint| {
@@ -66,17 +85,17 @@ fn location(
     let uri = url_from_abs_path(&file_name);
 
     let range = {
-        let offset_encoding = snap.config.offset_encoding();
+        let position_encoding = snap.config.position_encoding();
         lsp_types::Range::new(
-            position(&offset_encoding, span, span.line_start, span.column_start),
-            position(&offset_encoding, span, span.line_end, span.column_end),
+            position(position_encoding, span, span.line_start, span.column_start),
+            position(position_encoding, span, span.line_end, span.column_end),
         )
     };
     lsp_types::Location::new(uri, range)
 }
 
 fn position(
-    offset_encoding: &OffsetEncoding,
+    position_encoding: PositionEncoding,
     span: &DiagnosticSpan,
     line_offset: usize,
     column_offset: usize,
@@ -93,9 +112,10 @@ fn position(
             };
         }
         let mut char_offset = 0;
-        let len_func = match offset_encoding {
-            OffsetEncoding::Utf8 => char::len_utf8,
-            OffsetEncoding::Utf16 => char::len_utf16,
+        let len_func: fn(char) -> usize = match position_encoding {
+            PositionEncoding::Utf8 => char::len_utf8,
+            PositionEncoding::Wide(WideEncoding::Utf16) => char::len_utf16,
+            PositionEncoding::Wide(WideEncoding::Utf32) => |_| 1,
         };
         for c in line.text.chars() {
             char_offset += 1;
@@ -1077,6 +1097,127 @@ mod tests {
         );
     }
 
+    #[test]
+    fn clippy_pass_by_ref_as_hint() {
+        check_with_config(
+            DiagnosticsMapConfig {
+                clippy_warnings_as_hint: vec!["clippy::trivially_copy_pass_by_ref".to_string()],
+                ..DiagnosticsMapConfig::default()
+            },
+            r##"{
+    "message": "this argument is passed by reference, but would be more efficient if passed by value",
+    "code": {
+        "code": "clippy::trivially_copy_pass_by_ref",
+        "explanation": null
+    },
+    "level": "warning",
+    "spans": [
+        {
+            "file_name": "compiler/mir/tagset.rs",
+            "byte_start": 941,
+            "byte_end": 946,
+            "line_start": 42,
+            "line_end": 42,
+            "column_start": 24,
+            "column_end": 29,
+            "is_primary": true,
+            "text": [
+                {
+                    "text": "    pub fn is_disjoint(&self, other: Self) -> bool {",
+                    "highlight_start": 24,
+                    "highlight_end": 29
+                }
+            ],
+            "label": null,
+            "suggested_replacement": null,
+            "suggestion_applicability": null,
+            "expansion": null
+        }
+    ],
+    "children": [
+        {
+            "message": "lint level defined here",
+            "code": null,
+            "level": "note",
+            "spans": [
+                {
+                    "file_name": "compiler/lib.rs",
+                    "byte_start": 8,
+                    "byte_end": 19,
+                    "line_start": 1,
+                    "line_end": 1,
+                    "column_start": 9,
+                    "column_end": 20,
+                    "is_primary": true,
+                    "text": [
+                        {
+                            "text": "#![warn(clippy::all)]",
+                            "highlight_start": 9,
+                            "highlight_end": 20
+                        }
+                    ],
+                    "label": null,
+                    "suggested_replacement": null,
+                    "suggestion_applicability": null,
+                    "expansion": null
+                }
+            ],
+            "children": [],
+            "rendered": null
+        },
+        {
+            "message": "#[warn(clippy::trivially_copy_pass_by_ref)] implied by #[warn(clippy::all)]",
+            "code": null,
+            "level": "note",
+            "spans": [],
+            "children": [],
+            "rendered": null
+        },
+        {
+            "message": "for further information visit https://rust-lang.github.io/rust-clippy/master/index.html#trivially_copy_pass_by_ref",
+            "code": null,
+            "level": "help",
+            "spans": [],
+            "children": [],
+            "rendered": null
+        },
+        {
+            "message": "consider passing by value instead",
+            "code": null,
+            "level": "help",
+            "spans": [
+                {
+                    "file_name": "compiler/mir/tagset.rs",
+                    "byte_start": 941,
+                    "byte_end": 946,
+                    "line_start": 42,
+                    "line_end": 42,
+                    "column_start": 24,
+                    "column_end": 29,
+                    "is_primary": true,
+                    "text": [
+                        {
+                            "text": "    pub fn is_disjoint(&self, other: Self) -> bool {",
+                            "highlight_start": 24,
+                            "highlight_end": 29
+                        }
+                    ],
+                    "label": null,
+                    "suggested_replacement": "self",
+                    "suggestion_applicability": "Unspecified",
+                    "expansion": null
+                }
+            ],
+            "children": [],
+            "rendered": null
+        }
+    ],
+    "rendered": "warning: this argument is passed by reference, but would be more efficient if passed by value\n  --> compiler/mir/tagset.rs:42:24\n   |\n42 |     pub fn is_disjoint(&self, other: Self) -> bool {\n   |                        ^^^^^ help: consider passing by value instead: `self`\n   |\nnote: lint level defined here\n  --> compiler/lib.rs:1:9\n   |\n1  | #![warn(clippy::all)]\n   |         ^^^^^^^^^^^\n   = note: #[warn(clippy::trivially_copy_pass_by_ref)] implied by #[warn(clippy::all)]\n   = help: for further information visit https://rust-lang.github.io/rust-clippy/master/index.html#trivially_copy_pass_by_ref\n\n"
+    }"##,
+            expect_file!["./test_data/clippy_pass_by_ref_as_hint.txt"],
+        );
+    }
+
     #[test]
     fn rustc_range_map_lsp_position() {
         check(
@@ -0,0 +1,42 @@
+//! Runs the user-specified `rust-analyzer.workspace.discoverConfig` command,
+//! which generates a `rust-project.json` for build systems other than Cargo
+//! (Buck, Bazel, ...).
+
+use std::process::Command;
+
+use anyhow::{bail, format_err, Context};
+use project_model::{ProjectJson, ProjectJsonData};
+use vfs::{AbsPath, AbsPathBuf};
+
+use crate::config::DiscoverWorkspaceConfig;
+
+#[derive(Debug)]
+pub(crate) enum DiscoverProjectMessage {
+    Begin,
+    Finished { arg: AbsPathBuf, project: ProjectJson },
+    Error { arg: AbsPathBuf, error: String },
+    End,
+}
+
+/// Runs the discover command for `arg`, resolving relative paths of the
+/// produced project against `root`.
+pub(crate) fn discover_project(
+    config: &DiscoverWorkspaceConfig,
+    root: &AbsPath,
+    arg: &AbsPath,
+) -> anyhow::Result<ProjectJson> {
+    let (program, args) =
+        config.command.split_first().ok_or_else(|| format_err!("discover command is empty"))?;
+    let arg_str = arg.display().to_string();
+    let mut cmd = Command::new(program);
+    cmd.current_dir(root).args(args.iter().map(|it| it.replace("{arg}", &arg_str)));
+
+    let output = cmd.output().with_context(|| format!("{:?} failed", cmd))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("{:?} failed, {}\nstderr:\n{}", cmd, output.status, stderr);
+    }
+    let data: ProjectJsonData = serde_json::from_slice(&output.stdout)
+        .with_context(|| format!("{:?} did not print a valid rust-project.json", cmd))?;
+    Ok(ProjectJson::new(root, data))
+}
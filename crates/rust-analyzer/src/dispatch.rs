@@ -2,12 +2,14 @@
 use std::{fmt, panic, thread};
 
 use lsp_server::ExtractError;
+use lsp_types::Url;
 use serde::{de::DeserializeOwned, Serialize};
 
 use crate::{
     global_state::{GlobalState, GlobalStateSnapshot},
     lsp_utils::is_cancelled,
     main_loop::Task,
+    task_pool::Priority,
     LspError, Result,
 };
 
@@ -24,7 +26,11 @@ use crate::{
 /// `&mut` (`on_sync_mut`).
 ///
 /// Read-only requests are wrapped into `catch_unwind` -- they don't modify the
-/// state, so it's OK to recover from their failures.
+/// state, so it's OK to recover from their failures. A panic is answered with
+/// an `InternalError` carrying the panic message and the panic context frames,
+/// that is the request and whatever the handler entered with
+/// `stdx::panic_context::enter`. Salsa discards the memo of the
+/// query that was unwound, so only that query is recomputed by later requests.
 pub(crate) struct RequestDispatcher<'a> {
     pub(crate) req: Option<lsp_server::Request>,
     pub(crate) global_state: &'a mut GlobalState,
@@ -47,6 +53,7 @@ impl<'a> RequestDispatcher<'a> {
             Some(it) => it,
             None => return Ok(self),
         };
+        let _span = request_span::<R>(&id).entered();
         let _pctx = stdx::panic_context::enter(panic_context);
 
         let result = f(self.global_state, params);
@@ -71,6 +78,7 @@ impl<'a> RequestDispatcher<'a> {
             None => return Ok(self),
         };
         let global_state_snapshot = self.global_state.snapshot();
+        let _span = request_span::<R>(&id).entered();
 
         let result = panic::catch_unwind(move || {
             let _pctx = stdx::panic_context::enter(panic_context);
@@ -82,7 +90,11 @@ impl<'a> RequestDispatcher<'a> {
         Ok(self)
     }
 
-    /// Dispatches the request onto thread pool
+    /// Dispatches the request onto the interactive lane of the thread pool.
+    ///
+    /// If the request is about a document that gets edited or closed before a
+    /// worker picks it up, it is answered with `ContentModified` without running,
+    /// see [`TaskPool::drop_stale`](crate::task_pool::TaskPool::drop_stale).
     pub(crate) fn on<R>(
         &mut self,
         f: fn(GlobalStateSnapshot, R::Params) -> Result<R::Result>,
@@ -92,14 +104,21 @@ impl<'a> RequestDispatcher<'a> {
         R::Params: DeserializeOwned + panic::UnwindSafe + Send + fmt::Debug,
         R::Result: Serialize,
     {
+        let document = self.req.as_ref().and_then(|req| {
+            let uri = req.params.pointer("/textDocument/uri")?.as_str()?;
+            Url::parse(uri).ok()
+        });
         let (id, params, panic_context) = match self.parse::<R>() {
             Some(it) => it,
             None => return self,
         };
 
-        self.global_state.task_pool.handle.spawn({
+        let task = {
             let world = self.global_state.snapshot();
+            let span = request_span::<R>(&id);
+            let id = id.clone();
             move || {
+                let _span = span.entered();
                 let result = panic::catch_unwind(move || {
                     let _pctx = stdx::panic_context::enter(panic_context);
                     f(world, params)
@@ -107,7 +126,18 @@ impl<'a> RequestDispatcher<'a> {
                 let response = thread_result_to_response::<R>(id, result);
                 Task::Response(response)
             }
-        });
+        };
+        let pool = &mut self.global_state.task_pool.handle;
+        match document {
+            Some(document) => pool.spawn_for_document(document, task, move || {
+                Task::Response(lsp_server::Response::new_err(
+                    id,
+                    lsp_server::ErrorCode::ContentModified as i32,
+                    "content modified".to_string(),
+                ))
+            }),
+            None => pool.spawn(Priority::Interactive, task),
+        }
 
         self
     }
@@ -154,6 +184,12 @@ impl<'a> RequestDispatcher<'a> {
     }
 }
 
+/// The span all the work done for a request is recorded in, so that the traces of
+/// concurrent requests can be told apart.
+fn request_span<R: lsp_types::request::Request>(id: &lsp_server::RequestId) -> tracing::Span {
+    tracing::info_span!("request", method = R::METHOD, id = %id)
+}
+
 fn thread_result_to_response<R>(
     id: lsp_server::RequestId,
     result: thread::Result<Result<R::Result>>,
@@ -178,7 +214,18 @@ where
                 message.push_str(panic_message)
             };
 
-            lsp_server::Response::new_err(id, lsp_server::ErrorCode::InternalError as i32, message)
+            let context = stdx::panic_context::take_last_panic_context();
+            tracing::error!("{} ({}), context:\n{}", message, R::METHOD, context.join("\n"));
+
+            lsp_server::Response {
+                id,
+                result: None,
+                error: Some(lsp_server::ResponseError {
+                    code: lsp_server::ErrorCode::InternalError as i32,
+                    message,
+                    data: Some(serde_json::json!({ "panicContext": context })),
+                }),
+            }
         }
     }
 }
@@ -3,7 +3,10 @@
 //!
 //! Each tick provides an immutable snapshot of the state as `WorldSnapshot`.
 
-use std::{sync::Arc, time::Instant};
+use std::{
+    sync::{atomic::AtomicBool, Arc},
+    time::Instant,
+};
 
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use flycheck::FlycheckHandle;
@@ -12,13 +15,15 @@ use ide_db::base_db::{CrateId, FileLoader, SourceDatabase};
 use lsp_types::{SemanticTokens, Url};
 use parking_lot::{Mutex, RwLock};
 use proc_macro_api::ProcMacroServer;
-use project_model::{CargoWorkspace, ProjectWorkspace, Target, WorkspaceBuildScripts};
-use rustc_hash::FxHashMap;
-use vfs::AnchoredPathBuf;
+use project_model::{
+    Build, CargoWorkspace, ProjectJson, ProjectWorkspace, Target, WorkspaceBuildScripts,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use vfs::{AbsPathBuf, AnchoredPathBuf};
 
 use crate::{
-    config::Config,
-    diagnostics::{CheckFixes, DiagnosticCollection},
+    config::{Config, ServerTraceVerbosity},
+    diagnostics::{CheckDiagnostics, CheckFixes, DiagnosticCollection},
     from_proto,
     line_index::{LineEndings, LineIndex},
     lsp_ext,
@@ -62,6 +67,8 @@ pub(crate) struct GlobalState {
     pub(crate) last_reported_status: Option<lsp_ext::ServerStatusParams>,
     pub(crate) source_root_config: SourceRootConfig,
     pub(crate) proc_macro_client: Option<ProcMacroServer>,
+    /// Why the proc macros of some crates couldn't be loaded into the current crate graph.
+    pub(crate) proc_macro_load_errors: Vec<String>,
 
     pub(crate) flycheck: Vec<FlycheckHandle>,
     pub(crate) flycheck_sender: Sender<flycheck::Message>,
@@ -100,10 +107,37 @@ pub(crate) struct GlobalState {
     /// to invalidate any salsa caches.
     pub(crate) workspaces: Arc<Vec<ProjectWorkspace>>,
     pub(crate) fetch_workspaces_queue: OpQueue<Vec<anyhow::Result<ProjectWorkspace>>>,
+    /// Files whose change requested the pending workspace fetch, `None` if all workspaces have
+    /// to be fetched again.
+    pub(crate) workspace_changes: Option<FxHashSet<AbsPathBuf>>,
     pub(crate) fetch_build_data_queue:
         OpQueue<(Arc<Vec<ProjectWorkspace>>, Vec<anyhow::Result<WorkspaceBuildScripts>>)>,
+    /// Whether the pending build data fetch may keep the build script results of workspaces
+    /// that were not fetched again.
+    pub(crate) keep_build_scripts: bool,
 
     pub(crate) prime_caches_queue: OpQueue<()>,
+    /// The crates the requested cache priming is restricted to, `None` primes the whole
+    /// workspace.
+    pub(crate) prime_caches_only: Option<Vec<CrateId>>,
+    /// Set when the client cancels the running cache priming.
+    pub(crate) prime_caches_cancel: Arc<AtomicBool>,
+
+    /// When the memory usage was last compared against `memory.evictionThreshold`.
+    pub(crate) last_memory_check: Instant,
+    /// The memory usage in megabytes after the caches were last evicted, while the LRU caches
+    /// are shrunk because the memory usage crossed the threshold.
+    pub(crate) memory_after_eviction: Option<isize>,
+
+    /// Generated files left out of the VFS because of `files.generatedFileSizeLimit`, with
+    /// their size.
+    pub(crate) excluded_generated_files: Arc<FxHashMap<AbsPathBuf, usize>>,
+
+    /// Projects generated by `workspace.discoverConfig`, keyed by the file they were
+    /// discovered for.
+    pub(crate) discovered_workspaces: FxHashMap<AbsPathBuf, ProjectJson>,
+    /// Directories of the files `workspace.discoverConfig` is currently running for.
+    pub(crate) discovering_dirs: FxHashSet<AbsPathBuf>,
 }
 
 /// An immutable snapshot of the world's state at a point in time.
@@ -111,6 +145,8 @@ pub(crate) struct GlobalStateSnapshot {
     pub(crate) config: Arc<Config>,
     pub(crate) analysis: Analysis,
     pub(crate) check_fixes: CheckFixes,
+    pub(crate) check_diagnostics: CheckDiagnostics,
+    pub(crate) excluded_generated_files: Arc<FxHashMap<AbsPathBuf, usize>>,
     mem_docs: MemDocs,
     pub(crate) semantic_tokens_cache: Arc<Mutex<FxHashMap<Url, SemanticTokens>>>,
     vfs: Arc<RwLock<(vfs::Vfs, FxHashMap<FileId, LineEndings>)>>,
@@ -152,6 +188,7 @@ impl GlobalState {
             last_reported_status: None,
             source_root_config: SourceRootConfig::default(),
             proc_macro_client: None,
+            proc_macro_load_errors: Vec::new(),
 
             flycheck: Vec::new(),
             flycheck_sender,
@@ -165,9 +202,20 @@ impl GlobalState {
 
             workspaces: Arc::new(Vec::new()),
             fetch_workspaces_queue: OpQueue::default(),
+            workspace_changes: None,
             prime_caches_queue: OpQueue::default(),
+            prime_caches_only: None,
+            prime_caches_cancel: Arc::default(),
+
+            last_memory_check: Instant::now(),
+            memory_after_eviction: None,
+
+            excluded_generated_files: Arc::default(),
+            discovered_workspaces: FxHashMap::default(),
+            discovering_dirs: FxHashSet::default(),
 
             fetch_build_data_queue: OpQueue::default(),
+            keep_build_scripts: false,
         };
         // Apply any required database inputs from the config.
         this.update_configuration(config);
@@ -177,6 +225,7 @@ impl GlobalState {
     pub(crate) fn process_changes(&mut self) -> bool {
         let _p = profile::span("GlobalState::process_changes");
         let mut fs_changes = Vec::new();
+        let mut workspace_changes = Vec::new();
         // A file was added or deleted
         let mut has_structure_changes = false;
 
@@ -192,8 +241,7 @@ impl GlobalState {
                 if let Some(path) = vfs.file_path(file.file_id).as_path() {
                     let path = path.to_path_buf();
                     if reload::should_refresh_for_change(&path, file.change_kind) {
-                        self.fetch_workspaces_queue
-                            .request_op(format!("vfs file change: {}", path.display()));
+                        workspace_changes.push(path.clone());
                     }
                     fs_changes.push((path, file.change_kind));
                     if file.is_created_or_deleted() {
@@ -223,6 +271,10 @@ impl GlobalState {
             }
             (change, changed_files)
         };
+        for path in workspace_changes {
+            let cause = format!("vfs file change: {}", path.display());
+            self.request_workspace_reload_for(path, cause);
+        }
 
         self.analysis_host.apply_change(change);
 
@@ -244,6 +296,8 @@ impl GlobalState {
             analysis: self.analysis_host.analysis(),
             vfs: Arc::clone(&self.vfs),
             check_fixes: Arc::clone(&self.diagnostics.check_fixes),
+            check_diagnostics: Arc::clone(&self.diagnostics.check),
+            excluded_generated_files: Arc::clone(&self.excluded_generated_files),
             mem_docs: self.mem_docs.clone(),
             semantic_tokens_cache: Arc::clone(&self.semantic_tokens_cache),
         }
@@ -295,6 +349,14 @@ impl GlobalState {
 
             let duration = start.elapsed();
             tracing::debug!("handled {} - ({}) in {:0.2?}", method, response.id, duration);
+            if self.config.server_trace() >= ServerTraceVerbosity::Requests {
+                self.send_notification::<lsp_ext::ServerTrace>(lsp_ext::ServerTraceParams {
+                    kind: lsp_ext::ServerTraceKind::Request,
+                    name: method,
+                    id: Some(response.id.to_string()),
+                    duration_ms: duration.as_millis() as u64,
+                });
+            }
             self.send(response.into());
         }
     }
@@ -325,10 +387,18 @@ impl GlobalStateSnapshot {
         file_id_to_url(&self.vfs.read().0, id)
     }
 
+    /// The settings for the given file, see [`Config::for_path`].
+    pub(crate) fn file_config(&self, file_id: FileId) -> Config {
+        match self.vfs.read().0.file_path(file_id).as_path() {
+            Some(path) => self.config.for_path(path),
+            None => Config::clone(&self.config),
+        }
+    }
+
     pub(crate) fn file_line_index(&self, file_id: FileId) -> Cancellable<LineIndex> {
         let endings = self.vfs.read().1[&file_id];
         let index = self.analysis.file_line_index(file_id)?;
-        let res = LineIndex { index, endings, encoding: self.config.offset_encoding() };
+        let res = LineIndex { index, endings, encoding: self.config.position_encoding() };
         Ok(res)
     }
 
@@ -360,6 +430,21 @@ impl GlobalStateSnapshot {
             ProjectWorkspace::DetachedFiles { .. } => None,
         })
     }
+
+    pub(crate) fn json_build_for_crate_root(
+        &self,
+        crate_id: CrateId,
+    ) -> Option<(&ProjectJson, &Build)> {
+        let file_id = self.analysis.crate_root(crate_id).ok()?;
+        let path = self.vfs.read().0.file_path(file_id);
+        let path = path.as_path()?;
+        self.workspaces.iter().find_map(|ws| match ws {
+            ProjectWorkspace::Json { project, .. } => {
+                project.build_for_root(path).map(|it| (project, it))
+            }
+            ProjectWorkspace::Cargo { .. } | ProjectWorkspace::DetachedFiles { .. } => None,
+        })
+    }
 }
 
 pub(crate) fn file_id_to_url(vfs: &vfs::Vfs, id: FileId) -> Url {
@@ -3,15 +3,20 @@
 //! `ide` crate.
 
 use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
     io::Write as _,
+    path::{Path, PathBuf},
     process::{self, Stdio},
+    sync::Arc,
 };
 
 use anyhow::Context;
+use hir::symbols::FileSymbolKind;
 use ide::{
-    AnnotationConfig, AssistKind, AssistResolveStrategy, FileId, FilePosition, FileRange,
-    HoverAction, HoverGotoTypeData, Query, RangeInfo, Runnable, RunnableKind, SingleResolve,
-    SourceChange, TextEdit,
+    AnnotationConfig, AssistKind, AssistResolveStrategy, CrateGraphConfig, CrateGraphFormat,
+    FileId, FilePosition, FileRange, HoverAction, HoverGotoTypeData, Query, RangeInfo, Runnable,
+    RunnableKind, SingleResolve, SourceChange, TextEdit,
 };
 use ide_db::SymbolKind;
 use lsp_server::ErrorCode;
@@ -26,18 +31,19 @@ use lsp_types::{
     SymbolTag, TextDocumentIdentifier, Url, WorkspaceEdit,
 };
 use project_model::{ManifestPath, ProjectWorkspace, TargetKind};
+use rustc_hash::FxHasher;
 use serde_json::json;
 use stdx::{format_to, never};
 use syntax::{algo, ast, AstNode, TextRange, TextSize, T};
-use vfs::AbsPathBuf;
+use vfs::{AbsPathBuf, VfsPath};
 
 use crate::{
     cargo_target_spec::CargoTargetSpec,
     config::{RustfmtConfig, WorkspaceSymbolConfig},
     diff::diff,
     from_proto,
-    global_state::{GlobalState, GlobalStateSnapshot},
-    line_index::LineEndings,
+    global_state::{url_to_file_id, GlobalState, GlobalStateSnapshot},
+    line_index::{LineEndings, LineIndex},
     lsp_ext::{self, PositionOrRange, ViewCrateGraphParams, WorkspaceSymbolParams},
     lsp_utils::{all_edits_are_disjoint, invalid_params_error},
     to_proto, LspError, Result,
@@ -45,11 +51,61 @@ use crate::{
 
 pub(crate) fn handle_workspace_reload(state: &mut GlobalState, _: ()) -> Result<()> {
     state.proc_macro_client = None;
+    state.workspace_changes = None;
     state.fetch_workspaces_queue.request_op("reload workspace request".to_string());
+    state.keep_build_scripts = false;
     state.fetch_build_data_queue.request_op("reload workspace request".to_string());
     Ok(())
 }
 
+pub(crate) fn handle_proc_macros_rebuild(state: &mut GlobalState, _: ()) -> Result<()> {
+    // Running the build scripts also builds the proc macros, the proc macro server picks up the
+    // new dylibs as it keys them by their modification time.
+    state.keep_build_scripts = false;
+    state.fetch_build_data_queue.request_op("rebuild proc macros request".to_string());
+    Ok(())
+}
+
+pub(crate) fn handle_set_active_target(
+    state: &mut GlobalState,
+    params: lsp_ext::SetActiveTargetParams,
+) -> Result<()> {
+    let file_id = url_to_file_id(&state.vfs.read().0, &params.text_document.uri)?;
+    state.analysis_host.set_active_target(file_id, params.target);
+
+    // Everything about the file now comes from another crate.
+    if state.config.semantic_tokens_refresh() {
+        state.send_request::<lsp_types::request::SemanticTokensRefresh>((), |_, _| ());
+    }
+    if state.config.code_lens_refresh() {
+        state.send_request::<lsp_types::request::CodeLensRefresh>((), |_, _| ());
+    }
+    if state.config.pull_diagnostics() {
+        if state.config.diagnostics_refresh() {
+            state.send_request::<lsp_ext::WorkspaceDiagnosticRefresh>((), |_, _| ());
+        }
+    } else {
+        state.update_diagnostics();
+    }
+    Ok(())
+}
+
+pub(crate) fn handle_prime_caches(
+    state: &mut GlobalState,
+    params: lsp_ext::PrimeCachesParams,
+) -> Result<()> {
+    let only = match params.text_document {
+        Some(text_document) => {
+            let snap = state.snapshot();
+            let file_id = from_proto::file_id(&snap, &text_document.uri)?;
+            Some(snap.analysis.crate_for(file_id)?)
+        }
+        None => None,
+    };
+    state.request_prime_caches("prime caches request".to_string(), only);
+    Ok(())
+}
+
 pub(crate) fn handle_analyzer_status(
     snap: GlobalStateSnapshot,
     params: lsp_ext::AnalyzerStatusParams,
@@ -127,6 +183,36 @@ pub(crate) fn handle_view_hir(
     Ok(res)
 }
 
+pub(crate) fn handle_view_mir(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::TextDocumentPositionParams,
+) -> Result<String> {
+    let _p = profile::span("handle_view_mir");
+    let position = from_proto::file_position(&snap, params)?;
+    let res = snap.analysis.view_mir(position)?;
+    Ok(res)
+}
+
+pub(crate) fn handle_interpret_function(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::TextDocumentPositionParams,
+) -> Result<String> {
+    let _p = profile::span("handle_interpret_function");
+    let position = from_proto::file_position(&snap, params)?;
+    let res = snap.analysis.interpret_function(position)?;
+    Ok(res)
+}
+
+pub(crate) fn handle_evaluate_expression(
+    snap: GlobalStateSnapshot,
+    params: lsp_ext::EvaluateExpressionParams,
+) -> Result<String> {
+    let _p = profile::span("handle_evaluate_expression");
+    let frange = from_proto::file_range(&snap, params.text_document, params.range)?;
+    let res = snap.analysis.evaluate_expression(frange)?;
+    Ok(res)
+}
+
 pub(crate) fn handle_view_file_text(
     snap: GlobalStateSnapshot,
     params: lsp_types::TextDocumentIdentifier,
@@ -150,8 +236,51 @@ pub(crate) fn handle_view_crate_graph(
     params: ViewCrateGraphParams,
 ) -> Result<String> {
     let _p = profile::span("handle_view_crate_graph");
-    let dot = snap.analysis.view_crate_graph(params.full)??;
-    Ok(dot)
+    let reachable_from = match &params.reachable_from {
+        Some(text_document) => Some(from_proto::file_id(&snap, &text_document.uri)?),
+        None => None,
+    };
+    let config = CrateGraphConfig {
+        full: params.full,
+        reachable_from,
+        include_dev_deps: !params.exclude_dev_dependencies,
+        format: match params.format {
+            lsp_ext::CrateGraphFormat::Dot => CrateGraphFormat::Dot,
+            lsp_ext::CrateGraphFormat::Mermaid => CrateGraphFormat::Mermaid,
+        },
+    };
+    let graph = snap.analysis.view_crate_graph(config)??;
+    Ok(graph)
+}
+
+pub(crate) fn handle_dependency_tree(
+    snap: GlobalStateSnapshot,
+    params: lsp_ext::DependencyTreeParams,
+) -> Result<lsp_ext::CrateDependencyTree> {
+    let _p = profile::span("handle_dependency_tree");
+    let file_id = from_proto::file_id(&snap, &params.text_document.uri)?;
+    let tree = snap.analysis.dependency_tree(file_id)?;
+    let crates = tree
+        .crates
+        .into_iter()
+        .map(|krate| lsp_ext::DependencyCrate {
+            name: krate.name,
+            version: krate.version,
+            root_file: to_proto::url(&snap, krate.root_file_id),
+            features: krate.features,
+            is_workspace_member: !krate.is_library,
+            dependencies: krate
+                .dependencies
+                .into_iter()
+                .map(|dep| lsp_ext::DependencyEdge {
+                    name: dep.name,
+                    krate: dep.krate,
+                    is_dev: dep.is_dev,
+                })
+                .collect(),
+        })
+        .collect();
+    Ok(lsp_ext::CrateDependencyTree { roots: tree.roots, crates })
 }
 
 pub(crate) fn handle_expand_macro(
@@ -163,8 +292,54 @@ pub(crate) fn handle_expand_macro(
     let line_index = snap.file_line_index(file_id)?;
     let offset = from_proto::offset(&line_index, params.position)?;
 
-    let res = snap.analysis.expand_macro(FilePosition { file_id, offset })?;
-    Ok(res.map(|it| lsp_ext::ExpandedMacro { name: it.name, expansion: it.expansion }))
+    let position = FilePosition { file_id, offset };
+
+    if !params.single_step {
+        let res = snap.analysis.expand_macro(position)?;
+        return Ok(res.map(|it| lsp_ext::ExpandedMacro {
+            name: it.name,
+            expansion: it.expansion,
+            sub_invocations: Vec::new(),
+        }));
+    }
+
+    let path: Vec<usize> = params.invocation_path.iter().map(|&idx| idx as usize).collect();
+    let step = match snap.analysis.expand_macro_step(position, &path)? {
+        Some(it) => it,
+        None => return Ok(None),
+    };
+    let expansion_line_index = LineIndex {
+        index: Arc::new(ide::LineIndex::new(&step.expansion)),
+        endings: LineEndings::Unix,
+        encoding: snap.config.position_encoding(),
+    };
+    let sub_invocations = step
+        .sub_invocations
+        .into_iter()
+        .map(|sub| {
+            let original_location = match sub.original_range {
+                Some(frange) => Some(to_proto::location(&snap, frange)?),
+                None => None,
+            };
+            Ok(lsp_ext::MacroSubInvocation {
+                name: sub.name,
+                invocation_path: sub.path.into_iter().map(|idx| idx as u32).collect(),
+                range: to_proto::range(&expansion_line_index, sub.range),
+                original_location,
+            })
+        })
+        .collect::<Result<_>>()?;
+    Ok(Some(lsp_ext::ExpandedMacro { name: step.name, expansion: step.expansion, sub_invocations }))
+}
+
+pub(crate) fn handle_rerun_proc_macro_with_backtrace(
+    snap: GlobalStateSnapshot,
+    params: lsp_types::TextDocumentPositionParams,
+) -> Result<Option<lsp_ext::ProcMacroBacktrace>> {
+    let _p = profile::span("handle_rerun_proc_macro_with_backtrace");
+    let position = from_proto::file_position(&snap, params)?;
+    let res = snap.analysis.rerun_proc_macro_with_backtrace(position)?;
+    Ok(res.map(|it| lsp_ext::ProcMacroBacktrace { name: it.name, error: it.error }))
 }
 
 pub(crate) fn handle_selection_range(
@@ -239,8 +414,8 @@ pub(crate) fn handle_join_lines(
 ) -> Result<Vec<lsp_types::TextEdit>> {
     let _p = profile::span("handle_join_lines");
 
-    let config = snap.config.join_lines();
     let file_id = from_proto::file_id(&snap, &params.text_document.uri)?;
+    let config = snap.file_config(file_id).join_lines();
     let line_index = snap.file_line_index(file_id)?;
 
     let mut res = TextEdit::default();
@@ -409,11 +584,14 @@ pub(crate) fn handle_workspace_symbol(
     let _p = profile::span("handle_workspace_symbol");
 
     let config = snap.config.workspace_symbol();
-    let (all_symbols, libs) = decide_search_scope_and_kind(&params, &config);
+    let (filter, query) = split_query_filter(&params.query);
+    let (mut all_symbols, libs) = decide_search_scope_and_kind(&params, &config);
+    // An explicit filter asks for symbols other than types.
+    all_symbols |= filter.is_some();
     let limit = config.search_limit;
 
     let query = {
-        let query: String = params.query.chars().filter(|&c| c != '#' && c != '*').collect();
+        let query: String = query.chars().filter(|&c| c != '#' && c != '*').collect();
         let mut q = Query::new(query);
         if !all_symbols {
             q.only_types();
@@ -421,6 +599,11 @@ pub(crate) fn handle_workspace_symbol(
         if libs {
             q.libs();
         }
+        match filter {
+            Some(QueryFilter::Kind(kind)) => q.kind(kind),
+            Some(QueryFilter::Attr(attr)) => q.attr(attr),
+            None => (),
+        }
         q.limit(limit);
         q
     };
@@ -433,6 +616,37 @@ pub(crate) fn handle_workspace_symbol(
 
     return Ok(Some(res));
 
+    enum QueryFilter {
+        Kind(FileSymbolKind),
+        Attr(String),
+    }
+
+    /// Splits off a leading filter like `fn `, `struct ` or `#test ` from the query.
+    fn split_query_filter(query: &str) -> (Option<QueryFilter>, &str) {
+        let (prefix, rest) = match query.split_once(' ') {
+            Some(it) => it,
+            None => return (None, query),
+        };
+        let filter = match prefix.strip_prefix('#') {
+            Some("") => return (None, query),
+            Some(attr) => QueryFilter::Attr(attr.to_string()),
+            None => QueryFilter::Kind(match prefix {
+                "fn" => FileSymbolKind::Function,
+                "struct" => FileSymbolKind::Struct,
+                "enum" => FileSymbolKind::Enum,
+                "union" => FileSymbolKind::Union,
+                "trait" => FileSymbolKind::Trait,
+                "type" => FileSymbolKind::TypeAlias,
+                "const" => FileSymbolKind::Const,
+                "static" => FileSymbolKind::Static,
+                "mod" => FileSymbolKind::Module,
+                "macro" => FileSymbolKind::Macro,
+                _ => return (None, query),
+            }),
+        };
+        (Some(filter), rest.trim_start())
+    }
+
     fn decide_search_scope_and_kind(
         params: &WorkspaceSymbolParams,
         config: &WorkspaceSymbolConfig,
@@ -500,14 +714,13 @@ pub(crate) fn handle_will_rename_files(
     let source_changes: Vec<SourceChange> = params
         .files
         .into_iter()
-        .filter_map(|file_rename| {
+        .filter_map(|file_rename| -> Option<SourceChange> {
             let from = Url::parse(&file_rename.old_uri).ok()?;
             let to = Url::parse(&file_rename.new_uri).ok()?;
 
             let from_path = from.to_file_path().ok()?;
             let to_path = to.to_file_path().ok()?;
 
-            // Limit to single-level moves for now.
             match (from_path.parent(), to_path.parent()) {
                 (Some(p1), Some(p2)) if p1 == p2 => {
                     if from_path.is_dir() {
@@ -518,29 +731,38 @@ pub(crate) fn handle_will_rename_files(
 
                         let imitate_from_url = from_with_trailing_slash.join("mod.rs").ok()?;
                         let new_file_name = to_path.file_name()?.to_str()?;
-                        Some((
-                            snap.url_to_file_id(&imitate_from_url).ok()?,
-                            new_file_name.to_string(),
-                        ))
+                        let file_id = snap.url_to_file_id(&imitate_from_url).ok()?;
+                        snap.analysis.will_rename_file(file_id, new_file_name).ok()?
                     } else {
                         let old_name = from_path.file_stem()?.to_str()?;
                         let new_name = to_path.file_stem()?.to_str()?;
                         match (old_name, new_name) {
                             ("mod", _) => None,
                             (_, "mod") => None,
-                            _ => Some((snap.url_to_file_id(&from).ok()?, new_name.to_string())),
+                            _ => {
+                                let file_id = snap.url_to_file_id(&from).ok()?;
+                                snap.analysis.will_rename_file(file_id, new_name).ok()?
+                            }
                         }
                     }
                 }
-                _ => None,
+                // Moved to another directory, the module changes its parent.
+                _ => {
+                    let (from_path, to_path) = if from_path.is_dir() {
+                        (from_path.join("mod.rs"), to_path.join("mod.rs"))
+                    } else {
+                        (from_path, to_path)
+                    };
+                    let from = Url::from_file_path(&from_path).ok()?;
+                    let file_id = snap.url_to_file_id(&from).ok()?;
+                    let new_path = VfsPath::from(AbsPathBuf::try_from(to_path).ok()?);
+                    snap.analysis.will_move_file(file_id, &new_path).ok()?
+                }
             }
         })
-        .filter_map(|(file_id, new_name)| {
-            snap.analysis.will_rename_file(file_id, &new_name).ok()?
-        })
         .collect();
 
-    // Drop file system edits since we're just renaming things on the same level
+    // Drop file system edits, the client is already moving the files
     let mut source_changes = source_changes.into_iter();
     let mut source_change = source_changes.next().unwrap_or_default();
     source_change.file_system_edits.clear();
@@ -713,7 +935,9 @@ pub(crate) fn handle_runnables(
         let mut runnable = to_proto::runnable(&snap, runnable)?;
         if expect_test {
             runnable.label = format!("{} + expect", runnable.label);
-            runnable.args.expect_test = Some(true);
+            if let lsp_ext::RunnableArgs::Cargo(args) = &mut runnable.args {
+                args.expect_test = Some(true);
+            }
         }
         res.push(runnable);
     }
@@ -727,7 +951,7 @@ pub(crate) fn handle_runnables(
                     label: format!("cargo {} -p {} --all-targets", cmd, spec.package),
                     location: None,
                     kind: lsp_ext::RunnableKind::Cargo,
-                    args: lsp_ext::CargoRunnable {
+                    args: lsp_ext::RunnableArgs::Cargo(lsp_ext::CargoRunnable {
                         workspace_root: Some(spec.workspace_root.clone().into()),
                         override_cargo: config.override_cargo.clone(),
                         cargo_args: vec![
@@ -739,12 +963,14 @@ pub(crate) fn handle_runnables(
                         cargo_extra_args: config.cargo_extra_args.clone(),
                         executable_args: Vec::new(),
                         expect_test: None,
-                    },
+                    }),
                 })
             }
         }
         None => {
-            if !snap.config.linked_projects().is_empty()
+            if let Some(check) = to_proto::project_json_check(&snap, file_id)? {
+                res.push(check);
+            } else if !snap.config.linked_projects().is_empty()
                 || !snap
                     .config
                     .discovered_projects
@@ -756,14 +982,14 @@ pub(crate) fn handle_runnables(
                     label: "cargo check --workspace".to_string(),
                     location: None,
                     kind: lsp_ext::RunnableKind::Cargo,
-                    args: lsp_ext::CargoRunnable {
+                    args: lsp_ext::RunnableArgs::Cargo(lsp_ext::CargoRunnable {
                         workspace_root: None,
                         override_cargo: config.override_cargo,
                         cargo_args: vec!["check".to_string(), "--workspace".to_string()],
                         cargo_extra_args: config.cargo_extra_args,
                         executable_args: Vec::new(),
                         expect_test: None,
-                    },
+                    }),
                 });
             }
         }
@@ -789,6 +1015,145 @@ pub(crate) fn handle_related_tests(
     Ok(res)
 }
 
+pub(crate) fn handle_debug_launch(
+    snap: GlobalStateSnapshot,
+    params: lsp_ext::DebugLaunchParams,
+) -> Result<lsp_ext::DebugLaunchConfig> {
+    let _p = profile::span("handle_debug_launch");
+    let runnable = params.runnable;
+    let args = match runnable.args {
+        lsp_ext::RunnableArgs::Cargo(it) => it,
+        lsp_ext::RunnableArgs::Shell(_) => {
+            return Err(LspError::new(
+                ErrorCode::InvalidParams as i32,
+                "Only Cargo runnables can be debugged".into(),
+            )
+            .into())
+        }
+    };
+    let workspace_root = match args.workspace_root {
+        Some(it) => it,
+        None => {
+            return Err(LspError::new(
+                ErrorCode::InvalidParams as i32,
+                "Runnable has no workspace root".into(),
+            )
+            .into())
+        }
+    };
+
+    // Only build the runnable, running it is up to the debugger.
+    let mut cargo_args = args.cargo_args;
+    match cargo_args.first().map(String::as_str) {
+        Some("run") => cargo_args[0] = "build".to_string(),
+        Some("test" | "bench") => cargo_args.push("--no-run".to_string()),
+        _ => (),
+    }
+    let mut cmd = match args.override_cargo {
+        Some(it) => process::Command::new(it),
+        None => process::Command::new(toolchain::cargo()),
+    };
+    cmd.current_dir(&workspace_root)
+        .args(&cargo_args)
+        .args(&args.cargo_extra_args)
+        .arg("--message-format=json");
+    let program = match &*flycheck::build_executables(cmd)? {
+        [it] => it.clone(),
+        [] => return Err("The runnable did not produce an executable".into()),
+        executables => {
+            return Err(format!(
+                "The runnable produced more than one executable: {}",
+                executables
+                    .iter()
+                    .map(|it| it.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+            .into())
+        }
+    };
+
+    // Cargo runs tests and binaries from their package's directory.
+    let package_root = match runnable.location {
+        Some(location) => {
+            let file_id = from_proto::file_id(&snap, &location.target_uri)?;
+            CargoTargetSpec::for_file(&snap, file_id)?
+                .map(|spec| PathBuf::from(spec.cargo_toml.parent().to_path_buf()))
+        }
+        None => None,
+    };
+    let mut env = HashMap::default();
+    if let Some(package_root) = &package_root {
+        env.insert("CARGO_MANIFEST_DIR".to_string(), package_root.display().to_string());
+    }
+
+    let mut source_map = HashMap::default();
+    let sysroot = snap.workspaces.iter().find_map(|ws| match ws {
+        ProjectWorkspace::Cargo { sysroot, .. } | ProjectWorkspace::Json { sysroot, .. } => {
+            sysroot.as_ref()
+        }
+        ProjectWorkspace::DetachedFiles { .. } => None,
+    });
+    // The standard library's debug info points to the paths it was built from on CI.
+    if let (Some(sysroot), Some(commit_hash)) = (sysroot, rustc_commit_hash(&workspace_root)) {
+        let rust_src = sysroot.root().parent().unwrap_or_else(|| sysroot.root());
+        source_map
+            .insert(format!("/rustc/{}/", commit_hash), PathBuf::from(rust_src.to_path_buf()));
+    }
+
+    return Ok(lsp_ext::DebugLaunchConfig {
+        program,
+        args: args.executable_args,
+        cwd: package_root.unwrap_or(workspace_root),
+        env,
+        source_map,
+    });
+
+    fn rustc_commit_hash(workspace_root: &Path) -> Option<String> {
+        let output = process::Command::new(toolchain::rustc())
+            .current_dir(workspace_root)
+            .arg("-vV")
+            .output()
+            .ok()?;
+        let stdout = String::from_utf8(output.stdout).ok()?;
+        stdout.lines().find_map(|line| line.strip_prefix("commit-hash: ")).map(ToOwned::to_owned)
+    }
+}
+
+pub(crate) fn handle_discover_test(
+    snap: GlobalStateSnapshot,
+    params: lsp_ext::DiscoverTestParams,
+) -> Result<lsp_ext::DiscoverTestResults> {
+    let _p = profile::span("handle_discover_test");
+    let (tests, scope) = match params.test_id {
+        Some(id) => {
+            let crate_id = id.split_once("::").map_or(&*id, |(it, _)| it);
+            (
+                snap.analysis.discover_tests_in_crate_by_test_id(crate_id)?,
+                Some(vec![crate_id.to_string()]),
+            )
+        }
+        None => (snap.analysis.discover_test_roots()?, None),
+    };
+    let tests =
+        tests.into_iter().map(|it| to_proto::test_item(&snap, it)).collect::<Result<Vec<_>>>()?;
+    Ok(lsp_ext::DiscoverTestResults { tests, scope, scope_file: None })
+}
+
+pub(crate) fn discover_tests_in_file(
+    snap: &GlobalStateSnapshot,
+    file_id: FileId,
+) -> Result<lsp_ext::DiscoverTestResults> {
+    let tests = snap
+        .analysis
+        .discover_tests_in_file(file_id)?
+        .into_iter()
+        .map(|it| to_proto::test_item(snap, it))
+        .collect::<Result<Vec<_>>>()?;
+    let text_document = TextDocumentIdentifier { uri: to_proto::url(snap, file_id) };
+    Ok(lsp_ext::DiscoverTestResults { tests, scope: None, scope_file: Some(vec![text_document]) })
+}
+
 pub(crate) fn handle_completion(
     snap: GlobalStateSnapshot,
     params: lsp_types::CompletionParams,
@@ -815,7 +1180,7 @@ pub(crate) fn handle_completion(
         return Ok(None);
     }
 
-    let completion_config = &snap.config.completion();
+    let completion_config = &snap.file_config(position.file_id).completion();
     let items = match snap.analysis.completions(completion_config, position)? {
         None => return Ok(None),
         Some(items) => items,
@@ -852,12 +1217,29 @@ pub(crate) fn handle_completion_resolve(
     let file_id = from_proto::file_id(&snap, &resolve_data.position.text_document.uri)?;
     let line_index = snap.file_line_index(file_id)?;
     let offset = from_proto::offset(&line_index, resolve_data.position.position)?;
+    let position = FilePosition { file_id, offset };
+    let config = snap.file_config(file_id).completion();
+
+    if let Some(hash) = resolve_data.hash {
+        let hash = hash.parse::<u64>()?;
+        let imports_something = !resolve_data.imports.is_empty();
+        if let Some(resolved) =
+            snap.analysis.resolve_completion_item(&config, position, hash, imports_something)?
+        {
+            original_completion.detail = resolved.detail().map(|it| it.to_string());
+            original_completion.documentation =
+                resolved.documentation().map(to_proto::documentation);
+        }
+    }
+    if resolve_data.imports.is_empty() {
+        return Ok(original_completion);
+    }
 
     let additional_edits = snap
         .analysis
         .resolve_completion_edits(
-            &snap.config.completion(),
-            FilePosition { file_id, offset },
+            &config,
+            position,
             resolve_data
                 .imports
                 .into_iter()
@@ -912,7 +1294,7 @@ pub(crate) fn handle_signature_help(
         Some(it) => it,
         None => return Ok(None),
     };
-    let config = snap.config.call_info();
+    let config = snap.file_config(position.file_id).call_info();
     let res = to_proto::signature_help(help, config, snap.config.signature_help_label_offsets());
     Ok(Some(res))
 }
@@ -928,15 +1310,15 @@ pub(crate) fn handle_hover(
     };
 
     let file_range = from_proto::file_range(&snap, params.text_document, range)?;
-    let info = match snap.analysis.hover(&snap.config.hover(), file_range)? {
+    let hover_config = snap.file_config(file_range.file_id).hover();
+    let info = match snap.analysis.hover(&hover_config, file_range)? {
         None => return Ok(None),
         Some(info) => info,
     };
 
     let line_index = snap.file_line_index(file_range.file_id)?;
     let range = to_proto::range(&line_index, info.range);
-    let markup_kind =
-        snap.config.hover().documentation.map_or(ide::HoverDocFormat::Markdown, |kind| kind);
+    let markup_kind = hover_config.documentation.map_or(ide::HoverDocFormat::Markdown, |kind| kind);
     let hover = lsp_ext::Hover {
         hover: lsp_types::Hover {
             contents: HoverContents::Markup(to_proto::markup_content(
@@ -995,7 +1377,7 @@ pub(crate) fn handle_rename(
 pub(crate) fn handle_references(
     snap: GlobalStateSnapshot,
     params: lsp_types::ReferenceParams,
-) -> Result<Option<Vec<Location>>> {
+) -> Result<Option<Vec<lsp_ext::ReferenceLocation>>> {
     let _p = profile::span("handle_references");
     let position = from_proto::file_position(&snap, params.text_document_position)?;
 
@@ -1009,9 +1391,12 @@ pub(crate) fn handle_references(
         .into_iter()
         .flat_map(|refs| {
             let decl = if include_declaration {
-                refs.declaration.map(|decl| FileRange {
-                    file_id: decl.nav.file_id,
-                    range: decl.nav.focus_or_full_range(),
+                refs.declaration.map(|decl| {
+                    let frange = FileRange {
+                        file_id: decl.nav.file_id,
+                        range: decl.nav.focus_or_full_range(),
+                    };
+                    (frange, None)
                 })
             } else {
                 None
@@ -1019,11 +1404,16 @@ pub(crate) fn handle_references(
             refs.references
                 .into_iter()
                 .flat_map(|(file_id, refs)| {
-                    refs.into_iter().map(move |(range, _)| FileRange { file_id, range })
+                    refs.into_iter()
+                        .map(move |(range, category)| (FileRange { file_id, range }, category))
                 })
                 .chain(decl)
         })
-        .filter_map(|frange| to_proto::location(&snap, frange).ok())
+        .filter_map(|(frange, category)| {
+            let location = to_proto::location(&snap, frange).ok()?;
+            let category = category.map(to_proto::reference_category);
+            Some(lsp_ext::ReferenceLocation { location, category })
+        })
         .collect();
 
     Ok(Some(locations))
@@ -1064,7 +1454,8 @@ pub(crate) fn handle_code_action(
         snap.file_line_index(from_proto::file_id(&snap, &params.text_document.uri)?)?;
     let frange = from_proto::file_range(&snap, params.text_document.clone(), params.range)?;
 
-    let mut assists_config = snap.config.assist();
+    let file_config = snap.file_config(frange.file_id);
+    let mut assists_config = file_config.assist();
     assists_config.allowed = params
         .context
         .only
@@ -1081,7 +1472,7 @@ pub(crate) fn handle_code_action(
     };
     let assists = snap.analysis.assists_with_fixes(
         &assists_config,
-        &snap.config.diagnostics(),
+        &file_config.diagnostics(),
         resolve,
         frange,
     )?;
@@ -1125,7 +1516,8 @@ pub(crate) fn handle_code_action_resolve(
     let range = from_proto::text_range(&line_index, params.code_action_params.range)?;
     let frange = FileRange { file_id, range };
 
-    let mut assists_config = snap.config.assist();
+    let file_config = snap.file_config(file_id);
+    let mut assists_config = file_config.assist();
     assists_config.allowed = params
         .code_action_params
         .context
@@ -1148,7 +1540,7 @@ pub(crate) fn handle_code_action_resolve(
 
     let assists = snap.analysis.assists_with_fixes(
         &assists_config,
-        &snap.config.diagnostics(),
+        &file_config.diagnostics(),
         AssistResolveStrategy::Single(assist_resolve),
         frange,
     )?;
@@ -1195,13 +1587,13 @@ pub(crate) fn handle_code_lens(
 ) -> Result<Option<Vec<CodeLens>>> {
     let _p = profile::span("handle_code_lens");
 
-    let lens_config = snap.config.lens();
+    let file_id = from_proto::file_id(&snap, &params.text_document.uri)?;
+    let lens_config = snap.file_config(file_id).lens();
     if lens_config.none() {
         // early return before any db query!
         return Ok(Some(Vec::default()));
     }
 
-    let file_id = from_proto::file_id(&snap, &params.text_document.uri)?;
     let cargo_target_spec = CargoTargetSpec::for_file(&snap, file_id)?;
 
     let annotations = snap.analysis.annotations(
@@ -1217,6 +1609,8 @@ pub(crate) fn handle_code_lens(
             annotate_runnables: lens_config.runnable(),
             annotate_impls: lens_config.implementations,
             annotate_references: lens_config.refs_adt,
+            annotate_trait_references: lens_config.refs_trait,
+            annotate_public_item_references: lens_config.public_item_refs,
             annotate_method_references: lens_config.method_refs,
             annotate_enum_variant_references: lens_config.enum_variant_refs,
         },
@@ -1260,7 +1654,8 @@ pub(crate) fn handle_document_highlight(
     let position = from_proto::file_position(&snap, params.text_document_position_params)?;
     let line_index = snap.file_line_index(position.file_id)?;
 
-    let refs = match snap.analysis.highlight_related(snap.config.highlight_related(), position)? {
+    let config = snap.file_config(position.file_id).highlight_related();
+    let refs = match snap.analysis.highlight_related(config, position)? {
         None => return Ok(None),
         Some(refs) => refs,
     };
@@ -1299,11 +1694,16 @@ pub(crate) fn publish_diagnostics(
     file_id: FileId,
 ) -> Result<Vec<Diagnostic>> {
     let _p = profile::span("publish_diagnostics");
+    // Diagnostics can be turned off for some paths only.
+    let config = snap.file_config(file_id);
+    if !config.publish_diagnostics() {
+        return Ok(Vec::new());
+    }
     let line_index = snap.file_line_index(file_id)?;
 
     let diagnostics: Vec<Diagnostic> = snap
         .analysis
-        .diagnostics(&snap.config.diagnostics(), AssistResolveStrategy::None, file_id)?
+        .diagnostics(&config.diagnostics(), AssistResolveStrategy::None, file_id)?
         .into_iter()
         .map(|d| Diagnostic {
             range: to_proto::range(&line_index, d.range),
@@ -1326,6 +1726,49 @@ pub(crate) fn publish_diagnostics(
     Ok(diagnostics)
 }
 
+pub(crate) fn handle_document_diagnostic(
+    snap: GlobalStateSnapshot,
+    params: lsp_ext::DocumentDiagnosticParams,
+) -> Result<lsp_ext::DocumentDiagnosticReport> {
+    let _p = profile::span("handle_document_diagnostic");
+    // Excluded generated files aren't in the VFS, only the reason they are missing is reported.
+    let excluded_size = from_proto::abs_path(&params.text_document.uri)
+        .ok()
+        .and_then(|path| snap.excluded_generated_files.get(&path).copied());
+
+    // Like with published diagnostics, library files are never reported. Nothing is reported
+    // either after switching back to publishing diagnostics, to not show them twice.
+    let mut items = Vec::new();
+    if snap.config.pull_diagnostics() {
+        if let Some(size) = excluded_size {
+            items.push(crate::diagnostics::excluded_generated_file(size));
+        } else {
+            let file_id = from_proto::file_id(&snap, &params.text_document.uri)?;
+            if !snap.analysis.is_library_file(file_id)? {
+                items = publish_diagnostics(&snap, file_id)?;
+                items.extend(snap.check_diagnostics.get(&file_id).into_iter().flatten().cloned());
+            }
+        }
+    }
+
+    // The result id is derived from the report itself, so an unchanged set of diagnostics can be
+    // recognized without keeping per-client state around.
+    let result_id = {
+        let mut hasher = FxHasher::default();
+        serde_json::to_string(&items)?.hash(&mut hasher);
+        format!("{:x}", hasher.finish())
+    };
+    if params.previous_result_id.as_deref() == Some(result_id.as_str()) {
+        return Ok(lsp_ext::DocumentDiagnosticReport::Unchanged(
+            lsp_ext::UnchangedDocumentDiagnosticReport { result_id },
+        ));
+    }
+    Ok(lsp_ext::DocumentDiagnosticReport::Full(lsp_ext::FullDocumentDiagnosticReport {
+        result_id: Some(result_id),
+        items,
+    }))
+}
+
 pub(crate) fn handle_inlay_hints(
     snap: GlobalStateSnapshot,
     params: InlayHintParams,
@@ -1339,15 +1782,22 @@ pub(crate) fn handle_inlay_hints(
         TextDocumentIdentifier::new(document_uri.to_owned()),
         params.range,
     )?;
-    let inlay_hints_config = snap.config.inlay_hints();
+    let inlay_hints_config = snap.file_config(file_id).inlay_hints();
     Ok(Some(
         snap.analysis
             .inlay_hints(&inlay_hints_config, file_id, Some(range))?
             .into_iter()
             .map(|it| {
-                to_proto::inlay_hint(&snap, &line_index, inlay_hints_config.render_colons, it)
+                to_proto::inlay_hint(
+                    &snap,
+                    &line_index,
+                    inlay_hints_config.render_colons,
+                    file_id,
+                    range.range,
+                    it,
+                )
             })
-            .collect(),
+            .collect::<Result<Vec<_>>>()?,
     ))
 }
 
@@ -1361,23 +1811,52 @@ pub(crate) fn handle_inlay_hints_resolve(
         None => return Ok(hint),
     };
 
-    let resolve_data: lsp_ext::InlayHintResolveData = serde_json::from_value(data)?;
+    let mut resolve_data: lsp_ext::InlayHintResolveData = serde_json::from_value(data)?;
 
+    if let Some(lazy) = resolve_data.hint {
+        let file_id = from_proto::file_id(&snap, &lazy.text_document.uri)?;
+        let line_index = snap.file_line_index(file_id)?;
+        let resolve_range = from_proto::text_range(&line_index, lazy.resolve_range)?;
+        let hash = lazy.hash.parse::<u64>()?;
+        let config = snap.file_config(file_id).inlay_hints();
+        if let Some(resolved) =
+            snap.analysis.inlay_hints_resolve(&config, file_id, resolve_range, hash)?
+        {
+            resolve_data.tooltip =
+                to_proto::inlay_hint_tooltip_data(&snap, resolved.tooltip.as_ref())?;
+            let resolved = to_proto::inlay_hint(
+                &snap,
+                &line_index,
+                config.render_colons,
+                file_id,
+                resolve_range,
+                resolved,
+            )?;
+            hint.label = resolved.label;
+            hint.text_edits = resolved.text_edits;
+            hint.tooltip = resolved.tooltip;
+        }
+    }
+
+    let tooltip = match resolve_data.tooltip {
+        Some(it) => it,
+        None => return Ok(hint),
+    };
     let file_range = from_proto::file_range(
         &snap,
-        resolve_data.text_document,
-        match resolve_data.position {
+        tooltip.text_document,
+        match tooltip.position {
             PositionOrRange::Position(pos) => Range::new(pos, pos),
             PositionOrRange::Range(range) => range,
         },
     )?;
-    let info = match snap.analysis.hover(&snap.config.hover(), file_range)? {
+    let hover_config = snap.file_config(file_range.file_id).hover();
+    let info = match snap.analysis.hover(&hover_config, file_range)? {
         None => return Ok(hint),
         Some(info) => info,
     };
 
-    let markup_kind =
-        snap.config.hover().documentation.map_or(ide::HoverDocFormat::Markdown, |kind| kind);
+    let markup_kind = hover_config.documentation.map_or(ide::HoverDocFormat::Markdown, |kind| kind);
 
     // FIXME: hover actions?
     hint.tooltip = Some(lsp_types::InlayHintTooltip::MarkupContent(to_proto::markup_content(
@@ -1403,7 +1882,7 @@ pub(crate) fn handle_call_hierarchy_prepare(
     let res = navs
         .into_iter()
         .filter(|it| it.kind == Some(SymbolKind::Function))
-        .map(|it| to_proto::call_hierarchy_item(&snap, it))
+        .map(|it| to_proto::call_hierarchy_item(&snap, it, false))
         .collect::<Result<Vec<_>>>()?;
 
     Ok(Some(res))
@@ -1430,7 +1909,7 @@ pub(crate) fn handle_call_hierarchy_incoming(
     for call_item in call_items.into_iter() {
         let file_id = call_item.target.file_id;
         let line_index = snap.file_line_index(file_id)?;
-        let item = to_proto::call_hierarchy_item(&snap, call_item.target)?;
+        let item = to_proto::call_hierarchy_item(&snap, call_item.target, call_item.potential)?;
         res.push(CallHierarchyIncomingCall {
             from: item,
             from_ranges: call_item
@@ -1465,7 +1944,7 @@ pub(crate) fn handle_call_hierarchy_outgoing(
     for call_item in call_items.into_iter() {
         let file_id = call_item.target.file_id;
         let line_index = snap.file_line_index(file_id)?;
-        let item = to_proto::call_hierarchy_item(&snap, call_item.target)?;
+        let item = to_proto::call_hierarchy_item(&snap, call_item.target, call_item.potential)?;
         res.push(CallHierarchyOutgoingCall {
             to: item,
             from_ranges: call_item
@@ -1479,6 +1958,74 @@ pub(crate) fn handle_call_hierarchy_outgoing(
     Ok(Some(res))
 }
 
+pub(crate) fn handle_type_hierarchy_prepare(
+    snap: GlobalStateSnapshot,
+    params: lsp_ext::TypeHierarchyPrepareParams,
+) -> Result<Option<Vec<lsp_ext::TypeHierarchyItem>>> {
+    let _p = profile::span("handle_type_hierarchy_prepare");
+    let position = from_proto::file_position(&snap, params.text_document_position_params)?;
+
+    let nav_info = match snap.analysis.type_hierarchy(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+
+    let RangeInfo { range: _, info: navs } = nav_info;
+    let res = navs
+        .into_iter()
+        .map(|it| to_proto::type_hierarchy_item(&snap, it))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_type_hierarchy_supertypes(
+    snap: GlobalStateSnapshot,
+    params: lsp_ext::TypeHierarchySupertypesParams,
+) -> Result<Option<Vec<lsp_ext::TypeHierarchyItem>>> {
+    let _p = profile::span("handle_type_hierarchy_supertypes");
+    let fpos = type_hierarchy_item_position(&snap, params.item)?;
+
+    let navs = match snap.analysis.supertypes(fpos)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let res = navs
+        .into_iter()
+        .map(|it| to_proto::type_hierarchy_item(&snap, it))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Some(res))
+}
+
+pub(crate) fn handle_type_hierarchy_subtypes(
+    snap: GlobalStateSnapshot,
+    params: lsp_ext::TypeHierarchySubtypesParams,
+) -> Result<Option<Vec<lsp_ext::TypeHierarchyItem>>> {
+    let _p = profile::span("handle_type_hierarchy_subtypes");
+    let fpos = type_hierarchy_item_position(&snap, params.item)?;
+
+    let navs = match snap.analysis.subtypes(fpos)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let res = navs
+        .into_iter()
+        .map(|it| to_proto::type_hierarchy_item(&snap, it))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Some(res))
+}
+
+fn type_hierarchy_item_position(
+    snap: &GlobalStateSnapshot,
+    item: lsp_ext::TypeHierarchyItem,
+) -> Result<FilePosition> {
+    let doc = TextDocumentIdentifier::new(item.uri);
+    let frange = from_proto::file_range(snap, doc, item.selection_range)?;
+    Ok(FilePosition { file_id: frange.file_id, offset: frange.range.start() })
+}
+
 pub(crate) fn handle_semantic_tokens_full(
     snap: GlobalStateSnapshot,
     params: SemanticTokensParams,
@@ -1490,7 +2037,7 @@ pub(crate) fn handle_semantic_tokens_full(
     let line_index = snap.file_line_index(file_id)?;
 
     let highlights = snap.analysis.highlight(file_id)?;
-    let highlight_strings = snap.config.highlighting_strings();
+    let highlight_strings = snap.file_config(file_id).highlighting_strings();
     let semantic_tokens =
         to_proto::semantic_tokens(&text, &line_index, highlights, highlight_strings);
 
@@ -1511,10 +2058,12 @@ pub(crate) fn handle_semantic_tokens_full_delta(
     let line_index = snap.file_line_index(file_id)?;
 
     let highlights = snap.analysis.highlight(file_id)?;
-    let highlight_strings = snap.config.highlighting_strings();
+    let highlight_strings = snap.file_config(file_id).highlighting_strings();
     let semantic_tokens =
         to_proto::semantic_tokens(&text, &line_index, highlights, highlight_strings);
 
+    // Result ids are never reused, so a matching id means the cached tokens are exactly the ones
+    // the client holds, however the file changed since.
     let mut cache = snap.semantic_tokens_cache.lock();
     let cached_tokens = cache.entry(params.text_document.uri).or_default();
 
@@ -1542,7 +2091,7 @@ pub(crate) fn handle_semantic_tokens_range(
     let line_index = snap.file_line_index(frange.file_id)?;
 
     let highlights = snap.analysis.highlight_range(frange)?;
-    let highlight_strings = snap.config.highlighting_strings();
+    let highlight_strings = snap.file_config(frange.file_id).highlighting_strings();
     let semantic_tokens =
         to_proto::semantic_tokens(&text, &line_index, highlights, highlight_strings);
     Ok(Some(semantic_tokens.into()))
@@ -1553,9 +2102,18 @@ pub(crate) fn handle_open_docs(
     params: lsp_types::TextDocumentPositionParams,
 ) -> Result<Option<lsp_types::Url>> {
     let _p = profile::span("handle_open_docs");
-    let position = from_proto::file_position(&snap, params)?;
 
-    let remote = snap.analysis.external_docs(position)?;
+    let target_dir = params.text_document.uri.to_file_path().ok().and_then(|path| {
+        snap.workspaces.iter().find_map(|ws| match ws {
+            ProjectWorkspace::Cargo { cargo, .. } if path.starts_with(cargo.workspace_root()) => {
+                Some(cargo.target_directory().to_path_buf())
+            }
+            _ => None,
+        })
+    });
+    let position = from_proto::file_position(&snap, params)?;
+    let config = snap.config.external_docs(target_dir.as_deref());
+    let remote = snap.analysis.external_docs(position, &config)?;
 
     Ok(remote.and_then(|remote| Url::parse(&remote).ok()))
 }
@@ -1572,12 +2130,33 @@ pub(crate) fn handle_open_cargo_toml(
         None => return Ok(None),
     };
 
+    // Point at the `[package]` table of the owning package, or at the start of the manifest if
+    // it isn't loaded into the VFS.
+    let package_line = {
+        let vfs = &snap.vfs.read().0;
+        vfs.file_id(&VfsPath::from(cargo_spec.cargo_toml.to_path_buf()))
+            .and_then(|file_id| std::str::from_utf8(vfs.file_contents(file_id)).ok())
+            .and_then(package_table_line)
+            .unwrap_or(0)
+    };
+    let position = Position::new(package_line as u32, 0);
+
     let cargo_toml_url = to_proto::url_from_abs_path(&cargo_spec.cargo_toml);
     let res: lsp_types::GotoDefinitionResponse =
-        Location::new(cargo_toml_url, Range::default()).into();
+        Location::new(cargo_toml_url, Range::new(position, position)).into();
     Ok(Some(res))
 }
 
+/// The line of the `[package]` table header in a `Cargo.toml`.
+fn package_table_line(manifest: &str) -> Option<usize> {
+    manifest.lines().position(|line| {
+        let line = line.split_once('#').map_or(line, |(line, _comment)| line).trim();
+        line.strip_prefix('[')
+            .and_then(|it| it.strip_suffix(']'))
+            .map_or(false, |table| table.trim() == "package")
+    })
+}
+
 pub(crate) fn handle_move_item(
     snap: GlobalStateSnapshot,
     params: lsp_ext::MoveItemParams,
@@ -1591,7 +2170,7 @@ pub(crate) fn handle_move_item(
         lsp_ext::MoveItemDirection::Down => ide::Direction::Down,
     };
 
-    match snap.analysis.move_item(range, direction)? {
+    match snap.analysis.move_item(range, direction, snap.config.move_item_respect_trait_order())? {
         Some(text_edit) => {
             let line_index = snap.file_line_index(file_id)?;
             Ok(to_proto::snippet_text_edit_vec(&line_index, true, text_edit))
@@ -1762,7 +2341,7 @@ fn run_rustfmt(
 
     let line_index = snap.file_line_index(file_id)?;
 
-    let mut rustfmt = match snap.config.rustfmt() {
+    let mut rustfmt = match snap.file_config(file_id).rustfmt() {
         RustfmtConfig::Rustfmt { extra_args, enable_range_formatting } => {
             let mut cmd = process::Command::new(toolchain::rustfmt());
             cmd.args(extra_args);
@@ -1888,3 +2467,24 @@ fn run_rustfmt(
         Ok(Some(to_proto::text_edit_vec(&line_index, diff(&file, &new_text))))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_package_table_line() {
+        let manifest = r#"
+# [package]
+[workspace]
+members = ["a"]
+
+[ package ] # the package
+name = "foo"
+
+[[package.metadata.bin]]
+"#;
+        assert_eq!(package_table_line(manifest), Some(5));
+        assert_eq!(package_table_line("[[package]]\n[dependencies]\n"), None);
+    }
+}
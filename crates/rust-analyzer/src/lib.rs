@@ -18,6 +18,7 @@ macro_rules! eprintln {
 
 mod global_state;
 mod reload;
+mod discover;
 mod main_loop;
 mod dispatch;
 mod handlers;
@@ -44,7 +45,7 @@ use std::fmt;
 
 use serde::de::DeserializeOwned;
 
-pub use crate::{caps::server_capabilities, main_loop::main_loop};
+pub use crate::{caps::server_capabilities, line_index::PositionEncoding, main_loop::main_loop};
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Result<T, E = Error> = std::result::Result<T, E>;
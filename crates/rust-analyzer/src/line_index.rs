@@ -7,15 +7,30 @@
 
 use std::sync::Arc;
 
-pub enum OffsetEncoding {
+use ide::WideEncoding;
+
+/// The encoding a client counts the columns of positions in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionEncoding {
     Utf8,
-    Utf16,
+    Wide(WideEncoding),
+}
+
+impl PositionEncoding {
+    /// The name LSP uses for the encoding.
+    pub fn kind(self) -> &'static str {
+        match self {
+            PositionEncoding::Utf8 => "utf-8",
+            PositionEncoding::Wide(WideEncoding::Utf16) => "utf-16",
+            PositionEncoding::Wide(WideEncoding::Utf32) => "utf-32",
+        }
+    }
 }
 
 pub(crate) struct LineIndex {
     pub(crate) index: Arc<ide::LineIndex>,
     pub(crate) endings: LineEndings,
-    pub(crate) encoding: OffsetEncoding,
+    pub(crate) encoding: PositionEncoding,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -2,13 +2,16 @@
 
 use std::{collections::HashMap, path::PathBuf};
 
+use ide::WideEncoding;
 use lsp_types::request::Request;
 use lsp_types::{
-    notification::Notification, CodeActionKind, DocumentOnTypeFormattingParams,
+    notification::Notification, CodeActionKind, DocumentOnTypeFormattingParams, Location,
     PartialResultParams, Position, Range, TextDocumentIdentifier, WorkDoneProgressParams,
 };
 use serde::{Deserialize, Serialize};
 
+use crate::line_index::PositionEncoding;
+
 pub enum AnalyzerStatus {}
 
 impl Request for AnalyzerStatus {
@@ -47,6 +50,52 @@ impl Request for ReloadWorkspace {
     const METHOD: &'static str = "rust-analyzer/reloadWorkspace";
 }
 
+pub enum RebuildProcMacros {}
+
+impl Request for RebuildProcMacros {
+    type Params = ();
+    type Result = ();
+    const METHOD: &'static str = "rust-analyzer/rebuildProcMacros";
+}
+
+pub enum CancelFlycheck {}
+
+impl Notification for CancelFlycheck {
+    type Params = ();
+    const METHOD: &'static str = "rust-analyzer/cancelFlycheck";
+}
+
+pub enum SetActiveTarget {}
+
+impl Request for SetActiveTarget {
+    type Params = SetActiveTargetParams;
+    type Result = ();
+    const METHOD: &'static str = "rust-analyzer/setActiveTarget";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SetActiveTargetParams {
+    pub text_document: TextDocumentIdentifier,
+    /// One of the `cargo.extraTargets`, `None` for the default target.
+    pub target: Option<String>,
+}
+
+pub enum PrimeCaches {}
+
+impl Request for PrimeCaches {
+    type Params = PrimeCachesParams;
+    type Result = ();
+    const METHOD: &'static str = "rust-analyzer/primeCaches";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PrimeCachesParams {
+    /// Restricts priming to the crates containing this document.
+    pub text_document: Option<TextDocumentIdentifier>,
+}
+
 pub enum SyntaxTree {}
 
 impl Request for SyntaxTree {
@@ -70,6 +119,37 @@ impl Request for ViewHir {
     const METHOD: &'static str = "rust-analyzer/viewHir";
 }
 
+pub enum ViewMir {}
+
+impl Request for ViewMir {
+    type Params = lsp_types::TextDocumentPositionParams;
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/viewMir";
+}
+
+pub enum InterpretFunction {}
+
+impl Request for InterpretFunction {
+    type Params = lsp_types::TextDocumentPositionParams;
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/interpretFunction";
+}
+
+pub enum EvaluateExpression {}
+
+impl Request for EvaluateExpression {
+    type Params = EvaluateExpressionParams;
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/evaluateExpression";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluateExpressionParams {
+    pub text_document: TextDocumentIdentifier,
+    pub range: Range,
+}
+
 pub enum ViewFileText {}
 
 impl Request for ViewFileText {
@@ -83,6 +163,27 @@ impl Request for ViewFileText {
 pub struct ViewCrateGraphParams {
     /// Include *all* crates, not just crates in the workspace.
     pub full: bool,
+    /// Only include the crates reachable from the crates containing this document.
+    #[serde(default)]
+    pub reachable_from: Option<TextDocumentIdentifier>,
+    /// Leave out the edges that only exist because of dev-dependencies.
+    #[serde(default)]
+    pub exclude_dev_dependencies: bool,
+    #[serde(default)]
+    pub format: CrateGraphFormat,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CrateGraphFormat {
+    Dot,
+    Mermaid,
+}
+
+impl Default for CrateGraphFormat {
+    fn default() -> Self {
+        CrateGraphFormat::Dot
+    }
 }
 
 pub enum ViewCrateGraph {}
@@ -93,6 +194,50 @@ impl Request for ViewCrateGraph {
     const METHOD: &'static str = "rust-analyzer/viewCrateGraph";
 }
 
+pub enum DependencyTree {}
+
+impl Request for DependencyTree {
+    type Params = DependencyTreeParams;
+    type Result = CrateDependencyTree;
+    const METHOD: &'static str = "rust-analyzer/dependencyTree";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyTreeParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CrateDependencyTree {
+    /// Indices into `crates` of the crates containing the document.
+    pub roots: Vec<usize>,
+    pub crates: Vec<DependencyCrate>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyCrate {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub root_file: lsp_types::Url,
+    pub features: Vec<String>,
+    pub is_workspace_member: bool,
+    pub dependencies: Vec<DependencyEdge>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyEdge {
+    /// The name of the dependency inside the depending crate.
+    pub name: String,
+    /// Index into `CrateDependencyTree::crates`.
+    #[serde(rename = "crate")]
+    pub krate: usize,
+    pub is_dev: bool,
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ViewItemTreeParams {
@@ -120,6 +265,12 @@ impl Request for ExpandMacro {
 pub struct ExpandMacroParams {
     pub text_document: TextDocumentIdentifier,
     pub position: Position,
+    /// Expand a single level of the macro instead of expanding it recursively.
+    #[serde(default)]
+    pub single_step: bool,
+    /// Selects the nested macro call to expand in single step mode.
+    #[serde(default)]
+    pub invocation_path: Vec<u32>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -127,6 +278,37 @@ pub struct ExpandMacroParams {
 pub struct ExpandedMacro {
     pub name: String,
     pub expansion: String,
+    /// The macro calls in `expansion` which can be expanded further, only set in single step mode.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sub_invocations: Vec<MacroSubInvocation>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroSubInvocation {
+    pub name: String,
+    pub invocation_path: Vec<u32>,
+    /// The range of the call in `ExpandedMacro::expansion`.
+    pub range: Range,
+    /// Where the call was written in the original source, if all of its tokens come from there.
+    pub original_location: Option<Location>,
+}
+
+pub enum RerunProcMacroWithBacktrace {}
+
+impl Request for RerunProcMacroWithBacktrace {
+    type Params = lsp_types::TextDocumentPositionParams;
+    type Result = Option<ProcMacroBacktrace>;
+    const METHOD: &'static str = "rust-analyzer/rerunProcMacroWithBacktrace";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcMacroBacktrace {
+    pub name: String,
+    /// The error of the expansion, with the full backtrace if the macro panicked. `None` if
+    /// the expansion succeeded this time.
+    pub error: Option<String>,
 }
 
 pub enum MatchingBrace {}
@@ -197,13 +379,21 @@ pub struct Runnable {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub location: Option<lsp_types::LocationLink>,
     pub kind: RunnableKind,
-    pub args: CargoRunnable,
+    pub args: RunnableArgs,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum RunnableKind {
     Cargo,
+    Shell,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(untagged)]
+pub enum RunnableArgs {
+    Cargo(CargoRunnable),
+    Shell(ShellRunnable),
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -223,6 +413,41 @@ pub struct CargoRunnable {
     pub expect_test: Option<bool>,
 }
 
+/// A command from the `runnables` of a `rust-project.json`.
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellRunnable {
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: PathBuf,
+}
+
+pub enum DebugLaunch {}
+
+impl Request for DebugLaunch {
+    type Params = DebugLaunchParams;
+    type Result = DebugLaunchConfig;
+    const METHOD: &'static str = "rust-analyzer/debugLaunch";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DebugLaunchParams {
+    pub runnable: Runnable,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DebugLaunchConfig {
+    /// The executable built for the runnable.
+    pub program: PathBuf,
+    pub args: Vec<String>,
+    pub cwd: PathBuf,
+    pub env: HashMap<String, String>,
+    /// Maps the paths the standard library was compiled from to its sources in the sysroot.
+    pub source_map: HashMap<String, PathBuf>,
+}
+
 pub enum RelatedTests {}
 
 impl Request for RelatedTests {
@@ -236,6 +461,58 @@ pub struct TestInfo {
     pub runnable: Runnable,
 }
 
+pub enum DiscoverTest {}
+
+impl Request for DiscoverTest {
+    type Params = DiscoverTestParams;
+    type Result = DiscoverTestResults;
+    const METHOD: &'static str = "experimental/discoverTest";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoverTestParams {
+    pub test_id: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoverTestResults {
+    pub tests: Vec<TestItem>,
+    /// The ids of the items whose children were fully enumerated.
+    pub scope: Option<Vec<String>>,
+    /// The files whose tests were fully enumerated.
+    pub scope_file: Option<Vec<TextDocumentIdentifier>>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TestItem {
+    pub id: String,
+    pub label: String,
+    pub kind: TestItemKind,
+    pub can_resolve_children: bool,
+    pub parent: Option<String>,
+    pub text_document: Option<TextDocumentIdentifier>,
+    pub range: Option<Range>,
+    pub runnable: Option<Runnable>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TestItemKind {
+    Package,
+    Module,
+    Test,
+}
+
+pub enum DiscoveredTests {}
+
+impl Notification for DiscoveredTests {
+    type Params = DiscoverTestResults;
+    const METHOD: &'static str = "experimental/discoveredTests";
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct InlayHintsParams {
@@ -273,21 +550,72 @@ impl Notification for ServerStatusNotification {
     const METHOD: &'static str = "experimental/serverStatus";
 }
 
-#[derive(Deserialize, Serialize, PartialEq, Eq, Clone)]
+#[derive(Deserialize, Serialize, PartialEq, Clone)]
 pub struct ServerStatusParams {
     pub health: Health,
     pub quiescent: bool,
     pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub problems: Vec<ServerProblem>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerProblem {
+    pub kind: ServerProblemKind,
+    pub health: Health,
+    pub message: String,
+    /// A client command fixing the problem.
+    pub fix: Option<lsp_types::Command>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
+pub enum ServerProblemKind {
+    WorkspaceLoadFailed,
+    MissingRustSrc,
+    WorkspaceReloadRequired,
+    BuildScriptsFailed,
+    ProcMacroChanged,
+    ProcMacroLoadFailed,
+    UnlinkedFile,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
 pub enum Health {
     Ok,
     Warning,
     Error,
 }
 
+pub enum ServerTrace {}
+
+impl Notification for ServerTrace {
+    type Params = ServerTraceParams;
+    const METHOD: &'static str = "rust-analyzer/serverTrace";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerTraceParams {
+    pub kind: ServerTraceKind,
+    /// The method of a request, or the kind of event handled by a main loop turn.
+    pub name: String,
+    /// The ID of the request, if the trace is about one.
+    pub id: Option<String>,
+    pub duration_ms: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ServerTraceKind {
+    /// A request was answered, `durationMs` counts from the moment it was received.
+    Request,
+    /// A turn of the main loop, only reported with the `verbose` trace level.
+    LoopTurn,
+}
+
 pub enum CodeActionRequest {}
 
 impl Request for CodeActionRequest {
@@ -446,8 +774,27 @@ pub(crate) enum CodeLensResolveData {
     References(lsp_types::TextDocumentPositionParams),
 }
 
-pub fn supports_utf8(caps: &lsp_types::ClientCapabilities) -> bool {
-    caps.offset_encoding.as_deref().unwrap_or_default().iter().any(|it| it == "utf-8")
+/// Picks the encoding to count columns in, among the `general.positionEncodings` offered by a
+/// LSP 3.17 client and the older `offsetEncoding` extension.
+///
+/// UTF-8 is what we use internally, so it wins whenever the client supports it. Otherwise, the
+/// first wide encoding the client lists is used, and UTF-16 if it lists none.
+pub fn negotiated_encoding(
+    caps: &lsp_types::ClientCapabilities,
+    position_encodings: &[String],
+) -> PositionEncoding {
+    let offset_encodings = caps.offset_encoding.as_deref().unwrap_or_default();
+    if position_encodings.iter().chain(offset_encodings).any(|it| it == "utf-8") {
+        return PositionEncoding::Utf8;
+    }
+    position_encodings
+        .iter()
+        .find_map(|it| match it.as_str() {
+            "utf-16" => Some(PositionEncoding::Wide(WideEncoding::Utf16)),
+            "utf-32" => Some(PositionEncoding::Wide(WideEncoding::Utf32)),
+            _ => None,
+        })
+        .unwrap_or(PositionEncoding::Wide(WideEncoding::Utf16))
 }
 
 pub enum MoveItem {}
@@ -525,18 +872,187 @@ impl Request for OnTypeFormatting {
     const METHOD: &'static str = "textDocument/onTypeFormatting";
 }
 
+/// Same as `lsp_types::request::References`, but each location carries the
+/// category of the reference.
+pub enum References {}
+
+impl Request for References {
+    type Params = lsp_types::ReferenceParams;
+    type Result = Option<Vec<ReferenceLocation>>;
+    const METHOD: &'static str = "textDocument/references";
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReferenceLocation {
+    #[serde(flatten)]
+    pub location: lsp_types::Location,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<ReferenceCategory>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReferenceCategory {
+    Read,
+    Write,
+    Import,
+}
+
+// FIXME: the type hierarchy requests are part of LSP 3.17, drop these once
+// `lsp_types` provides them.
+pub enum PrepareTypeHierarchy {}
+
+impl Request for PrepareTypeHierarchy {
+    type Params = TypeHierarchyPrepareParams;
+    type Result = Option<Vec<TypeHierarchyItem>>;
+    const METHOD: &'static str = "textDocument/prepareTypeHierarchy";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeHierarchyPrepareParams {
+    #[serde(flatten)]
+    pub text_document_position_params: lsp_types::TextDocumentPositionParams,
+    #[serde(flatten)]
+    pub work_done_progress_params: WorkDoneProgressParams,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeHierarchyItem {
+    pub name: String,
+    pub kind: lsp_types::SymbolKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<lsp_types::SymbolTag>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub uri: lsp_types::Url,
+    pub range: Range,
+    pub selection_range: Range,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+pub enum TypeHierarchySupertypes {}
+
+impl Request for TypeHierarchySupertypes {
+    type Params = TypeHierarchySupertypesParams;
+    type Result = Option<Vec<TypeHierarchyItem>>;
+    const METHOD: &'static str = "typeHierarchy/supertypes";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeHierarchySupertypesParams {
+    pub item: TypeHierarchyItem,
+    #[serde(flatten)]
+    pub work_done_progress_params: WorkDoneProgressParams,
+    #[serde(flatten)]
+    pub partial_result_params: PartialResultParams,
+}
+
+pub enum TypeHierarchySubtypes {}
+
+impl Request for TypeHierarchySubtypes {
+    type Params = TypeHierarchySubtypesParams;
+    type Result = Option<Vec<TypeHierarchyItem>>;
+    const METHOD: &'static str = "typeHierarchy/subtypes";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeHierarchySubtypesParams {
+    pub item: TypeHierarchyItem,
+    #[serde(flatten)]
+    pub work_done_progress_params: WorkDoneProgressParams,
+    #[serde(flatten)]
+    pub partial_result_params: PartialResultParams,
+}
+
+// FIXME: pull diagnostics are part of LSP 3.17, drop these once `lsp_types`
+// provides them.
+pub enum DocumentDiagnosticRequest {}
+
+impl Request for DocumentDiagnosticRequest {
+    type Params = DocumentDiagnosticParams;
+    type Result = DocumentDiagnosticReport;
+    const METHOD: &'static str = "textDocument/diagnostic";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentDiagnosticParams {
+    pub text_document: TextDocumentIdentifier,
+    pub identifier: Option<String>,
+    pub previous_result_id: Option<String>,
+    #[serde(flatten)]
+    pub work_done_progress_params: WorkDoneProgressParams,
+    #[serde(flatten)]
+    pub partial_result_params: PartialResultParams,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum DocumentDiagnosticReport {
+    Full(FullDocumentDiagnosticReport),
+    Unchanged(UnchangedDocumentDiagnosticReport),
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FullDocumentDiagnosticReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_id: Option<String>,
+    pub items: Vec<lsp_types::Diagnostic>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UnchangedDocumentDiagnosticReport {
+    pub result_id: String,
+}
+
+pub enum WorkspaceDiagnosticRefresh {}
+
+impl Request for WorkspaceDiagnosticRefresh {
+    type Params = ();
+    type Result = ();
+    const METHOD: &'static str = "workspace/diagnostic/refresh";
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CompletionResolveData {
     pub position: lsp_types::TextDocumentPositionParams,
     pub imports: Vec<CompletionImport>,
+    /// `ide::CompletionItem::resolve_hash` as a string, set if some fields of the item were
+    /// left out, see `ide::CompletionFieldsToResolve`.
+    pub hash: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InlayHintResolveData {
+    /// Set if the tooltip is resolved from a hover.
+    pub tooltip: Option<InlayHintTooltipData>,
+    /// Set if some fields of the hint were left out, see `ide::InlayFieldsToResolve`.
+    pub hint: Option<InlayHintLazyData>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InlayHintTooltipData {
     pub text_document: TextDocumentIdentifier,
     pub position: PositionOrRange,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlayHintLazyData {
+    pub text_document: TextDocumentIdentifier,
+    /// The range of the original `textDocument/inlayHint` request.
+    pub resolve_range: Range,
+    /// `ide::InlayHint::resolve_hash`, as a string as it doesn't fit into a JS number.
+    pub hash: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CompletionImport {
     pub full_import_path: String,
@@ -1,20 +1,23 @@
 //! The main loop of `rust-analyzer` responsible for dispatching LSP
 //! requests/replies and notifications back to the client.
 use std::{
-    fmt,
-    sync::Arc,
+    fmt, iter,
+    sync::{atomic::Ordering, Arc},
     time::{Duration, Instant},
 };
 
 use always_assert::always;
 use crossbeam_channel::{select, Receiver};
-use ide_db::base_db::{SourceDatabaseExt, VfsPath};
+use ide_db::base_db::{CrateId, SourceDatabaseExt, VfsPath};
 use lsp_server::{Connection, Notification, Request};
-use lsp_types::notification::Notification as _;
-use vfs::{ChangeKind, FileId};
+use lsp_types::{notification::Notification as _, Url};
+use project_model::ProjectManifest;
+use stdx::format_to;
+use vfs::{AbsPathBuf, ChangeKind, FileId};
 
 use crate::{
-    config::Config,
+    config::{Config, ServerTraceVerbosity, LOCAL_CONFIG_FILE_NAME},
+    discover::DiscoverProjectMessage,
     dispatch::{NotificationDispatcher, RequestDispatcher},
     from_proto,
     global_state::{file_id_to_url, url_to_file_id, GlobalState},
@@ -22,7 +25,8 @@ use crate::{
     lsp_utils::{apply_document_changes, is_cancelled, notification_is, Progress},
     mem_docs::DocumentData,
     reload::{self, BuildDataProgress, ProjectWorkspaceProgress},
-    Result,
+    task_pool::Priority,
+    to_proto, Result,
 };
 
 pub fn main_loop(config: Config, connection: Connection) -> Result<()> {
@@ -61,16 +65,36 @@ enum Event {
 pub(crate) enum Task {
     Response(lsp_server::Response),
     Diagnostics(Vec<(FileId, Vec<lsp_types::Diagnostic>)>),
+    DiscoverTests(Vec<lsp_ext::DiscoverTestResults>),
     PrimeCaches(PrimeCachesProgress),
     FetchWorkspace(ProjectWorkspaceProgress),
     FetchBuildData(BuildDataProgress),
+    DiscoverProject(DiscoverProjectMessage),
 }
 
 #[derive(Debug)]
 pub(crate) enum PrimeCachesProgress {
     Begin,
     Report(ide::ParallelPrimeCachesProgress),
-    End { cancelled: bool },
+    End {
+        cancelled: bool,
+        /// The crates the priming was restricted to.
+        only: Option<Vec<CrateId>>,
+    },
+}
+
+impl Event {
+    /// The method of an LSP request or notification, or the kind of event otherwise.
+    fn name(&self) -> &str {
+        match self {
+            Event::Lsp(lsp_server::Message::Request(req)) => &req.method,
+            Event::Lsp(lsp_server::Message::Notification(not)) => &not.method,
+            Event::Lsp(lsp_server::Message::Response(_)) => "response",
+            Event::Task(_) => "task",
+            Event::Vfs(_) => "vfs",
+            Event::Flycheck(_) => "flycheck",
+        }
+    }
 }
 
 impl fmt::Debug for Event {
@@ -109,11 +133,16 @@ impl GlobalState {
     fn run(mut self, inbox: Receiver<lsp_server::Message>) -> Result<()> {
         if self.config.linked_projects().is_empty()
             && self.config.detached_files().is_empty()
+            && self.config.discover_workspace_config().is_none()
             && self.config.notifications().cargo_toml_not_found
         {
             self.show_and_log_error("rust-analyzer failed to discover workspace".to_string(), None);
         };
 
+        if self.config.pull_diagnostics() {
+            self.switch_diagnostics_mode();
+        }
+
         if self.config.did_save_text_document_dynamic_registration() {
             let save_registration_options = lsp_types::TextDocumentSaveRegistrationOptions {
                 include_text: Some(false),
@@ -134,6 +163,11 @@ impl GlobalState {
                             scheme: None,
                             pattern: Some("**/Cargo.lock".into()),
                         },
+                        lsp_types::DocumentFilter {
+                            language: None,
+                            scheme: None,
+                            pattern: Some(format!("**/{}", LOCAL_CONFIG_FILE_NAME)),
+                        },
                     ]),
                 },
             };
@@ -149,6 +183,11 @@ impl GlobalState {
             );
         }
 
+        // Workspace folders may override the settings sent in `initializationOptions`.
+        if self.config.workspace_roots.len() > 1 && self.config.workspace_configuration() {
+            self.fetch_configuration();
+        }
+
         self.fetch_workspaces_queue.request_op("startup".to_string());
         if let Some(cause) = self.fetch_workspaces_queue.should_start_op() {
             self.fetch_workspaces(cause);
@@ -186,6 +225,10 @@ impl GlobalState {
         let loop_start = Instant::now();
         // NOTE: don't count blocking select! call as a loop-turn time
         let _p = profile::span("GlobalState::handle_event");
+        // The span only evaluates the name when it is enabled.
+        let _span = tracing::info_span!("handle_event", event = event.name()).entered();
+        let traced_name = (self.config.server_trace() >= ServerTraceVerbosity::Verbose)
+            .then(|| event.name().to_owned());
 
         tracing::debug!("handle_event({:?})", event);
         let task_queue_len = self.task_pool.handle.len();
@@ -213,6 +256,11 @@ impl GlobalState {
                                 self.diagnostics.set_native_diagnostics(file_id, diagnostics)
                             }
                         }
+                        Task::DiscoverTests(results) => {
+                            for result in results {
+                                self.send_notification::<lsp_ext::DiscoveredTests>(result);
+                            }
+                        }
                         Task::PrimeCaches(progress) => match progress {
                             PrimeCachesProgress::Begin => prime_caches_progress.push(progress),
                             PrimeCachesProgress::Report(_) => {
@@ -240,6 +288,11 @@ impl GlobalState {
                                     let workspaces_updated = !Arc::ptr_eq(&old, &self.workspaces);
 
                                     if self.config.run_build_scripts() && workspaces_updated {
+                                        // Only the workspaces that were fetched again need to run
+                                        // their build scripts, unless a full rebuild is pending.
+                                        if !self.fetch_build_data_queue.op_requested() {
+                                            self.keep_build_scripts = true;
+                                        }
                                         self.fetch_build_data_queue
                                             .request_op(format!("workspace updated"));
                                     }
@@ -248,7 +301,7 @@ impl GlobalState {
                                 }
                             };
 
-                            self.report_progress("Fetching", state, msg, None);
+                            self.report_progress("Fetching", state, msg, None, false);
                         }
                         Task::FetchBuildData(progress) => {
                             let (state, msg) = match progress {
@@ -266,7 +319,51 @@ impl GlobalState {
                             };
 
                             if let Some(state) = state {
-                                self.report_progress("Loading", state, msg, None);
+                                self.report_progress("Loading", state, msg, None, false);
+                            }
+                        }
+                        Task::DiscoverProject(msg) => {
+                            let title = self
+                                .config
+                                .discover_workspace_config()
+                                .map_or("Discovering workspace", |it| it.progress_label())
+                                .to_string();
+                            match msg {
+                                DiscoverProjectMessage::Begin => {
+                                    self.report_progress(&title, Progress::Begin, None, None, false)
+                                }
+                                DiscoverProjectMessage::Finished { arg, project } => {
+                                    self.finish_discovering(&arg);
+                                    // Files of the same project yield the same output, only
+                                    // keep one of them around for re-running the command.
+                                    let unchanged =
+                                        self.discovered_workspaces.get(&arg) == Some(&project);
+                                    let duplicate = !self.discovered_workspaces.contains_key(&arg)
+                                        && self
+                                            .discovered_workspaces
+                                            .values()
+                                            .any(|it| *it == project);
+                                    if !unchanged && !duplicate {
+                                        self.discovered_workspaces.insert(arg.clone(), project);
+                                        self.request_workspace_reload(format!(
+                                            "discovered project for {}",
+                                            arg.display()
+                                        ));
+                                    }
+                                }
+                                DiscoverProjectMessage::Error { arg, error } => {
+                                    self.finish_discovering(&arg);
+                                    self.show_and_log_error(
+                                        format!(
+                                            "rust-analyzer failed to discover a project for {}",
+                                            arg.display()
+                                        ),
+                                        Some(error),
+                                    )
+                                }
+                                DiscoverProjectMessage::End => {
+                                    self.report_progress(&title, Progress::End, None, None, false)
+                                }
                             }
                         }
                     }
@@ -306,20 +403,26 @@ impl GlobalState {
 
                             fraction = Progress::fraction(report.crates_done, report.crates_total);
                         }
-                        PrimeCachesProgress::End { cancelled } => {
+                        PrimeCachesProgress::End { cancelled, only } => {
                             state = Progress::End;
-                            message = None;
                             fraction = 1.0;
 
                             self.prime_caches_queue.op_completed(());
                             if cancelled {
-                                self.prime_caches_queue
-                                    .request_op("restart after cancellation".to_string());
+                                self.request_prime_caches(
+                                    "restart after cancellation".to_string(),
+                                    only,
+                                );
                             }
+                            // Cancelled by the user, this is not restarted until requested again.
+                            message = self
+                                .prime_caches_cancel
+                                .load(Ordering::Relaxed)
+                                .then(|| "cancelled".to_string());
                         }
                     };
 
-                    self.report_progress("Indexing", state, message, Some(fraction));
+                    self.report_progress("Indexing", state, message, Some(fraction), true);
                 }
             }
             Event::Vfs(mut task) => {
@@ -327,13 +430,47 @@ impl GlobalState {
                 loop {
                     match task {
                         vfs::loader::Message::Loaded { files } => {
-                            let vfs = &mut self.vfs.write().0;
-                            for (path, contents) in files {
-                                let path = VfsPath::from(path);
-                                if !self.mem_docs.contains(&path) {
-                                    vfs.set_file_contents(path, contents);
+                            let size_limit = self.config.files().generated_file_size_limit;
+                            let mut exclusion_changes = Vec::new();
+                            {
+                                let vfs = &mut self.vfs.write().0;
+                                for (path, mut contents) in files {
+                                    let vfs_path = VfsPath::from(path.clone());
+                                    if self.mem_docs.contains(&vfs_path) {
+                                        continue;
+                                    }
+                                    let excluded_size = match (&contents, size_limit) {
+                                        (Some(it), Some(limit))
+                                            if is_large_generated_file(it, limit) =>
+                                        {
+                                            Some(it.len())
+                                        }
+                                        _ => None,
+                                    };
+                                    if excluded_size.is_some() {
+                                        contents = None;
+                                    }
+                                    if excluded_size
+                                        != self.excluded_generated_files.get(&path).copied()
+                                    {
+                                        exclusion_changes.push((path, excluded_size));
+                                    }
+                                    vfs.set_file_contents(vfs_path, contents);
                                 }
                             }
+                            let exclusion_changed = !exclusion_changes.is_empty();
+                            for (path, excluded_size) in exclusion_changes {
+                                self.set_generated_file_excluded(path, excluded_size);
+                            }
+                            if exclusion_changed
+                                && self.config.pull_diagnostics()
+                                && self.config.diagnostics_refresh()
+                            {
+                                self.send_request::<lsp_ext::WorkspaceDiagnosticRefresh>(
+                                    (),
+                                    |_, _| (),
+                                );
+                            }
                         }
                         vfs::loader::Message::Progress { n_total, n_done, config_version } => {
                             always!(config_version <= self.vfs_config_version);
@@ -355,6 +492,7 @@ impl GlobalState {
                                 state,
                                 Some(format!("{}/{}", n_done, n_total)),
                                 Some(Progress::fraction(n_done, n_total)),
+                                false,
                             )
                         }
                     }
@@ -397,13 +535,24 @@ impl GlobalState {
 
                         flycheck::Message::Progress { id, progress } => {
                             let (state, message) = match progress {
-                                flycheck::Progress::DidStart => {
+                                flycheck::Progress::DidStart(None) => {
                                     self.diagnostics.clear_check();
                                     (Progress::Begin, None)
                                 }
+                                flycheck::Progress::DidStart(Some(packages)) => {
+                                    // Diagnostics of the packages that aren't checked stay.
+                                    self.clear_check_for_packages(id, &packages);
+                                    (Progress::Begin, None)
+                                }
+                                flycheck::Progress::DidStartCrate(name) => {
+                                    (Progress::Report, Some(format!("checking {}", name)))
+                                }
                                 flycheck::Progress::DidCheckCrate(target) => {
                                     (Progress::Report, Some(target))
                                 }
+                                flycheck::Progress::DidStartClippy => {
+                                    (Progress::Report, Some("clippy".to_string()))
+                                }
                                 flycheck::Progress::DidCancel => (Progress::End, None),
                                 flycheck::Progress::DidFinish(result) => {
                                     if let Err(err) = result {
@@ -416,17 +565,8 @@ impl GlobalState {
                                 }
                             };
 
-                            // When we're running multiple flychecks, we have to include a disambiguator in
-                            // the title, or the editor complains. Note that this is a user-facing string.
-                            let title = if self.flycheck.len() == 1 {
-                                match self.config.flycheck() {
-                                    Some(config) => format!("{}", config),
-                                    None => "cargo check".to_string(),
-                                }
-                            } else {
-                                format!("cargo check (#{})", id + 1)
-                            };
-                            self.report_progress(&title, state, message, None);
+                            let title = self.flycheck_title(id);
+                            self.report_progress(&title, state, message, None, true);
                         }
                     }
                     // Coalesce many flycheck updates into a single loop turn
@@ -447,14 +587,14 @@ impl GlobalState {
                     flycheck.update();
                 }
                 if self.config.prefill_caches() {
-                    self.prime_caches_queue.request_op("became quiescent".to_string());
+                    self.request_prime_caches("became quiescent".to_string(), None);
                 }
             }
 
             if !was_quiescent || state_changed {
-                // Refresh semantic tokens if the client supports it.
+                // Refresh semantic tokens if the client supports it. The cached tokens are kept,
+                // the client asks for a delta against the ones it last received.
                 if self.config.semantic_tokens_refresh() {
-                    self.semantic_tokens_cache.lock().clear();
                     self.send_request::<lsp_types::request::SemanticTokensRefresh>((), |_, _| ());
                 }
 
@@ -462,37 +602,54 @@ impl GlobalState {
                 if self.config.code_lens_refresh() {
                     self.send_request::<lsp_types::request::CodeLensRefresh>((), |_, _| ());
                 }
+
+                // Let the client pull the diagnostics again.
+                if self.config.pull_diagnostics() && self.config.diagnostics_refresh() {
+                    self.send_request::<lsp_ext::WorkspaceDiagnosticRefresh>((), |_, _| ());
+                }
             }
 
             if !was_quiescent || state_changed || memdocs_added_or_removed {
-                if self.config.publish_diagnostics() {
+                if !self.config.pull_diagnostics() {
                     self.update_diagnostics()
                 }
+                if self.config.test_explorer() {
+                    self.update_tests()
+                }
             }
+
+            self.check_memory_usage();
         }
 
         if let Some(diagnostic_changes) = self.diagnostics.take_changes() {
-            for file_id in diagnostic_changes {
-                let db = self.analysis_host.raw_database();
-                let source_root = db.file_source_root(file_id);
-                if db.source_root(source_root).is_library {
-                    // Only publish diagnostics for files in the workspace, not from crates.io deps
-                    // or the sysroot.
-                    // While theoretically these should never have errors, we have quite a few false
-                    // positives particularly in the stdlib, and those diagnostics would stay around
-                    // forever if we emitted them here.
-                    continue;
-                }
-
-                let url = file_id_to_url(&self.vfs.read().0, file_id);
-                let diagnostics = self.diagnostics.diagnostics_for(file_id).cloned().collect();
-                let version = from_proto::vfs_path(&url)
-                    .map(|path| self.mem_docs.get(&path).map(|it| it.version))
-                    .unwrap_or_default();
-
-                self.send_notification::<lsp_types::notification::PublishDiagnostics>(
-                    lsp_types::PublishDiagnosticsParams { uri: url, diagnostics, version },
-                );
+            if self.config.pull_diagnostics() {
+                // Flycheck results changed, the client has to ask for them.
+                if self.config.diagnostics_refresh() {
+                    self.send_request::<lsp_ext::WorkspaceDiagnosticRefresh>((), |_, _| ());
+                }
+            } else {
+                for file_id in diagnostic_changes {
+                    let db = self.analysis_host.raw_database();
+                    let source_root = db.file_source_root(file_id);
+                    if db.source_root(source_root).is_library {
+                        // Only publish diagnostics for files in the workspace, not from crates.io
+                        // deps or the sysroot.
+                        // While theoretically these should never have errors, we have quite a few
+                        // false positives particularly in the stdlib, and those diagnostics would
+                        // stay around forever if we emitted them here.
+                        continue;
+                    }
+
+                    let url = file_id_to_url(&self.vfs.read().0, file_id);
+                    let diagnostics = self.diagnostics.diagnostics_for(file_id).cloned().collect();
+                    let version = from_proto::vfs_path(&url)
+                        .map(|path| self.mem_docs.get(&path).map(|it| it.version))
+                        .unwrap_or_default();
+
+                    self.send_notification::<lsp_types::notification::PublishDiagnostics>(
+                        lsp_types::PublishDiagnosticsParams { uri: url, diagnostics, version },
+                    );
+                }
             }
         }
 
@@ -511,18 +668,27 @@ impl GlobalState {
         if let Some(cause) = self.prime_caches_queue.should_start_op() {
             tracing::debug!(%cause, "will prime caches");
             let num_worker_threads = self.config.prime_caches_num_threads();
+            let only = self.prime_caches_only.take();
+            self.prime_caches_cancel = Arc::default();
 
-            self.task_pool.handle.spawn_with_sender({
+            self.task_pool.handle.spawn_with_sender(Priority::Background, {
                 let analysis = self.snapshot().analysis;
+                let cancel = Arc::clone(&self.prime_caches_cancel);
                 move |sender| {
                     sender.send(Task::PrimeCaches(PrimeCachesProgress::Begin)).unwrap();
-                    let res = analysis.parallel_prime_caches(num_worker_threads, |progress| {
-                        let report = PrimeCachesProgress::Report(progress);
-                        sender.send(Task::PrimeCaches(report)).unwrap();
-                    });
+                    let res = analysis.parallel_prime_caches(
+                        num_worker_threads,
+                        only.as_deref(),
+                        &cancel,
+                        |progress| {
+                            let report = PrimeCachesProgress::Report(progress);
+                            sender.send(Task::PrimeCaches(report)).unwrap();
+                        },
+                    );
                     sender
                         .send(Task::PrimeCaches(PrimeCachesProgress::End {
                             cancelled: res.is_err(),
+                            only,
                         }))
                         .unwrap();
                 }
@@ -533,8 +699,10 @@ impl GlobalState {
         if self.last_reported_status.as_ref() != Some(&status) {
             self.last_reported_status = Some(status.clone());
 
-            if let (lsp_ext::Health::Error, Some(message)) = (status.health, &status.message) {
-                self.show_message(lsp_types::MessageType::ERROR, message.clone());
+            for problem in &status.problems {
+                if problem.health == lsp_ext::Health::Error {
+                    self.show_message(lsp_types::MessageType::ERROR, problem.message.clone());
+                }
             }
 
             if self.config.server_status_notification() {
@@ -543,6 +711,14 @@ impl GlobalState {
         }
 
         let loop_duration = loop_start.elapsed();
+        if let Some(name) = traced_name {
+            self.send_notification::<lsp_ext::ServerTrace>(lsp_ext::ServerTraceParams {
+                kind: lsp_ext::ServerTraceKind::LoopTurn,
+                name,
+                id: None,
+                duration_ms: loop_duration.as_millis() as u64,
+            });
+        }
         if loop_duration > Duration::from_millis(100) && was_quiescent {
             tracing::warn!("overly long loop turn: {:?}", loop_duration);
             self.poke_rust_analyzer_developer(format!(
@@ -553,6 +729,88 @@ impl GlobalState {
         Ok(())
     }
 
+    /// Records that a generated file is left out of the analysis because of
+    /// `files.generatedFileSizeLimit`, or that it isn't anymore. The user is told with a
+    /// diagnostic on the file, which clients pulling diagnostics ask for themselves.
+    fn set_generated_file_excluded(&mut self, path: AbsPathBuf, excluded_size: Option<usize>) {
+        let uri = to_proto::url_from_abs_path(&path);
+        let excluded = Arc::make_mut(&mut self.excluded_generated_files);
+        let diagnostics = match excluded_size {
+            Some(size) => {
+                excluded.insert(path, size);
+                vec![crate::diagnostics::excluded_generated_file(size)]
+            }
+            None => {
+                excluded.remove(&path);
+                Vec::new()
+            }
+        };
+        if !self.config.pull_diagnostics() {
+            self.send_notification::<lsp_types::notification::PublishDiagnostics>(
+                lsp_types::PublishDiagnosticsParams { uri, diagnostics, version: None },
+            );
+        }
+    }
+
+    /// Evicts the caches that are cheap to recompute when the memory usage crosses
+    /// `memory.evictionThreshold`, and restores the LRU capacity once it went back below, see
+    /// [`memory_action`].
+    fn check_memory_usage(&mut self) {
+        const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+        let threshold = match self.config.memory_eviction_threshold() {
+            Some(it) => it as isize,
+            None => return,
+        };
+        // Measuring the memory usage is slow with some allocators.
+        if self.last_memory_check.elapsed() < CHECK_INTERVAL {
+            return;
+        }
+        self.last_memory_check = Instant::now();
+
+        let before = profile::memory_usage().allocated;
+        match memory_action(before.megabytes(), threshold, self.memory_after_eviction) {
+            MemoryAction::Nothing => return,
+            MemoryAction::Restore => {
+                self.memory_after_eviction = None;
+                self.analysis_host.update_lru_capacity(self.config.lru_capacity());
+                return;
+            }
+            MemoryAction::Evict => (),
+        }
+
+        let freed = self.analysis_host.evict_caches(self.config.lru_capacity());
+        let after = profile::memory_usage().allocated;
+        self.memory_after_eviction = Some(after.megabytes());
+
+        let mut message = format!(
+            "memory usage of {} exceeds the threshold of {}mb, evicted caches down to {}:",
+            before, threshold, after
+        );
+        for (step, bytes) in freed {
+            format_to!(message, "\n  {:>8} {}", bytes, step);
+        }
+        tracing::warn!("{}", message);
+        self.send_notification::<lsp_types::notification::LogMessage>(
+            lsp_types::LogMessageParams { typ: lsp_types::MessageType::WARNING, message },
+        );
+    }
+
+    /// The title of the progress of the flycheck with the given `id`, which also names its
+    /// progress token.
+    fn flycheck_title(&self, id: usize) -> String {
+        // When we're running multiple flychecks, we have to include a disambiguator in
+        // the title, or the editor complains. Note that this is a user-facing string.
+        if self.flycheck.len() == 1 {
+            match self.config.flycheck() {
+                Some(config) => format!("{}", config),
+                None => "cargo check".to_string(),
+            }
+        } else {
+            format!("cargo check (#{})", id + 1)
+        }
+    }
+
     fn on_request(&mut self, request_received: Instant, req: Request) -> Result<()> {
         self.register_request(&req, request_received);
 
@@ -582,6 +840,9 @@ impl GlobalState {
                 Ok(())
             })?
             .on_sync_mut::<lsp_ext::ReloadWorkspace>(handlers::handle_workspace_reload)?
+            .on_sync_mut::<lsp_ext::RebuildProcMacros>(handlers::handle_proc_macros_rebuild)?
+            .on_sync_mut::<lsp_ext::SetActiveTarget>(handlers::handle_set_active_target)?
+            .on_sync_mut::<lsp_ext::PrimeCaches>(handlers::handle_prime_caches)?
             .on_sync_mut::<lsp_ext::MemoryUsage>(handlers::handle_memory_usage)?
             .on_sync_mut::<lsp_ext::ShuffleCrateGraph>(handlers::handle_shuffle_crate_graph)?
             .on_sync::<lsp_ext::JoinLines>(handlers::handle_join_lines)?
@@ -591,21 +852,35 @@ impl GlobalState {
             .on::<lsp_ext::AnalyzerStatus>(handlers::handle_analyzer_status)
             .on::<lsp_ext::SyntaxTree>(handlers::handle_syntax_tree)
             .on::<lsp_ext::ViewHir>(handlers::handle_view_hir)
+            .on::<lsp_ext::ViewMir>(handlers::handle_view_mir)
+            .on::<lsp_ext::InterpretFunction>(handlers::handle_interpret_function)
+            .on::<lsp_ext::EvaluateExpression>(handlers::handle_evaluate_expression)
             .on::<lsp_ext::ViewFileText>(handlers::handle_view_file_text)
             .on::<lsp_ext::ViewCrateGraph>(handlers::handle_view_crate_graph)
+            .on::<lsp_ext::DependencyTree>(handlers::handle_dependency_tree)
             .on::<lsp_ext::ViewItemTree>(handlers::handle_view_item_tree)
             .on::<lsp_ext::ExpandMacro>(handlers::handle_expand_macro)
+            .on::<lsp_ext::RerunProcMacroWithBacktrace>(
+                handlers::handle_rerun_proc_macro_with_backtrace,
+            )
             .on::<lsp_ext::ParentModule>(handlers::handle_parent_module)
             .on::<lsp_ext::Runnables>(handlers::handle_runnables)
             .on::<lsp_ext::RelatedTests>(handlers::handle_related_tests)
+            .on::<lsp_ext::DebugLaunch>(handlers::handle_debug_launch)
+            .on::<lsp_ext::DiscoverTest>(handlers::handle_discover_test)
             .on::<lsp_ext::CodeActionRequest>(handlers::handle_code_action)
             .on::<lsp_ext::CodeActionResolveRequest>(handlers::handle_code_action_resolve)
             .on::<lsp_ext::HoverRequest>(handlers::handle_hover)
+            .on::<lsp_ext::References>(handlers::handle_references)
             .on::<lsp_ext::ExternalDocs>(handlers::handle_open_docs)
             .on::<lsp_ext::OpenCargoToml>(handlers::handle_open_cargo_toml)
             .on::<lsp_ext::MoveItem>(handlers::handle_move_item)
             .on::<lsp_ext::WorkspaceSymbol>(handlers::handle_workspace_symbol)
             .on::<lsp_ext::OnTypeFormatting>(handlers::handle_on_type_formatting)
+            .on::<lsp_ext::PrepareTypeHierarchy>(handlers::handle_type_hierarchy_prepare)
+            .on::<lsp_ext::TypeHierarchySupertypes>(handlers::handle_type_hierarchy_supertypes)
+            .on::<lsp_ext::TypeHierarchySubtypes>(handlers::handle_type_hierarchy_subtypes)
+            .on::<lsp_ext::DocumentDiagnosticRequest>(handlers::handle_document_diagnostic)
             .on::<lsp_types::request::DocumentSymbolRequest>(handlers::handle_document_symbol)
             .on::<lsp_types::request::GotoDefinition>(handlers::handle_goto_definition)
             .on::<lsp_types::request::GotoDeclaration>(handlers::handle_goto_declaration)
@@ -621,7 +896,6 @@ impl GlobalState {
             .on::<lsp_types::request::SignatureHelpRequest>(handlers::handle_signature_help)
             .on::<lsp_types::request::PrepareRenameRequest>(handlers::handle_prepare_rename)
             .on::<lsp_types::request::Rename>(handlers::handle_rename)
-            .on::<lsp_types::request::References>(handlers::handle_references)
             .on::<lsp_types::request::Formatting>(handlers::handle_formatting)
             .on::<lsp_types::request::RangeFormatting>(handlers::handle_range_formatting)
             .on::<lsp_types::request::DocumentHighlightRequest>(handlers::handle_document_highlight)
@@ -656,10 +930,28 @@ impl GlobalState {
                 this.cancel(id);
                 Ok(())
             })?
-            .on::<lsp_types::notification::WorkDoneProgressCancel>(|_this, _params| {
-                // Just ignore this. It is OK to continue sending progress
-                // notifications for this token, as the client can't know when
-                // we accepted notification.
+            .on::<lsp_types::notification::WorkDoneProgressCancel>(|this, params| {
+                // Only indexing and flychecks can be cancelled. It is OK to continue
+                // sending progress notifications for this token, as the client can't
+                // know when we accepted notification.
+                let token = match params.token {
+                    lsp_types::ProgressToken::String(it) => it,
+                    lsp_types::ProgressToken::Number(_) => return Ok(()),
+                };
+                if token == "rustAnalyzer/Indexing" {
+                    this.prime_caches_cancel.store(true, Ordering::Relaxed);
+                }
+                for flycheck in &this.flycheck {
+                    if token == format!("rustAnalyzer/{}", this.flycheck_title(flycheck.id())) {
+                        flycheck.cancel();
+                    }
+                }
+                Ok(())
+            })?
+            .on::<lsp_ext::CancelFlycheck>(|this, ()| {
+                for flycheck in &this.flycheck {
+                    flycheck.cancel();
+                }
                 Ok(())
             })?
             .on::<lsp_types::notification::DidOpenTextDocument>(|this, params| {
@@ -674,7 +966,12 @@ impl GlobalState {
                     this.vfs
                         .write()
                         .0
-                        .set_file_contents(path, Some(params.text_document.text.into_bytes()));
+                        .set_file_contents(path.clone(), Some(params.text_document.text.into_bytes()));
+                    if let Some(abs_path) = path.as_path() {
+                        if abs_path.extension().unwrap_or_default() == "rs" {
+                            this.discover_workspace_for(abs_path.to_path_buf());
+                        }
+                    }
                 }
                 Ok(())
             })?
@@ -691,11 +988,16 @@ impl GlobalState {
                             return Ok(());
                         }
                     };
+                    this.task_pool.handle.drop_stale(&params.text_document.uri);
 
                     let vfs = &mut this.vfs.write().0;
                     let file_id = vfs.file_id(&path).unwrap();
                     let mut text = String::from_utf8(vfs.file_contents(file_id).to_vec()).unwrap();
-                    apply_document_changes(&mut text, params.content_changes);
+                    apply_document_changes(
+                        this.config.position_encoding(),
+                        &mut text,
+                        params.content_changes,
+                    );
 
                     vfs.set_file_contents(path, Some(text.into_bytes()));
                 }
@@ -706,6 +1008,7 @@ impl GlobalState {
                     if this.mem_docs.remove(&path).is_err() {
                         tracing::error!("orphan DidCloseTextDocument: {}", path);
                     }
+                    this.task_pool.handle.drop_stale(&params.text_document.uri);
 
                     this.semantic_tokens_cache.lock().remove(&params.text_document.uri);
 
@@ -716,67 +1019,156 @@ impl GlobalState {
                 Ok(())
             })?
             .on::<lsp_types::notification::DidSaveTextDocument>(|this, params| {
-                for flycheck in &this.flycheck {
-                    flycheck.update();
+                let abs_path = from_proto::abs_path(&params.text_document.uri);
+                // Checking on save can be turned off for some paths only.
+                let check_on_save = match &abs_path {
+                    Ok(path) => this.config.for_path(path).flycheck().is_some(),
+                    Err(_) => true,
+                };
+                if check_on_save {
+                    // Manifest changes can affect the whole workspace, everything else only the
+                    // package owning the file and its reverse dependencies.
+                    let path = abs_path.as_ref().ok().filter(|path| {
+                        !reload::should_refresh_for_change(path, ChangeKind::Modify)
+                    });
+                    for flycheck in &this.flycheck {
+                        match path.and_then(|path| this.affected_packages(flycheck.id(), path)) {
+                            Some(packages) => flycheck.update_packages(packages),
+                            None => flycheck.update(),
+                        }
+                    }
                 }
-                if let Ok(abs_path) = from_proto::abs_path(&params.text_document.uri) {
+                if let Ok(abs_path) = abs_path {
                     if reload::should_refresh_for_change(&abs_path, ChangeKind::Modify) {
-                        this.fetch_workspaces_queue.request_op(format!("DidSaveTextDocument {}", abs_path.display()));
+                        let cause = format!("DidSaveTextDocument {}", abs_path.display());
+                        this.request_workspace_reload_for(abs_path.clone(), cause);
+                    }
+                    if this.config.local_config_paths().contains(&abs_path) {
+                        this.reload_local_config();
+                    }
+                    if this.config.discover_workspace_config().map_or(false, |it| it.watches(&abs_path)) {
+                        this.rediscover_workspaces(format!("DidSaveTextDocument {}", abs_path.display()));
                     }
                 }
                 Ok(())
             })?
             .on::<lsp_types::notification::DidChangeConfiguration>(|this, _params| {
-                // As stated in https://github.com/microsoft/language-server-protocol/issues/676,
-                // this notification's parameters should be ignored and the actual config queried separately.
-                this.send_request::<lsp_types::request::WorkspaceConfiguration>(
-                    lsp_types::ConfigurationParams {
-                        items: vec![lsp_types::ConfigurationItem {
-                            scope_uri: None,
-                            section: Some("rust-analyzer".to_string()),
-                        }],
-                    },
-                    |this, resp| {
-                        tracing::debug!("config update response: '{:?}", resp);
-                        let lsp_server::Response { error, result, .. } = resp;
-
-                        match (error, result) {
-                            (Some(err), _) => {
-                                tracing::error!("failed to fetch the server settings: {:?}", err)
-                            }
-                            (None, Some(mut configs)) => {
-                                if let Some(json) = configs.get_mut(0) {
-                                    // Note that json can be null according to the spec if the client can't
-                                    // provide a configuration. This is handled in Config::update below.
-                                    let mut config = Config::clone(&*this.config);
-                                    if let Err(error) = config.update(json.take()) {
-                                        this.show_message(lsp_types::MessageType::WARNING, error.to_string());
-                                    }
-                                    this.update_configuration(config);
-                                }
-                            }
-                            (None, None) => tracing::error!(
-                                "received empty server settings response from the client"
-                            ),
+                this.fetch_configuration();
+                Ok(())
+            })?
+            .on::<lsp_types::notification::DidChangeWorkspaceFolders>(|this, params| {
+                let mut config = Config::clone(&*this.config);
+                for workspace in params.event.removed {
+                    if let Ok(path) = from_proto::abs_path(&workspace.uri) {
+                        config.workspace_roots.retain(|it| *it != path);
+                    }
+                }
+                for workspace in params.event.added {
+                    if let Ok(path) = from_proto::abs_path(&workspace.uri) {
+                        if !config.workspace_roots.contains(&path) {
+                            config.workspace_roots.push(path);
                         }
-                    },
-                );
-
+                    }
+                }
+                if config.discovered_projects.is_some() {
+                    let discovered = ProjectManifest::discover_all(&config.workspace_roots);
+                    config.discovered_projects = Some(discovered);
+                }
+                // Reloads the workspaces if the discovered projects changed.
+                this.update_configuration(config);
+                // The settings of the new folders may differ from the global ones.
+                if this.config.workspace_configuration() {
+                    this.fetch_configuration();
+                }
                 Ok(())
             })?
             .on::<lsp_types::notification::DidChangeWatchedFiles>(|this, params| {
+                let local_config_paths = this.config.local_config_paths();
+                let mut local_config_changed = false;
+                let mut build_file_changed = None;
                 for change in params.changes {
                     if let Ok(path) = from_proto::abs_path(&change.uri) {
+                        local_config_changed |= local_config_paths.contains(&path);
+                        if this.config.discover_workspace_config().map_or(false, |it| it.watches(&path)) {
+                            build_file_changed = Some(path.clone());
+                        }
                         this.loader.handle.invalidate(path);
                     }
                 }
+                if local_config_changed {
+                    this.reload_local_config();
+                }
+                if let Some(path) = build_file_changed {
+                    this.rediscover_workspaces(format!("DidChangeWatchedFiles {}", path.display()));
+                }
                 Ok(())
             })?
             .finish();
         Ok(())
     }
 
-    fn update_diagnostics(&mut self) {
+    /// Asks the client for the global configuration and the one of each workspace folder.
+    fn fetch_configuration(&mut self) {
+        // As stated in https://github.com/microsoft/language-server-protocol/issues/676,
+        // the parameters of `DidChangeConfiguration` should be ignored and the actual config
+        // queried separately.
+        let roots = self.config.workspace_roots.clone();
+        let items = iter::once(None)
+            .chain(roots.iter().map(|root| Url::from_file_path(root).ok()))
+            .map(|scope_uri| lsp_types::ConfigurationItem {
+                scope_uri,
+                section: Some("rust-analyzer".to_string()),
+            })
+            .collect();
+        self.send_request::<lsp_types::request::WorkspaceConfiguration>(
+            lsp_types::ConfigurationParams { items },
+            move |this, resp| {
+                tracing::debug!("config update response: '{:?}", resp);
+                let lsp_server::Response { error, result, .. } = resp;
+
+                match (error, result) {
+                    (Some(err), _) => {
+                        tracing::error!("failed to fetch the server settings: {:?}", err)
+                    }
+                    (None, Some(mut configs)) => {
+                        if configs.is_empty() {
+                            return;
+                        }
+                        // Note that json can be null according to the spec if the client can't
+                        // provide a configuration. This is handled in Config::update below.
+                        let json = configs.remove(0);
+                        let folder_configs = roots.into_iter().zip(configs).collect();
+                        let mut config = Config::clone(&*this.config);
+                        if let Err(error) = config.update(json) {
+                            this.show_message(lsp_types::MessageType::WARNING, error.to_string());
+                        }
+                        if let Err(error) = config.update_folders(folder_configs) {
+                            this.show_message(lsp_types::MessageType::WARNING, error.to_string());
+                        }
+                        this.update_configuration(config);
+                    }
+                    (None, None) => {
+                        tracing::error!("received empty server settings response from the client")
+                    }
+                }
+            },
+        );
+    }
+
+    /// Requests priming the caches of `only` and their dependencies, or of the whole workspace.
+    pub(crate) fn request_prime_caches(&mut self, cause: String, only: Option<Vec<CrateId>>) {
+        self.prime_caches_only = match (self.prime_caches_queue.op_requested(), only) {
+            (false, only) => only,
+            (true, Some(only)) => self.prime_caches_only.take().map(|mut pending| {
+                pending.extend(only);
+                pending
+            }),
+            (true, None) => None,
+        };
+        self.prime_caches_queue.request_op(cause);
+    }
+
+    pub(crate) fn update_diagnostics(&mut self) {
         let subscriptions = self
             .mem_docs
             .iter()
@@ -786,7 +1178,7 @@ impl GlobalState {
         tracing::trace!("updating notifications for {:?}", subscriptions);
 
         let snapshot = self.snapshot();
-        self.task_pool.handle.spawn(move || {
+        self.task_pool.handle.spawn(Priority::Background, move || {
             let diagnostics = subscriptions
                 .into_iter()
                 .filter_map(|file_id| {
@@ -803,4 +1195,103 @@ impl GlobalState {
             Task::Diagnostics(diagnostics)
         })
     }
+
+    fn update_tests(&mut self) {
+        let subscriptions = self
+            .mem_docs
+            .iter()
+            .map(|path| self.vfs.read().0.file_id(path).unwrap())
+            .collect::<Vec<_>>();
+
+        let snapshot = self.snapshot();
+        self.task_pool.handle.spawn(Priority::Background, move || {
+            let results = subscriptions
+                .into_iter()
+                .filter_map(|file_id| {
+                    handlers::discover_tests_in_file(&snapshot, file_id)
+                        .map_err(|err| {
+                            if !is_cancelled(&*err) {
+                                tracing::error!("failed to discover tests: {:?}", err);
+                            }
+                        })
+                        .ok()
+                })
+                .collect::<Vec<_>>();
+            Task::DiscoverTests(results)
+        })
+    }
+}
+
+/// Whether `contents` is larger than `limit` bytes and marked as generated in its first lines.
+fn is_large_generated_file(contents: &[u8], limit: usize) -> bool {
+    contents.len() > limit
+        && contents.split(|&b| b == b'\n').take(5).any(|line| {
+            let line = String::from_utf8_lossy(line);
+            line.contains("@generated") || line.contains("automatically generated")
+        })
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum MemoryAction {
+    Nothing,
+    Evict,
+    Restore,
+}
+
+/// What to do about the caches at a memory usage of `usage` megabytes. The caches are evicted
+/// above `threshold`, and restored below nine tenths of it. When the last eviction didn't get
+/// below the threshold, the next one waits until the usage grew by another tenth of it, as it
+/// would mostly cancel the pending requests for nothing.
+fn memory_action(
+    usage: isize,
+    threshold: isize,
+    after_last_eviction: Option<isize>,
+) -> MemoryAction {
+    let margin = threshold / 10;
+    match after_last_eviction {
+        Some(_) if usage < threshold - margin => MemoryAction::Restore,
+        _ if usage < threshold => MemoryAction::Nothing,
+        Some(after) if usage < after.max(threshold) + margin => MemoryAction::Nothing,
+        _ => MemoryAction::Evict,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn large_generated_files() {
+        let generated = b"// @generated by prost-build\nstruct Foo;\n";
+        assert!(is_large_generated_file(generated, 10));
+        assert!(!is_large_generated_file(generated, generated.len()));
+
+        let generated =
+            b"#![allow(warnings)]\n\n//! This file was automatically generated.\nstruct Foo;\n";
+        assert!(is_large_generated_file(generated, 10));
+
+        // Only the first lines are searched for the marker.
+        let late = b"\n\n\n\n\n// @generated\nstruct Foo;\n";
+        assert!(!is_large_generated_file(late, 10));
+
+        let handwritten = b"//! Not generated.\nstruct Foo;\n";
+        assert!(!is_large_generated_file(handwritten, 10));
+    }
+
+    #[test]
+    fn memory_action_has_hysteresis() {
+        assert_eq!(memory_action(900, 1000, None), MemoryAction::Nothing);
+        assert_eq!(memory_action(1000, 1000, None), MemoryAction::Evict);
+
+        // Evicting got below the threshold.
+        assert_eq!(memory_action(950, 1000, Some(800)), MemoryAction::Nothing);
+        assert_eq!(memory_action(1000, 1000, Some(800)), MemoryAction::Nothing);
+        assert_eq!(memory_action(1100, 1000, Some(800)), MemoryAction::Evict);
+        assert_eq!(memory_action(850, 1000, Some(800)), MemoryAction::Restore);
+
+        // Evicting didn't help, only evict again once the usage grew.
+        assert_eq!(memory_action(1300, 1000, Some(1250)), MemoryAction::Nothing);
+        assert_eq!(memory_action(1349, 1000, Some(1250)), MemoryAction::Nothing);
+        assert_eq!(memory_action(1350, 1000, Some(1250)), MemoryAction::Evict);
+    }
 }
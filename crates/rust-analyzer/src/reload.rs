@@ -14,24 +14,31 @@
 //! still want to respond to various  requests.
 use std::{mem, sync::Arc};
 
-use flycheck::{FlycheckConfig, FlycheckHandle};
-use hir::db::DefDatabase;
+use flycheck::{FlycheckConfig, FlycheckHandle, PackageToCheck};
+use hir::db::{DefDatabase, HirDatabase};
 use ide::Change;
 use ide_db::base_db::{
-    CrateGraph, Env, ProcMacro, ProcMacroExpander, ProcMacroExpansionError, ProcMacroKind,
-    SourceRoot, VfsPath,
+    CrateData, CrateGraph, CrateOrigin, Env, FileLoader, ProcMacro, ProcMacroExpander,
+    ProcMacroExpansionError, ProcMacroKind, SourceDatabase, SourceRoot, VfsPath,
 };
 use proc_macro_api::{MacroDylib, ProcMacroServer};
-use project_model::{ProjectWorkspace, WorkspaceBuildScripts};
+use project_model::{
+    CargoWorkspace, CfgFlag, MissingSysrootSource, Package, ProjectManifest, ProjectWorkspace,
+    WorkspaceBuildScripts,
+};
+use rustc_hash::FxHashSet;
 use syntax::SmolStr;
-use vfs::{file_set::FileSetConfig, AbsPath, AbsPathBuf, ChangeKind};
+use vfs::{file_set::FileSetConfig, loader::Glob, AbsPath, AbsPathBuf, ChangeKind};
 
 use crate::{
-    config::{Config, FilesWatcher, LinkedProject},
-    global_state::GlobalState,
+    config::{Config, DiscoverWorkspaceConfig, FilesWatcher, LinkedProject},
+    discover::{self, DiscoverProjectMessage},
+    global_state::{file_id_to_url, GlobalState},
     lsp_ext,
     main_loop::Task,
     op_queue::Cause,
+    task_pool::Priority,
+    to_proto,
 };
 
 #[derive(Debug)]
@@ -56,17 +63,65 @@ impl GlobalState {
             || self.vfs_progress_n_done < self.vfs_progress_n_total)
     }
 
+    /// Re-reads the project-local `rust-analyzer.toml` files after one of them changed.
+    pub(crate) fn reload_local_config(&mut self) {
+        let mut config = Config::clone(&*self.config);
+        if let Err(error) = config.reload_local_configs() {
+            self.show_message(lsp_types::MessageType::WARNING, error.to_string());
+        }
+        self.update_configuration(config);
+    }
+
     pub(crate) fn update_configuration(&mut self, config: Config) {
         let _p = profile::span("GlobalState::update_configuration");
         let old_config = mem::replace(&mut self.config, Arc::new(config));
+        if self.config.outdated_keys() != old_config.outdated_keys() {
+            if let Some(warning) = self.config.outdated_keys_warning() {
+                self.show_message(lsp_types::MessageType::WARNING, warning);
+            }
+        }
         if self.config.lru_capacity() != old_config.lru_capacity() {
             self.analysis_host.update_lru_capacity(self.config.lru_capacity());
         }
+        if self.config.proc_macro_srv() != old_config.proc_macro_srv()
+            || self.config.proc_macro_expansion_cache() != old_config.proc_macro_expansion_cache()
+        {
+            // A new server is spawned when the crate graph is recreated.
+            self.proc_macro_client = None;
+        }
+
+        // Apply the change with the least amount of work that picks it up.
         if self.config.linked_projects() != old_config.linked_projects() {
-            self.fetch_workspaces_queue.request_op("linked projects changed".to_string())
-        } else if self.config.flycheck() != old_config.flycheck() {
+            self.request_workspace_reload("linked projects changed".to_string());
+        } else if setting_changed(&self.config, &old_config, Config::cargo) {
+            self.request_workspace_reload("cargo config changed".to_string());
+        } else if self.config.proc_macro_srv() != old_config.proc_macro_srv()
+            || self.config.proc_macro_expansion_cache() != old_config.proc_macro_expansion_cache()
+            || self.config.dummy_replacements() != old_config.dummy_replacements()
+            || self.config.files() != old_config.files()
+        {
+            self.recreate_crate_graph("proc-macro or files config changed".to_string(), true);
+        } else if self.config.cfgs_by_path() != old_config.cfgs_by_path() {
+            self.recreate_crate_graph("cfgs changed".to_string(), false);
+        } else if setting_changed(&self.config, &old_config, Config::flycheck) {
             self.reload_flycheck();
         }
+        if self.config.run_build_scripts() && !old_config.run_build_scripts() {
+            self.keep_build_scripts = false;
+            self.fetch_build_data_queue.request_op("build scripts enabled".to_string());
+        }
+        if self.config.pull_diagnostics() != old_config.pull_diagnostics() {
+            self.switch_diagnostics_mode();
+        }
+        if self.config.settings_changed(&old_config) {
+            // Highlighting and lenses depend on the settings, let the client ask for them again.
+            if self.config.semantic_tokens_refresh() {
+                self.send_request::<lsp_types::request::SemanticTokensRefresh>((), |_, _| ());
+            }
+            if self.config.code_lens_refresh() {
+                self.send_request::<lsp_types::request::CodeLensRefresh>((), |_, _| ());
+            }
+        }
 
         if self.analysis_host.raw_database().enable_proc_attr_macros()
             != self.config.expand_proc_attr_macros()
@@ -75,47 +130,220 @@ impl GlobalState {
                 .raw_database_mut()
                 .set_enable_proc_attr_macros(self.config.expand_proc_attr_macros());
         }
+        if self.analysis_host.raw_database().trait_solver_config() != self.config.trait_solver() {
+            self.analysis_host
+                .raw_database_mut()
+                .set_trait_solver_config(self.config.trait_solver());
+        }
+    }
+
+    /// Switches between publishing diagnostics and letting the client pull them. The diagnostic
+    /// provider is only ever registered dynamically, so that it can be dropped again when the
+    /// setting is turned off.
+    pub(crate) fn switch_diagnostics_mode(&mut self) {
+        let id = "textDocument/diagnostic".to_string();
+        let method = id.clone();
+        if self.config.pull_diagnostics() {
+            // Clear everything published so far, the client asks for diagnostics from now on.
+            let file_ids: Vec<_> = self
+                .diagnostics
+                .native
+                .keys()
+                .chain(self.diagnostics.check.keys())
+                .copied()
+                .collect();
+            let uris = {
+                let vfs = &self.vfs.read().0;
+                let mut uris: Vec<_> =
+                    file_ids.into_iter().map(|file_id| file_id_to_url(vfs, file_id)).collect();
+                uris.extend(
+                    self.excluded_generated_files
+                        .keys()
+                        .map(|path| to_proto::url_from_abs_path(path)),
+                );
+                uris
+            };
+            for uri in uris {
+                self.send_notification::<lsp_types::notification::PublishDiagnostics>(
+                    lsp_types::PublishDiagnosticsParams {
+                        uri,
+                        diagnostics: Vec::new(),
+                        version: None,
+                    },
+                );
+            }
+            // FIXME: use the `lsp_types` registration options once it supports pull diagnostics.
+            let registration = lsp_types::Registration {
+                id,
+                method,
+                register_options: Some(serde_json::json!({
+                    "documentSelector": null,
+                    "identifier": "rust-analyzer",
+                    "interFileDependencies": true,
+                    "workspaceDiagnostics": false,
+                })),
+            };
+            self.send_request::<lsp_types::request::RegisterCapability>(
+                lsp_types::RegistrationParams { registrations: vec![registration] },
+                |_, _| (),
+            );
+        } else {
+            self.send_request::<lsp_types::request::UnregisterCapability>(
+                lsp_types::UnregistrationParams {
+                    unregisterations: vec![lsp_types::Unregistration { id, method }],
+                },
+                |_, _| (),
+            );
+            self.diagnostics.mark_all_changed();
+            let excluded = Arc::clone(&self.excluded_generated_files);
+            for (path, &size) in excluded.iter() {
+                self.send_notification::<lsp_types::notification::PublishDiagnostics>(
+                    lsp_types::PublishDiagnosticsParams {
+                        uri: to_proto::url_from_abs_path(path),
+                        diagnostics: vec![crate::diagnostics::excluded_generated_file(size)],
+                        version: None,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Fetches the workspaces again, even if they aren't automatically reloaded on changes, as the
+    /// user explicitly asked for the new settings.
+    pub(crate) fn request_workspace_reload(&mut self, cause: Cause) {
+        self.workspace_changes = None;
+        self.fetch_workspaces_queue.request_op(cause);
+        if let Some(cause) = self.fetch_workspaces_queue.should_start_op() {
+            self.fetch_workspaces(cause);
+        }
+    }
+
+    /// Requests fetching the workspaces after `path` changed. Unless something else asked for a
+    /// full reload, only the workspaces affected by the changed files are fetched again.
+    pub(crate) fn request_workspace_reload_for(&mut self, path: AbsPathBuf, cause: Cause) {
+        if !self.fetch_workspaces_queue.op_requested() {
+            self.workspace_changes = Some(FxHashSet::default());
+        }
+        if let Some(changes) = &mut self.workspace_changes {
+            changes.insert(path);
+        }
+        self.fetch_workspaces_queue.request_op(cause);
     }
 
     pub(crate) fn current_status(&self) -> lsp_ext::ServerStatusParams {
-        let mut status = lsp_ext::ServerStatusParams {
-            health: lsp_ext::Health::Ok,
-            quiescent: self.is_quiescent(),
-            message: None,
+        use lsp_ext::{Health, ServerProblemKind as Kind};
+
+        let commands = self.config.client_commands();
+        let mut problems = Vec::new();
+        let mut problem = |kind, health, message: String, fix: Option<lsp_types::Command>| {
+            problems.push(lsp_ext::ServerProblem { kind, health, message, fix })
         };
 
-        if self.proc_macro_changed {
-            status.health = lsp_ext::Health::Warning;
-            status.message =
-                Some("Reload required due to source changes of a procedural macro.".into())
-        }
-        if let Err(_) = self.fetch_build_data_error() {
-            status.health = lsp_ext::Health::Warning;
-            status.message =
-                Some("Failed to run build scripts of some packages, check the logs.".to_string());
+        for ws in self.fetch_workspaces_queue.last_op_result() {
+            if let Err(err) = ws {
+                let message = format!("rust-analyzer failed to load workspace: {:#}", err);
+                if let Some(missing) = err.downcast_ref::<MissingSysrootSource>() {
+                    let fix = commands
+                        .install_rust_src
+                        .then(|| to_proto::command::install_rust_src(missing.toolchain.as_deref()));
+                    problem(Kind::MissingRustSrc, Health::Error, message, fix);
+                } else {
+                    let fix = commands.reload_workspace.then(to_proto::command::reload_workspace);
+                    problem(Kind::WorkspaceLoadFailed, Health::Error, message, fix);
+                }
+            }
         }
         if !self.config.cargo_autoreload()
             && self.is_quiescent()
             && self.fetch_workspaces_queue.op_requested()
         {
-            status.health = lsp_ext::Health::Warning;
-            status.message = Some("Workspace reload required".to_string())
+            let fix = commands.reload_workspace.then(to_proto::command::reload_workspace);
+            let message = "Workspace reload required".to_string();
+            problem(Kind::WorkspaceReloadRequired, Health::Warning, message, fix);
         }
 
-        if let Err(error) = self.fetch_workspace_error() {
-            status.health = lsp_ext::Health::Error;
-            status.message = Some(error)
+        let rebuild = commands.rebuild_proc_macros.then(to_proto::command::rebuild_proc_macros);
+        if self.fetch_build_data_error().is_err() {
+            let message = "Failed to run build scripts of some packages, check the logs.".into();
+            problem(Kind::BuildScriptsFailed, Health::Warning, message, rebuild.clone());
         }
-        status
+        if self.proc_macro_changed {
+            let message = "Rebuild required due to source changes of a procedural macro.".into();
+            problem(Kind::ProcMacroChanged, Health::Warning, message, rebuild.clone());
+        }
+        if !self.proc_macro_load_errors.is_empty() {
+            // Usually an ABI mismatch between the proc macros and the proc macro server.
+            let message = self.proc_macro_load_errors.join("\n");
+            problem(Kind::ProcMacroLoadFailed, Health::Warning, message, rebuild);
+        }
+
+        if self.is_quiescent() {
+            let db = self.analysis_host.raw_database();
+            let vfs = &self.vfs.read().0;
+            for path in self.mem_docs.iter() {
+                let file_id = match vfs.file_id(path) {
+                    Some(it) => it,
+                    None => continue,
+                };
+                if path.name_and_extension().map_or(true, |(_, ext)| ext != Some("rs"))
+                    || !db.relevant_crates(file_id).is_empty()
+                {
+                    continue;
+                }
+                let message = format!("{} is not included in any crate", path);
+                let fix = match path.as_path() {
+                    Some(abs_path) if commands.goto_location => {
+                        let url = to_proto::url_from_abs_path(abs_path);
+                        let title = "Open the file to add it to a module".to_string();
+                        Some(to_proto::command::goto_file(&self.config, title, url))
+                    }
+                    _ => None,
+                };
+                problem(Kind::UnlinkedFile, Health::Warning, message, fix);
+            }
+        }
+
+        let health = problems.iter().map(|it| it.health).max().unwrap_or(Health::Ok);
+        let message = (!problems.is_empty())
+            .then(|| problems.iter().map(|it| &*it.message).collect::<Vec<_>>().join("\n\n"));
+        lsp_ext::ServerStatusParams { health, quiescent: self.is_quiescent(), message, problems }
     }
 
     pub(crate) fn fetch_workspaces(&mut self, cause: Cause) {
         tracing::info!(%cause, "will fetch workspaces");
 
-        self.task_pool.handle.spawn_with_sender({
-            let linked_projects = self.config.linked_projects();
+        // A change no loaded workspace knows about, like a global cargo config, may affect any
+        // of them.
+        let changes = self.workspace_changes.take().filter(|changes| {
+            changes.iter().all(|path| self.workspaces.iter().any(|ws| is_affected_by(ws, path)))
+        });
+
+        self.task_pool.handle.spawn_with_sender(Priority::Background, {
+            // Each project is loaded with the settings of the workspace folder it lives in.
+            let linked_projects = self
+                .config
+                .linked_projects()
+                .into_iter()
+                .map(|project| {
+                    let cargo_config = match &project {
+                        LinkedProject::ProjectManifest(manifest) => {
+                            self.config.for_path(manifest.parent()).cargo()
+                        }
+                        LinkedProject::InlineJsonProject(_) => self.config.cargo(),
+                    };
+                    let unchanged = changes
+                        .as_ref()
+                        .and_then(|changes| {
+                            unchanged_workspace(&self.workspaces, &project, changes)
+                        })
+                        .cloned();
+                    (project, cargo_config, unchanged)
+                })
+                .chain(self.discovered_workspaces.values().map(|project| {
+                    (LinkedProject::InlineJsonProject(project.clone()), self.config.cargo(), None)
+                }))
+                .collect::<Vec<_>>();
             let detached_files = self.config.detached_files().to_vec();
-            let cargo_config = self.config.cargo();
 
             move |sender| {
                 let progress = {
@@ -131,15 +359,17 @@ impl GlobalState {
 
                 let mut workspaces = linked_projects
                     .iter()
-                    .map(|project| match project {
-                        LinkedProject::ProjectManifest(manifest) => {
+                    .map(|(project, cargo_config, unchanged)| match (project, unchanged) {
+                        // Unaffected workspaces keep their metadata and build script results.
+                        (_, Some(ws)) => Ok(ws.clone()),
+                        (LinkedProject::ProjectManifest(manifest), None) => {
                             project_model::ProjectWorkspace::load(
                                 manifest.clone(),
-                                &cargo_config,
+                                cargo_config,
                                 &progress,
                             )
                         }
-                        LinkedProject::InlineJsonProject(it) => {
+                        (LinkedProject::InlineJsonProject(it), None) => {
                             project_model::ProjectWorkspace::load_inline(
                                 it.clone(),
                                 cargo_config.target.as_deref(),
@@ -161,11 +391,87 @@ impl GlobalState {
         });
     }
 
+    /// Runs the `workspace.discoverConfig` command for `arg` unless a loaded workspace or a
+    /// discovered project already contains it, or a run for its directory is in flight.
+    pub(crate) fn discover_workspace_for(&mut self, arg: AbsPathBuf) {
+        let config = match self.config.discover_workspace_config() {
+            Some(it) => it.clone(),
+            None => return,
+        };
+        if self.workspaces.iter().any(|ws| is_covered_by(ws, &arg))
+            || self.discovered_workspaces.values().any(|project| project.includes_file(&arg))
+        {
+            return;
+        }
+        let dir = match arg.parent() {
+            Some(it) => it.to_path_buf(),
+            None => return,
+        };
+        if !self.discovering_dirs.insert(dir) {
+            return;
+        }
+        self.discover_workspace(config, vec![arg]);
+    }
+
+    /// Allows `workspace.discoverConfig` to run again for the directory of `arg`.
+    pub(crate) fn finish_discovering(&mut self, arg: &AbsPath) {
+        if let Some(dir) = arg.parent() {
+            self.discovering_dirs.remove(dir);
+        }
+    }
+
+    /// Runs the `workspace.discoverConfig` command again for all discovered projects, after
+    /// one of their build files changed.
+    pub(crate) fn rediscover_workspaces(&mut self, cause: Cause) {
+        let config = match self.config.discover_workspace_config() {
+            Some(it) => it.clone(),
+            None => return,
+        };
+        tracing::info!(%cause, "will rediscover workspaces");
+        let args = self.discovered_workspaces.keys().cloned().collect();
+        self.discover_workspace(config, args);
+    }
+
+    fn discover_workspace(&mut self, config: DiscoverWorkspaceConfig, args: Vec<AbsPathBuf>) {
+        if args.is_empty() {
+            return;
+        }
+        let root = self.config.root_path().clone();
+        self.task_pool.handle.spawn_with_sender(Priority::Background, move |sender| {
+            sender.send(Task::DiscoverProject(DiscoverProjectMessage::Begin)).unwrap();
+            for arg in args {
+                let msg = match discover::discover_project(&config, &root, &arg) {
+                    Ok(project) => DiscoverProjectMessage::Finished { arg, project },
+                    Err(error) => {
+                        DiscoverProjectMessage::Error { arg, error: format!("{:#}", error) }
+                    }
+                };
+                sender.send(Task::DiscoverProject(msg)).unwrap();
+            }
+            sender.send(Task::DiscoverProject(DiscoverProjectMessage::End)).unwrap();
+        });
+    }
+
     pub(crate) fn fetch_build_data(&mut self, cause: Cause) {
         tracing::debug!(%cause, "will fetch build data");
+        let keep_build_scripts = mem::take(&mut self.keep_build_scripts);
         let workspaces = Arc::clone(&self.workspaces);
-        let config = self.config.cargo();
-        self.task_pool.handle.spawn_with_sender(move |sender| {
+        let kept = workspaces
+            .iter()
+            .map(|ws| {
+                ws.build_scripts()
+                    .filter(|it| keep_build_scripts && **it != WorkspaceBuildScripts::default())
+                    .cloned()
+            })
+            .collect::<Vec<_>>();
+        let configs = workspaces
+            .iter()
+            .map(|ws| match ws.workspace_root() {
+                Some(root) => self.config.for_path(root).cargo(),
+                None => self.config.cargo(),
+            })
+            .collect::<Vec<_>>();
+        self.task_pool.handle.spawn_with_sender(Priority::Background, move |sender| {
             sender.send(Task::FetchBuildData(BuildDataProgress::Begin)).unwrap();
 
             let progress = {
@@ -175,8 +481,11 @@ impl GlobalState {
                 }
             };
             let mut res = Vec::new();
-            for ws in workspaces.iter() {
-                res.push(ws.run_build_scripts(&config, &progress));
+            for ((ws, config), kept) in workspaces.iter().zip(&configs).zip(kept) {
+                res.push(match kept {
+                    Some(it) => Ok(it),
+                    None => ws.run_build_scripts(config, &progress),
+                });
             }
             sender.send(Task::FetchBuildData(BuildDataProgress::End((workspaces, res)))).unwrap();
         });
@@ -220,9 +529,23 @@ impl GlobalState {
                     rustc,
                     rustc_cfg,
                     cfg_overrides,
+                    target_layout,
+                    crate_targets,
+                    extra_targets,
+                    rustc_version,
 
                     build_scripts: _,
-                } => Some((cargo, sysroot, rustc, rustc_cfg, cfg_overrides)),
+                } => Some((
+                    cargo,
+                    sysroot,
+                    rustc,
+                    rustc_cfg,
+                    cfg_overrides,
+                    target_layout,
+                    crate_targets,
+                    extra_targets,
+                    rustc_version,
+                )),
                 _ => None,
             };
             match (key(left), key(right)) {
@@ -237,6 +560,9 @@ impl GlobalState {
                 .zip(self.workspaces.iter())
                 .all(|(l, r)| eq_ignore_build_data(l, r));
 
+        // Updated build data may point at rebuilt proc-macro dylibs whose expanders can't be
+        // compared, so the crate graph is only kept for freshly fetched workspaces.
+        let reloaded_proc_macros = same_workspaces;
         if same_workspaces {
             let (workspaces, build_scripts) = self.fetch_build_data_queue.last_op_result();
             if Arc::ptr_eq(workspaces, &self.workspaces) {
@@ -268,6 +594,45 @@ impl GlobalState {
             self.workspaces = Arc::new(workspaces)
         }
 
+        self.recreate_crate_graph(cause, reloaded_proc_macros);
+        tracing::info!("did switch workspaces");
+    }
+
+    /// Sets up file watching, the vfs and the crate graph for the current workspaces.
+    ///
+    /// Also used on its own when a setting affecting these changes, as the workspaces don't need
+    /// to be fetched again then.
+    ///
+    /// With `reloaded_proc_macros`, the crate graph is always replaced, otherwise it is kept if
+    /// the new one describes the same crates.
+    /// Enables the `cargo.cfgs` of the settings for the path of each crate's root module.
+    fn add_configured_cfgs(&self, crate_graph: &mut CrateGraph) {
+        let vfs = &self.vfs.read().0;
+        for krate in crate_graph.iter().collect::<Vec<_>>() {
+            let data = &crate_graph[krate];
+            if let CrateOrigin::Lang(_) = data.origin {
+                continue;
+            }
+            let path = vfs.file_path(data.root_file_id);
+            let config = match path.as_path() {
+                Some(path) => self.config.for_path(path),
+                None => continue,
+            };
+            if config.cfgs().is_empty() {
+                continue;
+            }
+            let mut cfg_options = data.cfg_options.clone();
+            cfg_options.extend(config.cfgs().iter().filter_map(|cfg| {
+                cfg.parse::<CfgFlag>().map_err(|e| tracing::error!("invalid cfg: {}", e)).ok()
+            }));
+            crate_graph.set_crate_cfg_options(krate, cfg_options);
+        }
+    }
+
+    fn recreate_crate_graph(&mut self, cause: Cause, reloaded_proc_macros: bool) {
+        let _p = profile::span("GlobalState::recreate_crate_graph");
+        tracing::info!(%cause, "will recreate the crate graph");
+
         if let FilesWatcher::Client = self.config.files().watcher {
             let registration_options = lsp_types::DidChangeWatchedFilesRegistrationOptions {
                 watchers: self
@@ -284,6 +649,17 @@ impl GlobalState {
                             ]
                         })
                     })
+                    .chain(self.config.discover_workspace_config().into_iter().flat_map(|config| {
+                        config.files_to_watch.iter().map(|name| {
+                            format!("{}/**/{}", self.config.root_path().display(), name)
+                        })
+                    }))
+                    .chain(
+                        self.config
+                            .local_config_paths()
+                            .into_iter()
+                            .map(|path| path.display().to_string()),
+                    )
                     .map(|glob_pattern| lsp_types::FileSystemWatcher { glob_pattern, kind: None })
                     .collect(),
             };
@@ -301,18 +677,33 @@ impl GlobalState {
         let mut change = Change::new();
 
         let files_config = self.config.files();
-        let project_folders = ProjectFolders::new(&self.workspaces, &files_config.exclude);
-
+        let project_folders = ProjectFolders::new(
+            &self.workspaces,
+            &files_config.exclude,
+            &files_config.exclude_globs,
+        );
+
+        let mut proc_macro_errors = Vec::new();
+        let mut proc_macro_server_spawned = false;
         if self.proc_macro_client.is_none() {
-            if let Some((path, args)) = self.config.proc_macro_srv() {
-                match ProcMacroServer::spawn(path.clone(), args) {
-                    Ok(it) => self.proc_macro_client = Some(it),
+            if let Some((path, args, expansion_timeout)) = self.config.proc_macro_srv() {
+                match ProcMacroServer::spawn(path.clone(), args, expansion_timeout) {
+                    Ok(mut it) => {
+                        it.set_expansion_cache(self.config.proc_macro_expansion_cache());
+                        self.proc_macro_client = Some(it);
+                        proc_macro_server_spawned = true;
+                    }
                     Err(err) => {
                         tracing::error!(
                             "Failed to run proc_macro_srv from path {}, error: {:?}",
                             path.display(),
                             err
                         );
+                        proc_macro_errors.push(format!(
+                            "failed to run proc_macro_srv from path {}: {}",
+                            path.display(),
+                            err
+                        ));
                     }
                 }
             }
@@ -330,15 +721,24 @@ impl GlobalState {
         });
 
         // Create crate graph from all the workspaces
-        let crate_graph = {
+        let mut loaded_dylibs = FxHashSet::default();
+        let mut crate_graph = {
             let proc_macro_client = self.proc_macro_client.as_ref();
             let dummy_replacements = self.config.dummy_replacements();
+            let proc_macro_errors = &mut proc_macro_errors;
+            let loaded_dylibs = &mut loaded_dylibs;
             let mut load_proc_macro = move |crate_name: &str, path: &AbsPath| {
+                loaded_dylibs.insert(path.to_path_buf());
                 load_proc_macro(
                     proc_macro_client,
                     path,
                     dummy_replacements.get(crate_name).map(|v| &**v).unwrap_or_default(),
                 )
+                .unwrap_or_else(|err| {
+                    tracing::warn!("{}", err);
+                    proc_macro_errors.push(err);
+                    Vec::new()
+                })
             };
 
             let vfs = &mut self.vfs.write().0;
@@ -364,14 +764,25 @@ impl GlobalState {
             }
             crate_graph
         };
-        change.set_crate_graph(crate_graph);
+        if let Some(client) = &self.proc_macro_client {
+            client.retain_dylibs(|path| loaded_dylibs.contains(path));
+        }
+        self.add_configured_cfgs(&mut crate_graph);
+        // Leave the crate graph input alone if reloading didn't change it, so that salsa keeps
+        // everything computed from it.
+        if reloaded_proc_macros
+            || proc_macro_server_spawned
+            || !crate_graph_eq(&self.analysis_host.raw_database().crate_graph(), &crate_graph)
+        {
+            change.set_crate_graph(crate_graph);
+        }
+        self.proc_macro_load_errors = proc_macro_errors;
 
         self.source_root_config = project_folders.source_root_config;
 
         self.analysis_host.apply_change(change);
         self.process_changes();
         self.reload_flycheck();
-        tracing::info!("did switch workspaces");
     }
 
     fn fetch_workspace_error(&self) -> Result<(), String> {
@@ -413,45 +824,109 @@ impl GlobalState {
 
     fn reload_flycheck(&mut self) {
         let _p = profile::span("GlobalState::reload_flycheck");
-        let config = match self.config.flycheck() {
-            Some(it) => it,
-            None => {
-                self.flycheck = Vec::new();
-                self.diagnostics.clear_check();
-                return;
-            }
-        };
-
         let sender = self.flycheck_sender.clone();
+        let config = &self.config;
         self.flycheck = self
             .workspaces
             .iter()
             .enumerate()
-            .filter_map(|(id, w)| match w {
-                ProjectWorkspace::Cargo { cargo, .. } => Some((id, cargo.workspace_root())),
-                ProjectWorkspace::Json { project, .. } => {
+            .filter_map(|(id, w)| {
+                let root = w.workspace_root()?;
+                // Workspace folders may have their own check settings.
+                let config = config.for_path(root).flycheck()?;
+                match (w, &config) {
+                    (ProjectWorkspace::Cargo { .. }, _) => (),
                     // Enable flychecks for json projects if a custom flycheck command was supplied
                     // in the workspace configuration.
-                    match config {
-                        FlycheckConfig::CustomCommand { .. } => Some((id, project.path())),
-                        _ => None,
-                    }
+                    (ProjectWorkspace::Json { .. }, FlycheckConfig::CustomCommand { .. }) => (),
+                    _ => return None,
                 }
-                ProjectWorkspace::DetachedFiles { .. } => None,
+                Some((id, config, root))
             })
-            .map(|(id, root)| {
+            .map(|(id, config, root)| {
                 let sender = sender.clone();
                 FlycheckHandle::spawn(
                     id,
                     Box::new(move |msg| sender.send(msg).unwrap()),
-                    config.clone(),
+                    config,
                     root.to_path_buf(),
                 )
             })
             .collect();
+        if self.flycheck.is_empty() {
+            self.diagnostics.clear_check();
+        }
+    }
+
+    /// The packages of workspace `id` that have to be checked again after `path` was saved: the
+    /// package owning the file and all members depending on it, directly or not. `None` if the
+    /// whole workspace has to be checked.
+    pub(crate) fn affected_packages(
+        &self,
+        id: usize,
+        path: &AbsPath,
+    ) -> Option<Vec<PackageToCheck>> {
+        match self.workspaces.get(id)? {
+            ProjectWorkspace::Cargo { cargo, .. } => affected_packages(cargo, path),
+            _ => None,
+        }
+    }
+
+    /// Clears the check diagnostics of the files owned by `packages` of workspace `id`, as only
+    /// those are checked again.
+    pub(crate) fn clear_check_for_packages(&mut self, id: usize, packages: &[PackageToCheck]) {
+        let cargo = match self.workspaces.get(id) {
+            Some(ProjectWorkspace::Cargo { cargo, .. }) => cargo,
+            _ => return self.diagnostics.clear_check(),
+        };
+        let vfs = &self.vfs.read().0;
+        self.diagnostics.clear_check_for(|file_id| {
+            let path = vfs.file_path(file_id);
+            path.as_path().map_or(false, |path| is_checked_again(cargo, packages, path))
+        });
     }
 }
 
+fn affected_packages(cargo: &CargoWorkspace, path: &AbsPath) -> Option<Vec<PackageToCheck>> {
+    let mut affected = vec![owning_package(cargo, path)?];
+    let mut idx = 0;
+    while let Some(&pkg) = affected.get(idx) {
+        idx += 1;
+        let rdeps = cargo.packages().filter(|&rdep| {
+            cargo[rdep].is_member && cargo[rdep].dependencies.iter().any(|dep| dep.pkg == pkg)
+        });
+        for rdep in rdeps.collect::<Vec<_>>() {
+            if !affected.contains(&rdep) {
+                affected.push(rdep);
+            }
+        }
+    }
+    let packages = affected
+        .into_iter()
+        .map(|pkg| PackageToCheck {
+            spec: cargo.package_flag(&cargo[pkg]),
+            root: cargo[pkg].manifest.parent().to_path_buf(),
+        })
+        .collect();
+    Some(packages)
+}
+
+/// Whether `path` is owned by one of `packages`, so that its check diagnostics are replaced by
+/// the next check.
+fn is_checked_again(cargo: &CargoWorkspace, packages: &[PackageToCheck], path: &AbsPath) -> bool {
+    owning_package(cargo, path).map_or(false, |pkg| {
+        packages.iter().any(|it| it.root.as_path() == cargo[pkg].manifest.parent())
+    })
+}
+
+/// The workspace member whose directory is the closest ancestor of `path`.
+fn owning_package(cargo: &CargoWorkspace, path: &AbsPath) -> Option<Package> {
+    cargo
+        .packages()
+        .filter(|&pkg| cargo[pkg].is_member && path.starts_with(cargo[pkg].manifest.parent()))
+        .max_by_key(|&pkg| cargo[pkg].manifest.parent().as_ref().components().count())
+}
+
 #[derive(Default)]
 pub(crate) struct ProjectFolders {
     pub(crate) load: Vec<vfs::loader::Entry>,
@@ -463,6 +938,7 @@ impl ProjectFolders {
     pub(crate) fn new(
         workspaces: &[ProjectWorkspace],
         global_excludes: &[AbsPathBuf],
+        global_exclude_globs: &[Glob],
     ) -> ProjectFolders {
         let mut res = ProjectFolders::default();
         let mut fsc = FileSetConfig::builder();
@@ -486,6 +962,7 @@ impl ProjectFolders {
                         dirs.exclude.push(excl.clone());
                     }
                 }
+                dirs.exclude_globs.extend(global_exclude_globs.iter().cloned());
 
                 vfs::loader::Entry::Directories(dirs)
             };
@@ -539,35 +1016,23 @@ pub(crate) fn load_proc_macro(
     client: Option<&ProcMacroServer>,
     path: &AbsPath,
     dummy_replace: &[Box<str>],
-) -> Vec<ProcMacro> {
-    let dylib = match MacroDylib::new(path.to_path_buf()) {
-        Ok(it) => it,
-        Err(err) => {
-            // FIXME: that's not really right -- we store this error in a
-            // persistent status.
-            tracing::warn!("failed to load proc macro: {}", err);
-            return Vec::new();
+) -> Result<Vec<ProcMacro>, String> {
+    let dylib = MacroDylib::new(path.to_path_buf())
+        .map_err(|err| format!("failed to load proc macro {}: {}", path.display(), err))?;
+
+    let macros = match client.map(|it| it.load_dylib(dylib)) {
+        None => Vec::new(),
+        Some(Ok(Ok(macros))) => macros,
+        Some(Err(err)) => return Err(format!("proc macro server crashed: {}", err)),
+        Some(Ok(Err(err))) => {
+            return Err(format!("failed to load proc macro {}: {}", path.display(), err))
         }
     };
 
-    return client
-        .map(|it| it.load_dylib(dylib))
+    return Ok(macros
         .into_iter()
-        .flat_map(|it| match it {
-            Ok(Ok(macros)) => macros,
-            Err(err) => {
-                tracing::error!("proc macro server crashed: {}", err);
-                Vec::new()
-            }
-            Ok(Err(err)) => {
-                // FIXME: that's not really right -- we store this error in a
-                // persistent status.
-                tracing::warn!("failed to load proc macro: {}", err);
-                Vec::new()
-            }
-        })
         .map(|expander| expander_to_proc_macro(expander, dummy_replace))
-        .collect();
+        .collect());
 
     fn expander_to_proc_macro(
         expander: proc_macro_api::ProcMacro,
@@ -605,6 +1070,20 @@ pub(crate) fn load_proc_macro(
                 Err(err) => Err(ProcMacroExpansionError::System(err.to_string())),
             }
         }
+
+        fn expand_with_backtrace(
+            &self,
+            subtree: &tt::Subtree,
+            attrs: Option<&tt::Subtree>,
+            env: &Env,
+        ) -> Result<tt::Subtree, ProcMacroExpansionError> {
+            let env = env.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+            match self.0.expand_with_backtrace(subtree, attrs, env) {
+                Ok(Ok(subtree)) => Ok(subtree),
+                Ok(Err(err)) => Err(ProcMacroExpansionError::Panic(err.0)),
+                Err(err) => Err(ProcMacroExpansionError::System(err.to_string())),
+            }
+        }
     }
 
     /// Dummy identity expander, used for proc-macros that are deliberately ignored by the user.
@@ -623,6 +1102,102 @@ pub(crate) fn load_proc_macro(
     }
 }
 
+/// Whether a setting differs between two configurations, globally or for any of the workspace
+/// folders.
+fn setting_changed<T: PartialEq>(new: &Config, old: &Config, setting: fn(&Config) -> T) -> bool {
+    setting(new) != setting(old)
+        || new
+            .workspace_roots
+            .iter()
+            .any(|root| setting(&new.for_path(root)) != setting(&old.for_path(root)))
+}
+
+/// Whether a change of `path` may change the metadata of `ws`.
+fn is_affected_by(ws: &ProjectWorkspace, path: &AbsPath) -> bool {
+    match ws {
+        ProjectWorkspace::Cargo { cargo, .. } => {
+            path.starts_with(cargo.workspace_root())
+                || cargo.packages().any(|pkg| &*cargo[pkg].manifest == path)
+        }
+        ProjectWorkspace::Json { .. } | ProjectWorkspace::DetachedFiles { .. } => false,
+    }
+}
+
+/// Whether `path` lies in one of the local or library roots of `ws`. Detached files don't
+/// count, a project may still be discovered for them.
+fn is_covered_by(ws: &ProjectWorkspace, path: &AbsPath) -> bool {
+    if let ProjectWorkspace::DetachedFiles { .. } = ws {
+        return false;
+    }
+    ws.to_roots().iter().any(|root| {
+        root.include.iter().any(|dir| path.starts_with(dir))
+            && !root.exclude.iter().any(|dir| path.starts_with(dir))
+    })
+}
+
+/// The Cargo workspace loaded for `project` if none of the `changes` affect it.
+fn unchanged_workspace<'a>(
+    workspaces: &'a [ProjectWorkspace],
+    project: &LinkedProject,
+    changes: &FxHashSet<AbsPathBuf>,
+) -> Option<&'a ProjectWorkspace> {
+    let manifest = match project {
+        LinkedProject::ProjectManifest(ProjectManifest::CargoToml(it)) => it,
+        _ => return None,
+    };
+    let ws = workspaces.iter().find(|ws| match ws {
+        ProjectWorkspace::Cargo { cargo, .. } => {
+            cargo.workspace_root() == manifest.parent()
+                || cargo.packages().any(|pkg| cargo[pkg].manifest == *manifest)
+        }
+        ProjectWorkspace::Json { .. } | ProjectWorkspace::DetachedFiles { .. } => false,
+    })?;
+    (!changes.iter().any(|path| is_affected_by(ws, path))).then(|| ws)
+}
+
+/// Whether two crate graphs describe the same crates. Proc macros are compared by name and kind
+/// only, their expanders can't be compared, so this must not be used to skip a reload of the
+/// proc-macro dylibs.
+fn crate_graph_eq(old: &CrateGraph, new: &CrateGraph) -> bool {
+    old.iter().count() == new.iter().count()
+        && old.iter().zip(new.iter()).all(|(old_id, new_id)| {
+            // Destructured so that new fields can't be forgotten here.
+            let CrateData {
+                root_file_id,
+                edition,
+                version,
+                display_name,
+                cfg_options,
+                potential_cfg_options,
+                env,
+                dependencies,
+                proc_macro,
+                origin,
+                is_proc_macro,
+                target_layout,
+                target_triple,
+            } = &old[old_id];
+            let new = &new[new_id];
+            *root_file_id == new.root_file_id
+                && *edition == new.edition
+                && *version == new.version
+                && *display_name == new.display_name
+                && *cfg_options == new.cfg_options
+                && *potential_cfg_options == new.potential_cfg_options
+                && *env == new.env
+                && *dependencies == new.dependencies
+                && *origin == new.origin
+                && *is_proc_macro == new.is_proc_macro
+                && *target_layout == new.target_layout
+                && *target_triple == new.target_triple
+                && proc_macro.len() == new.proc_macro.len()
+                && proc_macro
+                    .iter()
+                    .zip(&new.proc_macro)
+                    .all(|(old, new)| old.name == new.name && old.kind == new.kind)
+        })
+}
+
 pub(crate) fn should_refresh_for_change(path: &AbsPath, change_kind: ChangeKind) -> bool {
     const IMPLICIT_TARGET_FILES: &[&str] = &["build.rs", "src/main.rs", "src/lib.rs"];
     const IMPLICIT_TARGET_DIRS: &[&str] = &["src/bin", "examples", "tests", "benches"];
@@ -663,3 +1238,154 @@ pub(crate) fn should_refresh_for_change(path: &AbsPath, change_kind: ChangeKind)
     }
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use project_model::{CargoMetadata, ManifestPath};
+
+    use super::*;
+
+    fn root() -> &'static str {
+        if cfg!(windows) {
+            r#"C:\ROOT\"#
+        } else {
+            "/ROOT/"
+        }
+    }
+
+    fn path(rel: &str) -> AbsPathBuf {
+        AbsPathBuf::assert(format!("{}{}", root(), rel).into())
+    }
+
+    fn cargo_workspace(json: &str) -> CargoWorkspace {
+        let json = json.replace("$ROOT$", &root().replace('\\', "\\\\"));
+        let meta: CargoMetadata = serde_json::from_str(&json).unwrap();
+        CargoWorkspace::new(meta)
+    }
+
+    fn hello_world() -> ProjectWorkspace {
+        ProjectWorkspace::Cargo {
+            cargo: cargo_workspace(include_str!(
+                "../../project-model/test_data/hello-world-metadata.json"
+            )),
+            build_scripts: WorkspaceBuildScripts::default(),
+            sysroot: None,
+            rustc: None,
+            rustc_cfg: Vec::new(),
+            cfg_overrides: Default::default(),
+            target_layout: None,
+            crate_targets: Default::default(),
+            extra_targets: Vec::new(),
+            rustc_version: None,
+        }
+    }
+
+    fn cargo_project(manifest: &str) -> LinkedProject {
+        let manifest = ManifestPath::try_from(path(manifest)).unwrap();
+        ProjectManifest::CargoToml(manifest).into()
+    }
+
+    #[test]
+    fn affected_by_manifests_and_workspace_files() {
+        let ws = hello_world();
+        assert!(is_affected_by(&ws, &path("hello-world/Cargo.toml")));
+        assert!(is_affected_by(&ws, &path("hello-world/Cargo.lock")));
+        assert!(is_affected_by(&ws, &path("hello-world/.cargo/config.toml")));
+        assert!(is_affected_by(
+            &ws,
+            &path(".cargo/registry/src/github.com-1ecc6299db9ec823/libc-0.2.98/Cargo.toml")
+        ));
+        assert!(!is_affected_by(
+            &ws,
+            &path(".cargo/registry/src/github.com-1ecc6299db9ec823/libc-0.2.98/build.rs")
+        ));
+        assert!(!is_affected_by(&ws, &path("other/Cargo.toml")));
+    }
+
+    #[test]
+    fn covered_by_local_and_library_roots() {
+        let ws = hello_world();
+        assert!(is_covered_by(&ws, &path("hello-world/src/main.rs")));
+        assert!(is_covered_by(
+            &ws,
+            &path(".cargo/registry/src/github.com-1ecc6299db9ec823/libc-0.2.98/src/lib.rs")
+        ));
+        assert!(!is_covered_by(&ws, &path("hello-world/target/debug/build/out.rs")));
+        assert!(!is_covered_by(&ws, &path("other/src/lib.rs")));
+    }
+
+    #[test]
+    fn unchanged_workspace_is_reused() {
+        let workspaces = [hello_world()];
+        let project = cargo_project("hello-world/Cargo.toml");
+
+        let changes = FxHashSet::default();
+        assert!(unchanged_workspace(&workspaces, &project, &changes).is_some());
+
+        let changes = [path("other/Cargo.toml")].into_iter().collect();
+        assert!(unchanged_workspace(&workspaces, &project, &changes).is_some());
+
+        let changes = [path("hello-world/Cargo.lock")].into_iter().collect();
+        assert!(unchanged_workspace(&workspaces, &project, &changes).is_none());
+    }
+
+    #[test]
+    fn unchanged_workspace_needs_a_loaded_cargo_workspace() {
+        let workspaces = [hello_world()];
+        let changes = FxHashSet::default();
+
+        let project = cargo_project("other/Cargo.toml");
+        assert!(unchanged_workspace(&workspaces, &project, &changes).is_none());
+
+        let project = cargo_project("hello-world/Cargo.toml");
+        assert!(unchanged_workspace(&[], &project, &changes).is_none());
+    }
+
+    fn monorepo() -> CargoWorkspace {
+        cargo_workspace(include_str!("../../project-model/test_data/monorepo-metadata.json"))
+    }
+
+    fn specs(packages: &[PackageToCheck]) -> Vec<&str> {
+        packages.iter().map(|it| it.spec.as_str()).collect()
+    }
+
+    #[test]
+    fn affected_packages_include_reverse_dependencies() {
+        let cargo = monorepo();
+        let affected = affected_packages(&cargo, &path("core/src/lib.rs")).unwrap();
+        assert_eq!(specs(&affected), ["core", "app", "cli"]);
+        assert_eq!(affected[0].root, path("core"));
+
+        let affected = affected_packages(&cargo, &path("core/macros/src/lib.rs")).unwrap();
+        assert_eq!(specs(&affected), ["core-macros", "core", "app", "cli"]);
+
+        let affected = affected_packages(&cargo, &path("cli/src/lib.rs")).unwrap();
+        assert_eq!(specs(&affected), ["cli"]);
+
+        let affected = affected_packages(&cargo, &path("other/src/lib.rs")).unwrap();
+        assert_eq!(specs(&affected), ["other"]);
+    }
+
+    #[test]
+    fn files_outside_of_members_check_everything() {
+        let cargo = monorepo();
+        assert!(affected_packages(&cargo, &path("build.rs")).is_none());
+        assert!(affected_packages(&cargo, &path("target/debug/build/out.rs")).is_none());
+    }
+
+    #[test]
+    fn only_checked_packages_lose_their_diagnostics() {
+        let cargo = monorepo();
+        let packages = affected_packages(&cargo, &path("app/src/lib.rs")).unwrap();
+        assert!(is_checked_again(&cargo, &packages, &path("app/src/main.rs")));
+        assert!(is_checked_again(&cargo, &packages, &path("cli/src/lib.rs")));
+        assert!(!is_checked_again(&cargo, &packages, &path("core/src/lib.rs")));
+        assert!(!is_checked_again(&cargo, &packages, &path("core/macros/src/lib.rs")));
+        assert!(!is_checked_again(&cargo, &packages, &path("other/src/lib.rs")));
+        assert!(!is_checked_again(&cargo, &packages, &path("build.rs")));
+
+        // The closest package owns a file, not the enclosing one.
+        let packages = affected_packages(&cargo, &path("core/src/lib.rs")).unwrap();
+        assert!(!is_checked_again(&cargo, &packages, &path("core/macros/src/lib.rs")));
+    }
+}
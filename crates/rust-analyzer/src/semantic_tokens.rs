@@ -213,6 +213,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_diff_unchanged() {
+        let before = [from((1, 2, 3, 4, 5)), from((6, 7, 8, 9, 10))];
+        let after = before.clone();
+
+        let edits = diff_tokens(&before, &after);
+        assert!(edits.is_empty());
+    }
+
     #[test]
     fn test_diff_insert_at_end() {
         let before = [from((1, 2, 3, 4, 5)), from((6, 7, 8, 9, 10))];
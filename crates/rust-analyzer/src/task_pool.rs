@@ -1,46 +1,262 @@
-//! A thin wrapper around `ThreadPool` to make sure that we join all things
-//! properly.
+//! A thread pool with a lane for interactive requests and one for background
+//! work, making sure that we join all things properly.
+use std::{collections::VecDeque, panic, sync::Arc, thread};
+
 use crossbeam_channel::Sender;
+use lsp_types::Url;
+use parking_lot::{Condvar, Mutex};
+
+/// Which lane of the pool a task is queued in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Priority {
+    /// Requests the user is waiting on, like completion or hover. These always
+    /// run before any queued background work.
+    Interactive,
+    /// Work nobody is directly waiting on, like priming caches or computing
+    /// diagnostics. At least one worker is always kept free of it, so that
+    /// interactive requests never wait for a long-running background task.
+    Background,
+}
+
+type Thunk = Box<dyn FnOnce() + Send + 'static>;
+
+struct Job {
+    run: Thunk,
+    /// For requests about a document, the document and the reply to send
+    /// instead of running the request once the document changed.
+    document: Option<(Url, Thunk)>,
+}
 
 pub(crate) struct TaskPool<T> {
     sender: Sender<T>,
-    inner: threadpool::ThreadPool,
+    shared: Arc<Shared>,
+    workers: Vec<thread::JoinHandle<()>>,
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    job_available: Condvar,
+}
+
+#[derive(Default)]
+struct Queue {
+    interactive: VecDeque<Job>,
+    background: VecDeque<Job>,
+    running_background: usize,
+    max_background: usize,
+    shutting_down: bool,
 }
 
 impl<T> TaskPool<T> {
     pub(crate) fn new(sender: Sender<T>) -> TaskPool<T> {
-        TaskPool { sender, inner: threadpool::ThreadPool::default() }
+        let threads = num_cpus::get().max(2);
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue { max_background: threads - 1, ..Queue::default() }),
+            job_available: Condvar::new(),
+        });
+        let workers = (0..threads)
+            .map(|idx| {
+                let shared = Arc::clone(&shared);
+                thread::Builder::new()
+                    .name(format!("rust-analyzer worker {}", idx))
+                    .spawn(move || shared.work())
+                    .expect("failed to spawn a worker thread")
+            })
+            .collect();
+        TaskPool { sender, shared, workers }
+    }
+
+    pub(crate) fn spawn<F>(&mut self, priority: Priority, task: F)
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let sender = self.sender.clone();
+        let run = Box::new(move || sender.send(task()).unwrap());
+        self.shared.push(priority, Job { run, document: None })
     }
 
-    pub(crate) fn spawn<F>(&mut self, task: F)
+    /// Queues an interactive request about `document`. If the document changes
+    /// before a worker picks the request up, `stale` is sent instead, see
+    /// [`TaskPool::drop_stale`].
+    pub(crate) fn spawn_for_document<F, S>(&mut self, document: Url, task: F, stale: S)
     where
         F: FnOnce() -> T + Send + 'static,
+        S: FnOnce() -> T + Send + 'static,
         T: Send + 'static,
     {
-        self.inner.execute({
-            let sender = self.sender.clone();
-            move || sender.send(task()).unwrap()
-        })
+        let sender = self.sender.clone();
+        let run = Box::new(move || sender.send(task()).unwrap());
+        let sender = self.sender.clone();
+        let stale = Box::new(move || sender.send(stale()).unwrap());
+        self.shared.push(Priority::Interactive, Job { run, document: Some((document, stale)) })
     }
 
-    pub(crate) fn spawn_with_sender<F>(&mut self, task: F)
+    pub(crate) fn spawn_with_sender<F>(&mut self, priority: Priority, task: F)
     where
         F: FnOnce(Sender<T>) + Send + 'static,
         T: Send + 'static,
     {
-        self.inner.execute({
-            let sender = self.sender.clone();
-            move || task(sender)
-        })
+        let sender = self.sender.clone();
+        self.shared.push(priority, Job { run: Box::new(move || task(sender)), document: None })
+    }
+
+    /// Answers the queued requests about `document` with their `stale` reply,
+    /// as their results would be outdated by the time they are computed.
+    pub(crate) fn drop_stale(&mut self, document: &Url) {
+        let stale = self.shared.queue.lock().take_stale(document);
+        for reply in stale {
+            reply();
+        }
     }
 
     pub(crate) fn len(&self) -> usize {
-        self.inner.queued_count()
+        let queue = self.shared.queue.lock();
+        queue.interactive.len() + queue.background.len()
+    }
+}
+
+impl Shared {
+    fn push(&self, priority: Priority, job: Job) {
+        let mut queue = self.queue.lock();
+        match priority {
+            Priority::Interactive => queue.interactive.push_back(job),
+            Priority::Background => queue.background.push_back(job),
+        }
+        drop(queue);
+        self.job_available.notify_one();
+    }
+
+    fn work(&self) {
+        let mut queue = self.queue.lock();
+        loop {
+            if let Some((job, priority)) = queue.pop() {
+                if priority == Priority::Background {
+                    queue.running_background += 1;
+                }
+                drop(queue);
+                run(job.run);
+                queue = self.queue.lock();
+                if priority == Priority::Background {
+                    queue.running_background -= 1;
+                    // A worker may be waiting for a background slot to free up.
+                    self.job_available.notify_one();
+                }
+                continue;
+            }
+            if queue.shutting_down && queue.interactive.is_empty() && queue.background.is_empty() {
+                // Wake up the workers that went to sleep while the queues were still non-empty.
+                self.job_available.notify_all();
+                return;
+            }
+            self.job_available.wait(&mut queue);
+        }
+    }
+}
+
+impl Queue {
+    /// The next job to run: interactive ones first, background ones only while
+    /// a worker is left for interactive requests.
+    fn pop(&mut self) -> Option<(Job, Priority)> {
+        if let Some(job) = self.interactive.pop_front() {
+            return Some((job, Priority::Interactive));
+        }
+        if self.running_background < self.max_background {
+            return self.background.pop_front().map(|job| (job, Priority::Background));
+        }
+        None
+    }
+
+    /// Removes the queued requests about `document`, returning their `stale` replies.
+    fn take_stale(&mut self, document: &Url) -> Vec<Thunk> {
+        let (stale, rest) = self.interactive.drain(..).partition::<Vec<_>, _>(|job| {
+            job.document.as_ref().map_or(false, |(it, _)| it == document)
+        });
+        self.interactive = rest.into();
+        stale.into_iter().filter_map(|job| job.document).map(|(_, reply)| reply).collect()
     }
 }
 
+/// Runs a job, keeping the worker alive if it panics. The panic hook already reported it.
+fn run(job: Thunk) {
+    let _ = panic::catch_unwind(panic::AssertUnwindSafe(job));
+}
+
 impl<T> Drop for TaskPool<T> {
     fn drop(&mut self) {
-        self.inner.join()
+        self.shared.queue.lock().shutting_down = true;
+        self.shared.job_available.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    fn job(sender: &mpsc::Sender<&'static str>, name: &'static str) -> Job {
+        let sender = sender.clone();
+        Job { run: Box::new(move || sender.send(name).unwrap()), document: None }
+    }
+
+    fn document_job(sender: &mpsc::Sender<&'static str>, uri: &str, name: &'static str) -> Job {
+        let stale = {
+            let sender = sender.clone();
+            Box::new(move || sender.send("stale").unwrap())
+        };
+        Job { document: Some((Url::parse(uri).unwrap(), stale)), ..job(sender, name) }
+    }
+
+    fn run_all(queue: &mut Queue) {
+        while let Some((job, _)) = queue.pop() {
+            (job.run)();
+        }
+    }
+
+    #[test]
+    fn interactive_jobs_run_first() {
+        let (sender, receiver) = mpsc::channel();
+        let mut queue = Queue { max_background: 1, ..Queue::default() };
+        queue.background.push_back(job(&sender, "prime caches"));
+        queue.interactive.push_back(job(&sender, "hover"));
+        queue.background.push_back(job(&sender, "diagnostics"));
+        queue.interactive.push_back(job(&sender, "completion"));
+
+        run_all(&mut queue);
+        let ran = receiver.try_iter().collect::<Vec<_>>();
+        assert_eq!(ran, ["hover", "completion", "prime caches", "diagnostics"]);
+    }
+
+    #[test]
+    fn background_jobs_leave_a_worker_free() {
+        let (sender, receiver) = mpsc::channel();
+        let mut queue = Queue { max_background: 1, running_background: 1, ..Queue::default() };
+        queue.background.push_back(job(&sender, "diagnostics"));
+        queue.interactive.push_back(job(&sender, "hover"));
+
+        run_all(&mut queue);
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), ["hover"]);
+        assert_eq!(queue.background.len(), 1);
+    }
+
+    #[test]
+    fn stale_requests_are_answered_without_running() {
+        let (sender, receiver) = mpsc::channel();
+        let mut queue = Queue { max_background: 1, ..Queue::default() };
+        queue.interactive.push_back(document_job(&sender, "file:///a.rs", "hover a"));
+        queue.interactive.push_back(document_job(&sender, "file:///b.rs", "hover b"));
+        queue.interactive.push_back(job(&sender, "workspace symbols"));
+        queue.interactive.push_back(document_job(&sender, "file:///a.rs", "completion a"));
+
+        for reply in queue.take_stale(&Url::parse("file:///a.rs").unwrap()) {
+            reply();
+        }
+        run_all(&mut queue);
+        let ran = receiver.try_iter().collect::<Vec<_>>();
+        assert_eq!(ran, ["stale", "stale", "hover b", "workspace symbols"]);
     }
 }
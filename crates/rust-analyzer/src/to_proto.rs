@@ -9,8 +9,9 @@ use ide::{
     Annotation, AnnotationKind, Assist, AssistKind, Cancellable, CompletionItem,
     CompletionItemKind, CompletionRelevance, Documentation, FileId, FileRange, FileSystemEdit,
     Fold, FoldKind, Highlight, HlMod, HlOperator, HlPunct, HlRange, HlTag, Indel, InlayHint,
-    InlayKind, Markup, NavigationTarget, ReferenceCategory, RenameError, Runnable, Severity,
-    SignatureHelp, SourceChange, StructureNodeKind, SymbolKind, TextEdit, TextRange, TextSize,
+    InlayHintLabel, InlayKind, Markup, NavigationTarget, ReferenceCategory, RenameError, Runnable,
+    Severity, SignatureHelp, SourceChange, StructureNodeKind, SymbolKind, TextEdit, TextRange,
+    TextSize,
 };
 use itertools::Itertools;
 use serde_json::to_value;
@@ -20,7 +21,7 @@ use crate::{
     cargo_target_spec::CargoTargetSpec,
     config::{CallInfoConfig, Config},
     global_state::GlobalStateSnapshot,
-    line_index::{LineEndings, LineIndex, OffsetEncoding},
+    line_index::{LineEndings, LineIndex, PositionEncoding},
     lsp_ext,
     lsp_utils::invalid_params_error,
     semantic_tokens, Result,
@@ -29,9 +30,9 @@ use crate::{
 pub(crate) fn position(line_index: &LineIndex, offset: TextSize) -> lsp_types::Position {
     let line_col = line_index.index.line_col(offset);
     match line_index.encoding {
-        OffsetEncoding::Utf8 => lsp_types::Position::new(line_col.line, line_col.col),
-        OffsetEncoding::Utf16 => {
-            let line_col = line_index.index.to_utf16(line_col);
+        PositionEncoding::Utf8 => lsp_types::Position::new(line_col.line, line_col.col),
+        PositionEncoding::Wide(enc) => {
+            let line_col = line_index.index.to_wide(enc, line_col);
             lsp_types::Position::new(line_col.line, line_col.col)
         }
     }
@@ -85,6 +86,15 @@ pub(crate) fn document_highlight_kind(
     match category {
         ReferenceCategory::Read => lsp_types::DocumentHighlightKind::READ,
         ReferenceCategory::Write => lsp_types::DocumentHighlightKind::WRITE,
+        ReferenceCategory::Import => lsp_types::DocumentHighlightKind::TEXT,
+    }
+}
+
+pub(crate) fn reference_category(category: ReferenceCategory) -> lsp_ext::ReferenceCategory {
+    match category {
+        ReferenceCategory::Read => lsp_ext::ReferenceCategory::Read,
+        ReferenceCategory::Write => lsp_ext::ReferenceCategory::Write,
+        ReferenceCategory::Import => lsp_ext::ReferenceCategory::Import,
     }
 }
 
@@ -279,24 +289,28 @@ fn completion_item(
     if item.is_snippet() {
         lsp_item.insert_text_format = Some(lsp_types::InsertTextFormat::SNIPPET);
     }
-    if config.completion().enable_imports_on_the_fly {
-        if let imports @ [_, ..] = item.imports_to_add() {
-            let imports: Vec<_> = imports
-                .iter()
-                .filter_map(|import_edit| {
-                    let import_path = &import_edit.import_path;
-                    let import_name = import_path.segments().last()?;
-                    Some(lsp_ext::CompletionImport {
-                        full_import_path: import_path.to_string(),
-                        imported_name: import_name.to_string(),
-                    })
+    let completion_config = config.completion();
+    let imports: Vec<_> = if completion_config.enable_imports_on_the_fly {
+        item.imports_to_add()
+            .iter()
+            .filter_map(|import_edit| {
+                let import_path = &import_edit.import_path;
+                let import_name = import_path.segments().last()?;
+                Some(lsp_ext::CompletionImport {
+                    full_import_path: import_path.to_string(),
+                    imported_name: import_name.to_string(),
                 })
-                .collect();
-            if !imports.is_empty() {
-                let data = lsp_ext::CompletionResolveData { position: tdpp.clone(), imports };
-                lsp_item.data = Some(to_value(data).unwrap());
-            }
-        }
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let fields_to_resolve = &completion_config.fields_to_resolve;
+    let hash = (fields_to_resolve.resolve_documentation || fields_to_resolve.resolve_detail)
+        .then(|| item.resolve_hash().to_string());
+    if !imports.is_empty() || hash.is_some() {
+        let data = lsp_ext::CompletionResolveData { position: tdpp.clone(), imports, hash };
+        lsp_item.data = Some(to_value(data).unwrap());
     }
 
     if let Some((mutability, relevance)) = item.ref_match() {
@@ -418,18 +432,29 @@ pub(crate) fn inlay_hint(
     snap: &GlobalStateSnapshot,
     line_index: &LineIndex,
     render_colons: bool,
+    file_id: FileId,
+    resolve_range: TextRange,
     inlay_hint: InlayHint,
-) -> lsp_types::InlayHint {
-    lsp_types::InlayHint {
+) -> Result<lsp_types::InlayHint> {
+    let (prefix, suffix) = match inlay_hint.kind {
+        InlayKind::ParameterHint if render_colons => ("", ":"),
+        InlayKind::TypeHint if render_colons => (": ", ""),
+        InlayKind::ClosureReturnTypeHint => (" -> ", ""),
+        _ => ("", ""),
+    };
+    let resolve_hash = inlay_hint.needs_resolve.then(|| inlay_hint.resolve_hash());
+    Ok(lsp_types::InlayHint {
         position: match inlay_hint.kind {
             // before annotated thing
             InlayKind::ParameterHint
             | InlayKind::ImplicitReborrowHint
+            | InlayKind::ReceiverBorrowHint
             | InlayKind::BindingModeHint => position(line_index, inlay_hint.range.start()),
             // after annotated thing
             InlayKind::ClosureReturnTypeHint
             | InlayKind::TypeHint
             | InlayKind::ChainingHint
+            | InlayKind::GenericArgListHint
             | InlayKind::GenericParamListHint
             | InlayKind::LifetimeHint
             | InlayKind::ClosingBraceHint => position(line_index, inlay_hint.range.end()),
@@ -439,68 +464,114 @@ pub(crate) fn inlay_hint(
             InlayKind::ChainingHint | InlayKind::ClosingBraceHint => true,
             InlayKind::BindingModeHint
             | InlayKind::ClosureReturnTypeHint
+            | InlayKind::GenericArgListHint
             | InlayKind::GenericParamListHint
             | InlayKind::ImplicitReborrowHint
             | InlayKind::LifetimeHint
-            | InlayKind::ParameterHint => false,
+            | InlayKind::ParameterHint
+            | InlayKind::ReceiverBorrowHint => false,
         }),
         padding_right: Some(match inlay_hint.kind {
             InlayKind::ChainingHint
             | InlayKind::ClosureReturnTypeHint
+            | InlayKind::GenericArgListHint
             | InlayKind::GenericParamListHint
             | InlayKind::ImplicitReborrowHint
             | InlayKind::TypeHint
             | InlayKind::ClosingBraceHint => false,
-            InlayKind::BindingModeHint => inlay_hint.label != "&",
+            InlayKind::BindingModeHint | InlayKind::ReceiverBorrowHint => {
+                inlay_hint.label.to_string() != "&"
+            }
             InlayKind::ParameterHint | InlayKind::LifetimeHint => true,
         }),
-        label: lsp_types::InlayHintLabel::String(match inlay_hint.kind {
-            InlayKind::ParameterHint if render_colons => format!("{}:", inlay_hint.label),
-            InlayKind::TypeHint if render_colons => format!(": {}", inlay_hint.label),
-            InlayKind::ClosureReturnTypeHint => format!(" -> {}", inlay_hint.label),
-            _ => inlay_hint.label.clone(),
-        }),
         kind: match inlay_hint.kind {
             InlayKind::ParameterHint => Some(lsp_types::InlayHintKind::PARAMETER),
-            InlayKind::ClosureReturnTypeHint | InlayKind::TypeHint | InlayKind::ChainingHint => {
-                Some(lsp_types::InlayHintKind::TYPE)
-            }
+            InlayKind::ClosureReturnTypeHint
+            | InlayKind::TypeHint
+            | InlayKind::ChainingHint
+            | InlayKind::GenericArgListHint => Some(lsp_types::InlayHintKind::TYPE),
             InlayKind::BindingModeHint
             | InlayKind::GenericParamListHint
             | InlayKind::LifetimeHint
             | InlayKind::ImplicitReborrowHint
+            | InlayKind::ReceiverBorrowHint
             | InlayKind::ClosingBraceHint => None,
         },
-        text_edits: None,
-        data: (|| match inlay_hint.tooltip {
-            Some(ide::InlayTooltip::HoverOffset(file_id, offset)) => {
-                let uri = url(snap, file_id);
-                let line_index = snap.file_line_index(file_id).ok()?;
-
-                let text_document = lsp_types::TextDocumentIdentifier { uri };
-                to_value(lsp_ext::InlayHintResolveData {
-                    text_document,
-                    position: lsp_ext::PositionOrRange::Position(position(&line_index, offset)),
-                })
-                .ok()
-            }
-            Some(ide::InlayTooltip::HoverRanged(file_id, text_range)) => {
-                let uri = url(snap, file_id);
-                let text_document = lsp_types::TextDocumentIdentifier { uri };
-                let line_index = snap.file_line_index(file_id).ok()?;
-                to_value(lsp_ext::InlayHintResolveData {
-                    text_document,
-                    position: lsp_ext::PositionOrRange::Range(range(&line_index, text_range)),
-                })
-                .ok()
+        text_edits: inlay_hint.text_edit.map(|it| text_edit_vec(line_index, it)),
+        data: to_value(lsp_ext::InlayHintResolveData {
+            tooltip: inlay_hint_tooltip_data(snap, inlay_hint.tooltip.as_ref())?,
+            hint: resolve_hash.map(|hash| lsp_ext::InlayHintLazyData {
+                text_document: lsp_types::TextDocumentIdentifier { uri: url(snap, file_id) },
+                resolve_range: range(line_index, resolve_range),
+                hash: hash.to_string(),
+            }),
+        })
+        .ok(),
+        tooltip: match &inlay_hint.tooltip {
+            Some(ide::InlayTooltip::String(s)) => {
+                Some(lsp_types::InlayHintTooltip::String(s.clone()))
             }
-            _ => None,
-        })(),
-        tooltip: Some(match inlay_hint.tooltip {
-            Some(ide::InlayTooltip::String(s)) => lsp_types::InlayHintTooltip::String(s),
-            _ => lsp_types::InlayHintTooltip::String(inlay_hint.label),
-        }),
+            // Left out tooltips are filled in by `inlayHint/resolve`.
+            None if inlay_hint.needs_resolve => None,
+            _ => Some(lsp_types::InlayHintTooltip::String(inlay_hint.label.to_string())),
+        },
+        label: inlay_hint_label(snap, inlay_hint.label, prefix, suffix)?,
+    })
+}
+
+/// The position to compute the hover of for tooltips that show a hover.
+pub(crate) fn inlay_hint_tooltip_data(
+    snap: &GlobalStateSnapshot,
+    tooltip: Option<&ide::InlayTooltip>,
+) -> Result<Option<lsp_ext::InlayHintTooltipData>> {
+    let (file_id, hover_position) = match tooltip {
+        Some(&ide::InlayTooltip::HoverOffset(file_id, offset)) => {
+            let line_index = snap.file_line_index(file_id)?;
+            (file_id, lsp_ext::PositionOrRange::Position(position(&line_index, offset)))
+        }
+        Some(&ide::InlayTooltip::HoverRanged(file_id, text_range)) => {
+            let line_index = snap.file_line_index(file_id)?;
+            (file_id, lsp_ext::PositionOrRange::Range(range(&line_index, text_range)))
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(lsp_ext::InlayHintTooltipData {
+        text_document: lsp_types::TextDocumentIdentifier { uri: url(snap, file_id) },
+        position: hover_position,
+    }))
+}
+
+fn inlay_hint_label(
+    snap: &GlobalStateSnapshot,
+    label: InlayHintLabel,
+    prefix: &str,
+    suffix: &str,
+) -> Result<lsp_types::InlayHintLabel> {
+    if let Some(text) = label.as_simple_str() {
+        return Ok(lsp_types::InlayHintLabel::String(format!("{}{}{}", prefix, text, suffix)));
     }
+    let mk_part = |value: &str| lsp_types::InlayHintLabelPart {
+        value: value.to_string(),
+        tooltip: None,
+        location: None,
+        command: None,
+    };
+    let mut parts = Vec::with_capacity(label.parts.len() + 2);
+    if !prefix.is_empty() {
+        parts.push(mk_part(prefix));
+    }
+    for part in label.parts {
+        parts.push(lsp_types::InlayHintLabelPart {
+            value: part.text,
+            tooltip: None,
+            location: part.linked_location.map(|it| location(snap, it)).transpose()?,
+            command: None,
+        });
+    }
+    if !suffix.is_empty() {
+        parts.push(mk_part(suffix));
+    }
+    Ok(lsp_types::InlayHintLabel::LabelParts(parts))
 }
 
 static TOKEN_RESULT_COUNTER: AtomicU32 = AtomicU32::new(1);
@@ -670,6 +741,7 @@ pub(crate) fn folding_range(
         | FoldKind::Statics
         | FoldKind::WhereClause
         | FoldKind::ReturnType
+        | FoldKind::MatchArm
         | FoldKind::Array => None,
     };
 
@@ -860,11 +932,12 @@ pub(crate) fn snippet_text_document_edit(
 
 pub(crate) fn snippet_text_document_ops(
     snap: &GlobalStateSnapshot,
+    snippets: &mut SnippetDocuments,
     file_system_edit: FileSystemEdit,
 ) -> Cancellable<Vec<lsp_ext::SnippetDocumentChangeOperation>> {
     let mut ops = Vec::new();
     match file_system_edit {
-        FileSystemEdit::CreateFile { dst, initial_contents } => {
+        FileSystemEdit::CreateFile { dst, mut initial_contents, is_snippet } => {
             let uri = snap.anchored_path(&dst);
             let create_file = lsp_types::ResourceOp::Create(lsp_types::CreateFile {
                 uri: uri.clone(),
@@ -873,12 +946,20 @@ pub(crate) fn snippet_text_document_ops(
             });
             ops.push(lsp_ext::SnippetDocumentChangeOperation::Op(create_file));
             if !initial_contents.is_empty() {
+                let keep_snippet = is_snippet && snippets.accept(once(&*initial_contents));
+                if is_snippet && !keep_snippet {
+                    initial_contents = snippet_to_plain_text(&initial_contents);
+                }
                 let text_document =
                     lsp_types::OptionalVersionedTextDocumentIdentifier { uri, version: None };
                 let text_edit = lsp_ext::SnippetTextEdit {
                     range: lsp_types::Range::default(),
                     new_text: initial_contents,
-                    insert_text_format: Some(lsp_types::InsertTextFormat::PLAIN_TEXT),
+                    insert_text_format: Some(if keep_snippet {
+                        lsp_types::InsertTextFormat::SNIPPET
+                    } else {
+                        lsp_types::InsertTextFormat::PLAIN_TEXT
+                    }),
                     annotation_id: None,
                 };
                 let edit_file =
@@ -923,13 +1004,23 @@ pub(crate) fn snippet_workspace_edit(
     source_change: SourceChange,
 ) -> Result<lsp_ext::SnippetWorkspaceEdit> {
     let mut document_changes: Vec<lsp_ext::SnippetDocumentChangeOperation> = Vec::new();
+    let mut snippets = SnippetDocuments::new(snap.config.multi_file_snippet_cap());
 
     for op in source_change.file_system_edits {
-        let ops = snippet_text_document_ops(snap, op)?;
+        let ops = snippet_text_document_ops(snap, &mut snippets, op)?;
         document_changes.extend_from_slice(&ops);
     }
-    for (file_id, edit) in source_change.source_file_edits {
-        let edit = snippet_text_document_edit(snap, source_change.is_snippet, file_id, edit)?;
+    for (file_id, mut edit) in source_change.source_file_edits {
+        let keep_snippet = source_change.is_snippet
+            && snippets.accept(edit.iter().map(|indel| indel.insert.as_str()));
+        if source_change.is_snippet && !keep_snippet {
+            let mut builder = TextEdit::builder();
+            for indel in edit {
+                builder.replace(indel.delete, snippet_to_plain_text(&indel.insert));
+            }
+            edit = builder.finish();
+        }
+        let edit = snippet_text_document_edit(snap, keep_snippet, file_id, edit)?;
         document_changes.push(lsp_ext::SnippetDocumentChangeOperation::Edit(edit));
     }
     let mut workspace_edit = lsp_ext::SnippetWorkspaceEdit {
@@ -955,11 +1046,73 @@ pub(crate) fn snippet_workspace_edit(
     Ok(workspace_edit)
 }
 
+/// Decides which documents of a workspace edit keep their snippets.
+///
+/// Clients that can't place tab stops in several documents at once only get the snippets of the
+/// first document that has any, the other documents are sent as plain text.
+pub(crate) struct SnippetDocuments {
+    multi_file: bool,
+    has_snippet_document: bool,
+}
+
+impl SnippetDocuments {
+    fn new(multi_file: bool) -> SnippetDocuments {
+        SnippetDocuments { multi_file, has_snippet_document: false }
+    }
+
+    /// Returns whether a document with the given snippet texts may be sent as a snippet.
+    fn accept<'a>(&mut self, mut texts: impl Iterator<Item = &'a str>) -> bool {
+        if self.multi_file {
+            return true;
+        }
+        if self.has_snippet_document || !texts.any(has_tab_stop) {
+            return false;
+        }
+        self.has_snippet_document = true;
+        true
+    }
+}
+
+/// Whether `snippet` contains a tab stop or a placeholder, like `$0` or `${1:name}`.
+fn has_tab_stop(snippet: &str) -> bool {
+    snippet.as_bytes().windows(2).any(|w| w[0] == b'$' && (w[1].is_ascii_digit() || w[1] == b'{'))
+}
+
+/// Turns a snippet into the text it expands to when all placeholders are kept as they are.
+///
+/// Like the assists rendering them, this only unescapes the text of placeholders.
+fn snippet_to_plain_text(snippet: &str) -> String {
+    let mut res = String::with_capacity(snippet.len());
+    let mut chars = snippet.chars().peekable();
+    let mut open_placeholders = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if open_placeholders > 0 => {
+                match chars.next_if(|c| matches!(c, '$' | '{' | '}')) {
+                    Some(escaped) => res.push(escaped),
+                    None => res.push('\\'),
+                }
+            }
+            '$' if chars.next_if(char::is_ascii_digit).is_some() => {
+                while chars.next_if(char::is_ascii_digit).is_some() {}
+            }
+            '$' if chars.next_if_eq(&'{').is_some() => {
+                while chars.next_if(char::is_ascii_digit).is_some() {}
+                chars.next_if_eq(&':');
+                open_placeholders += 1;
+            }
+            '}' if open_placeholders > 0 => open_placeholders -= 1,
+            _ => res.push(c),
+        }
+    }
+    res
+}
+
 pub(crate) fn workspace_edit(
     snap: &GlobalStateSnapshot,
     source_change: SourceChange,
 ) -> Result<lsp_types::WorkspaceEdit> {
-    assert!(!source_change.is_snippet);
+    assert!(!source_change.has_snippets());
     snippet_workspace_edit(snap, source_change).map(|it| it.into())
 }
 
@@ -1011,9 +1164,14 @@ impl From<lsp_ext::SnippetTextEdit>
 pub(crate) fn call_hierarchy_item(
     snap: &GlobalStateSnapshot,
     target: NavigationTarget,
+    potential: bool,
 ) -> Result<lsp_types::CallHierarchyItem> {
     let name = target.name.to_string();
-    let detail = target.description.clone();
+    let detail = match (target.description.clone(), potential) {
+        (detail, false) => detail,
+        (Some(detail), true) => Some(format!("{} (potential call)", detail)),
+        (None, true) => Some("potential call".to_string()),
+    };
     let kind = target.kind.map(symbol_kind).unwrap_or(lsp_types::SymbolKind::FUNCTION);
     let (uri, range, selection_range) = location_info(snap, target)?;
     Ok(lsp_types::CallHierarchyItem {
@@ -1028,6 +1186,60 @@ pub(crate) fn call_hierarchy_item(
     })
 }
 
+pub(crate) fn type_hierarchy_item(
+    snap: &GlobalStateSnapshot,
+    target: NavigationTarget,
+) -> Result<lsp_ext::TypeHierarchyItem> {
+    let name = target.name.to_string();
+    let detail = target.description.clone();
+    let kind = target.kind.map(symbol_kind).unwrap_or(lsp_types::SymbolKind::STRUCT);
+    let (uri, range, selection_range) = location_info(snap, target)?;
+    Ok(lsp_ext::TypeHierarchyItem {
+        name,
+        kind,
+        tags: None,
+        detail,
+        uri,
+        range,
+        selection_range,
+        data: None,
+    })
+}
+
+pub(crate) fn test_item(
+    snap: &GlobalStateSnapshot,
+    test_item: ide::TestItem,
+) -> Result<lsp_ext::TestItem> {
+    let (text_document, range) = match (test_item.file, test_item.text_range) {
+        (Some(file_id), Some(text_range)) => {
+            let line_index = snap.file_line_index(file_id)?;
+            (
+                Some(lsp_types::TextDocumentIdentifier { uri: url(snap, file_id) }),
+                Some(range(&line_index, text_range)),
+            )
+        }
+        _ => (None, None),
+    };
+    let runnable = match test_item.runnable {
+        Some(it) => Some(runnable(snap, it)?),
+        None => None,
+    };
+    Ok(lsp_ext::TestItem {
+        id: test_item.id,
+        label: test_item.label,
+        kind: match test_item.kind {
+            ide::TestItemKind::Crate => lsp_ext::TestItemKind::Package,
+            ide::TestItemKind::Module => lsp_ext::TestItemKind::Module,
+            ide::TestItemKind::Function => lsp_ext::TestItemKind::Test,
+        },
+        can_resolve_children: matches!(test_item.kind, ide::TestItemKind::Crate),
+        parent: test_item.parent,
+        text_document,
+        range,
+        runnable,
+    })
+}
+
 pub(crate) fn code_action_kind(kind: AssistKind) -> lsp_types::CodeActionKind {
     match kind {
         AssistKind::None | AssistKind::Generate => lsp_types::CodeActionKind::EMPTY,
@@ -1079,6 +1291,11 @@ pub(crate) fn runnable(
 ) -> Result<lsp_ext::Runnable> {
     let config = snap.config.runnables();
     let spec = CargoTargetSpec::for_file(snap, runnable.nav.file_id)?;
+    if spec.is_none() {
+        if let Some(res) = project_json_runnable(snap, &runnable)? {
+            return Ok(res);
+        }
+    }
     let workspace_root = spec.as_ref().map(|it| it.workspace_root.clone());
     let target = spec.as_ref().map(|s| s.target.clone());
     let (cargo_args, executable_args) =
@@ -1090,14 +1307,100 @@ pub(crate) fn runnable(
         label,
         location: Some(location),
         kind: lsp_ext::RunnableKind::Cargo,
-        args: lsp_ext::CargoRunnable {
+        args: lsp_ext::RunnableArgs::Cargo(lsp_ext::CargoRunnable {
             workspace_root: workspace_root.map(|it| it.into()),
             override_cargo: config.override_cargo,
             cargo_args,
             cargo_extra_args: config.cargo_extra_args,
             executable_args,
             expect_test: None,
-        },
+        }),
+    })
+}
+
+/// Fills in the matching command template of the `rust-project.json` the runnable's crate
+/// comes from.
+fn project_json_runnable(
+    snap: &GlobalStateSnapshot,
+    runnable: &Runnable,
+) -> Result<Option<lsp_ext::Runnable>> {
+    let (kind, test_id) = match &runnable.kind {
+        ide::RunnableKind::Test { test_id, .. } => {
+            (project_model::RunnableKind::TestOne, Some(test_id.to_string()))
+        }
+        ide::RunnableKind::TestMod { path } => {
+            (project_model::RunnableKind::TestOne, Some(path.clone()))
+        }
+        ide::RunnableKind::Bin => (project_model::RunnableKind::Run, None),
+        _ => return Ok(None),
+    };
+    let (template, build) = match project_json_command(snap, runnable.nav.file_id, kind)? {
+        Some(it) => it,
+        None => return Ok(None),
+    };
+
+    Ok(Some(lsp_ext::Runnable {
+        label: runnable.label(Some(build.label.clone())),
+        location: Some(location_link(snap, None, runnable.nav.clone())?),
+        kind: lsp_ext::RunnableKind::Shell,
+        args: shell_runnable_args(template, build, test_id.as_deref()),
+    }))
+}
+
+/// The `check` command of the `rust-project.json` crate `file_id` belongs to.
+pub(crate) fn project_json_check(
+    snap: &GlobalStateSnapshot,
+    file_id: FileId,
+) -> Result<Option<lsp_ext::Runnable>> {
+    let kind = project_model::RunnableKind::Check;
+    let (template, build) = match project_json_command(snap, file_id, kind)? {
+        Some(it) => it,
+        None => return Ok(None),
+    };
+    Ok(Some(lsp_ext::Runnable {
+        label: format!("check {}", build.label),
+        location: None,
+        kind: lsp_ext::RunnableKind::Shell,
+        args: shell_runnable_args(template, build, None),
+    }))
+}
+
+fn project_json_command(
+    snap: &GlobalStateSnapshot,
+    file_id: FileId,
+    kind: project_model::RunnableKind,
+) -> Result<Option<(&project_model::Runnable, &project_model::Build)>> {
+    let crate_id = match &*snap.analysis.crate_for(file_id)? {
+        &[crate_id, ..] => crate_id,
+        _ => return Ok(None),
+    };
+    let (project, build) = match snap.json_build_for_crate_root(crate_id) {
+        Some(it) => it,
+        None => return Ok(None),
+    };
+    Ok(project.runnables().iter().find(|it| it.kind == kind).map(|it| (it, build)))
+}
+
+fn shell_runnable_args(
+    template: &project_model::Runnable,
+    build: &project_model::Build,
+    test_id: Option<&str>,
+) -> lsp_ext::RunnableArgs {
+    let args = template
+        .args
+        .iter()
+        .map(|arg| {
+            let arg = arg.replace("{label}", &build.label);
+            match test_id {
+                Some(test_id) => arg.replace("{test_id}", test_id),
+                None => arg,
+            }
+        })
+        .collect();
+    lsp_ext::RunnableArgs::Shell(lsp_ext::ShellRunnable {
+        program: template.program.clone(),
+        args,
+        cwd: template.cwd.clone().into(),
     })
 }
 
@@ -1118,9 +1421,12 @@ pub(crate) fn code_lens(
                 ide::RunnableKind::TestMod { .. }
                 | ide::RunnableKind::Test { .. }
                 | ide::RunnableKind::Bench { .. }
+                | ide::RunnableKind::BenchHarness { .. }
                 | ide::RunnableKind::Bin => true,
             };
             let r = runnable(snap, run)?;
+            // Debugging needs Cargo to build the executable.
+            let can_debug = can_debug && matches!(r.kind, lsp_ext::RunnableKind::Cargo);
 
             let lens_config = snap.config.lens();
             if lens_config.run && client_commands_config.run_single {
@@ -1223,6 +1529,7 @@ pub(crate) mod command {
     use serde_json::to_value;
 
     use crate::{
+        config::Config,
         global_state::GlobalStateSnapshot,
         lsp_ext,
         to_proto::{location, location_link},
@@ -1292,6 +1599,53 @@ pub(crate) mod command {
             arguments: None,
         }
     }
+
+    pub(crate) fn goto_file(
+        config: &Config,
+        title: String,
+        uri: lsp_types::Url,
+    ) -> lsp_types::Command {
+        let range = lsp_types::Range::default();
+        let value = if config.location_link() {
+            to_value(lsp_types::LocationLink {
+                origin_selection_range: None,
+                target_uri: uri,
+                target_range: range,
+                target_selection_range: range,
+            })
+        } else {
+            to_value(lsp_types::Location { uri, range })
+        };
+        lsp_types::Command {
+            title,
+            command: "rust-analyzer.gotoLocation".into(),
+            arguments: Some(vec![value.unwrap()]),
+        }
+    }
+
+    pub(crate) fn reload_workspace() -> lsp_types::Command {
+        lsp_types::Command {
+            title: "Reload workspace".into(),
+            command: "rust-analyzer.reloadWorkspace".into(),
+            arguments: None,
+        }
+    }
+
+    pub(crate) fn rebuild_proc_macros() -> lsp_types::Command {
+        lsp_types::Command {
+            title: "Rebuild proc macros and build scripts".into(),
+            command: "rust-analyzer.rebuildProcMacros".into(),
+            arguments: None,
+        }
+    }
+
+    pub(crate) fn install_rust_src(toolchain: Option<&str>) -> lsp_types::Command {
+        lsp_types::Command {
+            title: "Install rust-src".into(),
+            command: "rust-analyzer.installRustSrc".into(),
+            arguments: toolchain.map(|it| vec![to_value(it).unwrap()]),
+        }
+    }
 }
 
 pub(crate) fn implementation_title(count: usize) -> String {
@@ -1332,7 +1686,7 @@ pub(crate) fn rename_error(err: RenameError) -> crate::LspError {
 mod tests {
     use std::sync::Arc;
 
-    use ide::Analysis;
+    use ide::{Analysis, WideEncoding};
 
     use super::*;
 
@@ -1357,7 +1711,7 @@ fn main() {
         let line_index = LineIndex {
             index: Arc::new(ide::LineIndex::new(text)),
             endings: LineEndings::Unix,
-            encoding: OffsetEncoding::Utf16,
+            encoding: PositionEncoding::Wide(WideEncoding::Utf16),
         };
         let converted: Vec<lsp_types::FoldingRange> =
             folds.into_iter().map(|it| folding_range(text, &line_index, true, it)).collect();
@@ -1372,6 +1726,23 @@ fn main() {
         }
     }
 
+    #[test]
+    fn snippets_as_plain_text() {
+        assert_eq!(snippet_to_plain_text("fn f() { $0 }"), "fn f() {  }");
+        assert_eq!(snippet_to_plain_text("make::<${1:_}, ${0:_}>()"), "make::<_, _>()");
+        assert_eq!(snippet_to_plain_text(r"let ${0:\{x\}} = $$x;"), "let {x} = $$x;");
+        assert_eq!(snippet_to_plain_text(r#"println!("\\{}");"#), r#"println!("\\{}");"#);
+
+        let mut single = SnippetDocuments::new(false);
+        assert!(!single.accept(once("fn f() {}")));
+        assert!(single.accept(once("fn f() { $0 }")));
+        assert!(!single.accept(once("fn g() { $0 }")));
+
+        let mut multi = SnippetDocuments::new(true);
+        assert!(multi.accept(once("fn f() { $0 }")));
+        assert!(multi.accept(once("fn g() { $0 }")));
+    }
+
     // `Url` is not able to parse windows paths on unix machines.
     #[test]
     #[cfg(target_os = "windows")]
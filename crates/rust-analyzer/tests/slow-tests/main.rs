@@ -13,26 +13,37 @@ mod tidy;
 mod testdir;
 mod support;
 
-use std::{collections::HashMap, path::PathBuf, time::Instant};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
 use expect_test::expect;
 use lsp_types::{
-    notification::DidOpenTextDocument,
+    notification::{DidChangeTextDocument, DidChangeWorkspaceFolders, DidOpenTextDocument},
     request::{
         CodeActionRequest, Completion, Formatting, GotoTypeDefinition, HoverRequest,
-        WillRenameFiles,
+        SemanticTokensFullDeltaRequest, SemanticTokensFullRequest, WillRenameFiles,
     },
-    CodeActionContext, CodeActionParams, CompletionParams, DidOpenTextDocumentParams,
-    DocumentFormattingParams, FileRename, FormattingOptions, GotoDefinitionParams, HoverParams,
-    PartialResultParams, Position, Range, RenameFilesParams, TextDocumentItem,
-    TextDocumentPositionParams, WorkDoneProgressParams,
+    CodeActionContext, CodeActionParams, CompletionParams, DidChangeTextDocumentParams,
+    DidChangeWorkspaceFoldersParams, DidOpenTextDocumentParams, DocumentFormattingParams,
+    FileRename, FormattingOptions, GotoDefinitionParams, HoverParams, PartialResultParams,
+    Position, Range, RenameFilesParams, SemanticTokensDeltaParams, SemanticTokensParams,
+    TextDocumentContentChangeEvent, TextDocumentItem, TextDocumentPositionParams, Url,
+    VersionedTextDocumentIdentifier, WorkDoneProgressParams, WorkspaceFolder,
+    WorkspaceFoldersChangeEvent,
+};
+use proc_macro_api::{MacroDylib, ProcMacroServer};
+use rust_analyzer::lsp_ext::{
+    DocumentDiagnosticParams, DocumentDiagnosticRequest, OnEnter, Runnables, RunnablesParams,
 };
-use rust_analyzer::lsp_ext::{OnEnter, Runnables, RunnablesParams};
 use serde_json::json;
 use test_utils::skip_slow_tests;
+use vfs::AbsPathBuf;
 
 use crate::{
-    support::{project, Project},
+    support::{project, Project, Server},
     testdir::TestDir,
 };
 
@@ -289,6 +300,75 @@ mod tests {
     }
 }
 
+#[test]
+fn added_workspace_folders_are_loaded() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = Project::with_fixture(
+        r#"
+//- /foo/Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- /foo/src/lib.rs
+pub fn foo() {}
+
+//- /bar/Cargo.toml
+[package]
+name = "bar"
+version = "0.0.0"
+
+//- /bar/src/lib.rs
+pub fn bar() {}
+"#,
+    )
+    .root("foo")
+    .server()
+    .wait_until_workspace_is_loaded();
+
+    server.notification::<DidChangeWorkspaceFolders>(DidChangeWorkspaceFoldersParams {
+        event: WorkspaceFoldersChangeEvent {
+            added: vec![WorkspaceFolder {
+                uri: Url::from_file_path(server.path().join("bar")).unwrap(),
+                name: "bar".to_string(),
+            }],
+            removed: Vec::new(),
+        },
+    });
+    let server = server.wait_until_workspace_is_reloaded();
+
+    server.request::<Runnables>(
+        RunnablesParams { text_document: server.doc_id("bar/src/lib.rs"), position: None },
+        json!([
+            {
+                "label": "cargo check -p bar --all-targets",
+                "kind": "cargo",
+                "args": {
+                    "overrideCargo": null,
+                    "workspaceRoot": server.path().join("bar"),
+                    "cargoArgs": ["check", "--package", "bar", "--all-targets"],
+                    "cargoExtraArgs": [],
+                    "executableArgs": []
+                },
+            },
+            {
+                "label": "cargo test -p bar --all-targets",
+                "kind": "cargo",
+                "args": {
+                    "overrideCargo": null,
+                    "workspaceRoot": server.path().join("bar"),
+                    "cargoArgs": ["test", "--package", "bar", "--all-targets"],
+                    "cargoExtraArgs": [],
+                    "executableArgs": []
+                },
+            }
+        ]),
+    );
+}
+
 #[test]
 fn test_format_document() {
     if skip_slow_tests() {
@@ -646,6 +726,107 @@ fn main() {{}}
     assert!(elapsed.as_millis() < 2000, "typing enter took {:?}", elapsed);
 }
 
+#[test]
+fn pull_diagnostics_need_client_support() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let fixture = r#"
+//- /Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- /src/lib.rs
+mod missing;
+"#;
+    let config = json!({ "diagnostics": { "pull": { "enable": true } } });
+    let params = |server: &Server| DocumentDiagnosticParams {
+        text_document: server.doc_id("src/lib.rs"),
+        identifier: None,
+        previous_result_id: None,
+        work_done_progress_params: WorkDoneProgressParams::default(),
+        partial_result_params: PartialResultParams::default(),
+    };
+
+    // A client that can't pull diagnostics still gets them published instead.
+    let server = Project::with_fixture(fixture)
+        .with_config(config.clone())
+        .server()
+        .wait_until_workspace_is_loaded();
+    let report = server.send_request::<DocumentDiagnosticRequest>(params(&server));
+    assert_eq!(report["items"], json!([]));
+    drop(server);
+
+    let server = Project::with_fixture(fixture)
+        .with_config(config)
+        .pull_diagnostics()
+        .server()
+        .wait_until_workspace_is_loaded();
+    let report = server.send_request::<DocumentDiagnosticRequest>(params(&server));
+    let items = report["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1, "{:#?}", items);
+    assert_eq!(items[0]["code"], "unresolved-module");
+}
+
+#[test]
+fn semantic_tokens_delta_after_refresh() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = Project::with_fixture(
+        r#"
+//- /Cargo.toml
+[package]
+name = "foo"
+version = "0.0.0"
+
+//- /src/lib.rs
+fn foo() {}
+"#,
+    )
+    .server()
+    .wait_until_workspace_is_loaded();
+
+    let uri = server.doc_id("src/lib.rs").uri;
+    server.notification::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+        text_document: TextDocumentItem {
+            uri: uri.clone(),
+            language_id: "rust".to_string(),
+            version: 0,
+            text: "fn foo() {}".to_string(),
+        },
+    });
+    let tokens = server.send_request::<SemanticTokensFullRequest>(SemanticTokensParams {
+        text_document: server.doc_id("src/lib.rs"),
+        work_done_progress_params: WorkDoneProgressParams::default(),
+        partial_result_params: PartialResultParams::default(),
+    });
+    let result_id = tokens["resultId"].as_str().unwrap().to_string();
+
+    // Changing the file makes the server ask the client to refresh its semantic tokens.
+    server.notification::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+        text_document: VersionedTextDocumentIdentifier { uri, version: 1 },
+        content_changes: vec![TextDocumentContentChangeEvent {
+            range: None,
+            range_length: None,
+            text: "fn foo() {}\nfn bar() {}".to_string(),
+        }],
+    });
+
+    // The refresh must not have dropped the tokens the client holds for `result_id`.
+    let delta = server.send_request::<SemanticTokensFullDeltaRequest>(SemanticTokensDeltaParams {
+        text_document: server.doc_id("src/lib.rs"),
+        previous_result_id: result_id,
+        work_done_progress_params: WorkDoneProgressParams::default(),
+        partial_result_params: PartialResultParams::default(),
+    });
+    let edits = delta["edits"].as_array().unwrap_or_else(|| panic!("no delta: {delta:#?}"));
+    assert!(!edits.is_empty());
+}
+
 #[test]
 fn preserves_dos_line_endings() {
     if skip_slow_tests() {
@@ -928,6 +1109,44 @@ pub fn foo(_input: TokenStream) -> TokenStream {
     .assert_eq(value);
 }
 
+#[test]
+fn proc_macro_server_restarts_after_timeout() {
+    if skip_slow_tests() {
+        return;
+    }
+
+    let server = ProcMacroServer::spawn(
+        AbsPathBuf::assert(PathBuf::from(env!("CARGO_BIN_EXE_rust-analyzer"))),
+        ["proc-macro"],
+        Duration::from_secs(1),
+    )
+    .unwrap();
+    let dylib = AbsPathBuf::assert(proc_macro_test::PROC_MACRO_TEST_LOCATION.into());
+    let macros = match server.load_dylib(MacroDylib::new(dylib).unwrap()) {
+        Ok(Ok(it)) => it,
+        Ok(Err(err)) => panic!("failed to load the test macros: {}", err),
+        Err(err) => panic!("failed to load the test macros: {}", err),
+    };
+    let expand = |name: &str| {
+        let input = tt::Subtree::default();
+        macros.iter().find(|it| it.name() == name).unwrap().expand(&input, None, Vec::new())
+    };
+
+    let start = Instant::now();
+    match expand("fn_like_loop") {
+        Err(err) => assert!(err.message.contains("timed out"), "{}", err),
+        Ok(res) => panic!("the expansion didn't time out: {:?}", res),
+    }
+    assert!(start.elapsed() < Duration::from_secs(30));
+
+    // The hanging process was killed, a new one expands the following macros.
+    match expand("fn_like_noop") {
+        Ok(Ok(it)) => assert_eq!(it, tt::Subtree::default()),
+        Ok(Err(err)) => panic!("fn_like_noop panicked: {:?}", err),
+        Err(err) => panic!("the server wasn't restarted: {}", err),
+    }
+}
+
 #[test]
 fn test_will_rename_files_same_level() {
     if skip_slow_tests() {
@@ -23,6 +23,7 @@ pub(crate) struct Project<'a> {
     tmp_dir: Option<TestDir>,
     roots: Vec<PathBuf>,
     config: serde_json::Value,
+    pull_diagnostics: bool,
 }
 
 impl<'a> Project<'a> {
@@ -41,6 +42,7 @@ impl<'a> Project<'a> {
                     },
                 }
             }),
+            pull_diagnostics: false,
         }
     }
 
@@ -54,6 +56,12 @@ impl<'a> Project<'a> {
         self
     }
 
+    /// Makes the client announce support for pulling diagnostics.
+    pub(crate) fn pull_diagnostics(mut self) -> Project<'a> {
+        self.pull_diagnostics = true;
+        self
+    }
+
     pub(crate) fn with_config(mut self, config: serde_json::Value) -> Project<'a> {
         fn merge(dst: &mut serde_json::Value, src: serde_json::Value) {
             match (dst, src) {
@@ -96,8 +104,8 @@ impl<'a> Project<'a> {
             roots.push(tmp_dir_path.clone());
         }
         let discovered_projects = roots
-            .into_iter()
-            .map(|it| ProjectManifest::discover_single(&it).unwrap())
+            .iter()
+            .map(|it| ProjectManifest::discover_single(it).unwrap())
             .collect::<Vec<_>>();
 
         let mut config = Config::new(
@@ -109,6 +117,9 @@ impl<'a> Project<'a> {
                             dynamic_registration: Some(true),
                         },
                     ),
+                    semantic_tokens: Some(lsp_types::SemanticTokensWorkspaceClientCapabilities {
+                        refresh_support: Some(true),
+                    }),
                     ..Default::default()
                 }),
                 text_document: Some(lsp_types::TextDocumentClientCapabilities {
@@ -139,6 +150,8 @@ impl<'a> Project<'a> {
             },
         );
         config.discovered_projects = Some(discovered_projects);
+        config.workspace_roots = roots;
+        config.set_diagnostic_capabilities(self.pull_diagnostics, self.pull_diagnostics);
         config.update(self.config).expect("invalid config");
 
         Server::new(tmp_dir, config)
@@ -219,11 +232,20 @@ impl Server {
         while let Some(msg) = self.recv().unwrap_or_else(|Timeout| panic!("timeout: {:?}", r)) {
             match msg {
                 Message::Request(req) => {
+                    if req.method == "workspace/diagnostic/refresh"
+                        || req.method == "workspace/semanticTokens/refresh"
+                    {
+                        continue;
+                    }
                     if req.method == "client/registerCapability" {
                         let params = req.params.to_string();
-                        if ["workspace/didChangeWatchedFiles", "textDocument/didSave"]
-                            .into_iter()
-                            .any(|it| params.contains(it))
+                        if [
+                            "workspace/didChangeWatchedFiles",
+                            "textDocument/didSave",
+                            "textDocument/diagnostic",
+                        ]
+                        .into_iter()
+                        .any(|it| params.contains(it))
                         {
                             continue;
                         }
@@ -243,17 +265,14 @@ impl Server {
         panic!("no response for {:?}", r);
     }
     pub(crate) fn wait_until_workspace_is_loaded(self) -> Server {
-        self.wait_for_message_cond(1, &|msg: &Message| match msg {
-            Message::Notification(n) if n.method == "experimental/serverStatus" => {
-                let status = n
-                    .clone()
-                    .extract::<lsp_ext::ServerStatusParams>("experimental/serverStatus")
-                    .unwrap();
-                status.quiescent
-            }
-            _ => false,
-        })
-        .unwrap_or_else(|Timeout| panic!("timeout while waiting for ws to load"));
+        self.wait_for_message_cond(1, &is_quiescent_status)
+            .unwrap_or_else(|Timeout| panic!("timeout while waiting for ws to load"));
+        self
+    }
+    /// Waits until the workspace is loaded a second time, after a change made it reload.
+    pub(crate) fn wait_until_workspace_is_reloaded(self) -> Server {
+        self.wait_for_message_cond(2, &is_quiescent_status)
+            .unwrap_or_else(|Timeout| panic!("timeout while waiting for ws to reload"));
         self
     }
     fn wait_for_message_cond(
@@ -316,6 +335,19 @@ fn recv_timeout(receiver: &Receiver<Message>) -> Result<Option<Message>, Timeout
 /// You can use `[..]` wildcard in strings (useful for OS dependent things such
 /// as paths). You can use a `"{...}"` string literal as a wildcard for
 /// arbitrary nested JSON. Arrays are sorted before comparison.
+fn is_quiescent_status(msg: &Message) -> bool {
+    match msg {
+        Message::Notification(n) if n.method == "experimental/serverStatus" => {
+            let status = n
+                .clone()
+                .extract::<lsp_ext::ServerStatusParams>("experimental/serverStatus")
+                .unwrap();
+            status.quiescent
+        }
+        _ => false,
+    }
+}
+
 fn find_mismatch<'a>(expected: &'a Value, actual: &'a Value) -> Option<(&'a Value, &'a Value)> {
     match (expected, actual) {
         (Value::Number(l), Value::Number(r)) if l == r => None,
@@ -8,10 +8,23 @@ pub fn enter(context: String) -> PanicContext {
     static ONCE: Once = Once::new();
     ONCE.call_once(PanicContext::init);
 
-    with_ctx(|ctx| ctx.push(context));
+    with_ctx(|ctx| {
+        if ctx.is_empty() {
+            with_last_panic(|last| last.clear());
+        }
+        ctx.push(context)
+    });
     PanicContext { _priv: () }
 }
 
+/// Returns the context frames that were active when this thread last panicked.
+///
+/// The frames are unwound together with the stack, so they are recorded by the
+/// panic hook and can be retrieved after catching the panic.
+pub fn take_last_panic_context() -> Vec<String> {
+    with_last_panic(std::mem::take)
+}
+
 #[must_use]
 pub struct PanicContext {
     _priv: (),
@@ -28,6 +41,7 @@ impl PanicContext {
                         eprintln!("> {}\n", frame);
                     }
                 }
+                with_last_panic(|last| last.clone_from(ctx));
                 default_hook(panic_info);
             });
         };
@@ -47,3 +61,29 @@ fn with_ctx(f: impl FnOnce(&mut Vec<String>)) {
     }
     CTX.with(|ctx| f(&mut *ctx.borrow_mut()));
 }
+
+fn with_last_panic<T>(f: impl FnOnce(&mut Vec<String>) -> T) -> T {
+    thread_local! {
+        static LAST_PANIC: RefCell<Vec<String>> = RefCell::new(Vec::new());
+    }
+    LAST_PANIC.with(|last| f(&mut *last.borrow_mut()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic;
+
+    use super::*;
+
+    #[test]
+    fn last_panic_context_survives_unwinding() {
+        let res = panic::catch_unwind(|| {
+            let _outer = enter("outer".to_string());
+            let _inner = enter("inner".to_string());
+            panic!("boom");
+        });
+        assert!(res.is_err());
+        assert_eq!(take_last_panic_context(), ["outer", "inner"]);
+        assert!(take_last_panic_context().is_empty());
+    }
+}
@@ -732,7 +732,7 @@ impl ast::RangePat {
     pub fn start(&self) -> Option<ast::Pat> {
         self.syntax()
             .children_with_tokens()
-            .take_while(|it| !(it.kind() == T![..] || it.kind() == T![..=]))
+            .take_while(|it| !matches!(it.kind(), T![..] | T![..=] | T![...]))
             .filter_map(|it| it.into_node())
             .find_map(ast::Pat::cast)
     }
@@ -740,10 +740,25 @@ impl ast::RangePat {
     pub fn end(&self) -> Option<ast::Pat> {
         self.syntax()
             .children_with_tokens()
-            .skip_while(|it| !(it.kind() == T![..] || it.kind() == T![..=]))
+            .skip_while(|it| !matches!(it.kind(), T![..] | T![..=] | T![...]))
             .filter_map(|it| it.into_node())
             .find_map(ast::Pat::cast)
     }
+
+    pub fn op_token(&self) -> Option<SyntaxToken> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| matches!(it.kind(), T![..] | T![..=] | T![...]))
+    }
+
+    pub fn op_kind(&self) -> Option<ast::RangeOp> {
+        match self.op_token()?.kind() {
+            T![..] => Some(ast::RangeOp::Exclusive),
+            T![..=] | T![...] => Some(ast::RangeOp::Inclusive),
+            _ => None,
+        }
+    }
 }
 
 impl ast::TokenTree {
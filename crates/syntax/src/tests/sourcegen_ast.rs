@@ -562,6 +562,7 @@ impl Field {
                     "," => "comma",
                     "|" => "pipe",
                     "~" => "tilde",
+                    "-" => "minus",
                     _ => name,
                 };
                 format_ident!("{}_token", name)
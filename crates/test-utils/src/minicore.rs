@@ -37,6 +37,7 @@
 //!     add:
 //!     as_ref: sized
 //!     drop:
+//!     size_of:
 
 pub mod marker {
     // region:sized
@@ -375,6 +376,14 @@ pub mod cmp {
         fn cmp(&self, other: &Self) -> Ordering;
     }
 
+    pub fn min<T: Ord>(v1: T, v2: T) -> T {
+        loop {}
+    }
+
+    pub fn max<T: Ord>(v1: T, v2: T) -> T {
+        loop {}
+    }
+
     pub enum Ordering {
         Less = -1,
         Equal = 0,
@@ -392,6 +401,18 @@ pub mod cmp {
 }
 // endregion:eq
 
+// region:size_of
+pub mod mem {
+    pub const fn size_of<T>() -> usize {
+        loop {}
+    }
+
+    pub const fn align_of<T>() -> usize {
+        loop {}
+    }
+}
+// endregion:size_of
+
 // region:fmt
 pub mod fmt {
     pub struct Error;
@@ -1,5 +1,8 @@
 //! Discovery of `cargo` & `rustc` executables.
-use std::{env, iter, path::PathBuf};
+use std::{
+    env, fs, iter,
+    path::{Path, PathBuf},
+};
 
 pub fn cargo() -> PathBuf {
     get_path_for_executable("cargo")
@@ -17,6 +20,36 @@ pub fn rustfmt() -> PathBuf {
     get_path_for_executable("rustfmt")
 }
 
+/// Returns the toolchain the project at `dir` is pinned to with a
+/// `rust-toolchain.toml` (or legacy `rust-toolchain`) file, looking in the
+/// parent directories just like rustup does.
+///
+/// This is only a hint for messages: `RUSTUP_TOOLCHAIN` and `rustup override`
+/// take precedence over the file, so commands are left to rustup to resolve by
+/// running them in `dir`.
+pub fn toolchain_override(dir: &Path) -> Option<String> {
+    let contents = dir.ancestors().find_map(|dir| {
+        ["rust-toolchain", "rust-toolchain.toml"]
+            .iter()
+            .find_map(|name| fs::read_to_string(dir.join(name)).ok())
+    })?;
+    parse_toolchain_file(&contents)
+}
+
+fn parse_toolchain_file(contents: &str) -> Option<String> {
+    let contents = contents.trim();
+    if !contents.contains(|c: char| c == '\n' || c == '=' || c == '[') {
+        // The legacy format, which is just the name of the toolchain.
+        return Some(contents.to_string()).filter(|it| !it.is_empty());
+    }
+    // Only `toolchain.channel` is of interest, no need for a TOML parser.
+    contents.lines().find_map(|line| {
+        let value = line.trim().strip_prefix("channel")?.trim_start().strip_prefix('=')?;
+        let value = value.split('#').next()?.trim().trim_matches(|c: char| c == '"' || c == '\'');
+        Some(value.to_string()).filter(|it| !it.is_empty())
+    })
+}
+
 /// Return a `PathBuf` to use for the given executable.
 ///
 /// E.g., `get_path_for_executable("cargo")` may return just `cargo` if that
@@ -64,3 +97,34 @@ fn probe(path: PathBuf) -> Option<PathBuf> {
     };
     iter::once(path).chain(with_extension).find(|it| it.is_file())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::parse_toolchain_file;
+
+    fn check(contents: &str, expected: Option<&str>) {
+        assert_eq!(parse_toolchain_file(contents).as_deref(), expected);
+    }
+
+    #[test]
+    fn legacy_format() {
+        check("nightly-2022-05-01\n", Some("nightly-2022-05-01"));
+        check("  stable  ", Some("stable"));
+        check("", None);
+    }
+
+    #[test]
+    fn toml_format() {
+        check(
+            r#"
+[toolchain]
+channel = "1.62.0"
+components = ["rust-src"]
+"#,
+            Some("1.62.0"),
+        );
+        check("[toolchain]\nchannel='nightly' # pinned for rustfmt\n", Some("nightly"));
+        check("[toolchain]\ncomponents = [\"rustfmt\"]\n", None);
+        check("[toolchain]\nchannel = \"\"\n", None);
+    }
+}
@@ -122,32 +122,7 @@ impl NotifyActor {
                             .paths
                             .into_iter()
                             .map(|path| AbsPathBuf::try_from(path).unwrap())
-                            .filter_map(|path| {
-                                let meta = fs::metadata(&path).ok()?;
-                                if meta.file_type().is_dir()
-                                    && self
-                                        .watched_entries
-                                        .iter()
-                                        .any(|entry| entry.contains_dir(&path))
-                                {
-                                    self.watch(path);
-                                    return None;
-                                }
-
-                                if !meta.file_type().is_file() {
-                                    return None;
-                                }
-                                if !self
-                                    .watched_entries
-                                    .iter()
-                                    .any(|entry| entry.contains_file(&path))
-                                {
-                                    return None;
-                                }
-
-                                let contents = read(&path);
-                                Some((path, contents))
-                            })
+                            .flat_map(|path| self.changed_files(path))
                             .collect();
                         self.send(loader::Message::Loaded { files });
                     }
@@ -155,6 +130,53 @@ impl NotifyActor {
             }
         }
     }
+    /// Returns the watched files affected by a change of `path`.
+    fn changed_files(&mut self, path: AbsPathBuf) -> Vec<(AbsPathBuf, Option<Vec<u8>>)> {
+        let meta = match fs::metadata(&path) {
+            Ok(it) => it,
+            // The file is gone, let the vfs know it was removed.
+            Err(_) if self.is_watched_file(&path) => return vec![(path, None)],
+            Err(_) => return Vec::new(),
+        };
+        if meta.file_type().is_dir() {
+            if !self.watched_entries.iter().any(|entry| entry.contains_dir(&path)) {
+                return Vec::new();
+            }
+            // Files may have been created in the new directory before we started watching it.
+            let (dirs, files): (Vec<_>, Vec<_>) = WalkDir::new(&path)
+                .follow_links(true)
+                .into_iter()
+                .filter_entry(|entry| {
+                    !entry.file_type().is_dir()
+                        || self
+                            .watched_entries
+                            .iter()
+                            .any(|it| it.contains_dir(AbsPath::assert(entry.path())))
+                })
+                .filter_map(|it| it.ok())
+                .partition(|entry| entry.file_type().is_dir());
+            for dir in dirs {
+                self.watch(AbsPathBuf::assert(dir.into_path()));
+            }
+            return files
+                .into_iter()
+                .map(|entry| AbsPathBuf::assert(entry.into_path()))
+                .filter(|path| self.is_watched_file(path))
+                .map(|path| {
+                    let contents = read(&path);
+                    (path, contents)
+                })
+                .collect();
+        }
+        if !meta.file_type().is_file() || !self.is_watched_file(&path) {
+            return Vec::new();
+        }
+        let contents = read(&path);
+        vec![(path, contents)]
+    }
+    fn is_watched_file(&self, path: &AbsPath) -> bool {
+        self.watched_entries.iter().any(|entry| entry.contains_file(path))
+    }
     fn load_entry(
         &mut self,
         entry: loader::Entry,
@@ -182,7 +204,8 @@ impl NotifyActor {
                             }
                             let path = AbsPath::assert(entry.path());
                             root == path
-                                || dirs.exclude.iter().chain(&dirs.include).all(|it| it != path)
+                                || (dirs.exclude.iter().chain(&dirs.include).all(|it| it != path)
+                                    && !dirs.matches_exclude_glob(path))
                         });
 
                     let files = walkdir.filter_map(|it| it.ok()).filter_map(|entry| {
@@ -196,7 +219,9 @@ impl NotifyActor {
                             return None;
                         }
                         let ext = abs_path.extension().unwrap_or_default();
-                        if dirs.extensions.iter().all(|it| it.as_str() != ext) {
+                        if dirs.extensions.iter().all(|it| it.as_str() != ext)
+                            || dirs.matches_exclude_glob(&abs_path)
+                        {
                             return None;
                         }
                         Some(abs_path)
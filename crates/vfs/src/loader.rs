@@ -1,5 +1,5 @@
 //! Object safe interface for file watching and reading.
-use std::fmt;
+use std::{fmt, path::Path};
 
 use paths::{AbsPath, AbsPathBuf};
 
@@ -18,6 +18,8 @@ pub enum Entry {
 ///   * it has included extension
 ///   * it is under an `include` path
 ///   * it is not under `exclude` path
+///   * neither it nor one of its directories below the `include` path matches
+///     one of `exclude_globs`
 ///
 /// If many include/exclude paths match, the longest one wins.
 ///
@@ -27,6 +29,17 @@ pub struct Directories {
     pub extensions: Vec<String>,
     pub include: Vec<AbsPathBuf>,
     pub exclude: Vec<AbsPathBuf>,
+    pub exclude_globs: Vec<Glob>,
+}
+
+/// A glob pattern matched against absolute paths.
+///
+/// Within a path component, `*` matches any sequence of characters, `?` any single
+/// character and `[...]` (or `[!...]`) a character (not) in the set, which may contain
+/// ranges like `a-z`. A `**` component matches any number of components.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    components: Vec<Vec<char>>,
 }
 
 /// [`Handle`]'s configuration.
@@ -177,9 +190,99 @@ impl Directories {
         };
 
         !self.exclude.iter().any(|excl| path.starts_with(excl) && excl.starts_with(include))
+            && !Path::ancestors(path.as_ref())
+                .take_while(|it| it.starts_with(include))
+                .any(|it| self.matches_exclude_glob(AbsPath::assert(it)))
+    }
+
+    /// Returns `true` if `path` itself matches one of `self.exclude_globs`.
+    ///
+    /// Unlike [`Directories::contains_file`], this doesn't look at the directories
+    /// containing `path`, which is what a directory walk that already skipped the
+    /// excluded directories needs.
+    pub fn matches_exclude_glob(&self, path: &AbsPath) -> bool {
+        self.exclude_globs.iter().any(|glob| glob.matches(path))
     }
 }
 
+impl Glob {
+    /// Returns `true` if `pattern` uses glob syntax, rather than being a plain path.
+    pub fn is_glob(pattern: &str) -> bool {
+        pattern.chars().any(|c| matches!(c, '*' | '?' | '['))
+    }
+
+    pub fn new(pattern: &AbsPath) -> Glob {
+        let components = Path::components(pattern.as_ref())
+            .map(|it| it.as_os_str().to_string_lossy().chars().collect())
+            .collect();
+        Glob { components }
+    }
+
+    pub fn matches(&self, path: &AbsPath) -> bool {
+        let path: Vec<Vec<char>> = Path::components(path.as_ref())
+            .map(|it| it.as_os_str().to_string_lossy().chars().collect())
+            .collect();
+        match_components(&self.components, &path)
+    }
+}
+
+fn match_components(pattern: &[Vec<char>], path: &[Vec<char>]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first[..] == ['*', '*'] => {
+            (0..=path.len()).any(|skip| match_components(rest, &path[skip..]))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((head, tail)) => match_component(first, head) && match_components(rest, tail),
+            None => false,
+        },
+    }
+}
+
+fn match_component(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| match_component(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && match_component(rest, &name[1..]),
+        // `]` right after the opening bracket is part of the set.
+        Some(('[', rest)) => match rest.iter().skip(1).position(|&c| c == ']') {
+            Some(close) => {
+                let (class, rest) = (&rest[..close + 1], &rest[close + 2..]);
+                let (negated, class) = match class.split_first() {
+                    Some(('!', class)) => (true, class),
+                    _ => (false, class),
+                };
+                match name.split_first() {
+                    Some((&c, tail)) => {
+                        class_contains(class, c) != negated && match_component(rest, tail)
+                    }
+                    None => false,
+                }
+            }
+            None => name.first() == Some(&'[') && match_component(rest, &name[1..]),
+        },
+        Some((&c, rest)) => name.first() == Some(&c) && match_component(rest, &name[1..]),
+    }
+}
+
+fn class_contains(class: &[char], c: char) -> bool {
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            if class[i] <= c && c <= class[i + 2] {
+                return true;
+            }
+            i += 3;
+        } else {
+            if class[i] == c {
+                return true;
+            }
+            i += 1;
+        }
+    }
+    false
+}
+
 /// Returns :
 /// ```text
 /// Directories {
@@ -190,7 +293,12 @@ impl Directories {
 /// ```
 fn dirs(base: AbsPathBuf, exclude: &[&str]) -> Directories {
     let exclude = exclude.iter().map(|it| base.join(it)).collect::<Vec<_>>();
-    Directories { extensions: vec!["rs".to_string()], include: vec![base], exclude }
+    Directories {
+        extensions: vec!["rs".to_string()],
+        include: vec![base],
+        exclude,
+        exclude_globs: Vec::new(),
+    }
 }
 
 impl fmt::Debug for Message {
@@ -213,3 +321,33 @@ impl fmt::Debug for Message {
 fn handle_is_object_safe() {
     fn _assert(_: &dyn Handle) {}
 }
+
+#[test]
+#[cfg(unix)]
+fn glob_matching() {
+    fn check(pattern: &str, path: &str) -> bool {
+        let pattern = AbsPathBuf::assert(pattern.into());
+        Glob::new(&pattern).matches(AbsPath::assert(Path::new(path)))
+    }
+
+    assert!(check("/ws/**/generated", "/ws/generated"));
+    assert!(check("/ws/**/generated", "/ws/a/b/generated"));
+    assert!(!check("/ws/**/generated", "/ws/a/generated/x.rs"));
+    assert!(check("/ws/**/*.pb.rs", "/ws/proto/foo.pb.rs"));
+    assert!(!check("/ws/*.pb.rs", "/ws/proto/foo.pb.rs"));
+    assert!(check("/ws/gen-?/[a-c]*.rs", "/ws/gen-1/bar.rs"));
+    assert!(!check("/ws/gen-?/[!a-c]*.rs", "/ws/gen-1/bar.rs"));
+    assert!(check("/ws/[]x]", "/ws/]"));
+}
+
+#[test]
+#[cfg(unix)]
+fn exclude_globs_apply_to_directories() {
+    let base = AbsPathBuf::assert("/ws".into());
+    let mut dirs = dirs(base, &[]);
+    dirs.exclude_globs.push(Glob::new(&AbsPathBuf::assert("/ws/**/generated".into())));
+
+    assert!(dirs.contains_file(AbsPath::assert(Path::new("/ws/src/lib.rs"))));
+    assert!(!dirs.contains_file(AbsPath::assert(Path::new("/ws/src/generated/mod.rs"))));
+    assert!(!dirs.contains_dir(AbsPath::assert(Path::new("/ws/src/generated"))));
+}
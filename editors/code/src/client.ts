@@ -265,6 +265,7 @@ class ExperimentalFeatures implements lc.StaticFeature {
     fillClientCapabilities(capabilities: lc.ClientCapabilities): void {
         const caps: any = capabilities.experimental ?? {};
         caps.snippetTextEdit = true;
+        caps.multiFileSnippetTextEdit = true;
         caps.codeActionGroup = true;
         caps.hoverActions = true;
         caps.serverStatusNotification = true;
@@ -275,6 +276,9 @@ class ExperimentalFeatures implements lc.StaticFeature {
                 "rust-analyzer.showReferences",
                 "rust-analyzer.gotoLocation",
                 "editor.action.triggerParameterHints",
+                "rust-analyzer.reloadWorkspace",
+                "rust-analyzer.rebuildProcMacros",
+                "rust-analyzer.installRustSrc",
             ],
         };
         capabilities.experimental = caps;
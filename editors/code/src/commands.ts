@@ -412,12 +412,15 @@ export function syntaxTree(ctx: Ctx): Cmd {
     };
 }
 
-// Opens the virtual file that will show the HIR of the function containing the cursor position
-//
-// The contents of the file come from the `TextDocumentContentProvider`
-export function viewHir(ctx: Ctx): Cmd {
+// Opens a virtual file showing the response of a request about the function containing the
+// cursor position, which is updated as the cursor moves.
+function viewForCursorPosition(
+    ctx: Ctx,
+    uri: string,
+    requestType: lc.RequestType<lc.TextDocumentPositionParams, string, void>
+): Cmd {
     const tdcp = new (class implements vscode.TextDocumentContentProvider {
-        readonly uri = vscode.Uri.parse("rust-analyzer://viewHir/hir.txt");
+        readonly uri = vscode.Uri.parse(uri);
         readonly eventEmitter = new vscode.EventEmitter<vscode.Uri>();
         constructor() {
             vscode.workspace.onDidChangeTextDocument(
@@ -459,7 +462,7 @@ export function viewHir(ctx: Ctx): Cmd {
                 ),
                 position: client.code2ProtocolConverter.asPosition(rustEditor.selection.active),
             };
-            return client.sendRequest(ra.viewHir, params, ct);
+            return client.sendRequest(requestType, params, ct);
         }
 
         get onDidChange(): vscode.Event<vscode.Uri> {
@@ -479,6 +482,45 @@ export function viewHir(ctx: Ctx): Cmd {
     };
 }
 
+// Opens the virtual file that will show the HIR of the function containing the cursor position
+//
+// The contents of the file come from the `TextDocumentContentProvider`
+export function viewHir(ctx: Ctx): Cmd {
+    return viewForCursorPosition(ctx, "rust-analyzer://viewHir/hir.txt", ra.viewHir);
+}
+
+// Opens the virtual file that will show the MIR of the function containing the cursor position
+//
+// The contents of the file come from the `TextDocumentContentProvider`
+export function viewMir(ctx: Ctx): Cmd {
+    return viewForCursorPosition(ctx, "rust-analyzer://viewMir/mir.txt", ra.viewMir);
+}
+
+// Opens the virtual file that will show the outcome of running the function containing the cursor
+// position in the MIR interpreter
+export function interpretFunction(ctx: Ctx): Cmd {
+    return viewForCursorPosition(
+        ctx,
+        "rust-analyzer://interpretFunction/result.log",
+        ra.interpretFunction
+    );
+}
+
+// Shows the value of the selected expression, or of the innermost expression at the cursor
+export function evaluateExpression(ctx: Ctx): Cmd {
+    return async () => {
+        const editor = ctx.activeRustEditor;
+        const client = ctx.client;
+        if (!editor || !client) return;
+
+        const result = await client.sendRequest(ra.evaluateExpression, {
+            textDocument: client.code2ProtocolConverter.asTextDocumentIdentifier(editor.document),
+            range: client.code2ProtocolConverter.asRange(editor.selection),
+        });
+        void vscode.window.showInformationMessage(result);
+    };
+}
+
 export function viewFileText(ctx: Ctx): Cmd {
     const tdcp = new (class implements vscode.TextDocumentContentProvider {
         readonly uri = vscode.Uri.parse("rust-analyzer://viewFileText/file.rs");
@@ -603,8 +645,17 @@ export function viewItemTree(ctx: Ctx): Cmd {
     };
 }
 
-function crateGraph(ctx: Ctx, full: boolean): Cmd {
+function crateGraph(ctx: Ctx, full: boolean, currentCrateOnly: boolean = false): Cmd {
     return async () => {
+        const params: ra.ViewCrateGraphParams = { full };
+        if (currentCrateOnly) {
+            const editor = ctx.activeRustEditor;
+            if (!editor) return;
+            params.reachableFrom = ctx.client.code2ProtocolConverter.asTextDocumentIdentifier(
+                editor.document
+            );
+        }
+
         const nodeModulesPath = vscode.Uri.file(path.join(ctx.extensionPath, "node_modules"));
 
         const panel = vscode.window.createWebviewPanel(
@@ -617,10 +668,6 @@ function crateGraph(ctx: Ctx, full: boolean): Cmd {
                 localResourceRoots: [nodeModulesPath],
             }
         );
-        const params = {
-            full: full,
-        };
-
         const dot = await ctx.client.sendRequest(ra.viewCrateGraph, params);
         const uri = panel.webview.asWebviewUri(nodeModulesPath);
 
@@ -674,6 +721,23 @@ export function viewFullCrateGraph(ctx: Ctx): Cmd {
     return crateGraph(ctx, true);
 }
 
+export function viewCurrentCrateGraph(ctx: Ctx): Cmd {
+    return crateGraph(ctx, false, true);
+}
+
+// Opens the workspace crate graph as a Mermaid diagram in a new markdown document
+export function viewCrateGraphMermaid(ctx: Ctx): Cmd {
+    return async () => {
+        const params: ra.ViewCrateGraphParams = { full: false, format: "mermaid" };
+        const mermaid = await ctx.client.sendRequest(ra.viewCrateGraph, params);
+        const document = await vscode.workspace.openTextDocument({
+            language: "markdown",
+            content: "```mermaid\n" + mermaid + "```\n",
+        });
+        await vscode.window.showTextDocument(document, vscode.ViewColumn.Two);
+    };
+}
+
 // Opens the virtual file that will show the syntax tree
 //
 // The contents of the file come from the `TextDocumentContentProvider`
@@ -723,10 +787,167 @@ export function expandMacro(ctx: Ctx): Cmd {
     };
 }
 
+// Expands the macro at the cursor a single level. Running the command again with the cursor on a
+// macro call in the shown expansion expands that call next.
+export function expandMacroStep(ctx: Ctx): Cmd {
+    let request: ra.ExpandMacroParams | undefined;
+    let expanded: ra.ExpandedMacro | null = null;
+    // The lines preceding the expansion in the shown document
+    let headerLines = 0;
+
+    const tdcp = new (class implements vscode.TextDocumentContentProvider {
+        uri = vscode.Uri.parse("rust-analyzer://expandMacroStep/[EXPANSION].rs");
+        eventEmitter = new vscode.EventEmitter<vscode.Uri>();
+        async provideTextDocumentContent(_uri: vscode.Uri): Promise<string> {
+            if (!request) return "";
+
+            expanded = await ctx.client.sendRequest(ra.expandMacro, request);
+            if (expanded == null) return "Not available";
+
+            let result = `// Expansion of ${expanded.name}! macro, one step at a time\n`;
+            result += "// " + "=".repeat(result.length - 3) + "\n";
+            for (const sub of expanded.subInvocations ?? []) {
+                const origin = sub.originalLocation
+                    ? `, written at line ${sub.originalLocation.range.start.line + 1}`
+                    : "";
+                result += `// ${sub.name}! at line ${sub.range.start.line + 1}${origin}\n`;
+            }
+            result += "\n";
+            headerLines = result.split("\n").length - 1;
+            return result + expanded.expansion;
+        }
+
+        get onDidChange(): vscode.Event<vscode.Uri> {
+            return this.eventEmitter.event;
+        }
+    })();
+
+    ctx.pushCleanup(vscode.workspace.registerTextDocumentContentProvider("rust-analyzer", tdcp));
+
+    return async () => {
+        const editor = vscode.window.activeTextEditor;
+        if (editor?.document.uri.toString() === tdcp.uri.toString()) {
+            if (!request || !expanded) return;
+            const cursor = editor.selection.active.translate(-headerLines);
+            const sub = expanded.subInvocations?.find((it) =>
+                ctx.client.protocol2CodeConverter.asRange(it.range).contains(cursor)
+            );
+            if (!sub) {
+                await vscode.window.showInformationMessage("No macro call at the cursor");
+                return;
+            }
+            request = { ...request, invocationPath: sub.invocationPath };
+        } else {
+            const rustEditor = ctx.activeRustEditor;
+            if (!rustEditor) return;
+            request = {
+                textDocument: ctx.client.code2ProtocolConverter.asTextDocumentIdentifier(
+                    rustEditor.document
+                ),
+                position: rustEditor.selection.active,
+                singleStep: true,
+                invocationPath: [],
+            };
+        }
+
+        const document = await vscode.workspace.openTextDocument(tdcp.uri);
+        tdcp.eventEmitter.fire(tdcp.uri);
+        return vscode.window.showTextDocument(document, vscode.ViewColumn.Two, true);
+    };
+}
+
+// Expands the proc-macro at the cursor again with `RUST_BACKTRACE=full` and shows the panic
+// message with the full backtrace, to be attached to a bug report to the macro's author.
+export function rerunProcMacroWithBacktrace(ctx: Ctx): Cmd {
+    const tdcp = new (class implements vscode.TextDocumentContentProvider {
+        uri = vscode.Uri.parse("rust-analyzer-proc-macro-backtrace://backtrace/[BACKTRACE].txt");
+        eventEmitter = new vscode.EventEmitter<vscode.Uri>();
+        async provideTextDocumentContent(_uri: vscode.Uri): Promise<string> {
+            const editor = vscode.window.activeTextEditor;
+            const client = ctx.client;
+            if (!editor || !client) return "";
+
+            const res = await client.sendRequest(ra.rerunProcMacroWithBacktrace, {
+                textDocument: client.code2ProtocolConverter.asTextDocumentIdentifier(
+                    editor.document
+                ),
+                position: editor.selection.active,
+            });
+
+            if (res == null) return "No proc-macro call at the cursor";
+            if (res.error == null) return `${res.name} expanded without errors this time`;
+            return `Expansion of ${res.name} failed:\n\n${res.error}\n`;
+        }
+
+        get onDidChange(): vscode.Event<vscode.Uri> {
+            return this.eventEmitter.event;
+        }
+    })();
+
+    ctx.pushCleanup(
+        vscode.workspace.registerTextDocumentContentProvider(
+            "rust-analyzer-proc-macro-backtrace",
+            tdcp
+        )
+    );
+
+    return async () => {
+        const document = await vscode.workspace.openTextDocument(tdcp.uri);
+        tdcp.eventEmitter.fire(tdcp.uri);
+        return vscode.window.showTextDocument(document, vscode.ViewColumn.Two, true);
+    };
+}
+
 export function reloadWorkspace(ctx: Ctx): Cmd {
     return async () => ctx.client.sendRequest(ra.reloadWorkspace);
 }
 
+export function rebuildProcMacros(ctx: Ctx): Cmd {
+    return async () => ctx.client.sendRequest(ra.rebuildProcMacros);
+}
+
+export function cancelFlycheck(ctx: Ctx): Cmd {
+    return async () => ctx.client.sendNotification(ra.cancelFlycheck);
+}
+
+export function switchTarget(ctx: Ctx): Cmd {
+    return async () => {
+        const editor = ctx.activeRustEditor;
+        const client = ctx.client;
+        if (!editor || !client) return;
+
+        const extraTargets = ctx.config.extraTargets;
+        if (extraTargets.length === 0) {
+            void vscode.window.showInformationMessage(
+                "Set `rust-analyzer.cargo.extraTargets` to analyze the workspace for further targets."
+            );
+            return;
+        }
+        const items = [
+            { label: "Default target", target: null },
+            ...extraTargets.map((target) => ({ label: target, target })),
+        ];
+        const selected = await vscode.window.showQuickPick(items, {
+            placeHolder: "Target to analyze this file for",
+        });
+        if (!selected) return;
+
+        await client.sendRequest(ra.setActiveTarget, {
+            textDocument: client.code2ProtocolConverter.asTextDocumentIdentifier(editor.document),
+            target: selected.target,
+        });
+    };
+}
+
+export function installRustSrc(_ctx: Ctx): Cmd {
+    return async (toolchain?: string) => {
+        const terminal = vscode.window.createTerminal("rustup");
+        terminal.show();
+        const toolchainArg = toolchain ? ` --toolchain ${toolchain}` : "";
+        terminal.sendText(`rustup component add rust-src${toolchainArg}`);
+    };
+}
+
 async function showReferencesImpl(
     client: LanguageClient,
     uri: string,
@@ -896,10 +1117,13 @@ export function copyRunCommandLine(ctx: Ctx) {
     return async () => {
         const item = await selectRunnable(ctx, prevRunnable);
         if (!item) return;
-        const args = createArgs(item.runnable);
-        const commandLine = ["cargo", ...args].join(" ");
+        const runnable = item.runnable;
+        const commandLine =
+            runnable.kind === "shell"
+                ? [runnable.args.program, ...runnable.args.args].join(" ")
+                : ["cargo", ...createArgs(runnable)].join(" ");
         await vscode.env.clipboard.writeText(commandLine);
-        await vscode.window.showInformationMessage("Cargo invocation copied to the clipboard.");
+        await vscode.window.showInformationMessage("Command line copied to the clipboard.");
     };
 }
 
@@ -16,14 +16,10 @@ export class Config {
     readonly extensionId = "rust-lang.rust-analyzer";
 
     readonly rootSection = "rust-analyzer";
-    private readonly requiresReloadOpts = [
-        "serverPath",
-        "server",
-        "cargo",
-        "procMacro",
-        "files",
-        "lens", // works as lens.*
-    ].map((opt) => `${this.rootSection}.${opt}`);
+    // The server applies all of its own settings on the fly.
+    private readonly requiresReloadOpts = ["serverPath", "server"].map(
+        (opt) => `${this.rootSection}.${opt}`
+    );
 
     readonly package: {
         version: string;
@@ -119,6 +115,10 @@ export class Config {
         return this.get<RunnableEnvCfg>("runnableEnv");
     }
 
+    get extraTargets() {
+        return this.get<string[]>("cargo.extraTargets");
+    }
+
     get debug() {
         let sourceFileMap = this.get<Record<string, string> | "auto">("debug.sourceFileMap");
         if (sourceFileMap !== "auto") {
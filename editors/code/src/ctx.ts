@@ -45,6 +45,13 @@ export class Ctx {
         res.pushCleanup(client.start());
         await client.onReady();
         client.onNotification(ra.serverStatus, (params) => res.setServerStatus(params));
+        client.onNotification(ra.serverTrace, (params) =>
+            client.traceOutputChannel.appendLine(
+                `[serverTrace] ${params.kind} ${params.name}` +
+                    (params.id !== undefined ? ` (${params.id})` : "") +
+                    ` in ${params.durationMs}ms`
+            )
+        );
         return res;
     }
 
@@ -78,14 +85,18 @@ export class Ctx {
 
     setServerStatus(status: ServerStatusParams) {
         this.statusBar.tooltip = status.message ?? "Ready";
+        // Clicking the status bar applies the fix of the most severe problem, if any.
+        const fix = status.problems
+            ?.filter((problem) => problem.health === status.health)
+            .find((problem) => problem.fix)?.fix;
         let icon = "";
         switch (status.health) {
             case "ok":
                 this.statusBar.color = undefined;
                 break;
             case "warning":
-                this.statusBar.tooltip += "\nClick to reload.";
-                this.statusBar.command = "rust-analyzer.reloadWorkspace";
+                this.statusBar.tooltip += `\nClick to ${fix?.title.toLowerCase() ?? "reload"}.`;
+                this.statusBar.command = fix ?? "rust-analyzer.reloadWorkspace";
                 this.statusBar.color = new vscode.ThemeColor("statusBarItem.warningForeground");
                 this.statusBar.backgroundColor = new vscode.ThemeColor(
                     "statusBarItem.warningBackground"
@@ -93,8 +104,8 @@ export class Ctx {
                 icon = "$(warning) ";
                 break;
             case "error":
-                this.statusBar.tooltip += "\nClick to reload.";
-                this.statusBar.command = "rust-analyzer.reloadWorkspace";
+                this.statusBar.tooltip += `\nClick to ${fix?.title.toLowerCase() ?? "reload"}.`;
+                this.statusBar.command = fix ?? "rust-analyzer.reloadWorkspace";
                 this.statusBar.color = new vscode.ThemeColor("statusBarItem.errorForeground");
                 this.statusBar.backgroundColor = new vscode.ThemeColor(
                     "statusBarItem.errorBackground"
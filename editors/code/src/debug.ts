@@ -3,13 +3,13 @@ import * as vscode from "vscode";
 import * as path from "path";
 import * as ra from "./lsp_ext";
 
-import { Cargo, getRustcId, getSysroot } from "./toolchain";
 import { Ctx } from "./ctx";
 import { prepareEnv } from "./run";
 
 const debugOutput = vscode.window.createOutputChannel("Debug");
 type DebugConfigProvider = (
     config: ra.Runnable,
+    launch: ra.DebugLaunchConfig,
     executable: string,
     env: Record<string, string>,
     sourceFileMap?: Record<string, string>
@@ -105,11 +105,12 @@ async function getDebugConfiguration(
     const workspaceFolders = vscode.workspace.workspaceFolders!;
     const isMultiFolderWorkspace = workspaceFolders.length > 1;
     const firstWorkspace = workspaceFolders[0];
+    const workspaceRoot =
+        runnable.kind === "cargo" ? runnable.args.workspaceRoot : runnable.args.cwd;
     const workspace =
-        !isMultiFolderWorkspace || !runnable.args.workspaceRoot
+        !isMultiFolderWorkspace || !workspaceRoot
             ? firstWorkspace
-            : workspaceFolders.find((w) => runnable.args.workspaceRoot?.includes(w.uri.fsPath)) ||
-              firstWorkspace;
+            : workspaceFolders.find((w) => workspaceRoot.includes(w.uri.fsPath)) || firstWorkspace;
 
     const wsFolder = path.normalize(workspace.uri.fsPath);
     const workspaceQualifier = isMultiFolderWorkspace ? `:${workspace.name}` : "";
@@ -118,20 +119,25 @@ async function getDebugConfiguration(
         return path.normalize(p).replace(wsFolder, "${workspaceFolder" + workspaceQualifier + "}");
     }
 
-    const executable = await getDebugExecutable(runnable);
-    const env = prepareEnv(runnable, ctx.config.runnableEnv);
+    // The server builds the runnable and tells us how to launch the result.
+    let launch: ra.DebugLaunchConfig;
+    try {
+        launch = await ctx.client.sendRequest(ra.debugLaunch, { runnable });
+    } catch (e) {
+        debugOutput.append(String(e instanceof Error ? e.message : e));
+        await vscode.window.showErrorMessage(`Failed to build ${runnable.label} for debugging`);
+        return;
+    }
+    const executable = launch.program;
+    const env = { ...launch.env, ...prepareEnv(runnable, ctx.config.runnableEnv) };
     let sourceFileMap = debugOptions.sourceFileMap;
     if (sourceFileMap === "auto") {
-        // let's try to use the default toolchain
-        const commitHash = await getRustcId(wsFolder);
-        const sysroot = await getSysroot(wsFolder);
-        const rustlib = path.normalize(sysroot + "/lib/rustlib/src/rust");
-        sourceFileMap = {};
-        sourceFileMap[`/rustc/${commitHash}/`] = rustlib;
+        sourceFileMap = launch.sourceMap;
     }
 
     const debugConfig = knownEngines[debugEngine.id](
         runnable,
+        launch,
         simplifyPath(executable),
         env,
         sourceFileMap
@@ -156,16 +162,9 @@ async function getDebugConfiguration(
     return debugConfig;
 }
 
-async function getDebugExecutable(runnable: ra.Runnable): Promise<string> {
-    const cargo = new Cargo(runnable.args.workspaceRoot || ".", debugOutput);
-    const executable = await cargo.executableFromArgs(runnable.args.cargoArgs);
-
-    // if we are here, there were no compilation errors.
-    return executable;
-}
-
 function getLldbDebugConfig(
     runnable: ra.Runnable,
+    launch: ra.DebugLaunchConfig,
     executable: string,
     env: Record<string, string>,
     sourceFileMap?: Record<string, string>
@@ -175,8 +174,8 @@ function getLldbDebugConfig(
         request: "launch",
         name: runnable.label,
         program: executable,
-        args: runnable.args.executableArgs,
-        cwd: runnable.args.workspaceRoot,
+        args: launch.args,
+        cwd: launch.cwd,
         sourceMap: sourceFileMap,
         sourceLanguages: ["rust"],
         env,
@@ -185,6 +184,7 @@ function getLldbDebugConfig(
 
 function getCppvsDebugConfig(
     runnable: ra.Runnable,
+    launch: ra.DebugLaunchConfig,
     executable: string,
     env: Record<string, string>,
     sourceFileMap?: Record<string, string>
@@ -194,8 +194,8 @@ function getCppvsDebugConfig(
         request: "launch",
         name: runnable.label,
         program: executable,
-        args: runnable.args.executableArgs,
-        cwd: runnable.args.workspaceRoot,
+        args: launch.args,
+        cwd: launch.cwd,
         sourceFileMap,
         env,
     };
@@ -17,12 +17,47 @@ export interface ServerStatusParams {
     health: "ok" | "warning" | "error";
     quiescent: boolean;
     message?: string;
+    problems?: ServerProblem[];
+}
+export interface ServerProblem {
+    kind:
+        | "workspaceLoadFailed"
+        | "missingRustSrc"
+        | "workspaceReloadRequired"
+        | "buildScriptsFailed"
+        | "procMacroChanged"
+        | "procMacroLoadFailed"
+        | "unlinkedFile";
+    health: "warning" | "error";
+    message: string;
+    fix?: lc.Command;
 }
 export const serverStatus = new lc.NotificationType<ServerStatusParams>(
     "experimental/serverStatus"
 );
 
+export interface ServerTraceParams {
+    kind: "request" | "loopTurn";
+    name: string;
+    id?: string;
+    durationMs: number;
+}
+export const serverTrace = new lc.NotificationType<ServerTraceParams>("rust-analyzer/serverTrace");
+
 export const reloadWorkspace = new lc.RequestType0<null, void>("rust-analyzer/reloadWorkspace");
+export const rebuildProcMacros = new lc.RequestType0<null, void>("rust-analyzer/rebuildProcMacros");
+export const cancelFlycheck = new lc.NotificationType0("rust-analyzer/cancelFlycheck");
+export interface SetActiveTargetParams {
+    textDocument: lc.TextDocumentIdentifier;
+    target: string | null;
+}
+export const setActiveTarget = new lc.RequestType<SetActiveTargetParams, void, void>(
+    "rust-analyzer/setActiveTarget"
+);
+export interface PrimeCachesParams {
+    textDocument?: lc.TextDocumentIdentifier;
+}
+export const primeCaches = new lc.RequestType<PrimeCachesParams, void, void>("rust-analyzer/primeCaches");
 
 export const hover = new lc.RequestType<HoverParams, lc.Hover | null, void>("textDocument/hover");
 
@@ -43,6 +78,22 @@ export const viewHir = new lc.RequestType<lc.TextDocumentPositionParams, string,
     "rust-analyzer/viewHir"
 );
 
+export const viewMir = new lc.RequestType<lc.TextDocumentPositionParams, string, void>(
+    "rust-analyzer/viewMir"
+);
+
+export const interpretFunction = new lc.RequestType<lc.TextDocumentPositionParams, string, void>(
+    "rust-analyzer/interpretFunction"
+);
+
+export interface EvaluateExpressionParams {
+    textDocument: lc.TextDocumentIdentifier;
+    range: lc.Range;
+}
+export const evaluateExpression = new lc.RequestType<EvaluateExpressionParams, string, void>(
+    "rust-analyzer/evaluateExpression"
+);
+
 export const viewFileText = new lc.RequestType<lc.TextDocumentIdentifier, string, void>(
     "rust-analyzer/viewFileText"
 );
@@ -57,23 +108,69 @@ export const viewItemTree = new lc.RequestType<ViewItemTreeParams, string, void>
 
 export interface ViewCrateGraphParams {
     full: boolean;
+    reachableFrom?: lc.TextDocumentIdentifier;
+    excludeDevDependencies?: boolean;
+    format?: "dot" | "mermaid";
 }
 
 export const viewCrateGraph = new lc.RequestType<ViewCrateGraphParams, string, void>(
     "rust-analyzer/viewCrateGraph"
 );
 
+export interface DependencyTreeParams {
+    textDocument: lc.TextDocumentIdentifier;
+}
+export interface DependencyEdge {
+    name: string;
+    crate: number;
+    isDev: boolean;
+}
+export interface DependencyCrate {
+    name?: string;
+    version?: string;
+    rootFile: string;
+    features: string[];
+    isWorkspaceMember: boolean;
+    dependencies: DependencyEdge[];
+}
+export interface CrateDependencyTree {
+    roots: number[];
+    crates: DependencyCrate[];
+}
+
+export const dependencyTree = new lc.RequestType<DependencyTreeParams, CrateDependencyTree, void>(
+    "rust-analyzer/dependencyTree"
+);
+
 export interface ExpandMacroParams {
     textDocument: lc.TextDocumentIdentifier;
     position: lc.Position;
+    singleStep?: boolean;
+    invocationPath?: number[];
 }
 export interface ExpandedMacro {
     name: string;
     expansion: string;
+    subInvocations?: MacroSubInvocation[];
+}
+export interface MacroSubInvocation {
+    name: string;
+    invocationPath: number[];
+    range: lc.Range;
+    originalLocation: lc.Location | null;
 }
 export const expandMacro = new lc.RequestType<ExpandMacroParams, ExpandedMacro | null, void>(
     "rust-analyzer/expandMacro"
 );
+export interface ProcMacroBacktrace {
+    name: string;
+    error: string | null;
+}
+export const rerunProcMacroWithBacktrace = new lc.RequestType<
+    lc.TextDocumentPositionParams,
+    ProcMacroBacktrace | null,
+    void
+>("rust-analyzer/rerunProcMacroWithBacktrace");
 
 export interface MatchingBraceParams {
     textDocument: lc.TextDocumentIdentifier;
@@ -106,7 +203,8 @@ export interface RunnablesParams {
     position: lc.Position | null;
 }
 
-export interface Runnable {
+export type Runnable = CargoRunnable | ShellRunnable;
+export interface CargoRunnable {
     label: string;
     location?: lc.LocationLink;
     kind: "cargo";
@@ -119,6 +217,16 @@ export interface Runnable {
         overrideCargo?: string;
     };
 }
+export interface ShellRunnable {
+    label: string;
+    location?: lc.LocationLink;
+    kind: "shell";
+    args: {
+        program: string;
+        args: string[];
+        cwd: string;
+    };
+}
 export const runnables = new lc.RequestType<RunnablesParams, Runnable[], void>(
     "experimental/runnables"
 );
@@ -131,6 +239,20 @@ export const relatedTests = new lc.RequestType<lc.TextDocumentPositionParams, Te
     "rust-analyzer/relatedTests"
 );
 
+export interface DebugLaunchParams {
+    runnable: Runnable;
+}
+export interface DebugLaunchConfig {
+    program: string;
+    args: string[];
+    cwd: string;
+    env: Record<string, string>;
+    sourceMap: Record<string, string>;
+}
+export const debugLaunch = new lc.RequestType<DebugLaunchParams, DebugLaunchConfig, void>(
+    "rust-analyzer/debugLaunch"
+);
+
 export interface SsrParams {
     query: string;
     parseOnly: boolean;
@@ -125,16 +125,27 @@ async function initCommonContext(context: vscode.ExtensionContext, ctx: Ctx) {
     ctx.registerCommand("memoryUsage", commands.memoryUsage);
     ctx.registerCommand("shuffleCrateGraph", commands.shuffleCrateGraph);
     ctx.registerCommand("reloadWorkspace", commands.reloadWorkspace);
+    ctx.registerCommand("rebuildProcMacros", commands.rebuildProcMacros);
+    ctx.registerCommand("cancelFlycheck", commands.cancelFlycheck);
+    ctx.registerCommand("switchTarget", commands.switchTarget);
+    ctx.registerCommand("installRustSrc", commands.installRustSrc);
     ctx.registerCommand("matchingBrace", commands.matchingBrace);
     ctx.registerCommand("joinLines", commands.joinLines);
     ctx.registerCommand("parentModule", commands.parentModule);
     ctx.registerCommand("syntaxTree", commands.syntaxTree);
     ctx.registerCommand("viewHir", commands.viewHir);
+    ctx.registerCommand("viewMir", commands.viewMir);
+    ctx.registerCommand("interpretFunction", commands.interpretFunction);
+    ctx.registerCommand("evaluateExpression", commands.evaluateExpression);
     ctx.registerCommand("viewFileText", commands.viewFileText);
     ctx.registerCommand("viewItemTree", commands.viewItemTree);
     ctx.registerCommand("viewCrateGraph", commands.viewCrateGraph);
     ctx.registerCommand("viewFullCrateGraph", commands.viewFullCrateGraph);
+    ctx.registerCommand("viewCurrentCrateGraph", commands.viewCurrentCrateGraph);
+    ctx.registerCommand("viewCrateGraphMermaid", commands.viewCrateGraphMermaid);
     ctx.registerCommand("expandMacro", commands.expandMacro);
+    ctx.registerCommand("expandMacroStep", commands.expandMacroStep);
+    ctx.registerCommand("rerunProcMacroWithBacktrace", commands.rerunProcMacroWithBacktrace);
     ctx.registerCommand("run", commands.run);
     ctx.registerCommand("copyRunCommandLine", commands.copyRunCommandLine);
     ctx.registerCommand("debug", commands.debug);
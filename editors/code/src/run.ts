@@ -104,7 +104,7 @@ export function prepareEnv(
 ): Record<string, string> {
     const env: Record<string, string> = { RUST_BACKTRACE: "short" };
 
-    if (runnable.args.expectTest) {
+    if (runnable.kind === "cargo" && runnable.args.expectTest) {
         env["UPDATE_EXPECT"] = "1";
     }
 
@@ -126,11 +126,26 @@ export function prepareEnv(
 }
 
 export async function createTask(runnable: ra.Runnable, config: Config): Promise<vscode.Task> {
-    if (runnable.kind !== "cargo") {
-        // rust-analyzer supports only one kind, "cargo"
-        // do not use tasks.TASK_TYPE here, these are completely different meanings.
+    // eslint-disable-next-line @typescript-eslint/no-unnecessary-type-assertion
+    const target = vscode.workspace.workspaceFolders![0]; // safe, see main activate()
 
-        throw `Unexpected runnable kind: ${runnable.kind}`;
+    if (runnable.kind === "shell") {
+        // Commands from the `runnables` of a rust-project.json are run as they are.
+        const exec = new vscode.ProcessExecution(runnable.args.program, runnable.args.args, {
+            cwd: runnable.args.cwd,
+            env: prepareEnv(runnable, config.runnableEnv),
+        });
+        const shellTask = new vscode.Task(
+            { type: "shell", command: runnable.args.program, args: runnable.args.args },
+            target,
+            runnable.label,
+            tasks.TASK_SOURCE,
+            exec,
+            ["$rustc"]
+        );
+        shellTask.presentationOptions.clear = true;
+        shellTask.presentationOptions.focus = false;
+        return shellTask;
     }
 
     const args = createArgs(runnable);
@@ -144,8 +159,6 @@ export async function createTask(runnable: ra.Runnable, config: Config): Promise
         overrideCargo: runnable.args.overrideCargo,
     };
 
-    // eslint-disable-next-line @typescript-eslint/no-unnecessary-type-assertion
-    const target = vscode.workspace.workspaceFolders![0]; // safe, see main activate()
     const cargoTask = await tasks.buildCargoTask(
         target,
         definition,
@@ -163,7 +176,7 @@ export async function createTask(runnable: ra.Runnable, config: Config): Promise
     return cargoTask;
 }
 
-export function createArgs(runnable: ra.Runnable): string[] {
+export function createArgs(runnable: ra.CargoRunnable): string[] {
     const args = [...runnable.args.cargoArgs]; // should be a copy!
     if (runnable.args.cargoExtraArgs) {
         args.push(...runnable.args.cargoExtraArgs); // Append user-specified cargo options.
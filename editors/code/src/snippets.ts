@@ -1,7 +1,5 @@
 import * as vscode from "vscode";
 
-import { assert } from "./util";
-
 export async function applySnippetWorkspaceEdit(edit: vscode.WorkspaceEdit) {
     if (edit.entries().length === 1) {
         const [uri, edits] = edit.entries()[0];
@@ -9,20 +7,26 @@ export async function applySnippetWorkspaceEdit(edit: vscode.WorkspaceEdit) {
         if (editor) await applySnippetTextEdits(editor, edits);
         return;
     }
-    for (const [uri, edits] of edit.entries()) {
+    // Documents with tab stops go last, so that the cursor ends up in one of them.
+    const isSnippet = (edits: vscode.TextEdit[]) =>
+        edits.some((indel) => parseSnippet(indel.newText));
+    const entries = edit.entries();
+    const plainEntries = entries.filter((entry) => !isSnippet(entry[1]));
+    const snippetEntries = entries.filter((entry) => isSnippet(entry[1]));
+    for (const [uri, edits] of plainEntries) {
         const editor = await editorFromUri(uri);
         if (editor) {
             await editor.edit((builder) => {
                 for (const indel of edits) {
-                    assert(
-                        !parseSnippet(indel.newText),
-                        `bad ws edit: snippet received with multiple edits: ${JSON.stringify(edit)}`
-                    );
                     builder.replace(indel.range, indel.newText);
                 }
             });
         }
     }
+    for (const [uri, edits] of snippetEntries) {
+        const editor = await editorFromUri(uri);
+        if (editor) await applySnippetTextEdits(editor, edits);
+    }
 }
 
 async function editorFromUri(uri: vscode.Uri): Promise<vscode.TextEditor | undefined> {
@@ -8,10 +8,13 @@ mod stdio;
 mod error;
 mod socket;
 mod req_queue;
+#[cfg(windows)]
+mod windows_pipe;
 
 use std::{
     io,
     net::{TcpListener, TcpStream, ToSocketAddrs},
+    path::Path,
 };
 
 use crossbeam_channel::{Receiver, Sender};
@@ -44,8 +47,7 @@ impl Connection {
     /// Use this to create a real language server.
     pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<(Connection, IoThreads)> {
         let stream = TcpStream::connect(addr)?;
-        let (sender, receiver, io_threads) = socket::socket_transport(stream);
-        Ok((Connection { sender, receiver }, io_threads))
+        Ok(Connection::tcp(stream))
     }
 
     /// Listen for a connection over tcp.
@@ -54,11 +56,48 @@ impl Connection {
     /// Use this to create a real language server.
     pub fn listen<A: ToSocketAddrs>(addr: A) -> io::Result<(Connection, IoThreads)> {
         let listener = TcpListener::bind(addr)?;
+        Connection::accept(&listener)
+    }
+
+    /// Accept the next connection on an already bound tcp listener.
+    /// This call blocks until a connection is established.
+    ///
+    /// Use this to keep serving clients on the same address, one after another.
+    pub fn accept(listener: &TcpListener) -> io::Result<(Connection, IoThreads)> {
         let (stream, _) = listener.accept()?;
-        let (sender, receiver, io_threads) = socket::socket_transport(stream);
+        Ok(Connection::tcp(stream))
+    }
+
+    /// Open a connection over a pipe created by the client.
+    /// This is a named pipe (`\\.\pipe\name`) on Windows and a unix domain socket elsewhere.
+    ///
+    /// Use this to create a real language server.
+    pub fn pipe(path: impl AsRef<Path>) -> io::Result<(Connection, IoThreads)> {
+        #[cfg(windows)]
+        let (read, write) = {
+            let pipe = windows_pipe::Pipe::open(path.as_ref())?;
+            (pipe.clone(), pipe)
+        };
+        #[cfg(unix)]
+        let (read, write) = {
+            let stream = std::os::unix::net::UnixStream::connect(path)?;
+            (stream.try_clone()?, stream)
+        };
+        #[cfg(not(any(windows, unix)))]
+        let (read, write): (io::Empty, io::Sink) = {
+            let _ = path;
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "pipes are not supported"));
+        };
+        let (sender, receiver, io_threads) = socket::socket_transport(read, write);
         Ok((Connection { sender, receiver }, io_threads))
     }
 
+    fn tcp(stream: TcpStream) -> (Connection, IoThreads) {
+        let read = stream.try_clone().unwrap();
+        let (sender, receiver, io_threads) = socket::socket_transport(read, stream);
+        (Connection { sender, receiver }, io_threads)
+    }
+
     /// Creates a pair of connected connections.
     ///
     /// Use this for testing.
@@ -227,3 +266,91 @@ impl Connection {
         Ok(true)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, Read, Write};
+
+    use super::*;
+
+    /// Sends a request to `server`, which answers while its reader thread waits for the next
+    /// message, and shuts it down.
+    fn round_trip(client: impl Read + Write, server: Connection, io_threads: IoThreads) {
+        let mut client = BufReader::new(client);
+        let request = Request::new(RequestId::from(1), "ping".to_string(), ());
+        Message::from(request).write(client.get_mut()).unwrap();
+
+        let request = match server.receiver.recv().unwrap() {
+            Message::Request(it) => it,
+            msg => panic!("expected a request, got {:?}", msg),
+        };
+        assert_eq!(request.method, "ping");
+        server.sender.send(Response::new_ok(request.id, "pong").into()).unwrap();
+
+        match Message::read(&mut client).unwrap() {
+            Some(Message::Response(it)) => {
+                assert_eq!(it.id, RequestId::from(1));
+                assert_eq!(it.result, Some(serde_json::json!("pong")));
+            }
+            msg => panic!("expected a response, got {:?}", msg),
+        }
+
+        let exit = Notification::new("exit".to_string(), ());
+        Message::from(exit).write(client.get_mut()).unwrap();
+        assert!(matches!(server.receiver.recv(), Ok(Message::Notification(n)) if n.is_exit()));
+        drop(server);
+        io_threads.join().unwrap();
+    }
+
+    #[test]
+    fn tcp_round_trip() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let (server, io_threads) = Connection::connect(listener.local_addr().unwrap()).unwrap();
+        let (client, _) = listener.accept().unwrap();
+        round_trip(client, server, io_threads);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pipe_round_trip() {
+        let path = std::env::temp_dir().join(format!("lsp-server-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let (server, io_threads) = Connection::pipe(&path).unwrap();
+        let (client, _) = listener.accept().unwrap();
+        round_trip(client, server, io_threads);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn pipe_round_trip() {
+        use std::{os::windows::ffi::OsStrExt, os::windows::io::FromRawHandle, ptr};
+
+        use winapi::um::{
+            handleapi::INVALID_HANDLE_VALUE,
+            namedpipeapi::CreateNamedPipeW,
+            winbase::{PIPE_ACCESS_DUPLEX, PIPE_TYPE_BYTE, PIPE_WAIT},
+        };
+
+        let path = format!(r"\\.\pipe\lsp-server-{}", std::process::id());
+        let wide: Vec<u16> = std::ffi::OsStr::new(&path).encode_wide().chain(Some(0)).collect();
+        let handle = unsafe {
+            CreateNamedPipeW(
+                wide.as_ptr(),
+                PIPE_ACCESS_DUPLEX,
+                PIPE_TYPE_BYTE | PIPE_WAIT,
+                1,
+                4096,
+                4096,
+                0,
+                ptr::null_mut(),
+            )
+        };
+        assert_ne!(handle, INVALID_HANDLE_VALUE);
+        // The client side only reads and writes in turn, so synchronous I/O is fine there.
+        let client = unsafe { std::fs::File::from_raw_handle(handle.cast()) };
+        let (server, io_threads) = Connection::pipe(&path).unwrap();
+        round_trip(client, server, io_threads);
+    }
+}
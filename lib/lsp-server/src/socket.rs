@@ -1,6 +1,5 @@
 use std::{
-    io::{self, BufReader},
-    net::TcpStream,
+    io::{self, BufReader, Read, Write},
     thread,
 };
 
@@ -11,23 +10,26 @@ use crate::{
     Message,
 };
 
+/// Creates an LSP connection over the two halves of a stream, like a tcp socket or a pipe.
 pub(crate) fn socket_transport(
-    stream: TcpStream,
+    read: impl Read + Send + 'static,
+    write: impl Write + Send + 'static,
 ) -> (Sender<Message>, Receiver<Message>, IoThreads) {
-    let (reader_receiver, reader) = make_reader(stream.try_clone().unwrap());
-    let (writer_sender, writer) = make_write(stream.try_clone().unwrap());
+    let (reader_receiver, reader) = make_reader(read);
+    let (writer_sender, writer) = make_write(write);
     let io_threads = make_io_threads(reader, writer);
     (writer_sender, reader_receiver, io_threads)
 }
 
-fn make_reader(stream: TcpStream) -> (Receiver<Message>, thread::JoinHandle<io::Result<()>>) {
+fn make_reader(
+    stream: impl Read + Send + 'static,
+) -> (Receiver<Message>, thread::JoinHandle<io::Result<()>>) {
     let (reader_sender, reader_receiver) = bounded::<Message>(0);
     let reader = thread::spawn(move || {
         let mut buf_read = BufReader::new(stream);
-        while let Some(msg) = Message::read(&mut buf_read).unwrap() {
+        while let Some(msg) = Message::read(&mut buf_read)? {
             let is_exit = matches!(&msg, Message::Notification(n) if n.is_exit());
-            reader_sender.send(msg).unwrap();
-            if is_exit {
+            if reader_sender.send(msg).is_err() || is_exit {
                 break;
             }
         }
@@ -36,10 +38,12 @@ fn make_reader(stream: TcpStream) -> (Receiver<Message>, thread::JoinHandle<io::
     (reader_receiver, reader)
 }
 
-fn make_write(mut stream: TcpStream) -> (Sender<Message>, thread::JoinHandle<io::Result<()>>) {
+fn make_write(
+    mut stream: impl Write + Send + 'static,
+) -> (Sender<Message>, thread::JoinHandle<io::Result<()>>) {
     let (writer_sender, writer_receiver) = bounded::<Message>(0);
     let writer = thread::spawn(move || {
-        writer_receiver.into_iter().try_for_each(|it| it.write(&mut stream)).unwrap();
+        writer_receiver.into_iter().try_for_each(|it| it.write(&mut stream))?;
         Ok(())
     });
     (writer_sender, writer)
@@ -0,0 +1,112 @@
+//! The client end of a Windows named pipe.
+//!
+//! The operations on a handle opened for synchronous I/O are serialized, so a read blocking on
+//! the pipe would block all writes to it as well. The pipe is opened for overlapped I/O instead,
+//! which lets one thread read while another one writes.
+
+use std::{io, mem, os::windows::ffi::OsStrExt, path::Path, ptr, sync::Arc};
+
+use winapi::{
+    shared::{
+        minwindef::{DWORD, FALSE, TRUE},
+        winerror::{ERROR_BROKEN_PIPE, ERROR_IO_PENDING},
+    },
+    um::{
+        fileapi::{CreateFileW, ReadFile, WriteFile, OPEN_EXISTING},
+        handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
+        ioapiset::GetOverlappedResult,
+        minwinbase::OVERLAPPED,
+        synchapi::CreateEventW,
+        winbase::FILE_FLAG_OVERLAPPED,
+        winnt::{GENERIC_READ, GENERIC_WRITE, HANDLE},
+    },
+};
+
+struct Handle(HANDLE);
+
+// SAFETY: the handle is only closed on drop, and overlapped I/O may be issued from any thread.
+unsafe impl Send for Handle {}
+unsafe impl Sync for Handle {}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        unsafe { CloseHandle(self.0) };
+    }
+}
+
+/// A connected pipe, cloned to read and write on different threads.
+#[derive(Clone)]
+pub(crate) struct Pipe(Arc<Handle>);
+
+impl Pipe {
+    pub(crate) fn open(path: &Path) -> io::Result<Pipe> {
+        let path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        let handle = unsafe {
+            CreateFileW(
+                path.as_ptr(),
+                GENERIC_READ | GENERIC_WRITE,
+                0,
+                ptr::null_mut(),
+                OPEN_EXISTING,
+                FILE_FLAG_OVERLAPPED,
+                ptr::null_mut(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Pipe(Arc::new(Handle(handle))))
+    }
+
+    /// Starts an operation with `start` and waits until it completes, returning the number of
+    /// bytes transferred. Every operation gets its own `OVERLAPPED` and event.
+    fn run(&self, start: impl FnOnce(HANDLE, *mut OVERLAPPED) -> i32) -> io::Result<usize> {
+        let event = unsafe { CreateEventW(ptr::null_mut(), TRUE, FALSE, ptr::null()) };
+        if event.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        let event = Handle(event);
+        let mut overlapped: OVERLAPPED = unsafe { mem::zeroed() };
+        overlapped.hEvent = event.0;
+        if start(self.0 .0, &mut overlapped) == FALSE {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(ERROR_IO_PENDING as i32) {
+                return Err(err);
+            }
+        }
+        let mut transferred: DWORD = 0;
+        if unsafe { GetOverlappedResult(self.0 .0, &mut overlapped, &mut transferred, TRUE) }
+            == FALSE
+        {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(transferred as usize)
+    }
+}
+
+impl io::Read for Pipe {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(DWORD::MAX as usize) as DWORD;
+        let res = self.run(|handle, overlapped| unsafe {
+            ReadFile(handle, buf.as_mut_ptr().cast(), len, ptr::null_mut(), overlapped)
+        });
+        match res {
+            // The other end closed the pipe.
+            Err(err) if err.raw_os_error() == Some(ERROR_BROKEN_PIPE as i32) => Ok(0),
+            res => res,
+        }
+    }
+}
+
+impl io::Write for Pipe {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(DWORD::MAX as usize) as DWORD;
+        self.run(|handle, overlapped| unsafe {
+            WriteFile(handle, buf.as_ptr().cast(), len, ptr::null_mut(), overlapped)
+        })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}